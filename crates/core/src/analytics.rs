@@ -1,14 +1,68 @@
-use crate::types::{RunId, StepId};
-use chrono::{DateTime, Utc};
+use crate::types::{
+    CapacityUsage, Run, RunId, RunStatus, StepExecution, StepId, StepStatus, WorkflowSpec,
+};
+use crate::validation::ValidationErrors;
+use crate::workflow::WorkflowDag;
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Months, Timelike, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
 
+/// Default number of finished execution traces kept in memory
+pub const DEFAULT_MAX_TRACES: usize = 10_000;
+
 /// Performance analytics for workflows and steps
 pub struct PerformanceAnalytics {
     workflow_stats: Arc<Mutex<HashMap<String, WorkflowStats>>>,
     step_stats: Arc<Mutex<HashMap<String, StepStats>>>,
     execution_traces: Arc<Mutex<Vec<ExecutionTrace>>>,
+    retention: TraceRetention,
+}
+
+/// How many finished execution traces are kept.
+///
+/// Workflow and step stats are updated as each run finishes, so they keep counting
+/// evicted runs. Traces still running are never evicted.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraceRetention {
+    /// Keep at most this many finished traces
+    pub max_traces: Option<usize>,
+    /// Drop finished traces that completed more than this many days ago
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for TraceRetention {
+    fn default() -> Self {
+        Self {
+            max_traces: Some(DEFAULT_MAX_TRACES),
+            max_age_days: None,
+        }
+    }
+}
+
+impl TraceRetention {
+    /// Evict finished traces outside the retention, oldest first
+    fn apply(&self, traces: &mut Vec<ExecutionTrace>) {
+        if let Some(days) = self.max_age_days {
+            let cutoff = Utc::now() - chrono::Duration::days(days as i64);
+            traces.retain(|t| t.completed_at.is_none_or(|completed| completed >= cutoff));
+        }
+
+        if let Some(max_traces) = self.max_traces {
+            let finished = traces.iter().filter(|t| t.completed_at.is_some()).count();
+            let mut excess = finished.saturating_sub(max_traces);
+            traces.retain(|t| {
+                if excess > 0 && t.completed_at.is_some() {
+                    excess -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+    }
 }
 
 /// Statistics for a workflow
@@ -54,6 +108,40 @@ pub struct ExecutionTrace {
     pub status: TraceStatus,
     pub steps: Vec<StepTrace>,
     pub bottleneck: Option<BottleneckInfo>,
+    /// Tokens consumed by the run, once usage has been attributed
+    #[serde(default)]
+    pub total_tokens: u64,
+    /// Cost of the run, once usage has been attributed
+    #[serde(default)]
+    pub total_cost: f64,
+}
+
+impl ExecutionTrace {
+    /// Attribute capacity usage to this trace's steps, joined on `run_id` and `step_id`.
+    ///
+    /// Recomputes token and cost figures from scratch, so it is safe to call repeatedly.
+    /// Usage for a retried step is attributed to its latest attempt.
+    pub fn attribute_usage(&mut self, usage: &[CapacityUsage]) {
+        self.total_tokens = 0;
+        self.total_cost = 0.0;
+        for step in &mut self.steps {
+            step.tokens = 0;
+            step.cost = 0.0;
+        }
+
+        for record in usage.iter().filter(|u| u.run_id == Some(self.run_id)) {
+            self.total_tokens += record.total_tokens as u64;
+            self.total_cost += record.cost;
+
+            let step = record.step_id.as_ref().and_then(|step_id| {
+                self.steps.iter_mut().rev().find(|s| &s.step_id == step_id)
+            });
+            if let Some(step) = step {
+                step.tokens += record.total_tokens as u64;
+                step.cost += record.cost;
+            }
+        }
+    }
 }
 
 /// Status of an execution trace
@@ -75,6 +163,10 @@ pub struct StepTrace {
     pub status: TraceStatus,
     pub attempt: u32,
     pub error: Option<String>,
+    #[serde(default)]
+    pub tokens: u64,
+    #[serde(default)]
+    pub cost: f64,
 }
 
 /// Information about detected bottlenecks
@@ -100,6 +192,137 @@ pub struct BottleneckStep {
     pub avg_duration_secs: f64,
     pub percentage_of_workflow: f64,
     pub execution_count: u64,
+    /// Average cost per execution, from attributed capacity usage
+    #[serde(default)]
+    pub avg_cost: f64,
+}
+
+/// How bottleneck steps are ranked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BottleneckRanking {
+    #[default]
+    Duration,
+    Cost,
+}
+
+/// Step-by-step comparison of two runs, typically a failing run against a prior success
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunComparison {
+    pub run_a: RunId,
+    pub run_b: RunId,
+    pub status_a: RunStatus,
+    pub status_b: RunStatus,
+    /// Run `b`'s duration minus run `a`'s, if both have finished
+    pub duration_delta_secs: Option<f64>,
+    pub steps: Vec<StepComparison>,
+    /// Earliest-started step whose status differs between the runs
+    pub first_divergence: Option<StepId>,
+}
+
+/// Comparison of one step across two runs. A status of `None` means the step is absent
+/// from that run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepComparison {
+    pub step_id: StepId,
+    pub status_a: Option<StepStatus>,
+    pub status_b: Option<StepStatus>,
+    pub status_differs: bool,
+    pub duration_a_secs: Option<f64>,
+    pub duration_b_secs: Option<f64>,
+    /// Step duration in run `b` minus run `a`, if it finished in both
+    pub duration_delta_secs: Option<f64>,
+}
+
+impl RunComparison {
+    /// Compare two runs step by step. Step durations come from the execution traces when
+    /// available (latest attempt), falling back to the run's own step timestamps.
+    pub fn compare(
+        a: &Run,
+        trace_a: Option<&ExecutionTrace>,
+        b: &Run,
+        trace_b: Option<&ExecutionTrace>,
+    ) -> Self {
+        let mut step_ids: Vec<&StepId> = a.steps.iter().map(|s| &s.id).collect();
+        for step in &b.steps {
+            if !step_ids.contains(&&step.id) {
+                step_ids.push(&step.id);
+            }
+        }
+
+        let mut divergences = Vec::new();
+        let mut steps = Vec::with_capacity(step_ids.len());
+
+        for step_id in step_ids {
+            let step_a = a.steps.iter().find(|s| &s.id == step_id);
+            let step_b = b.steps.iter().find(|s| &s.id == step_id);
+            let status_a = step_a.map(|s| s.status);
+            let status_b = step_b.map(|s| s.status);
+            let status_differs = status_a != status_b;
+
+            if status_differs {
+                let started_at = [step_a, step_b]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|s| s.started_at)
+                    .min();
+                divergences.push((started_at, step_id));
+            }
+
+            let duration_a_secs = step_duration_secs(step_a, trace_a);
+            let duration_b_secs = step_duration_secs(step_b, trace_b);
+
+            steps.push(StepComparison {
+                step_id: step_id.clone(),
+                status_a,
+                status_b,
+                status_differs,
+                duration_a_secs,
+                duration_b_secs,
+                duration_delta_secs: duration_a_secs.zip(duration_b_secs).map(|(a, b)| b - a),
+            });
+        }
+
+        // Steps that never started in either run sort after those that did
+        let first_divergence = divergences
+            .into_iter()
+            .min_by_key(|(started_at, _)| (started_at.is_none(), *started_at))
+            .map(|(_, step_id)| step_id.clone());
+
+        Self {
+            run_a: a.id,
+            run_b: b.id,
+            status_a: a.status,
+            status_b: b.status,
+            duration_delta_secs: run_duration_secs(a)
+                .zip(run_duration_secs(b))
+                .map(|(a, b)| b - a),
+            steps,
+            first_divergence,
+        }
+    }
+}
+
+fn run_duration_secs(run: &Run) -> Option<f64> {
+    run.completed_at
+        .map(|completed_at| (completed_at - run.started_at).num_milliseconds() as f64 / 1000.0)
+}
+
+fn step_duration_secs(step: Option<&StepExecution>, trace: Option<&ExecutionTrace>) -> Option<f64> {
+    let step = step?;
+    let traced = trace.and_then(|t| {
+        t.steps
+            .iter()
+            .rev()
+            .find(|s| s.step_id == step.id)
+            .and_then(|s| s.duration_secs)
+    });
+
+    traced.or_else(|| {
+        step.started_at
+            .zip(step.completed_at)
+            .map(|(started, completed)| (completed - started).num_milliseconds() as f64 / 1000.0)
+    })
 }
 
 impl PerformanceAnalytics {
@@ -109,9 +332,16 @@ impl PerformanceAnalytics {
             workflow_stats: Arc::new(Mutex::new(HashMap::new())),
             step_stats: Arc::new(Mutex::new(HashMap::new())),
             execution_traces: Arc::new(Mutex::new(Vec::new())),
+            retention: TraceRetention::default(),
         }
     }
 
+    /// Bound how many finished execution traces are kept
+    pub fn with_retention(mut self, retention: TraceRetention) -> Self {
+        self.retention = retention;
+        self
+    }
+
     /// Start tracking a workflow execution
     pub fn start_workflow(&self, run_id: RunId, workflow_id: String) {
         let mut traces = self.execution_traces.lock().unwrap();
@@ -124,6 +354,8 @@ impl PerformanceAnalytics {
             status: TraceStatus::Running,
             steps: Vec::new(),
             bottleneck: None,
+            total_tokens: 0,
+            total_cost: 0.0,
         });
     }
 
@@ -139,6 +371,8 @@ impl PerformanceAnalytics {
                 status: TraceStatus::Running,
                 attempt,
                 error: None,
+                tokens: 0,
+                cost: 0.0,
             });
         }
     }
@@ -258,6 +492,9 @@ impl PerformanceAnalytics {
                     last_execution: Some(now),
                 });
         }
+
+        // Stats are up to date, so older traces can go
+        self.retention.apply(&mut traces);
     }
 
     /// Get workflow statistics
@@ -301,10 +538,98 @@ impl PerformanceAnalytics {
         traces.iter().rev().take(limit).cloned().collect()
     }
 
+    /// Project when `run` will finish from its progress and historical per-step averages.
+    ///
+    /// Steps run one at a time, so the remaining time is the average duration of every
+    /// step yet to finish, less what the running step has already taken. Live progress
+    /// comes from the run's execution trace when there is one, since the indexed run is
+    /// only updated as it starts and ends. Returns `None` if a remaining step has no
+    /// history, and `completed_at` for finished runs.
+    pub fn estimate_completion(&self, run: &Run) -> Option<DateTime<Utc>> {
+        if run.status.is_terminal() {
+            return run.completed_at;
+        }
+
+        let trace = self.get_trace(&run.id);
+        let step_stats = self.step_stats.lock().unwrap();
+        let now = Utc::now();
+        let mut remaining_secs = 0.0;
+
+        for step in &run.steps {
+            // Latest attempt of the step, if the trace has seen it
+            let traced = trace
+                .as_ref()
+                .and_then(|t| t.steps.iter().rev().find(|s| s.step_id == step.id));
+
+            let finished = match traced {
+                Some(traced) => traced.status == TraceStatus::Completed,
+                None => matches!(step.status, StepStatus::Completed | StepStatus::Skipped),
+            };
+            if finished {
+                continue;
+            }
+
+            let avg_secs = step_stats.get(&step.id.0)?.avg_duration_secs;
+            let started_at = match traced {
+                Some(traced) if traced.status == TraceStatus::Running => Some(traced.started_at),
+                None if step.status == StepStatus::Running => step.started_at,
+                _ => None,
+            };
+            let elapsed_secs = started_at
+                .map(|started| (now - started).num_milliseconds() as f64 / 1000.0)
+                .unwrap_or(0.0);
+
+            remaining_secs += (avg_secs - elapsed_secs).max(0.0);
+        }
+
+        Some(now + chrono::Duration::milliseconds((remaining_secs * 1000.0) as i64))
+    }
+
+    /// The chain of dependent steps in `workflow` that takes longest on average, which
+    /// bounds its wall-clock time: speeding up any other step doesn't shorten a run.
+    ///
+    /// Steps are weighted by their historical average duration; steps with no history
+    /// count as instant.
+    pub fn critical_path(&self, workflow: &WorkflowSpec) -> Result<Vec<StepId>> {
+        let dag = WorkflowDag::from_workflow(workflow)?;
+        let step_stats = self.step_stats.lock().unwrap();
+        Ok(dag.longest_path(|step_id| {
+            step_stats
+                .get(&step_id.0)
+                .map(|s| s.avg_duration_secs)
+                .unwrap_or(0.0)
+        }))
+    }
+
     /// Detect bottlenecks in a workflow
     pub fn detect_bottlenecks(&self, workflow_id: &str) -> Option<BottleneckReport> {
+        self.detect_bottlenecks_ranked(workflow_id, BottleneckRanking::Duration, &[])
+    }
+
+    /// Detect bottlenecks in a workflow, attributing `usage` to steps for cost figures
+    pub fn detect_bottlenecks_ranked(
+        &self,
+        workflow_id: &str,
+        ranking: BottleneckRanking,
+        usage: &[CapacityUsage],
+    ) -> Option<BottleneckReport> {
         let workflow_stats = self.get_workflow_stats(workflow_id)?;
 
+        // Average cost per step execution across this workflow's traces
+        let mut step_costs: HashMap<String, (f64, u64)> = HashMap::new();
+        if !usage.is_empty() {
+            let traces = self.execution_traces.lock().unwrap();
+            for trace in traces.iter().filter(|t| t.workflow_id == workflow_id) {
+                let mut trace = trace.clone();
+                trace.attribute_usage(usage);
+                for step in &trace.steps {
+                    let entry = step_costs.entry(step.step_id.0.clone()).or_default();
+                    entry.0 += step.cost;
+                    entry.1 += 1;
+                }
+            }
+        }
+
         // Get all step stats for steps in this workflow
         let step_stats = self.step_stats.lock().unwrap();
 
@@ -314,17 +639,29 @@ impl PerformanceAnalytics {
             .filter(|s| s.execution_count > 0)
             .map(|s| {
                 let percentage = (s.avg_duration_secs / workflow_stats.avg_duration_secs) * 100.0;
+                let avg_cost = step_costs
+                    .get(&s.step_id)
+                    .map(|(total, count)| total / *count as f64)
+                    .unwrap_or(0.0);
                 BottleneckStep {
                     step_id: s.step_id.clone(),
                     avg_duration_secs: s.avg_duration_secs,
                     percentage_of_workflow: percentage,
                     execution_count: s.execution_count,
+                    avg_cost,
                 }
             })
             .collect();
 
-        // Sort by percentage of workflow time
-        bottlenecks.sort_by(|a, b| b.percentage_of_workflow.partial_cmp(&a.percentage_of_workflow).unwrap());
+        match ranking {
+            // Sort by percentage of workflow time
+            BottleneckRanking::Duration => bottlenecks.sort_by(|a, b| {
+                b.percentage_of_workflow.partial_cmp(&a.percentage_of_workflow).unwrap()
+            }),
+            BottleneckRanking::Cost => {
+                bottlenecks.sort_by(|a, b| b.avg_cost.partial_cmp(&a.avg_cost).unwrap())
+            }
+        }
 
         Some(BottleneckReport {
             workflow_id: workflow_id.to_string(),
@@ -352,6 +689,143 @@ impl Default for PerformanceAnalytics {
     }
 }
 
+/// Width of the time buckets capacity usage is grouped into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostGrouping {
+    Hour,
+    Day,
+}
+
+impl CostGrouping {
+    fn width(self) -> Duration {
+        match self {
+            CostGrouping::Hour => Duration::hours(1),
+            CostGrouping::Day => Duration::days(1),
+        }
+    }
+
+    /// Start of the bucket containing `at`
+    fn bucket_start(self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let hour = match self {
+            CostGrouping::Hour => at.hour(),
+            CostGrouping::Day => 0,
+        };
+        at.date_naive()
+            .and_hms_opt(hour, 0, 0)
+            .expect("hour of an existing time is valid")
+            .and_utc()
+    }
+}
+
+/// Capacity usage recorded within one time bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostBucket {
+    pub start: DateTime<Utc>,
+    pub cost: f64,
+    pub tokens: u64,
+    pub requests: u64,
+}
+
+impl CostBucket {
+    fn empty(start: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            cost: 0.0,
+            tokens: 0,
+            requests: 0,
+        }
+    }
+}
+
+/// Most buckets `cost_buckets` will break usage into, a little over a year of hours
+pub const MAX_COST_BUCKETS: usize = 10_000;
+
+/// Total usage per bucket, oldest first, from the first record's bucket to the last's.
+/// Buckets without usage in between are included, so gaps in spend stay visible.
+///
+/// Only records within `since..=now` count, so a stray timestamp can't stretch the range,
+/// and a range needing more than `MAX_COST_BUCKETS` buckets is refused.
+pub fn cost_buckets(
+    usage: &[CapacityUsage],
+    grouping: CostGrouping,
+    since: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<Vec<CostBucket>> {
+    let mut totals: BTreeMap<DateTime<Utc>, CostBucket> = BTreeMap::new();
+    let in_range = usage
+        .iter()
+        .filter(|u| since.is_none_or(|since| u.timestamp >= since) && u.timestamp <= now);
+    for record in in_range {
+        let start = grouping.bucket_start(record.timestamp);
+        let bucket = totals.entry(start).or_insert_with(|| CostBucket::empty(start));
+        bucket.cost += record.cost;
+        bucket.tokens = bucket.tokens.saturating_add(u64::from(record.total_tokens));
+        bucket.requests = bucket.requests.saturating_add(u64::from(record.request_count));
+    }
+
+    let (Some(&first), Some(&last)) = (totals.keys().next(), totals.keys().next_back()) else {
+        return Ok(Vec::new());
+    };
+    let count = (last - first).num_seconds() / grouping.width().num_seconds() + 1;
+    if count > MAX_COST_BUCKETS as i64 {
+        let mut errors = ValidationErrors::new();
+        errors.push(
+            "group_by",
+            format!(
+                "Usage spans {} buckets, more than the {} allowed; narrow it with since",
+                count, MAX_COST_BUCKETS
+            ),
+        );
+        errors.into_result()?;
+    }
+
+    let mut buckets = Vec::new();
+    let mut start = first;
+    while start <= last {
+        buckets.push(totals.remove(&start).unwrap_or_else(|| CostBucket::empty(start)));
+        start += grouping.width();
+    }
+    Ok(buckets)
+}
+
+/// Spend so far in the current calendar month, extrapolated linearly to the month's end
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MonthlyCostProjection {
+    pub month_to_date_cost: f64,
+    pub projected_month_cost: f64,
+}
+
+/// Project this month's total spend from the usage recorded in it up to `now`
+pub fn project_month_cost(usage: &[CapacityUsage], now: DateTime<Utc>) -> MonthlyCostProjection {
+    let month_start = now
+        .date_naive()
+        .with_day(1)
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .expect("first of the month is a valid date")
+        .and_utc();
+    let month_end = month_start + Months::new(1);
+
+    let month_to_date_cost: f64 = usage
+        .iter()
+        .filter(|u| u.timestamp >= month_start && u.timestamp <= now)
+        .map(|u| u.cost)
+        .sum();
+
+    let elapsed = (now - month_start).num_seconds() as f64;
+    let month = (month_end - month_start).num_seconds() as f64;
+    let projected_month_cost = if elapsed > 0.0 {
+        month_to_date_cost * month / elapsed
+    } else {
+        month_to_date_cost
+    };
+
+    MonthlyCostProjection {
+        month_to_date_cost,
+        projected_month_cost,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -370,6 +844,146 @@ mod tests {
         assert_eq!(stats.failure_count, 0);
     }
 
+    #[test]
+    fn test_trace_retention_keeps_stats() {
+        let analytics = PerformanceAnalytics::new().with_retention(TraceRetention {
+            max_traces: Some(3),
+            max_age_days: None,
+        });
+        let step_id = StepId::new("build");
+
+        // A run still in flight when the others finish is never evicted
+        let in_flight = RunId::new();
+        analytics.start_workflow(in_flight, "deploy".to_string());
+
+        let mut run_ids = Vec::new();
+        for _ in 0..5 {
+            let run_id = RunId::new();
+            analytics.start_workflow(run_id, "deploy".to_string());
+            analytics.start_step(&run_id, step_id.clone(), 0);
+            analytics.complete_step(&run_id, &step_id, true, None);
+            analytics.complete_workflow(&run_id, true);
+            run_ids.push(run_id);
+        }
+
+        let traces = analytics.get_all_traces();
+        assert_eq!(traces.len(), 4);
+        assert!(analytics.get_trace(&in_flight).is_some());
+        assert!(analytics.get_trace(&run_ids[0]).is_none());
+        assert!(analytics.get_trace(&run_ids[1]).is_none());
+        assert!(analytics.get_trace(&run_ids[4]).is_some());
+        assert_eq!(analytics.get_recent_traces(50).len(), 4);
+
+        let stats = analytics.get_workflow_stats("deploy").unwrap();
+        assert_eq!(stats.execution_count, 5);
+        assert_eq!(stats.success_count, 5);
+        assert!((stats.avg_duration_secs - stats.total_duration_secs / 5.0).abs() < 1e-9);
+
+        let step_stats = analytics.get_step_stats("build").unwrap();
+        assert_eq!(step_stats.execution_count, 5);
+        assert!((step_stats.avg_duration_secs - step_stats.total_duration_secs / 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_partial_trace_retention_keeps_default_cap() {
+        // Setting only an age limit mustn't lift the count cap
+        let retention: TraceRetention = serde_json::from_str(r#"{"max_age_days": 30}"#).unwrap();
+        assert_eq!(retention.max_traces, Some(DEFAULT_MAX_TRACES));
+        assert_eq!(retention.max_age_days, Some(30));
+    }
+
+    fn step_stats_with_avg(step_id: &str, avg_duration_secs: f64) -> StepStats {
+        StepStats {
+            step_id: step_id.to_string(),
+            execution_count: 1,
+            success_count: 1,
+            failure_count: 0,
+            retry_count: 0,
+            total_duration_secs: avg_duration_secs,
+            min_duration_secs: avg_duration_secs,
+            max_duration_secs: avg_duration_secs,
+            avg_duration_secs,
+            p50_duration_secs: Some(avg_duration_secs),
+            p95_duration_secs: Some(avg_duration_secs),
+            p99_duration_secs: Some(avg_duration_secs),
+            durations: vec![avg_duration_secs],
+        }
+    }
+
+    #[test]
+    fn test_estimate_completion_for_half_done_run() {
+        let analytics = PerformanceAnalytics::new();
+        let run_id = RunId::new();
+        let step_ids = ["fetch", "build", "test", "deploy"];
+
+        let run = Run {
+            id: run_id,
+            work_item_id: "release".to_string(),
+            status: RunStatus::Running,
+            started_at: Utc::now(),
+            completed_at: None,
+            steps: step_ids
+                .iter()
+                .map(|id| StepExecution {
+                    id: StepId::new(*id),
+                    status: StepStatus::Pending,
+                    started_at: None,
+                    completed_at: None,
+                    attempt: 0,
+                    error: None,
+                    tokens: None,
+                    cost: None,
+                })
+                .collect(),
+            parent_run_id: None,
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        };
+
+        // fetch and build are done, test has been running for 10s
+        analytics.start_workflow(run_id, "release".to_string());
+        for id in &step_ids[..2] {
+            analytics.start_step(&run_id, StepId::new(*id), 0);
+            analytics.complete_step(&run_id, &StepId::new(*id), true, None);
+        }
+        analytics.start_step(&run_id, StepId::new("test"), 0);
+        {
+            let mut traces = analytics.execution_traces.lock().unwrap();
+            let step = traces[0].steps.last_mut().unwrap();
+            step.started_at = Utc::now() - chrono::Duration::seconds(10);
+        }
+
+        {
+            let mut stats = analytics.step_stats.lock().unwrap();
+            for (id, avg) in [("fetch", 10.0), ("build", 20.0), ("test", 30.0), ("deploy", 40.0)] {
+                stats.insert(id.to_string(), step_stats_with_avg(id, avg));
+            }
+        }
+
+        // 20s left of test, then 40s of deploy
+        let before = Utc::now();
+        let eta = analytics.estimate_completion(&run).unwrap();
+        let after = Utc::now();
+        assert!(eta >= before + chrono::Duration::seconds(59));
+        assert!(eta <= after + chrono::Duration::seconds(61));
+
+        // No history for a remaining step
+        analytics.step_stats.lock().unwrap().remove("deploy");
+        assert!(analytics.estimate_completion(&run).is_none());
+
+        let finished = Run {
+            status: RunStatus::Completed,
+            completed_at: Some(after),
+            ..run
+        };
+        assert_eq!(analytics.estimate_completion(&finished), Some(after));
+    }
+
     #[test]
     fn test_step_tracking() {
         let analytics = PerformanceAnalytics::new();
@@ -458,4 +1072,263 @@ mod tests {
         assert!(stats.p95_duration_secs.is_some());
         assert!(stats.p99_duration_secs.is_some());
     }
+
+    fn usage(run_id: RunId, step_id: &str, tokens: u32, cost: f64) -> CapacityUsage {
+        CapacityUsage {
+            id: uuid::Uuid::new_v4().to_string(),
+            source_id: crate::types::CapacitySourceId::new("anthropic"),
+            timestamp: Utc::now(),
+            input_tokens: tokens / 2,
+            output_tokens: tokens - tokens / 2,
+            total_tokens: tokens,
+            cost,
+            request_count: 1,
+            run_id: Some(run_id),
+            step_id: Some(StepId::new(step_id)),
+            fallback_from: None,
+        }
+    }
+
+    #[test]
+    fn test_step_cost_attribution() {
+        let analytics = PerformanceAnalytics::new();
+        let run_id = RunId::new();
+        let other_run = RunId::new();
+
+        analytics.start_workflow(run_id, "costly_workflow".to_string());
+        for step in ["plan", "implement"] {
+            let step_id = StepId::new(step);
+            analytics.start_step(&run_id, step_id.clone(), 0);
+            analytics.complete_step(&run_id, &step_id, true, None);
+        }
+        analytics.complete_workflow(&run_id, true);
+
+        let records = vec![
+            usage(run_id, "plan", 100, 0.25),
+            usage(run_id, "implement", 400, 1.0),
+            usage(run_id, "implement", 200, 0.5),
+            usage(other_run, "plan", 1000, 10.0),
+        ];
+
+        let mut trace = analytics.get_trace(&run_id).unwrap();
+        trace.attribute_usage(&records);
+
+        let plan = trace.steps.iter().find(|s| s.step_id.0 == "plan").unwrap();
+        let implement = trace.steps.iter().find(|s| s.step_id.0 == "implement").unwrap();
+        assert_eq!(plan.tokens, 100);
+        assert!((plan.cost - 0.25).abs() < 1e-9);
+        assert_eq!(implement.tokens, 600);
+        assert!((implement.cost - 1.5).abs() < 1e-9);
+        assert_eq!(trace.total_tokens, 700);
+        assert!((trace.total_cost - 1.75).abs() < 1e-9);
+
+        // Attribution is idempotent
+        trace.attribute_usage(&records);
+        assert!((trace.total_cost - 1.75).abs() < 1e-9);
+
+        let report = analytics
+            .detect_bottlenecks_ranked("costly_workflow", BottleneckRanking::Cost, &records)
+            .unwrap();
+        assert_eq!(report.bottlenecks[0].step_id, "implement");
+        assert!((report.bottlenecks[0].avg_cost - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compare_runs_finds_first_divergence() {
+        let t0 = Utc::now();
+        let at = |secs: i64| Some(t0 + chrono::Duration::seconds(secs));
+        let step = |id: &str, status, started_at, completed_at| StepExecution {
+            id: StepId::new(id),
+            status,
+            started_at,
+            completed_at,
+            attempt: 1,
+            error: None,
+            tokens: None,
+            cost: None,
+        };
+        let run = |status, steps| Run {
+            id: RunId::new(),
+            work_item_id: "build".to_string(),
+            status,
+            started_at: t0,
+            completed_at: at(30),
+            steps,
+            parent_run_id: None,
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        };
+
+        // `report` is listed before `build` but runs after it
+        let success = run(
+            RunStatus::Completed,
+            vec![
+                step("fetch", StepStatus::Completed, at(0), at(5)),
+                step("report", StepStatus::Completed, at(20), at(30)),
+                step("build", StepStatus::Completed, at(5), at(20)),
+            ],
+        );
+        let failure = run(
+            RunStatus::Failed,
+            vec![
+                step("fetch", StepStatus::Completed, at(0), at(8)),
+                step("report", StepStatus::Pending, None, None),
+                step("build", StepStatus::Failed, at(8), at(10)),
+            ],
+        );
+
+        let analytics = PerformanceAnalytics::new();
+        analytics.start_workflow(success.id, "build".to_string());
+        analytics.start_step(&success.id, StepId::new("fetch"), 1);
+        analytics.complete_step(&success.id, &StepId::new("fetch"), true, None);
+        let trace = analytics.get_trace(&success.id).unwrap();
+
+        let comparison = RunComparison::compare(&success, Some(&trace), &failure, None);
+
+        assert_eq!(comparison.first_divergence, Some(StepId::new("build")));
+        assert_eq!(comparison.status_b, RunStatus::Failed);
+
+        let differing: Vec<&str> = comparison
+            .steps
+            .iter()
+            .filter(|s| s.status_differs)
+            .map(|s| s.step_id.0.as_str())
+            .collect();
+        assert_eq!(differing, vec!["report", "build"]);
+
+        // The traced duration is used for run `a`; run `b` falls back to step timestamps
+        let fetch = &comparison.steps[0];
+        assert!(!fetch.status_differs);
+        assert!(fetch.duration_a_secs.unwrap() < 1.0);
+        assert_eq!(fetch.duration_b_secs, Some(8.0));
+
+        let build = &comparison.steps[2];
+        assert_eq!(build.duration_delta_secs, Some(-13.0));
+        assert_eq!(comparison.duration_delta_secs, Some(0.0));
+    }
+
+    #[test]
+    fn test_critical_path_follows_longer_branch() {
+        use crate::types::{RoleId, StepAction, StepSpec};
+
+        let step = |id: &str| StepSpec {
+            id: StepId::new(id),
+            name: id.to_string(),
+            description: None,
+            role: RoleId::new("engineer"),
+            action: StepAction::AgentTask {
+                prompt: id.to_string(),
+            },
+            timeout_secs: None,
+            retry_policy: None,
+            requires_approval: false,
+        };
+        // fetch fans out to build and lint, which both feed package
+        let workflow = WorkflowSpec {
+            steps: ["fetch", "build", "lint", "package"].map(step).to_vec(),
+            dependencies: HashMap::from([
+                (StepId::new("build"), vec![StepId::new("fetch")]),
+                (StepId::new("lint"), vec![StepId::new("fetch")]),
+                (
+                    StepId::new("package"),
+                    vec![StepId::new("build"), StepId::new("lint")],
+                ),
+            ]),
+            inputs: Vec::new(),
+        };
+
+        let analytics = PerformanceAnalytics::new();
+        {
+            let mut stats = analytics.step_stats.lock().unwrap();
+            for (id, avg) in [("fetch", 5.0), ("build", 60.0), ("lint", 10.0), ("package", 5.0)] {
+                stats.insert(id.to_string(), step_stats_with_avg(id, avg));
+            }
+        }
+        let path: Vec<_> = analytics
+            .critical_path(&workflow)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.0)
+            .collect();
+        assert_eq!(path, vec!["fetch", "build", "package"]);
+
+        // Once lint becomes the slow branch, the path moves with it
+        analytics
+            .step_stats
+            .lock()
+            .unwrap()
+            .insert("lint".to_string(), step_stats_with_avg("lint", 90.0));
+        let path = analytics.critical_path(&workflow).unwrap();
+        assert_eq!(path[1], StepId::new("lint"));
+    }
+
+    #[test]
+    fn test_cost_buckets_by_day_and_projection() {
+        let at = |day: u32, hour: u32| {
+            chrono::NaiveDate::from_ymd_opt(2026, 4, day)
+                .unwrap()
+                .and_hms_opt(hour, 30, 0)
+                .unwrap()
+                .and_utc()
+        };
+        let record = |day, hour, cost| CapacityUsage {
+            timestamp: at(day, hour),
+            ..usage(RunId::new(), "step", 100, cost)
+        };
+        let usage = vec![
+            record(1, 9, 1.0),
+            record(1, 17, 2.0),
+            record(3, 8, 4.0),
+            record(4, 23, 3.0),
+            // Last month's spend doesn't count towards this month
+            CapacityUsage {
+                timestamp: at(1, 0) - Duration::days(2),
+                ..usage(RunId::new(), "step", 100, 50.0)
+            },
+        ];
+
+        let now = at(6, 0);
+        let buckets = cost_buckets(&usage[..4], CostGrouping::Day, None, now).unwrap();
+        let days: Vec<_> = buckets.iter().map(|b| (b.start.day(), b.cost, b.requests)).collect();
+        assert_eq!(days, vec![(1, 3.0, 2), (2, 0.0, 0), (3, 4.0, 1), (4, 3.0, 1)]);
+        assert_eq!(buckets[0].start, at(1, 0) - Duration::minutes(30));
+        assert_eq!(buckets[0].tokens, 200);
+        assert_eq!(cost_buckets(&usage[..2], CostGrouping::Hour, None, now).unwrap().len(), 9);
+
+        // Records outside since..=now are left out rather than stretching the range
+        let since = Some(at(3, 0));
+        let buckets = cost_buckets(&usage, CostGrouping::Day, since, at(3, 12)).unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].cost, 4.0);
+
+        // A range needing too many buckets is refused
+        let stray = CapacityUsage {
+            timestamp: at(1, 0) - Duration::days(3650),
+            ..usage[0].clone()
+        };
+        let err = cost_buckets(&[stray, usage[0].clone()], CostGrouping::Hour, None, now)
+            .unwrap_err();
+        assert!(err.is::<ValidationErrors>());
+
+        // Token and request totals don't overflow
+        let heavy = CapacityUsage {
+            total_tokens: u32::MAX,
+            request_count: u32::MAX,
+            ..usage[0].clone()
+        };
+        let buckets =
+            cost_buckets(&[heavy.clone(), heavy], CostGrouping::Day, None, now).unwrap();
+        assert_eq!(buckets[0].tokens, 2 * u64::from(u32::MAX));
+        assert_eq!(buckets[0].requests, 2 * u64::from(u32::MAX));
+
+        // 10 spent over the first 5 of April's 30 days
+        let projection = project_month_cost(&usage, at(6, 0) - Duration::minutes(30));
+        assert_eq!(projection.month_to_date_cost, 10.0);
+        assert!((projection.projected_month_cost - 60.0).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,425 @@
+use crate::audit::{constant_time_eq, hmac_sha256};
+use crate::rbac::{Action, Permission, Resource};
+use crate::tenant::TenantId;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// How long a key may go unused before its next use counts as a new session
+pub const SESSION_IDLE_TIMEOUT_SECS: i64 = 30 * 60;
+
+/// Unique identifier for an API key
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ApiKeyId(pub String);
+
+impl ApiKeyId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+}
+
+/// API key bound to an RBAC user. Only the SHA-256 hash of the key is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: ApiKeyId,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    /// RBAC user the key authenticates as
+    pub user_id: String,
+    pub tenant_id: Option<TenantId>,
+    /// Tenants the key may act on; empty means any tenant
+    pub allowed_tenants: Vec<TenantId>,
+    /// Actions the key may perform; empty means anything the RBAC user may do
+    pub allowed_actions: Vec<Permission>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked: bool,
+}
+
+impl ApiKey {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+
+    /// Whether the key's action scope covers `action` on `resource`
+    pub fn permits_action(&self, resource: Resource, action: Action) -> bool {
+        let requested = Permission::new(resource, action);
+        self.allowed_actions.is_empty() || self.allowed_actions.iter().any(|p| p.matches(&requested))
+    }
+
+    /// Whether the key's tenant scope covers `tenant_id`: its allowed tenants if it lists
+    /// any, otherwise its own tenant if it has one
+    pub fn permits_tenant(&self, tenant_id: &TenantId) -> bool {
+        if !self.allowed_tenants.is_empty() {
+            return self.allowed_tenants.contains(tenant_id);
+        }
+        self.tenant_id.as_ref().is_none_or(|own| own == tenant_id)
+    }
+
+    /// Whether the key is limited to some tenants
+    pub fn is_tenant_scoped(&self) -> bool {
+        self.tenant_id.is_some() || !self.allowed_tenants.is_empty()
+    }
+
+    /// Whether the key is limited to some tenants or actions
+    pub fn is_scoped(&self) -> bool {
+        self.is_tenant_scoped() || !self.allowed_actions.is_empty()
+    }
+
+    /// The tenant a request that names none acts on: the key's own, or its only allowed
+    /// tenant
+    pub fn default_tenant(&self) -> Option<TenantId> {
+        match (&self.tenant_id, self.allowed_tenants.as_slice()) {
+            (Some(own), _) => Some(own.clone()),
+            (None, [only]) => Some(only.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Parameters for issuing a new API key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewApiKey {
+    pub name: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub tenant_id: Option<TenantId>,
+    #[serde(default)]
+    pub allowed_tenants: Vec<TenantId>,
+    #[serde(default)]
+    pub allowed_actions: Vec<Permission>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Store of issued API keys, indexed by key hash
+#[derive(Clone)]
+pub struct ApiKeyStore {
+    keys: Arc<Mutex<HashMap<String, ApiKey>>>,
+}
+
+impl ApiKeyStore {
+    pub fn new() -> Self {
+        Self {
+            keys: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Hash a raw API key for storage and lookup
+    pub fn hash_key(raw_key: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(raw_key.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Issue a new API key. The raw key is returned once and never stored.
+    pub fn create(&self, new_key: NewApiKey) -> Result<(ApiKey, String)> {
+        let raw_key = format!(
+            "sk-{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let key = self.register(&raw_key, new_key)?;
+        Ok((key, raw_key))
+    }
+
+    /// Register a caller-supplied raw key, e.g. a bootstrap key from configuration
+    pub fn register(&self, raw_key: &str, new_key: NewApiKey) -> Result<ApiKey> {
+        if raw_key.is_empty() {
+            return Err(anyhow::anyhow!("API key must not be empty"));
+        }
+
+        let key_hash = Self::hash_key(raw_key);
+        let mut keys = self.keys.lock().unwrap();
+
+        if keys.contains_key(&key_hash) {
+            return Err(anyhow::anyhow!("API key already registered"));
+        }
+
+        let key = ApiKey {
+            id: ApiKeyId::generate(),
+            name: new_key.name,
+            key_hash: key_hash.clone(),
+            user_id: new_key.user_id,
+            tenant_id: new_key.tenant_id,
+            allowed_tenants: new_key.allowed_tenants,
+            allowed_actions: new_key.allowed_actions,
+            created_at: Utc::now(),
+            expires_at: new_key.expires_at,
+            last_used_at: None,
+            revoked: false,
+        };
+
+        keys.insert(key_hash, key.clone());
+        tracing::info!("Registered API key {} for user {}", key.id.0, key.user_id);
+
+        Ok(key)
+    }
+
+    /// Validate a raw key, recording its use. Fails if unknown, revoked, or expired.
+    pub fn validate(&self, raw_key: &str) -> Result<ApiKey> {
+        self.validate_session(raw_key).map(|(key, _)| key)
+    }
+
+    /// Validate a raw key like `validate`, also reporting whether this use starts a
+    /// session: the key's first use, or its first after `SESSION_IDLE_TIMEOUT_SECS`
+    pub fn validate_session(&self, raw_key: &str) -> Result<(ApiKey, bool)> {
+        let mut keys = self.keys.lock().unwrap();
+
+        let key = keys
+            .get_mut(&Self::hash_key(raw_key))
+            .ok_or_else(|| anyhow::anyhow!("Invalid API key"))?;
+
+        if key.revoked {
+            return Err(anyhow::anyhow!("API key revoked"));
+        }
+
+        if key.is_expired() {
+            return Err(anyhow::anyhow!("API key expired"));
+        }
+
+        let now = Utc::now();
+        let new_session = key.last_used_at.is_none_or(|last_used| {
+            now - last_used > chrono::Duration::seconds(SESSION_IDLE_TIMEOUT_SECS)
+        });
+        key.last_used_at = Some(now);
+        Ok((key.clone(), new_session))
+    }
+
+    /// Get an API key by ID
+    pub fn get(&self, key_id: &ApiKeyId) -> Option<ApiKey> {
+        self.keys
+            .lock()
+            .unwrap()
+            .values()
+            .find(|k| k.id == *key_id)
+            .cloned()
+    }
+
+    /// List all API keys
+    pub fn list(&self) -> Vec<ApiKey> {
+        self.keys.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Revoke an API key
+    pub fn revoke(&self, key_id: &ApiKeyId) -> Result<()> {
+        let mut keys = self.keys.lock().unwrap();
+
+        let key = keys
+            .values_mut()
+            .find(|k| k.id == *key_id)
+            .ok_or_else(|| anyhow::anyhow!("API key not found"))?;
+
+        key.revoked = true;
+        tracing::info!("Revoked API key {}", key_id.0);
+
+        Ok(())
+    }
+}
+
+impl Default for ApiKeyStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Header carrying a signed request's HMAC signature
+pub const SIGNATURE_HEADER: &str = "X-Shiioo-Signature";
+/// Header carrying a signed request's Unix timestamp in seconds
+pub const TIMESTAMP_HEADER: &str = "X-Shiioo-Timestamp";
+/// Header carrying a signed request's single-use nonce
+pub const NONCE_HEADER: &str = "X-Shiioo-Nonce";
+
+/// Sign a request with the raw API key. `path` includes the query string, if any.
+pub fn sign_request(
+    raw_key: &str,
+    method: &str,
+    path: &str,
+    body: &[u8],
+    timestamp: i64,
+    nonce: &str,
+) -> String {
+    let mut message =
+        format!("{}\n{}\n{}\n{}\n", method.to_ascii_uppercase(), path, timestamp, nonce)
+            .into_bytes();
+    message.extend_from_slice(body);
+    hmac_sha256(raw_key.as_bytes(), &message)
+}
+
+/// Verifies signed requests, rejecting stale timestamps and replayed nonces
+pub struct RequestVerifier {
+    max_age_secs: u64,
+    // Nonces seen within the window, with the time after which they can be forgotten
+    seen_nonces: Mutex<HashMap<String, i64>>,
+}
+
+impl RequestVerifier {
+    /// Accept requests whose timestamp is within `max_age_secs` of the server's clock
+    pub fn new(max_age_secs: u64) -> Self {
+        Self {
+            max_age_secs,
+            seen_nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Check a request's signature, freshness and nonce. A nonce is only recorded
+    /// once the signature checks out, so forged requests can't burn real nonces.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify(
+        &self,
+        raw_key: &str,
+        method: &str,
+        path: &str,
+        body: &[u8],
+        timestamp: i64,
+        nonce: &str,
+        signature: &str,
+    ) -> Result<()> {
+        let now = Utc::now().timestamp();
+        if now.abs_diff(timestamp) > self.max_age_secs {
+            return Err(anyhow::anyhow!("Request timestamp is outside the allowed window"));
+        }
+
+        if nonce.is_empty() {
+            return Err(anyhow::anyhow!("Request nonce must not be empty"));
+        }
+
+        let expected = sign_request(raw_key, method, path, body, timestamp, nonce);
+        if !constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            return Err(anyhow::anyhow!("Invalid request signature"));
+        }
+
+        let mut seen = self.seen_nonces.lock().unwrap();
+        seen.retain(|_, expires_at| *expires_at >= now);
+        if seen.contains_key(nonce) {
+            return Err(anyhow::anyhow!("Request nonce has already been used"));
+        }
+        let max_age = i64::try_from(self.max_age_secs).unwrap_or(i64::MAX);
+        seen.insert(nonce.to_string(), timestamp.saturating_add(max_age));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn new_key(expires_at: Option<DateTime<Utc>>) -> NewApiKey {
+        NewApiKey {
+            name: "ci".to_string(),
+            user_id: "user1".to_string(),
+            tenant_id: None,
+            allowed_tenants: Vec::new(),
+            allowed_actions: Vec::new(),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_create_and_validate_key() {
+        let store = ApiKeyStore::new();
+        let (key, raw_key) = store.create(new_key(None)).unwrap();
+
+        assert_ne!(key.key_hash, raw_key);
+
+        let (validated, new_session) = store.validate_session(&raw_key).unwrap();
+        assert_eq!(validated.id, key.id);
+        assert_eq!(validated.user_id, "user1");
+        assert!(validated.last_used_at.is_some());
+        assert!(new_session);
+
+        // Later uses continue the same session
+        let (_, new_session) = store.validate_session(&raw_key).unwrap();
+        assert!(!new_session);
+    }
+
+    #[test]
+    fn test_invalid_key_rejected() {
+        let store = ApiKeyStore::new();
+        store.create(new_key(None)).unwrap();
+
+        assert!(store.validate("sk-not-a-real-key").is_err());
+    }
+
+    #[test]
+    fn test_expired_key_rejected() {
+        let store = ApiKeyStore::new();
+        let (_, raw_key) = store
+            .create(new_key(Some(Utc::now() - Duration::hours(1))))
+            .unwrap();
+
+        let err = store.validate(&raw_key).unwrap_err();
+        assert!(err.to_string().contains("expired"));
+    }
+
+    #[test]
+    fn test_revoked_key_rejected() {
+        let store = ApiKeyStore::new();
+        let (key, raw_key) = store.create(new_key(None)).unwrap();
+
+        store.revoke(&key.id).unwrap();
+
+        let err = store.validate(&raw_key).unwrap_err();
+        assert!(err.to_string().contains("revoked"));
+    }
+
+    #[test]
+    fn test_action_and_tenant_scopes() {
+        let store = ApiKeyStore::new();
+        let mut scoped = new_key(None);
+        scoped.allowed_actions = vec![Permission::new(Resource::All, Action::Read)];
+        scoped.allowed_tenants = vec![TenantId::new("tenant1")];
+        let (key, _) = store.create(scoped).unwrap();
+
+        assert!(key.permits_action(Resource::Workflow, Action::Read));
+        assert!(!key.permits_action(Resource::Workflow, Action::Execute));
+        assert!(key.permits_tenant(&TenantId::new("tenant1")));
+        assert!(!key.permits_tenant(&TenantId::new("tenant2")));
+
+        assert!(key.is_scoped());
+        assert_eq!(key.default_tenant(), Some(TenantId::new("tenant1")));
+
+        let (unscoped, _) = store.create(new_key(None)).unwrap();
+        assert!(unscoped.permits_action(Resource::Secret, Action::Delete));
+        assert!(unscoped.permits_tenant(&TenantId::new("tenant2")));
+        assert!(!unscoped.is_scoped());
+        assert_eq!(unscoped.default_tenant(), None);
+
+        // A key with its own tenant and no allowed tenants is confined to that tenant
+        let mut own = new_key(None);
+        own.tenant_id = Some(TenantId::new("tenant1"));
+        let (own, _) = store.create(own).unwrap();
+        assert!(own.permits_tenant(&TenantId::new("tenant1")));
+        assert!(!own.permits_tenant(&TenantId::new("tenant2")));
+    }
+
+    #[test]
+    fn test_verifier_rejects_extreme_timestamps() {
+        let verifier = RequestVerifier::new(300);
+        for timestamp in [i64::MIN, i64::MAX] {
+            let signature = sign_request("key", "POST", "/api/jobs", b"{}", timestamp, "n1");
+            let err = verifier
+                .verify("key", "POST", "/api/jobs", b"{}", timestamp, "n1", &signature)
+                .unwrap_err();
+            assert!(err.to_string().contains("outside the allowed window"));
+        }
+
+        // A window wider than any timestamp accepts them without overflowing the nonce expiry
+        let verifier = RequestVerifier::new(u64::MAX);
+        let signature = sign_request("key", "POST", "/api/jobs", b"{}", i64::MAX, "n2");
+        verifier
+            .verify("key", "POST", "/api/jobs", b"{}", i64::MAX, "n2", &signature)
+            .unwrap();
+    }
+}
@@ -1,29 +1,321 @@
 use crate::types::{
     Approval, ApprovalBoard, ApprovalBoardId, ApprovalId, ApprovalStatus, ApprovalSubject,
-    ApprovalVote, PersonId, QuorumRule, VoteDecision,
+    ApprovalVote, ForcedResolution, PersonId, QuorumRule, VoteDecision,
 };
+use crate::clock::{Clock, SystemClock};
+use crate::validation::ValidationErrors;
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Maximum nesting depth of `All`/`Any`/`Scoped` quorum rules
+pub const MAX_QUORUM_RULE_DEPTH: usize = 8;
+
+/// Longest a reminder policy can wait before or between reminders (a year)
+pub const MAX_REMINDER_DELAY_SECS: u64 = 365 * 24 * 3600;
+
+/// A single vote within a bulk vote
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkVote {
+    pub approval_id: ApprovalId,
+    pub decision: VoteDecision,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Outcome of one vote within a bulk vote
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkVoteResult {
+    pub approval_id: ApprovalId,
+    /// Approval status after the vote, if it was accepted
+    pub status: Option<ApprovalStatus>,
+    pub error: Option<String>,
+}
+
+/// When approvers who haven't voted are reminded of a pending approval. Reminders stop
+/// once the approval resolves; they don't expire it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReminderPolicy {
+    /// Send the first reminder once an approval has been pending this long
+    #[serde(default = "default_reminder_after_secs")]
+    pub after_secs: u64,
+
+    /// Then remind again this often
+    #[serde(default = "default_reminder_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Stop after this many reminders per approval. Zero disables reminders.
+    #[serde(default = "default_max_reminders")]
+    pub max_reminders: u32,
+}
+
+fn default_reminder_after_secs() -> u64 {
+    24 * 3600
+}
+
+fn default_reminder_interval_secs() -> u64 {
+    24 * 3600
+}
+
+fn default_max_reminders() -> u32 {
+    3
+}
+
+impl ReminderPolicy {
+    /// Check the delays are within `MAX_REMINDER_DELAY_SECS` and that repeated
+    /// reminders are spaced apart
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = ValidationErrors::new();
+        let delays = [("after_secs", self.after_secs), ("interval_secs", self.interval_secs)];
+        for (field, secs) in delays {
+            if secs > MAX_REMINDER_DELAY_SECS {
+                errors.push(field, format!("Must be at most {}", MAX_REMINDER_DELAY_SECS));
+            }
+        }
+        if self.interval_secs == 0 && self.max_reminders > 1 {
+            errors.push("interval_secs", "Must be positive when sending more than one reminder");
+        }
+        errors.into_result()
+    }
+}
+
+impl Default for ReminderPolicy {
+    fn default() -> Self {
+        Self {
+            after_secs: default_reminder_after_secs(),
+            interval_secs: default_reminder_interval_secs(),
+            max_reminders: default_max_reminders(),
+        }
+    }
+}
+
+/// A nudge to the approvers who haven't voted on a pending approval
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalReminder {
+    pub approval_id: ApprovalId,
+    pub board_id: ApprovalBoardId,
+    /// Approvers yet to vote
+    pub approvers: Vec<PersonId>,
+    /// How many reminders have now been sent for the approval, counting this one
+    pub reminder: u32,
+    pub pending_since: DateTime<Utc>,
+}
+
+/// Acts on the resolution of `ApprovalSubject::Custom` approvals of one subject type,
+/// e.g. by calling a webhook or flipping a feature flag. Handlers run on the call that
+/// resolves the approval, so slow work should be handed off to a task.
+pub trait ApprovalResolutionHandler: Send + Sync {
+    /// `approval` has been approved or denied; `subject_id` identifies what it was for
+    fn on_resolved(&self, subject_id: &str, approval: &Approval) -> Result<()>;
+}
+
+/// Delivers approval reminders beyond subscribers of `subscribe_reminders`, e.g. by
+/// calling a webhook or recording them in the audit log. Handlers run on the reminder
+/// pass, so slow work should be handed off to a task.
+pub trait ApprovalReminderHandler: Send + Sync {
+    fn on_reminder(&self, reminder: &ApprovalReminder) -> Result<()>;
+}
 
 /// Approval board manager
 pub struct ApprovalManager {
     boards: Arc<Mutex<HashMap<ApprovalBoardId, ApprovalBoard>>>,
     approvals: Arc<Mutex<HashMap<ApprovalId, Approval>>>,
+    // Notifies subscribers of each approval as it is resolved
+    resolved: broadcast::Sender<Approval>,
+    reminder_policy: ReminderPolicy,
+    // Reminders sent so far for each pending approval
+    reminders_sent: Arc<Mutex<HashMap<ApprovalId, u32>>>,
+    reminders: broadcast::Sender<ApprovalReminder>,
+    reminder_handlers: Arc<Mutex<Vec<Arc<dyn ApprovalReminderHandler>>>>,
+    // Handlers for custom approvals, keyed by subject type
+    resolution_handlers: Arc<Mutex<HashMap<String, Arc<dyn ApprovalResolutionHandler>>>>,
+    clock: Arc<dyn Clock>,
 }
 
 impl ApprovalManager {
     /// Create a new approval manager
     pub fn new() -> Self {
+        let (resolved, _) = broadcast::channel(256);
+        let (reminders, _) = broadcast::channel(256);
         Self {
             boards: Arc::new(Mutex::new(HashMap::new())),
             approvals: Arc::new(Mutex::new(HashMap::new())),
+            resolved,
+            reminder_policy: ReminderPolicy::default(),
+            reminders_sent: Arc::new(Mutex::new(HashMap::new())),
+            reminders,
+            reminder_handlers: Arc::new(Mutex::new(Vec::new())),
+            resolution_handlers: Arc::new(Mutex::new(HashMap::new())),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Remind approvers according to `policy` instead of the default
+    pub fn with_reminder_policy(mut self, policy: ReminderPolicy) -> Self {
+        self.reminder_policy = policy;
+        self
+    }
+
+    /// Use `clock` for approval timestamps and reminder due times
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Receive each approval as a decisive vote resolves it
+    pub fn subscribe(&self) -> broadcast::Receiver<Approval> {
+        self.resolved.subscribe()
+    }
+
+    /// Invoke `handler` whenever a custom approval of `subject_type` resolves, replacing
+    /// any handler already registered for it
+    pub fn register_resolution_handler(
+        &self,
+        subject_type: impl Into<String>,
+        handler: Arc<dyn ApprovalResolutionHandler>,
+    ) {
+        self.resolution_handlers
+            .lock()
+            .unwrap()
+            .insert(subject_type.into(), handler);
+    }
+
+    /// Announce a resolved approval and run its custom subject's handler, if any. A
+    /// failing handler is logged; the resolution stands.
+    fn notify_resolved(&self, approval: &Approval) {
+        let _ = self.resolved.send(approval.clone());
+
+        let ApprovalSubject::Custom {
+            subject_type,
+            subject_id,
+        } = &approval.subject
+        else {
+            return;
+        };
+        let handler = self.resolution_handlers.lock().unwrap().get(subject_type).cloned();
+        if let Some(handler) = handler {
+            if let Err(e) = handler.on_resolved(subject_id, approval) {
+                tracing::error!(
+                    "Resolution handler for {} approval {} failed: {}",
+                    subject_type,
+                    approval.id.0,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Receive each reminder `send_due_reminders` sends
+    pub fn subscribe_reminders(&self) -> broadcast::Receiver<ApprovalReminder> {
+        self.reminders.subscribe()
+    }
+
+    /// Hand every reminder to `handler` as well as to subscribers
+    pub fn add_reminder_handler(&self, handler: Arc<dyn ApprovalReminderHandler>) {
+        self.reminder_handlers.lock().unwrap().push(handler);
+    }
+
+    /// Remind the approvers who haven't voted on each approval pending past its next
+    /// reminder time, at most once per approval per call. Returns the reminders sent.
+    pub fn send_due_reminders(&self) -> Vec<ApprovalReminder> {
+        let reminders = self.due_reminders();
+
+        let handlers = self.reminder_handlers.lock().unwrap().clone();
+        for reminder in &reminders {
+            let _ = self.reminders.send(reminder.clone());
+            for handler in &handlers {
+                if let Err(e) = handler.on_reminder(reminder) {
+                    tracing::error!(
+                        "Reminder handler failed for approval {}: {}",
+                        reminder.approval_id.0,
+                        e
+                    );
+                }
+            }
+        }
+
+        if !reminders.is_empty() {
+            tracing::info!("Sent {} approval reminders", reminders.len());
+        }
+        reminders
+    }
+
+    /// Reminders now due, counted as sent
+    fn due_reminders(&self) -> Vec<ApprovalReminder> {
+        let policy = &self.reminder_policy;
+        let now = self.clock.now();
+        let approvals = self.approvals.lock().unwrap();
+        let mut sent = self.reminders_sent.lock().unwrap();
+
+        // Resolved approvals get no more reminders
+        sent.retain(|id, _| {
+            approvals
+                .get(id)
+                .is_some_and(|a| a.status == ApprovalStatus::Pending)
+        });
+
+        let mut reminders = Vec::new();
+        for approval in approvals
+            .values()
+            .filter(|a| a.status == ApprovalStatus::Pending)
+        {
+            let count = sent.get(&approval.id).copied().unwrap_or(0);
+            if count >= policy.max_reminders {
+                continue;
+            }
+            // A due time too far off to represent is never reached
+            let due_at = policy
+                .interval_secs
+                .checked_mul(u64::from(count))
+                .and_then(|later| later.checked_add(policy.after_secs))
+                .and_then(|secs| i64::try_from(secs).ok())
+                .and_then(Duration::try_seconds)
+                .and_then(|wait| approval.created_at.checked_add_signed(wait));
+            if due_at.is_none_or(|due_at| now < due_at) {
+                continue;
+            }
+
+            let Some(board) = self.get_board(&approval.board_id) else {
+                continue;
+            };
+            let approvers: Vec<PersonId> = approval
+                .roster(&board)
+                .iter()
+                .filter(|person| !approval.votes.iter().any(|v| &v.voter == *person))
+                .cloned()
+                .collect();
+            if approvers.is_empty() {
+                continue;
+            }
+
+            sent.insert(approval.id.clone(), count + 1);
+            let reminder = ApprovalReminder {
+                approval_id: approval.id.clone(),
+                board_id: approval.board_id.clone(),
+                approvers,
+                reminder: count + 1,
+                pending_since: approval.created_at,
+            };
+            reminders.push(reminder);
+        }
+        reminders
+    }
+
     /// Register an approval board
     pub fn register_board(&self, board: ApprovalBoard) -> Result<()> {
+        let mut errors = ValidationErrors::new();
+        validate_quorum_rule(
+            &board.quorum_rule,
+            &board.approvers,
+            &board.approvers,
+            "quorum_rule",
+            0,
+            &mut errors,
+        );
+        errors.into_result()?;
         self.boards.lock().unwrap().insert(board.id.clone(), board);
         Ok(())
     }
@@ -61,10 +353,12 @@ impl ApprovalManager {
             board_id,
             subject,
             status: ApprovalStatus::Pending,
+            approvers: board.approvers.clone(),
             votes: Vec::new(),
-            created_at: Utc::now(),
+            created_at: self.clock.now(),
             created_by,
             resolved_at: None,
+            forced_resolution: None,
         };
 
         self.approvals
@@ -76,7 +370,7 @@ impl ApprovalManager {
             "Created approval {} on board {} with {} approvers",
             approval.id.0,
             board.name,
-            board.approvers.len()
+            approval.approvers.len()
         );
 
         Ok(approval)
@@ -100,12 +394,12 @@ impl ApprovalManager {
             return Err(anyhow::anyhow!("Approval already resolved"));
         }
 
-        // Check if voter is on the board
+        // Check if voter was on the board when the approval was created
         let board = self
             .get_board(&approval.board_id)
             .ok_or_else(|| anyhow::anyhow!("Approval board not found"))?;
 
-        if !board.approvers.contains(&voter) {
+        if !approval.roster(&board).contains(&voter) {
             return Err(anyhow::anyhow!("Voter is not an approver on this board"));
         }
 
@@ -119,84 +413,102 @@ impl ApprovalManager {
             voter,
             vote,
             comment,
-            voted_at: Utc::now(),
+            voted_at: self.clock.now(),
         });
 
         // Check if quorum is met
-        let result = self.check_quorum(&board, &approval.votes)?;
+        let result = self.check_quorum(&board, approval)?;
         if result != ApprovalStatus::Pending {
             approval.status = result;
-            approval.resolved_at = Some(Utc::now());
+            approval.resolved_at = Some(self.clock.now());
             tracing::info!(
                 "Approval {} resolved with status: {:?}",
                 approval.id.0,
                 result
             );
+            // Handlers may call back into the manager, so release the lock first
+            let approval = approval.clone();
+            drop(approvals);
+            self.notify_resolved(&approval);
         }
 
         Ok(result)
     }
 
-    /// Check if quorum is met
+    /// Decide a pending approval without reaching quorum, e.g. when the board is deadlocked.
+    /// The overriding admin and their justification are recorded on the approval.
+    pub fn force_resolve(
+        &self,
+        approval_id: &ApprovalId,
+        status: ApprovalStatus,
+        resolved_by: String,
+        justification: String,
+    ) -> Result<Approval> {
+        if status == ApprovalStatus::Pending {
+            return Err(anyhow::anyhow!("A forced resolution must approve or deny"));
+        }
+        if justification.trim().is_empty() {
+            return Err(anyhow::anyhow!("A justification is required to force-resolve an approval"));
+        }
+
+        let mut approvals = self.approvals.lock().unwrap();
+        let approval = approvals
+            .get_mut(approval_id)
+            .ok_or_else(|| anyhow::anyhow!("Approval not found"))?;
+
+        if approval.status != ApprovalStatus::Pending {
+            return Err(anyhow::anyhow!("Approval already resolved"));
+        }
+
+        tracing::warn!(
+            "Approval {} force-resolved with status {:?} by {}",
+            approval.id.0,
+            status,
+            resolved_by
+        );
+        approval.status = status;
+        approval.resolved_at = Some(self.clock.now());
+        approval.forced_resolution = Some(ForcedResolution {
+            resolved_by,
+            justification,
+        });
+        let approval = approval.clone();
+        drop(approvals);
+        self.notify_resolved(&approval);
+
+        Ok(approval)
+    }
+
+    /// Cast a voter's decisions on several approvals. Each vote is applied independently,
+    /// so an invalid vote is reported in its result without aborting the rest.
+    pub fn cast_votes(&self, voter: &PersonId, votes: Vec<BulkVote>) -> Vec<BulkVoteResult> {
+        votes
+            .into_iter()
+            .map(|vote| {
+                match self.cast_vote(&vote.approval_id, voter.clone(), vote.decision, vote.comment) {
+                    Ok(status) => BulkVoteResult {
+                        approval_id: vote.approval_id,
+                        status: Some(status),
+                        error: None,
+                    },
+                    Err(e) => BulkVoteResult {
+                        approval_id: vote.approval_id,
+                        status: None,
+                        error: Some(e.to_string()),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Check if quorum is met against the approval's snapshotted roster
     fn check_quorum(
         &self,
         board: &ApprovalBoard,
-        votes: &[ApprovalVote],
+        approval: &Approval,
     ) -> Result<ApprovalStatus> {
-        let total_approvers = board.approvers.len();
-        let approve_votes = votes.iter().filter(|v| v.vote == VoteDecision::Approve).count();
-        let reject_votes = votes.iter().filter(|v| v.vote == VoteDecision::Reject).count();
-        let abstain_votes = votes.iter().filter(|v| v.vote == VoteDecision::Abstain).count();
-
-        match &board.quorum_rule {
-            QuorumRule::Unanimous => {
-                // All approvers must approve
-                if approve_votes == total_approvers {
-                    Ok(ApprovalStatus::Approved)
-                } else if reject_votes > 0 {
-                    Ok(ApprovalStatus::Denied)
-                } else {
-                    Ok(ApprovalStatus::Pending)
-                }
-            }
-            QuorumRule::Majority => {
-                // More than 50% must approve
-                let required = (total_approvers / 2) + 1;
-                if approve_votes >= required {
-                    Ok(ApprovalStatus::Approved)
-                } else if reject_votes >= required {
-                    Ok(ApprovalStatus::Denied)
-                } else if approve_votes + reject_votes + abstain_votes == total_approvers {
-                    // All voted but no quorum
-                    Ok(ApprovalStatus::Denied)
-                } else {
-                    Ok(ApprovalStatus::Pending)
-                }
-            }
-            QuorumRule::MinCount { min } => {
-                // At least N approvers must approve
-                if approve_votes >= *min as usize {
-                    Ok(ApprovalStatus::Approved)
-                } else if (total_approvers - reject_votes) < *min as usize {
-                    // Not enough approvers left to reach quorum
-                    Ok(ApprovalStatus::Denied)
-                } else {
-                    Ok(ApprovalStatus::Pending)
-                }
-            }
-            QuorumRule::Percentage { percent } => {
-                // At least X% of approvers must approve
-                let required = ((total_approvers as f64 * (*percent as f64 / 100.0)).ceil()) as usize;
-                if approve_votes >= required {
-                    Ok(ApprovalStatus::Approved)
-                } else if (total_approvers - reject_votes) < required {
-                    // Not enough approvers left to reach quorum
-                    Ok(ApprovalStatus::Denied)
-                } else {
-                    Ok(ApprovalStatus::Pending)
-                }
-            }
-        }
+        let total_approvers = approval.roster(board).len();
+        evaluate_quorum(&board.quorum_rule, total_approvers, &approval.votes, 0)
     }
 
     /// List all approvals
@@ -220,23 +532,70 @@ impl ApprovalManager {
             .collect()
     }
 
-    /// List approvals for a person
+    /// List approvals a person is an approver on
     pub fn list_approvals_for_person(&self, person_id: &PersonId) -> Vec<Approval> {
-        let boards = self.boards.lock().unwrap();
-        let approvals = self.approvals.lock().unwrap();
-
-        approvals
+        let boards = self.boards.lock().unwrap().clone();
+        self.approvals
+            .lock()
+            .unwrap()
             .values()
-            .filter(|a| {
-                if let Some(board) = boards.get(&a.board_id) {
-                    board.approvers.contains(person_id)
-                } else {
-                    false
-                }
+            .filter(|a| match boards.get(&a.board_id) {
+                Some(board) => a.roster(board).contains(person_id),
+                None => a.approvers.contains(person_id),
             })
             .cloned()
             .collect()
     }
+
+    /// Compute time-in-state statistics for approvals, optionally scoped to a board
+    pub fn get_approval_stats(&self, board_id: Option<&ApprovalBoardId>) -> ApprovalStats {
+        let approvals = self.approvals.lock().unwrap();
+
+        let mut pending_count = 0;
+        let mut approved_count = 0;
+        let mut denied_count = 0;
+        let mut durations: Vec<f64> = Vec::new();
+
+        for approval in approvals
+            .values()
+            .filter(|a| board_id.is_none_or(|id| a.board_id == *id))
+        {
+            match approval.status {
+                ApprovalStatus::Pending => pending_count += 1,
+                ApprovalStatus::Approved => approved_count += 1,
+                ApprovalStatus::Denied => denied_count += 1,
+            }
+
+            if let Some(resolved_at) = approval.resolved_at {
+                let secs = (resolved_at - approval.created_at).num_milliseconds() as f64 / 1000.0;
+                durations.push(secs.max(0.0));
+            }
+        }
+
+        durations.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let resolved_count = approved_count + denied_count;
+        let (approval_rate, denial_rate) = if resolved_count > 0 {
+            (
+                approved_count as f64 / resolved_count as f64,
+                denied_count as f64 / resolved_count as f64,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+
+        ApprovalStats {
+            board_id: board_id.cloned(),
+            resolved_count,
+            pending_count,
+            approved_count,
+            denied_count,
+            approval_rate,
+            denial_rate,
+            median_resolution_secs: median(&durations),
+            p95_resolution_secs: percentile(&durations, 95.0),
+        }
+    }
 }
 
 impl Default for ApprovalManager {
@@ -245,6 +604,305 @@ impl Default for ApprovalManager {
     }
 }
 
+/// Approval turnaround statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalStats {
+    pub board_id: Option<ApprovalBoardId>,
+    pub resolved_count: usize,
+    pub pending_count: usize,
+    pub approved_count: usize,
+    pub denied_count: usize,
+    /// Fraction of resolved approvals that were approved (0.0 - 1.0)
+    pub approval_rate: f64,
+    /// Fraction of resolved approvals that were denied (0.0 - 1.0)
+    pub denial_rate: f64,
+    /// Median time from creation to resolution
+    pub median_resolution_secs: Option<f64>,
+    /// 95th percentile time from creation to resolution
+    pub p95_resolution_secs: Option<f64>,
+}
+
+/// Median of a sorted slice
+fn median(sorted: &[f64]) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    } else {
+        Some(sorted[mid])
+    }
+}
+
+impl ApprovalBoard {
+    /// Fewest approve votes that can approve a request under the board's quorum rule.
+    ///
+    /// A vote can count towards several sub-rules of an `All`, so its count is the
+    /// largest of its sub-rules', or their sum when they are scoped to disjoint groups.
+    /// May exceed the roster if the rule can never be met.
+    pub fn min_approvals_needed(&self) -> usize {
+        min_approvals(&self.quorum_rule, self.approvers.len())
+    }
+
+    /// Human-readable quorum requirement, e.g. "Needs 2 of 3 approvers (majority)"
+    pub fn quorum_description(&self) -> String {
+        format!(
+            "Needs {}",
+            describe_quorum(&self.quorum_rule, self.approvers.len())
+        )
+    }
+}
+
+/// Fewest approve votes satisfying `rule` among `total_approvers`
+fn min_approvals(rule: &QuorumRule, total_approvers: usize) -> usize {
+    match rule {
+        QuorumRule::Unanimous => total_approvers,
+        QuorumRule::Majority => (total_approvers / 2) + 1,
+        QuorumRule::MinCount { min } => *min as usize,
+        QuorumRule::Percentage { percent } => {
+            ((total_approvers as f64 * (*percent as f64 / 100.0)).ceil()) as usize
+        }
+        QuorumRule::All(rules) => {
+            let counts = rules.iter().map(|rule| min_approvals(rule, total_approvers));
+            if scopes_are_disjoint(rules) {
+                counts.sum()
+            } else {
+                counts.max().unwrap_or(0)
+            }
+        }
+        QuorumRule::Any(rules) => rules
+            .iter()
+            .map(|rule| min_approvals(rule, total_approvers))
+            .min()
+            .unwrap_or(0),
+        QuorumRule::Scoped { approvers, rule } => min_approvals(rule, approvers.len()),
+    }
+}
+
+/// Whether every rule is scoped and no approver appears in two scopes
+fn scopes_are_disjoint(rules: &[QuorumRule]) -> bool {
+    let mut seen = std::collections::HashSet::new();
+    rules.iter().all(|rule| match rule {
+        QuorumRule::Scoped { approvers, .. } => approvers.iter().all(|a| seen.insert(a)),
+        _ => false,
+    })
+}
+
+fn describe_quorum(rule: &QuorumRule, total_approvers: usize) -> String {
+    let count = |qualifier: &str| {
+        format!(
+            "{} of {} approvers{}",
+            min_approvals(rule, total_approvers),
+            total_approvers,
+            qualifier
+        )
+    };
+    let join = |rules: &[QuorumRule]| {
+        rules
+            .iter()
+            .map(|rule| describe_quorum(rule, total_approvers))
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+
+    match rule {
+        QuorumRule::Unanimous => count(" (unanimous)"),
+        QuorumRule::Majority => count(" (majority)"),
+        QuorumRule::MinCount { .. } => count(""),
+        QuorumRule::Percentage { percent } => count(&format!(" ({}%)", percent)),
+        QuorumRule::All(rules) => format!("all of: {}", join(rules)),
+        QuorumRule::Any(rules) => format!("any of: {}", join(rules)),
+        QuorumRule::Scoped { approvers, rule } => {
+            let names: Vec<_> = approvers.iter().map(|a| a.0.as_str()).collect();
+            format!(
+                "{} among {}",
+                describe_quorum(rule, approvers.len()),
+                names.join(", ")
+            )
+        }
+    }
+}
+
+/// Nearest-rank percentile of a sorted slice
+fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() || !(0.0..=100.0).contains(&p) {
+        return None;
+    }
+
+    let rank = ((sorted.len() as f64) * (p / 100.0)).ceil() as usize;
+    Some(sorted[rank.saturating_sub(1).min(sorted.len() - 1)])
+}
+
+/// Collect the problems with a quorum rule evaluated over `approvers`: nesting deeper
+/// than `MAX_QUORUM_RULE_DEPTH`, composites without sub-rules, unanimity over nobody
+/// (which would pass without a vote) and scopes naming people off the board
+fn validate_quorum_rule(
+    rule: &QuorumRule,
+    approvers: &[PersonId],
+    board_approvers: &[PersonId],
+    field: &str,
+    depth: usize,
+    errors: &mut ValidationErrors,
+) {
+    if depth > MAX_QUORUM_RULE_DEPTH {
+        errors.push(
+            field,
+            format!(
+                "Quorum rule nesting exceeds maximum depth of {}",
+                MAX_QUORUM_RULE_DEPTH
+            ),
+        );
+        return;
+    }
+
+    match rule {
+        QuorumRule::Unanimous if approvers.is_empty() => {
+            errors.push(field, "Unanimous quorum needs at least one approver");
+        }
+        QuorumRule::All(rules) | QuorumRule::Any(rules) => {
+            if rules.is_empty() {
+                errors.push(field, "Composite quorum rule has no sub-rules");
+            }
+            for (i, sub_rule) in rules.iter().enumerate() {
+                let field = format!("{}[{}]", field, i);
+                validate_quorum_rule(
+                    sub_rule,
+                    approvers,
+                    board_approvers,
+                    &field,
+                    depth + 1,
+                    errors,
+                );
+            }
+        }
+        QuorumRule::Scoped {
+            approvers: scoped,
+            rule,
+        } => {
+            if scoped.is_empty() {
+                errors.push(
+                    format!("{}.approvers", field),
+                    "Scoped quorum rule has no approvers",
+                );
+            }
+            for (i, approver) in scoped.iter().enumerate() {
+                if !board_approvers.contains(approver) {
+                    errors.push(
+                        format!("{}.approvers[{}]", field, i),
+                        format!("{} is not an approver on the board", approver.0),
+                    );
+                }
+            }
+            let field = format!("{}.rule", field);
+            validate_quorum_rule(rule, scoped, board_approvers, &field, depth + 1, errors);
+        }
+        _ => {}
+    }
+}
+
+/// Evaluate a quorum rule against the votes cast by `total_approvers` approvers
+fn evaluate_quorum(
+    rule: &QuorumRule,
+    total_approvers: usize,
+    votes: &[ApprovalVote],
+    depth: usize,
+) -> Result<ApprovalStatus> {
+    if depth > MAX_QUORUM_RULE_DEPTH {
+        return Err(anyhow::anyhow!(
+            "Quorum rule nesting exceeds maximum depth of {}",
+            MAX_QUORUM_RULE_DEPTH
+        ));
+    }
+
+    let approve_votes = votes.iter().filter(|v| v.vote == VoteDecision::Approve).count();
+    let reject_votes = votes.iter().filter(|v| v.vote == VoteDecision::Reject).count();
+    let abstain_votes = votes.iter().filter(|v| v.vote == VoteDecision::Abstain).count();
+
+    match rule {
+        QuorumRule::Unanimous => {
+            // All approvers must approve
+            if approve_votes == total_approvers {
+                Ok(ApprovalStatus::Approved)
+            } else if reject_votes > 0 {
+                Ok(ApprovalStatus::Denied)
+            } else {
+                Ok(ApprovalStatus::Pending)
+            }
+        }
+        QuorumRule::Majority => {
+            // More than 50% must approve
+            let required = (total_approvers / 2) + 1;
+            if approve_votes >= required {
+                Ok(ApprovalStatus::Approved)
+            } else if reject_votes >= required {
+                Ok(ApprovalStatus::Denied)
+            } else if approve_votes + reject_votes + abstain_votes == total_approvers {
+                // All voted but no quorum
+                Ok(ApprovalStatus::Denied)
+            } else {
+                Ok(ApprovalStatus::Pending)
+            }
+        }
+        QuorumRule::MinCount { min } => {
+            // At least N approvers must approve
+            if approve_votes >= *min as usize {
+                Ok(ApprovalStatus::Approved)
+            } else if (total_approvers - reject_votes) < *min as usize {
+                // Not enough approvers left to reach quorum
+                Ok(ApprovalStatus::Denied)
+            } else {
+                Ok(ApprovalStatus::Pending)
+            }
+        }
+        QuorumRule::Percentage { percent } => {
+            // At least X% of approvers must approve
+            let required = ((total_approvers as f64 * (*percent as f64 / 100.0)).ceil()) as usize;
+            if approve_votes >= required {
+                Ok(ApprovalStatus::Approved)
+            } else if (total_approvers - reject_votes) < required {
+                // Not enough approvers left to reach quorum
+                Ok(ApprovalStatus::Denied)
+            } else {
+                Ok(ApprovalStatus::Pending)
+            }
+        }
+        QuorumRule::All(rules) => {
+            // Denied as soon as any branch is denied, approved once every branch is
+            let mut status = ApprovalStatus::Approved;
+            for rule in rules {
+                match evaluate_quorum(rule, total_approvers, votes, depth + 1)? {
+                    ApprovalStatus::Denied => return Ok(ApprovalStatus::Denied),
+                    ApprovalStatus::Pending => status = ApprovalStatus::Pending,
+                    ApprovalStatus::Approved => {}
+                }
+            }
+            Ok(status)
+        }
+        QuorumRule::Any(rules) => {
+            // Approved as soon as any branch is approved, denied once every branch is
+            let mut status = ApprovalStatus::Denied;
+            for rule in rules {
+                match evaluate_quorum(rule, total_approvers, votes, depth + 1)? {
+                    ApprovalStatus::Approved => return Ok(ApprovalStatus::Approved),
+                    ApprovalStatus::Pending => status = ApprovalStatus::Pending,
+                    ApprovalStatus::Denied => {}
+                }
+            }
+            Ok(status)
+        }
+        QuorumRule::Scoped { approvers, rule } => {
+            let scoped_votes: Vec<ApprovalVote> = votes
+                .iter()
+                .filter(|v| approvers.contains(&v.voter))
+                .cloned()
+                .collect();
+            evaluate_quorum(rule, approvers.len(), &scoped_votes, depth + 1)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -550,4 +1208,617 @@ mod tests {
         );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_open_approval_keeps_original_roster() {
+        let manager = ApprovalManager::new();
+        let mut board = create_test_board();
+        board.quorum_rule = QuorumRule::Unanimous;
+        manager.register_board(board.clone()).unwrap();
+
+        let approval = manager
+            .create_approval(
+                board.id.clone(),
+                ApprovalSubject::ConfigChange {
+                    change_id: ConfigChangeId::new("test_change"),
+                },
+                "admin".to_string(),
+            )
+            .unwrap();
+        assert_eq!(approval.approvers, board.approvers);
+
+        manager
+            .cast_vote(&approval.id, PersonId::new("approver1"), VoteDecision::Approve, None)
+            .unwrap();
+
+        // Remove the approver who already voted and add a newcomer
+        board.approvers = vec![
+            PersonId::new("approver2"),
+            PersonId::new("approver3"),
+            PersonId::new("approver4"),
+        ];
+        manager.register_board(board.clone()).unwrap();
+
+        // The newcomer can't vote on the open approval
+        assert!(manager
+            .cast_vote(&approval.id, PersonId::new("approver4"), VoteDecision::Approve, None)
+            .is_err());
+
+        // Unanimity is still measured against the original three, including approver1's vote
+        let status = manager
+            .cast_vote(&approval.id, PersonId::new("approver2"), VoteDecision::Approve, None)
+            .unwrap();
+        assert_eq!(status, ApprovalStatus::Pending);
+        let status = manager
+            .cast_vote(&approval.id, PersonId::new("approver3"), VoteDecision::Approve, None)
+            .unwrap();
+        assert_eq!(status, ApprovalStatus::Approved);
+
+        // New approvals pick up the edited roster
+        let next = manager
+            .create_approval(
+                board.id.clone(),
+                ApprovalSubject::ConfigChange {
+                    change_id: ConfigChangeId::new("next_change"),
+                },
+                "admin".to_string(),
+            )
+            .unwrap();
+        assert_eq!(next.approvers, board.approvers);
+    }
+
+    #[test]
+    fn test_legacy_approval_without_roster_uses_board_roster() {
+        let manager = ApprovalManager::new();
+        let board = create_test_board();
+        manager.register_board(board.clone()).unwrap();
+
+        // Recorded before approvals snapshotted the board's roster
+        let legacy: Approval = serde_json::from_str(
+            r#"{
+                "id": "legacy",
+                "board_id": "test_board",
+                "subject": {"config_change": {"change_id": "old_change"}},
+                "status": "pending",
+                "votes": [],
+                "created_at": "2026-01-01T00:00:00Z",
+                "created_by": "admin",
+                "resolved_at": null
+            }"#,
+        )
+        .unwrap();
+        assert!(legacy.approvers.is_empty());
+        assert_eq!(legacy.roster(&board), board.approvers.as_slice());
+        manager
+            .approvals
+            .lock()
+            .unwrap()
+            .insert(legacy.id.clone(), legacy.clone());
+
+        assert_eq!(
+            manager.list_approvals_for_person(&PersonId::new("approver1")).len(),
+            1
+        );
+        assert!(manager
+            .cast_vote(&legacy.id, PersonId::new("outsider"), VoteDecision::Approve, None)
+            .is_err());
+
+        // Majority of the board's three approvers
+        let status = manager
+            .cast_vote(&legacy.id, PersonId::new("approver1"), VoteDecision::Approve, None)
+            .unwrap();
+        assert_eq!(status, ApprovalStatus::Pending);
+        let status = manager
+            .cast_vote(&legacy.id, PersonId::new("approver2"), VoteDecision::Approve, None)
+            .unwrap();
+        assert_eq!(status, ApprovalStatus::Approved);
+    }
+
+    #[test]
+    fn test_approval_stats() {
+        let manager = ApprovalManager::new();
+        let board = create_test_board();
+        manager.register_board(board.clone()).unwrap();
+
+        // Resolve approvals with known durations: 10s, 20s, 30s, 40s (approved) and 100s (denied)
+        for (secs, status) in [
+            (10, ApprovalStatus::Approved),
+            (20, ApprovalStatus::Approved),
+            (30, ApprovalStatus::Approved),
+            (40, ApprovalStatus::Approved),
+            (100, ApprovalStatus::Denied),
+        ] {
+            let approval = manager
+                .create_approval(
+                    board.id.clone(),
+                    ApprovalSubject::ConfigChange {
+                        change_id: ConfigChangeId::new("test_change"),
+                    },
+                    "admin".to_string(),
+                )
+                .unwrap();
+
+            let mut approvals = manager.approvals.lock().unwrap();
+            let a = approvals.get_mut(&approval.id).unwrap();
+            a.status = status;
+            a.resolved_at = Some(a.created_at + chrono::Duration::seconds(secs));
+        }
+
+        // One still pending
+        manager
+            .create_approval(
+                board.id.clone(),
+                ApprovalSubject::ConfigChange {
+                    change_id: ConfigChangeId::new("pending_change"),
+                },
+                "admin".to_string(),
+            )
+            .unwrap();
+
+        let stats = manager.get_approval_stats(None);
+        assert_eq!(stats.resolved_count, 5);
+        assert_eq!(stats.pending_count, 1);
+        assert_eq!(stats.approved_count, 4);
+        assert_eq!(stats.denied_count, 1);
+        assert_eq!(stats.median_resolution_secs, Some(30.0));
+        assert_eq!(stats.p95_resolution_secs, Some(100.0));
+        assert!((stats.approval_rate - 0.8).abs() < f64::EPSILON);
+        assert!((stats.denial_rate - 0.2).abs() < f64::EPSILON);
+
+        // Scoped to an unknown board yields nothing
+        let stats = manager.get_approval_stats(Some(&ApprovalBoardId::new("other_board")));
+        assert_eq!(stats.resolved_count, 0);
+        assert_eq!(stats.median_resolution_secs, None);
+    }
+
+    #[test]
+    fn test_median_even_count() {
+        assert_eq!(median(&[10.0, 20.0, 30.0, 40.0]), Some(25.0));
+        assert_eq!(median(&[]), None);
+    }
+
+    fn create_leads_and_engineers_board(quorum_rule: QuorumRule) -> ApprovalBoard {
+        let engineers = [
+            PersonId::new("eng1"),
+            PersonId::new("eng2"),
+            PersonId::new("eng3"),
+        ];
+        let leads = [PersonId::new("lead1"), PersonId::new("lead2")];
+
+        let mut board = create_test_board();
+        board.approvers = engineers.iter().chain(leads.iter()).cloned().collect();
+        board.quorum_rule = quorum_rule;
+        board
+    }
+
+    fn engineers_majority() -> QuorumRule {
+        QuorumRule::Scoped {
+            approvers: vec![
+                PersonId::new("eng1"),
+                PersonId::new("eng2"),
+                PersonId::new("eng3"),
+            ],
+            rule: Box::new(QuorumRule::Majority),
+        }
+    }
+
+    fn leads_unanimous() -> QuorumRule {
+        QuorumRule::Scoped {
+            approvers: vec![PersonId::new("lead1"), PersonId::new("lead2")],
+            rule: Box::new(QuorumRule::Unanimous),
+        }
+    }
+
+    #[test]
+    fn test_any_quorum_passes_on_one_branch() {
+        let manager = ApprovalManager::new();
+        let board = create_leads_and_engineers_board(QuorumRule::Any(vec![
+            engineers_majority(),
+            leads_unanimous(),
+        ]));
+        manager.register_board(board.clone()).unwrap();
+
+        let approval = manager
+            .create_approval(
+                board.id.clone(),
+                ApprovalSubject::ConfigChange {
+                    change_id: ConfigChangeId::new("test_change"),
+                },
+                "admin".to_string(),
+            )
+            .unwrap();
+
+        let status = manager
+            .cast_vote(&approval.id, PersonId::new("lead1"), VoteDecision::Approve, None)
+            .unwrap();
+        assert_eq!(status, ApprovalStatus::Pending);
+
+        // Both leads approving satisfies the second branch without any engineer votes
+        let status = manager
+            .cast_vote(&approval.id, PersonId::new("lead2"), VoteDecision::Approve, None)
+            .unwrap();
+        assert_eq!(status, ApprovalStatus::Approved);
+    }
+
+    #[test]
+    fn test_all_quorum_pending_until_both_branches_pass() {
+        let manager = ApprovalManager::new();
+        let board = create_leads_and_engineers_board(QuorumRule::All(vec![
+            engineers_majority(),
+            leads_unanimous(),
+        ]));
+        manager.register_board(board.clone()).unwrap();
+
+        let approval = manager
+            .create_approval(
+                board.id.clone(),
+                ApprovalSubject::ConfigChange {
+                    change_id: ConfigChangeId::new("test_change"),
+                },
+                "admin".to_string(),
+            )
+            .unwrap();
+
+        // Engineers reach majority, but the leads branch is still open
+        for voter in ["eng1", "eng2"] {
+            let status = manager
+                .cast_vote(&approval.id, PersonId::new(voter), VoteDecision::Approve, None)
+                .unwrap();
+            assert_eq!(status, ApprovalStatus::Pending);
+        }
+
+        let status = manager
+            .cast_vote(&approval.id, PersonId::new("lead1"), VoteDecision::Approve, None)
+            .unwrap();
+        assert_eq!(status, ApprovalStatus::Pending);
+
+        let status = manager
+            .cast_vote(&approval.id, PersonId::new("lead2"), VoteDecision::Approve, None)
+            .unwrap();
+        assert_eq!(status, ApprovalStatus::Approved);
+    }
+
+    #[test]
+    fn test_quorum_rule_depth_limit() {
+        let manager = ApprovalManager::new();
+
+        let mut rule = QuorumRule::Majority;
+        for _ in 0..=MAX_QUORUM_RULE_DEPTH {
+            rule = QuorumRule::All(vec![rule]);
+        }
+
+        let board = create_leads_and_engineers_board(rule);
+        assert!(manager.register_board(board).is_err());
+    }
+
+    #[test]
+    fn test_invalid_scopes_rejected() {
+        let manager = ApprovalManager::new();
+
+        let board = create_leads_and_engineers_board(QuorumRule::Any(vec![
+            QuorumRule::Scoped {
+                approvers: vec![],
+                rule: Box::new(QuorumRule::Majority),
+            },
+            QuorumRule::Scoped {
+                approvers: vec![PersonId::new("lead1"), PersonId::new("outsider")],
+                rule: Box::new(QuorumRule::Majority),
+            },
+        ]));
+        let err = manager.register_board(board).unwrap_err();
+        let errors = err.downcast_ref::<ValidationErrors>().unwrap();
+        let fields: Vec<_> = errors.errors().iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["quorum_rule[0].approvers", "quorum_rule[1].approvers[1]"]);
+
+        // Unanimity over nobody would approve without a vote
+        let board = create_leads_and_engineers_board(QuorumRule::Scoped {
+            approvers: vec![],
+            rule: Box::new(QuorumRule::Unanimous),
+        });
+        let err = manager.register_board(board).unwrap_err();
+        let errors = err.downcast_ref::<ValidationErrors>().unwrap();
+        let fields: Vec<_> = errors.errors().iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["quorum_rule.approvers", "quorum_rule.rule"]);
+
+        let mut board = create_test_board();
+        board.approvers.clear();
+        board.quorum_rule = QuorumRule::Unanimous;
+        let err = manager.register_board(board).unwrap_err();
+        let errors = err.downcast_ref::<ValidationErrors>().unwrap();
+        assert_eq!(errors.errors()[0].field, "quorum_rule");
+    }
+
+    #[test]
+    fn test_bulk_vote_partial_failure() {
+        let manager = ApprovalManager::new();
+        let board = create_test_board();
+        manager.register_board(board.clone()).unwrap();
+
+        let approvals: Vec<Approval> = (0..3)
+            .map(|i| {
+                manager
+                    .create_approval(
+                        board.id.clone(),
+                        ApprovalSubject::ConfigChange {
+                            change_id: ConfigChangeId::new(format!("change{}", i)),
+                        },
+                        "admin".to_string(),
+                    )
+                    .unwrap()
+            })
+            .collect();
+
+        // One vote each brings the first two approvals to the brink of quorum
+        for approval in &approvals[..2] {
+            manager
+                .cast_vote(&approval.id, PersonId::new("approver2"), VoteDecision::Approve, None)
+                .unwrap();
+        }
+
+        // The third is resolved before the bulk vote arrives
+        for voter in ["approver2", "approver3"] {
+            manager
+                .cast_vote(&approvals[2].id, PersonId::new(voter), VoteDecision::Approve, None)
+                .unwrap();
+        }
+
+        let results = manager.cast_votes(
+            &PersonId::new("approver1"),
+            approvals
+                .iter()
+                .map(|a| BulkVote {
+                    approval_id: a.id.clone(),
+                    decision: VoteDecision::Approve,
+                    comment: Some("Back from leave".to_string()),
+                })
+                .collect(),
+        );
+
+        assert_eq!(results.len(), 3);
+        for result in &results[..2] {
+            assert_eq!(result.status, Some(ApprovalStatus::Approved));
+            assert!(result.error.is_none());
+        }
+        assert!(results[2].status.is_none());
+        assert!(results[2]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("already resolved"));
+
+        // The invalid vote was not recorded
+        assert_eq!(manager.get_approval(&approvals[2].id).unwrap().votes.len(), 2);
+    }
+
+    #[test]
+    fn test_force_resolve_requires_justification_and_decision() {
+        let manager = ApprovalManager::new();
+        let board = create_test_board();
+        manager.register_board(board.clone()).unwrap();
+
+        let approval = manager
+            .create_approval(
+                board.id.clone(),
+                ApprovalSubject::ConfigChange {
+                    change_id: ConfigChangeId::new("test_change"),
+                },
+                "admin".to_string(),
+            )
+            .unwrap();
+
+        let force = |status, justification: &str| {
+            manager.force_resolve(
+                &approval.id,
+                status,
+                "admin".to_string(),
+                justification.to_string(),
+            )
+        };
+
+        assert!(force(ApprovalStatus::Approved, "  ").is_err());
+        assert!(force(ApprovalStatus::Pending, "Board is deadlocked").is_err());
+
+        let resolved = force(ApprovalStatus::Denied, "Board is deadlocked").unwrap();
+        assert_eq!(resolved.status, ApprovalStatus::Denied);
+        assert!(resolved.resolved_at.is_some());
+        assert_eq!(resolved.forced_resolution.unwrap().resolved_by, "admin");
+
+        // Votes can't reopen it
+        assert!(manager
+            .cast_vote(&approval.id, PersonId::new("approver1"), VoteDecision::Approve, None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_min_approvals_needed_per_rule() {
+        let mut board = create_test_board();
+        let cases = [
+            (QuorumRule::Unanimous, 3),
+            (QuorumRule::Majority, 2),
+            (QuorumRule::MinCount { min: 1 }, 1),
+            (QuorumRule::Percentage { percent: 66 }, 2),
+            (QuorumRule::Percentage { percent: 67 }, 3),
+            (QuorumRule::Percentage { percent: 0 }, 0),
+            (QuorumRule::Any(vec![QuorumRule::Unanimous, QuorumRule::MinCount { min: 1 }]), 1),
+            (QuorumRule::All(vec![QuorumRule::Majority, QuorumRule::MinCount { min: 1 }]), 2),
+        ];
+        for (rule, expected) in cases {
+            board.quorum_rule = rule.clone();
+            assert_eq!(board.min_approvals_needed(), expected, "{:?}", rule);
+        }
+
+        board.quorum_rule = QuorumRule::Majority;
+        assert_eq!(board.quorum_description(), "Needs 2 of 3 approvers (majority)");
+    }
+
+    #[test]
+    fn test_min_approvals_needed_for_scoped_rules() {
+        // Engineers and leads are separate groups, so their approvals add up
+        let board = create_leads_and_engineers_board(QuorumRule::All(vec![
+            engineers_majority(),
+            leads_unanimous(),
+        ]));
+        assert_eq!(board.min_approvals_needed(), 4);
+        assert_eq!(
+            board.quorum_description(),
+            "Needs all of: 2 of 3 approvers (majority) among eng1, eng2, eng3; \
+             2 of 2 approvers (unanimous) among lead1, lead2"
+        );
+
+        let board = create_leads_and_engineers_board(QuorumRule::Any(vec![
+            engineers_majority(),
+            leads_unanimous(),
+        ]));
+        assert_eq!(board.min_approvals_needed(), 2);
+
+        // Overlapping groups can share approvals
+        let board = create_leads_and_engineers_board(QuorumRule::All(vec![
+            engineers_majority(),
+            QuorumRule::MinCount { min: 3 },
+        ]));
+        assert_eq!(board.min_approvals_needed(), 3);
+    }
+
+    #[test]
+    fn test_reminders_go_to_approvers_who_havent_voted() {
+        use crate::clock::ManualClock;
+
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let manager = ApprovalManager::new()
+            .with_clock(clock.clone())
+            .with_reminder_policy(ReminderPolicy {
+                after_secs: 3600,
+                interval_secs: 3600,
+                max_reminders: 3,
+            });
+        let mut reminders = manager.subscribe_reminders();
+        let audit_log = crate::audit::AuditLog::new();
+        manager.add_reminder_handler(Arc::new(audit_log.clone()));
+        let board = create_test_board();
+        manager.register_board(board.clone()).unwrap();
+        let approval = manager
+            .create_approval(
+                board.id.clone(),
+                ApprovalSubject::ConfigChange {
+                    change_id: ConfigChangeId::new("change"),
+                },
+                "admin".to_string(),
+            )
+            .unwrap();
+        manager
+            .cast_vote(&approval.id, PersonId::new("approver1"), VoteDecision::Approve, None)
+            .unwrap();
+
+        clock.advance(Duration::minutes(59));
+        assert!(manager.send_due_reminders().is_empty());
+
+        clock.advance(Duration::minutes(1));
+        let sent = manager.send_due_reminders();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].reminder, 1);
+        assert_eq!(
+            sent[0].approvers,
+            vec![PersonId::new("approver2"), PersonId::new("approver3")]
+        );
+        assert_eq!(reminders.try_recv().unwrap(), sent[0]);
+        let entries = audit_log.list_all();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            &entries[0].action,
+            crate::audit::AuditAction::ApprovalReminderSent { reminder: 1, .. }
+        ));
+
+        // Not again until the next interval
+        assert!(manager.send_due_reminders().is_empty());
+        clock.advance(Duration::hours(1));
+        assert_eq!(manager.send_due_reminders()[0].reminder, 2);
+
+        // Resolving the approval stops the reminders
+        manager
+            .cast_vote(&approval.id, PersonId::new("approver2"), VoteDecision::Approve, None)
+            .unwrap();
+        clock.advance(Duration::hours(1));
+        assert!(manager.send_due_reminders().is_empty());
+    }
+
+    #[test]
+    fn test_reminder_policy_bounds() {
+        let policy = ReminderPolicy {
+            after_secs: u64::MAX,
+            interval_secs: u64::MAX,
+            max_reminders: 3,
+        };
+        let err = policy.validate().unwrap_err();
+        assert_eq!(err.downcast_ref::<ValidationErrors>().unwrap().errors().len(), 2);
+        let unspaced = ReminderPolicy {
+            interval_secs: 0,
+            ..Default::default()
+        };
+        let err = unspaced.validate().unwrap_err();
+        let errors = err.downcast_ref::<ValidationErrors>().unwrap();
+        assert_eq!(errors.errors()[0].field, "interval_secs");
+        ReminderPolicy::default().validate().unwrap();
+
+        // Even unvalidated, a due time past what a timestamp can hold is never reached
+        let manager = ApprovalManager::new().with_reminder_policy(policy);
+        let board = create_test_board();
+        manager.register_board(board.clone()).unwrap();
+        manager
+            .create_approval(
+                board.id.clone(),
+                ApprovalSubject::ConfigChange {
+                    change_id: ConfigChangeId::new("change"),
+                },
+                "admin".to_string(),
+            )
+            .unwrap();
+        assert!(manager.send_due_reminders().is_empty());
+    }
+
+    #[test]
+    fn test_custom_resolution_handler_fires_with_subject_id() {
+        struct FlagFlipper(Mutex<Vec<(String, ApprovalStatus)>>);
+
+        impl ApprovalResolutionHandler for FlagFlipper {
+            fn on_resolved(&self, subject_id: &str, approval: &Approval) -> Result<()> {
+                self.0.lock().unwrap().push((subject_id.to_string(), approval.status));
+                Ok(())
+            }
+        }
+
+        let manager = ApprovalManager::new();
+        let board = create_test_board();
+        manager.register_board(board.clone()).unwrap();
+        let flipper = Arc::new(FlagFlipper(Mutex::new(Vec::new())));
+        manager.register_resolution_handler("feature_flag", flipper.clone());
+
+        let custom = |subject_type: &str| {
+            manager
+                .create_approval(
+                    board.id.clone(),
+                    ApprovalSubject::Custom {
+                        subject_type: subject_type.to_string(),
+                        subject_id: "dark_mode".to_string(),
+                    },
+                    "admin".to_string(),
+                )
+                .unwrap()
+        };
+        let flag = custom("feature_flag");
+        let other = custom("webhook");
+
+        for approval in [&flag, &other] {
+            for voter in ["approver1", "approver2"] {
+                manager
+                    .cast_vote(&approval.id, PersonId::new(voter), VoteDecision::Approve, None)
+                    .unwrap();
+            }
+        }
+
+        // Only the handler for the approval's own subject type runs, once
+        assert_eq!(
+            *flipper.0.lock().unwrap(),
+            vec![("dark_mode".to_string(), ApprovalStatus::Approved)]
+        );
+    }
 }
@@ -1,8 +1,12 @@
+use crate::approval::{ApprovalReminder, ApprovalReminderHandler};
+use crate::redaction::Redactor;
+use crate::validation::ValidationErrors;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
 
 /// Unique identifier for an audit log entry
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -69,9 +73,12 @@ pub enum AuditAction {
     WorkflowCreated { workflow_id: String, created_by: String },
     WorkflowExecuted { run_id: String, workflow_id: String },
     WorkflowFailed { run_id: String, error: String },
+    ToolCalled { tool_name: String, succeeded: bool },
 
     // Configuration changes
     ConfigChanged { change_id: String, change_type: String, approved_by: Option<String> },
+    ApprovalForceResolved { approval_id: String, status: String, resolved_by: String },
+    ApprovalReminderSent { approval_id: String, approvers: Vec<String>, reminder: u32 },
     TenantCreated { tenant_id: String, created_by: String },
     TenantSuspended { tenant_id: String, suspended_by: String },
 
@@ -93,6 +100,18 @@ pub enum AuditAction {
     DataRetentionPolicyApplied { policy_id: String, records_deleted: usize },
 }
 
+impl AuditAction {
+    /// Free-text fields that may quote secrets, such as failure reasons and descriptions
+    pub fn free_text_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            AuditAction::LoginFailed { reason, .. } => vec![reason],
+            AuditAction::WorkflowFailed { error, .. } => vec![error],
+            AuditAction::SecurityIncident { description, .. } => vec![description],
+            _ => Vec::new(),
+        }
+    }
+}
+
 /// Tamper-proof audit log entry with chain verification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditEntry {
@@ -202,22 +221,164 @@ impl AuditEntry {
     }
 }
 
+/// Default number of entries between checkpoints
+pub const DEFAULT_CHECKPOINT_INTERVAL: usize = 1000;
+
+/// Signed record of the chain's hash at an entry, letting verification start there
+/// instead of at genesis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditCheckpoint {
+    /// Position of the checkpointed entry in the log
+    pub index: usize,
+    pub entry_hash: String,
+    pub created_at: DateTime<Utc>,
+    /// Hex HMAC-SHA256 over the index and entry hash
+    pub signature: String,
+}
+
+impl AuditCheckpoint {
+    fn new(index: usize, entry_hash: String, key: &[u8]) -> Self {
+        let signature = Self::sign(index, &entry_hash, key);
+        Self {
+            index,
+            entry_hash,
+            created_at: Utc::now(),
+            signature,
+        }
+    }
+
+    fn sign(index: usize, entry_hash: &str, key: &[u8]) -> String {
+        hmac_sha256(key, format!("{}:{}", index, entry_hash).as_bytes())
+    }
+
+    /// Verify the checkpoint was signed with `key` and not altered since
+    pub fn verify_signature(&self, key: &[u8]) -> bool {
+        let expected = Self::sign(self.index, &self.entry_hash, key);
+        constant_time_eq(expected.as_bytes(), self.signature.as_bytes())
+    }
+}
+
+/// Compare signatures in time independent of where they first differ
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Hex-encoded HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> String {
+    hex::encode(hmac_sha256_bytes(key, message))
+}
+
+/// HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256_bytes(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(message);
+
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+/// Most entries accepted in one ingested batch
+pub const MAX_INGEST_BATCH: usize = 500;
+
+/// Most metadata entries on an ingested audit event
+const MAX_INGEST_METADATA: usize = 32;
+
+/// Longest metadata value on an ingested audit event, in bytes
+const MAX_INGEST_METADATA_VALUE: usize = 4096;
+
+/// Metadata key marking entries reported by external components
+pub const SOURCE_METADATA_KEY: &str = "source";
+
+/// Value of `SOURCE_METADATA_KEY` on ingested entries
+pub const INGEST_SOURCE: &str = "ingest";
+
+impl AuditAction {
+    /// Whether an external component may report this action. Authentication,
+    /// authorization, secret, approval, tenant, system and compliance events are only
+    /// recorded by the server itself, so they can't be forged through ingestion.
+    pub fn is_ingestible(&self) -> bool {
+        matches!(
+            self,
+            AuditAction::DataAccessed { .. }
+                | AuditAction::DataDeleted { .. }
+                | AuditAction::ToolCalled { .. }
+                | AuditAction::SecurityScanStarted { .. }
+                | AuditAction::SecurityScanCompleted { .. }
+                | AuditAction::SecurityIncident { .. }
+                | AuditAction::VulnerabilityDetected { .. }
+        )
+    }
+}
+
+/// An audit event reported by an external component. The log assigns its ID,
+/// timestamp and chain hashes when it is ingested, so none can be supplied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditSubmission {
+    pub category: AuditCategory,
+    pub severity: AuditSeverity,
+    pub action: AuditAction,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
 /// Tamper-proof audit log manager
 #[derive(Clone)]
 pub struct AuditLog {
     entries: Arc<Mutex<Vec<AuditEntry>>>,
     last_hash: Arc<Mutex<Option<String>>>,
+    redactor: Option<Redactor>,
+    checkpoints: Arc<Mutex<Vec<AuditCheckpoint>>>,
+    checkpoint_interval: usize,
+    checkpoint_key: Option<Arc<Vec<u8>>>,
+    // Notifies subscribers of each entry as it is recorded
+    recorded: broadcast::Sender<AuditEntry>,
 }
 
 impl AuditLog {
     /// Create a new audit log
     pub fn new() -> Self {
+        let (recorded, _) = broadcast::channel(256);
         Self {
             entries: Arc::new(Mutex::new(Vec::new())),
             last_hash: Arc::new(Mutex::new(None)),
+            redactor: None,
+            checkpoints: Arc::new(Mutex::new(Vec::new())),
+            checkpoint_interval: DEFAULT_CHECKPOINT_INTERVAL,
+            checkpoint_key: None,
+            recorded,
         }
     }
 
+    /// Redact secrets from actions and metadata before entries are recorded
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Record a checkpoint signed with `key` every `interval` entries
+    pub fn with_checkpoints(mut self, interval: usize, key: &[u8]) -> Self {
+        self.checkpoint_interval = interval.max(1);
+        self.checkpoint_key = Some(Arc::new(key.to_vec()));
+        self
+    }
+
+    /// Receive each entry as it is recorded
+    pub fn subscribe(&self) -> broadcast::Receiver<AuditEntry> {
+        self.recorded.subscribe()
+    }
+
     /// Record an audit event
     pub fn record(
         &self,
@@ -229,6 +390,13 @@ impl AuditLog {
         ip_address: Option<String>,
         metadata: HashMap<String, String>,
     ) -> AuditEntry {
+        // Redact before hashing so the chain covers exactly what is stored
+        let (mut action, mut metadata) = (action, metadata);
+        if let Some(redactor) = &self.redactor {
+            redactor.redact_fields(action.free_text_mut());
+            redactor.redact_fields(metadata.values_mut());
+        }
+
         let mut entries = self.entries.lock().unwrap();
         let mut last_hash = self.last_hash.lock().unwrap();
 
@@ -248,6 +416,16 @@ impl AuditLog {
 
         entries.push(entry.clone());
 
+        if let Some(key) = &self.checkpoint_key {
+            if entries.len().is_multiple_of(self.checkpoint_interval) {
+                self.checkpoints.lock().unwrap().push(AuditCheckpoint::new(
+                    entries.len() - 1,
+                    entry.entry_hash.clone(),
+                    key,
+                ));
+            }
+        }
+
         tracing::info!(
             audit_id = %entry.id.0,
             category = ?entry.category,
@@ -255,6 +433,7 @@ impl AuditLog {
             "Audit event recorded"
         );
 
+        let _ = self.recorded.send(entry.clone());
         entry
     }
 
@@ -271,6 +450,72 @@ impl AuditLog {
         self.record(category, severity, action, user_id, tenant_id, ip_address, HashMap::new())
     }
 
+    /// Validate a batch of externally reported events and record them all, in order,
+    /// attributed to `user_id` and marked with `source=ingest`. Nothing is recorded if
+    /// any submission is invalid or reports an action only the server may record.
+    pub fn ingest(
+        &self,
+        submissions: Vec<AuditSubmission>,
+        user_id: String,
+        tenant_id: Option<String>,
+    ) -> anyhow::Result<Vec<AuditEntry>> {
+        let mut errors = ValidationErrors::new();
+        if submissions.is_empty() {
+            errors.push("entries", "At least one entry is required");
+        }
+        if submissions.len() > MAX_INGEST_BATCH {
+            errors.push(
+                "entries",
+                format!("At most {} entries can be ingested at once", MAX_INGEST_BATCH),
+            );
+        }
+        for (i, submission) in submissions.iter().enumerate() {
+            if !submission.action.is_ingestible() {
+                errors.push(
+                    format!("entries[{}].action", i),
+                    "Only data access, tool call and security events can be ingested",
+                );
+            }
+            if submission.metadata.len() > MAX_INGEST_METADATA {
+                errors.push(
+                    format!("entries[{}].metadata", i),
+                    format!("At most {} metadata entries are allowed", MAX_INGEST_METADATA),
+                );
+            }
+            for (key, value) in &submission.metadata {
+                if value.len() > MAX_INGEST_METADATA_VALUE {
+                    errors.push(
+                        format!("entries[{}].metadata.{}", i, key),
+                        format!("Values are limited to {} bytes", MAX_INGEST_METADATA_VALUE),
+                    );
+                }
+            }
+        }
+        errors.into_result()?;
+
+        Ok(submissions
+            .into_iter()
+            .map(|submission| {
+                let mut metadata = submission.metadata;
+                metadata.insert(SOURCE_METADATA_KEY.to_string(), INGEST_SOURCE.to_string());
+                self.record(
+                    submission.category,
+                    submission.severity,
+                    submission.action,
+                    Some(user_id.clone()),
+                    tenant_id.clone(),
+                    None,
+                    metadata,
+                )
+            })
+            .collect())
+    }
+
+    /// Hash of the most recently recorded entry, i.e. the head of the chain
+    pub fn last_hash(&self) -> Option<String> {
+        self.last_hash.lock().unwrap().clone()
+    }
+
     /// Get all audit entries
     pub fn list_entries(&self) -> Vec<AuditEntry> {
         self.entries.lock().unwrap().clone()
@@ -364,31 +609,66 @@ impl AuditLog {
         let entries = self.entries.lock().unwrap();
         let mut errors = Vec::new();
 
-        for (i, entry) in entries.iter().enumerate() {
-            // Verify entry hash
-            if !entry.verify_hash() {
-                errors.push(format!("Entry {} hash mismatch", entry.id.0));
-            }
-
-            // Verify chain link
-            if i > 0 {
-                let prev_entry = &entries[i - 1];
-                if entry.previous_hash.as_ref() != Some(&prev_entry.entry_hash) {
-                    errors.push(format!(
-                        "Chain broken at entry {} (expected previous hash: {}, got: {:?})",
-                        entry.id.0,
-                        prev_entry.entry_hash,
-                        entry.previous_hash
-                    ));
-                }
-            } else if entry.previous_hash.is_some() {
+        if let Some(first) = entries.first() {
+            if first.previous_hash.is_some() {
                 errors.push(format!(
                     "First entry {} should not have previous hash",
-                    entry.id.0
+                    first.id.0
                 ));
             }
         }
 
+        verify_entries(&entries, 0, &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Verify the chain from the checkpoint taken at entry `checkpoint_index` onwards,
+    /// trusting everything before it. The checkpoint's signature and the checkpointed
+    /// entry are checked first, so tampering anywhere after the checkpoint is detected.
+    pub fn verify_chain_from(&self, checkpoint_index: usize) -> Result<(), Vec<String>> {
+        let key = self
+            .checkpoint_key
+            .as_ref()
+            .ok_or_else(|| vec!["Checkpoints are not enabled for this audit log".to_string()])?;
+
+        let checkpoint = self
+            .checkpoints
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|c| c.index == checkpoint_index)
+            .cloned()
+            .ok_or_else(|| vec![format!("No checkpoint at entry {}", checkpoint_index)])?;
+
+        if !checkpoint.verify_signature(key) {
+            return Err(vec![format!(
+                "Checkpoint at entry {} has an invalid signature",
+                checkpoint_index
+            )]);
+        }
+
+        let entries = self.entries.lock().unwrap();
+        let mut errors = Vec::new();
+
+        match entries.get(checkpoint.index) {
+            Some(entry) if entry.entry_hash == checkpoint.entry_hash => {}
+            Some(entry) => errors.push(format!(
+                "Entry {} does not match checkpoint (expected hash: {}, got: {})",
+                entry.id.0, checkpoint.entry_hash, entry.entry_hash
+            )),
+            None => errors.push(format!(
+                "Checkpointed entry {} is missing",
+                checkpoint.index
+            )),
+        }
+
+        verify_entries(&entries, checkpoint.index, &mut errors);
+
         if errors.is_empty() {
             Ok(())
         } else {
@@ -396,6 +676,16 @@ impl AuditLog {
         }
     }
 
+    /// List all checkpoints, oldest first
+    pub fn list_checkpoints(&self) -> Vec<AuditCheckpoint> {
+        self.checkpoints.lock().unwrap().clone()
+    }
+
+    /// Most recent checkpoint, the natural starting point for verification
+    pub fn latest_checkpoint(&self) -> Option<AuditCheckpoint> {
+        self.checkpoints.lock().unwrap().last().cloned()
+    }
+
     /// Get audit statistics
     pub fn get_statistics(&self) -> AuditStatistics {
         let entries = self.entries.lock().unwrap();
@@ -418,6 +708,49 @@ impl AuditLog {
     }
 }
 
+/// Check the hash of each entry from `start` on, and its link to the previous entry
+fn verify_entries(entries: &[AuditEntry], start: usize, errors: &mut Vec<String>) {
+    for (i, entry) in entries.iter().enumerate().skip(start) {
+        // Verify entry hash
+        if !entry.verify_hash() {
+            errors.push(format!("Entry {} hash mismatch", entry.id.0));
+        }
+
+        // Verify chain link
+        if i > start {
+            let prev_entry = &entries[i - 1];
+            if entry.previous_hash.as_ref() != Some(&prev_entry.entry_hash) {
+                errors.push(format!(
+                    "Chain broken at entry {} (expected previous hash: {}, got: {:?})",
+                    entry.id.0,
+                    prev_entry.entry_hash,
+                    entry.previous_hash
+                ));
+            }
+        }
+    }
+}
+
+/// Records each approval reminder, so who was nudged and when is on the audit trail
+impl ApprovalReminderHandler for AuditLog {
+    fn on_reminder(&self, reminder: &ApprovalReminder) -> anyhow::Result<()> {
+        self.record(
+            AuditCategory::Authorization,
+            AuditSeverity::Info,
+            AuditAction::ApprovalReminderSent {
+                approval_id: reminder.approval_id.0.clone(),
+                approvers: reminder.approvers.iter().map(|p| p.0.clone()).collect(),
+                reminder: reminder.reminder,
+            },
+            None,
+            None,
+            None,
+            HashMap::new(),
+        );
+        Ok(())
+    }
+}
+
 impl Default for AuditLog {
     fn default() -> Self {
         Self::new()
@@ -650,5 +983,58 @@ mod tests {
         // Verification should fail
         assert!(!log.verify_chain());
     }
+
+    #[test]
+    fn test_hmac_sha256_known_vector() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            hmac_sha256(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_verify_chain_from_checkpoint() {
+        let log = AuditLog::new().with_checkpoints(2, b"checkpoint-key");
+
+        for i in 0..5 {
+            log.record(
+                AuditCategory::Authentication,
+                AuditSeverity::Info,
+                AuditAction::UserLogin {
+                    user_id: format!("user{}", i),
+                    ip_address: "127.0.0.1".to_string(),
+                },
+                Some(format!("user{}", i)),
+                None,
+                None,
+                HashMap::new(),
+            );
+        }
+
+        let indices: Vec<usize> = log.list_checkpoints().iter().map(|c| c.index).collect();
+        assert_eq!(indices, vec![1, 3]);
+        assert_eq!(log.latest_checkpoint().unwrap().index, 3);
+        assert!(log.verify_chain_from(3).is_ok());
+        assert!(log.verify_chain_from(2).is_err());
+
+        // Tampering before the checkpoint is outside the verified range
+        log.entries.lock().unwrap()[0].user_id = Some("tampered".to_string());
+        assert!(log.verify_chain_from(3).is_ok());
+        assert!(!log.verify_chain());
+
+        // Tampering after the checkpoint is detected without re-hashing from genesis
+        log.entries.lock().unwrap()[4].user_id = Some("tampered".to_string());
+        let errors = log.verify_chain_from(3).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("hash mismatch"));
+
+        // So is forging the checkpoint itself
+        log.checkpoints.lock().unwrap()[1].entry_hash = "forged".to_string();
+        assert!(log.verify_chain_from(3).unwrap_err()[0].contains("invalid signature"));
+
+        // Logs without checkpoints can't verify incrementally
+        assert!(AuditLog::new().verify_chain_from(0).is_err());
+    }
 }
 
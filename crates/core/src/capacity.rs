@@ -1,19 +1,124 @@
+use crate::audit::{AuditAction, AuditCategory, AuditLog, AuditSeverity};
+use crate::clock::{Clock, SystemClock};
+use crate::storage::IndexBackend;
 use crate::types::{
-    CapacitySource, CapacitySourceId, CapacityUsage, LlmError, LlmRequest, LlmResponse,
-    PriorityRequest, RateLimitState, RoleId, RunId, StepId,
+    BlobHash, CapacityAlert, CapacityAlertKind, CapacitySource, CapacitySourceId, CapacityUsage,
+    DeadLetter, LlmChunk, LlmError, LlmProvider, LlmRequest, LlmResponse, PriorityRequest,
+    RateLimitState, RoleId, RoleSpec, RunId, SelectionTrace, SourceConsideration, SourceSkipReason,
+    StepId,
 };
 use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
-use std::collections::{BinaryHeap, HashMap};
+use futures_util::Stream;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
 use tokio::time::sleep;
 
+/// Attempts a queued request gets before it is moved to the dead-letter store
+pub const DEFAULT_MAX_QUEUE_ATTEMPTS: u32 = 3;
+
+/// Consecutive authentication failures after which a source is disabled, as its key
+/// has most likely been revoked
+pub const AUTH_FAILURES_BEFORE_DISABLE: u32 = 2;
+
+/// Seconds after which tokens served by a source count half as much towards its fair
+/// share, so a source that served a lot long ago isn't passed over forever
+pub const FAIR_SHARE_HALF_LIFE_SECS: f64 = 3600.0;
+
 /// Capacity broker for multi-source LLM capacity pooling
 pub struct CapacityBroker {
     sources: Arc<Mutex<HashMap<CapacitySourceId, CapacitySource>>>,
     rate_limits: Arc<Mutex<HashMap<CapacitySourceId, RateLimitState>>>,
     usage_history: Arc<Mutex<Vec<CapacityUsage>>>,
     priority_queue: Arc<Mutex<BinaryHeap<PriorityRequestWrapper>>>,
+    dead_letters: Arc<Mutex<Vec<DeadLetter>>>,
+    max_queue_attempts: u32,
+    // Notifies subscribers of backoffs and dead-lettered requests
+    alerts: broadcast::Sender<CapacityAlert>,
+    last_selection: Arc<Mutex<Option<SelectionTrace>>>,
+    clock: Arc<dyn Clock>,
+    // Breaks selection ties and jitters backoffs; seed it for reproducible scheduling
+    rng: Arc<Mutex<SplitMix64>>,
+    // Fair-share accounting: tokens reserved per source, decaying over time. Among
+    // sources of equal priority, the one furthest behind (fewest tokens served) is
+    // tried first.
+    served_tokens: Arc<Mutex<HashMap<CapacitySourceId, ServedTokens>>>,
+    response_cache: Option<Arc<ResponseCache>>,
+    // Authentication failures per source since its last successful call
+    auth_failures: Arc<Mutex<HashMap<CapacitySourceId, u32>>>,
+    audit_log: Option<AuditLog>,
+    // Where sources are persisted, so one disabled here stays disabled across restarts
+    index_store: Option<Arc<dyn IndexBackend>>,
+}
+
+/// Tokens a source has served, halving every `FAIR_SHARE_HALF_LIFE_SECS`
+#[derive(Debug, Clone, Copy)]
+struct ServedTokens {
+    tokens: f64,
+    updated_at: DateTime<Utc>,
+}
+
+impl ServedTokens {
+    /// The decayed count as of `now`
+    fn at(&self, now: DateTime<Utc>) -> f64 {
+        let elapsed = (now - self.updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+        self.tokens * 0.5f64.powf(elapsed / FAIR_SHARE_HALF_LIFE_SECS)
+    }
+
+    fn add(
+        served: &mut HashMap<CapacitySourceId, Self>,
+        id: &CapacitySourceId,
+        tokens: u64,
+        now: DateTime<Utc>,
+    ) {
+        let decayed = served.get(id).map_or(0.0, |s| s.at(now));
+        served.insert(
+            id.clone(),
+            Self {
+                tokens: decayed + tokens as f64,
+                updated_at: now,
+            },
+        );
+    }
+}
+
+/// Responses to deterministic requests, keyed by a hash of what determines the output
+struct ResponseCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<BlobHash, (DateTime<Utc>, LlmResponse)>>,
+}
+
+impl ResponseCache {
+    /// Key for a request served by `model`, or `None` if its output isn't deterministic
+    /// (temperature > 0)
+    fn key(request: &LlmRequest, model: &str) -> Option<BlobHash> {
+        if request.temperature.is_some_and(|t| t != 0.0) {
+            return None;
+        }
+        let key = serde_json::json!([
+            model,
+            request.prompt,
+            request.max_tokens,
+            request.temperature.unwrap_or(0.0),
+        ]);
+        Some(BlobHash::from_bytes(key.to_string().as_bytes()))
+    }
+}
+
+/// Small seeded PRNG (SplitMix64). Scheduling needs reproducibility, not statistical
+/// quality, so this avoids pulling in a dependency.
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
 }
 
 /// Wrapper for PriorityRequest to implement Ord for BinaryHeap
@@ -47,26 +152,131 @@ impl Ord for PriorityRequestWrapper {
 impl CapacityBroker {
     /// Create a new capacity broker
     pub fn new() -> Self {
+        let (alerts, _) = broadcast::channel(256);
         Self {
             sources: Arc::new(Mutex::new(HashMap::new())),
             rate_limits: Arc::new(Mutex::new(HashMap::new())),
             usage_history: Arc::new(Mutex::new(Vec::new())),
             priority_queue: Arc::new(Mutex::new(BinaryHeap::new())),
+            dead_letters: Arc::new(Mutex::new(Vec::new())),
+            max_queue_attempts: DEFAULT_MAX_QUEUE_ATTEMPTS,
+            alerts,
+            last_selection: Arc::new(Mutex::new(None)),
+            clock: Arc::new(SystemClock),
+            rng: Arc::new(Mutex::new(SplitMix64(uuid::Uuid::new_v4().as_u64_pair().0))),
+            served_tokens: Arc::new(Mutex::new(HashMap::new())),
+            response_cache: None,
+            auth_failures: Arc::new(Mutex::new(HashMap::new())),
+            audit_log: None,
+            index_store: None,
+        }
+    }
+
+    /// Cache responses to deterministic requests (temperature 0 or unset) for `ttl`.
+    /// Hits are returned with `cache_hit` set and zero cost, without calling a provider.
+    pub fn with_response_cache(mut self, ttl: Duration) -> Self {
+        self.response_cache = Some(Arc::new(ResponseCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }));
+        self
+    }
+
+    /// Cached response for `request` from `model`, dropping it if it has expired
+    fn cached_response(&self, request: &LlmRequest, model: &str) -> Option<LlmResponse> {
+        let cache = self.response_cache.as_ref()?;
+        let key = ResponseCache::key(request, model)?;
+        let mut entries = cache.entries.lock().unwrap();
+        let (cached_at, response) = entries.get(&key)?;
+        if self.clock.now() >= *cached_at + cache.ttl {
+            entries.remove(&key);
+            return None;
         }
+
+        Some(LlmResponse {
+            cost: 0.0,
+            cache_hit: true,
+            ..response.clone()
+        })
+    }
+
+    fn cache_response(&self, request: &LlmRequest, response: &LlmResponse) {
+        let key = ResponseCache::key(request, &response.model);
+        if let (Some(cache), Some(key)) = (&self.response_cache, key) {
+            let now = self.clock.now();
+            let mut entries = cache.entries.lock().unwrap();
+            entries.retain(|_, (cached_at, _)| now < *cached_at + cache.ttl);
+            entries.insert(key, (now, response.clone()));
+        }
+    }
+
+    /// Seed the RNG behind selection tie-breaks and backoff jitter, so the same request
+    /// sequence always produces the same selections
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Arc::new(Mutex::new(SplitMix64(seed)));
+        self
+    }
+
+    /// Use `clock` for rate-limit windows, backoffs and timestamps. Set it before
+    /// registering sources.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record a security incident in `audit_log` when a source is disabled for failing
+    /// authentication
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Persist sources to `index_store` when the broker disables them for failing
+    /// authentication
+    pub fn with_index_store(mut self, index_store: Arc<dyn IndexBackend>) -> Self {
+        self.index_store = Some(index_store);
+        self
     }
 
-    /// Register a capacity source
+    /// Receive an alert whenever a source backs off, is disabled, or a request is
+    /// dead-lettered
+    pub fn subscribe_alerts(&self) -> broadcast::Receiver<CapacityAlert> {
+        self.alerts.subscribe()
+    }
+
+    fn raise_alert(
+        &self,
+        kind: CapacityAlertKind,
+        source_id: Option<CapacitySourceId>,
+        message: String,
+    ) {
+        let _ = self.alerts.send(CapacityAlert {
+            kind,
+            source_id,
+            message,
+            raised_at: self.clock.now(),
+        });
+    }
+
+    /// Set how many attempts a queued request gets before it is dead-lettered
+    pub fn with_max_queue_attempts(mut self, max_queue_attempts: u32) -> Self {
+        self.max_queue_attempts = max_queue_attempts.max(1);
+        self
+    }
+
+    /// Register a capacity source, replacing any with the same ID. Authentication
+    /// failures counted against the one replaced are forgotten, as its key may have been.
     pub fn register_source(&self, source: CapacitySource) -> Result<()> {
         let source_id = source.id.clone();
 
         // Initialize rate limit state
         let state = RateLimitState {
             source_id: source_id.clone(),
-            window_start: Utc::now(),
+            window_start: self.clock.now(),
             requests_in_window: 0,
             tokens_in_window: 0,
             daily_tokens: 0,
-            daily_reset_at: Utc::now() + Duration::days(1),
+            daily_reset_at: self.clock.now() + Duration::days(1),
             next_available: None,
             backoff_until: None,
         };
@@ -76,6 +286,7 @@ impl CapacityBroker {
             .unwrap()
             .insert(source_id.clone(), source);
 
+        self.auth_failures.lock().unwrap().remove(&source_id);
         self.rate_limits
             .lock()
             .unwrap()
@@ -96,6 +307,11 @@ impl CapacityBroker {
         self.sources.lock().unwrap().values().cloned().collect()
     }
 
+    /// Whether any source is registered, enabled or not
+    pub fn has_sources(&self) -> bool {
+        !self.sources.lock().unwrap().is_empty()
+    }
+
     /// Get a specific source
     pub fn get_source(&self, source_id: &CapacitySourceId) -> Option<CapacitySource> {
         self.sources.lock().unwrap().get(source_id).cloned()
@@ -105,67 +321,155 @@ impl CapacityBroker {
     pub fn update_source_enabled(&self, source_id: &CapacitySourceId, enabled: bool) -> Result<()> {
         if let Some(source) = self.sources.lock().unwrap().get_mut(source_id) {
             source.enabled = enabled;
-            source.updated_at = Utc::now();
+            source.updated_at = self.clock.now();
+            if enabled {
+                self.auth_failures.lock().unwrap().remove(source_id);
+            }
             Ok(())
         } else {
             Err(anyhow::anyhow!("Source not found"))
         }
     }
 
-    /// Select the best available source for a request
+    /// Select the best available source for a request. The decision is kept for
+    /// `last_selection_trace`.
     pub fn select_source(&self, required_tokens: u32) -> Option<CapacitySourceId> {
+        self.select_source_for_model(required_tokens, None)
+    }
+
+    /// Select the best available source serving `model`, or any model if `None`
+    pub fn select_source_for_model(
+        &self,
+        required_tokens: u32,
+        model: Option<&str>,
+    ) -> Option<CapacitySourceId> {
+        let trace = self.trace_selection(required_tokens, model);
+        let selected = trace.selected.clone();
+        *self.last_selection.lock().unwrap() = Some(trace);
+        selected
+    }
+
+    /// The most recent `select_source` decision, with the reason each source was skipped
+    pub fn last_selection_trace(&self) -> Option<SelectionTrace> {
+        self.last_selection.lock().unwrap().clone()
+    }
+
+    fn trace_selection(&self, required_tokens: u32, model: Option<&str>) -> SelectionTrace {
         let sources = self.sources.lock().unwrap();
         let mut rate_limits = self.rate_limits.lock().unwrap();
-        let now = Utc::now();
+        let now = self.clock.now();
 
-        // Filter and sort sources by priority
-        let mut candidates: Vec<_> = sources
+        // Filter by model, ordering by ID first so tie-break draws don't depend on map
+        // iteration order
+        let mut filtered: Vec<_> = sources
             .values()
-            .filter(|s| s.enabled)
+            .filter(|s| model.is_none_or(|model| s.model == model))
             .collect();
+        filtered.sort_by(|a, b| a.id.0.cmp(&b.id.0));
+
+        // Highest priority first; among equals, the source furthest behind its fair share,
+        // then a seeded tie-break
+        let mut candidates: Vec<_> = {
+            let served = self.served_tokens.lock().unwrap();
+            let mut rng = self.rng.lock().unwrap();
+            filtered
+                .into_iter()
+                .map(|s| (s, served.get(&s.id).map_or(0.0, |t| t.at(now)), rng.next_u64()))
+                .collect()
+        };
+        candidates.sort_by(|a, b| {
+            b.0.priority
+                .cmp(&a.0.priority)
+                .then(a.1.total_cmp(&b.1))
+                .then(a.2.cmp(&b.2))
+        });
 
-        candidates.sort_by(|a, b| b.priority.cmp(&a.priority));
+        let mut trace = SelectionTrace {
+            required_tokens,
+            model: model.map(String::from),
+            considered: Vec::new(),
+            selected: None,
+            selected_at: now,
+        };
 
         // Find first available source
-        for source in candidates {
-            let state = rate_limits.get_mut(&source.id)?;
-
-            // Check if in backoff period
-            if let Some(backoff_until) = state.backoff_until {
-                if now < backoff_until {
-                    continue;
-                }
-                // Clear backoff
-                state.backoff_until = None;
+        for (source, _, _) in candidates {
+            let Some(state) = rate_limits.get_mut(&source.id) else {
+                continue;
+            };
+
+            let skipped = Self::skip_reason(source, state, required_tokens, now);
+            trace.considered.push(SourceConsideration {
+                source_id: source.id.clone(),
+                priority: source.priority,
+                skipped: skipped.clone(),
+            });
+
+            if skipped.is_none() {
+                trace.selected = Some(source.id.clone());
+                break;
             }
+        }
 
-            // Reset window if needed
-            if now >= state.window_start + Duration::minutes(1) {
-                state.window_start = now;
-                state.requests_in_window = 0;
-                state.tokens_in_window = 0;
-            }
+        trace
+    }
 
-            // Reset daily counter if needed
-            if now >= state.daily_reset_at {
-                state.daily_tokens = 0;
-                state.daily_reset_at = now + Duration::days(1);
-            }
+    /// Why `source` can't take a request right now, resetting expired windows on the way
+    fn skip_reason(
+        source: &CapacitySource,
+        state: &mut RateLimitState,
+        required_tokens: u32,
+        now: DateTime<Utc>,
+    ) -> Option<SourceSkipReason> {
+        if !source.enabled {
+            return Some(SourceSkipReason::Disabled);
+        }
 
-            // Check rate limits
-            if state.requests_in_window >= source.rate_limits.requests_per_minute {
-                continue;
-            }
-            if state.tokens_in_window + required_tokens > source.rate_limits.tokens_per_minute {
-                continue;
-            }
-            if let Some(daily_limit) = source.rate_limits.tokens_per_day {
-                if state.daily_tokens + required_tokens > daily_limit {
-                    continue;
-                }
+        // Check if in backoff period
+        if let Some(backoff_until) = state.backoff_until {
+            if now < backoff_until {
+                return Some(SourceSkipReason::Backoff { until: backoff_until });
             }
+            // Clear backoff
+            state.backoff_until = None;
+        }
 
-            return Some(source.id.clone());
+        // Reset window if needed
+        if now >= state.window_start + Duration::minutes(1) {
+            state.window_start = now;
+            state.requests_in_window = 0;
+            state.tokens_in_window = 0;
+        }
+
+        // Reset daily counter if needed
+        if now >= state.daily_reset_at {
+            state.daily_tokens = 0;
+            state.daily_reset_at = now + Duration::days(1);
+        }
+
+        // Check rate limits
+        let limits = &source.rate_limits;
+        if state.requests_in_window >= limits.requests_per_minute {
+            return Some(SourceSkipReason::RequestRateLimit {
+                requests_in_window: state.requests_in_window,
+                limit: limits.requests_per_minute,
+            });
+        }
+        if state.tokens_in_window + required_tokens > limits.tokens_per_minute {
+            return Some(SourceSkipReason::TokenRateLimit {
+                tokens_in_window: state.tokens_in_window,
+                required_tokens,
+                limit: limits.tokens_per_minute,
+            });
+        }
+        if let Some(daily_limit) = limits.tokens_per_day {
+            if state.daily_tokens + required_tokens > daily_limit {
+                return Some(SourceSkipReason::DailyCap {
+                    daily_tokens: state.daily_tokens,
+                    required_tokens,
+                    limit: daily_limit,
+                });
+            }
         }
 
         None
@@ -179,13 +483,108 @@ impl CapacityBroker {
         step_id: StepId,
         role: RoleId,
         priority: u8,
+    ) -> Result<LlmResponse> {
+        self.execute_with_fallback(request, &[], run_id, step_id, role, priority)
+            .await
+    }
+
+    /// Execute an LLM request for `role`. If no source for the requested model has
+    /// capacity, the role's `model_fallback` models are tried in order before the
+    /// request is queued; the response's `fallback_from` records any downgrade.
+    ///
+    /// The request is first bounded by the role's `claude_settings`, if it has any.
+    pub async fn execute_request_for_role(
+        &self,
+        request: LlmRequest,
+        role: &RoleSpec,
+        run_id: RunId,
+        step_id: StepId,
+        priority: u8,
+    ) -> Result<LlmResponse> {
+        let request = match &role.claude_settings {
+            Some(settings) => {
+                let requested_tokens = request.max_tokens;
+                let request = settings
+                    .bound_request(request)
+                    .map_err(|e| anyhow::anyhow!("Request for role {} refused: {}", role.id.0, e))?;
+                if request.max_tokens < requested_tokens {
+                    tracing::debug!(
+                        "Clamped max_tokens for role {} from {} to {}",
+                        role.id.0,
+                        requested_tokens,
+                        request.max_tokens
+                    );
+                }
+                request
+            }
+            None => request,
+        };
+
+        self.execute_with_fallback(
+            request,
+            &role.model_fallback,
+            run_id,
+            step_id,
+            role.id.clone(),
+            priority,
+        )
+        .await
+    }
+
+    async fn execute_with_fallback(
+        &self,
+        request: LlmRequest,
+        model_fallback: &[String],
+        run_id: RunId,
+        step_id: StepId,
+        role: RoleId,
+        priority: u8,
     ) -> Result<LlmResponse> {
         let required_tokens = request.max_tokens;
 
+        // Without a requested model any source will do, so there is nothing to fall back from
+        let requested = request.model.as_deref();
+        let fallbacks = model_fallback
+            .iter()
+            .map(|model| Some(model.as_str()))
+            .filter(|_| requested.is_some());
+        let selected = std::iter::once(requested)
+            .chain(fallbacks)
+            .find_map(|model| Some((self.select_source_for_model(required_tokens, model)?, model)));
+
+        // Responses are cached under the model that served them, so without a requested
+        // model the selected source's model decides which entry applies
+        let served_model = match &selected {
+            Some((source_id, _)) => {
+                self.sources.lock().unwrap().get(source_id).map(|s| s.model.clone())
+            }
+            None => request.model.clone(),
+        };
+        let cached = served_model.and_then(|model| self.cached_response(&request, &model));
+        if let Some(response) = cached {
+            tracing::debug!("Serving LLM request for step {} from cache", step_id);
+            return Ok(response);
+        }
+
         // Try to select a source
-        if let Some(source_id) = self.select_source(required_tokens) {
-            match self.execute_with_source(&source_id, &request, run_id, step_id.clone()).await {
-                Ok(response) => return Ok(response),
+        if let Some((source_id, model)) = selected {
+            let fallback_from = requested.filter(|requested| Some(*requested) != model);
+            if let Some(fallback_from) = fallback_from {
+                tracing::info!(
+                    "No capacity for model {}, falling back to {}",
+                    fallback_from,
+                    model.unwrap_or_default()
+                );
+            }
+
+            match self
+                .execute_with_source(&source_id, &request, run_id, step_id.clone(), fallback_from)
+                .await
+            {
+                Ok(response) => {
+                    self.cache_response(&request, &response);
+                    return Ok(response);
+                }
                 Err(LlmError::RateLimited { retry_after }) => {
                     // Apply backoff
                     self.apply_backoff(&source_id, retry_after);
@@ -207,7 +606,8 @@ impl CapacityBroker {
             role,
             prompt: request.prompt,
             max_tokens: request.max_tokens,
-            created_at: Utc::now(),
+            model: request.model,
+            created_at: self.clock.now(),
             attempts: 0,
         });
 
@@ -221,30 +621,121 @@ impl CapacityBroker {
         request: &LlmRequest,
         run_id: RunId,
         step_id: StepId,
+        fallback_from: Option<&str>,
     ) -> Result<LlmResponse, LlmError> {
+        let source = self.reserve_capacity(source_id, request)?;
+
+        // Simulate LLM API call (in production, this would call the actual API)
+        let mut response = self.call_source(&source, request).await?;
+        response.fallback_from = fallback_from.map(String::from);
+
+        // Track usage
+        self.record_usage(&response, run_id, step_id);
+
+        Ok(response)
+    }
+
+    /// Stream an LLM request, yielding text as the provider generates it.
+    ///
+    /// Providers without streaming support yield their full response as a single chunk.
+    /// Usage is recorded once, when the final chunk (carrying the complete response) is
+    /// produced. Unlike `execute_request`, the request is not queued if no source is free.
+    pub fn stream_request(
+        &self,
+        request: LlmRequest,
+        run_id: RunId,
+        step_id: StepId,
+    ) -> impl Stream<Item = Result<LlmChunk, LlmError>> + '_ {
+        futures_util::stream::unfold(StreamState::Start(request), move |state| {
+            let step_id = step_id.clone();
+            async move {
+                let (mut pending, response) = match state {
+                    StreamState::Start(request) => match self.start_stream(&request).await {
+                        Ok(started) => started,
+                        Err(err) => return Some((Err(err), StreamState::Done)),
+                    },
+                    StreamState::Streaming { pending, response } => {
+                        // Simulate token-by-token generation latency
+                        sleep(tokio::time::Duration::from_millis(5)).await;
+                        (pending, response)
+                    }
+                    StreamState::Done => return None,
+                };
+
+                let delta = pending.pop_front().unwrap_or_default();
+                if pending.is_empty() {
+                    self.record_usage(&response, run_id, step_id);
+                    let chunk = LlmChunk {
+                        delta,
+                        response: Some(response),
+                    };
+                    Some((Ok(chunk), StreamState::Done))
+                } else {
+                    let chunk = LlmChunk {
+                        delta,
+                        response: None,
+                    };
+                    Some((Ok(chunk), StreamState::Streaming { pending, response }))
+                }
+            }
+        })
+    }
+
+    /// Pick a source for a streamed request and split its response into chunks
+    async fn start_stream(
+        &self,
+        request: &LlmRequest,
+    ) -> Result<(VecDeque<String>, LlmResponse), LlmError> {
+        let source_id = self
+            .select_source_for_model(request.max_tokens, request.model.as_deref())
+            .ok_or(LlmError::ServiceUnavailable)?;
+        let source = self.reserve_capacity(&source_id, request)?;
+
+        // Simulate the provider's streaming API by replaying the mock response word by word
+        let response = self.call_source(&source, request).await?;
+        let pending = if supports_streaming(&source.provider) {
+            response.text.split_inclusive(' ').map(String::from).collect()
+        } else {
+            VecDeque::from([response.text.clone()])
+        };
+
+        Ok((pending, response))
+    }
+
+    /// Look up a source and count a request against its rate limits
+    fn reserve_capacity(
+        &self,
+        source_id: &CapacitySourceId,
+        request: &LlmRequest,
+    ) -> Result<CapacitySource, LlmError> {
         let source = self.sources.lock().unwrap()
             .get(source_id)
             .cloned()
             .ok_or(LlmError::Other { message: "Source not found".to_string() })?;
 
         // Update rate limit state
-        {
-            let mut rate_limits = self.rate_limits.lock().unwrap();
-            if let Some(state) = rate_limits.get_mut(source_id) {
-                state.requests_in_window += 1;
-                state.tokens_in_window += request.max_tokens;
-                state.daily_tokens += request.max_tokens;
-            }
+        let mut rate_limits = self.rate_limits.lock().unwrap();
+        if let Some(state) = rate_limits.get_mut(source_id) {
+            state.requests_in_window += 1;
+            state.tokens_in_window += request.max_tokens;
+            state.daily_tokens += request.max_tokens;
         }
+        ServedTokens::add(
+            &mut self.served_tokens.lock().unwrap(),
+            source_id,
+            request.max_tokens as u64,
+            self.clock.now(),
+        );
+
+        Ok(source)
+    }
 
-        // Simulate LLM API call (in production, this would call the actual API)
-        let response = self.call_llm_api(&source, request).await?;
-
-        // Track usage
+    /// Track usage for a completed response
+    fn record_usage(&self, response: &LlmResponse, run_id: RunId, step_id: StepId) {
         let usage = CapacityUsage {
             id: uuid::Uuid::new_v4().to_string(),
-            source_id: source_id.clone(),
-            timestamp: Utc::now(),
+            source_id: response.source_id.clone(),
+            timestamp: self.clock.now(),
             input_tokens: response.input_tokens,
             output_tokens: response.output_tokens,
             total_tokens: response.input_tokens + response.output_tokens,
@@ -252,11 +743,105 @@ impl CapacityBroker {
             request_count: 1,
             run_id: Some(run_id),
             step_id: Some(step_id),
+            fallback_from: response.fallback_from.clone(),
         };
 
         self.usage_history.lock().unwrap().push(usage);
+    }
 
-        Ok(response)
+    /// Record usage reported by a client that called the provider itself, counting it
+    /// against the source's rate limits. Fails if the source isn't registered.
+    pub fn ingest_usage(&self, usage: CapacityUsage) -> Result<()> {
+        if !self.sources.lock().unwrap().contains_key(&usage.source_id) {
+            return Err(anyhow::anyhow!("Unknown capacity source: {}", usage.source_id.0));
+        }
+
+        if let Some(state) = self.rate_limits.lock().unwrap().get_mut(&usage.source_id) {
+            state.requests_in_window += usage.request_count;
+            state.tokens_in_window += usage.total_tokens;
+            state.daily_tokens += usage.total_tokens;
+        }
+        ServedTokens::add(
+            &mut self.served_tokens.lock().unwrap(),
+            &usage.source_id,
+            usage.total_tokens as u64,
+            self.clock.now(),
+        );
+
+        self.usage_history.lock().unwrap().push(usage);
+        Ok(())
+    }
+
+    /// Call a source's provider, keeping track of authentication failures
+    async fn call_source(
+        &self,
+        source: &CapacitySource,
+        request: &LlmRequest,
+    ) -> Result<LlmResponse, LlmError> {
+        let result = self.call_llm_api(source, request).await;
+        self.track_authentication(&source.id, &result);
+        result
+    }
+
+    /// Count a call's outcome towards disabling its source. Only consecutive
+    /// `AuthenticationFailed` errors count; anything else, including being rate
+    /// limited, resets the count.
+    fn track_authentication(
+        &self,
+        source_id: &CapacitySourceId,
+        result: &Result<LlmResponse, LlmError>,
+    ) {
+        let failures = {
+            let mut auth_failures = self.auth_failures.lock().unwrap();
+            if !matches!(result, Err(LlmError::AuthenticationFailed)) {
+                auth_failures.remove(source_id);
+                return;
+            }
+            let failures = auth_failures.entry(source_id.clone()).or_default();
+            *failures += 1;
+            *failures
+        };
+
+        let was_enabled = self
+            .get_source(source_id)
+            .is_some_and(|source| source.enabled);
+        if failures < AUTH_FAILURES_BEFORE_DISABLE || !was_enabled {
+            return;
+        }
+        if self.update_source_enabled(source_id, false).is_err() {
+            return;
+        }
+        if let (Some(index_store), Some(source)) = (&self.index_store, self.get_source(source_id)) {
+            if let Err(e) = index_store.store_capacity_source(&source) {
+                tracing::error!("Failed to persist disabled source {}: {}", source_id.0, e);
+            }
+        }
+
+        let message = format!(
+            "Source {} disabled after {} consecutive authentication failures; re-enable it \
+             once its API key has been replaced",
+            source_id.0, failures
+        );
+        tracing::warn!("{}", message);
+        self.raise_alert(
+            CapacityAlertKind::SourceDisabled,
+            Some(source_id.clone()),
+            message.clone(),
+        );
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record(
+                AuditCategory::SecurityEvent,
+                AuditSeverity::Critical,
+                AuditAction::SecurityIncident {
+                    incident_id: uuid::Uuid::new_v4().to_string(),
+                    description: message,
+                },
+                None,
+                None,
+                None,
+                HashMap::from([("source_id".to_string(), source_id.0.clone())]),
+            );
+        }
     }
 
     /// Call the LLM API (stub for MVP, would integrate with actual APIs)
@@ -282,6 +867,8 @@ impl CapacityBroker {
             cost,
             model: source.model.clone(),
             source_id: source.id.clone(),
+            fallback_from: None,
+            cache_hit: false,
         })
     }
 
@@ -289,13 +876,21 @@ impl CapacityBroker {
     fn apply_backoff(&self, source_id: &CapacitySourceId, retry_after: Option<u64>) {
         let mut rate_limits = self.rate_limits.lock().unwrap();
         if let Some(state) = rate_limits.get_mut(source_id) {
-            let backoff_secs = retry_after.unwrap_or(60); // Default 60s
-            state.backoff_until = Some(Utc::now() + Duration::seconds(backoff_secs as i64));
+            // Default 60s, plus up to 10% jitter so sources don't all retry at once
+            let base_secs = retry_after.unwrap_or(60);
+            let jitter_secs = self.rng.lock().unwrap().next_u64() % (base_secs / 10 + 1);
+            let backoff_secs = base_secs + jitter_secs;
+            state.backoff_until = Some(self.clock.now() + Duration::seconds(backoff_secs as i64));
             tracing::info!(
                 "Applied backoff to source {}: retry in {}s",
                 source_id.0,
                 backoff_secs
             );
+            self.raise_alert(
+                CapacityAlertKind::RateLimited,
+                Some(source_id.clone()),
+                format!("Source {} rate limited, retrying in {}s", source_id.0, backoff_secs),
+            );
         }
     }
 
@@ -312,11 +907,124 @@ impl CapacityBroker {
         self.priority_queue.lock().unwrap().pop().map(|w| w.0)
     }
 
+    /// Drop the queued requests of a step whose caller gave up waiting on them,
+    /// returning how many were removed
+    pub fn remove_queued(&self, run_id: RunId, step_id: &StepId) -> usize {
+        let mut queue = self.priority_queue.lock().unwrap();
+        let before = queue.len();
+        queue.retain(|w| !(w.0.run_id == run_id && &w.0.step_id == step_id));
+        before - queue.len()
+    }
+
     /// Get queue length
     pub fn queue_length(&self) -> usize {
         self.priority_queue.lock().unwrap().len()
     }
 
+    /// Retry the highest-priority queued request. Returns `None` if the queue is empty.
+    ///
+    /// A failed request goes back on the queue until it has used `max_queue_attempts`,
+    /// then moves to the dead-letter store with its last error.
+    pub async fn process_next_queued(&self) -> Option<Result<LlmResponse>> {
+        let mut queued = self.dequeue_request()?;
+        let request = LlmRequest {
+            prompt: queued.prompt.clone(),
+            max_tokens: queued.max_tokens,
+            temperature: None,
+            model: queued.model.clone(),
+        };
+
+        let error = match self.select_source_for_model(queued.max_tokens, queued.model.as_deref()) {
+            Some(source_id) => {
+                match self
+                    .execute_with_source(
+                        &source_id,
+                        &request,
+                        queued.run_id,
+                        queued.step_id.clone(),
+                        None,
+                    )
+                    .await
+                {
+                    Ok(response) => return Some(Ok(response)),
+                    Err(err) => {
+                        if let LlmError::RateLimited { retry_after } = err {
+                            self.apply_backoff(&source_id, retry_after);
+                        }
+                        format!("{:?}", err)
+                    }
+                }
+            }
+            None => "No capacity available".to_string(),
+        };
+
+        queued.attempts += 1;
+        if queued.attempts >= self.max_queue_attempts {
+            tracing::warn!(
+                "Queued request {} failed after {} attempts, moving to dead-letter store: {}",
+                queued.id,
+                queued.attempts,
+                error
+            );
+            self.raise_alert(
+                CapacityAlertKind::DeadLettered,
+                None,
+                format!(
+                    "Request {} dead-lettered after {} attempts: {}",
+                    queued.id, queued.attempts, error
+                ),
+            );
+            self.dead_letters.lock().unwrap().push(DeadLetter {
+                request: queued,
+                last_error: error.clone(),
+                dead_lettered_at: self.clock.now(),
+            });
+        } else {
+            self.enqueue_request(queued);
+        }
+
+        Some(Err(anyhow::anyhow!(error)))
+    }
+
+    /// Retry each currently queued request once
+    pub async fn process_queue(&self) {
+        for _ in 0..self.queue_length() {
+            if self.process_next_queued().await.is_none() {
+                break;
+            }
+        }
+    }
+
+    /// Retry queued requests every `interval`, each request at most once per tick
+    pub async fn run_queue_worker(self: Arc<Self>, interval: std::time::Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            self.process_queue().await;
+        }
+    }
+
+    /// List requests that exhausted their attempts, oldest first
+    pub fn list_dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.lock().unwrap().clone()
+    }
+
+    /// Move a dead-lettered request back onto the queue with a fresh attempt count
+    pub fn requeue_dead_letter(&self, request_id: &str) -> Result<PriorityRequest> {
+        let mut dead_letters = self.dead_letters.lock().unwrap();
+        let index = dead_letters
+            .iter()
+            .position(|d| d.request.id == request_id)
+            .ok_or_else(|| anyhow::anyhow!("Dead-lettered request {} not found", request_id))?;
+
+        let mut request = dead_letters.remove(index).request;
+        request.attempts = 0;
+        self.enqueue_request(request.clone());
+        tracing::info!("Requeued dead-lettered request {}", request_id);
+
+        Ok(request)
+    }
+
     /// Get total usage for a source
     pub fn get_source_usage(&self, source_id: &CapacitySourceId, since: DateTime<Utc>) -> Vec<CapacityUsage> {
         self.usage_history
@@ -367,6 +1075,21 @@ impl CapacityBroker {
     }
 }
 
+/// Progress of a streamed request
+enum StreamState {
+    Start(LlmRequest),
+    Streaming {
+        pending: VecDeque<String>,
+        response: LlmResponse,
+    },
+    Done,
+}
+
+/// Whether a provider's API can stream partial responses
+fn supports_streaming(provider: &LlmProvider) -> bool {
+    !matches!(provider, LlmProvider::Custom { .. })
+}
+
 impl Default for CapacityBroker {
     fn default() -> Self {
         Self::new()
@@ -454,6 +1177,59 @@ mod tests {
         assert!(selected.is_some());
     }
 
+    #[test]
+    fn test_selection_trace_explains_rate_limited_sources() {
+        let broker = CapacityBroker::new();
+        assert!(broker.last_selection_trace().is_none());
+
+        broker.register_source(create_test_source("high", 100)).unwrap();
+        broker.register_source(create_test_source("low", 10)).unwrap();
+        let mut disabled = create_test_source("disabled", 50);
+        disabled.enabled = false;
+        broker.register_source(disabled).unwrap();
+
+        for state in broker.rate_limits.lock().unwrap().values_mut() {
+            state.requests_in_window = 60;
+        }
+
+        assert!(broker.select_source(1000).is_none());
+
+        let trace = broker.last_selection_trace().unwrap();
+        assert_eq!(trace.required_tokens, 1000);
+        assert!(trace.selected.is_none());
+
+        let reasons: Vec<_> = trace
+            .considered
+            .iter()
+            .map(|c| (c.source_id.0.as_str(), c.skipped.clone()))
+            .collect();
+        let rate_limited = Some(SourceSkipReason::RequestRateLimit {
+            requests_in_window: 60,
+            limit: 60,
+        });
+        assert_eq!(
+            reasons,
+            vec![
+                ("high", rate_limited.clone()),
+                ("disabled", Some(SourceSkipReason::Disabled)),
+                ("low", rate_limited),
+            ]
+        );
+
+        // Once a source frees up, the trace names it as the choice
+        {
+            let mut rate_limits = broker.rate_limits.lock().unwrap();
+            if let Some(state) = rate_limits.get_mut(&CapacitySourceId::new("low")) {
+                state.requests_in_window = 0;
+            }
+        }
+        assert_eq!(broker.select_source(1000).unwrap().0, "low");
+
+        let trace = broker.last_selection_trace().unwrap();
+        assert_eq!(trace.selected, Some(CapacitySourceId::new("low")));
+        assert_eq!(trace.considered.last().unwrap().skipped, None);
+    }
+
     #[test]
     fn test_priority_queue() {
         let broker = CapacityBroker::new();
@@ -466,6 +1242,7 @@ mod tests {
             role: RoleId::new("analyst"),
             prompt: "Low priority".to_string(),
             max_tokens: 1000,
+            model: None,
             created_at: Utc::now(),
             attempts: 0,
         };
@@ -478,6 +1255,7 @@ mod tests {
             role: RoleId::new("engineer"),
             prompt: "High priority".to_string(),
             max_tokens: 1000,
+            model: None,
             created_at: Utc::now(),
             attempts: 0,
         };
@@ -523,6 +1301,281 @@ mod tests {
         assert!(response.cost > 0.0);
     }
 
+    #[tokio::test]
+    async fn test_role_falls_back_to_cheaper_model() {
+        let broker = CapacityBroker::new();
+
+        broker.register_source(create_test_source("premium", 100)).unwrap();
+        let mut budget = create_test_source("budget", 10);
+        budget.model = "claude-haiku-4".to_string();
+        broker.register_source(budget).unwrap();
+
+        // The premium model's only source is out of requests for this window
+        {
+            let mut rate_limits = broker.rate_limits.lock().unwrap();
+            if let Some(state) = rate_limits.get_mut(&CapacitySourceId::new("premium")) {
+                state.requests_in_window = 60;
+            }
+        }
+
+        let request = LlmRequest {
+            prompt: "Review this change".to_string(),
+            max_tokens: 1000,
+            temperature: None,
+            model: Some("claude-opus-4".to_string()),
+        };
+        let role = RoleSpec {
+            id: RoleId::new("reviewer"),
+            name: "Reviewer".to_string(),
+            description: String::new(),
+            prompt_template: String::new(),
+            allowed_tools: vec![],
+            budgets: crate::types::RoleBudgets {
+                daily_tokens: None,
+                daily_cost_cents: None,
+                ..Default::default()
+            },
+            requires_approval_for: vec![],
+            model_fallback: vec!["claude-haiku-4".to_string()],
+            max_concurrent: None,
+            claude_settings: None,
+        };
+
+        let run_id = RunId::new();
+        let response = broker
+            .execute_request_for_role(request.clone(), &role, run_id, StepId::new("review"), 50)
+            .await
+            .unwrap();
+        assert_eq!(response.source_id.0, "budget");
+        assert_eq!(response.model, "claude-haiku-4");
+        assert_eq!(response.fallback_from.as_deref(), Some("claude-opus-4"));
+
+        let usage = broker.get_all_usage(Utc::now() - Duration::hours(1));
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].fallback_from.as_deref(), Some("claude-opus-4"));
+
+        // Without a fallback chain, the request waits for the premium model instead
+        let result = broker
+            .execute_request(request, run_id, StepId::new("review"), role.id.clone(), 50)
+            .await;
+        assert!(result.is_err());
+        assert_eq!(broker.queue_length(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_role_settings_bound_requests() {
+        let broker = CapacityBroker::new();
+        broker.register_source(create_test_source("src1", 100)).unwrap();
+
+        let mut role = RoleSpec {
+            id: RoleId::new("writer"),
+            name: "Writer".to_string(),
+            description: String::new(),
+            prompt_template: String::new(),
+            allowed_tools: vec![],
+            budgets: Default::default(),
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: Some(crate::types::ClaudeSettings {
+                max_tokens: Some(500),
+                allowed_models: vec!["claude-opus-4".to_string()],
+                ..Default::default()
+            }),
+        };
+        let request = |max_tokens, model: &str| LlmRequest {
+            prompt: "Draft the release notes".to_string(),
+            max_tokens,
+            temperature: None,
+            model: Some(model.to_string()),
+        };
+        let run_id = RunId::new();
+        let step_id = StepId::new("draft");
+
+        // The stub provider answers with half the requested max_tokens
+        let oversized = request(200_000, "claude-opus-4");
+        let response = broker
+            .execute_request_for_role(oversized, &role, run_id, step_id.clone(), 50)
+            .await
+            .unwrap();
+        assert_eq!(response.output_tokens, 250);
+
+        let result = broker
+            .execute_request_for_role(request(100, "gpt-4o"), &role, run_id, step_id.clone(), 50)
+            .await;
+        assert!(result.unwrap_err().to_string().contains("not allowed"));
+        assert_eq!(broker.queue_length(), 0);
+
+        // A request naming no model is held to the allowed ones rather than any source
+        let unnamed = LlmRequest {
+            model: None,
+            ..request(100, "")
+        };
+        let response = broker
+            .execute_request_for_role(unnamed, &role, run_id, step_id.clone(), 50)
+            .await
+            .unwrap();
+        assert_eq!(response.model, "claude-opus-4");
+
+        // Nor does a default model outside the allowlist slip through
+        if let Some(settings) = role.claude_settings.as_mut() {
+            settings.model = Some("gpt-4o".to_string());
+        }
+        let unnamed = LlmRequest {
+            model: None,
+            ..request(100, "")
+        };
+        let result = broker
+            .execute_request_for_role(unnamed, &role, run_id, step_id.clone(), 50)
+            .await;
+        assert!(result.unwrap_err().to_string().contains("not allowed"));
+        if let Some(settings) = role.claude_settings.as_mut() {
+            settings.model = None;
+        }
+
+        if let Some(settings) = role.claude_settings.as_mut() {
+            settings.max_tokens_enforcement = crate::types::LimitEnforcement::Reject;
+        }
+        let result = broker
+            .execute_request_for_role(request(200_000, "claude-opus-4"), &role, run_id, step_id, 50)
+            .await;
+        assert!(result.unwrap_err().to_string().contains("exceeds the limit"));
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_request_dead_lettered_and_requeued() {
+        let broker = CapacityBroker::new().with_max_queue_attempts(2);
+
+        // No sources registered, so the request is queued
+        let request = LlmRequest {
+            prompt: "Summarize the incident".to_string(),
+            max_tokens: 500,
+            temperature: None,
+            model: None,
+        };
+        let result = broker
+            .execute_request(
+                request,
+                RunId::new(),
+                StepId::new("step1"),
+                RoleId::new("analyst"),
+                10,
+            )
+            .await;
+        assert!(result.is_err());
+        assert_eq!(broker.queue_length(), 1);
+
+        // First retry fails and goes back on the queue
+        assert!(broker.process_next_queued().await.unwrap().is_err());
+        assert_eq!(broker.queue_length(), 1);
+        assert!(broker.list_dead_letters().is_empty());
+
+        // Second retry exhausts its attempts
+        assert!(broker.process_next_queued().await.unwrap().is_err());
+        assert_eq!(broker.queue_length(), 0);
+        let dead_letters = broker.list_dead_letters();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].request.attempts, 2);
+        assert!(dead_letters[0].last_error.contains("No capacity"));
+
+        // Once capacity exists, the requeued request succeeds
+        broker.register_source(create_test_source("src1", 100)).unwrap();
+        let requeued = broker
+            .requeue_dead_letter(&dead_letters[0].request.id)
+            .unwrap();
+        assert_eq!(requeued.attempts, 0);
+        assert_eq!(broker.queue_length(), 1);
+        assert!(broker.list_dead_letters().is_empty());
+
+        assert!(broker.process_next_queued().await.unwrap().is_ok());
+        assert!(broker.process_next_queued().await.is_none());
+        assert!(broker.requeue_dead_letter("missing").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_retried_with_its_model() {
+        let broker = CapacityBroker::new();
+        let request = LlmRequest {
+            prompt: "Summarize the incident".to_string(),
+            max_tokens: 500,
+            temperature: None,
+            model: Some("claude-sonnet-4".to_string()),
+        };
+        let result = broker
+            .execute_request(
+                request,
+                RunId::new(),
+                StepId::new("step1"),
+                RoleId::new("analyst"),
+                10,
+            )
+            .await;
+        assert!(result.is_err());
+
+        // A source serving another model doesn't take the retry
+        broker.register_source(create_test_source("opus", 100)).unwrap();
+        assert!(broker.process_next_queued().await.unwrap().is_err());
+        assert_eq!(broker.queue_length(), 1);
+
+        let mut sonnet = create_test_source("sonnet", 10);
+        sonnet.model = "claude-sonnet-4".to_string();
+        broker.register_source(sonnet).unwrap();
+        let response = broker.process_next_queued().await.unwrap().unwrap();
+        assert_eq!(response.source_id, CapacitySourceId::new("sonnet"));
+        assert_eq!(response.model, "claude-sonnet-4");
+    }
+
+    #[tokio::test]
+    async fn test_stream_request_matches_execute_request() {
+        use futures_util::StreamExt;
+
+        let broker = CapacityBroker::new();
+        broker.register_source(create_test_source("src1", 100)).unwrap();
+
+        let request = LlmRequest {
+            prompt: "Summarize the quarterly report".to_string(),
+            max_tokens: 1000,
+            temperature: None,
+            model: None,
+        };
+
+        let expected = broker
+            .execute_request(
+                request.clone(),
+                RunId::new(),
+                StepId::new("step1"),
+                RoleId::new("analyst"),
+                50,
+            )
+            .await
+            .unwrap();
+        let since = Utc::now();
+
+        let run_id = RunId::new();
+        let chunks: Vec<LlmChunk> = broker
+            .stream_request(request, run_id, StepId::new("step2"))
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert!(chunks.len() > 1);
+        let text: String = chunks.iter().map(|c| c.delta.as_str()).collect();
+        assert_eq!(text, expected.text);
+
+        // Only the final chunk carries the complete response
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| c.response.is_none()));
+        let response = chunks.last().unwrap().response.as_ref().unwrap();
+        assert_eq!(response.text, expected.text);
+
+        let usage: Vec<CapacityUsage> = broker
+            .get_all_usage(since)
+            .into_iter()
+            .filter(|u| u.run_id == Some(run_id))
+            .collect();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].output_tokens, response.output_tokens);
+    }
+
     #[test]
     fn test_usage_tracking() {
         let broker = CapacityBroker::new();
@@ -539,6 +1592,7 @@ mod tests {
             request_count: 1,
             run_id: Some(RunId::new()),
             step_id: Some(StepId::new("step1")),
+            fallback_from: None,
         };
 
         broker.usage_history.lock().unwrap().push(usage);
@@ -551,6 +1605,59 @@ mod tests {
         assert_eq!(total_cost, 0.05);
     }
 
+    #[test]
+    fn test_repeated_auth_failures_disable_source() {
+        use crate::storage::InMemoryIndexStore;
+
+        let audit_log = AuditLog::new();
+        let index_store = Arc::new(InMemoryIndexStore::new());
+        let broker = CapacityBroker::new()
+            .with_audit_log(audit_log.clone())
+            .with_index_store(index_store.clone());
+        let mut alerts = broker.subscribe_alerts();
+        broker.register_source(create_test_source("revoked", 100)).unwrap();
+        broker.register_source(create_test_source("busy", 50)).unwrap();
+        let revoked = CapacitySourceId::new("revoked");
+        let busy = CapacitySourceId::new("busy");
+
+        // Rate limiting only backs a source off, however often it happens
+        for _ in 0..3 {
+            let rate_limited = Err(LlmError::RateLimited { retry_after: Some(30) });
+            broker.track_authentication(&busy, &rate_limited);
+        }
+        assert!(broker.get_source(&busy).unwrap().enabled);
+
+        // Any other outcome in between means the failures weren't consecutive
+        let failed = Err(LlmError::AuthenticationFailed);
+        broker.track_authentication(&revoked, &failed);
+        broker.track_authentication(&revoked, &Err(LlmError::ServiceUnavailable));
+        broker.track_authentication(&revoked, &failed);
+        assert!(broker.get_source(&revoked).unwrap().enabled);
+
+        broker.track_authentication(&revoked, &failed);
+        assert!(!broker.get_source(&revoked).unwrap().enabled);
+        assert_eq!(broker.select_source(1000), Some(busy.clone()));
+        let stored = index_store.get_capacity_source(&revoked).unwrap().unwrap();
+        assert!(!stored.enabled);
+
+        let alert = alerts.try_recv().unwrap();
+        assert_eq!(alert.kind, CapacityAlertKind::SourceDisabled);
+        assert_eq!(alert.source_id, Some(revoked.clone()));
+        let incidents = audit_log.list_by_category(AuditCategory::SecurityEvent);
+        assert_eq!(incidents.len(), 1);
+        assert!(matches!(incidents[0].action, AuditAction::SecurityIncident { .. }));
+
+        // Disabled until an operator re-enables it, which starts the count afresh
+        broker.update_source_enabled(&revoked, true).unwrap();
+        broker.track_authentication(&revoked, &failed);
+        assert!(broker.get_source(&revoked).unwrap().enabled);
+
+        // as does registering it again, e.g. with a replaced key
+        broker.register_source(create_test_source("revoked", 100)).unwrap();
+        broker.track_authentication(&revoked, &failed);
+        assert!(broker.get_source(&revoked).unwrap().enabled);
+    }
+
     #[test]
     fn test_backoff() {
         let broker = CapacityBroker::new();
@@ -565,4 +1672,155 @@ mod tests {
         let backoff_until = state.backoff_until.unwrap();
         assert!(backoff_until > Utc::now());
     }
+
+    #[test]
+    fn test_seeded_selection_is_reproducible() {
+        use crate::clock::ManualClock;
+
+        // Replay a request sequence against a fresh broker, recording every selection
+        fn replay(seed: u64, token_sizes: &[u32]) -> Vec<SelectionTrace> {
+            let start = "2026-01-01T00:00:00Z".parse().unwrap();
+            let clock = Arc::new(ManualClock::new(start));
+            let broker = CapacityBroker::new().with_seed(seed).with_clock(clock.clone());
+            for (id, priority) in [("a", 50), ("b", 50), ("c", 50), ("d", 10)] {
+                let mut source = create_test_source(id, priority);
+                source.rate_limits.tokens_per_minute = 5_000;
+                broker.register_source(source).unwrap();
+            }
+
+            token_sizes
+                .iter()
+                .map(|&max_tokens| {
+                    if let Some(source_id) = broker.select_source(max_tokens) {
+                        let request = LlmRequest {
+                            prompt: String::new(),
+                            max_tokens,
+                            temperature: None,
+                            model: None,
+                        };
+                        broker.reserve_capacity(&source_id, &request).unwrap();
+                    }
+                    clock.advance(Duration::seconds(7));
+                    broker.last_selection_trace().unwrap()
+                })
+                .collect()
+        }
+
+        for seed in 0..32 {
+            let mut sizes_rng = SplitMix64(seed);
+            let token_sizes: Vec<u32> =
+                (0..64).map(|_| 500 + (sizes_rng.next_u64() % 4_000) as u32).collect();
+
+            let first = replay(seed, &token_sizes);
+            assert_eq!(first, replay(seed, &token_sizes), "seed {}", seed);
+
+            // Fair share spreads load across the equal-priority sources
+            for id in ["a", "b", "c"] {
+                let picked = first
+                    .iter()
+                    .any(|t| t.selected == Some(CapacitySourceId::new(id)));
+                assert!(picked, "seed {} never selected {}", seed, id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_served_tokens_decay_out_of_fair_share() {
+        use crate::clock::ManualClock;
+
+        let clock = Arc::new(ManualClock::new("2026-01-01T00:00:00Z".parse().unwrap()));
+        let broker = CapacityBroker::new().with_clock(clock.clone());
+        broker.register_source(create_test_source("a", 50)).unwrap();
+        broker.register_source(create_test_source("b", 50)).unwrap();
+        let reserve = |id: &str, max_tokens: u32| {
+            let request = LlmRequest {
+                prompt: String::new(),
+                max_tokens,
+                temperature: None,
+                model: None,
+            };
+            broker.reserve_capacity(&CapacitySourceId::new(id), &request).unwrap();
+        };
+
+        reserve("a", 90_000);
+        assert_eq!(broker.select_source(1_000), Some(CapacitySourceId::new("b")));
+
+        // Long after, what "a" served then weighs less than what "b" serves now
+        clock.advance(Duration::hours(12));
+        reserve("b", 1_000);
+        assert_eq!(broker.select_source(1_000), Some(CapacitySourceId::new("a")));
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_request_served_from_cache() {
+        let broker = CapacityBroker::new().with_response_cache(Duration::minutes(5));
+        broker.register_source(create_test_source("src1", 100)).unwrap();
+
+        let request = LlmRequest {
+            prompt: "Summarize the release notes".to_string(),
+            max_tokens: 1000,
+            temperature: Some(0.0),
+            model: None,
+        };
+        let execute = |request: LlmRequest| {
+            broker.execute_request(request, RunId::new(), StepId::new("s1"), RoleId::new("r"), 5)
+        };
+
+        let first = execute(request.clone()).await.unwrap();
+        assert!(!first.cache_hit && first.cost > 0.0);
+        let since = Utc::now() - Duration::hours(1);
+        let cost = broker.get_total_cost(since);
+
+        let second = execute(request.clone()).await.unwrap();
+        assert!(second.cache_hit);
+        assert_eq!(second.cost, 0.0);
+        assert_eq!(second.text, first.text);
+        assert_eq!(broker.get_total_cost(since), cost);
+
+        // One provider call between them; sampled requests always go to the provider
+        let source_id = CapacitySourceId::new("src1");
+        assert_eq!(broker.get_rate_limit_state(&source_id).unwrap().requests_in_window, 1);
+        let sampled = execute(LlmRequest {
+            temperature: Some(0.7),
+            ..request
+        })
+        .await
+        .unwrap();
+        assert!(!sampled.cache_hit);
+        assert_eq!(broker.get_rate_limit_state(&source_id).unwrap().requests_in_window, 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_keyed_by_model_that_served_the_request() {
+        let broker = CapacityBroker::new().with_response_cache(Duration::minutes(5));
+        broker.register_source(create_test_source("opus", 50)).unwrap();
+
+        let request = LlmRequest {
+            prompt: "Summarize the release notes".to_string(),
+            max_tokens: 1000,
+            temperature: None,
+            model: None,
+        };
+        let execute = || {
+            broker.execute_request(
+                request.clone(),
+                RunId::new(),
+                StepId::new("s1"),
+                RoleId::new("r"),
+                5,
+            )
+        };
+        assert!(!execute().await.unwrap().cache_hit);
+        assert!(execute().await.unwrap().cache_hit);
+
+        // A source serving another model now takes unpinned requests, so the cached
+        // answer of the first model no longer applies
+        let mut sonnet = create_test_source("sonnet", 100);
+        sonnet.model = "claude-sonnet-4".to_string();
+        broker.register_source(sonnet).unwrap();
+        let response = execute().await.unwrap();
+        assert!(!response.cache_hit);
+        assert_eq!(response.model, "claude-sonnet-4");
+        assert!(execute().await.unwrap().cache_hit);
+    }
 }
@@ -1,23 +1,133 @@
 // Claude Config Compiler - generates .claude/config.json from organization config
 
+use crate::clock::{Clock, SystemClock};
+use crate::storage::IndexBackend;
 use crate::types::{
-    ClaudeConfig, ClaudeSettings, McpServerConfig, Organization, PolicySpec, RoleId, RoleSpec,
-    ToolConfig,
+    ClaudeConfig, ClaudeSettings, McpServerConfig, OrgId, Organization, PolicySpec, RoleId,
+    RoleSpec, ToolConfig,
 };
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// Tools a compiled config can grant, with their tiers
+const KNOWN_TOOLS: &[(&str, u8)] = &[
+    ("context_get", 0),
+    ("context_search", 0),
+    ("context_events", 0),
+    ("repo_read", 0),
+    ("web_fetch", 0),
+    ("repo_write", 1),
+    ("database_execute", 2),
+    ("deploy_production", 2),
+];
 
 /// Compiler for generating Claude Code configuration from organization setup
 pub struct ClaudeCompiler {
     org: Organization,
     roles: Vec<RoleSpec>,
     policies: Vec<PolicySpec>,
+    context: HashMap<String, String>,
+    clock: Arc<dyn Clock>,
+}
+
+/// Value of the `{{date}}` prompt variable at `now`
+fn prompt_date(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m-%d").to_string()
 }
 
 impl ClaudeCompiler {
     pub fn new(org: Organization, roles: Vec<RoleSpec>, policies: Vec<PolicySpec>) -> Self {
-        Self { org, roles, policies }
+        Self {
+            org,
+            roles,
+            policies,
+            context: HashMap::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Load an organization (the first one when `org_id` is `None`) with every role and
+    /// policy from the index
+    pub fn from_store(index: &dyn IndexBackend, org_id: Option<&OrgId>) -> Result<Self> {
+        let org = match org_id {
+            Some(org_id) => index
+                .get_organization(org_id)?
+                .ok_or_else(|| anyhow::anyhow!("Organization {} not found", org_id.0))?,
+            None => index
+                .list_organizations()?
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("No organization configured"))?,
+        };
+
+        Ok(Self::new(org, index.list_roles()?, index.list_policies()?))
+    }
+
+    /// Compile both the config and README for a role
+    pub fn compile(&self, role_id: &RoleId) -> Result<CompiledClaudeConfig> {
+        let config = self.compile_for_role(role_id)?;
+        let role = self
+            .roles
+            .iter()
+            .find(|r| &r.id == role_id)
+            .ok_or_else(|| anyhow::anyhow!("Role {} not found", role_id.0))?;
+
+        Ok(CompiledClaudeConfig {
+            config,
+            readme: self.generate_readme(role_id)?,
+            warnings: self.role_warnings(role),
+        })
+    }
+
+    /// Compile every role in one pass over the loaded organization, roles and policies.
+    /// A role that fails to compile doesn't stop the others.
+    pub fn compile_all(&self) -> Vec<(RoleId, Result<CompiledClaudeConfig>)> {
+        self.roles
+            .iter()
+            .map(|role| (role.id.clone(), self.compile(&role.id)))
+            .collect()
+    }
+
+    /// Parts of a role's setup its compiled config can't honour
+    fn role_warnings(&self, role: &RoleSpec) -> Vec<String> {
+        let is_known = |tool: &str| KNOWN_TOOLS.iter().any(|(name, _)| *name == tool);
+        let mut warnings = Vec::new();
+
+        for tool in &role.allowed_tools {
+            if !is_known(tool) {
+                warnings.push(format!("Allowed tool '{}' is not a known tool", tool));
+            }
+        }
+        for requirement in &role.requires_approval_for {
+            let is_tier = matches!(requirement.as_str(), "tier0" | "tier1" | "tier2");
+            if !is_tier && !is_known(requirement) {
+                warnings.push(format!(
+                    "Approval requirement '{}' is neither a known tool nor a tier",
+                    requirement
+                ));
+            }
+        }
+        if !self.org.people.iter().any(|p| p.role == role.id) {
+            warnings.push(format!("No one in {} holds this role", self.org.name));
+        }
+
+        warnings
+    }
+
+    /// Supply values for prompt template variables, overriding built-in ones
+    pub fn with_context(mut self, context: HashMap<String, String>) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Use `clock` for the `{{date}}` prompt variable instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
     }
 
     /// Generate Claude configuration for a specific role
@@ -37,10 +147,57 @@ impl ClaudeCompiler {
         // Generate Claude settings based on role budgets
         let settings = self.generate_settings(role);
 
+        let system_prompt = self.render_prompt(role)?;
+
         Ok(ClaudeConfig {
             mcp_servers,
             tools,
             settings,
+            system_prompt: Some(system_prompt),
+        })
+    }
+
+    /// Render a role's prompt template.
+    ///
+    /// `{{variable}}` placeholders resolve from the context map, then from `org.*`,
+    /// `role.*` and `team.*` fields (`id`, `name`, `description`) and `date`. The team is
+    /// that of the first person holding the role. Unresolved placeholders are an error,
+    /// unless marked optional as `{{variable?}}`, which renders empty.
+    pub fn render_prompt(&self, role: &RoleSpec) -> Result<String> {
+        let team = self
+            .org
+            .people
+            .iter()
+            .find(|p| p.role == role.id)
+            .and_then(|p| self.org.teams.iter().find(|t| t.id == p.team));
+
+        let resolve = |name: &str| {
+            if let Some(value) = self.context.get(name) {
+                return Some(value.clone());
+            }
+
+            let value = match name {
+                "org.id" => &self.org.id.0,
+                "org.name" => &self.org.name,
+                "org.description" => &self.org.description,
+                "role.id" => &role.id.0,
+                "role.name" => &role.name,
+                "role.description" => &role.description,
+                "team.id" => &team?.id.0,
+                "team.name" => &team?.name,
+                "team.description" => &team?.description,
+                "date" => return Some(prompt_date(self.clock.now())),
+                _ => return None,
+            };
+            Some(value.clone())
+        };
+
+        render_template(&role.prompt_template, resolve).map_err(|unresolved| {
+            anyhow::anyhow!(
+                "Unresolved variables in prompt template for role {}: {}",
+                role.id.0,
+                unresolved.join(", ")
+            )
         })
     }
 
@@ -71,19 +228,7 @@ impl ClaudeCompiler {
     fn generate_tool_configs(&self, role: &RoleSpec) -> Vec<ToolConfig> {
         let mut tools = Vec::new();
 
-        // Define all available tools
-        let all_tools = vec![
-            ("context_get", 0),
-            ("context_search", 0),
-            ("context_events", 0),
-            ("repo_read", 0),
-            ("web_fetch", 0),
-            ("repo_write", 1),
-            ("database_execute", 2),
-            ("deploy_production", 2),
-        ];
-
-        for (tool_name, tier) in all_tools {
+        for &(tool_name, tier) in KNOWN_TOOLS {
             let enabled = if role.allowed_tools.is_empty() {
                 // Empty allowlist means all tools allowed
                 true
@@ -107,14 +252,20 @@ impl ClaudeCompiler {
         tools
     }
 
-    /// Generate Claude settings from role budgets
+    /// Generate Claude settings from role budgets, unless the role configures its own
     fn generate_settings(&self, role: &RoleSpec) -> ClaudeSettings {
-        let max_tokens = role.budgets.daily_tokens.map(|t| t / 10); // Conservative per-request limit
+        if let Some(settings) = &role.claude_settings {
+            return settings.clone();
+        }
+
+        // Conservative per-request limit
+        let max_tokens = role.budgets.hard_token_limit().map(|t| t / 10);
 
         ClaudeSettings {
             max_tokens,
             temperature: Some(0.7),
             model: Some("claude-opus-4-5".to_string()),
+            ..Default::default()
         }
     }
 
@@ -195,11 +346,11 @@ Generated at: {}
                 .collect::<Vec<_>>()
                 .join("\n"),
             role.budgets
-                .daily_tokens
+                .hard_token_limit()
                 .map(|t| t.to_string())
                 .unwrap_or_else(|| "Unlimited".to_string()),
             role.budgets
-                .daily_cost_cents
+                .hard_cost_limit()
                 .map(|c| format!("${:.2}", c as f64 / 100.0))
                 .unwrap_or_else(|| "Unlimited".to_string()),
             if role.requires_approval_for.is_empty() {
@@ -221,6 +372,133 @@ Generated at: {}
     }
 }
 
+/// Substitute `{{name}}` placeholders in `template`, returning the names of any
+/// required placeholders `resolve` couldn't fill
+fn render_template(
+    template: &str,
+    resolve: impl Fn(&str) -> Option<String>,
+) -> std::result::Result<String, Vec<String>> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut unresolved = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+
+        rendered.push_str(&rest[..start]);
+        let placeholder = rest[start + 2..start + 2 + len].trim();
+        let (name, optional) = match placeholder.strip_suffix('?') {
+            Some(name) => (name.trim(), true),
+            None => (placeholder, false),
+        };
+
+        match resolve(name) {
+            Some(value) => rendered.push_str(&value),
+            None if optional => {}
+            None => unresolved.push(name.to_string()),
+        }
+
+        rest = &rest[start + 2 + len + 2..];
+    }
+    rendered.push_str(rest);
+
+    if unresolved.is_empty() {
+        Ok(rendered)
+    } else {
+        Err(unresolved)
+    }
+}
+
+/// A role's compiled configuration and README
+#[derive(Debug, Clone)]
+pub struct CompiledClaudeConfig {
+    pub config: ClaudeConfig,
+    pub readme: String,
+    /// Parts of the role's setup the config can't honour, such as unknown tools
+    pub warnings: Vec<String>,
+}
+
+type CacheKey = (Option<OrgId>, RoleId);
+/// Config generation and `{{date}}` value an entry was compiled at
+type CacheStamp = (u64, String);
+type CachedCompile = Arc<OnceCell<Arc<CompiledClaudeConfig>>>;
+
+/// Cache of compiled configs keyed by `(org_id, role_id)`. Each entry remembers the
+/// store's config generation it was compiled at, so any role, policy or organization
+/// write invalidates it, and the date, so prompts using `{{date}}` are recompiled when
+/// the day changes. A `None` org is the default organization.
+pub struct ClaudeConfigCache {
+    entries: Mutex<HashMap<CacheKey, (CacheStamp, CachedCompile)>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for ClaudeConfigCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClaudeConfigCache {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Date entries by `clock`, which should be the one their compilers render with
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Return the config cached for `generation` today, or run `compile` to produce it.
+    /// Concurrent calls for the same key and generation share one compilation.
+    /// `generation` must be read before `compile` reads the store, so a write that
+    /// lands mid-compilation leaves the entry stale rather than wrong. Failed
+    /// compilations leave no entry behind, so lookups of unknown roles don't pile up.
+    pub async fn get_or_compile<F>(
+        &self,
+        org_id: Option<OrgId>,
+        role_id: RoleId,
+        generation: u64,
+        compile: F,
+    ) -> Result<Arc<CompiledClaudeConfig>>
+    where
+        F: FnOnce() -> Result<CompiledClaudeConfig>,
+    {
+        let key = (org_id, role_id);
+        let stamp = (generation, prompt_date(self.clock.now()));
+        let cell = {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries
+                .entry(key.clone())
+                .or_insert_with(|| (stamp.clone(), Arc::default()));
+            if entry.0 != stamp {
+                *entry = (stamp, Arc::default());
+            }
+            entry.1.clone()
+        };
+
+        let compiled = cell
+            .get_or_try_init(|| async { compile().map(Arc::new) })
+            .await
+            .cloned();
+        if compiled.is_err() {
+            let mut entries = self.entries.lock().unwrap();
+            let unfilled = entries
+                .get(&key)
+                .is_some_and(|(_, current)| Arc::ptr_eq(current, &cell) && !current.initialized());
+            if unfilled {
+                entries.remove(&key);
+            }
+        }
+        compiled
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -271,8 +549,12 @@ mod tests {
                 budgets: RoleBudgets {
                     daily_tokens: Some(100000),
                     daily_cost_cents: Some(1000),
+                    ..Default::default()
                 },
                 requires_approval_for: vec!["repo_write".to_string()],
+                model_fallback: vec![],
+                max_concurrent: None,
+                claude_settings: None,
             },
             RoleSpec {
                 id: RoleId::new("analyst"),
@@ -283,8 +565,12 @@ mod tests {
                 budgets: RoleBudgets {
                     daily_tokens: Some(50000),
                     daily_cost_cents: Some(500),
+                    ..Default::default()
                 },
                 requires_approval_for: vec![],
+                model_fallback: vec![],
+                max_concurrent: None,
+                claude_settings: None,
             },
         ];
 
@@ -350,4 +636,146 @@ mod tests {
         assert!(readme.contains("context_search"));
         assert!(readme.contains("repo_write"));
     }
+
+    #[test]
+    fn test_render_prompt_template() {
+        let (org, mut roles) = create_test_setup();
+        roles[0].prompt_template =
+            "You are a {{role.name}} at {{org.name}} on the {{ team.name }} team.{{note?}}"
+                .to_string();
+        let compiler = ClaudeCompiler::new(org, roles, vec![]);
+
+        let config = compiler.compile_for_role(&RoleId::new("engineer")).unwrap();
+        assert_eq!(
+            config.system_prompt.as_deref(),
+            Some("You are a Software Engineer at Test Organization on the Engineering team.")
+        );
+
+        let compiler =
+            compiler.with_context(HashMap::from([("note".to_string(), " Be brief.".to_string())]));
+        let config = compiler.compile_for_role(&RoleId::new("engineer")).unwrap();
+        assert!(config.system_prompt.unwrap().ends_with("team. Be brief."));
+    }
+
+    #[test]
+    fn test_render_prompt_reports_unknown_variable() {
+        let (org, mut roles) = create_test_setup();
+        roles[1].prompt_template = "Analyse {{project}} for {{org.name}}".to_string();
+        let compiler = ClaudeCompiler::new(org, roles, vec![]);
+
+        let err = compiler
+            .compile_for_role(&RoleId::new("analyst"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("project"));
+        assert!(!err.contains("org.name"));
+    }
+
+    #[test]
+    fn test_compile_all_covers_every_role_with_warnings() {
+        let (org, mut roles) = create_test_setup();
+        roles[0].allowed_tools.push("shell_exec".to_string());
+        let compiler = ClaudeCompiler::new(org, roles, vec![]);
+
+        let compiled: HashMap<_, _> = compiler
+            .compile_all()
+            .into_iter()
+            .map(|(role_id, result)| (role_id.0, result.unwrap()))
+            .collect();
+        assert_eq!(compiled.len(), 2);
+
+        assert_eq!(
+            compiled["engineer"].warnings,
+            vec!["Allowed tool 'shell_exec' is not a known tool".to_string()]
+        );
+        assert!(compiled["engineer"].config.system_prompt.is_some());
+        // Nobody in the org is an analyst
+        assert_eq!(
+            compiled["analyst"].warnings,
+            vec!["No one in Test Organization holds this role".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_config_cache_hits_until_role_changes() {
+        use crate::storage::InMemoryIndexStore;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let (org, roles) = create_test_setup();
+        let store = InMemoryIndexStore::new();
+        store.store_organization(&org).unwrap();
+        for role in &roles {
+            store.store_role(role).unwrap();
+        }
+
+        let cache = ClaudeConfigCache::new();
+        let loads = AtomicUsize::new(0);
+        let compile = |generation: u64| {
+            let (store, loads, cache) = (&store, &loads, &cache);
+            async move {
+                let role_id = RoleId::new("engineer");
+                cache
+                    .get_or_compile(None, role_id.clone(), generation, || {
+                        loads.fetch_add(1, Ordering::SeqCst);
+                        ClaudeCompiler::from_store(store, None)?.compile(&role_id)
+                    })
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // Concurrent and repeated identical requests compile once
+        let generation = store.config_generation();
+        let (first, second) = tokio::join!(compile(generation), compile(generation));
+        let third = compile(store.config_generation()).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert!(Arc::ptr_eq(&first, &second) && Arc::ptr_eq(&first, &third));
+
+        // Editing a role invalidates the entry
+        let mut engineer = roles[0].clone();
+        engineer.budgets.daily_tokens = Some(200000);
+        store.store_role(&engineer).unwrap();
+        let recompiled = compile(store.config_generation()).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+        assert_eq!(recompiled.config.settings.max_tokens, Some(20000));
+    }
+
+    #[tokio::test]
+    async fn test_config_cache_recompiles_dated_prompt_after_midnight() {
+        use crate::clock::ManualClock;
+
+        let (org, mut roles) = create_test_setup();
+        roles[0].prompt_template = "You are a software engineer. Today is {{date}}.".to_string();
+        let clock = Arc::new(ManualClock::new("2026-10-14T23:59:00Z".parse().unwrap()));
+        let cache = ClaudeConfigCache::new().with_clock(clock.clone());
+        let compile = || {
+            let compiler = ClaudeCompiler::new(org.clone(), roles.clone(), vec![])
+                .with_clock(clock.clone());
+            cache.get_or_compile(None, RoleId::new("engineer"), 1, move || {
+                compiler.compile(&RoleId::new("engineer"))
+            })
+        };
+        let prompt = |compiled: &CompiledClaudeConfig| compiled.config.system_prompt.clone();
+
+        let before = compile().await.unwrap();
+        assert!(prompt(&before).unwrap().ends_with("Today is 2026-10-14."));
+        assert!(Arc::ptr_eq(&before, &compile().await.unwrap()));
+
+        // Same config generation, but the prompt's date has moved on
+        clock.advance(chrono::Duration::minutes(2));
+        let after = compile().await.unwrap();
+        assert!(prompt(&after).unwrap().ends_with("Today is 2026-10-15."));
+    }
+
+    #[tokio::test]
+    async fn test_config_cache_keeps_no_failed_compiles() {
+        let cache = ClaudeConfigCache::new();
+        let result = cache
+            .get_or_compile(None, RoleId::new("ghost"), 1, || {
+                Err(anyhow::anyhow!("Role not found: ghost"))
+            })
+            .await;
+        assert!(result.is_err());
+        assert!(cache.entries.lock().unwrap().is_empty());
+    }
 }
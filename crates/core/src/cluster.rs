@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+use crate::validation::ValidationErrors;
+
 /// Unique identifier for a cluster node
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct NodeId(pub String);
@@ -31,6 +33,23 @@ pub struct ClusterNode {
     pub metadata: HashMap<String, String>,
 }
 
+impl ClusterNode {
+    /// Value of a placement label on this node
+    pub fn label(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .get(key)
+            .map(String::as_str)
+            .or_else(|| (key == "region").then_some(self.region.as_deref()).flatten())
+    }
+
+    /// Check whether the node's labels match all `requirements`
+    pub fn satisfies(&self, requirements: &HashMap<String, String>) -> bool {
+        requirements
+            .iter()
+            .all(|(key, value)| self.label(key) == Some(value.as_str()))
+    }
+}
+
 /// Node status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NodeStatus {
@@ -38,6 +57,8 @@ pub enum NodeStatus {
     Degraded,
     Unhealthy,
     Offline,
+    /// Finishing in-flight work before maintenance; not assigned new work
+    Draining,
 }
 
 /// Node role in the cluster
@@ -48,13 +69,52 @@ pub enum NodeRole {
     Candidate,
 }
 
+/// Region reported for nodes without a `region` label
+pub const UNASSIGNED_REGION: &str = "unassigned";
+
+/// Node counts for one region
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionHealth {
+    pub total_nodes: usize,
+    pub healthy_nodes: usize,
+    /// Whether a majority of the region's nodes are healthy
+    pub has_quorum: bool,
+}
+
+/// How many terms ahead of its own a node will accept from another node's report
+pub const MAX_TERM_LEAD: u64 = 1024;
+
 /// Cluster manager for node discovery and health tracking
 pub struct ClusterManager {
     local_node_id: NodeId,
     nodes: Arc<Mutex<HashMap<NodeId, ClusterNode>>>,
     heartbeat_timeout_secs: i64,
+    /// Leader term, incremented on each election. Leader-only writes carry the term
+    /// their leader was elected in, so a deposed leader's writes can be fenced off.
+    term: Arc<Mutex<u64>>,
+    /// Term the local node was elected leader in, if it has been
+    elected_term: Arc<Mutex<Option<u64>>>,
 }
 
+/// A leader-only write stamped with a term a newer election has superseded
+#[derive(Debug)]
+pub struct StaleTermError {
+    pub term: u64,
+    pub current_term: u64,
+}
+
+impl std::fmt::Display for StaleTermError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Write from stale leader term {} rejected (current term is {})",
+            self.term, self.current_term
+        )
+    }
+}
+
+impl std::error::Error for StaleTermError {}
+
 impl ClusterManager {
     /// Create a new cluster manager
     pub fn new(local_node_id: NodeId, heartbeat_timeout_secs: i64) -> Self {
@@ -62,7 +122,79 @@ impl ClusterManager {
             local_node_id,
             nodes: Arc::new(Mutex::new(HashMap::new())),
             heartbeat_timeout_secs,
+            term: Arc::new(Mutex::new(0)),
+            elected_term: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// ID of the node this manager runs on
+    pub fn local_node_id(&self) -> &NodeId {
+        &self.local_node_id
+    }
+
+    /// The latest leader term this node knows of
+    pub fn current_term(&self) -> u64 {
+        *self.term.lock().unwrap()
+    }
+
+    /// Adopt a term learned from another node, e.g. from a heartbeat response.
+    /// Terms only move forward; an older term is ignored. A term more than
+    /// `MAX_TERM_LEAD` ahead is rejected rather than letting one bad report fence
+    /// off every write; a node that far behind resyncs from the election lock.
+    pub fn observe_term(&self, term: u64) -> anyhow::Result<()> {
+        let mut current = self.term.lock().unwrap();
+        if term > current.saturating_add(MAX_TERM_LEAD) {
+            let mut errors = ValidationErrors::new();
+            errors.push(
+                "term",
+                format!(
+                    "Term {} is more than {} ahead of the current term {}",
+                    term, MAX_TERM_LEAD, *current
+                ),
+            );
+            return errors.into_result();
+        }
+        *current = (*current).max(term);
+        Ok(())
+    }
+
+    /// Start `term`, as issued by the election lock, led by `leader`, demoting any
+    /// other node still marked leader
+    fn begin_term(&self, leader: &NodeId, term: u64) -> anyhow::Result<u64> {
+        let mut current = self.term.lock().unwrap();
+        if term <= *current {
+            return Err(StaleTermError { term, current_term: *current }.into());
+        }
+        *current = term;
+        *self.elected_term.lock().unwrap() =
+            (leader == &self.local_node_id).then_some(term);
+
+        for node in self.nodes.lock().unwrap().values_mut() {
+            node.role = if &node.id == leader {
+                NodeRole::Leader
+            } else if node.role == NodeRole::Leader {
+                NodeRole::Follower
+            } else {
+                node.role
+            };
         }
+
+        Ok(term)
+    }
+
+    /// Run a leader-only write stamped with the term its leader was elected in. The
+    /// write is rejected if a newer term has begun since, and no election can start
+    /// while it runs, so `write` mustn't call back into this manager's term methods.
+    pub fn fenced_write<T>(
+        &self,
+        term: u64,
+        write: impl FnOnce() -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let current = self.term.lock().unwrap();
+        if term < *current {
+            return Err(StaleTermError { term, current_term: *current }.into());
+        }
+        write()
     }
 
     /// Register a node in the cluster
@@ -80,11 +212,44 @@ impl ClusterManager {
             .ok_or_else(|| anyhow::anyhow!("Node not found: {}", node_id.0))?;
 
         node.last_heartbeat = Utc::now();
-        node.status = NodeStatus::Healthy;
+        if node.status != NodeStatus::Draining {
+            node.status = NodeStatus::Healthy;
+        }
 
         Ok(())
     }
 
+    /// Stop assigning new work to a node. It stays in the cluster, heartbeating and
+    /// finishing in-flight work, until it is undrained or removed.
+    pub fn drain_node(&self, node_id: &NodeId) -> anyhow::Result<ClusterNode> {
+        let mut nodes = self.nodes.lock().unwrap();
+        let node = nodes
+            .get_mut(node_id)
+            .ok_or_else(|| anyhow::anyhow!("Node not found: {}", node_id.0))?;
+
+        node.status = NodeStatus::Draining;
+        tracing::info!("Draining cluster node {}", node_id.0);
+
+        Ok(node.clone())
+    }
+
+    /// Return a draining node to service
+    pub fn undrain_node(&self, node_id: &NodeId) -> anyhow::Result<ClusterNode> {
+        let mut nodes = self.nodes.lock().unwrap();
+        let node = nodes
+            .get_mut(node_id)
+            .ok_or_else(|| anyhow::anyhow!("Node not found: {}", node_id.0))?;
+
+        if node.status != NodeStatus::Draining {
+            return Err(anyhow::anyhow!("Node {} is not draining", node_id.0));
+        }
+
+        node.status = NodeStatus::Healthy;
+        tracing::info!("Undrained cluster node {}", node_id.0);
+
+        Ok(node.clone())
+    }
+
     /// Get a node by ID
     pub fn get_node(&self, node_id: &NodeId) -> Option<ClusterNode> {
         self.nodes.lock().unwrap().get(node_id).cloned()
@@ -106,6 +271,24 @@ impl ClusterManager {
             .collect()
     }
 
+    /// Pick a healthy node whose labels satisfy every requirement, e.g. `region=eu-west`.
+    ///
+    /// Labels come from the node's metadata, with `region` also matching the node's
+    /// `region` field. Among matching nodes the one with the freshest heartbeat wins.
+    pub fn select_node(&self, requirements: &HashMap<String, String>) -> Option<NodeId> {
+        self.nodes
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|n| n.status == NodeStatus::Healthy && n.satisfies(requirements))
+            .max_by(|a, b| {
+                a.last_heartbeat
+                    .cmp(&b.last_heartbeat)
+                    .then_with(|| b.id.0.cmp(&a.id.0))
+            })
+            .map(|n| n.id.clone())
+    }
+
     /// Get the current leader node
     pub fn get_leader(&self) -> Option<ClusterNode> {
         self.nodes
@@ -123,6 +306,15 @@ impl ClusterManager {
             .unwrap_or(false)
     }
 
+    /// Term the local node was elected in, while it still holds the leader role. This
+    /// is the term its leader-only writes must carry; it may already be stale.
+    pub fn leader_term(&self) -> Option<u64> {
+        if !self.is_leader() {
+            return None;
+        }
+        *self.elected_term.lock().unwrap()
+    }
+
     /// Remove a node from the cluster
     pub fn remove_node(&self, node_id: &NodeId) -> anyhow::Result<()> {
         let mut nodes = self.nodes.lock().unwrap();
@@ -132,7 +324,8 @@ impl ClusterManager {
         Ok(())
     }
 
-    /// Check for stale nodes and mark them as unhealthy
+    /// Check for stale nodes and mark them as unhealthy. Draining nodes keep their
+    /// status, so a node restarted during maintenance doesn't rejoin rotation.
     pub fn check_stale_nodes(&self) -> Vec<NodeId> {
         let mut nodes = self.nodes.lock().unwrap();
         let now = Utc::now();
@@ -141,7 +334,9 @@ impl ClusterManager {
 
         for (node_id, node) in nodes.iter_mut() {
             if now - node.last_heartbeat > timeout {
-                node.status = NodeStatus::Unhealthy;
+                if node.status != NodeStatus::Draining {
+                    node.status = NodeStatus::Unhealthy;
+                }
                 stale_nodes.push(node_id.clone());
             }
         }
@@ -158,11 +353,35 @@ impl ClusterManager {
     pub fn healthy_node_count(&self) -> usize {
         self.list_healthy_nodes().len()
     }
+
+    /// Node counts per region, keyed by each node's `region` label. Nodes without one
+    /// are counted under `UNASSIGNED_REGION`.
+    pub fn region_health(&self) -> HashMap<String, RegionHealth> {
+        let mut regions: HashMap<String, RegionHealth> = HashMap::new();
+
+        for node in self.nodes.lock().unwrap().values() {
+            let region = node.label("region").unwrap_or(UNASSIGNED_REGION);
+            let health = regions.entry(region.to_string()).or_default();
+            health.total_nodes += 1;
+            if node.status == NodeStatus::Healthy {
+                health.healthy_nodes += 1;
+            }
+        }
+
+        for health in regions.values_mut() {
+            health.has_quorum = health.healthy_nodes > health.total_nodes / 2;
+        }
+
+        regions
+    }
 }
 
 /// Distributed lock for coordinating exclusive access
 pub struct DistributedLock {
     locks: Arc<Mutex<HashMap<String, LockInfo>>>,
+    /// Fencing term per key, bumped on each new acquisition. Nodes electing through
+    /// the same lock draw their terms from here, so no two leaders share a term.
+    terms: Arc<Mutex<HashMap<String, u64>>>,
     default_ttl_secs: i64,
 }
 
@@ -178,6 +397,7 @@ impl DistributedLock {
     pub fn new(default_ttl_secs: i64) -> Self {
         Self {
             locks: Arc::new(Mutex::new(HashMap::new())),
+            terms: Arc::new(Mutex::new(HashMap::new())),
             default_ttl_secs,
         }
     }
@@ -216,6 +436,52 @@ impl DistributedLock {
         Ok(true)
     }
 
+    /// Acquire a lock, or extend it if `holder` already has it, returning the
+    /// fencing term the holding is under. A fresh acquisition starts a new term.
+    pub fn acquire_term(
+        &self,
+        key: &str,
+        holder: NodeId,
+        ttl_secs: i64,
+    ) -> anyhow::Result<Option<u64>> {
+        let mut locks = self.locks.lock().unwrap();
+        let mut terms = self.terms.lock().unwrap();
+        let now = Utc::now();
+        let term = terms.get(key).copied().unwrap_or(0);
+
+        let renewing = match locks.get(key) {
+            Some(lock_info) if lock_info.expires_at > now => {
+                if lock_info.holder != holder {
+                    return Ok(None);
+                }
+                true
+            }
+            _ => false,
+        };
+
+        let term = if renewing {
+            term
+        } else {
+            term.checked_add(1)
+                .ok_or_else(|| anyhow::anyhow!("Fencing term for lock '{}' is exhausted", key))?
+        };
+        terms.insert(key.to_string(), term);
+        locks.insert(
+            key.to_string(),
+            LockInfo {
+                holder,
+                acquired_at: now,
+                expires_at: now + Duration::seconds(ttl_secs),
+            },
+        );
+        Ok(Some(term))
+    }
+
+    /// Latest fencing term issued for a key
+    pub fn current_term(&self, key: &str) -> u64 {
+        self.terms.lock().unwrap().get(key).copied().unwrap_or(0)
+    }
+
     /// Release a lock
     pub fn release(&self, key: &str, holder: &NodeId) -> anyhow::Result<bool> {
         let mut locks = self.locks.lock().unwrap();
@@ -298,24 +564,26 @@ impl LeaderElection {
         }
     }
 
-    /// Attempt to become leader
+    /// Attempt to become leader. Each successful acquisition starts a new term, issued
+    /// by the lock so that every manager electing through it agrees on the order of
+    /// terms. The leader reads it from `ClusterManager::current_term` to stamp its writes.
     pub fn try_become_leader(&self, node_id: &NodeId) -> anyhow::Result<bool> {
         // Try to acquire the leader lock
-        let acquired = self
-            .lock
-            .acquire_with_ttl(&self.election_key, node_id.clone(), self.lease_duration_secs)?;
-
-        if acquired {
-            // Update node role to leader
-            if let Some(mut node) = self.cluster_manager.get_node(node_id) {
-                node.role = NodeRole::Leader;
-                self.cluster_manager.register_node(node)?;
-            }
+        let Some(term) = self.lock.acquire_term(
+            &self.election_key,
+            node_id.clone(),
+            self.lease_duration_secs,
+        )?
+        else {
+            return Ok(false);
+        };
 
-            tracing::info!("Node {} became leader", node_id.0);
+        if self.cluster_manager.current_term() < term {
+            self.cluster_manager.begin_term(node_id, term)?;
+            tracing::info!("Node {} became leader for term {}", node_id.0, term);
         }
 
-        Ok(acquired)
+        Ok(true)
     }
 
     /// Renew leadership
@@ -331,11 +599,23 @@ impl LeaderElection {
         Ok(false)
     }
 
+    /// Adopt the election lock's latest term, however far ahead it is. Unlike a
+    /// term reported by another node, the lock is the source of terms.
+    pub fn sync_term(&self) {
+        let term = self.lock.current_term(&self.election_key);
+        let mut current = self.cluster_manager.term.lock().unwrap();
+        *current = (*current).max(term);
+    }
+
     /// Step down as leader
     pub fn step_down(&self, node_id: &NodeId) -> anyhow::Result<()> {
         // Release the leader lock
         self.lock.release(&self.election_key, node_id)?;
 
+        if node_id == &self.cluster_manager.local_node_id {
+            *self.cluster_manager.elected_term.lock().unwrap() = None;
+        }
+
         // Update node role to follower
         if let Some(mut node) = self.cluster_manager.get_node(node_id) {
             node.role = NodeRole::Follower;
@@ -414,6 +694,127 @@ mod tests {
         assert_eq!(healthy[0].id, node2.id);
     }
 
+    #[test]
+    fn test_select_node_by_region() {
+        let manager = ClusterManager::new(NodeId::new("node1"), 30);
+
+        let mut us = create_test_node("us");
+        us.metadata.insert("gpu".to_string(), "a100".to_string());
+        let mut eu = create_test_node("eu");
+        eu.region = Some("eu-west".to_string());
+        let mut eu_gpu = create_test_node("eu-gpu");
+        eu_gpu.region = None;
+        eu_gpu.metadata = HashMap::from([
+            ("region".to_string(), "eu-west".to_string()),
+            ("gpu".to_string(), "a100".to_string()),
+        ]);
+        let mut eu_down = create_test_node("eu-down");
+        eu_down.region = Some("eu-west".to_string());
+        eu_down.status = NodeStatus::Unhealthy;
+
+        for node in [us, eu, eu_gpu, eu_down] {
+            manager.register_node(node).unwrap();
+        }
+
+        let require = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        assert_eq!(
+            manager.select_node(&require(&[("region", "eu-west"), ("gpu", "a100")])),
+            Some(NodeId::new("eu-gpu"))
+        );
+        assert_eq!(
+            manager.select_node(&require(&[("gpu", "a100"), ("region", "us-east-1")])),
+            Some(NodeId::new("us"))
+        );
+
+        let eu_node = manager.select_node(&require(&[("region", "eu-west")])).unwrap();
+        assert!(eu_node == NodeId::new("eu") || eu_node == NodeId::new("eu-gpu"));
+
+        assert_eq!(manager.select_node(&require(&[("region", "ap-south")])), None);
+        assert!(manager.select_node(&HashMap::new()).is_some());
+    }
+
+    #[test]
+    fn test_region_health() {
+        let manager = ClusterManager::new(NodeId::new("us-1"), 30);
+
+        for id in ["us-1", "us-2", "us-3", "eu-1", "eu-2"] {
+            let mut node = create_test_node(id);
+            if id.starts_with("eu") {
+                node.region = Some("eu-west".to_string());
+            }
+            manager.register_node(node).unwrap();
+        }
+
+        // eu-west goes dark
+        for id in ["eu-1", "eu-2"] {
+            let mut node = manager.get_node(&NodeId::new(id)).unwrap();
+            node.status = NodeStatus::Offline;
+            manager.register_node(node).unwrap();
+        }
+
+        let regions = manager.region_health();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(
+            regions["us-east-1"],
+            RegionHealth {
+                total_nodes: 3,
+                healthy_nodes: 3,
+                has_quorum: true,
+            }
+        );
+        assert_eq!(
+            regions["eu-west"],
+            RegionHealth {
+                total_nodes: 2,
+                healthy_nodes: 0,
+                has_quorum: false,
+            }
+        );
+
+        // Losing one of three nodes keeps a majority
+        let mut node = manager.get_node(&NodeId::new("us-3")).unwrap();
+        node.status = NodeStatus::Unhealthy;
+        manager.register_node(node).unwrap();
+        assert!(manager.region_health()["us-east-1"].has_quorum);
+    }
+
+    #[test]
+    fn test_draining_node_not_selected() {
+        let manager = ClusterManager::new(NodeId::new("node1"), 30);
+        let mut node1 = create_test_node("node1");
+        let node2 = create_test_node("node2");
+        // node1 would win on heartbeat freshness
+        node1.last_heartbeat = node2.last_heartbeat + Duration::seconds(1);
+        manager.register_node(node1.clone()).unwrap();
+        manager.register_node(node2.clone()).unwrap();
+
+        let any = HashMap::new();
+        assert_eq!(manager.select_node(&any), Some(node1.id.clone()));
+
+        manager.drain_node(&node1.id).unwrap();
+        assert_eq!(manager.select_node(&any), Some(node2.id.clone()));
+
+        // Still a member, and heartbeats don't end the drain
+        manager.heartbeat(&node1.id).unwrap();
+        assert_eq!(manager.cluster_size(), 2);
+        assert_eq!(manager.get_node(&node1.id).unwrap().status, NodeStatus::Draining);
+        assert_eq!(manager.select_node(&any), Some(node2.id.clone()));
+
+        manager.undrain_node(&node1.id).unwrap();
+        assert_eq!(manager.select_node(&any), Some(node1.id.clone()));
+        assert!(manager.undrain_node(&node1.id).is_err());
+
+        manager.drain_node(&node1.id).unwrap();
+        manager.remove_node(&node1.id).unwrap();
+        assert_eq!(manager.cluster_size(), 1);
+    }
+
     #[test]
     fn test_distributed_lock_acquire() {
         let lock = DistributedLock::new(30);
@@ -476,4 +877,90 @@ mod tests {
         // Now node2 can become leader
         assert!(election.try_become_leader(&node2.id).unwrap());
     }
+
+    #[test]
+    fn test_stale_leader_write_rejected() {
+        let cluster_manager = Arc::new(ClusterManager::new(NodeId::new("node1"), 30));
+        let node1 = create_test_node("node1");
+        let node2 = create_test_node("node2");
+        cluster_manager.register_node(node1.clone()).unwrap();
+        cluster_manager.register_node(node2.clone()).unwrap();
+
+        // Leases lapse immediately, as if node1 were partitioned past its lease
+        let lock = Arc::new(DistributedLock::new(0));
+        let election = LeaderElection::new(cluster_manager.clone(), lock, 0);
+
+        assert!(election.try_become_leader(&node1.id).unwrap());
+        let old_term = cluster_manager.current_term();
+        assert_eq!(cluster_manager.leader_term(), Some(old_term));
+        assert_eq!(cluster_manager.fenced_write(old_term, || Ok("applied")).unwrap(), "applied");
+
+        assert!(election.try_become_leader(&node2.id).unwrap());
+        let new_term = cluster_manager.current_term();
+        assert!(new_term > old_term);
+        assert_eq!(cluster_manager.get_leader().unwrap().id, node2.id);
+
+        // node1 still believes it leads and tries to write with its old term
+        let mut written = false;
+        let err = cluster_manager
+            .fenced_write(old_term, || {
+                written = true;
+                Ok(())
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("stale leader term"));
+        assert!(!written);
+        cluster_manager.fenced_write(new_term, || Ok(())).unwrap();
+
+        // node1 is no longer leader, so it has no term to stamp writes with
+        assert_eq!(cluster_manager.leader_term(), None);
+
+        // A node that hears the new term via heartbeat won't fall back to the old one
+        let follower = ClusterManager::new(NodeId::new("node3"), 30);
+        follower.observe_term(new_term).unwrap();
+        follower.observe_term(old_term).unwrap();
+        assert_eq!(follower.current_term(), new_term);
+    }
+
+    #[test]
+    fn test_term_far_ahead_rejected() {
+        let manager = ClusterManager::new(NodeId::new("node1"), 30);
+        manager.observe_term(MAX_TERM_LEAD).unwrap();
+        assert_eq!(manager.current_term(), MAX_TERM_LEAD);
+
+        let err = manager.observe_term(u64::MAX).unwrap_err();
+        assert!(err.is::<ValidationErrors>());
+        assert_eq!(manager.current_term(), MAX_TERM_LEAD);
+    }
+
+    #[test]
+    fn test_managers_contend_through_shared_lock() {
+        // Two nodes, each with its own manager, electing through one lock
+        let lock = Arc::new(DistributedLock::new(30));
+        let manager1 = Arc::new(ClusterManager::new(NodeId::new("node1"), 30));
+        let manager2 = Arc::new(ClusterManager::new(NodeId::new("node2"), 30));
+        let election1 = LeaderElection::new(manager1.clone(), lock.clone(), 30);
+        let election2 = LeaderElection::new(manager2.clone(), lock.clone(), 30);
+        manager1.register_node(create_test_node("node1")).unwrap();
+        manager2.register_node(create_test_node("node2")).unwrap();
+
+        assert!(election1.try_become_leader(&NodeId::new("node1")).unwrap());
+        assert!(!election2.try_become_leader(&NodeId::new("node2")).unwrap());
+        assert_eq!(manager1.leader_term(), Some(1));
+        assert_eq!(manager2.leader_term(), None);
+
+        // Renewing keeps the term
+        assert!(election1.renew_leadership(&NodeId::new("node1")).unwrap());
+        assert_eq!(manager1.leader_term(), Some(1));
+
+        // Once node1 steps down node2 wins, in a term node1 never held
+        election1.step_down(&NodeId::new("node1")).unwrap();
+        assert!(election2.try_become_leader(&NodeId::new("node2")).unwrap());
+        assert_eq!(manager2.leader_term(), Some(2));
+
+        // node1 learns of the new term and its old writes are fenced off
+        election1.sync_term();
+        assert!(manager1.fenced_write(1, || Ok(())).is_err());
+        assert_eq!(manager1.current_term(), 2);
+    }
 }
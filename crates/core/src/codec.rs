@@ -0,0 +1,398 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Content type for JSON payloads
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Content type for MessagePack payloads
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Encoding used for stored values and API payloads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Codec {
+    #[default]
+    Json,
+    /// Compact binary encoding; requires the `msgpack` feature
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+}
+
+impl Codec {
+    /// Encode a value in this codec's format
+    pub fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Codec::Json => Ok(serde_json::to_vec(value)?),
+            #[cfg(feature = "msgpack")]
+            Codec::MessagePack => msgpack::to_vec(value),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Codec::Json => JSON_CONTENT_TYPE,
+            #[cfg(feature = "msgpack")]
+            Codec::MessagePack => MSGPACK_CONTENT_TYPE,
+        }
+    }
+
+    /// Codec for an HTTP `Content-Type` or `Accept` header value, if supported
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        match mime {
+            JSON_CONTENT_TYPE => Some(Codec::Json),
+            #[cfg(feature = "msgpack")]
+            MSGPACK_CONTENT_TYPE | "application/x-msgpack" => Some(Codec::MessagePack),
+            _ => None,
+        }
+    }
+}
+
+/// Decode a value written by any codec.
+///
+/// Stored values are always objects or arrays, so JSON starts with an ASCII byte while
+/// a MessagePack map or array starts with a byte of `0x80` or above. This lets stores
+/// read legacy JSON after switching to MessagePack.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    #[cfg(feature = "msgpack")]
+    if bytes.first().is_some_and(|b| !b.is_ascii()) {
+        return msgpack::from_slice(bytes);
+    }
+
+    Ok(serde_json::from_slice(bytes)?)
+}
+
+/// MessagePack encoding of the JSON data model
+#[cfg(feature = "msgpack")]
+pub mod msgpack {
+    use anyhow::{anyhow, Result};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use serde_json::{Map, Number, Value};
+
+    pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        write_value(&mut buf, &serde_json::to_value(value)?);
+        Ok(buf)
+    }
+
+    /// Deepest nesting of arrays and maps accepted, so a hostile payload of nested
+    /// one-element arrays can't overflow the stack
+    pub const MAX_DEPTH: usize = 64;
+
+    pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        let mut reader = Reader {
+            bytes,
+            pos: 0,
+            depth: 0,
+        };
+        let value = reader.read_value()?;
+        if reader.pos != bytes.len() {
+            return Err(anyhow!("Trailing bytes after MessagePack value"));
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    fn write_value(buf: &mut Vec<u8>, value: &Value) {
+        match value {
+            Value::Null => buf.push(0xc0),
+            Value::Bool(false) => buf.push(0xc2),
+            Value::Bool(true) => buf.push(0xc3),
+            Value::Number(n) => write_number(buf, n),
+            Value::String(s) => {
+                write_str_len(buf, s.len());
+                buf.extend_from_slice(s.as_bytes());
+            }
+            Value::Array(items) => {
+                write_container_len(buf, items.len(), 0x90, 0xdc, 0xdd);
+                items.iter().for_each(|v| write_value(buf, v));
+            }
+            Value::Object(map) => {
+                write_container_len(buf, map.len(), 0x80, 0xde, 0xdf);
+                for (k, v) in map {
+                    write_value(buf, &Value::String(k.clone()));
+                    write_value(buf, v);
+                }
+            }
+        }
+    }
+
+    fn write_str_len(buf: &mut Vec<u8>, len: usize) {
+        if len < 32 {
+            buf.push(0xa0 | len as u8);
+        } else if len <= u8::MAX as usize {
+            buf.extend([0xd9, len as u8]);
+        } else {
+            write_wide_len(buf, len, 0xda, 0xdb);
+        }
+    }
+
+    fn write_container_len(buf: &mut Vec<u8>, len: usize, fix: u8, m16: u8, m32: u8) {
+        if len < 16 {
+            buf.push(fix | len as u8);
+        } else {
+            write_wide_len(buf, len, m16, m32);
+        }
+    }
+
+    fn write_wide_len(buf: &mut Vec<u8>, len: usize, m16: u8, m32: u8) {
+        if len <= u16::MAX as usize {
+            buf.push(m16);
+            buf.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            buf.push(m32);
+            buf.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+
+    fn write_number(buf: &mut Vec<u8>, n: &Number) {
+        if let Some(u) = n.as_u64() {
+            match u {
+                0..=0x7f => buf.push(u as u8),
+                0x80..=0xff => buf.extend([0xcc, u as u8]),
+                0x100..=0xffff => {
+                    buf.push(0xcd);
+                    buf.extend_from_slice(&(u as u16).to_be_bytes());
+                }
+                0x1_0000..=0xffff_ffff => {
+                    buf.push(0xce);
+                    buf.extend_from_slice(&(u as u32).to_be_bytes());
+                }
+                _ => {
+                    buf.push(0xcf);
+                    buf.extend_from_slice(&u.to_be_bytes());
+                }
+            }
+        } else if let Some(i) = n.as_i64() {
+            // Only negative values reach here
+            if i >= -32 {
+                buf.push(i as i8 as u8);
+            } else if i >= i8::MIN as i64 {
+                buf.extend([0xd0, i as i8 as u8]);
+            } else if i >= i16::MIN as i64 {
+                buf.push(0xd1);
+                buf.extend_from_slice(&(i as i16).to_be_bytes());
+            } else if i >= i32::MIN as i64 {
+                buf.push(0xd2);
+                buf.extend_from_slice(&(i as i32).to_be_bytes());
+            } else {
+                buf.push(0xd3);
+                buf.extend_from_slice(&i.to_be_bytes());
+            }
+        } else {
+            buf.push(0xcb);
+            buf.extend_from_slice(&n.as_f64().unwrap_or_default().to_be_bytes());
+        }
+    }
+
+    struct Reader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+        /// Arrays and maps currently open
+        depth: usize,
+    }
+
+    impl Reader<'_> {
+        fn take(&mut self, n: usize) -> Result<&[u8]> {
+            let end = self
+                .pos
+                .checked_add(n)
+                .filter(|&end| end <= self.bytes.len())
+                .ok_or_else(|| anyhow!("Unexpected end of MessagePack data"))?;
+            let slice = &self.bytes[self.pos..end];
+            self.pos = end;
+            Ok(slice)
+        }
+
+        fn take_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+            Ok(self.take(N)?.try_into().expect("slice has length N"))
+        }
+
+        fn read_len(&mut self, width: usize) -> Result<usize> {
+            Ok(match width {
+                1 => self.take_array::<1>()?[0] as usize,
+                2 => u16::from_be_bytes(self.take_array()?) as usize,
+                _ => u32::from_be_bytes(self.take_array()?) as usize,
+            })
+        }
+
+        fn read_value(&mut self) -> Result<Value> {
+            let marker = self.take_array::<1>()?[0];
+            Ok(match marker {
+                0x00..=0x7f => Value::from(marker),
+                0x80..=0x8f => self.read_map((marker & 0x0f) as usize)?,
+                0x90..=0x9f => self.read_array((marker & 0x0f) as usize)?,
+                0xa0..=0xbf => self.read_str((marker & 0x1f) as usize)?,
+                0xc0 => Value::Null,
+                0xc2 => Value::Bool(false),
+                0xc3 => Value::Bool(true),
+                0xca => float(f32::from_be_bytes(self.take_array()?) as f64)?,
+                0xcb => float(f64::from_be_bytes(self.take_array()?))?,
+                0xcc => Value::from(self.take_array::<1>()?[0]),
+                0xcd => Value::from(u16::from_be_bytes(self.take_array()?)),
+                0xce => Value::from(u32::from_be_bytes(self.take_array()?)),
+                0xcf => Value::from(u64::from_be_bytes(self.take_array()?)),
+                0xd0 => Value::from(self.take_array::<1>()?[0] as i8),
+                0xd1 => Value::from(i16::from_be_bytes(self.take_array()?)),
+                0xd2 => Value::from(i32::from_be_bytes(self.take_array()?)),
+                0xd3 => Value::from(i64::from_be_bytes(self.take_array()?)),
+                0xd9 => {
+                    let len = self.read_len(1)?;
+                    self.read_str(len)?
+                }
+                0xda => {
+                    let len = self.read_len(2)?;
+                    self.read_str(len)?
+                }
+                0xdb => {
+                    let len = self.read_len(4)?;
+                    self.read_str(len)?
+                }
+                0xdc => {
+                    let len = self.read_len(2)?;
+                    self.read_array(len)?
+                }
+                0xdd => {
+                    let len = self.read_len(4)?;
+                    self.read_array(len)?
+                }
+                0xde => {
+                    let len = self.read_len(2)?;
+                    self.read_map(len)?
+                }
+                0xdf => {
+                    let len = self.read_len(4)?;
+                    self.read_map(len)?
+                }
+                0xe0..=0xff => Value::from(marker as i8),
+                _ => return Err(anyhow!("Unsupported MessagePack marker 0x{:02x}", marker)),
+            })
+        }
+
+        fn read_str(&mut self, len: usize) -> Result<Value> {
+            let bytes = self.take(len)?;
+            Ok(Value::String(std::str::from_utf8(bytes)?.to_string()))
+        }
+
+        fn enter(&mut self) -> Result<()> {
+            if self.depth >= MAX_DEPTH {
+                return Err(anyhow!(
+                    "MessagePack value nested deeper than {} levels",
+                    MAX_DEPTH
+                ));
+            }
+            self.depth += 1;
+            Ok(())
+        }
+
+        fn read_array(&mut self, len: usize) -> Result<Value> {
+            self.enter()?;
+            // Cap the preallocation so a corrupt length can't exhaust memory
+            let mut items = Vec::with_capacity(len.min(1024));
+            for _ in 0..len {
+                items.push(self.read_value()?);
+            }
+            self.depth -= 1;
+            Ok(Value::Array(items))
+        }
+
+        fn read_map(&mut self, len: usize) -> Result<Value> {
+            self.enter()?;
+            let mut map = Map::new();
+            for _ in 0..len {
+                let key = match self.read_value()? {
+                    Value::String(key) => key,
+                    other => {
+                        return Err(anyhow!("MessagePack map key must be a string, got {}", other))
+                    }
+                };
+                map.insert(key, self.read_value()?);
+            }
+            self.depth -= 1;
+            Ok(Value::Object(map))
+        }
+    }
+
+    fn float(f: f64) -> Result<Value> {
+        Number::from_f64(f)
+            .map(Value::Number)
+            .ok_or_else(|| anyhow!("MessagePack float {} is not representable in JSON", f))
+    }
+}
+
+#[cfg(all(test, feature = "msgpack"))]
+mod tests {
+    use super::*;
+    use crate::types::{Run, RunId, RunStatus, StepExecution, StepId, StepStatus};
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_run_round_trips_through_msgpack() {
+        let run = Run {
+            id: RunId::new(),
+            work_item_id: "work-1".to_string(),
+            status: RunStatus::Failed,
+            started_at: Utc::now(),
+            completed_at: Some(Utc::now()),
+            steps: vec![StepExecution {
+                id: StepId::new("build"),
+                status: StepStatus::Failed,
+                started_at: Some(Utc::now()),
+                completed_at: None,
+                attempt: 3,
+                error: Some("x".repeat(300)),
+                tokens: Some(1200),
+                cost: Some(0.04),
+            }],
+            parent_run_id: Some(RunId::new()),
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        };
+
+        let bytes = Codec::MessagePack.encode(&run).unwrap();
+        assert!(bytes.len() < serde_json::to_vec(&run).unwrap().len());
+
+        let decoded: Run = decode(&bytes).unwrap();
+        assert_eq!(decoded, run);
+    }
+
+    #[test]
+    fn test_msgpack_numbers_and_legacy_json() {
+        let value = serde_json::json!({
+            "small": 7,
+            "large": u64::MAX,
+            "negative": -40_000,
+            "tiny": -3,
+            "float": 1.5,
+            "items": (0..20).collect::<Vec<_>>(),
+        });
+
+        let bytes = Codec::MessagePack.encode(&value).unwrap();
+        assert_eq!(decode::<serde_json::Value>(&bytes).unwrap(), value);
+
+        let json = Codec::Json.encode(&value).unwrap();
+        assert_eq!(decode::<serde_json::Value>(&json).unwrap(), value);
+    }
+
+    #[test]
+    fn test_msgpack_rejects_deep_nesting() {
+        // One-element arrays wrapping a nil
+        let nested = |depth: usize| {
+            let mut bytes = vec![0x91; depth];
+            bytes.push(0xc0);
+            bytes
+        };
+
+        assert!(msgpack::from_slice::<serde_json::Value>(&nested(msgpack::MAX_DEPTH)).is_ok());
+
+        let err = msgpack::from_slice::<serde_json::Value>(&nested(100_000)).unwrap_err();
+        assert!(err.to_string().contains("nested deeper"), "{}", err);
+    }
+}
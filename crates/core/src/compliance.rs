@@ -1,8 +1,13 @@
-use crate::audit::{AuditAction, AuditCategory, AuditEntry, AuditId, AuditLog, AuditSeverity};
+use crate::audit::{
+    constant_time_eq, hmac_sha256, AuditAction, AuditCategory, AuditEntry, AuditId, AuditLog,
+    AuditSeverity,
+};
 use crate::rbac::{Action, Permission, RbacManager, Resource};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
 
 /// Compliance frameworks
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -14,6 +19,19 @@ pub enum ComplianceFramework {
     PCI_DSS,
 }
 
+impl ComplianceFramework {
+    /// Every framework the compliance checker can evaluate
+    pub fn all() -> &'static [ComplianceFramework] {
+        &[
+            ComplianceFramework::SOC2,
+            ComplianceFramework::GDPR,
+            ComplianceFramework::HIPAA,
+            ComplianceFramework::ISO27001,
+            ComplianceFramework::PCI_DSS,
+        ]
+    }
+}
+
 /// Compliance requirement status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ComplianceStatus {
@@ -82,6 +100,39 @@ pub struct ComplianceReport {
     pub period_end: DateTime<Utc>,
     pub requirements: Vec<ComplianceRequirement>,
     pub summary: ComplianceSummary,
+    /// Hash of the latest audit entry when the report was generated, tying the
+    /// report to the state of the audit chain it was built from
+    #[serde(default)]
+    pub audit_chain_hash: Option<String>,
+    /// Hex HMAC-SHA256 over the rest of the report, if the generator had a signing key
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl ComplianceReport {
+    /// Sign the report's current content with `key`
+    pub fn sign(&mut self, key: &[u8]) {
+        self.signature = Some(self.compute_signature(key));
+    }
+
+    /// Verify the report was signed with `key` and not altered since
+    pub fn verify_signature(&self, key: &[u8]) -> bool {
+        self.signature
+            .as_deref()
+            .is_some_and(|signature| {
+                constant_time_eq(signature.as_bytes(), self.compute_signature(key).as_bytes())
+            })
+    }
+
+    fn compute_signature(&self, key: &[u8]) -> String {
+        // The canonical content is the report's JSON with the signature left out
+        let unsigned = ComplianceReport {
+            signature: None,
+            ..self.clone()
+        };
+        let content = serde_json::to_vec(&unsigned).expect("compliance report serializes");
+        hmac_sha256(key, &content)
+    }
 }
 
 /// Compliance summary statistics
@@ -122,9 +173,11 @@ impl ComplianceSummary {
 }
 
 /// Compliance checker
+#[derive(Clone)]
 pub struct ComplianceChecker {
     audit_log: AuditLog,
     rbac_manager: RbacManager,
+    signing_key: Option<Arc<Vec<u8>>>,
 }
 
 impl ComplianceChecker {
@@ -132,9 +185,16 @@ impl ComplianceChecker {
         Self {
             audit_log,
             rbac_manager,
+            signing_key: None,
         }
     }
 
+    /// Sign generated reports with `key`, so recipients can verify them
+    pub fn with_signing_key(mut self, key: &[u8]) -> Self {
+        self.signing_key = Some(Arc::new(key.to_vec()));
+        self
+    }
+
     /// Generate compliance report for a framework
     pub fn generate_report(
         &self,
@@ -142,17 +202,64 @@ impl ComplianceChecker {
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
     ) -> ComplianceReport {
-        let requirements = match framework {
-            ComplianceFramework::SOC2 => self.check_soc2_compliance(period_start, period_end),
-            ComplianceFramework::GDPR => self.check_gdpr_compliance(period_start, period_end),
-            ComplianceFramework::HIPAA => self.check_hipaa_compliance(period_start, period_end),
-            ComplianceFramework::ISO27001 => self.check_iso27001_compliance(period_start, period_end),
-            ComplianceFramework::PCI_DSS => self.check_pci_dss_compliance(period_start, period_end),
-        };
+        let mut requirements = Vec::new();
+        self.check_framework(framework, period_start, period_end, &mut |r| requirements.push(r));
+        self.build_report(framework, period_start, period_end, requirements)
+    }
+
+    /// Generate a compliance report off the async runtime, sending each requirement
+    /// over `progress` as soon as it has been evaluated.
+    ///
+    /// Progress delivery is best-effort: if the receiver is dropped the report is still
+    /// completed and returned.
+    pub async fn generate_report_streaming(
+        &self,
+        framework: ComplianceFramework,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        progress: mpsc::Sender<ComplianceRequirement>,
+    ) -> anyhow::Result<ComplianceReport> {
+        let checker = self.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut requirements = Vec::new();
+            checker.check_framework(framework, period_start, period_end, &mut |r| {
+                let _ = progress.blocking_send(r.clone());
+                requirements.push(r);
+            });
+            checker.build_report(framework, period_start, period_end, requirements)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Compliance report task failed: {}", e))
+    }
+
+    /// Evaluate every requirement of a framework, emitting each one as it completes
+    fn check_framework(
+        &self,
+        framework: ComplianceFramework,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        emit: &mut dyn FnMut(ComplianceRequirement),
+    ) {
+        match framework {
+            ComplianceFramework::SOC2 => self.check_soc2_compliance(period_start, period_end, emit),
+            ComplianceFramework::GDPR => self.check_gdpr_compliance(period_start, period_end, emit),
+            ComplianceFramework::HIPAA => self.check_hipaa_compliance(period_start, period_end, emit),
+            ComplianceFramework::ISO27001 => self.check_iso27001_compliance(period_start, period_end, emit),
+            ComplianceFramework::PCI_DSS => self.check_pci_dss_compliance(period_start, period_end, emit),
+        }
+    }
 
+    fn build_report(
+        &self,
+        framework: ComplianceFramework,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        requirements: Vec<ComplianceRequirement>,
+    ) -> ComplianceReport {
         let summary = ComplianceSummary::from_requirements(&requirements);
 
-        ComplianceReport {
+        let mut report = ComplianceReport {
             id: uuid::Uuid::new_v4().to_string(),
             framework,
             generated_at: Utc::now(),
@@ -160,7 +267,15 @@ impl ComplianceChecker {
             period_end,
             requirements,
             summary,
+            audit_chain_hash: self.audit_log.last_hash(),
+            signature: None,
+        };
+
+        if let Some(key) = &self.signing_key {
+            report.sign(key);
         }
+
+        report
     }
 
     /// Check SOC2 compliance
@@ -168,8 +283,8 @@ impl ComplianceChecker {
         &self,
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
-    ) -> Vec<ComplianceRequirement> {
-        let mut requirements = Vec::new();
+        emit: &mut dyn FnMut(ComplianceRequirement),
+    ) {
 
         // CC6.1: Access controls - Logical and physical access to systems
         let mut cc6_1 = ComplianceRequirement::new(
@@ -206,7 +321,7 @@ impl ComplianceChecker {
             cc6_1.update_status(ComplianceStatus::Compliant);
         }
 
-        requirements.push(cc6_1);
+        emit(cc6_1);
 
         // CC6.2: Prior to issuing system credentials, the entity registers and authorizes new users
         let mut cc6_2 = ComplianceRequirement::new(
@@ -230,7 +345,7 @@ impl ComplianceChecker {
             cc6_2.update_status(ComplianceStatus::NotApplicable);
         }
 
-        requirements.push(cc6_2);
+        emit(cc6_2);
 
         // CC7.2: Detection and monitoring of security events
         let mut cc7_2 = ComplianceRequirement::new(
@@ -249,7 +364,7 @@ impl ComplianceChecker {
             cc7_2.update_status(ComplianceStatus::Compliant);
         }
 
-        requirements.push(cc7_2);
+        emit(cc7_2);
 
         // CC7.3: Audit logs are retained and reviewed
         let mut cc7_3 = ComplianceRequirement::new(
@@ -276,9 +391,7 @@ impl ComplianceChecker {
             cc7_3.update_status(ComplianceStatus::Compliant);
         }
 
-        requirements.push(cc7_3);
-
-        requirements
+        emit(cc7_3);
     }
 
     /// Check GDPR compliance
@@ -286,8 +399,8 @@ impl ComplianceChecker {
         &self,
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
-    ) -> Vec<ComplianceRequirement> {
-        let mut requirements = Vec::new();
+        emit: &mut dyn FnMut(ComplianceRequirement),
+    ) {
 
         // Article 5: Principles relating to processing of personal data
         let mut art5 = ComplianceRequirement::new(
@@ -311,7 +424,7 @@ impl ComplianceChecker {
             art5.update_status(ComplianceStatus::Compliant);
         }
 
-        requirements.push(art5);
+        emit(art5);
 
         // Article 17: Right to erasure
         let mut art17 = ComplianceRequirement::new(
@@ -335,7 +448,7 @@ impl ComplianceChecker {
             art17.update_status(ComplianceStatus::NotApplicable);
         }
 
-        requirements.push(art17);
+        emit(art17);
 
         // Article 30: Records of processing activities
         let mut art30 = ComplianceRequirement::new(
@@ -359,7 +472,7 @@ impl ComplianceChecker {
             art30.update_status(ComplianceStatus::Compliant);
         }
 
-        requirements.push(art30);
+        emit(art30);
 
         // Article 32: Security of processing
         let mut art32 = ComplianceRequirement::new(
@@ -386,7 +499,7 @@ impl ComplianceChecker {
             art32.update_status(ComplianceStatus::Compliant);
         }
 
-        requirements.push(art32);
+        emit(art32);
 
         // Article 33: Breach notification
         let mut art33 = ComplianceRequirement::new(
@@ -410,9 +523,7 @@ impl ComplianceChecker {
             art33.update_status(ComplianceStatus::Compliant);
         }
 
-        requirements.push(art33);
-
-        requirements
+        emit(art33);
     }
 
     /// Check HIPAA compliance (placeholder)
@@ -420,16 +531,15 @@ impl ComplianceChecker {
         &self,
         _period_start: DateTime<Utc>,
         _period_end: DateTime<Utc>,
-    ) -> Vec<ComplianceRequirement> {
-        vec![
-            ComplianceRequirement::new(
-                "HIPAA-1".to_string(),
-                ComplianceFramework::HIPAA,
-                "Access Control".to_string(),
-                "Implement technical policies and procedures for electronic information systems.".to_string(),
-                "Administrative Safeguards".to_string(),
-            ),
-        ]
+        emit: &mut dyn FnMut(ComplianceRequirement),
+    ) {
+        emit(ComplianceRequirement::new(
+            "HIPAA-1".to_string(),
+            ComplianceFramework::HIPAA,
+            "Access Control".to_string(),
+            "Implement technical policies and procedures for electronic information systems.".to_string(),
+            "Administrative Safeguards".to_string(),
+        ));
     }
 
     /// Check ISO27001 compliance (placeholder)
@@ -437,16 +547,15 @@ impl ComplianceChecker {
         &self,
         _period_start: DateTime<Utc>,
         _period_end: DateTime<Utc>,
-    ) -> Vec<ComplianceRequirement> {
-        vec![
-            ComplianceRequirement::new(
-                "ISO27001-1".to_string(),
-                ComplianceFramework::ISO27001,
-                "Information Security Policy".to_string(),
-                "A set of policies for information security shall be defined.".to_string(),
-                "Policy".to_string(),
-            ),
-        ]
+        emit: &mut dyn FnMut(ComplianceRequirement),
+    ) {
+        emit(ComplianceRequirement::new(
+            "ISO27001-1".to_string(),
+            ComplianceFramework::ISO27001,
+            "Information Security Policy".to_string(),
+            "A set of policies for information security shall be defined.".to_string(),
+            "Policy".to_string(),
+        ));
     }
 
     /// Check PCI-DSS compliance (placeholder)
@@ -454,16 +563,15 @@ impl ComplianceChecker {
         &self,
         _period_start: DateTime<Utc>,
         _period_end: DateTime<Utc>,
-    ) -> Vec<ComplianceRequirement> {
-        vec![
-            ComplianceRequirement::new(
-                "PCI-DSS-1".to_string(),
-                ComplianceFramework::PCI_DSS,
-                "Install and Maintain Firewall Configuration".to_string(),
-                "Install and maintain a firewall configuration to protect cardholder data.".to_string(),
-                "Network Security".to_string(),
-            ),
-        ]
+        emit: &mut dyn FnMut(ComplianceRequirement),
+    ) {
+        emit(ComplianceRequirement::new(
+            "PCI-DSS-1".to_string(),
+            ComplianceFramework::PCI_DSS,
+            "Install and Maintain Firewall Configuration".to_string(),
+            "Install and maintain a firewall configuration to protect cardholder data.".to_string(),
+            "Network Security".to_string(),
+        ));
     }
 
     /// Helper: Count events by category
@@ -498,6 +606,117 @@ impl ComplianceChecker {
     }
 }
 
+/// Status of a background compliance report job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComplianceReportJobStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Background compliance report generation, pollable by ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplianceReportJob {
+    pub id: String,
+    pub framework: ComplianceFramework,
+    pub status: ComplianceReportJobStatus,
+    /// Requirements evaluated so far
+    pub requirements_completed: Vec<ComplianceRequirement>,
+    /// Final report, once the job has completed
+    pub report: Option<ComplianceReport>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Tracks compliance reports generated in the background
+#[derive(Clone)]
+pub struct ComplianceReportJobs {
+    jobs: Arc<Mutex<HashMap<String, ComplianceReportJob>>>,
+}
+
+impl ComplianceReportJobs {
+    pub fn new() -> Self {
+        Self {
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Start generating a report in the background and return the pending job
+    pub fn start(
+        &self,
+        checker: &ComplianceChecker,
+        framework: ComplianceFramework,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> ComplianceReportJob {
+        let job = ComplianceReportJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            framework,
+            status: ComplianceReportJobStatus::Running,
+            requirements_completed: Vec::new(),
+            report: None,
+            error: None,
+            started_at: Utc::now(),
+            completed_at: None,
+        };
+
+        self.jobs.lock().unwrap().insert(job.id.clone(), job.clone());
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let checker = checker.clone();
+        let jobs = self.jobs.clone();
+        let job_id = job.id.clone();
+
+        tokio::spawn(async move {
+            let generation = tokio::spawn(async move {
+                checker
+                    .generate_report_streaming(framework, period_start, period_end, tx)
+                    .await
+            });
+
+            while let Some(requirement) = rx.recv().await {
+                if let Some(job) = jobs.lock().unwrap().get_mut(&job_id) {
+                    job.requirements_completed.push(requirement);
+                }
+            }
+
+            let result = generation
+                .await
+                .map_err(|e| anyhow::anyhow!("Compliance report task failed: {}", e))
+                .and_then(|r| r);
+
+            if let Some(job) = jobs.lock().unwrap().get_mut(&job_id) {
+                match result {
+                    Ok(report) => {
+                        job.status = ComplianceReportJobStatus::Completed;
+                        job.report = Some(report);
+                    }
+                    Err(e) => {
+                        tracing::error!("Compliance report {} failed: {}", job_id, e);
+                        job.status = ComplianceReportJobStatus::Failed;
+                        job.error = Some(e.to_string());
+                    }
+                }
+                job.completed_at = Some(Utc::now());
+            }
+        });
+
+        job
+    }
+
+    /// Get a report job by ID
+    pub fn get(&self, job_id: &str) -> Option<ComplianceReportJob> {
+        self.jobs.lock().unwrap().get(job_id).cloned()
+    }
+}
+
+impl Default for ComplianceReportJobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Security scanner for vulnerability detection
 pub struct SecurityScanner {
     audit_log: AuditLog,
@@ -852,4 +1071,119 @@ mod tests {
         assert_eq!(cc7_3.status, ComplianceStatus::Compliant);
         assert!(cc7_3.evidence.iter().any(|e| e.contains("chain integrity verified")));
     }
+
+    fn create_large_audit_log() -> AuditLog {
+        let audit_log = AuditLog::new();
+        for i in 0..5000 {
+            audit_log.log(
+                AuditCategory::DataAccess,
+                AuditSeverity::Info,
+                AuditAction::DataAccessed {
+                    resource_type: "user_profile".to_string(),
+                    resource_id: format!("profile{}", i),
+                },
+                Some(format!("user{}", i % 50)),
+                None,
+                None,
+            );
+        }
+        audit_log
+    }
+
+    #[test]
+    fn test_signed_report_verification() {
+        let audit_log = create_large_audit_log();
+        let key = b"compliance-signing-key";
+        let checker =
+            ComplianceChecker::new(audit_log.clone(), RbacManager::new()).with_signing_key(key);
+
+        let report = checker.generate_report(
+            ComplianceFramework::SOC2,
+            Utc::now() - Duration::days(1),
+            Utc::now(),
+        );
+        assert_eq!(report.audit_chain_hash, audit_log.last_hash());
+        assert!(report.verify_signature(key));
+        assert!(!report.verify_signature(b"some-other-key"));
+
+        // Survives a JSON round trip, as an auditor would receive it
+        let json = serde_json::to_string(&report).unwrap();
+        let received: ComplianceReport = serde_json::from_str(&json).unwrap();
+        assert!(received.verify_signature(key));
+
+        let mut tampered = received.clone();
+        tampered.summary.compliant += 1;
+        assert!(!tampered.verify_signature(key));
+
+        let mut tampered = received;
+        tampered.audit_chain_hash = Some("0".repeat(64));
+        assert!(!tampered.verify_signature(key));
+
+        // Unsigned reports never verify
+        let unsigned = ComplianceChecker::new(audit_log, RbacManager::new()).generate_report(
+            ComplianceFramework::SOC2,
+            Utc::now() - Duration::days(1),
+            Utc::now(),
+        );
+        assert!(unsigned.signature.is_none());
+        assert!(!unsigned.verify_signature(key));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_report_matches_sync() {
+        let checker = ComplianceChecker::new(create_large_audit_log(), RbacManager::new());
+        let period_start = Utc::now() - Duration::days(365);
+        let period_end = Utc::now();
+
+        let sync_report = checker.generate_report(ComplianceFramework::GDPR, period_start, period_end);
+
+        let (tx, mut rx) = mpsc::channel(4);
+        let streaming = {
+            let checker = checker.clone();
+            tokio::spawn(async move {
+                checker
+                    .generate_report_streaming(ComplianceFramework::GDPR, period_start, period_end, tx)
+                    .await
+            })
+        };
+
+        let mut streamed = Vec::new();
+        while let Some(requirement) = rx.recv().await {
+            streamed.push(requirement);
+        }
+        let report = streaming.await.unwrap().unwrap();
+
+        assert_eq!(report.requirements.len(), sync_report.requirements.len());
+        assert_eq!(streamed.len(), sync_report.requirements.len());
+        assert_eq!(report.summary.compliant, sync_report.summary.compliant);
+    }
+
+    #[tokio::test]
+    async fn test_report_job_completes() {
+        let checker = ComplianceChecker::new(create_large_audit_log(), RbacManager::new());
+        let jobs = ComplianceReportJobs::new();
+
+        let job = jobs.start(
+            &checker,
+            ComplianceFramework::SOC2,
+            Utc::now() - Duration::days(365),
+            Utc::now(),
+        );
+        assert_eq!(job.status, ComplianceReportJobStatus::Running);
+
+        let mut completed = None;
+        for _ in 0..100 {
+            let current = jobs.get(&job.id).unwrap();
+            if current.status != ComplianceReportJobStatus::Running {
+                completed = Some(current);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let completed = completed.expect("report job did not complete");
+        assert_eq!(completed.status, ComplianceReportJobStatus::Completed);
+        let report = completed.report.unwrap();
+        assert_eq!(completed.requirements_completed.len(), report.requirements.len());
+    }
 }
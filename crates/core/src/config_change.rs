@@ -1,10 +1,12 @@
 use crate::approval::ApprovalManager;
+use crate::policy::{ChangeRisk, ChangeRiskPolicy};
 use crate::types::{
     ApprovalBoardId, ApprovalSubject, ConfigChange, ConfigChangeId, ConfigChangeStatus,
-    ConfigChangeType,
+    ConfigChangeType, Routine, RoutineId, RunId, StepAction, WorkflowSpec,
 };
 use anyhow::Result;
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
@@ -12,6 +14,7 @@ use std::sync::{Arc, Mutex};
 pub struct ConfigChangeManager {
     changes: Arc<Mutex<HashMap<ConfigChangeId, ConfigChange>>>,
     approval_manager: Arc<ApprovalManager>,
+    change_risk_policy: ChangeRiskPolicy,
 }
 
 impl ConfigChangeManager {
@@ -20,9 +23,16 @@ impl ConfigChangeManager {
         Self {
             changes: Arc::new(Mutex::new(HashMap::new())),
             approval_manager,
+            change_risk_policy: ChangeRiskPolicy::default(),
         }
     }
 
+    /// Route proposed changes to approval boards according to `policy`
+    pub fn with_change_risk_policy(mut self, policy: ChangeRiskPolicy) -> Self {
+        self.change_risk_policy = policy;
+        self
+    }
+
     /// Propose a configuration change
     pub fn propose_change(
         &self,
@@ -35,6 +45,16 @@ impl ConfigChangeManager {
     ) -> Result<ConfigChange> {
         let change_id = ConfigChangeId::new(uuid::Uuid::new_v4().to_string());
 
+        // High-risk changes go to the high-risk board if one is configured; others to the
+        // board asked for, or else the default board
+        let entity_id =
+            config_entity_id(&after).or_else(|| before.as_deref().and_then(config_entity_id));
+        let risk = self.change_risk_policy.assess_entity(change_type, entity_id.as_deref());
+        let approval_board = match (risk, self.change_risk_policy.board(risk)) {
+            (ChangeRisk::High, Some(board)) => Some(board.clone()),
+            (_, board) => approval_board.or_else(|| board.cloned()),
+        };
+
         // Create approval if board is specified
         let (approval_id, status) = if let Some(board_id) = approval_board {
             let approval = self.approval_manager.create_approval(
@@ -49,7 +69,7 @@ impl ConfigChangeManager {
             (None, ConfigChangeStatus::Proposed)
         };
 
-        let change = ConfigChange {
+        let mut change = ConfigChange {
             id: change_id,
             change_type,
             description,
@@ -60,12 +80,35 @@ impl ConfigChangeManager {
             after,
             applied_at: None,
             created_at: Utc::now(),
+            conflicts_with: Vec::new(),
         };
 
-        self.changes
-            .lock()
-            .unwrap()
-            .insert(change.id.clone(), change.clone());
+        let mut changes = self.changes.lock().unwrap();
+
+        // Flag open changes to the same entity on both sides
+        if let Some(entity_id) = changed_entity_id(&change) {
+            for other in changes.values_mut().filter(|c| {
+                c.change_type == change.change_type
+                    && is_open(c.status)
+                    && changed_entity_id(c).as_deref() == Some(entity_id.as_str())
+            }) {
+                other.conflicts_with.push(change.id.clone());
+                change.conflicts_with.push(other.id.clone());
+            }
+
+            if !change.conflicts_with.is_empty() {
+                tracing::warn!(
+                    "Config change {} to {:?} {} conflicts with open changes: {:?}",
+                    change.id.0,
+                    change.change_type,
+                    entity_id,
+                    change.conflicts_with
+                );
+            }
+        }
+
+        changes.insert(change.id.clone(), change.clone());
+        drop(changes);
 
         tracing::info!(
             "Proposed config change {}: {}",
@@ -97,13 +140,39 @@ impl ConfigChangeManager {
             .collect()
     }
 
-    /// Apply a config change (after approval if required)
-    pub fn apply_change(&self, change_id: &ConfigChangeId) -> Result<()> {
+    /// Apply a config change (after approval if required).
+    ///
+    /// `current` is the target entity's JSON as it is now, or `None` if it doesn't
+    /// exist. If the change recorded a `before` snapshot, it must still match, so a
+    /// change proposed against state that has since been modified is rejected.
+    pub fn apply_change(&self, change_id: &ConfigChangeId, current: Option<&str>) -> Result<()> {
         let mut changes = self.changes.lock().unwrap();
         let change = changes
             .get_mut(change_id)
             .ok_or_else(|| anyhow::anyhow!("Config change not found"))?;
 
+        self.check_applicable(change, current)?;
+
+        // Mark as applied
+        change.status = ConfigChangeStatus::Applied;
+        change.applied_at = Some(Utc::now());
+
+        tracing::info!("Applied config change {}: {}", change.id.0, change.description);
+
+        Ok(())
+    }
+
+    /// Run the checks `apply_change` would, without applying anything
+    pub fn check_apply(&self, change_id: &ConfigChangeId, current: Option<&str>) -> Result<()> {
+        let changes = self.changes.lock().unwrap();
+        let change = changes
+            .get(change_id)
+            .ok_or_else(|| anyhow::anyhow!("Config change not found"))?;
+
+        self.check_applicable(change, current)
+    }
+
+    fn check_applicable(&self, change: &ConfigChange, current: Option<&str>) -> Result<()> {
         // Check if approval is required
         if let Some(approval_id) = &change.approval_id {
             let approval = self
@@ -123,11 +192,14 @@ impl ConfigChangeManager {
             ));
         }
 
-        // Mark as applied
-        change.status = ConfigChangeStatus::Applied;
-        change.applied_at = Some(Utc::now());
-
-        tracing::info!("Applied config change {}: {}", change.id.0, change.description);
+        if let Some(before) = &change.before {
+            if !same_config(before, current) {
+                return Err(anyhow::anyhow!(
+                    "Config change {} conflicts: the target changed underneath you since it was proposed",
+                    change.id.0
+                ));
+            }
+        }
 
         Ok(())
     }
@@ -169,12 +241,119 @@ impl ConfigChangeManager {
 
         Ok(())
     }
+
+    /// Preview which active runs and routines a change would affect.
+    ///
+    /// `active_runs` pairs each in-flight run with the workflow it is executing.
+    /// Only role and policy changes are analysed; other change types report no impact.
+    pub fn preview_impact(
+        &self,
+        change_id: &ConfigChangeId,
+        active_runs: &[(RunId, WorkflowSpec)],
+        routines: &[Routine],
+    ) -> Result<ConfigChangeImpact> {
+        let change = self
+            .get_change(change_id)
+            .ok_or_else(|| anyhow::anyhow!("Config change not found"))?;
+
+        let entity_id = changed_entity_id(&change);
+
+        let mut affected_runs = Vec::new();
+        let mut affected_routines = Vec::new();
+
+        if let Some(entity_id) = &entity_id {
+            for (run_id, workflow) in active_runs {
+                if workflow_references(workflow, change.change_type, entity_id) {
+                    affected_runs.push(*run_id);
+                }
+            }
+
+            for routine in routines {
+                if workflow_references(&routine.workflow, change.change_type, entity_id) {
+                    affected_routines.push(routine.id.clone());
+                }
+            }
+        }
+
+        Ok(ConfigChangeImpact {
+            change_id: change.id,
+            change_type: change.change_type,
+            entity_id,
+            affected_runs,
+            affected_routines,
+        })
+    }
+}
+
+/// Downstream impact of a config change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigChangeImpact {
+    pub change_id: ConfigChangeId,
+    pub change_type: ConfigChangeType,
+    /// ID of the role/policy being changed, if it could be determined
+    pub entity_id: Option<String>,
+    pub affected_runs: Vec<RunId>,
+    pub affected_routines: Vec<RoutineId>,
+}
+
+fn is_open(status: ConfigChangeStatus) -> bool {
+    matches!(status, ConfigChangeStatus::Proposed | ConfigChangeStatus::PendingApproval)
+}
+
+/// Compare config snapshots as JSON, so formatting and key order don't matter
+fn same_config(before: &str, current: Option<&str>) -> bool {
+    let Some(current) = current else {
+        return false;
+    };
+    match (
+        serde_json::from_str::<serde_json::Value>(before),
+        serde_json::from_str::<serde_json::Value>(current),
+    ) {
+        (Ok(before), Ok(current)) => before == current,
+        _ => before == current,
+    }
+}
+
+/// Extract the `id` of the changed entity from the proposed (or previous) config
+pub fn changed_entity_id(change: &ConfigChange) -> Option<String> {
+    std::iter::once(change.after.as_str())
+        .chain(change.before.as_deref())
+        .find_map(config_entity_id)
+}
+
+/// The top-level `id` of a config snapshot, if it is JSON and has one
+pub fn config_entity_id(config: &str) -> Option<String> {
+    let value = serde_json::from_str::<serde_json::Value>(config).ok()?;
+    value.get("id").and_then(|id| id.as_str()).map(String::from)
+}
+
+/// Check whether a workflow depends on the given role or policy
+fn workflow_references(
+    workflow: &WorkflowSpec,
+    change_type: ConfigChangeType,
+    entity_id: &str,
+) -> bool {
+    workflow.steps.iter().any(|s| {
+        let step_references = match change_type {
+            ConfigChangeType::Role => s.role.0 == entity_id,
+            // Policies are evaluated on every tool call, so any step that can invoke tools is affected
+            ConfigChangeType::Policy => matches!(
+                s.action,
+                StepAction::AgentTask { .. } | StepAction::ToolSequence { .. }
+            ),
+            _ => false,
+        };
+
+        step_references
+            || matches!(&s.action, StepAction::SubWorkflow { workflow: child }
+                if workflow_references(child, change_type, entity_id))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{ApprovalBoard, PersonId, QuorumRule};
+    use crate::types::{ApprovalBoard, CapacitySourceId, PersonId, QuorumRule};
 
     fn create_test_approval_board() -> ApprovalBoard {
         ApprovalBoard {
@@ -247,7 +426,7 @@ mod tests {
             )
             .unwrap();
 
-        change_mgr.apply_change(&change.id).unwrap();
+        change_mgr.apply_change(&change.id, None).unwrap();
 
         let updated = change_mgr.get_change(&change.id).unwrap();
         assert_eq!(updated.status, ConfigChangeStatus::Applied);
@@ -274,7 +453,7 @@ mod tests {
             .unwrap();
 
         // Should fail - not yet approved
-        let result = change_mgr.apply_change(&change.id);
+        let result = change_mgr.apply_change(&change.id, None);
         assert!(result.is_err());
     }
 
@@ -330,7 +509,7 @@ mod tests {
             )
             .unwrap();
 
-        change_mgr.apply_change(&change2.id).unwrap();
+        change_mgr.apply_change(&change2.id, None).unwrap();
 
         let proposed = change_mgr.list_changes_by_status(ConfigChangeStatus::Proposed);
         assert_eq!(proposed.len(), 1);
@@ -338,4 +517,197 @@ mod tests {
         let applied = change_mgr.list_changes_by_status(ConfigChangeStatus::Applied);
         assert_eq!(applied.len(), 1);
     }
+
+    #[test]
+    fn test_concurrent_role_changes_conflict() {
+        let approval_mgr = Arc::new(ApprovalManager::new());
+        let change_mgr = ConfigChangeManager::new(approval_mgr);
+
+        let original = r#"{"id": "analyst", "name": "Analyst"}"#;
+        let propose = |after: &str| {
+            change_mgr
+                .propose_change(
+                    ConfigChangeType::Role,
+                    "Rename analyst".to_string(),
+                    Some(original.to_string()),
+                    after.to_string(),
+                    "admin".to_string(),
+                    None,
+                )
+                .unwrap()
+        };
+
+        let first = propose(r#"{"id": "analyst", "name": "Senior Analyst"}"#);
+        let second = propose(r#"{"id": "analyst", "name": "Lead Analyst"}"#);
+
+        // Both proposals are flagged against each other
+        assert_eq!(second.conflicts_with, vec![first.id.clone()]);
+        assert_eq!(
+            change_mgr.get_change(&first.id).unwrap().conflicts_with,
+            vec![second.id.clone()]
+        );
+
+        // A change to another role isn't a conflict
+        let other = change_mgr
+            .propose_change(
+                ConfigChangeType::Role,
+                "Rename engineer".to_string(),
+                None,
+                r#"{"id": "engineer", "name": "Engineer"}"#.to_string(),
+                "admin".to_string(),
+                None,
+            )
+            .unwrap();
+        assert!(other.conflicts_with.is_empty());
+
+        // Key order and whitespace don't matter when comparing snapshots
+        change_mgr
+            .apply_change(&first.id, Some(r#"{"name":"Analyst","id":"analyst"}"#))
+            .unwrap();
+
+        // The role now reflects the first change, so the second's `before` is stale
+        let result = change_mgr.apply_change(&second.id, Some(first.after.as_str()));
+        assert!(result.unwrap_err().to_string().contains("changed underneath you"));
+        assert_eq!(
+            change_mgr.get_change(&second.id).unwrap().status,
+            ConfigChangeStatus::Proposed
+        );
+    }
+
+    #[test]
+    fn test_role_change_impact_lists_routine() {
+        use crate::types::{RoleId, RoutineSchedule, StepId, StepSpec};
+
+        let approval_mgr = Arc::new(ApprovalManager::new());
+        let change_mgr = ConfigChangeManager::new(approval_mgr);
+
+        let workflow_for = |role: &str| WorkflowSpec {
+            steps: vec![StepSpec {
+                id: StepId::new("step1"),
+                name: "Step".to_string(),
+                description: None,
+                role: RoleId::new(role),
+                action: StepAction::AgentTask {
+                    prompt: "Do the thing".to_string(),
+                },
+                timeout_secs: None,
+                retry_policy: None,
+                requires_approval: false,
+            }],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+
+        let routine_for = |id: &str, role: &str| Routine {
+            id: RoutineId::new(id),
+            name: id.to_string(),
+            description: String::new(),
+            schedule: RoutineSchedule {
+                cron: "0 0 * * *".to_string(),
+                timezone: "UTC".to_string(),
+            },
+            workflow: workflow_for(role),
+            enabled: true,
+            last_run: None,
+            next_run: Utc::now(),
+            created_at: Utc::now(),
+            created_by: "admin".to_string(),
+            updated_at: Utc::now(),
+            tags: HashMap::new(),
+        };
+
+        let change = change_mgr
+            .propose_change(
+                ConfigChangeType::Role,
+                "Tighten analyst tools".to_string(),
+                None,
+                r#"{"id": "analyst", "name": "Analyst"}"#.to_string(),
+                "admin".to_string(),
+                None,
+            )
+            .unwrap();
+
+        let affected_run = RunId::new();
+        let active_runs = vec![
+            (affected_run, workflow_for("analyst")),
+            (RunId::new(), workflow_for("engineer")),
+        ];
+        let routines = vec![
+            routine_for("daily_report", "analyst"),
+            routine_for("nightly_build", "engineer"),
+        ];
+
+        let impact = change_mgr
+            .preview_impact(&change.id, &active_runs, &routines)
+            .unwrap();
+
+        assert_eq!(impact.entity_id.as_deref(), Some("analyst"));
+        assert_eq!(impact.affected_runs, vec![affected_run]);
+        assert_eq!(impact.affected_routines, vec![RoutineId::new("daily_report")]);
+    }
+
+    #[test]
+    fn test_proposals_routed_to_change_risk_boards() {
+        let approval_mgr = Arc::new(ApprovalManager::new());
+        for id in ["config_board", "exec_board", "test_board"] {
+            approval_mgr
+                .register_board(ApprovalBoard {
+                    id: ApprovalBoardId::new(id),
+                    ..create_test_approval_board()
+                })
+                .unwrap();
+        }
+        let policy = ChangeRiskPolicy {
+            production_capacity_sources: vec![CapacitySourceId::new("prod-anthropic")],
+            default_board: Some(ApprovalBoardId::new("config_board")),
+            high_risk_board: Some(ApprovalBoardId::new("exec_board")),
+            ..Default::default()
+        };
+        let change_mgr =
+            ConfigChangeManager::new(approval_mgr.clone()).with_change_risk_policy(policy);
+        let board_of = |change: &ConfigChange| {
+            let approval_id = change.approval_id.as_ref().unwrap();
+            approval_mgr.get_approval(approval_id).unwrap().board_id
+        };
+
+        // A low-risk change proposed without a board goes to the default board
+        let template = change_mgr
+            .propose_change(
+                ConfigChangeType::Template,
+                "Add template".to_string(),
+                None,
+                r#"{"id": "weekly_report"}"#.to_string(),
+                "admin".to_string(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(template.status, ConfigChangeStatus::PendingApproval);
+        assert_eq!(board_of(&template), ApprovalBoardId::new("config_board"));
+
+        // One naming its own board keeps it
+        let role = change_mgr
+            .propose_change(
+                ConfigChangeType::Role,
+                "Add role".to_string(),
+                None,
+                r#"{"id": "analyst"}"#.to_string(),
+                "admin".to_string(),
+                Some(ApprovalBoardId::new("test_board")),
+            )
+            .unwrap();
+        assert_eq!(board_of(&role), ApprovalBoardId::new("test_board"));
+
+        // A production capacity source change goes to the high-risk board regardless
+        let source = change_mgr
+            .propose_change(
+                ConfigChangeType::CapacitySource,
+                "Raise rate limit".to_string(),
+                Some(r#"{"id": "prod-anthropic", "rpm": 50}"#.to_string()),
+                r#"{"id": "prod-anthropic", "rpm": 500}"#.to_string(),
+                "admin".to_string(),
+                Some(ApprovalBoardId::new("test_board")),
+            )
+            .unwrap();
+        assert_eq!(board_of(&source), ApprovalBoardId::new("exec_board"));
+    }
 }
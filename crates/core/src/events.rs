@@ -20,6 +20,41 @@ impl Event {
             event_type,
         }
     }
+
+    /// Key identifying the logical event, `run_id:event_type[:step_id][:attempt]`, for
+    /// lifecycle events that happen at most once per run, step or attempt. Appending an
+    /// event whose key is already logged is a no-op, so retried writes aren't counted
+    /// twice. Events that can legitimately repeat have no key.
+    pub fn idempotency_key(&self) -> Option<String> {
+        let (kind, step_id, attempt) = match &self.event_type {
+            EventType::RunStarted { .. } => ("run_started", None, None),
+            EventType::RunCompleted { .. } => ("run_completed", None, None),
+            EventType::RunFailed { .. } => ("run_failed", None, None),
+            EventType::RunCancelled { .. } => ("run_cancelled", None, None),
+            EventType::StepScheduled { step_id, .. } => ("step_scheduled", Some(step_id), None),
+            EventType::StepStarted { step_id, attempt } => {
+                ("step_started", Some(step_id), Some(*attempt))
+            }
+            EventType::StepCompleted { step_id, .. } => ("step_completed", Some(step_id), None),
+            EventType::StepFailed {
+                step_id, attempt, ..
+            } => ("step_failed", Some(step_id), Some(*attempt)),
+            EventType::StepSkipped { step_id, .. } => ("step_skipped", Some(step_id), None),
+            EventType::StepWaiting {
+                step_id, attempt, ..
+            } => ("step_waiting", Some(step_id), Some(*attempt)),
+            _ => return None,
+        };
+
+        let mut key = format!("{}:{}", self.run_id, kind);
+        if let Some(step_id) = step_id {
+            key.push_str(&format!(":{}", step_id));
+        }
+        if let Some(attempt) = attempt {
+            key.push_str(&format!(":{}", attempt));
+        }
+        Some(key)
+    }
 }
 
 /// Types of events that can occur in the system
@@ -66,6 +101,18 @@ pub enum EventType {
         reason: String,
     },
 
+    // Signal events
+    StepWaiting {
+        step_id: StepId,
+        event_key: String,
+        attempt: u32,
+        timeout_secs: Option<u64>,
+    },
+    SignalReceived {
+        event_key: String,
+        payload: Option<serde_json::Value>,
+    },
+
     // Agent interaction events
     AgentMessage {
         step_id: StepId,
@@ -79,6 +126,12 @@ pub enum EventType {
         step_id: StepId,
         tool_id: String,
         parameters_hash: BlobHash,
+        /// Role and tier the call was checked under, so it can be replayed against
+        /// other policies. Missing from events recorded before they were captured.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        role_id: Option<RoleId>,
+        #[serde(default)]
+        tool_tier: u8,
     },
     ToolCallApproved {
         step_id: StepId,
@@ -122,6 +175,16 @@ pub enum EventType {
         content_hash: BlobHash,
         metadata: serde_json::Value,
     },
+    /// Captured output of a script step. `content` holds at most the executor's log
+    /// limit; the full log is in the blob `log_hash` when it was cut short.
+    StepOutput {
+        step_id: StepId,
+        attempt: u32,
+        stream: OutputStream,
+        content: String,
+        total_bytes: u64,
+        log_hash: Option<BlobHash>,
+    },
 
     // Configuration change events
     ConfigProposalCreated {
@@ -159,6 +222,81 @@ pub enum EventType {
     },
 }
 
+impl EventType {
+    /// Free-text fields that may quote secrets, such as script output and error messages
+    pub fn free_text_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            EventType::RunFailed { error, .. } | EventType::StepFailed { error, .. } => {
+                vec![error]
+            }
+            EventType::StepOutput { content, .. } => vec![content],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Output captured from one stream of a step attempt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepLog {
+    pub step_id: StepId,
+    pub attempt: u32,
+    pub stream: OutputStream,
+    pub content: String,
+    pub total_bytes: u64,
+    /// Whether `content` was cut short; the full log is in `log_hash`
+    pub truncated: bool,
+    pub log_hash: Option<BlobHash>,
+}
+
+/// Every captured step output in a run, in the order it was recorded
+pub fn step_logs(events: &[Event]) -> Vec<StepLog> {
+    events
+        .iter()
+        .filter_map(|event| match &event.event_type {
+            EventType::StepOutput {
+                step_id,
+                attempt,
+                stream,
+                content,
+                total_bytes,
+                log_hash,
+            } => Some(StepLog {
+                step_id: step_id.clone(),
+                attempt: *attempt,
+                stream: *stream,
+                content: content.clone(),
+                total_bytes: *total_bytes,
+                truncated: log_hash.is_some(),
+                log_hash: log_hash.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Steps parked on a `WaitForEvent` that no signal has released yet, with the
+/// event key each is waiting for
+pub fn waiting_steps(events: &[Event]) -> Vec<(StepId, String)> {
+    let mut waiting: Vec<(StepId, String)> = Vec::new();
+
+    for event in events {
+        match &event.event_type {
+            EventType::StepWaiting {
+                step_id, event_key, ..
+            } => waiting.push((step_id.clone(), event_key.clone())),
+            EventType::SignalReceived { event_key, .. } => {
+                waiting.retain(|(_, key)| key != event_key)
+            }
+            EventType::StepCompleted { step_id, .. } | EventType::StepFailed { step_id, .. } => {
+                waiting.retain(|(id, _)| id != step_id)
+            }
+            _ => {}
+        }
+    }
+
+    waiting
+}
+
 /// Direction of agent message
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -167,6 +305,14 @@ pub enum MessageDirection {
     FromAgent,
 }
 
+/// Output stream of a script step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
 /// Event log writer trait
 #[async_trait::async_trait]
 pub trait EventLog: Send + Sync {
@@ -183,4 +329,13 @@ pub trait EventLog: Send + Sync {
         start: DateTime<Utc>,
         end: DateTime<Utc>,
     ) -> anyhow::Result<Vec<Event>>;
+
+    /// Get a run's events past the first `after`, waiting up to `wait` for one to be
+    /// appended if there are none yet. Returns an empty list on timeout.
+    async fn tail_run_events(
+        &self,
+        run_id: RunId,
+        after: usize,
+        wait: std::time::Duration,
+    ) -> anyhow::Result<Vec<Event>>;
 }
@@ -9,6 +9,7 @@ pub mod organization;
 pub mod template;
 pub mod claude_compiler;
 pub mod capacity;
+pub mod clock;
 pub mod scheduler;
 pub mod approval;
 pub mod config_change;
@@ -19,6 +20,11 @@ pub mod cluster;
 pub mod secrets;
 pub mod audit;
 pub mod rbac;
+pub mod api_key;
+pub mod redaction;
+pub mod codec;
+pub mod query;
 pub mod compliance;
+pub mod validation;
 
 pub use types::*;
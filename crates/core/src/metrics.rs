@@ -1,13 +1,34 @@
-use chrono::{DateTime, Utc};
+use crate::clock::{Clock, SystemClock};
+use crate::validation::ValidationErrors;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 
+/// Width of a history bucket unless configured otherwise
+pub const DEFAULT_HISTORY_RESOLUTION_SECS: i64 = 60;
+
+/// History buckets kept per series unless configured otherwise (an hour at the default
+/// resolution)
+pub const DEFAULT_HISTORY_BUCKETS: usize = 60;
+
+/// Widest history bucket that can be configured (a day)
+pub const MAX_HISTORY_RESOLUTION_SECS: i64 = 86_400;
+
+/// Most history buckets that can be kept per series
+pub const MAX_HISTORY_BUCKETS: usize = 10_000;
+
 /// Metrics collector for system observability
 pub struct MetricsCollector {
     counters: Arc<Mutex<HashMap<String, Counter>>>,
     gauges: Arc<Mutex<HashMap<String, Gauge>>>,
     histograms: Arc<Mutex<HashMap<String, Histogram>>>,
+    // Recent counter and gauge values per metric key, one point per bucket
+    history: Arc<Mutex<HashMap<String, MetricSeries>>>,
+    history_resolution: Duration,
+    history_buckets: usize,
+    clock: Arc<dyn Clock>,
 }
 
 /// Counter - monotonically increasing value
@@ -28,6 +49,34 @@ pub struct Gauge {
     pub last_updated: DateTime<Utc>,
 }
 
+/// Kind of metric a history series was recorded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+/// Value of a metric at the end of a bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricPoint {
+    /// Start of the bucket
+    pub timestamp: DateTime<Utc>,
+    /// Last value recorded in the bucket; cumulative for counters
+    pub value: f64,
+}
+
+/// Recent values of one metric and label set, oldest first. Buckets in which the
+/// metric wasn't updated have no point.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricSeries {
+    pub name: String,
+    pub kind: MetricKind,
+    pub labels: HashMap<String, String>,
+    pub resolution_secs: i64,
+    pub points: VecDeque<MetricPoint>,
+}
+
 /// Histogram - tracks distribution of values
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Histogram {
@@ -47,9 +96,27 @@ impl MetricsCollector {
             counters: Arc::new(Mutex::new(HashMap::new())),
             gauges: Arc::new(Mutex::new(HashMap::new())),
             histograms: Arc::new(Mutex::new(HashMap::new())),
+            history: Arc::new(Mutex::new(HashMap::new())),
+            history_resolution: Duration::seconds(DEFAULT_HISTORY_RESOLUTION_SECS),
+            history_buckets: DEFAULT_HISTORY_BUCKETS,
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Keep the last `buckets` values of each counter and gauge, one per `resolution`.
+    /// Memory per series is bounded by `buckets`; zero disables history.
+    pub fn with_history(mut self, resolution: Duration, buckets: usize) -> Self {
+        self.history_resolution = resolution.max(Duration::seconds(1));
+        self.history_buckets = buckets;
+        self
+    }
+
+    /// Use `clock` for update times and history buckets
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Increment a counter
     pub fn increment_counter(&self, name: &str, labels: HashMap<String, String>) {
         self.increment_counter_by(name, 1, labels);
@@ -60,18 +127,19 @@ impl MetricsCollector {
         let mut counters = self.counters.lock().unwrap();
         let key = Self::metric_key(name, &labels);
 
-        counters
+        let counter = counters
             .entry(key.clone())
             .and_modify(|c| {
                 c.value += value;
-                c.last_updated = Utc::now();
+                c.last_updated = self.clock.now();
             })
             .or_insert_with(|| Counter {
                 name: name.to_string(),
                 value,
                 labels,
-                last_updated: Utc::now(),
+                last_updated: self.clock.now(),
             });
+        self.record_history(&key, MetricKind::Counter, name, &counter.labels, counter.value as f64);
     }
 
     /// Set a gauge value
@@ -79,18 +147,19 @@ impl MetricsCollector {
         let mut gauges = self.gauges.lock().unwrap();
         let key = Self::metric_key(name, &labels);
 
-        gauges
+        let gauge = gauges
             .entry(key.clone())
             .and_modify(|g| {
                 g.value = value;
-                g.last_updated = Utc::now();
+                g.last_updated = self.clock.now();
             })
             .or_insert_with(|| Gauge {
                 name: name.to_string(),
                 value,
                 labels,
-                last_updated: Utc::now(),
+                last_updated: self.clock.now(),
             });
+        self.record_history(&key, MetricKind::Gauge, name, &gauge.labels, gauge.value);
     }
 
     /// Increment a gauge
@@ -98,18 +167,19 @@ impl MetricsCollector {
         let mut gauges = self.gauges.lock().unwrap();
         let key = Self::metric_key(name, &labels);
 
-        gauges
+        let gauge = gauges
             .entry(key.clone())
             .and_modify(|g| {
                 g.value += delta;
-                g.last_updated = Utc::now();
+                g.last_updated = self.clock.now();
             })
             .or_insert_with(|| Gauge {
                 name: name.to_string(),
                 value: delta,
                 labels,
-                last_updated: Utc::now(),
+                last_updated: self.clock.now(),
             });
+        self.record_history(&key, MetricKind::Gauge, name, &gauge.labels, gauge.value);
     }
 
     /// Decrement a gauge
@@ -134,7 +204,7 @@ impl MetricsCollector {
                         h.counts[i] += 1;
                     }
                 }
-                h.last_updated = Utc::now();
+                h.last_updated = self.clock.now();
             })
             .or_insert_with(|| {
                 // Default buckets: 0.01, 0.1, 0.5, 1, 5, 10, 30, 60, 120, 300
@@ -155,7 +225,7 @@ impl MetricsCollector {
                     sum: value,
                     count: 1,
                     labels,
-                    last_updated: Utc::now(),
+                    last_updated: self.clock.now(),
                 }
             });
     }
@@ -193,11 +263,126 @@ impl MetricsCollector {
         self.histograms.lock().unwrap().get(&key).cloned()
     }
 
+    /// History of every series of the counter or gauge `name`, from the bucket
+    /// containing `since` (or as far back as is kept). With a `resolution`, which must
+    /// be a multiple of the configured one, buckets are merged to that width, keeping
+    /// the last value of each.
+    pub fn get_history(
+        &self,
+        name: &str,
+        since: Option<DateTime<Utc>>,
+        resolution: Option<Duration>,
+    ) -> Result<Vec<MetricSeries>> {
+        let resolution = resolution.unwrap_or(self.history_resolution);
+        let step = self.history_resolution.num_seconds();
+        let secs = resolution.num_seconds();
+        if secs <= 0 || secs % step != 0 {
+            let mut errors = ValidationErrors::new();
+            errors.push(
+                "resolution",
+                format!("Resolution must be a positive multiple of {} seconds", step),
+            );
+            return Err(errors.into());
+        }
+        // Coarser than all the history kept there is only ever one bucket
+        let span = i64::try_from(self.history_buckets.max(1))
+            .ok()
+            .and_then(|buckets| step.checked_mul(buckets))
+            .unwrap_or(i64::MAX);
+        if secs > span {
+            let mut errors = ValidationErrors::new();
+            errors.push(
+                "resolution",
+                format!("Resolution must be at most the {} seconds of history kept", span),
+            );
+            return Err(errors.into());
+        }
+        let since = since.map(|since| bucket_start(since, resolution));
+
+        let history = self.history.lock().unwrap();
+        let mut keys: Vec<_> = history
+            .iter()
+            .filter(|(_, series)| series.name == name)
+            .map(|(key, _)| key)
+            .collect();
+        keys.sort();
+
+        Ok(keys
+            .into_iter()
+            .map(|key| {
+                let series = &history[key];
+                let mut points: VecDeque<MetricPoint> = VecDeque::new();
+                for point in &series.points {
+                    let timestamp = bucket_start(point.timestamp, resolution);
+                    if since.is_some_and(|since| timestamp < since) {
+                        continue;
+                    }
+                    match points.back_mut() {
+                        Some(last) if last.timestamp == timestamp => last.value = point.value,
+                        _ => points.push_back(MetricPoint {
+                            timestamp,
+                            value: point.value,
+                        }),
+                    }
+                }
+                MetricSeries {
+                    resolution_secs: secs,
+                    points,
+                    ..series.clone()
+                }
+            })
+            .collect())
+    }
+
     /// Reset all metrics
     pub fn reset(&self) {
         self.counters.lock().unwrap().clear();
         self.gauges.lock().unwrap().clear();
         self.histograms.lock().unwrap().clear();
+        self.history.lock().unwrap().clear();
+    }
+
+    /// Record a counter or gauge's new value in the current bucket of its series
+    fn record_history(
+        &self,
+        key: &str,
+        kind: MetricKind,
+        name: &str,
+        labels: &HashMap<String, String>,
+        value: f64,
+    ) {
+        if self.history_buckets == 0 {
+            return;
+        }
+
+        let timestamp = bucket_start(self.clock.now(), self.history_resolution);
+        let mut history = self.history.lock().unwrap();
+        let series = history.entry(key.to_string()).or_insert_with(|| MetricSeries {
+            name: name.to_string(),
+            kind,
+            labels: labels.clone(),
+            resolution_secs: self.history_resolution.num_seconds(),
+            points: VecDeque::new(),
+        });
+
+        match series.points.back_mut() {
+            Some(last) if last.timestamp == timestamp => last.value = value,
+            _ => series.points.push_back(MetricPoint { timestamp, value }),
+        }
+
+        // Drop buckets that have aged out, even if the series went quiet in between. A
+        // span too long to represent reaches back further than any point.
+        let oldest = i32::try_from(self.history_buckets - 1)
+            .ok()
+            .and_then(|older| self.history_resolution.checked_mul(older))
+            .and_then(|span| timestamp.checked_sub_signed(span));
+        while series
+            .points
+            .front()
+            .is_some_and(|point| oldest.is_some_and(|oldest| point.timestamp < oldest))
+        {
+            series.points.pop_front();
+        }
     }
 
     /// Generate a unique key for a metric with labels
@@ -219,6 +404,13 @@ impl MetricsCollector {
     }
 }
 
+/// Start of the `resolution`-wide bucket containing `at`
+fn bucket_start(at: DateTime<Utc>, resolution: Duration) -> DateTime<Utc> {
+    let secs = resolution.num_seconds().max(1);
+    let start = at.timestamp().div_euclid(secs) * secs;
+    DateTime::from_timestamp(start, 0).unwrap_or(at)
+}
+
 impl Default for MetricsCollector {
     fn default() -> Self {
         Self::new()
@@ -408,4 +600,69 @@ mod tests {
         assert_eq!(collector.get_counters().len(), 0);
         assert_eq!(collector.get_gauges().len(), 0);
     }
+
+    #[test]
+    fn test_history_buckets_values_over_time() {
+        use crate::clock::ManualClock;
+
+        let start = DateTime::from_timestamp(1_700_000_040, 0).unwrap();
+        let clock = Arc::new(ManualClock::new(start));
+        let collector = MetricsCollector::new()
+            .with_history(Duration::seconds(60), 3)
+            .with_clock(clock.clone());
+        let labels = HashMap::new();
+
+        // Two updates in the first bucket keep only the later value
+        collector.set_gauge("queue_depth", 4.0, labels.clone());
+        collector.set_gauge("queue_depth", 7.0, labels.clone());
+        collector.increment_counter("requests", labels.clone());
+        for value in [2.0, 5.0, 1.0] {
+            clock.advance(Duration::seconds(60));
+            collector.set_gauge("queue_depth", value, labels.clone());
+            collector.increment_counter("requests", labels.clone());
+        }
+
+        // Only the last three buckets are kept
+        let series = collector.get_history("queue_depth", None, None).unwrap();
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].kind, MetricKind::Gauge);
+        let values: Vec<_> = series[0].points.iter().map(|p| p.value).collect();
+        assert_eq!(values, vec![2.0, 5.0, 1.0]);
+        assert_eq!(series[0].points[0].timestamp.timestamp() % 60, 0);
+
+        let counts = collector.get_history("requests", None, None).unwrap();
+        let values: Vec<_> = counts[0].points.iter().map(|p| p.value).collect();
+        assert_eq!(values, vec![2.0, 3.0, 4.0]);
+
+        // Coarser resolutions merge buckets, and `since` drops earlier ones
+        let since = clock.now() - Duration::seconds(60);
+        let recent = collector.get_history("queue_depth", Some(since), None).unwrap();
+        assert_eq!(recent[0].points.len(), 2);
+        let coarse = collector
+            .get_history("queue_depth", None, Some(Duration::seconds(120)))
+            .unwrap();
+        assert_eq!(coarse[0].resolution_secs, 120);
+        let values: Vec<_> = coarse[0].points.iter().map(|p| p.value).collect();
+        assert_eq!(values, vec![2.0, 1.0]);
+
+        assert!(collector
+            .get_history("queue_depth", None, Some(Duration::seconds(90)))
+            .is_err());
+        // Wider than the three minutes kept
+        let err = collector
+            .get_history("queue_depth", None, Some(Duration::seconds(240)))
+            .unwrap_err();
+        assert!(err.is::<ValidationErrors>());
+    }
+
+    #[test]
+    fn test_history_with_huge_bucket_count() {
+        let collector = MetricsCollector::new().with_history(Duration::days(1), usize::MAX);
+        collector.set_gauge("queue_depth", 1.0, HashMap::new());
+        collector.set_gauge("queue_depth", 2.0, HashMap::new());
+
+        let series = collector.get_history("queue_depth", None, None).unwrap();
+        assert_eq!(series[0].points.len(), 1);
+        assert_eq!(series[0].points[0].value, 2.0);
+    }
 }
@@ -1,8 +1,10 @@
 // Organization management
 
 use crate::types::{Organization, Person, PersonId, Team, TeamId};
+use crate::validation::ValidationErrors;
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
 
 /// Organization manager for validating and querying org structures
 pub struct OrganizationManager {
@@ -16,76 +18,125 @@ impl OrganizationManager {
         Ok(Self { org })
     }
 
-    /// Validate the organization structure
-    fn validate(org: &Organization) -> Result<()> {
-        // Check that all team members exist as people
-        for team in &org.teams {
-            for member_id in &team.members {
-                if !org.people.iter().any(|p| &p.id == member_id) {
-                    anyhow::bail!("Team {} references non-existent person {}", team.id.0, member_id.0);
+    /// Validate the organization structure, reporting every problem found
+    pub fn validate(org: &Organization) -> Result<()> {
+        let mut errors = ValidationErrors::new();
+        let person_exists = |id: &PersonId| org.people.iter().any(|p| &p.id == id);
+        let team_exists = |id: &TeamId| org.teams.iter().any(|t| &t.id == id);
+
+        for (i, team) in org.teams.iter().enumerate() {
+            // Check that all team members exist as people
+            for (j, member_id) in team.members.iter().enumerate() {
+                if !person_exists(member_id) {
+                    errors.push(
+                        format!("teams[{}].members[{}]", i, j),
+                        format!(
+                            "Team {} references non-existent person {}",
+                            team.id.0, member_id.0
+                        ),
+                    );
                 }
             }
 
             // Check team lead exists
             if let Some(lead_id) = &team.lead {
-                if !org.people.iter().any(|p| &p.id == lead_id) {
-                    anyhow::bail!("Team {} has non-existent lead {}", team.id.0, lead_id.0);
+                if !person_exists(lead_id) {
+                    errors.push(
+                        format!("teams[{}].lead", i),
+                        format!("Team {} has non-existent lead {}", team.id.0, lead_id.0),
+                    );
                 }
             }
 
             // Check parent team exists
             if let Some(parent_id) = &team.parent_team {
-                if !org.teams.iter().any(|t| &t.id == parent_id) {
-                    anyhow::bail!("Team {} has non-existent parent team {}", team.id.0, parent_id.0);
+                if !team_exists(parent_id) {
+                    errors.push(
+                        format!("teams[{}].parent_team", i),
+                        format!("Team {} has non-existent parent team {}", team.id.0, parent_id.0),
+                    );
                 }
             }
         }
 
         // Check that all people reference valid teams
-        for person in &org.people {
-            if !org.teams.iter().any(|t| t.id == person.team) {
-                anyhow::bail!("Person {} references non-existent team {}", person.id.0, person.team.0);
+        for (i, person) in org.people.iter().enumerate() {
+            if !team_exists(&person.team) {
+                errors.push(
+                    format!("people[{}].team", i),
+                    format!(
+                        "Person {} references non-existent team {}",
+                        person.id.0, person.team.0
+                    ),
+                );
             }
 
             // Check that reports_to exists
             if let Some(manager_id) = &person.reports_to {
-                if !org.people.iter().any(|p| &p.id == manager_id) {
-                    anyhow::bail!("Person {} reports to non-existent person {}", person.id.0, manager_id.0);
+                if !person_exists(manager_id) {
+                    errors.push(
+                        format!("people[{}].reports_to", i),
+                        format!(
+                            "Person {} reports to non-existent person {}",
+                            person.id.0, manager_id.0
+                        ),
+                    );
                 }
             }
         }
 
         // Check root team exists
-        if !org.teams.iter().any(|t| t.id == org.org_chart.root_team) {
-            anyhow::bail!("Org chart references non-existent root team {}", org.org_chart.root_team.0);
+        if !team_exists(&org.org_chart.root_team) {
+            errors.push(
+                "org_chart.root_team",
+                format!(
+                    "Org chart references non-existent root team {}",
+                    org.org_chart.root_team.0
+                ),
+            );
         }
 
         // Check for cycles in reporting structure
-        Self::check_reporting_cycles(&org.people)?;
+        errors.extend(Self::check_reporting_cycles(&org.people));
 
-        Ok(())
+        errors.into_result()
     }
 
-    /// Check for cycles in the reporting structure
-    fn check_reporting_cycles(people: &[Person]) -> Result<()> {
+    /// Check for cycles in the reporting structure, reporting each cycle once
+    fn check_reporting_cycles(people: &[Person]) -> ValidationErrors {
         let reporting: HashMap<PersonId, PersonId> = people
             .iter()
             .filter_map(|p| p.reports_to.as_ref().map(|m| (p.id.clone(), m.clone())))
             .collect();
 
+        let mut errors = ValidationErrors::new();
+        let mut in_reported_cycle = HashSet::new();
         for person in people {
-            let mut visited = std::collections::HashSet::new();
+            let mut visited = HashSet::new();
             let mut current = person.id.clone();
 
             while let Some(manager) = reporting.get(&current) {
                 if !visited.insert(current.clone()) {
-                    anyhow::bail!("Reporting cycle detected involving person {}", person.id.0);
+                    // `current` is on the cycle; walk it once to record its members
+                    if in_reported_cycle.insert(current.clone()) {
+                        let mut member = reporting[&current].clone();
+                        while member != current {
+                            in_reported_cycle.insert(member.clone());
+                            member = reporting[&member].clone();
+                        }
+                        let index = people.iter().position(|p| p.id == current).unwrap_or(0);
+                        errors.push(
+                            format!("people[{}].reports_to", index),
+                            format!("Reporting cycle detected involving person {}", current.0),
+                        );
+                    }
+                    break;
                 }
                 current = manager.clone();
             }
         }
 
-        Ok(())
+        errors
     }
 
     /// Get a person by ID
@@ -161,6 +212,137 @@ impl OrganizationManager {
     pub fn organization(&self) -> &Organization {
         &self.org
     }
+
+    /// Render the org chart as a Graphviz DOT digraph: teams are nested clusters,
+    /// people are nodes, and edges run from manager to report
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph {} {{", dot_id(&self.org.id.0)).unwrap();
+        writeln!(out, "  label={};", dot_id(&self.org.name)).unwrap();
+        writeln!(out, "  node [shape=box];").unwrap();
+        for team in self.child_teams(None) {
+            self.write_dot_team(&mut out, team, 1);
+        }
+        for (manager, report) in self.reporting_edges() {
+            writeln!(out, "  {} -> {};", dot_id(&manager.0), dot_id(&report.0)).unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn write_dot_team(&self, out: &mut String, team: &Team, depth: usize) {
+        let indent = "  ".repeat(depth);
+        writeln!(out, "{}subgraph {} {{", indent, dot_id(&format!("cluster_{}", team.id.0)))
+            .unwrap();
+        writeln!(out, "{}  label={};", indent, dot_id(&team.name)).unwrap();
+        for person in self.org.people.iter().filter(|p| p.team == team.id) {
+            writeln!(out, "{}  {} [label={}];", indent, dot_id(&person.id.0), dot_id(&person.name))
+                .unwrap();
+        }
+        for child in self.child_teams(Some(&team.id)) {
+            self.write_dot_team(out, child, depth + 1);
+        }
+        writeln!(out, "{}}}", indent).unwrap();
+    }
+
+    /// Render the org chart as a Mermaid flowchart with the same layout as `to_dot`.
+    /// Mermaid IDs are restricted, so nodes are numbered and the real IDs only appear
+    /// in labels.
+    pub fn to_mermaid(&self) -> String {
+        let person_ids: HashMap<&PersonId, String> = self
+            .org
+            .people
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (&p.id, format!("p{}", i)))
+            .collect();
+
+        let mut out = String::from("flowchart TD\n");
+        for team in self.child_teams(None) {
+            self.write_mermaid_team(&mut out, team, &person_ids, 1);
+        }
+        for (manager, report) in self.reporting_edges() {
+            writeln!(out, "  {} --> {}", person_ids[manager], person_ids[report]).unwrap();
+        }
+        out
+    }
+
+    fn write_mermaid_team(
+        &self,
+        out: &mut String,
+        team: &Team,
+        person_ids: &HashMap<&PersonId, String>,
+        depth: usize,
+    ) {
+        let indent = "  ".repeat(depth);
+        let index = self.org.teams.iter().position(|t| t.id == team.id).unwrap_or_default();
+        writeln!(out, "{}subgraph t{}[{}]", indent, index, mermaid_label(&team.name)).unwrap();
+        for person in self.org.people.iter().filter(|p| p.team == team.id) {
+            writeln!(out, "{}  {}[{}]", indent, person_ids[&person.id], mermaid_label(&person.name))
+                .unwrap();
+        }
+        for child in self.child_teams(Some(&team.id)) {
+            self.write_mermaid_team(out, child, person_ids, depth + 1);
+        }
+        writeln!(out, "{}end", indent).unwrap();
+    }
+
+    /// Teams directly under `parent`; with `None`, the top-level teams
+    fn child_teams<'a>(&'a self, parent: Option<&'a TeamId>) -> impl Iterator<Item = &'a Team> {
+        self.org.teams.iter().filter(move |t| t.parent_team.as_ref() == parent)
+    }
+
+    /// `(manager, report)` pairs from the reporting structure between known people,
+    /// sorted so rendered output is stable
+    fn reporting_edges(&self) -> Vec<(&PersonId, &PersonId)> {
+        let mut edges: Vec<_> = self
+            .org
+            .org_chart
+            .reporting_structure
+            .iter()
+            .filter(|(report, manager)| {
+                self.get_person(report).is_some() && self.get_person(manager).is_some()
+            })
+            .map(|(report, manager)| (manager, report))
+            .collect();
+        edges.sort_by(|a, b| (&a.0 .0, &a.1 .0).cmp(&(&b.0 .0, &b.1 .0)));
+        edges
+    }
+}
+
+/// Quote a DOT identifier or label, escaping characters that would end the string
+fn dot_id(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quote a Mermaid node label, using entity codes for characters Mermaid treats as syntax
+fn mermaid_label(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("#quot;"),
+            '#' => quoted.push_str("#35;"),
+            '<' => quoted.push_str("#lt;"),
+            '>' => quoted.push_str("#gt;"),
+            '\n' | '\r' => quoted.push(' '),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
 }
 
 #[cfg(test)]
@@ -298,6 +480,33 @@ mod tests {
         assert_eq!(chain[2].id, PersonId::new("ceo"));
     }
 
+    #[test]
+    fn test_render_chart_as_dot() {
+        let mut org = create_test_org();
+        org.people[2].name = "Eng \"One\"".to_string();
+        let manager = OrganizationManager::new(org).unwrap();
+
+        let dot = manager.to_dot();
+        assert!(dot.starts_with("digraph \"test_org\" {"));
+        assert!(dot.trim_end().ends_with('}'));
+        assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+
+        // Engineering is nested inside the executive cluster
+        let exec = dot.find("subgraph \"cluster_executive\"").unwrap();
+        let eng = dot.find("subgraph \"cluster_engineering\"").unwrap();
+        assert!(exec < eng);
+        assert!(dot.contains("\"eng1\" [label=\"Eng \\\"One\\\"\"];"));
+
+        let edges: Vec<_> = dot.lines().filter(|l| l.contains("->")).map(str::trim).collect();
+        assert_eq!(edges, vec!["\"ceo\" -> \"cto\";", "\"cto\" -> \"eng1\";"]);
+
+        let mermaid = manager.to_mermaid();
+        assert!(mermaid.starts_with("flowchart TD"));
+        assert!(mermaid.contains("p2[\"Eng #quot;One#quot;\"]"));
+        assert!(mermaid.contains("p0 --> p1"));
+        assert!(mermaid.contains("p1 --> p2"));
+    }
+
     #[test]
     fn test_cycle_detection() {
         let mut org = create_test_org();
@@ -308,4 +517,20 @@ mod tests {
         let result = OrganizationManager::new(org);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_validation_reports_all_problems() {
+        let mut org = create_test_org();
+        org.people[0].reports_to = Some(PersonId::new("eng1"));
+        org.teams[1].members.push(PersonId::new("ghost"));
+        org.people[2].team = TeamId::new("missing");
+
+        let err = OrganizationManager::new(org).err().unwrap();
+        let errors = err.downcast_ref::<ValidationErrors>().unwrap();
+        let fields: Vec<_> = errors.errors().iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["teams[1].members[1]", "people[2].team", "people[0].reports_to"]
+        );
+    }
 }
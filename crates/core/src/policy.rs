@@ -1,14 +1,19 @@
 // Policy engine for governance and authorization
 
-use crate::types::{ConfigDiff, PolicyId, PolicyRule, PolicySpec, RoleId, RoleSpec};
+use crate::types::{
+    ApprovalBoardId, CapacitySourceId, ConfigChangeType, ConfigDiff, PolicyId, PolicyRule,
+    PolicySpec, RoleId, RoleSpec, RunId, StepId,
+};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 /// Policy decision
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
 pub enum PolicyDecision {
     Allow,
     Deny { reason: String },
@@ -16,13 +21,37 @@ pub enum PolicyDecision {
 }
 
 /// Budget usage for a role
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BudgetUsage {
     pub tokens_used: u64,
     pub cost_cents: u64,
     pub last_reset: DateTime<Utc>,
 }
 
+impl BudgetUsage {
+    /// Usage that counts against today's budget, zeroed if the last reset was on an
+    /// earlier day
+    pub fn as_of(&self, now: DateTime<Utc>) -> Self {
+        if self.last_reset.date_naive() < now.date_naive() {
+            Self {
+                tokens_used: 0,
+                cost_cents: 0,
+                last_reset: now,
+            }
+        } else {
+            self.clone()
+        }
+    }
+
+    /// When daily budgets next reset: the following UTC midnight
+    pub fn next_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+        (now.date_naive() + chrono::Days::new(1))
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+}
+
 /// Policy context for evaluation
 #[derive(Debug, Clone)]
 pub struct PolicyContext {
@@ -33,11 +62,148 @@ pub struct PolicyContext {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Check made while evaluating a tool call, in evaluation order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluationCheck {
+    RoleTools,
+    Budget,
+    ApprovalRequirement,
+    PolicyRule,
+}
+
+/// Outcome of one check in a tool call evaluation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluationStep {
+    pub check: EvaluationCheck,
+    pub decision: PolicyDecision,
+    /// Policy the evaluated rule belongs to, for policy rule checks
+    pub policy_id: Option<PolicyId>,
+    pub rule: Option<PolicyRule>,
+    /// Pattern, domain or tool that triggered a non-allow decision
+    pub matched: Option<String>,
+}
+
+impl EvaluationStep {
+    fn new(check: EvaluationCheck, decision: PolicyDecision) -> Self {
+        Self {
+            check,
+            decision,
+            policy_id: None,
+            rule: None,
+            matched: None,
+        }
+    }
+}
+
+/// Risk level of a config change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeRisk {
+    Low,
+    High,
+}
+
+/// Rules deciding how risky a config change is and who must approve it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangeRiskPolicy {
+    /// Approvers for low-risk changes
+    pub default_approvers: Vec<String>,
+    /// Approvers for high-risk changes
+    pub high_risk_approvers: Vec<String>,
+    /// Diffs touching more entities than this are high-risk
+    pub max_low_risk_changes: usize,
+    /// Capacity sources serving production; any change to one is high-risk
+    pub production_capacity_sources: Vec<CapacitySourceId>,
+    /// Whether removing a policy is high-risk regardless of diff size
+    pub policy_removal_is_high_risk: bool,
+    /// Board that approves low-risk config changes proposed without one
+    pub default_board: Option<ApprovalBoardId>,
+    /// Board that approves every high-risk config change, whichever board it names
+    pub high_risk_board: Option<ApprovalBoardId>,
+}
+
+impl Default for ChangeRiskPolicy {
+    fn default() -> Self {
+        Self {
+            default_approvers: vec!["ceo".to_string(), "cto".to_string()],
+            high_risk_approvers: vec![
+                "ceo".to_string(),
+                "cto".to_string(),
+                "security_lead".to_string(),
+            ],
+            max_low_risk_changes: 5,
+            production_capacity_sources: vec![],
+            policy_removal_is_high_risk: true,
+            default_board: None,
+            high_risk_board: None,
+        }
+    }
+}
+
+impl ChangeRiskPolicy {
+    /// Classify a diff
+    pub fn assess(&self, diff: &ConfigDiff) -> ChangeRisk {
+        let removes_policy = self.policy_removal_is_high_risk && !diff.policies_removed.is_empty();
+        let touches_production = diff
+            .capacity_source_ids()
+            .any(|id| self.production_capacity_sources.contains(id));
+
+        if removes_policy || touches_production || diff.change_count() > self.max_low_risk_changes {
+            ChangeRisk::High
+        } else {
+            ChangeRisk::Low
+        }
+    }
+
+    /// Classify a change to the single entity `entity_id`, which is high-risk only if
+    /// it is a production capacity source
+    pub fn assess_entity(
+        &self,
+        change_type: ConfigChangeType,
+        entity_id: Option<&str>,
+    ) -> ChangeRisk {
+        let touches_production = change_type == ConfigChangeType::CapacitySource
+            && entity_id
+                .is_some_and(|id| self.production_capacity_sources.iter().any(|s| s.0 == id));
+        if touches_production {
+            ChangeRisk::High
+        } else {
+            ChangeRisk::Low
+        }
+    }
+
+    /// Approvers required at a risk level
+    pub fn approvers(&self, risk: ChangeRisk) -> &[String] {
+        match risk {
+            ChangeRisk::Low => &self.default_approvers,
+            ChangeRisk::High => &self.high_risk_approvers,
+        }
+    }
+
+    /// Board configured for a risk level
+    pub fn board(&self, risk: ChangeRisk) -> Option<&ApprovalBoardId> {
+        match risk {
+            ChangeRisk::Low => self.default_board.as_ref(),
+            ChangeRisk::High => self.high_risk_board.as_ref(),
+        }
+    }
+}
+
 /// Policy engine trait
 #[async_trait::async_trait]
 pub trait PolicyEngine: Send + Sync {
     /// Check if a tool call is allowed for a role
-    async fn check_tool_call(&self, context: &PolicyContext) -> Result<PolicyDecision>;
+    async fn check_tool_call(&self, context: &PolicyContext) -> Result<PolicyDecision> {
+        Ok(self.check_tool_call_explained(context).await?.0)
+    }
+
+    /// Check a tool call, also returning each check made up to the decision, in order
+    async fn check_tool_call_explained(
+        &self,
+        context: &PolicyContext,
+    ) -> Result<(PolicyDecision, Vec<EvaluationStep>)>;
 
     /// Check if a configuration change is allowed
     async fn check_config_change(
@@ -72,6 +238,7 @@ pub struct InMemoryPolicyEngine {
     policies: Arc<RwLock<HashMap<PolicyId, PolicySpec>>>,
     roles: Arc<RwLock<HashMap<RoleId, RoleSpec>>>,
     budget_usage: Arc<RwLock<HashMap<RoleId, BudgetUsage>>>,
+    change_risk_policy: ChangeRiskPolicy,
 }
 
 impl InMemoryPolicyEngine {
@@ -80,9 +247,32 @@ impl InMemoryPolicyEngine {
             policies: Arc::new(RwLock::new(HashMap::new())),
             roles: Arc::new(RwLock::new(HashMap::new())),
             budget_usage: Arc::new(RwLock::new(HashMap::new())),
+            change_risk_policy: ChangeRiskPolicy::default(),
         }
     }
 
+    /// Decide config change approvers with `policy` instead of the defaults
+    pub fn with_change_risk_policy(mut self, policy: ChangeRiskPolicy) -> Self {
+        self.change_risk_policy = policy;
+        self
+    }
+
+    /// Start with `roles` and `policies` loaded
+    pub fn with_specs(mut self, roles: Vec<RoleSpec>, policies: Vec<PolicySpec>) -> Self {
+        self.roles = Arc::new(RwLock::new(roles.into_iter().map(|r| (r.id.clone(), r)).collect()));
+        self.policies = Arc::new(RwLock::new(
+            policies.into_iter().map(|p| (p.id.clone(), p)).collect(),
+        ));
+        self
+    }
+
+    /// Replace the loaded roles and policies, dropping any not listed. Recorded budget
+    /// usage is kept.
+    pub async fn reload(&self, roles: Vec<RoleSpec>, policies: Vec<PolicySpec>) {
+        *self.roles.write().await = roles.into_iter().map(|r| (r.id.clone(), r)).collect();
+        *self.policies.write().await = policies.into_iter().map(|p| (p.id.clone(), p)).collect();
+    }
+
     /// Check if a role is allowed to use a specific tool
     async fn check_role_tool_permission(
         &self,
@@ -108,26 +298,28 @@ impl InMemoryPolicyEngine {
         role: &RoleSpec,
         tool_id: &str,
         tool_tier: u8,
-    ) -> PolicyDecision {
+    ) -> (PolicyDecision, Option<String>) {
         // Check tool-specific approval requirements
         if role.requires_approval_for.contains(&tool_id.to_string()) {
-            return PolicyDecision::RequiresApproval {
+            let decision = PolicyDecision::RequiresApproval {
                 approvers: vec!["ceo".to_string(), "cto".to_string()], // TODO: configurable
             };
+            return (decision, Some(tool_id.to_string()));
         }
 
         // Check tier-based approval requirements
         let tier_str = format!("tier{}", tool_tier);
         if role.requires_approval_for.contains(&tier_str) {
-            return PolicyDecision::RequiresApproval {
+            let decision = PolicyDecision::RequiresApproval {
                 approvers: vec!["ceo".to_string()],
             };
+            return (decision, Some(tier_str));
         }
 
-        PolicyDecision::Allow
+        (PolicyDecision::Allow, None)
     }
 
-    /// Check budget limits
+    /// Check budget limits: hard limits deny, soft limits require approval
     async fn check_budget_limits(&self, role: &RoleSpec, role_id: &RoleId) -> PolicyDecision {
         let usage_map = self.budget_usage.read().await;
         let usage = usage_map.get(role_id).cloned().unwrap_or_default();
@@ -135,100 +327,243 @@ impl InMemoryPolicyEngine {
         // Check if we need to reset (new day)
         let now = Utc::now();
         let should_reset = usage.last_reset.date_naive() < now.date_naive();
+        if should_reset {
+            return PolicyDecision::Allow;
+        }
 
-        if !should_reset {
-            // Check daily token limit
-            if let Some(limit) = role.budgets.daily_tokens {
-                if usage.tokens_used >= limit {
-                    return PolicyDecision::Deny {
-                        reason: format!(
-                            "Daily token budget exceeded: {} / {}",
-                            usage.tokens_used, limit
-                        ),
-                    };
-                }
+        let budgets = &role.budgets;
+
+        // Check daily token limit
+        if let Some(limit) = budgets.hard_token_limit() {
+            if usage.tokens_used >= limit {
+                return PolicyDecision::Deny {
+                    reason: format!(
+                        "Daily token budget exceeded: {} / {}",
+                        usage.tokens_used, limit
+                    ),
+                };
             }
+        }
 
-            // Check daily cost limit
-            if let Some(limit) = role.budgets.daily_cost_cents {
-                if usage.cost_cents >= limit {
-                    return PolicyDecision::Deny {
-                        reason: format!(
-                            "Daily cost budget exceeded: {} / {} cents",
-                            usage.cost_cents, limit
-                        ),
-                    };
-                }
+        // Check daily cost limit
+        if let Some(limit) = budgets.hard_cost_limit() {
+            if usage.cost_cents >= limit {
+                return PolicyDecision::Deny {
+                    reason: format!(
+                        "Daily cost budget exceeded: {} / {} cents",
+                        usage.cost_cents, limit
+                    ),
+                };
             }
         }
 
+        let over_soft_tokens = budgets.soft_daily_tokens.is_some_and(|l| usage.tokens_used >= l);
+        let over_soft_cost = budgets.soft_daily_cost_cents.is_some_and(|l| usage.cost_cents >= l);
+        if over_soft_tokens || over_soft_cost {
+            let approvers = if budgets.soft_limit_approvers.is_empty() {
+                self.change_risk_policy.default_approvers.clone()
+            } else {
+                budgets.soft_limit_approvers.clone()
+            };
+            return PolicyDecision::RequiresApproval { approvers };
+        }
+
         PolicyDecision::Allow
     }
 
-    /// Evaluate policy rules against a context
-    async fn evaluate_policy_rules(&self, context: &PolicyContext) -> PolicyDecision {
+    /// Evaluate policy rules against a context, in policy ID order, recording a step per
+    /// rule until one denies or requires approval
+    async fn evaluate_policy_rules(
+        &self,
+        context: &PolicyContext,
+        steps: &mut Vec<EvaluationStep>,
+    ) -> PolicyDecision {
         let policies = self.policies.read().await;
+        let mut policies: Vec<&PolicySpec> = policies.values().collect();
+        policies.sort_by(|a, b| a.id.0.cmp(&b.id.0));
 
-        for policy in policies.values() {
+        for policy in policies {
             for rule in &policy.rules {
-                match rule {
-                    PolicyRule::DenyPath { patterns } => {
-                        // Check if any parameter values match denied patterns
-                        if let Some(obj) = context.parameters.as_object() {
-                            for (key, value) in obj {
-                                if let Some(val_str) = value.as_str() {
-                                    for pattern in patterns {
-                                        if val_str.contains(pattern) {
-                                            return PolicyDecision::Deny {
-                                                reason: format!(
-                                                    "Path matches denied pattern '{}' in parameter '{}'",
-                                                    pattern, key
-                                                ),
-                                            };
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    PolicyRule::AllowDomain { domains } => {
-                        // For web fetch tools, check domain allowlist
-                        if context.tool_id == "web_fetch" {
-                            if let Some(url) = context.parameters.get("url").and_then(|v| v.as_str())
-                            {
-                                let is_allowed = domains.iter().any(|d| url.contains(d));
-                                if !is_allowed {
-                                    return PolicyDecision::Deny {
-                                        reason: format!(
-                                            "Domain not in allowlist. Allowed domains: {}",
-                                            domains.join(", ")
-                                        ),
-                                    };
-                                }
+                let (decision, matched) = evaluate_rule(rule, context);
+                steps.push(EvaluationStep {
+                    policy_id: Some(policy.id.clone()),
+                    rule: Some(rule.clone()),
+                    matched,
+                    ..EvaluationStep::new(EvaluationCheck::PolicyRule, decision.clone())
+                });
+
+                if decision != PolicyDecision::Allow {
+                    return decision;
+                }
+            }
+        }
+
+        PolicyDecision::Allow
+    }
+}
+
+/// Evaluate a single policy rule, returning the pattern, domain or tool behind a
+/// non-allow decision
+fn evaluate_rule(rule: &PolicyRule, context: &PolicyContext) -> (PolicyDecision, Option<String>) {
+    match rule {
+        PolicyRule::DenyPath { patterns } => {
+            // Check if any parameter values match denied patterns
+            if let Some(obj) = context.parameters.as_object() {
+                for (key, value) in obj {
+                    if let Some(val_str) = value.as_str() {
+                        for pattern in patterns {
+                            if val_str.contains(pattern) {
+                                let decision = PolicyDecision::Deny {
+                                    reason: format!(
+                                        "Path matches denied pattern '{}' in parameter '{}'",
+                                        pattern, key
+                                    ),
+                                };
+                                return (decision, Some(pattern.clone()));
                             }
                         }
                     }
-                    PolicyRule::RequireApproval { tool_ids } => {
-                        if tool_ids.contains(&context.tool_id) {
-                            return PolicyDecision::RequiresApproval {
-                                approvers: vec!["ceo".to_string()],
-                            };
-                        }
-                    }
-                    PolicyRule::EnforceEnvironment { environment } => {
-                        // Check environment-specific constraints
-                        // For now, this is a placeholder
-                        if environment == "production" {
-                            // Production might require additional checks
-                            // Could check for specific parameter patterns, etc.
-                        }
+                }
+            }
+        }
+        PolicyRule::AllowDomain { domains } => {
+            // For web fetch tools, check domain allowlist
+            if context.tool_id == "web_fetch" {
+                if let Some(url) = context.parameters.get("url").and_then(|v| v.as_str()) {
+                    let is_allowed = domains.iter().any(|d| url.contains(d));
+                    if !is_allowed {
+                        let decision = PolicyDecision::Deny {
+                            reason: format!(
+                                "Domain not in allowlist. Allowed domains: {}",
+                                domains.join(", ")
+                            ),
+                        };
+                        return (decision, Some(url.to_string()));
                     }
                 }
             }
         }
+        PolicyRule::DenyDomain { domains } => {
+            // Any tool that takes a URL, not just web fetches
+            if let Some(url) = context.parameters.get("url").and_then(|v| v.as_str()) {
+                let host = url_host(url);
+                if let Some(blocked) = domains.iter().find(|d| host_matches(&host, d)) {
+                    let decision = PolicyDecision::Deny {
+                        reason: format!("Domain '{}' is blocked by '{}'", host, blocked),
+                    };
+                    return (decision, Some(blocked.clone()));
+                }
+            }
+        }
+        PolicyRule::RequireApproval { tool_ids } => {
+            if tool_ids.contains(&context.tool_id) {
+                let decision = PolicyDecision::RequiresApproval {
+                    approvers: vec!["ceo".to_string()],
+                };
+                return (decision, Some(context.tool_id.clone()));
+            }
+        }
+        PolicyRule::EnforceEnvironment { environment } => {
+            // Check environment-specific constraints
+            // For now, this is a placeholder
+            if environment == "production" {
+                // Production might require additional checks
+                // Could check for specific parameter patterns, etc.
+            }
+        }
+    }
 
-        PolicyDecision::Allow
+    (PolicyDecision::Allow, None)
+}
+
+/// Lowercased host of a URL, without scheme, credentials, port or path
+fn url_host(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = host.split(':').next().unwrap_or_default();
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Whether `host` is `domain` or one of its subdomains
+fn host_matches(host: &str, domain: &str) -> bool {
+    let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+    host == domain || host.strip_suffix(&domain).is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// A tool call recorded during a run, replayable through a policy simulation
+#[derive(Debug, Clone)]
+pub struct RecordedToolCall {
+    pub run_id: RunId,
+    pub step_id: StepId,
+    pub context: PolicyContext,
+}
+
+/// A replayed tool call whose decision differs under the candidate policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecisionChange {
+    pub run_id: RunId,
+    pub step_id: StepId,
+    pub role_id: RoleId,
+    pub tool_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub current: PolicyDecision,
+    pub simulated: PolicyDecision,
+}
+
+/// Outcome of replaying recorded tool calls against a candidate policy
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicySimulation {
+    /// Calls replayed through both the current and candidate policies
+    pub evaluated: usize,
+    /// Calls skipped because their role no longer exists
+    pub skipped: usize,
+    pub changes: Vec<DecisionChange>,
+}
+
+/// Replay `calls` through engines loaded with `policies` as they are and with
+/// `candidate` added (replacing any policy with the same ID), reporting every call
+/// whose decision changes. Both engines start with no recorded usage, so budgets
+/// never decide the outcome, and the live engine is left untouched.
+pub async fn simulate_policy(
+    roles: Vec<RoleSpec>,
+    policies: Vec<PolicySpec>,
+    candidate: PolicySpec,
+    calls: &[RecordedToolCall],
+) -> Result<PolicySimulation> {
+    let current = InMemoryPolicyEngine::new();
+    current.load_roles(roles.clone()).await?;
+    current.load_policies(policies.clone()).await?;
+
+    let simulated = InMemoryPolicyEngine::new();
+    simulated.load_roles(roles).await?;
+    simulated.load_policies(policies).await?;
+    simulated.load_policies(vec![candidate]).await?;
+
+    let mut simulation = PolicySimulation::default();
+    for call in calls {
+        if current.get_role(&call.context.role_id).await?.is_none() {
+            simulation.skipped += 1;
+            continue;
+        }
+
+        let before = current.check_tool_call(&call.context).await?;
+        let after = simulated.check_tool_call(&call.context).await?;
+        simulation.evaluated += 1;
+        if before != after {
+            simulation.changes.push(DecisionChange {
+                run_id: call.run_id,
+                step_id: call.step_id.clone(),
+                role_id: call.context.role_id.clone(),
+                tool_id: call.context.tool_id.clone(),
+                timestamp: call.context.timestamp,
+                current: before,
+                simulated: after,
+            });
+        }
     }
+
+    Ok(simulation)
 }
 
 impl Default for InMemoryPolicyEngine {
@@ -239,40 +574,57 @@ impl Default for InMemoryPolicyEngine {
 
 #[async_trait::async_trait]
 impl PolicyEngine for InMemoryPolicyEngine {
-    async fn check_tool_call(&self, context: &PolicyContext) -> Result<PolicyDecision> {
+    async fn check_tool_call_explained(
+        &self,
+        context: &PolicyContext,
+    ) -> Result<(PolicyDecision, Vec<EvaluationStep>)> {
         // Get the role
         let roles = self.roles.read().await;
         let role = roles
             .get(&context.role_id)
             .context(format!("Role not found: {}", context.role_id.0))?;
 
+        let mut steps = Vec::new();
+
         // 1. Check role-based tool permissions
         let decision = self.check_role_tool_permission(role, &context.tool_id).await;
-        if matches!(decision, PolicyDecision::Deny { .. }) {
-            return Ok(decision);
+        let denied = matches!(decision, PolicyDecision::Deny { .. });
+        steps.push(EvaluationStep {
+            matched: denied.then(|| context.tool_id.clone()),
+            ..EvaluationStep::new(EvaluationCheck::RoleTools, decision.clone())
+        });
+        if denied {
+            return Ok((decision, steps));
         }
 
-        // 2. Check budget limits
+        // 2. Check budget limits. Past a soft limit the call still needs approval, but
+        // only once the checks below have had a chance to deny it outright.
         let decision = self.check_budget_limits(role, &context.role_id).await;
-        if matches!(decision, PolicyDecision::Deny { .. }) {
-            return Ok(decision);
-        }
+        steps.push(EvaluationStep::new(EvaluationCheck::Budget, decision.clone()));
+        let soft_limit = match decision {
+            PolicyDecision::Allow => None,
+            PolicyDecision::RequiresApproval { .. } => Some(decision),
+            PolicyDecision::Deny { .. } => return Ok((decision, steps)),
+        };
 
         // 3. Check approval requirements
-        let decision = self
+        let (decision, matched) = self
             .check_approval_requirement(role, &context.tool_id, context.tool_tier)
             .await;
+        steps.push(EvaluationStep {
+            matched,
+            ..EvaluationStep::new(EvaluationCheck::ApprovalRequirement, decision.clone())
+        });
         if matches!(decision, PolicyDecision::RequiresApproval { .. }) {
-            return Ok(decision);
+            return Ok((decision, steps));
         }
 
         // 4. Evaluate policy rules
-        let decision = self.evaluate_policy_rules(context).await;
-        if !matches!(decision, PolicyDecision::Allow) {
-            return Ok(decision);
+        let decision = self.evaluate_policy_rules(context, &mut steps).await;
+        match (decision, soft_limit) {
+            (PolicyDecision::Allow, Some(soft_limit)) => Ok((soft_limit, steps)),
+            (decision, _) => Ok((decision, steps)),
         }
-
-        Ok(PolicyDecision::Allow)
     }
 
     async fn check_config_change(
@@ -280,43 +632,15 @@ impl PolicyEngine for InMemoryPolicyEngine {
         _proposed_by: &str,
         diff: &ConfigDiff,
     ) -> Result<PolicyDecision> {
-        // For now, all config changes require approval
-        // In a real system, you might have admin roles that can approve their own changes
-
-        let has_changes = !diff.roles_added.is_empty()
-            || !diff.roles_modified.is_empty()
-            || !diff.roles_removed.is_empty()
-            || !diff.policies_added.is_empty()
-            || !diff.policies_modified.is_empty()
-            || !diff.policies_removed.is_empty();
-
-        if !has_changes {
+        // All config changes require approval; the risk policy decides by whom
+        if diff.change_count() == 0 {
             return Ok(PolicyDecision::Allow);
         }
 
-        // Count significant changes
-        let change_count = diff.roles_added.len()
-            + diff.roles_modified.len()
-            + diff.roles_removed.len()
-            + diff.policies_added.len()
-            + diff.policies_modified.len()
-            + diff.policies_removed.len();
-
-        if change_count > 5 {
-            // Large changes require multiple approvers
-            Ok(PolicyDecision::RequiresApproval {
-                approvers: vec![
-                    "ceo".to_string(),
-                    "cto".to_string(),
-                    "security_lead".to_string(),
-                ],
-            })
-        } else {
-            // Small changes require single approver
-            Ok(PolicyDecision::RequiresApproval {
-                approvers: vec!["ceo".to_string(), "cto".to_string()],
-            })
-        }
+        let risk = self.change_risk_policy.assess(diff);
+        Ok(PolicyDecision::RequiresApproval {
+            approvers: self.change_risk_policy.approvers(risk).to_vec(),
+        })
     }
 
     async fn record_usage(
@@ -377,7 +701,7 @@ impl PolicyEngine for InMemoryPolicyEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{RoleBudgets, RoleId};
+    use crate::types::{ProcessTemplate, RoleBudgets, RoleId, TemplateId, WorkflowSpec};
 
     #[tokio::test]
     async fn test_policy_engine_allow() {
@@ -392,8 +716,12 @@ mod tests {
             budgets: RoleBudgets {
                 daily_tokens: Some(100000),
                 daily_cost_cents: Some(1000),
+                ..Default::default()
             },
             requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
         };
 
         engine.load_roles(vec![role]).await.unwrap();
@@ -423,8 +751,12 @@ mod tests {
             budgets: RoleBudgets {
                 daily_tokens: None,
                 daily_cost_cents: None,
+                ..Default::default()
             },
             requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
         };
 
         engine.load_roles(vec![role]).await.unwrap();
@@ -454,8 +786,12 @@ mod tests {
             budgets: RoleBudgets {
                 daily_tokens: Some(1000),
                 daily_cost_cents: None,
+                ..Default::default()
             },
             requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
         };
 
         engine.load_roles(vec![role]).await.unwrap();
@@ -478,6 +814,77 @@ mod tests {
         assert!(matches!(decision, PolicyDecision::Deny { .. }));
     }
 
+    #[tokio::test]
+    async fn test_soft_and_hard_budget_limits() {
+        let engine = InMemoryPolicyEngine::new();
+        let role_id = RoleId::new("analyst");
+
+        let role = RoleSpec {
+            id: role_id.clone(),
+            name: "Analyst".to_string(),
+            description: "Data analyst role".to_string(),
+            prompt_template: "You are an analyst".to_string(),
+            allowed_tools: vec!["context_search".to_string()],
+            budgets: RoleBudgets {
+                soft_daily_tokens: Some(1000),
+                hard_daily_tokens: Some(2000),
+                soft_limit_approvers: vec!["finance_lead".to_string()],
+                ..Default::default()
+            },
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        };
+        engine.load_roles(vec![role]).await.unwrap();
+
+        let context = PolicyContext {
+            role_id: role_id.clone(),
+            tool_id: "context_search".to_string(),
+            tool_tier: 0,
+            parameters: serde_json::json!({"query": "test"}),
+            timestamp: Utc::now(),
+        };
+
+        // Below soft
+        engine.record_usage(&role_id, 500, 0).await.unwrap();
+        assert_eq!(engine.check_tool_call(&context).await.unwrap(), PolicyDecision::Allow);
+
+        // Between soft and hard, the role's approvers are asked
+        engine.record_usage(&role_id, 1000, 0).await.unwrap();
+        let decision = engine.check_tool_call(&context).await.unwrap();
+        assert_eq!(
+            decision,
+            PolicyDecision::RequiresApproval {
+                approvers: vec!["finance_lead".to_string()]
+            }
+        );
+
+        // unless a policy rule would deny the call anyway
+        let blocked = PolicyContext {
+            parameters: serde_json::json!({"query": "payroll/secrets"}),
+            ..context.clone()
+        };
+        engine
+            .load_policies(vec![PolicySpec {
+                id: PolicyId("no_secrets".to_string()),
+                name: "No Secrets".to_string(),
+                description: String::new(),
+                rules: vec![PolicyRule::DenyPath {
+                    patterns: vec!["secrets".to_string()],
+                }],
+            }])
+            .await
+            .unwrap();
+        let decision = engine.check_tool_call(&blocked).await.unwrap();
+        assert!(matches!(decision, PolicyDecision::Deny { .. }));
+
+        // Above hard
+        engine.record_usage(&role_id, 1000, 0).await.unwrap();
+        let decision = engine.check_tool_call(&context).await.unwrap();
+        assert!(matches!(decision, PolicyDecision::Deny { .. }));
+    }
+
     #[tokio::test]
     async fn test_policy_engine_requires_approval() {
         let engine = InMemoryPolicyEngine::new();
@@ -491,8 +898,12 @@ mod tests {
             budgets: RoleBudgets {
                 daily_tokens: None,
                 daily_cost_cents: None,
+                ..Default::default()
             },
             requires_approval_for: vec!["repo_write".to_string()],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
         };
 
         engine.load_roles(vec![role]).await.unwrap();
@@ -521,6 +932,12 @@ mod tests {
         let usage = engine.get_budget_usage(&role_id).await.unwrap();
         assert_eq!(usage.tokens_used, 1500);
         assert_eq!(usage.cost_cents, 75);
+
+        // Yesterday's usage no longer counts
+        let tomorrow = usage.last_reset + chrono::Duration::days(1);
+        assert_eq!(usage.as_of(tomorrow).tokens_used, 0);
+        assert_eq!(usage.as_of(usage.last_reset).tokens_used, 1500);
+        assert!(BudgetUsage::next_reset(usage.last_reset) > usage.last_reset);
     }
 
     #[tokio::test]
@@ -547,8 +964,12 @@ mod tests {
             budgets: RoleBudgets {
                 daily_tokens: None,
                 daily_cost_cents: None,
+                ..Default::default()
             },
             requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
         };
 
         engine.load_roles(vec![role]).await.unwrap();
@@ -563,5 +984,174 @@ mod tests {
 
         let decision = engine.check_tool_call(&context).await.unwrap();
         assert!(matches!(decision, PolicyDecision::Deny { .. }));
+
+        let (explained, steps) = engine.check_tool_call_explained(&context).await.unwrap();
+        assert_eq!(explained, decision);
+
+        let checks: Vec<_> = steps.iter().map(|s| s.check).collect();
+        assert_eq!(
+            checks,
+            vec![
+                EvaluationCheck::RoleTools,
+                EvaluationCheck::Budget,
+                EvaluationCheck::ApprovalRequirement,
+                EvaluationCheck::PolicyRule,
+            ]
+        );
+
+        let denial = steps.last().unwrap();
+        assert_eq!(denial.decision, decision);
+        assert_eq!(denial.policy_id, Some(PolicyId("no_secrets".to_string())));
+        assert_eq!(denial.matched.as_deref(), Some(".env"));
+        assert!(matches!(denial.rule, Some(PolicyRule::DenyPath { .. })));
+    }
+
+    #[test]
+    fn test_policy_rule_deny_domain() {
+        let rule = PolicyRule::DenyDomain {
+            domains: vec!["example.com".to_string()],
+        };
+        let call = |url: &str| PolicyContext {
+            role_id: RoleId::new("analyst"),
+            tool_id: "http_request".to_string(),
+            tool_tier: 0,
+            parameters: serde_json::json!({ "url": url }),
+            timestamp: Utc::now(),
+        };
+
+        for url in ["https://example.com/a", "http://user@API.Example.com:8080/x?y=1"] {
+            let (decision, matched) = evaluate_rule(&rule, &call(url));
+            assert!(matches!(decision, PolicyDecision::Deny { .. }), "{}", url);
+            assert_eq!(matched.as_deref(), Some("example.com"));
+        }
+
+        // Only whole labels match
+        let (decision, _) = evaluate_rule(&rule, &call("https://notexample.com/"));
+        assert_eq!(decision, PolicyDecision::Allow);
+    }
+
+    #[tokio::test]
+    async fn test_deny_domain_wins_over_allow_domain() {
+        let engine = InMemoryPolicyEngine::new();
+        // The allow rule's policy sorts first, so it is evaluated first
+        engine
+            .load_policies(vec![
+                PolicySpec {
+                    id: PolicyId("a_allow".to_string()),
+                    name: "Allow".to_string(),
+                    description: String::new(),
+                    rules: vec![PolicyRule::AllowDomain {
+                        domains: vec!["example.com".to_string()],
+                    }],
+                },
+                PolicySpec {
+                    id: PolicyId("b_deny".to_string()),
+                    name: "Deny".to_string(),
+                    description: String::new(),
+                    rules: vec![PolicyRule::DenyDomain {
+                        domains: vec!["internal.example.com".to_string()],
+                    }],
+                },
+            ])
+            .await
+            .unwrap();
+        engine
+            .load_roles(vec![RoleSpec {
+                id: RoleId::new("analyst"),
+                name: "Analyst".to_string(),
+                description: String::new(),
+                prompt_template: String::new(),
+                allowed_tools: vec!["web_fetch".to_string()],
+                budgets: RoleBudgets::default(),
+                requires_approval_for: vec![],
+                model_fallback: vec![],
+                max_concurrent: None,
+                claude_settings: None,
+            }])
+            .await
+            .unwrap();
+
+        let check = |url: &'static str| {
+            let engine = &engine;
+            async move {
+                let context = PolicyContext {
+                    role_id: RoleId::new("analyst"),
+                    tool_id: "web_fetch".to_string(),
+                    tool_tier: 0,
+                    parameters: serde_json::json!({ "url": url }),
+                    timestamp: Utc::now(),
+                };
+                engine.check_tool_call(&context).await.unwrap()
+            }
+        };
+
+        assert_eq!(check("https://docs.example.com/").await, PolicyDecision::Allow);
+        assert!(matches!(
+            check("https://internal.example.com/").await,
+            PolicyDecision::Deny { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_config_change_risk_selects_approvers() {
+        let policy = ChangeRiskPolicy {
+            default_approvers: vec!["eng_manager".to_string()],
+            high_risk_approvers: vec!["ceo".to_string(), "security_lead".to_string()],
+            production_capacity_sources: vec![CapacitySourceId::new("prod-anthropic")],
+            ..Default::default()
+        };
+        let engine = InMemoryPolicyEngine::new().with_change_risk_policy(policy);
+
+        // Removing a single policy is high-risk
+        let diff = ConfigDiff {
+            policies_removed: vec![PolicyId("no_secrets".to_string())],
+            ..Default::default()
+        };
+        assert_eq!(
+            engine.check_config_change("alice", &diff).await.unwrap(),
+            PolicyDecision::RequiresApproval {
+                approvers: vec!["ceo".to_string(), "security_lead".to_string()],
+            }
+        );
+
+        // So is touching a production capacity source
+        let diff = ConfigDiff {
+            capacity_sources_removed: vec![CapacitySourceId::new("prod-anthropic")],
+            ..Default::default()
+        };
+        assert_eq!(engine.change_risk_policy.assess(&diff), ChangeRisk::High);
+
+        // Adding a template only needs the default approvers
+        let diff = ConfigDiff {
+            templates_added: vec![ProcessTemplate {
+                id: TemplateId::new("code_review"),
+                name: "Code Review".to_string(),
+                description: "Review a pull request".to_string(),
+                category: "code_review".to_string(),
+                parameters: vec![],
+                workflow_template: WorkflowSpec {
+                    steps: vec![],
+                    dependencies: HashMap::new(),
+                    inputs: Vec::new(),
+                },
+                created_at: Utc::now(),
+                created_by: "alice".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            engine.check_config_change("alice", &diff).await.unwrap(),
+            PolicyDecision::RequiresApproval {
+                approvers: vec!["eng_manager".to_string()],
+            }
+        );
+
+        assert_eq!(
+            engine
+                .check_config_change("alice", &ConfigDiff::default())
+                .await
+                .unwrap(),
+            PolicyDecision::Allow
+        );
     }
 }
@@ -0,0 +1,475 @@
+// Uniform sorting, filtering and pagination for list endpoints
+
+use crate::validation::ValidationErrors;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Items per page when a request doesn't set a limit
+pub const DEFAULT_PAGE_LIMIT: usize = 100;
+
+/// Largest page a request may ask for; bigger limits are clamped to it
+pub const MAX_PAGE_LIMIT: usize = 1000;
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl std::str::FromStr for Order {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "asc" => Ok(Order::Asc),
+            "desc" => Ok(Order::Desc),
+            _ => Err(anyhow!("Invalid sort order '{}', expected 'asc' or 'desc'", s)),
+        }
+    }
+}
+
+/// Field to sort by. Nested fields use dotted paths, e.g. `budgets.daily_tokens`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortBy {
+    pub field: String,
+    pub order: Order,
+}
+
+/// Keep items whose field equals `value`. Array fields match if any element does.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Filter {
+    pub field: String,
+    pub value: String,
+}
+
+impl Filter {
+    fn matches(&self, item: &Value) -> bool {
+        match lookup(item, &self.field) {
+            Some(Value::Array(values)) => values.iter().any(|v| value_eq(v, &self.value)),
+            Some(value) => value_eq(value, &self.value),
+            None => false,
+        }
+    }
+}
+
+/// Sorting and filtering applied to a list of items, matched against their
+/// serialized field names
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuerySpec {
+    pub sort: Option<SortBy>,
+    pub filters: Vec<Filter>,
+}
+
+impl QuerySpec {
+    /// Parse `?sort=<field>&order=<asc|desc>&filter=<field>:<value>,...` parameters
+    pub fn parse(sort: Option<&str>, order: Option<&str>, filter: Option<&str>) -> Result<Self> {
+        let order = order.map(str::parse).transpose()?.unwrap_or_default();
+        let sort = sort.filter(|s| !s.is_empty()).map(|field| SortBy {
+            field: field.to_string(),
+            order,
+        });
+
+        let filters = filter
+            .unwrap_or_default()
+            .split(',')
+            .filter(|f| !f.is_empty())
+            .map(|f| {
+                let (field, value) = f
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("Invalid filter '{}', expected field:value", f))?;
+                Ok(Filter {
+                    field: field.trim().to_string(),
+                    value: value.trim().to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { sort, filters })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sort.is_none() && self.filters.is_empty()
+    }
+
+    /// Filter then sort `items`. Sorting is stable, and items missing the sort field
+    /// go last regardless of order.
+    pub fn apply<T: Serialize>(&self, items: Vec<T>) -> Result<Vec<T>> {
+        if self.is_empty() {
+            return Ok(items);
+        }
+
+        let keyed = self.filter_and_sort(items, self.sort.as_ref())?;
+        Ok(keyed.into_iter().map(|(_, item)| item).collect())
+    }
+
+    /// Filter and sort `items`, then return the requested page. Without a sort, items
+    /// are ordered by `id` so pages stay consistent as the list changes; only then is a
+    /// `next_cursor` offered, since other orders can shift under a cursor.
+    pub fn paginate<T: Serialize>(
+        &self,
+        items: Vec<T>,
+        page: &PageRequest,
+    ) -> Result<Paginated<T>> {
+        self.paginate_by(items, page, "id")
+    }
+
+    /// As `paginate`, for items identified by the field at `id_field` rather than `id`,
+    /// e.g. `request.id`
+    pub fn paginate_by<T: Serialize>(
+        &self,
+        items: Vec<T>,
+        page: &PageRequest,
+        id_field: &str,
+    ) -> Result<Paginated<T>> {
+        let by_id = SortBy {
+            field: id_field.to_string(),
+            order: Order::Asc,
+        };
+        let keyed = self.filter_and_sort(items, Some(self.sort.as_ref().unwrap_or(&by_id)))?;
+        let total = keyed.len();
+        let limit = page.limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+
+        let offset = match &page.cursor {
+            Some(cursor) => {
+                let position = keyed.iter().position(|(value, _)| {
+                    item_id(value, id_field).as_deref() == Some(cursor.as_str())
+                });
+                match (position, &self.sort) {
+                    (Some(position), _) => position + 1,
+                    // The cursor's item was removed; in ID order the page resumes at the
+                    // first item after where it was
+                    (None, None) => keyed
+                        .iter()
+                        .position(|(value, _)| follows_cursor(value, id_field, cursor))
+                        .unwrap_or(total),
+                    (None, Some(_)) => {
+                        let mut errors = ValidationErrors::new();
+                        errors.push(
+                            "cursor",
+                            format!("Cursor '{}' does not match any item in the list", cursor),
+                        );
+                        return Err(errors.into());
+                    }
+                }
+            }
+            None => page.offset.min(total),
+        };
+
+        let page_items: Vec<_> = keyed.into_iter().skip(offset).take(limit).collect();
+        let has_more = offset + page_items.len() < total;
+        let next_cursor = if has_more && self.sort.is_none() {
+            page_items.last().and_then(|(value, _)| item_id(value, id_field))
+        } else {
+            None
+        };
+
+        Ok(Paginated {
+            items: page_items.into_iter().map(|(_, item)| item).collect(),
+            total,
+            offset,
+            limit,
+            has_more,
+            next_cursor,
+        })
+    }
+
+    /// Serialize and filter `items`, then sort them by `sort` if given
+    fn filter_and_sort<T: Serialize>(
+        &self,
+        items: Vec<T>,
+        sort: Option<&SortBy>,
+    ) -> Result<Vec<(Value, T)>> {
+        let mut keyed = Vec::with_capacity(items.len());
+        for item in items {
+            let value = serde_json::to_value(&item)?;
+            if self.filters.iter().all(|f| f.matches(&value)) {
+                keyed.push((value, item));
+            }
+        }
+
+        if let Some(sort) = sort {
+            keyed.sort_by(|(a, _), (b, _)| {
+                match (lookup(a, &sort.field), lookup(b, &sort.field)) {
+                    (Some(a), Some(b)) => {
+                        let ordering = compare_values(a, b);
+                        match sort.order {
+                            Order::Asc => ordering,
+                            Order::Desc => ordering.reverse(),
+                        }
+                    }
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            });
+        }
+
+        Ok(keyed)
+    }
+}
+
+/// Which page of a list to return. A cursor, when given, takes precedence over `offset`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRequest {
+    #[serde(default)]
+    pub offset: usize,
+    /// Defaults to `DEFAULT_PAGE_LIMIT`
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// `next_cursor` from the previous page
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// One page of a list, with what's needed to fetch the rest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    /// Matching items across all pages
+    pub total: usize,
+    /// Position of the first item in `items` within the whole list
+    pub offset: usize,
+    pub limit: usize,
+    pub has_more: bool,
+    /// Pass as `cursor` to fetch the next page. Unlike an offset it keeps its place when
+    /// earlier items are added or removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// An item's ID field as a string, for cursors
+fn item_id(item: &Value, id_field: &str) -> Option<String> {
+    match lookup(item, id_field)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Whether an item sorts after `cursor` in ascending ID order. Items without an ID
+/// sort last, so they follow any cursor.
+fn follows_cursor(item: &Value, id_field: &str, cursor: &str) -> bool {
+    match lookup(item, id_field) {
+        Some(Value::Number(n)) => match (n.as_f64(), cursor.parse::<f64>()) {
+            (Some(id), Ok(cursor)) => id > cursor,
+            _ => false,
+        },
+        Some(id) => compare_values(id, &Value::String(cursor.to_string())) == Ordering::Greater,
+        None => true,
+    }
+}
+
+/// Look up a dotted field path, treating `null` as missing
+fn lookup<'a>(item: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(item, |value, key| value.get(key))
+        .filter(|value| !value.is_null())
+}
+
+fn value_eq(value: &Value, expected: &str) -> bool {
+    match value {
+        Value::String(s) => s.eq_ignore_ascii_case(expected),
+        Value::Number(n) => n.to_string() == expected,
+        Value::Bool(b) => b.to_string() == expected,
+        _ => false,
+    }
+}
+
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => {
+            // Timestamps serialize with varying fractional digits, so compare them as times
+            match (a.parse::<DateTime<Utc>>(), b.parse::<DateTime<Utc>>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                _ => a.cmp(b),
+            }
+        }
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Routine, RoutineId, RoutineSchedule, Run, RunId, RunStatus, WorkflowSpec};
+    use chrono::Duration;
+    use std::collections::HashMap;
+
+    fn run(work_item_id: &str, status: RunStatus, started_secs_ago: i64) -> Run {
+        Run {
+            id: RunId::new(),
+            work_item_id: work_item_id.to_string(),
+            status,
+            started_at: Utc::now() - Duration::seconds(started_secs_ago),
+            completed_at: None,
+            steps: vec![],
+            parent_run_id: None,
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        }
+    }
+
+    fn routine(id: &str, enabled: bool, created_secs_ago: i64) -> Routine {
+        Routine {
+            id: RoutineId::new(id),
+            name: id.to_string(),
+            description: String::new(),
+            schedule: RoutineSchedule {
+                cron: "0 * * * *".to_string(),
+                timezone: "UTC".to_string(),
+            },
+            workflow: WorkflowSpec {
+                steps: vec![],
+                dependencies: HashMap::new(),
+                inputs: Vec::new(),
+            },
+            enabled,
+            last_run: None,
+            next_run: Utc::now(),
+            created_at: Utc::now() - Duration::seconds(created_secs_ago),
+            created_by: "test".to_string(),
+            updated_at: Utc::now(),
+            tags: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_query_spec() {
+        let spec = QuerySpec::parse(Some("started_at"), Some("desc"), Some("status:failed,a.b:1"))
+            .unwrap();
+        assert_eq!(
+            spec.sort,
+            Some(SortBy {
+                field: "started_at".to_string(),
+                order: Order::Desc,
+            })
+        );
+        assert_eq!(spec.filters.len(), 2);
+        assert_eq!(spec.filters[1].field, "a.b");
+
+        assert!(QuerySpec::parse(None, None, None).unwrap().is_empty());
+        assert!(QuerySpec::parse(Some("started_at"), Some("sideways"), None).is_err());
+        assert!(QuerySpec::parse(None, None, Some("status")).is_err());
+    }
+
+    #[test]
+    fn test_sort_and_filter_runs() {
+        let runs = vec![
+            run("a", RunStatus::Failed, 30),
+            run("b", RunStatus::Completed, 20),
+            run("c", RunStatus::Failed, 10),
+        ];
+
+        let spec = QuerySpec::parse(Some("started_at"), Some("desc"), Some("status:failed"))
+            .unwrap();
+        let ids: Vec<_> = spec
+            .apply(runs.clone())
+            .unwrap()
+            .into_iter()
+            .map(|r| r.work_item_id)
+            .collect();
+        assert_eq!(ids, vec!["c", "a"]);
+
+        let spec = QuerySpec::parse(Some("work_item_id"), None, None).unwrap();
+        let ids: Vec<_> = spec
+            .apply(runs)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.work_item_id)
+            .collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_paginate_reports_total_and_has_more() {
+        let routines: Vec<_> = ["e", "c", "a", "d", "b"]
+            .into_iter()
+            .map(|id| routine(id, id != "d", 0))
+            .collect();
+        let spec = QuerySpec::default();
+        let page = |offset, cursor: Option<&str>| PageRequest {
+            offset,
+            limit: Some(2),
+            cursor: cursor.map(str::to_string),
+        };
+        let ids = |page: &Paginated<Routine>| -> Vec<String> {
+            page.items.iter().map(|r| r.id.0.clone()).collect()
+        };
+
+        // Unsorted lists are paged in ID order
+        let first = spec.paginate(routines.clone(), &page(0, None)).unwrap();
+        assert_eq!(ids(&first), vec!["a", "b"]);
+        assert_eq!((first.total, first.offset, first.has_more), (5, 0, true));
+        assert_eq!(first.next_cursor.as_deref(), Some("b"));
+
+        let last = spec.paginate(routines.clone(), &page(4, None)).unwrap();
+        assert_eq!(ids(&last), vec!["e"]);
+        assert!(!last.has_more);
+        assert_eq!(last.next_cursor, None);
+
+        // A cursor keeps its place when an earlier item is removed
+        let shrunk: Vec<_> = routines.iter().filter(|r| r.id.0 != "a").cloned().collect();
+        let second = spec.paginate(shrunk, &page(0, Some("b"))).unwrap();
+        assert_eq!(ids(&second), vec!["c", "d"]);
+        assert_eq!((second.total, second.offset, second.has_more), (4, 1, true));
+
+        // As does one whose own item was removed, resuming at the next ID
+        let deleted: Vec<_> = routines.iter().filter(|r| r.id.0 != "b").cloned().collect();
+        let resumed = spec.paginate(deleted, &page(0, Some("b"))).unwrap();
+        assert_eq!(ids(&resumed), vec!["c", "d"]);
+        assert_eq!(resumed.offset, 1);
+        let past_end = spec.paginate(routines.clone(), &page(0, Some("zzz"))).unwrap();
+        assert!(past_end.items.is_empty() && !past_end.has_more);
+
+        // Other orders can't seek past a missing cursor
+        let by_name = QuerySpec::parse(Some("name"), None, None).unwrap();
+        assert!(by_name.paginate(routines.clone(), &page(0, Some("zzz"))).is_err());
+
+        // Totals count filtered items only, and explicit sorts offer no cursor
+        let spec = QuerySpec::parse(Some("id"), Some("desc"), Some("enabled:true")).unwrap();
+        let filtered = spec.paginate(routines, &page(2, None)).unwrap();
+        assert_eq!(ids(&filtered), vec!["b", "a"]);
+        assert_eq!((filtered.total, filtered.has_more), (4, false));
+        assert_eq!(filtered.next_cursor, None);
+    }
+
+    #[test]
+    fn test_sort_and_filter_routines() {
+        let routines = vec![
+            routine("nightly", true, 10),
+            routine("weekly", false, 30),
+            routine("hourly", true, 20),
+        ];
+
+        let spec = QuerySpec::parse(Some("created_at"), Some("asc"), Some("enabled:true"))
+            .unwrap();
+        let ids: Vec<_> = spec
+            .apply(routines.clone())
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id.0)
+            .collect();
+        assert_eq!(ids, vec!["hourly", "nightly"]);
+
+        // Nested fields and missing values
+        let spec = QuerySpec::parse(Some("last_run"), None, Some("schedule.cron:0 * * * *"))
+            .unwrap();
+        assert_eq!(spec.apply(routines).unwrap().len(), 3);
+    }
+}
@@ -16,6 +16,8 @@ pub enum Resource {
     Organization,
     Template,
     AuditLog,
+    /// Capacity sources, usage and the request queue
+    Capacity,
     All,
 }
 
@@ -29,52 +31,135 @@ pub enum Action {
     Execute,
     Approve,
     Audit,
+    /// Act on a tenant's data on the tenant's behalf
+    Impersonate,
+    /// Overrule a process's normal outcome, e.g. force-resolving an approval
+    Override,
     All,
 }
 
+/// Which instances of a resource type a permission covers
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceSelector {
+    /// Every instance
+    #[default]
+    Any,
+    /// The instance with this ID, e.g. one approval board
+    Id(String),
+    /// Instances carrying this tag, e.g. `team:payments`. Run tags are written as by
+    /// `storage::index::run_tag_key`, so a colon in the tag's key is escaped.
+    Tag(String),
+}
+
+impl ResourceSelector {
+    /// Whether `target` is one of the selected instances
+    pub fn selects(&self, target: &ResourceTarget) -> bool {
+        match self {
+            ResourceSelector::Any => true,
+            ResourceSelector::Id(id) => &target.id == id,
+            ResourceSelector::Tag(tag) => target.tags.contains(tag),
+        }
+    }
+}
+
+/// The concrete resource a permission is checked against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceTarget {
+    pub id: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl ResourceTarget {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+}
+
 /// Fine-grained permission
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "PermissionFields")]
 pub struct Permission {
     pub resource: Resource,
     pub action: Action,
-    /// Optional resource ID for instance-level permissions
-    pub resource_id: Option<String>,
+    /// Instances the permission is limited to (default: all of them)
+    pub selector: ResourceSelector,
+}
+
+/// Serialized form of a permission, which may still use the `resource_id` field that
+/// preceded `selector`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PermissionFields {
+    resource: Resource,
+    action: Action,
+    #[serde(default)]
+    selector: Option<ResourceSelector>,
+    #[serde(default)]
+    resource_id: Option<String>,
+}
+
+impl TryFrom<PermissionFields> for Permission {
+    type Error = String;
+
+    fn try_from(fields: PermissionFields) -> Result<Self, Self::Error> {
+        let selector = match (fields.selector, fields.resource_id) {
+            (Some(_), Some(_)) => {
+                return Err("a permission takes either `selector` or `resource_id`".to_string())
+            }
+            (Some(selector), None) => selector,
+            (None, Some(id)) => ResourceSelector::Id(id),
+            (None, None) => ResourceSelector::Any,
+        };
+        Ok(Self::with_selector(fields.resource, fields.action, selector))
+    }
 }
 
 impl Permission {
     pub fn new(resource: Resource, action: Action) -> Self {
-        Self {
-            resource,
-            action,
-            resource_id: None,
-        }
+        Self::with_selector(resource, action, ResourceSelector::Any)
     }
 
     pub fn with_resource_id(resource: Resource, action: Action, resource_id: String) -> Self {
+        Self::with_selector(resource, action, ResourceSelector::Id(resource_id))
+    }
+
+    pub fn with_selector(resource: Resource, action: Action, selector: ResourceSelector) -> Self {
         Self {
             resource,
             action,
-            resource_id: Some(resource_id),
+            selector,
         }
     }
 
-    /// Check if this permission matches another (considering wildcards)
+    /// Check if this permission matches another (considering wildcards). A permission
+    /// limited to some instances only matches one limited the same way.
     pub fn matches(&self, other: &Permission) -> bool {
-        // Check resource match
-        let resource_match = self.resource == Resource::All
-            || self.resource == other.resource
-            || other.resource == Resource::All;
+        self.covers(other)
+            && (self.selector == ResourceSelector::Any || self.selector == other.selector)
+    }
 
-        // Check action match
-        let action_match = self.action == Action::All
-            || self.action == other.action
-            || other.action == Action::All;
+    /// Check if this permission grants `requested` on the concrete `target`
+    pub fn grants(&self, requested: &Permission, target: &ResourceTarget) -> bool {
+        self.covers(requested) && self.selector.selects(target)
+    }
 
-        // Check resource ID match
-        let resource_id_match = self.resource_id.is_none()
-            || self.resource_id == other.resource_id;
+    /// Whether the resource and action cover another's. A wildcard in `self` covers
+    /// anything, but a requested wildcard is only covered by a wildcard.
+    fn covers(&self, other: &Permission) -> bool {
+        let resource_match = self.resource == Resource::All || self.resource == other.resource;
+        let action_match = self.action == Action::All || self.action == other.action;
 
-        resource_match && action_match && resource_id_match
+        resource_match && action_match
     }
 }
 
@@ -110,6 +195,16 @@ impl RbacRole {
     pub fn has_permission(&self, permission: &Permission) -> bool {
         self.permissions.iter().any(|p| p.matches(permission))
     }
+
+    /// Whether the role grants `permission` on the concrete `target`
+    pub fn has_permission_on(&self, permission: &Permission, target: &ResourceTarget) -> bool {
+        self.permissions.iter().any(|p| p.grants(permission, target))
+    }
+
+    /// Whether the role grants `permission` on at least some instances of the resource
+    pub fn has_permission_on_some(&self, permission: &Permission) -> bool {
+        self.permissions.iter().any(|p| p.covers(permission))
+    }
 }
 
 /// User with role assignments
@@ -263,6 +358,28 @@ impl RbacManager {
 
     /// Check if user has permission
     pub fn check_permission(&self, user_id: &str, permission: &Permission) -> bool {
+        self.any_role(user_id, |role| role.has_permission(permission))
+    }
+
+    /// Check if user has permission on a concrete resource, e.g. voting on one approval
+    /// board, honouring permissions limited to some instances
+    pub fn check_permission_on(
+        &self,
+        user_id: &str,
+        permission: &Permission,
+        target: &ResourceTarget,
+    ) -> bool {
+        self.any_role(user_id, |role| role.has_permission_on(permission, target))
+    }
+
+    /// Check if user has permission on at least some instances of the resource. Callers
+    /// still check the concrete instance once they know it.
+    pub fn check_permission_on_some(&self, user_id: &str, permission: &Permission) -> bool {
+        self.any_role(user_id, |role| role.has_permission_on_some(permission))
+    }
+
+    /// Whether any of the user's roles satisfies `check`
+    fn any_role(&self, user_id: &str, check: impl Fn(&RbacRole) -> bool) -> bool {
         let users = self.users.lock().unwrap();
         let roles = self.roles.lock().unwrap();
 
@@ -272,15 +389,10 @@ impl RbacManager {
         };
 
         // Check all user's roles for the permission
-        for role_id in &user.roles {
-            if let Some(role) = roles.get(role_id) {
-                if role.has_permission(permission) {
-                    return true;
-                }
-            }
-        }
-
-        false
+        user.roles
+            .iter()
+            .filter_map(|role_id| roles.get(role_id))
+            .any(check)
     }
 
     /// Get all permissions for a user
@@ -357,6 +469,27 @@ pub fn create_system_roles() -> Vec<RbacRole> {
             role.add_permission(Permission::new(Resource::AuditLog, Action::Audit));
             role
         },
+        // Audit writer - components reporting events into the audit log
+        {
+            let mut role = RbacRole::new(
+                "audit_writer".to_string(),
+                "Audit Writer".to_string(),
+                "Submit audit events from external components".to_string(),
+            );
+            role.add_permission(Permission::new(Resource::AuditLog, Action::Create));
+            role
+        },
+        // Platform admin - support access to any tenant's data
+        {
+            let mut role = RbacRole::new(
+                "platform_admin".to_string(),
+                "Platform Admin".to_string(),
+                "Impersonate tenants for support".to_string(),
+            );
+            role.add_permission(Permission::new(Resource::Tenant, Action::Impersonate));
+            role.add_permission(Permission::new(Resource::All, Action::Read));
+            role
+        },
         // Viewer - read-only access
         {
             let mut role = RbacRole::new(
@@ -368,6 +501,7 @@ pub fn create_system_roles() -> Vec<RbacRole> {
             role.add_permission(Permission::new(Resource::Routine, Action::Read));
             role.add_permission(Permission::new(Resource::Template, Action::Read));
             role.add_permission(Permission::new(Resource::Organization, Action::Read));
+            role.add_permission(Permission::new(Resource::Capacity, Action::Read));
             role
         },
     ]
@@ -387,6 +521,10 @@ mod tests {
         assert!(perm1.matches(&perm2));
         assert!(perm3.matches(&perm1)); // All action matches specific action
         assert!(perm4.matches(&perm1)); // All resource matches specific resource
+        // ...but a narrower grant never satisfies a request for everything
+        assert!(!perm1.matches(&perm4));
+        assert!(!perm1.matches(&perm3));
+        assert!(!perm3.matches(&Permission::new(Resource::All, Action::All)));
     }
 
     #[test]
@@ -538,5 +676,93 @@ mod tests {
         let viewer_role = roles.iter().find(|r| r.id == "viewer").unwrap();
         assert!(viewer_role.has_permission(&Permission::new(Resource::Workflow, Action::Read)));
         assert!(!viewer_role.has_permission(&Permission::new(Resource::Workflow, Action::Delete)));
+
+        // Forcing an approval through is reserved for admins
+        let override_approval = Permission::new(Resource::Approval, Action::Override);
+        let overriders: Vec<_> = roles
+            .iter()
+            .filter(|r| r.has_permission(&override_approval))
+            .map(|r| r.id.as_str())
+            .collect();
+        assert_eq!(overriders, vec!["admin"]);
+    }
+
+    #[test]
+    fn test_permission_reads_legacy_resource_id() {
+        let legacy: Permission = serde_json::from_str(
+            r#"{"resource":"Approval","action":"Approve","resource_id":"security"}"#,
+        )
+        .unwrap();
+        let board = ResourceSelector::Id("security".to_string());
+        assert_eq!(legacy, Permission::with_selector(Resource::Approval, Action::Approve, board));
+
+        let unscoped: Permission =
+            serde_json::from_str(r#"{"resource":"Workflow","action":"Read","resource_id":null}"#)
+                .unwrap();
+        assert_eq!(unscoped.selector, ResourceSelector::Any);
+
+        // Written back in the current shape, which reads the same
+        let json = serde_json::to_value(&legacy).unwrap();
+        assert_eq!(json["selector"], serde_json::json!({ "id": "security" }));
+        assert_eq!(serde_json::from_value::<Permission>(json).unwrap(), legacy);
+
+        // Conflicting or misspelled fields are rejected rather than widening the grant
+        assert!(serde_json::from_str::<Permission>(
+            r#"{"resource":"Approval","action":"Approve","resource_id":"a","selector":"any"}"#
+        )
+        .is_err());
+        assert!(serde_json::from_str::<Permission>(
+            r#"{"resource":"Approval","action":"Approve","resource_ids":["security"]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_permission_scoped_to_one_board() {
+        let manager = RbacManager::new();
+
+        let mut role = RbacRole::new(
+            "security_approver".to_string(),
+            "Security Approver".to_string(),
+            "Votes on the security board".to_string(),
+        );
+        role.add_permission(Permission::with_resource_id(
+            Resource::Approval,
+            Action::Approve,
+            "security_board".to_string(),
+        ));
+        role.add_permission(Permission::with_selector(
+            Resource::Workflow,
+            Action::Read,
+            ResourceSelector::Tag("team:payments".to_string()),
+        ));
+        manager.register_role(role).unwrap();
+
+        let user = RbacUser::new(
+            "user1".to_string(),
+            "testuser".to_string(),
+            "test@example.com".to_string(),
+        );
+        manager.register_user(user).unwrap();
+        manager.assign_role("user1", "security_approver").unwrap();
+
+        let vote = Permission::new(Resource::Approval, Action::Approve);
+        let board = |id: &str| ResourceTarget::new(id);
+        assert!(manager.check_permission_on("user1", &vote, &board("security_board")));
+        assert!(!manager.check_permission_on("user1", &vote, &board("finance_board")));
+        // Voting on one board doesn't grant voting on boards in general
+        assert!(!manager.check_permission("user1", &vote));
+        // ...but lets the user reach the voting routes at all
+        assert!(manager.check_permission_on_some("user1", &vote));
+        assert!(!manager.check_permission_on_some(
+            "user1",
+            &Permission::new(Resource::Secret, Action::Read)
+        ));
+
+        let read = Permission::new(Resource::Workflow, Action::Read);
+        let payments_run =
+            ResourceTarget::new("run-1").with_tags(vec!["team:payments".to_string()]);
+        assert!(manager.check_permission_on("user1", &read, &payments_run));
+        assert!(!manager.check_permission_on("user1", &read, &ResourceTarget::new("run-2")));
     }
 }
@@ -0,0 +1,257 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// Replacement written in place of a redacted value
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Prefixes of well-known API key and token formats
+pub const DEFAULT_SECRET_PREFIXES: &[&str] = &[
+    "sk-", "sk_live_", "pk_live_", "ghp_", "gho_", "github_pat_", "xoxb-", "xoxp-", "AKIA",
+];
+
+/// Minimum number of characters after a prefix for a token to be treated as a secret,
+/// so ordinary words such as `sk-learn` are left alone
+const MIN_SECRET_BODY_LEN: usize = 8;
+
+/// Shortest stored secret value that is redacted. Stored values are matched anywhere in
+/// the text, so a short one such as `admin` or `8080` would blank out unrelated output.
+const MIN_STORED_SECRET_LEN: usize = 12;
+
+/// Configuration for redacting secrets from persisted events and audit entries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Tokens starting with any of these prefixes are redacted
+    #[serde(default = "default_prefixes")]
+    pub prefixes: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_prefixes() -> Vec<String> {
+    DEFAULT_SECRET_PREFIXES.iter().map(|p| p.to_string()).collect()
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            prefixes: default_prefixes(),
+        }
+    }
+}
+
+/// Replaces secret-looking tokens with [`REDACTED`].
+///
+/// A token is redacted if it starts with a configured prefix. The value of a stored
+/// secret is redacted wherever it appears, whatever characters it contains. Callers only
+/// apply it to free-text fields such as script output and error messages, never to IDs
+/// or hashes.
+#[derive(Clone)]
+pub struct Redactor {
+    config: RedactionConfig,
+    // Plaintexts of stored secrets with the number of secrets holding each, longest
+    // first so one containing another is redacted whole
+    secrets: Arc<RwLock<Vec<(String, usize)>>>,
+}
+
+impl Redactor {
+    pub fn new(config: RedactionConfig) -> Self {
+        Self {
+            config,
+            secrets: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// Register the plaintext of a stored secret so it is redacted
+    pub fn register_secret(&self, value: impl Into<String>) {
+        let value = value.into();
+        if value.len() < MIN_STORED_SECRET_LEN {
+            return;
+        }
+
+        let mut secrets = self.secrets.write().unwrap();
+        match secrets.iter_mut().find(|(s, _)| *s == value) {
+            Some((_, holders)) => *holders += 1,
+            None => {
+                secrets.push((value, 1));
+                secrets.sort_by_key(|(s, _)| std::cmp::Reverse(s.len()));
+            }
+        }
+    }
+
+    /// Stop redacting the plaintext of a deleted or rotated secret, unless another
+    /// stored secret still holds the same value
+    pub fn unregister_secret(&self, value: &str) {
+        let mut secrets = self.secrets.write().unwrap();
+        if let Some(i) = secrets.iter().position(|(s, _)| s == value) {
+            secrets[i].1 -= 1;
+            if secrets[i].1 == 0 {
+                secrets.remove(i);
+            }
+        }
+    }
+
+    /// Redact secret tokens and stored secret values in `text`, preserving everything
+    /// around them
+    pub fn redact(&self, text: &str) -> String {
+        if !self.config.enabled {
+            return text.to_string();
+        }
+
+        let mut text = text.to_string();
+        for (secret, _) in self.secrets.read().unwrap().iter() {
+            if text.contains(secret.as_str()) {
+                text = text.replace(secret.as_str(), REDACTED);
+            }
+        }
+
+        let text = text.as_str();
+        let mut result = String::with_capacity(text.len());
+        let mut token_start = None;
+
+        for (i, c) in text.char_indices() {
+            if is_token_char(c) {
+                token_start.get_or_insert(i);
+            } else {
+                if let Some(start) = token_start.take() {
+                    self.push_token(&mut result, &text[start..i]);
+                }
+                result.push(c);
+            }
+        }
+        if let Some(start) = token_start {
+            self.push_token(&mut result, &text[start..]);
+        }
+
+        result
+    }
+
+    /// Redact each of `fields` in place
+    pub fn redact_fields<'a>(&self, fields: impl IntoIterator<Item = &'a mut String>) {
+        for field in fields {
+            *field = self.redact(field);
+        }
+    }
+
+    fn push_token(&self, result: &mut String, token: &str) {
+        if self.is_secret(token) {
+            result.push_str(REDACTED);
+        } else {
+            result.push_str(token);
+        }
+    }
+
+    fn is_secret(&self, token: &str) -> bool {
+        self.config.prefixes.iter().any(|prefix| {
+            token.len() >= prefix.len() + MIN_SECRET_BODY_LEN && token.starts_with(prefix.as_str())
+        })
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(RedactionConfig::default())
+    }
+}
+
+/// Characters that can appear inside an API key or token. Separators such as `=`, `:`
+/// and `/` end a token so `KEY=value` and URLs are matched on the value alone.
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_prefixed_tokens() {
+        let redactor = Redactor::default();
+
+        let redacted = redactor.redact("Use key sk-abc123def456ghi789 to call the API.");
+        assert_eq!(redacted, "Use key [REDACTED] to call the API.");
+
+        // Short prefixed words are not secrets
+        assert_eq!(redactor.redact("Train with sk-learn"), "Train with sk-learn");
+    }
+
+    #[test]
+    fn test_redacts_registered_secret_values() {
+        let redactor = Redactor::default();
+        redactor.register_secret("hunter2-database-pw");
+
+        let redacted = redactor.redact("password=hunter2-database-pw; user=admin");
+        assert_eq!(redacted, "password=[REDACTED]; user=admin");
+    }
+
+    #[test]
+    fn test_redacts_stored_secrets_with_separator_characters() {
+        use crate::events::{Event, EventType};
+        use crate::types::RunId;
+
+        let redactor = Redactor::default();
+        let dsn = "postgres://app:s3cret@db:5432/main";
+        redactor.register_secret("ab+cd/ef==gh/ij");
+        redactor.register_secret(dsn);
+
+        let mut event = Event::new(
+            RunId::new(),
+            EventType::RunFailed {
+                error: format!("Key ab+cd/ef==gh/ij rejected connecting to {}", dsn),
+                duration_secs: 1,
+            },
+        );
+        redactor.redact_fields(event.event_type.free_text_mut());
+        match event.event_type {
+            EventType::RunFailed { error, .. } => {
+                assert_eq!(error, "Key [REDACTED] rejected connecting to [REDACTED]")
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_short_and_unregistered_secrets_left_alone() {
+        let redactor = Redactor::default();
+        redactor.register_secret("admin");
+        assert_eq!(redactor.redact("user=admin"), "user=admin");
+
+        // Two secrets share a value; it stays redacted until both are gone
+        redactor.register_secret("correct-horse-battery");
+        redactor.register_secret("correct-horse-battery");
+        redactor.unregister_secret("correct-horse-battery");
+        assert_eq!(redactor.redact("pw correct-horse-battery"), "pw [REDACTED]");
+        redactor.unregister_secret("correct-horse-battery");
+        assert_eq!(
+            redactor.redact("pw correct-horse-battery"),
+            "pw correct-horse-battery"
+        );
+    }
+
+    #[test]
+    fn test_custom_prefixes_and_disabled() {
+        let redactor = Redactor::new(RedactionConfig {
+            enabled: true,
+            prefixes: vec!["acme_".to_string()],
+        });
+        assert_eq!(redactor.redact("token acme_0123456789"), "token [REDACTED]");
+        assert_eq!(
+            redactor.redact("token sk-abc123def456ghi789"),
+            "token sk-abc123def456ghi789"
+        );
+
+        let disabled = Redactor::new(RedactionConfig {
+            enabled: false,
+            prefixes: default_prefixes(),
+        });
+        assert_eq!(
+            disabled.redact("token sk-abc123def456ghi789"),
+            "token sk-abc123def456ghi789"
+        );
+    }
+}
@@ -1,17 +1,26 @@
-use crate::types::{Routine, RoutineExecution, RoutineId, RunId, RunStatus};
+use crate::types::{Routine, RoutineExecution, RoutineId, RunId, RunStatus, DEFAULT_RUN_PRIORITY};
 use crate::workflow::executor::WorkflowExecutor;
 use anyhow::Result;
 use chrono::{DateTime, Datelike, Timelike, Utc};
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::{Arc, Mutex};
-use tokio::task::JoinHandle;
+use tokio::task::AbortHandle;
 
-/// Cron scheduler for recurring routines
+/// A scheduled routine execution, ready to be spawned
+pub type RoutineRun = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Cron scheduler for recurring routines. Routines run when [`run_due`] finds them
+/// due, so the caller decides how often to check.
+///
+/// [`run_due`]: RoutineScheduler::run_due
 pub struct RoutineScheduler {
     routines: Arc<Mutex<HashMap<RoutineId, Routine>>>,
     executions: Arc<Mutex<Vec<RoutineExecution>>>,
     executor: Arc<WorkflowExecutor>,
-    running_tasks: Arc<Mutex<HashMap<RoutineId, JoinHandle<()>>>>,
+    // Scheduled executions still under way, aborted if their routine is disabled
+    running_tasks: Arc<Mutex<HashMap<RoutineId, AbortHandle>>>,
 }
 
 impl RoutineScheduler {
@@ -26,16 +35,11 @@ impl RoutineScheduler {
     }
 
     /// Register a routine
-    pub fn register_routine(&self, routine: Routine) -> Result<()> {
-        let routine_id = routine.id.clone();
-
-        // Store the routine
-        self.routines.lock().unwrap().insert(routine_id.clone(), routine.clone());
-
-        // Start the scheduler task for this routine
+    pub fn register_routine(&self, mut routine: Routine) -> Result<()> {
         if routine.enabled {
-            self.start_routine_task(routine)?;
+            routine.next_run = calculate_next_run(&routine.schedule.cron)?;
         }
+        self.routines.lock().unwrap().insert(routine.id.clone(), routine);
 
         Ok(())
     }
@@ -67,13 +71,9 @@ impl RoutineScheduler {
     pub fn enable_routine(&self, routine_id: &RoutineId) -> Result<()> {
         let mut routines = self.routines.lock().unwrap();
         if let Some(routine) = routines.get_mut(routine_id) {
+            routine.next_run = calculate_next_run(&routine.schedule.cron)?;
             routine.enabled = true;
             routine.updated_at = Utc::now();
-
-            // Start the task
-            let routine_clone = routine.clone();
-            drop(routines);
-            self.start_routine_task(routine_clone)?;
             Ok(())
         } else {
             Err(anyhow::anyhow!("Routine not found"))
@@ -98,96 +98,81 @@ impl RoutineScheduler {
         }
     }
 
-    /// Start a scheduler task for a routine
-    fn start_routine_task(&self, routine: Routine) -> Result<()> {
-        let routine_id = routine.id.clone();
-        let routine_id_for_task = routine_id.clone();
-        let executor = self.executor.clone();
-        let executions = self.executions.clone();
-        let routines = self.routines.clone();
-
-        let handle = tokio::spawn(async move {
-            loop {
-                // Calculate next run time based on cron expression
-                let next_run = match calculate_next_run(&routine.schedule.cron) {
-                    Ok(next) => next,
-                    Err(e) => {
-                        tracing::error!("Failed to calculate next run for routine {}: {}", routine_id_for_task.0, e);
-                        break;
-                    }
-                };
+    /// Start every enabled routine whose next run time has passed, and schedule its
+    /// next run. A routine whose previous execution is still under way waits for the
+    /// next check, so executions of one routine never overlap.
+    pub fn run_due(&self) {
+        self.run_due_on(|run| tokio::spawn(run).abort_handle())
+    }
 
-                // Update next_run in the routine
-                {
-                    let mut routines_lock = routines.lock().unwrap();
-                    if let Some(r) = routines_lock.get_mut(&routine_id_for_task) {
-                        r.next_run = next_run;
-                    }
-                }
-
-                // Wait until next run time
-                let now = Utc::now();
-                if next_run > now {
-                    let wait_duration = (next_run - now).to_std().unwrap_or(std::time::Duration::from_secs(1));
-                    tokio::time::sleep(wait_duration).await;
-                }
-
-                // Execute the routine
-                tracing::info!("Executing routine: {}", routine.name);
-                let scheduled_at = next_run;
-                let executed_at = Utc::now();
-
-                match executor.execute(routine.id.0.clone(), routine.workflow.clone()).await {
-                    Ok(run) => {
-                        let execution = RoutineExecution {
-                            id: uuid::Uuid::new_v4().to_string(),
-                            routine_id: routine_id_for_task.clone(),
-                            run_id: run.id,
-                            scheduled_at,
-                            executed_at,
-                            status: RunStatus::Running,
-                            error: None,
-                        };
-
-                        executions.lock().unwrap().push(execution);
-
-                        // Update last_run
-                        let mut routines_lock = routines.lock().unwrap();
-                        if let Some(r) = routines_lock.get_mut(&routine_id_for_task) {
-                            r.last_run = Some(executed_at);
+    /// As [`run_due`], but start each execution with `spawn`, e.g. on a task set the
+    /// caller caps and shuts down
+    ///
+    /// [`run_due`]: RoutineScheduler::run_due
+    pub fn run_due_on(&self, mut spawn: impl FnMut(RoutineRun) -> AbortHandle) {
+        let now = Utc::now();
+        let mut running_tasks = self.running_tasks.lock().unwrap();
+        running_tasks.retain(|_, handle| !handle.is_finished());
+
+        let due: Vec<(Routine, DateTime<Utc>)> = {
+            let mut routines = self.routines.lock().unwrap();
+            routines
+                .values_mut()
+                .filter(|r| r.enabled && r.next_run <= now && !running_tasks.contains_key(&r.id))
+                .filter_map(|routine| {
+                    let scheduled_at = routine.next_run;
+                    match calculate_next_run(&routine.schedule.cron) {
+                        Ok(next) => routine.next_run = next,
+                        Err(e) => {
+                            tracing::error!(
+                                "Failed to calculate next run for routine {}: {}",
+                                routine.id.0,
+                                e
+                            );
+                            return None;
                         }
                     }
-                    Err(e) => {
-                        tracing::error!("Failed to execute routine {}: {}", routine.name, e);
-                        let error_msg = format!("{}", e);
-                        let execution = RoutineExecution {
-                            id: uuid::Uuid::new_v4().to_string(),
-                            routine_id: routine_id_for_task.clone(),
-                            run_id: RunId::new(),
-                            scheduled_at,
-                            executed_at,
-                            status: RunStatus::Failed,
-                            error: Some(error_msg),
-                        };
-                        executions.lock().unwrap().push(execution);
-                    }
-                }
-
-                // Check if routine is still enabled
-                let enabled = {
-                    let routines_lock = routines.lock().unwrap();
-                    routines_lock.get(&routine_id_for_task).map(|r| r.enabled).unwrap_or(false)
-                };
-
-                if !enabled {
-                    tracing::info!("Routine {} has been disabled, stopping scheduler", routine.name);
-                    break;
-                }
-            }
-        });
+                    Some((routine.clone(), scheduled_at))
+                })
+                .collect()
+        };
 
-        self.running_tasks.lock().unwrap().insert(routine_id, handle);
-        Ok(())
+        for (routine, scheduled_at) in due {
+            let executor = self.executor.clone();
+            let executions = self.executions.clone();
+            let routines = self.routines.clone();
+            let routine_id = routine.id.clone();
+            let handle = spawn(Box::pin(async move {
+                run_routine(&executor, &executions, &routines, &routine, scheduled_at, false)
+                    .await;
+            }));
+            running_tasks.insert(routine_id, handle);
+        }
+    }
+
+    /// Run a routine's workflow once, outside its schedule. `next_run` is left alone so
+    /// the schedule is unaffected. Disabled routines are only run when `force` is set.
+    pub async fn trigger_now(&self, routine_id: &RoutineId, force: bool) -> Result<RoutineExecution> {
+        let routine = self
+            .get_routine(routine_id)
+            .ok_or_else(|| anyhow::anyhow!("Routine not found"))?;
+
+        if !routine.enabled && !force {
+            return Err(anyhow::anyhow!(
+                "Routine {} is disabled; set force to trigger it anyway",
+                routine_id.0
+            ));
+        }
+
+        Ok(run_routine(
+            &self.executor,
+            &self.executions,
+            &self.routines,
+            &routine,
+            Utc::now(),
+            true,
+        )
+        .await)
     }
 
     /// Get execution history for a routine
@@ -207,6 +192,67 @@ impl RoutineScheduler {
     }
 }
 
+/// Execute a routine's workflow and record the execution
+async fn run_routine(
+    executor: &WorkflowExecutor,
+    executions: &Mutex<Vec<RoutineExecution>>,
+    routines: &Mutex<HashMap<RoutineId, Routine>>,
+    routine: &Routine,
+    scheduled_at: DateTime<Utc>,
+    manual: bool,
+) -> RoutineExecution {
+    tracing::info!("Executing routine: {}", routine.name);
+    let executed_at = Utc::now();
+
+    let execution = match executor
+        .execute_tagged(
+            routine.id.0.clone(),
+            routine.workflow.clone(),
+            None,
+            routine.tags.clone(),
+            HashMap::new(),
+            DEFAULT_RUN_PRIORITY,
+            None,
+        )
+        .await
+    {
+        Ok(run) => {
+            // Update last_run
+            let mut routines_lock = routines.lock().unwrap();
+            if let Some(r) = routines_lock.get_mut(&routine.id) {
+                r.last_run = Some(executed_at);
+            }
+
+            RoutineExecution {
+                id: uuid::Uuid::new_v4().to_string(),
+                routine_id: routine.id.clone(),
+                run_id: run.id,
+                scheduled_at,
+                executed_at,
+                status: RunStatus::Running,
+                error: None,
+                manual,
+            }
+        }
+        Err(e) => {
+            tracing::error!("Failed to execute routine {}: {}", routine.name, e);
+            RoutineExecution {
+                id: uuid::Uuid::new_v4().to_string(),
+                routine_id: routine.id.clone(),
+                run_id: RunId::new(),
+                scheduled_at,
+                executed_at,
+                status: RunStatus::Failed,
+                error: Some(format!("{}", e)),
+                manual,
+            }
+        }
+    };
+
+    executions.lock().unwrap().push(execution.clone());
+    execution
+}
+
 /// Calculate next run time from cron expression (simplified)
 /// In production, use a proper cron parsing library like `cron` or `tokio-cron-scheduler`
 fn calculate_next_run(cron_expr: &str) -> Result<DateTime<Utc>> {
@@ -335,6 +381,7 @@ mod tests {
                     requires_approval: false,
                 }],
                 dependencies: HashMap::new(),
+                inputs: Vec::new(),
             },
             enabled: false, // Disabled to avoid actual execution
             last_run: None,
@@ -342,6 +389,7 @@ mod tests {
             created_at: Utc::now(),
             created_by: "test".to_string(),
             updated_at: Utc::now(),
+            tags: HashMap::new(),
         };
 
         scheduler.register_routine(routine.clone()).unwrap();
@@ -378,6 +426,7 @@ mod tests {
             workflow: WorkflowSpec {
                 steps: vec![],
                 dependencies: HashMap::new(),
+                inputs: Vec::new(),
             },
             enabled: false,
             last_run: None,
@@ -385,6 +434,7 @@ mod tests {
             created_at: Utc::now(),
             created_by: "test".to_string(),
             updated_at: Utc::now(),
+            tags: HashMap::new(),
         };
 
         scheduler.register_routine(routine.clone()).unwrap();
@@ -399,4 +449,127 @@ mod tests {
         let retrieved = scheduler.get_routine(&routine.id).unwrap();
         assert!(!retrieved.enabled);
     }
+
+    #[tokio::test]
+    async fn test_trigger_now_records_manual_execution() {
+        let temp_dir = TempDir::new().unwrap();
+        let index_path = temp_dir.path().join("index.redb");
+        let event_dir = temp_dir.path().join("events");
+        let blob_dir = temp_dir.path().join("blobs");
+
+        std::fs::create_dir_all(&event_dir).unwrap();
+        std::fs::create_dir_all(&blob_dir).unwrap();
+
+        let index_store = Arc::new(RedbIndexStore::new(index_path).unwrap());
+        let event_log = Arc::new(JsonlEventLog::new(event_dir).unwrap());
+        let blob_store = Arc::new(FilesystemBlobStore::new(blob_dir).unwrap());
+        let executor = Arc::new(WorkflowExecutor::new(event_log, blob_store, index_store));
+        let scheduler = RoutineScheduler::new(executor);
+
+        let next_run = Utc::now() + chrono::Duration::hours(6);
+        let routine = Routine {
+            id: RoutineId::new("test_routine"),
+            name: "Test Routine".to_string(),
+            description: "A test routine".to_string(),
+            schedule: RoutineSchedule {
+                cron: "0 */6 * * *".to_string(),
+                timezone: "UTC".to_string(),
+            },
+            workflow: WorkflowSpec {
+                steps: vec![],
+                dependencies: HashMap::new(),
+                inputs: Vec::new(),
+            },
+            enabled: false,
+            last_run: None,
+            next_run,
+            created_at: Utc::now(),
+            created_by: "test".to_string(),
+            updated_at: Utc::now(),
+            tags: HashMap::new(),
+        };
+
+        scheduler.register_routine(routine.clone()).unwrap();
+
+        // Disabled routines need force
+        assert!(scheduler.trigger_now(&routine.id, false).await.is_err());
+        assert!(scheduler.get_executions(&routine.id).is_empty());
+
+        let execution = scheduler.trigger_now(&routine.id, true).await.unwrap();
+        assert!(execution.manual);
+        assert!(execution.error.is_none());
+
+        let executions = scheduler.get_executions(&routine.id);
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].id, execution.id);
+
+        let retrieved = scheduler.get_routine(&routine.id).unwrap();
+        assert_eq!(retrieved.next_run, next_run);
+        assert_eq!(retrieved.last_run, Some(execution.executed_at));
+        assert!(!retrieved.enabled);
+
+        assert!(scheduler
+            .trigger_now(&RoutineId::new("missing"), true)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_due_starts_due_routines_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let index_path = temp_dir.path().join("index.redb");
+        let event_dir = temp_dir.path().join("events");
+        let blob_dir = temp_dir.path().join("blobs");
+
+        std::fs::create_dir_all(&event_dir).unwrap();
+        std::fs::create_dir_all(&blob_dir).unwrap();
+
+        let index_store = Arc::new(RedbIndexStore::new(index_path).unwrap());
+        let event_log = Arc::new(JsonlEventLog::new(event_dir).unwrap());
+        let blob_store = Arc::new(FilesystemBlobStore::new(blob_dir).unwrap());
+        let executor = Arc::new(WorkflowExecutor::new(event_log, blob_store, index_store));
+        let scheduler = RoutineScheduler::new(executor);
+
+        let routine = Routine {
+            id: RoutineId::new("test_routine"),
+            name: "Test Routine".to_string(),
+            description: "A test routine".to_string(),
+            schedule: RoutineSchedule {
+                cron: "*/15 * * * *".to_string(),
+                timezone: "UTC".to_string(),
+            },
+            workflow: WorkflowSpec {
+                steps: vec![],
+                dependencies: HashMap::new(),
+                inputs: Vec::new(),
+            },
+            enabled: true,
+            last_run: None,
+            next_run: Utc::now(),
+            created_at: Utc::now(),
+            created_by: "test".to_string(),
+            updated_at: Utc::now(),
+            tags: HashMap::new(),
+        };
+        scheduler.register_routine(routine.clone()).unwrap();
+
+        // Not due yet: registering scheduled the next run from the cron expression
+        scheduler.run_due();
+        assert!(scheduler.running_tasks.lock().unwrap().is_empty());
+
+        let scheduled_at = Utc::now() - chrono::Duration::minutes(1);
+        scheduler.routines.lock().unwrap().get_mut(&routine.id).unwrap().next_run = scheduled_at;
+        scheduler.run_due();
+        scheduler.run_due();
+        let handle = scheduler.running_tasks.lock().unwrap().remove(&routine.id).unwrap();
+        while !handle.is_finished() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let executions = scheduler.get_executions(&routine.id);
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].scheduled_at, scheduled_at);
+        assert!(!executions[0].manual);
+        assert!(scheduler.get_routine(&routine.id).unwrap().next_run > Utc::now());
+    }
 }
@@ -1,8 +1,19 @@
 use crate::types::BlobHash;
 use anyhow::{Context, Result};
 use bytes::Bytes;
+use sha2::{Digest, Sha256};
 use std::path::PathBuf;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+/// Chunk size used when streaming blobs
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Directory under the blob store root for partially written blobs. Not a valid
+/// two-character hash prefix, so it can't collide with stored blobs.
+const TMP_DIR: &str = "tmp";
+
+/// Readable blob contents
+pub type BlobReader = Box<dyn AsyncRead + Send + Unpin>;
 
 /// Content-addressed blob storage abstraction
 #[async_trait::async_trait]
@@ -18,6 +29,30 @@ pub trait BlobStore: Send + Sync {
 
     /// Delete a blob (for garbage collection)
     async fn delete(&self, hash: &BlobHash) -> Result<()>;
+
+    /// Store a blob read from `reader` and return its content hash.
+    ///
+    /// The default implementation buffers the whole blob; stores that can write
+    /// incrementally should override it.
+    async fn put_stream(&self, reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<BlobHash> {
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .await
+            .context("Failed to read blob stream")?;
+        self.put(Bytes::from(data)).await
+    }
+
+    /// Open a blob for reading by its content hash.
+    ///
+    /// The default implementation buffers the whole blob; stores that can read
+    /// incrementally should override it.
+    async fn get_stream(&self, hash: &BlobHash) -> Result<Option<BlobReader>> {
+        Ok(self
+            .get(hash)
+            .await?
+            .map(|data| Box::new(std::io::Cursor::new(data)) as BlobReader))
+    }
 }
 
 /// Filesystem-based blob store (for local development and single-node deployments)
@@ -45,7 +80,65 @@ impl FilesystemBlobStore {
 #[async_trait::async_trait]
 impl BlobStore for FilesystemBlobStore {
     async fn put(&self, data: Bytes) -> Result<BlobHash> {
-        let hash = BlobHash::from_bytes(&data);
+        self.put_stream(&mut data.as_ref()).await
+    }
+
+    async fn get(&self, hash: &BlobHash) -> Result<Option<Bytes>> {
+        let Some(mut reader) = self.get_stream(hash).await? else {
+            return Ok(None);
+        };
+
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .await
+            .context("Failed to read blob")?;
+        Ok(Some(Bytes::from(data)))
+    }
+
+    async fn put_stream(&self, reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<BlobHash> {
+        // The hash isn't known until the whole stream is read, so write to a temporary
+        // file while hashing and move it into place afterwards
+        let tmp_dir = self.base_path.join(TMP_DIR);
+        tokio::fs::create_dir_all(&tmp_dir)
+            .await
+            .context("Failed to create blob temp directory")?;
+        let tmp_path = tmp_dir.join(uuid::Uuid::new_v4().to_string());
+
+        let result = async {
+            let mut file = tokio::fs::File::create(&tmp_path)
+                .await
+                .context("Failed to create blob file")?;
+            let mut hasher = Sha256::new();
+            let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+
+            loop {
+                let n = reader
+                    .read(&mut buf)
+                    .await
+                    .context("Failed to read blob stream")?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+                file.write_all(&buf[..n])
+                    .await
+                    .context("Failed to write blob")?;
+            }
+            file.sync_all().await.context("Failed to sync blob")?;
+
+            Ok::<_, anyhow::Error>(BlobHash(hex::encode(hasher.finalize())))
+        }
+        .await;
+
+        let hash = match result {
+            Ok(hash) => hash,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&tmp_path).await;
+                return Err(e);
+            }
+        };
+
         let path = self.blob_path(&hash);
 
         // Create parent directory
@@ -55,30 +148,30 @@ impl BlobStore for FilesystemBlobStore {
                 .context("Failed to create blob directory")?;
         }
 
-        // Write blob to disk (only if it doesn't exist - content-addressed)
-        if !path.exists() {
-            let mut file = tokio::fs::File::create(&path)
+        // Keep the existing copy if present - content-addressed
+        if path.exists() {
+            tokio::fs::remove_file(&tmp_path)
                 .await
-                .context("Failed to create blob file")?;
-            file.write_all(&data)
+                .context("Failed to remove duplicate blob")?;
+        } else {
+            tokio::fs::rename(&tmp_path, &path)
                 .await
-                .context("Failed to write blob")?;
-            file.sync_all().await.context("Failed to sync blob")?;
+                .context("Failed to move blob into place")?;
         }
 
         Ok(hash)
     }
 
-    async fn get(&self, hash: &BlobHash) -> Result<Option<Bytes>> {
+    async fn get_stream(&self, hash: &BlobHash) -> Result<Option<BlobReader>> {
         let path = self.blob_path(hash);
         if !path.exists() {
             return Ok(None);
         }
 
-        let data = tokio::fs::read(&path)
+        let file = tokio::fs::File::open(&path)
             .await
-            .context("Failed to read blob")?;
-        Ok(Some(Bytes::from(data)))
+            .context("Failed to open blob")?;
+        Ok(Some(Box::new(file)))
     }
 
     async fn exists(&self, hash: &BlobHash) -> Result<bool> {
@@ -190,4 +283,92 @@ mod tests {
         store.delete(&hash).await.unwrap();
         assert!(!store.exists(&hash).await.unwrap());
     }
+
+    /// Generates `remaining` bytes of a repeating pattern without holding them in memory
+    struct PatternReader {
+        offset: usize,
+        remaining: usize,
+    }
+
+    fn pattern_byte(offset: usize) -> u8 {
+        (offset % 251) as u8
+    }
+
+    impl AsyncRead for PatternReader {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            let n = buf.remaining().min(self.remaining);
+            let chunk: Vec<u8> = (0..n).map(|i| pattern_byte(self.offset + i)).collect();
+            buf.put_slice(&chunk);
+            self.offset += n;
+            self.remaining -= n;
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_large_blob() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FilesystemBlobStore::new(temp_dir.path().to_path_buf()).unwrap();
+
+        let size = 8 * 1024 * 1024 + 17;
+        let mut hasher = Sha256::new();
+        let mut expected = PatternReader {
+            offset: 0,
+            remaining: size,
+        };
+        let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+        loop {
+            let n = expected.read(&mut buf).await.unwrap();
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        let expected_hash = BlobHash(hex::encode(hasher.finalize()));
+
+        let mut reader = PatternReader {
+            offset: 0,
+            remaining: size,
+        };
+        let hash = store.put_stream(&mut reader).await.unwrap();
+        assert_eq!(hash, expected_hash);
+        assert!(store.exists(&hash).await.unwrap());
+
+        // Nothing is left behind in the temp directory
+        let tmp_entries = std::fs::read_dir(temp_dir.path().join(TMP_DIR)).unwrap().count();
+        assert_eq!(tmp_entries, 0);
+
+        // Read back chunk by chunk, comparing against the pattern
+        let mut stream = store.get_stream(&hash).await.unwrap().unwrap();
+        let mut offset = 0;
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            if n == 0 {
+                break;
+            }
+            assert!(buf[..n]
+                .iter()
+                .enumerate()
+                .all(|(i, b)| *b == pattern_byte(offset + i)));
+            offset += n;
+        }
+        assert_eq!(offset, size);
+
+        // Storing the same content again is deduplicated
+        let mut reader = PatternReader {
+            offset: 0,
+            remaining: size,
+        };
+        assert_eq!(store.put_stream(&mut reader).await.unwrap(), hash);
+
+        assert!(store
+            .get_stream(&BlobHash::from_bytes(b"missing"))
+            .await
+            .unwrap()
+            .is_none());
+    }
 }
@@ -1,63 +1,471 @@
-use crate::events::{Event, EventLog};
-use crate::types::RunId;
+use crate::events::{Event, EventLog, EventType};
+use crate::redaction::Redactor;
+use crate::storage::BlobStore;
+use crate::types::{BlobHash, RunId};
 use anyhow::{Context, Result};
-use chrono::{DateTime, Datelike, Utc};
+use bytes::Bytes;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::Write;
-use std::path::PathBuf;
-use tokio::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{broadcast, mpsc, oneshot};
 
-/// Event log implementation using JSONL (JSON Lines) format with optional compression
+/// Appends queued for the background writer before `append` waits for room
+pub const WRITE_QUEUE_CAPACITY: usize = 1024;
+
+/// Most events the background writer persists in one batch
+const MAX_WRITE_BATCH: usize = 256;
+
+/// Size at which a run's segment for the day is closed and a new one started
+pub const DEFAULT_MAX_SEGMENT_BYTES: u64 = 8 * 1024 * 1024;
+
+/// File under the base path listing the live segments of each run
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Unfinished runs whose idempotency keys the writer keeps in memory. The least
+/// recently loaded are dropped past this and read from disk again when next written.
+const MAX_CACHED_RUNS: usize = 1024;
+
+/// How long event log segments are kept. A finished run's segments expire together;
+/// those of runs that haven't finished are kept regardless, so no run loses part of
+/// its history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Expire runs whose segments are all from more than this many days ago
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+
+    /// Expire the oldest runs while the log is larger than this
+    #[serde(default)]
+    pub max_total_bytes: Option<u64>,
+
+    /// Start a new segment once a run's current one reaches this size
+    #[serde(default = "default_max_segment_bytes")]
+    pub max_segment_bytes: u64,
+
+    /// Move expired segments to the blob store instead of deleting them
+    #[serde(default)]
+    pub archive: bool,
+}
+
+fn default_max_segment_bytes() -> u64 {
+    DEFAULT_MAX_SEGMENT_BYTES
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_days: None,
+            max_total_bytes: None,
+            max_segment_bytes: default_max_segment_bytes(),
+            archive: false,
+        }
+    }
+}
+
+/// Segments expired by one compaction pass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactionReport {
+    pub segments_deleted: usize,
+    pub segments_archived: usize,
+    pub bytes_freed: u64,
+}
+
+/// Where each run's events live. Once the log is open, the background writer adds
+/// segments and compaction removes those of finished runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    runs: HashMap<String, RunSegments>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RunSegments {
+    /// Whether the run has recorded a terminal event, making its segments expirable
+    finished: bool,
+    /// Live segments, oldest first
+    segments: Vec<Segment>,
+    /// Blob hashes of expired segments moved to the archive
+    #[serde(default)]
+    archived: Vec<BlobHash>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Segment {
+    /// Relative to the log's base path
+    path: PathBuf,
+    day: NaiveDate,
+}
+
+/// Work for the background writer, handled in the order it was queued
+enum WriteCommand {
+    /// Reply with the outcome once the batch holding the event is on disk
+    Append(Box<Event>, oneshot::Sender<std::result::Result<(), String>>),
+    /// Reply once everything queued before it is on disk
+    Flush(oneshot::Sender<std::result::Result<(), String>>),
+}
+
+/// Event log implementation using JSONL (JSON Lines) format with optional compression.
+///
+/// Appends are queued to a background task that writes them in batches, so concurrent
+/// callers share one write and sync. `append` returns once its event is on disk and
+/// reports the write's outcome. When the queue is full, it waits for room rather than
+/// dropping events. Reads flush the queue first, so they see every prior append.
+///
+/// Each run's events for a day go to one or more size-capped segments, recorded in a
+/// manifest. [`JsonlEventLog::compact`] expires segments past the retention policy.
 pub struct JsonlEventLog {
     base_path: PathBuf,
-    // In-memory buffer for the current day's events (flushed periodically)
-    buffer: RwLock<Vec<Event>>,
+    // Queue feeding the background writer, started by the first append
+    writer: OnceLock<mpsc::Sender<WriteCommand>>,
+    // Notifies tailing readers of the run each appended event belongs to
+    appended: broadcast::Sender<RunId>,
+    // Scrubs secrets from event payloads before they are persisted
+    redactor: Option<Redactor>,
+    manifest: Arc<Mutex<Manifest>>,
+    retention: RetentionPolicy,
+    // Receives expired segments when the retention policy archives them
+    archive: Option<Arc<dyn BlobStore>>,
+    // Held while the manifest is written, so the writer and compaction take turns
+    manifest_file: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl JsonlEventLog {
     pub fn new(base_path: PathBuf) -> Result<Self> {
         std::fs::create_dir_all(&base_path)
             .context("Failed to create event log directory")?;
+        let manifest = load_manifest(&base_path)?;
+        let (appended, _) = broadcast::channel(256);
         Ok(Self {
             base_path,
-            buffer: RwLock::new(Vec::new()),
+            writer: OnceLock::new(),
+            appended,
+            redactor: None,
+            manifest: Arc::new(Mutex::new(manifest)),
+            retention: RetentionPolicy::default(),
+            archive: None,
+            manifest_file: Arc::new(tokio::sync::Mutex::new(())),
         })
     }
 
-    /// Get the path to the event log file for a specific run
-    /// Format: events/YYYY/MM/DD/<run_id>.jsonl.gz
-    fn event_log_path(&self, run_id: &RunId, date: &DateTime<Utc>) -> PathBuf {
-        self.base_path
-            .join("events")
-            .join(format!("{:04}", date.year()))
-            .join(format!("{:02}", date.month()))
-            .join(format!("{:02}", date.day()))
-            .join(format!("{}.jsonl.gz", run_id))
+    /// Redact secrets from every event before it is written
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Rotate and expire segments according to `retention`
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Store expired segments in `blob_store` when the retention policy archives them
+    pub fn with_archive(mut self, blob_store: Arc<dyn BlobStore>) -> Self {
+        self.archive = Some(blob_store);
+        self
     }
 
-    /// Flush buffered events to disk
-    async fn flush(&self, run_id: &RunId) -> Result<()> {
-        let mut buffer = self.buffer.write().await;
-        if buffer.is_empty() {
+    /// Queue to the background writer, spawning it on the current runtime on first use
+    fn writer(&self) -> &mpsc::Sender<WriteCommand> {
+        self.writer.get_or_init(|| {
+            let (writer, commands) = mpsc::channel(WRITE_QUEUE_CAPACITY);
+            let segments = SegmentWriter {
+                store: self.store(),
+                keys: HashMap::new(),
+                loaded: VecDeque::new(),
+                max_cached_runs: MAX_CACHED_RUNS,
+            };
+            tokio::spawn(run_writer(segments, commands));
+            writer
+        })
+    }
+
+    /// Wait until every event appended so far has been written and synced to disk.
+    /// Reports a write failure since the previous flush, if there was one.
+    pub async fn flush(&self) -> Result<()> {
+        // Nothing has been appended yet
+        let Some(writer) = self.writer.get() else {
             return Ok(());
+        };
+
+        let (done, written) = oneshot::channel();
+        writer
+            .send(WriteCommand::Flush(done))
+            .await
+            .map_err(|_| anyhow::anyhow!("Event log writer has stopped"))?;
+
+        written
+            .await
+            .map_err(|_| anyhow::anyhow!("Event log writer has stopped"))?
+            .map_err(|e| anyhow::anyhow!("Failed to write events: {}", e))
+    }
+
+    /// Expire segments of finished runs that fall outside the retention policy. Runs
+    /// alongside the background writer, so appends don't wait for it.
+    pub async fn compact(&self) -> Result<CompactionReport> {
+        self.store()
+            .compact()
+            .await
+            .context("Failed to compact event log")
+    }
+
+    /// The segments and manifest, shared by the background writer and compaction
+    fn store(&self) -> SegmentStore {
+        SegmentStore {
+            base_path: self.base_path.clone(),
+            manifest: self.manifest.clone(),
+            retention: self.retention.clone(),
+            archive: self.archive.clone(),
+            manifest_file: self.manifest_file.clone(),
+        }
+    }
+
+    /// Get all event log files for a run
+    async fn get_log_files(&self, run_id: &RunId) -> Result<Vec<PathBuf>> {
+        let listed = self.manifest.lock().unwrap().runs.get(&run_id.to_string()).map(|run| {
+            run.segments
+                .iter()
+                .map(|segment| self.base_path.join(&segment.path))
+                .collect::<Vec<_>>()
+        });
+
+        match listed {
+            Some(files) => Ok(files.into_iter().filter(|file| file.exists()).collect()),
+            // Not written through this log, e.g. by another process sharing the directory
+            None => Ok(scan_segments(&self.base_path)?
+                .into_iter()
+                .filter(|(id, _)| *id == run_id.to_string())
+                .map(|(_, segment)| self.base_path.join(segment.path))
+                .collect()),
         }
+    }
+}
+
+/// Read the manifest, or build one from the segments on disk if there is none yet
+fn load_manifest(base_path: &Path) -> Result<Manifest> {
+    let path = base_path.join(MANIFEST_FILE);
+    if path.exists() {
+        let bytes = std::fs::read(&path).context("Failed to read event log manifest")?;
+        return serde_json::from_slice(&bytes).context("Failed to parse event log manifest");
+    }
+
+    let mut manifest = Manifest::default();
+    for (run_id, segment) in scan_segments(base_path)? {
+        let events = read_jsonl_gz(&base_path.join(&segment.path))?;
+        let run = manifest.runs.entry(run_id).or_default();
+        run.finished |= events.iter().any(is_terminal);
+        run.segments.push(segment);
+    }
+    Ok(manifest)
+}
+
+/// Every segment under `events/YYYY/MM/DD/`, with the run it belongs to, in order
+fn scan_segments(base_path: &Path) -> Result<Vec<(String, Segment)>> {
+    let events_dir = base_path.join("events");
+    if !events_dir.exists() {
+        return Ok(Vec::new());
+    }
 
-        // Group events by date
-        let mut events_by_date: std::collections::HashMap<DateTime<Utc>, Vec<Event>> =
-            std::collections::HashMap::new();
+    let mut segments = Vec::new();
 
-        for event in buffer.drain(..) {
-            let date = event.timestamp.date_naive().and_hms_opt(0, 0, 0).unwrap();
-            let date_utc = DateTime::<Utc>::from_naive_utc_and_offset(date, Utc);
-            events_by_date.entry(date_utc).or_default().push(event);
+    // Walk through year/month/day directories
+    for year_entry in std::fs::read_dir(&events_dir)
+        .context("Failed to read events directory")?
+    {
+        let year_entry = year_entry.context("Failed to read year entry")?;
+        if !year_entry.path().is_dir() {
+            continue;
         }
 
-        // Write each date's events to its own file
-        for (date, events) in events_by_date {
-            let path = self.event_log_path(run_id, &date);
+        for month_entry in std::fs::read_dir(year_entry.path())
+            .context("Failed to read month directory")?
+        {
+            let month_entry = month_entry.context("Failed to read month entry")?;
+            if !month_entry.path().is_dir() {
+                continue;
+            }
+
+            for day_entry in std::fs::read_dir(month_entry.path())
+                .context("Failed to read day directory")?
+            {
+                let day_entry = day_entry.context("Failed to read day entry")?;
+                let day = NaiveDate::parse_from_str(
+                    &format!(
+                        "{}-{}-{}",
+                        year_entry.file_name().to_string_lossy(),
+                        month_entry.file_name().to_string_lossy(),
+                        day_entry.file_name().to_string_lossy()
+                    ),
+                    "%Y-%m-%d",
+                );
+                let (Ok(day), true) = (day, day_entry.path().is_dir()) else {
+                    continue;
+                };
+
+                for file_entry in std::fs::read_dir(day_entry.path())
+                    .context("Failed to read segment directory")?
+                {
+                    let file_entry = file_entry.context("Failed to read segment entry")?;
+                    let name = file_entry.file_name().to_string_lossy().into_owned();
+                    let Some((run_id, index)) = parse_segment_name(&name) else {
+                        continue;
+                    };
+                    let path = file_entry
+                        .path()
+                        .strip_prefix(base_path)
+                        .context("Segment outside the event log")?
+                        .to_path_buf();
+                    segments.push((run_id, index, Segment { path, day }));
+                }
+            }
+        }
+    }
+
+    segments.sort_by_key(|(_, index, segment)| (segment.day, *index));
+    Ok(segments
+        .into_iter()
+        .map(|(run_id, _, segment)| (run_id, segment))
+        .collect())
+}
+
+/// The run and rotation index of a segment file name, `<run_id>[.<n>].jsonl.gz`
+fn parse_segment_name(name: &str) -> Option<(String, u32)> {
+    let stem = name.strip_suffix(".jsonl.gz")?;
+    match stem.split_once('.') {
+        Some((run_id, index)) => Some((run_id.to_string(), index.parse().ok()?)),
+        None => Some((stem.to_string(), 0)),
+    }
+}
+
+fn is_terminal(event: &Event) -> bool {
+    matches!(
+        event.event_type,
+        EventType::RunCompleted { .. }
+            | EventType::RunFailed { .. }
+            | EventType::RunCancelled { .. }
+    )
+}
+
+/// Background writer: drains queued appends in batches and persists each batch with
+/// one write and sync per log file
+async fn run_writer(mut segments: SegmentWriter, mut commands: mpsc::Receiver<WriteCommand>) {
+    let mut failure: Option<String> = None;
 
-            // Create parent directory
+    while let Some(command) = commands.recv().await {
+        let mut batch = Vec::new();
+        let mut appends = Vec::new();
+        let mut flushes = Vec::new();
+
+        let mut next = Some(command);
+        while let Some(command) = next.take() {
+            match command {
+                WriteCommand::Append(event, done) => {
+                    batch.push(*event);
+                    appends.push(done);
+                }
+                WriteCommand::Flush(done) => flushes.push(done),
+            }
+            if batch.len() < MAX_WRITE_BATCH {
+                next = commands.try_recv().ok();
+            }
+        }
+
+        let written = segments.write_batch(batch).await.map_err(|e| format!("{:#}", e));
+        if let Err(e) = &written {
+            tracing::error!("Failed to write event batch: {}", e);
+            failure = Some(e.clone());
+        }
+        for done in appends {
+            let _ = done.send(written.clone());
+        }
+
+        if !flushes.is_empty() {
+            let result = failure.take().map_or(Ok(()), Err);
+            for done in flushes {
+                let _ = done.send(result.clone());
+            }
+        }
+    }
+}
+
+/// A log's segments on disk and the manifest listing them
+#[derive(Clone)]
+struct SegmentStore {
+    base_path: PathBuf,
+    manifest: Arc<Mutex<Manifest>>,
+    retention: RetentionPolicy,
+    archive: Option<Arc<dyn BlobStore>>,
+    manifest_file: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// The background writer's view of the log
+struct SegmentWriter {
+    store: SegmentStore,
+    // Idempotency keys recorded across every segment of recently written unfinished
+    // runs, read from disk the first time the run is written to
+    keys: HashMap<RunId, HashSet<String>>,
+    // Runs in `keys`, least recently loaded first
+    loaded: VecDeque<RunId>,
+    max_cached_runs: usize,
+}
+
+impl SegmentWriter {
+    /// Append a batch of events to their runs' current segments, keeping each run's
+    /// events in order
+    async fn write_batch(&mut self, batch: Vec<Event>) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let mut current: HashMap<(RunId, NaiveDate), PathBuf> = HashMap::new();
+        // The manifest is only rewritten when a segment is started or a run finishes
+        let mut changed = false;
+        let mut files: HashMap<PathBuf, (RunId, NaiveDate, Vec<Event>)> = HashMap::new();
+        for event in batch {
+            let day = event.timestamp.date_naive();
+            let path = match current.get(&(event.run_id, day)) {
+                Some(path) => path.clone(),
+                None => {
+                    let path = self.current_segment(&event.run_id, day).await;
+                    current.insert((event.run_id, day), path.clone());
+                    path
+                }
+            };
+            files
+                .entry(path)
+                .or_insert_with(|| (event.run_id, day, Vec::new()))
+                .2
+                .push(event);
+        }
+
+        for (segment_path, (run_id, day, events)) in files {
+            // Skip events already recorded for the run, e.g. by a retried write, in this
+            // segment or any before it
+            self.load_keys(&run_id).await?;
+            let known = &self.keys[&run_id];
+            let mut new_keys = HashSet::new();
+            let appended = events.len();
+            let events: Vec<Event> = events
+                .into_iter()
+                .filter(|event| {
+                    event
+                        .idempotency_key()
+                        .is_none_or(|key| !known.contains(&key) && new_keys.insert(key))
+                })
+                .collect();
+            if events.is_empty() {
+                tracing::debug!("Skipped {} duplicate events for run {}", appended, run_id);
+                continue;
+            }
+            let finished = events.iter().any(is_terminal);
+
+            let path = self.store.base_path.join(&segment_path);
             if let Some(parent) = path.parent() {
                 tokio::fs::create_dir_all(parent)
                     .await
@@ -65,133 +473,362 @@ impl JsonlEventLog {
             }
 
             // Read existing events if file exists
-            let mut all_events = if path.exists() {
-                self.read_jsonl_gz(&path).await?
+            let mut all_events = if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+                read_segment(path.clone()).await?
             } else {
                 Vec::new()
             };
-
             all_events.extend(events);
+            write_jsonl_gz(&path, &all_events).await?;
 
-            // Write all events to compressed JSONL
-            self.write_jsonl_gz(&path, &all_events).await?;
+            {
+                let mut manifest = self.store.manifest.lock().unwrap();
+                let run = manifest.runs.entry(run_id.to_string()).or_default();
+                if !run.segments.iter().any(|s| s.path == segment_path) {
+                    run.segments.push(Segment {
+                        path: segment_path,
+                        day,
+                    });
+                    changed = true;
+                }
+                changed |= finished && !run.finished;
+                run.finished |= finished;
+            }
+
+            // Finished runs rarely see another write, so their keys are read again if so
+            if finished {
+                self.forget_keys(&run_id);
+            } else if let Some(keys) = self.keys.get_mut(&run_id) {
+                keys.extend(new_keys);
+            }
         }
 
+        if changed {
+            self.store.save_manifest().await?;
+        }
         Ok(())
     }
 
-    /// Read JSONL.GZ file
-    async fn read_jsonl_gz(&self, path: &PathBuf) -> Result<Vec<Event>> {
-        use flate2::read::GzDecoder;
-        use std::io::BufRead;
+    /// Read the idempotency keys of a run's recorded events, unless already known
+    async fn load_keys(&mut self, run_id: &RunId) -> Result<()> {
+        if self.keys.contains_key(run_id) {
+            return Ok(());
+        }
 
-        let file = std::fs::File::open(path).context("Failed to open event log")?;
-        let decoder = GzDecoder::new(file);
-        let reader = std::io::BufReader::new(decoder);
+        let paths: Vec<PathBuf> = self
+            .store
+            .manifest
+            .lock()
+            .unwrap()
+            .runs
+            .get(&run_id.to_string())
+            .map(|run| run.segments.iter().map(|s| self.store.base_path.join(&s.path)).collect())
+            .unwrap_or_default();
 
-        let mut events = Vec::new();
-        for line in reader.lines() {
-            let line = line.context("Failed to read line from event log")?;
-            let event: Event = serde_json::from_str(&line).context("Failed to parse event")?;
-            events.push(event);
+        let mut keys = HashSet::new();
+        for path in paths {
+            if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+                let events = read_segment(path).await?;
+                keys.extend(events.iter().filter_map(Event::idempotency_key));
+            }
         }
+        if self.loaded.len() >= self.max_cached_runs {
+            if let Some(oldest) = self.loaded.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        self.keys.insert(*run_id, keys);
+        self.loaded.push_back(*run_id);
+        Ok(())
+    }
 
-        Ok(events)
+    /// Drop a run's idempotency keys from memory
+    fn forget_keys(&mut self, run_id: &RunId) {
+        if self.keys.remove(run_id).is_some() {
+            self.loaded.retain(|loaded| loaded != run_id);
+        }
     }
 
-    /// Write JSONL.GZ file
-    async fn write_jsonl_gz(&self, path: &PathBuf, events: &[Event]) -> Result<()> {
-        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    /// The segment a run's next event of `day` goes to, relative to the base path.
+    /// Picks a new one when the latest is full; it is added to the manifest once
+    /// written.
+    async fn current_segment(&self, run_id: &RunId, day: NaiveDate) -> PathBuf {
+        let listed: Vec<Segment> = self
+            .store
+            .manifest
+            .lock()
+            .unwrap()
+            .runs
+            .get(&run_id.to_string())
+            .map(|run| run.segments.clone())
+            .unwrap_or_default();
 
-        for event in events {
-            let json = serde_json::to_string(event).context("Failed to serialize event")?;
-            encoder
-                .write_all(json.as_bytes())
-                .context("Failed to write event")?;
-            encoder.write_all(b"\n").context("Failed to write newline")?;
+        if let Some(segment) = listed.iter().rev().find(|s| s.day == day) {
+            let size = segment_size(&self.store.base_path.join(&segment.path)).await;
+            if size < self.store.retention.max_segment_bytes {
+                return segment.path.clone();
+            }
         }
 
-        let compressed = encoder.finish().context("Failed to finish compression")?;
+        for index in 0.. {
+            let path = segment_path(run_id, day, index);
+            if !listed.iter().any(|s| s.path == path)
+                && !tokio::fs::try_exists(self.store.base_path.join(&path))
+                    .await
+                    .unwrap_or(false)
+            {
+                return path;
+            }
+        }
+        unreachable!("unbounded range")
+    }
+}
 
-        tokio::fs::write(path, compressed)
-            .await
-            .context("Failed to write event log file")?;
+impl SegmentStore {
+    /// Expire finished runs whose every segment is older than the age limit, then the
+    /// oldest finished runs while the log is over the size limit. A run's segments are
+    /// expired together, so none is left with part of its history.
+    async fn compact(&self) -> Result<CompactionReport> {
+        let cutoff = self
+            .retention
+            .max_age_days
+            .map(|days| Utc::now().date_naive() - chrono::Days::new(u64::from(days)));
 
-        Ok(())
-    }
+        let runs: Vec<(String, bool, Vec<Segment>)> = self
+            .manifest
+            .lock()
+            .unwrap()
+            .runs
+            .iter()
+            .map(|(run_id, run)| (run_id.clone(), run.finished, run.segments.clone()))
+            .collect();
 
-    /// Get all event log files for a run
-    async fn get_log_files(&self, run_id: &RunId) -> Result<Vec<PathBuf>> {
-        let events_dir = self.base_path.join("events");
-        if !events_dir.exists() {
-            return Ok(Vec::new());
+        let mut total: u64 = 0;
+        let mut candidates: Vec<(String, Vec<(Segment, u64)>)> = Vec::new();
+        for (run_id, finished, segments) in runs {
+            let mut sized = Vec::new();
+            for segment in segments {
+                let size = segment_size(&self.base_path.join(&segment.path)).await;
+                total += size;
+                sized.push((segment, size));
+            }
+            if finished && !sized.is_empty() {
+                candidates.push((run_id, sized));
+            }
         }
 
-        let mut files = Vec::new();
-        let filename = format!("{}.jsonl.gz", run_id);
+        // Oldest first, by the last day each run wrote to
+        let last_day = |segments: &[(Segment, u64)]| {
+            segments.iter().map(|(segment, _)| segment.day).max()
+        };
+        candidates.sort_by(|a, b| (last_day(&a.1), &a.0).cmp(&(last_day(&b.1), &b.0)));
 
-        // Walk through year/month/day directories
-        for year_entry in std::fs::read_dir(&events_dir)
-            .context("Failed to read events directory")?
-        {
-            let year_entry = year_entry.context("Failed to read year entry")?;
-            if !year_entry.path().is_dir() {
-                continue;
+        let mut expired = Vec::new();
+        for (run_id, segments) in candidates {
+            let too_old = cutoff.is_some_and(|cutoff| last_day(&segments) < Some(cutoff));
+            let too_big = self.retention.max_total_bytes.is_some_and(|max| total > max);
+            if too_old || too_big {
+                for (segment, size) in segments {
+                    total = total.saturating_sub(size);
+                    expired.push((run_id.clone(), segment, size));
+                }
             }
+        }
 
-            for month_entry in std::fs::read_dir(year_entry.path())
-                .context("Failed to read month directory")?
-            {
-                let month_entry = month_entry.context("Failed to read month entry")?;
-                if !month_entry.path().is_dir() {
-                    continue;
+        let mut report = CompactionReport::default();
+        for (run_id, segment, size) in expired {
+            let path = self.base_path.join(&segment.path);
+            let archived = match (&self.archive, self.retention.archive) {
+                (Some(archive), true) => {
+                    let bytes = tokio::fs::read(&path).await.context("Failed to read segment")?;
+                    Some(archive.put(Bytes::from(bytes)).await?)
                 }
+                _ => None,
+            };
+            tokio::fs::remove_file(&path)
+                .await
+                .with_context(|| format!("Failed to remove segment {}", path.display()))?;
+            remove_empty_dirs(&self.base_path, &path);
 
-                for day_entry in std::fs::read_dir(month_entry.path())
-                    .context("Failed to read day directory")?
-                {
-                    let day_entry = day_entry.context("Failed to read day entry")?;
-                    if !day_entry.path().is_dir() {
-                        continue;
-                    }
-
-                    let log_file = day_entry.path().join(&filename);
-                    if log_file.exists() {
-                        files.push(log_file);
-                    }
+            let mut manifest = self.manifest.lock().unwrap();
+            if let Some(run) = manifest.runs.get_mut(&run_id) {
+                run.segments.retain(|s| *s != segment);
+                if let Some(hash) = &archived {
+                    run.archived.push(hash.clone());
+                }
+                if run.segments.is_empty() && run.archived.is_empty() {
+                    manifest.runs.remove(&run_id);
                 }
             }
+
+            match archived {
+                Some(_) => report.segments_archived += 1,
+                None => report.segments_deleted += 1,
+            }
+            report.bytes_freed += size;
         }
 
-        Ok(files)
+        if report.segments_archived + report.segments_deleted > 0 {
+            tracing::info!(
+                "Compacted event log: {} segments deleted, {} archived, {} bytes freed",
+                report.segments_deleted,
+                report.segments_archived,
+                report.bytes_freed
+            );
+            self.save_manifest().await?;
+        }
+
+        Ok(report)
     }
+
+    /// Replace the manifest on disk in one rename, so a crash leaves the old or new one
+    async fn save_manifest(&self) -> Result<()> {
+        // Serialized under the file lock, so the last write carries the latest state
+        let _writing = self.manifest_file.lock().await;
+        let bytes = serde_json::to_vec(&*self.manifest.lock().unwrap())
+            .context("Failed to serialize event log manifest")?;
+        let path = self.base_path.join(MANIFEST_FILE);
+        let temp = path.with_extension("json.tmp");
+        let mut file = tokio::fs::File::create(&temp)
+            .await
+            .context("Failed to create event log manifest")?;
+        file.write_all(&bytes)
+            .await
+            .context("Failed to write event log manifest")?;
+        file.sync_all()
+            .await
+            .context("Failed to sync event log manifest")?;
+        tokio::fs::rename(&temp, &path)
+            .await
+            .context("Failed to replace event log manifest")
+    }
+}
+
+/// Remove the day, month and year directories above a deleted segment once empty
+fn remove_empty_dirs(base_path: &Path, segment: &Path) {
+    let events_dir = base_path.join("events");
+    for dir in segment.ancestors().skip(1).take_while(|dir| *dir != events_dir) {
+        // Fails, leaving it in place, while the directory still has entries
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+/// Path of a run's segment, relative to the base path
+/// Format: events/YYYY/MM/DD/<run_id>.jsonl.gz, then <run_id>.<n>.jsonl.gz once rotated
+fn segment_path(run_id: &RunId, day: NaiveDate, index: u32) -> PathBuf {
+    let name = match index {
+        0 => format!("{}.jsonl.gz", run_id),
+        n => format!("{}.{}.jsonl.gz", run_id, n),
+    };
+    PathBuf::from("events")
+        .join(format!("{:04}", day.year()))
+        .join(format!("{:02}", day.month()))
+        .join(format!("{:02}", day.day()))
+        .join(name)
+}
+
+/// Size of a segment on disk, zero if it hasn't been written yet
+async fn segment_size(path: &Path) -> u64 {
+    tokio::fs::metadata(path).await.map_or(0, |m| m.len())
+}
+
+/// Read a segment on the blocking thread pool, off the writer task
+async fn read_segment(path: PathBuf) -> Result<Vec<Event>> {
+    tokio::task::spawn_blocking(move || read_jsonl_gz(&path))
+        .await
+        .context("Event log reader panicked")?
+}
+
+/// Read JSONL.GZ file
+fn read_jsonl_gz(path: &Path) -> Result<Vec<Event>> {
+    use flate2::read::GzDecoder;
+    use std::io::BufRead;
+
+    let file = std::fs::File::open(path).context("Failed to open event log")?;
+    let decoder = GzDecoder::new(file);
+    let reader = std::io::BufReader::new(decoder);
+
+    let mut events = Vec::new();
+    for line in reader.lines() {
+        let line = line.context("Failed to read line from event log")?;
+        let event: Event = serde_json::from_str(&line).context("Failed to parse event")?;
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+/// Write JSONL.GZ file and sync it to disk. The file is replaced in one rename, so
+/// concurrent readers see the old or new contents, never a partial write.
+async fn write_jsonl_gz(path: &Path, events: &[Event]) -> Result<()> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+
+    for event in events {
+        let json = serde_json::to_string(event).context("Failed to serialize event")?;
+        encoder
+            .write_all(json.as_bytes())
+            .context("Failed to write event")?;
+        encoder.write_all(b"\n").context("Failed to write newline")?;
+    }
+
+    let compressed = encoder.finish().context("Failed to finish compression")?;
+
+    let temp = path.with_extension("gz.tmp");
+    let mut file = tokio::fs::File::create(&temp)
+        .await
+        .context("Failed to create event log file")?;
+    file.write_all(&compressed)
+        .await
+        .context("Failed to write event log file")?;
+    file.sync_all()
+        .await
+        .context("Failed to sync event log file")?;
+    tokio::fs::rename(&temp, path)
+        .await
+        .context("Failed to replace event log file")?;
+
+    Ok(())
 }
 
 #[async_trait::async_trait]
 impl EventLog for JsonlEventLog {
     async fn append(&self, event: Event) -> Result<()> {
+        let mut event = event;
+        if let Some(redactor) = &self.redactor {
+            redactor.redact_fields(event.event_type.free_text_mut());
+        }
+
         let run_id = event.run_id;
-        let mut buffer = self.buffer.write().await;
-        buffer.push(event);
 
-        // Flush buffer if it gets large (e.g., > 100 events)
-        if buffer.len() > 100 {
-            drop(buffer);
-            self.flush(&run_id).await?;
-        }
+        // Waits for room when the writer is behind, so bursts slow down rather than drop
+        let (done, written) = oneshot::channel();
+        self.writer()
+            .send(WriteCommand::Append(Box::new(event), done))
+            .await
+            .map_err(|_| anyhow::anyhow!("Event log writer has stopped"))?;
+
+        written
+            .await
+            .map_err(|_| anyhow::anyhow!("Event log writer has stopped"))?
+            .map_err(|e| anyhow::anyhow!("Failed to write event: {}", e))?;
+
+        // No receivers just means nobody is tailing
+        let _ = self.appended.send(run_id);
 
         Ok(())
     }
 
     async fn get_run_events(&self, run_id: RunId) -> Result<Vec<Event>> {
-        // Flush any buffered events first
-        self.flush(&run_id).await?;
+        // Wait for queued events to land first
+        self.flush().await?;
 
         let log_files = self.get_log_files(&run_id).await?;
         let mut all_events = Vec::new();
 
         for file in log_files {
-            let events = self.read_jsonl_gz(&file).await?;
+            let events = read_jsonl_gz(&file)?;
             all_events.extend(events);
         }
 
@@ -213,6 +850,35 @@ impl EventLog for JsonlEventLog {
             .filter(|e| e.timestamp >= start && e.timestamp <= end)
             .collect())
     }
+
+    async fn tail_run_events(
+        &self,
+        run_id: RunId,
+        after: usize,
+        wait: Duration,
+    ) -> Result<Vec<Event>> {
+        // Subscribe before reading so an append between the read and the wait is not missed
+        let mut appended = self.appended.subscribe();
+        let deadline = tokio::time::Instant::now() + wait;
+
+        loop {
+            let events = self.get_run_events(run_id).await?;
+            if events.len() > after {
+                return Ok(events.into_iter().skip(after).collect());
+            }
+
+            loop {
+                match tokio::time::timeout_at(deadline, appended.recv()).await {
+                    Err(_) | Ok(Err(broadcast::error::RecvError::Closed)) => {
+                        return Ok(Vec::new())
+                    }
+                    Ok(Ok(id)) if id != run_id => continue,
+                    // Either our run was appended to or we lagged; re-read the log
+                    Ok(_) => break,
+                }
+            }
+        }
+    }
 }
 
 /// Trait for event log storage
@@ -241,15 +907,385 @@ mod tests {
                 workflow_spec: crate::types::WorkflowSpec {
                     steps: vec![],
                     dependencies: std::collections::HashMap::new(),
+                    inputs: Vec::new(),
                 },
             },
         );
 
         log.append(event.clone()).await.unwrap();
-        log.flush(&run_id).await.unwrap();
+        log.flush().await.unwrap();
+
+        let events = log.get_run_events(run_id).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, event.id);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_event_stored_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = JsonlEventLog::new(temp_dir.path().to_path_buf()).unwrap();
+
+        let run_id = RunId::new();
+        let completed = || {
+            Event::new(
+                run_id,
+                EventType::StepCompleted {
+                    step_id: crate::types::StepId::new("build"),
+                    duration_secs: 3,
+                },
+            )
+        };
+
+        // Once within a batch and once more after the first write hit disk
+        let first = completed();
+        log.append(first.clone()).await.unwrap();
+        log.append(completed()).await.unwrap();
+        log.flush().await.unwrap();
+        log.append(completed()).await.unwrap();
 
         let events = log.get_run_events(run_id).await.unwrap();
         assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, first.id);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_event_across_segments_stored_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let retention = RetentionPolicy {
+            // Every flushed batch after the first starts a new segment
+            max_segment_bytes: 1,
+            ..Default::default()
+        };
+        let log = JsonlEventLog::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_retention(retention.clone());
+
+        let run_id = RunId::new();
+        let completed = |step: &str| {
+            Event::new(
+                run_id,
+                EventType::StepCompleted {
+                    step_id: crate::types::StepId::new(step),
+                    duration_secs: 3,
+                },
+            )
+        };
+        log.append(completed("build")).await.unwrap();
+        log.flush().await.unwrap();
+        log.append(completed("test")).await.unwrap();
+        log.flush().await.unwrap();
+        log.append(completed("build")).await.unwrap();
+        log.flush().await.unwrap();
+        assert_eq!(log.get_log_files(&run_id).await.unwrap().len(), 2);
+
+        // Keys are read back from every segment after a restart
+        drop(log);
+        let reopened = JsonlEventLog::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_retention(retention);
+        reopened.append(completed("build")).await.unwrap();
+        reopened.append(completed("test")).await.unwrap();
+        assert_eq!(reopened.get_run_events(run_id).await.unwrap().len(), 2);
+        assert_eq!(reopened.get_log_files(&run_id).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_evicted_keys_still_deduplicate() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = JsonlEventLog::new(temp_dir.path().to_path_buf()).unwrap();
+        let mut writer = SegmentWriter {
+            store: log.store(),
+            keys: HashMap::new(),
+            loaded: VecDeque::new(),
+            max_cached_runs: 2,
+        };
+
+        let completed = |run_id| {
+            Event::new(
+                run_id,
+                EventType::StepCompleted {
+                    step_id: crate::types::StepId::new("build"),
+                    duration_secs: 3,
+                },
+            )
+        };
+        let runs = [RunId::new(), RunId::new(), RunId::new()];
+        for run_id in runs {
+            writer.write_batch(vec![completed(run_id)]).await.unwrap();
+        }
+        assert_eq!(writer.keys.len(), 2);
+        assert!(!writer.keys.contains_key(&runs[0]));
+
+        // The first run's keys are read back from its segment
+        writer.write_batch(vec![completed(runs[0])]).await.unwrap();
+        assert_eq!(log.get_run_events(runs[0]).await.unwrap().len(), 1);
+        assert_eq!(writer.keys.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_rewritten_only_when_segments_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = JsonlEventLog::new(temp_dir.path().to_path_buf()).unwrap();
+        let manifest = temp_dir.path().join(MANIFEST_FILE);
+
+        let run_id = RunId::new();
+        let signal = |key: &str| {
+            Event::new(
+                run_id,
+                EventType::SignalReceived {
+                    event_key: key.to_string(),
+                    payload: None,
+                },
+            )
+        };
+        log.append(signal("first")).await.unwrap();
+        assert!(manifest.exists());
+
+        // Later writes to the same segment leave the manifest alone
+        std::fs::remove_file(&manifest).unwrap();
+        log.append(signal("second")).await.unwrap();
+        assert!(!manifest.exists());
+    }
+
+    #[tokio::test]
+    async fn test_burst_of_appends_lands_in_order_after_flush() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = JsonlEventLog::new(temp_dir.path().to_path_buf()).unwrap();
+
+        // More than the queue holds, appended concurrently so later ones wait on the writer
+        let runs = [RunId::new(), RunId::new()];
+        let mut appended: [Vec<_>; 2] = Default::default();
+        let mut pending = Vec::new();
+        for i in 0..WRITE_QUEUE_CAPACITY * 2 {
+            // Signals have no idempotency key, so none of these are deduplicated
+            let event = Event::new(
+                runs[i % 2],
+                EventType::SignalReceived {
+                    event_key: i.to_string(),
+                    payload: None,
+                },
+            );
+            appended[i % 2].push(event.id.clone());
+            pending.push(log.append(event));
+        }
+        for result in futures_util::future::join_all(pending).await {
+            result.unwrap();
+        }
+
+        // Everything is on disk: a fresh log over the same directory sees it all
+        drop(log);
+        let reopened = JsonlEventLog::new(temp_dir.path().to_path_buf()).unwrap();
+        for (run_id, expected) in runs.iter().zip(&appended) {
+            let ids: Vec<_> = reopened
+                .get_run_events(*run_id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|e| e.id)
+                .collect();
+            assert_eq!(&ids, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_append_reports_failed_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = JsonlEventLog::new(temp_dir.path().to_path_buf()).unwrap();
+
+        // A file where the segment directories go makes every write fail
+        std::fs::write(temp_dir.path().join("events"), b"").unwrap();
+
+        let event = Event::new(
+            RunId::new(),
+            EventType::SignalReceived {
+                event_key: "deploy".to_string(),
+                payload: None,
+            },
+        );
+        let err = log.append(event).await.unwrap_err();
+        assert!(err.to_string().contains("Failed to write event"));
+    }
+
+    #[tokio::test]
+    async fn test_tail_waits_for_new_event() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = std::sync::Arc::new(JsonlEventLog::new(temp_dir.path().to_path_buf()).unwrap());
+
+        let run_id = RunId::new();
+        let other_run_id = RunId::new();
+        log.append(Event::new(
+            run_id,
+            EventType::RunCompleted { duration_secs: 0 },
+        ))
+        .await
+        .unwrap();
+
+        // Already-seen events don't satisfy the tail; it holds until the deadline
+        let start = std::time::Instant::now();
+        let events = log
+            .tail_run_events(run_id, 1, Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert!(events.is_empty());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let tail = tokio::spawn({
+            let log = log.clone();
+            async move {
+                log.tail_run_events(run_id, 1, Duration::from_secs(10))
+                    .await
+                    .unwrap()
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!tail.is_finished());
+
+        // Events on other runs don't wake the tail
+        log.append(Event::new(
+            other_run_id,
+            EventType::RunCompleted { duration_secs: 0 },
+        ))
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!tail.is_finished());
+
+        let start = std::time::Instant::now();
+        let event = Event::new(
+            run_id,
+            EventType::RunCancelled {
+                reason: "test".to_string(),
+            },
+        );
+        log.append(event.clone()).await.unwrap();
+
+        let events = tail.await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(events.len(), 1);
         assert_eq!(events[0].id, event.id);
     }
+
+    #[tokio::test]
+    async fn test_event_payload_redacted() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = JsonlEventLog::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_redactor(Redactor::default());
+
+        let run_id = RunId::new();
+        log.append(Event::new(
+            run_id,
+            EventType::RunFailed {
+                error: "Provider rejected key sk-proj4f9a8b7c6d5e4f3a2b1c: quota exceeded".to_string(),
+                duration_secs: 3,
+            },
+        ))
+        .await
+        .unwrap();
+
+        let events = log.get_run_events(run_id).await.unwrap();
+        match &events[0].event_type {
+            EventType::RunFailed { error, duration_secs } => {
+                assert_eq!(error, "Provider rejected key [REDACTED]: quota exceeded");
+                assert_eq!(*duration_secs, 3);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compaction_keeps_unfinished_runs() {
+        let temp_dir = TempDir::new().unwrap();
+        let retention = RetentionPolicy {
+            max_age_days: Some(7),
+            // Every flushed batch after the first starts a new segment
+            max_segment_bytes: 1,
+            ..Default::default()
+        };
+        let log = JsonlEventLog::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_retention(retention.clone());
+
+        let old = |run_id, event_type| {
+            let mut event = Event::new(run_id, event_type);
+            event.timestamp = Utc::now() - chrono::Duration::days(30);
+            event
+        };
+        let finished = RunId::new();
+        let in_flight = RunId::new();
+        for run_id in [finished, in_flight] {
+            log.append(old(run_id, EventType::StepStarted {
+                step_id: crate::types::StepId::new("build"),
+                attempt: 1,
+            }))
+            .await
+            .unwrap();
+            log.flush().await.unwrap();
+        }
+        log.append(old(finished, EventType::RunCompleted { duration_secs: 5 }))
+            .await
+            .unwrap();
+        log.append(old(in_flight, EventType::StepCompleted {
+            step_id: crate::types::StepId::new("build"),
+            duration_secs: 5,
+        }))
+        .await
+        .unwrap();
+        log.flush().await.unwrap();
+        assert_eq!(log.get_log_files(&finished).await.unwrap().len(), 2);
+        assert_eq!(log.get_log_files(&in_flight).await.unwrap().len(), 2);
+
+        let report = log.compact().await.unwrap();
+        assert_eq!(report.segments_deleted, 2);
+        assert_eq!(report.segments_archived, 0);
+        assert!(log.get_run_events(finished).await.unwrap().is_empty());
+        assert_eq!(log.get_run_events(in_flight).await.unwrap().len(), 2);
+
+        // The manifest survives a restart
+        drop(log);
+        let reopened = JsonlEventLog::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_retention(retention);
+        assert!(reopened.get_run_events(finished).await.unwrap().is_empty());
+        assert_eq!(reopened.get_run_events(in_flight).await.unwrap().len(), 2);
+        assert_eq!(reopened.compact().await.unwrap(), CompactionReport::default());
+    }
+
+    #[tokio::test]
+    async fn test_compaction_expires_whole_runs() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = JsonlEventLog::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_retention(RetentionPolicy {
+                max_age_days: Some(7),
+                ..Default::default()
+            });
+
+        let at = |run_id, days_ago, event_type| {
+            let mut event = Event::new(run_id, event_type);
+            event.timestamp = Utc::now() - chrono::Duration::days(days_ago);
+            event
+        };
+        let started = || EventType::StepStarted {
+            step_id: crate::types::StepId::new("build"),
+            attempt: 1,
+        };
+        let finished = || EventType::RunCompleted { duration_secs: 5 };
+
+        // One run entirely in the past, one that started then but finished recently
+        let old = RunId::new();
+        let recent = RunId::new();
+        log.append(at(old, 30, started())).await.unwrap();
+        log.append(at(old, 29, finished())).await.unwrap();
+        log.append(at(recent, 30, started())).await.unwrap();
+        log.append(at(recent, 1, finished())).await.unwrap();
+        log.flush().await.unwrap();
+        assert_eq!(log.get_log_files(&recent).await.unwrap().len(), 2);
+
+        let report = log.compact().await.unwrap();
+        assert_eq!(report.segments_deleted, 2);
+        assert!(log.get_run_events(old).await.unwrap().is_empty());
+        assert_eq!(log.get_run_events(recent).await.unwrap().len(), 2);
+    }
 }
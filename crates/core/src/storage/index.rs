@@ -4,9 +4,16 @@ use crate::types::{
     ProcessTemplate, RoleId, RoleSpec, Routine, RoutineExecution, RoutineId, Run, RunId,
     RunStatus, TemplateId,
 };
+use crate::codec::{self, Codec};
 use anyhow::{Context, Result};
-use redb::{Database, ReadableTable, TableDefinition};
+use redb::{
+    Database, MultimapTableDefinition, ReadableTable,
+    ReadableTableMetadata, TableDefinition, WriteTransaction,
+};
+use serde::Serialize;
+use std::cell::Cell;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 const RUNS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("runs");
@@ -21,11 +28,57 @@ const ROUTINE_EXECUTIONS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::
 const APPROVAL_BOARDS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("approval_boards");
 const APPROVALS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("approvals");
 const CONFIG_CHANGES_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("config_changes");
+// Scratch row written by the startup self-check
+const PROBE_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("probe");
+
+// Secondary indexes over runs: work_item_id -> [RunId], status -> [RunId]
+const RUNS_BY_WORK_ITEM_TABLE: MultimapTableDefinition<&str, &str> =
+    MultimapTableDefinition::new("runs_by_work_item");
+const RUNS_BY_STATUS_TABLE: MultimapTableDefinition<&str, &str> =
+    MultimapTableDefinition::new("runs_by_status");
+// Unique index of caller-supplied external IDs: external_id -> RunId
+const RUNS_BY_EXTERNAL_ID_TABLE: TableDefinition<&str, &str> =
+    TableDefinition::new("runs_by_external_id");
+// Run tags: "key:value" -> [RunId]
+const RUNS_BY_TAG_TABLE: MultimapTableDefinition<&str, &str> =
+    MultimapTableDefinition::new("runs_by_tag");
+
+/// Key used for a run status in the status index
+fn run_status_key(status: RunStatus) -> &'static str {
+    match status {
+        RunStatus::Pending => "pending",
+        RunStatus::Running => "running",
+        RunStatus::Paused => "paused",
+        RunStatus::Completed => "completed",
+        RunStatus::Failed => "failed",
+        RunStatus::Cancelled => "cancelled",
+    }
+}
+
+/// Key used for a run tag in the tag index, e.g. `team:payments`
+///
+/// Colons and backslashes in the tag's key are escaped with a backslash, so the first
+/// unescaped colon always ends the key: `a:b`=`c` becomes `a\:b:c`, distinct from
+/// `a`=`b:c`.
+pub fn run_tag_key(key: &str, value: &str) -> String {
+    let mut tag = String::with_capacity(key.len() + value.len() + 1);
+    for c in key.chars() {
+        if c == ':' || c == '\\' {
+            tag.push('\\');
+        }
+        tag.push(c);
+    }
+    tag.push(':');
+    tag.push_str(value);
+    tag
+}
 
 /// Index store for fast queries using redb
 #[derive(Clone)]
 pub struct RedbIndexStore {
     db: Arc<Database>,
+    codec: Codec,
+    config_generation: Arc<AtomicU64>,
 }
 
 impl RedbIndexStore {
@@ -40,7 +93,7 @@ impl RedbIndexStore {
         // Initialize tables
         let write_txn = db.begin_write().context("Failed to begin write transaction")?;
         {
-            let _runs_table = write_txn
+            let runs_table = write_txn
                 .open_table(RUNS_TABLE)
                 .context("Failed to open runs table")?;
             let _roles_table = write_txn
@@ -76,10 +129,98 @@ impl RedbIndexStore {
             let _config_changes_table = write_txn
                 .open_table(CONFIG_CHANGES_TABLE)
                 .context("Failed to open config changes table")?;
+            let _runs_by_external_id_table = write_txn
+                .open_table(RUNS_BY_EXTERNAL_ID_TABLE)
+                .context("Failed to open runs by external ID table")?;
+            let _runs_by_tag_table = write_txn
+                .open_multimap_table(RUNS_BY_TAG_TABLE)
+                .context("Failed to open runs by tag table")?;
+
+            // Secondary run indexes, backfilled for databases created before they existed
+            let mut by_work_item = write_txn
+                .open_multimap_table(RUNS_BY_WORK_ITEM_TABLE)
+                .context("Failed to open runs by work item table")?;
+            let mut by_status = write_txn
+                .open_multimap_table(RUNS_BY_STATUS_TABLE)
+                .context("Failed to open runs by status table")?;
+
+            if by_status.is_empty().context("Failed to read runs by status table")? {
+                for item in runs_table.iter().context("Failed to iterate runs")? {
+                    let (key, value) = item.context("Failed to read item")?;
+                    let run: Run = codec::decode(value.value())
+                        .context("Failed to deserialize run")?;
+                    by_work_item
+                        .insert(run.work_item_id.as_str(), key.value())
+                        .context("Failed to index run by work item")?;
+                    by_status
+                        .insert(run_status_key(run.status), key.value())
+                        .context("Failed to index run by status")?;
+                }
+            }
         }
         write_txn.commit().context("Failed to commit transaction")?;
 
-        Ok(Self { db: Arc::new(db) })
+        Ok(Self {
+            db: Arc::new(db),
+            codec: Codec::default(),
+            config_generation: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Set the codec used for newly written values. Values are read in whichever
+    /// format they were written, so existing JSON stays readable after a switch.
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Write a scratch value and read it back, to confirm the database is usable
+    pub fn probe(&self) -> Result<()> {
+        let written = uuid::Uuid::new_v4().to_string();
+
+        let write_txn = self.db.begin_write().context("Failed to begin write")?;
+        {
+            let mut table = write_txn.open_table(PROBE_TABLE).context("Failed to open table")?;
+            table
+                .insert("probe", written.as_bytes())
+                .context("Failed to write probe")?;
+        }
+        write_txn.commit().context("Failed to commit probe")?;
+
+        let read_txn = self.db.begin_read().context("Failed to begin read")?;
+        let table = read_txn.open_table(PROBE_TABLE).context("Failed to open table")?;
+        let read = table.get("probe").context("Failed to read probe")?;
+        match read {
+            Some(guard) if guard.value() == written.as_bytes() => Ok(()),
+            _ => Err(anyhow::anyhow!("Probe read back a different value than was written")),
+        }
+    }
+
+    /// Run `f` in a single write transaction. Everything it writes is committed
+    /// together if it returns `Ok`, and nothing is if it returns an error.
+    pub fn transaction<T>(&self, f: impl FnOnce(&IndexTransaction) -> Result<T>) -> Result<T> {
+        let txn = IndexTransaction {
+            txn: self.db.begin_write().context("Failed to begin write")?,
+            codec: self.codec,
+            config_changed: Cell::new(false),
+        };
+
+        match f(&txn) {
+            Ok(value) => {
+                let config_changed = txn.config_changed.get();
+                txn.txn.commit().context("Failed to commit")?;
+                if config_changed {
+                    self.config_generation.fetch_add(1, Ordering::SeqCst);
+                }
+                Ok(value)
+            }
+            Err(e) => {
+                if let Err(abort_err) = txn.txn.abort() {
+                    tracing::error!("Failed to abort index transaction: {}", abort_err);
+                }
+                Err(e)
+            }
+        }
     }
 
     /// Index a run for fast queries
@@ -90,12 +231,80 @@ impl RedbIndexStore {
                 .open_table(RUNS_TABLE)
                 .context("Failed to open table")?;
 
+            let mut by_work_item = write_txn
+                .open_multimap_table(RUNS_BY_WORK_ITEM_TABLE)
+                .context("Failed to open runs by work item table")?;
+            let mut by_status = write_txn
+                .open_multimap_table(RUNS_BY_STATUS_TABLE)
+                .context("Failed to open runs by status table")?;
+            let mut by_external_id = write_txn
+                .open_table(RUNS_BY_EXTERNAL_ID_TABLE)
+                .context("Failed to open runs by external ID table")?;
+            let mut by_tag = write_txn
+                .open_multimap_table(RUNS_BY_TAG_TABLE)
+                .context("Failed to open runs by tag table")?;
+
             let key = run.id.to_string();
-            let value = serde_json::to_vec(run).context("Failed to serialize run")?;
+            let value = self.codec.encode(run).context("Failed to serialize run")?;
+
+            // External IDs are unique; reject one already claimed by another run
+            if let Some(external_id) = &run.external_id {
+                let owner = by_external_id
+                    .get(external_id.as_str())
+                    .context("Failed to read runs by external ID index")?
+                    .map(|guard| guard.value().to_string());
+                if let Some(owner) = owner.filter(|owner| *owner != key) {
+                    return Err(anyhow::anyhow!(
+                        "External ID {} is already used by run {}",
+                        external_id,
+                        owner
+                    ));
+                }
+            }
 
-            table
+            // Drop stale secondary index entries if the run is being re-indexed
+            let previous = table
                 .insert(key.as_str(), value.as_slice())
-                .context("Failed to insert run")?;
+                .context("Failed to insert run")?
+                .map(|guard| codec::decode::<Run>(guard.value()))
+                .transpose()
+                .context("Failed to deserialize previous run")?;
+
+            if let Some(previous) = previous {
+                by_work_item
+                    .remove(previous.work_item_id.as_str(), key.as_str())
+                    .context("Failed to update runs by work item index")?;
+                by_status
+                    .remove(run_status_key(previous.status), key.as_str())
+                    .context("Failed to update runs by status index")?;
+                if let Some(external_id) = &previous.external_id {
+                    by_external_id
+                        .remove(external_id.as_str())
+                        .context("Failed to update runs by external ID index")?;
+                }
+                for (tag_key, tag_value) in &previous.tags {
+                    by_tag
+                        .remove(run_tag_key(tag_key, tag_value).as_str(), key.as_str())
+                        .context("Failed to update runs by tag index")?;
+                }
+            }
+
+            by_work_item
+                .insert(run.work_item_id.as_str(), key.as_str())
+                .context("Failed to index run by work item")?;
+            by_status
+                .insert(run_status_key(run.status), key.as_str())
+                .context("Failed to index run by status")?;
+            if let Some(external_id) = &run.external_id {
+                by_external_id
+                    .insert(external_id.as_str(), key.as_str())
+                    .context("Failed to index run by external ID")?;
+            }
+            for (tag_key, tag_value) in &run.tags {
+                by_tag
+                    .insert(run_tag_key(tag_key, tag_value).as_str(), key.as_str())
+                    .context("Failed to index run by tag")?;
+            }
         }
         write_txn.commit().context("Failed to commit")?;
         Ok(())
@@ -112,13 +321,38 @@ impl RedbIndexStore {
         match value {
             Some(guard) => {
                 let bytes = guard.value();
-                let run: Run = serde_json::from_slice(bytes).context("Failed to deserialize run")?;
+                let run: Run = codec::decode(bytes).context("Failed to deserialize run")?;
                 Ok(Some(run))
             }
             None => Ok(None),
         }
     }
 
+    /// Get a run by its caller-supplied external ID
+    pub fn get_run_by_external_id(&self, external_id: &str) -> Result<Option<Run>> {
+        let read_txn = self.db.begin_read().context("Failed to begin read")?;
+        let index_table = read_txn
+            .open_table(RUNS_BY_EXTERNAL_ID_TABLE)
+            .context("Failed to open runs by external ID table")?;
+
+        let run_key = index_table
+            .get(external_id)
+            .context("Failed to read runs by external ID index")?
+            .map(|guard| guard.value().to_string());
+
+        match run_key {
+            Some(run_key) => {
+                let runs_table = read_txn.open_table(RUNS_TABLE).context("Failed to open table")?;
+                let value = runs_table.get(run_key.as_str()).context("Failed to get run")?;
+                value
+                    .map(|guard| codec::decode(guard.value()))
+                    .transpose()
+                    .context("Failed to deserialize run")
+            }
+            None => Ok(None),
+        }
+    }
+
     /// List all runs (for MVP - in production this would need pagination)
     pub fn list_runs(&self) -> Result<Vec<Run>> {
         let read_txn = self.db.begin_read().context("Failed to begin read")?;
@@ -127,7 +361,7 @@ impl RedbIndexStore {
         let mut runs = Vec::new();
         for item in table.iter().context("Failed to iterate runs")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let run: Run = serde_json::from_slice(value.value())
+            let run: Run = codec::decode(value.value())
                 .context("Failed to deserialize run")?;
             runs.push(run);
         }
@@ -138,40 +372,64 @@ impl RedbIndexStore {
         Ok(runs)
     }
 
+    /// List all runs for a work item
+    pub fn list_runs_by_work_item(&self, work_item_id: &str) -> Result<Vec<Run>> {
+        self.list_runs_from_index(RUNS_BY_WORK_ITEM_TABLE, work_item_id)
+    }
+
+    /// List all runs with the given status
+    pub fn list_runs_by_status(&self, status: RunStatus) -> Result<Vec<Run>> {
+        self.list_runs_from_index(RUNS_BY_STATUS_TABLE, run_status_key(status))
+    }
+
+    /// List all runs carrying a `key:value` tag
+    pub fn list_runs_by_tag(&self, tag: &str) -> Result<Vec<Run>> {
+        self.list_runs_from_index(RUNS_BY_TAG_TABLE, tag)
+    }
+
+    /// Resolve the run IDs stored under `index_key` in a secondary index
+    fn list_runs_from_index(
+        &self,
+        index: MultimapTableDefinition<&str, &str>,
+        index_key: &str,
+    ) -> Result<Vec<Run>> {
+        let read_txn = self.db.begin_read().context("Failed to begin read")?;
+        let index_table = read_txn
+            .open_multimap_table(index)
+            .context("Failed to open index table")?;
+        let runs_table = read_txn.open_table(RUNS_TABLE).context("Failed to open table")?;
+
+        let mut runs = Vec::new();
+        for item in index_table.get(index_key).context("Failed to read index")? {
+            let run_key = item.context("Failed to read index entry")?;
+            if let Some(value) = runs_table
+                .get(run_key.value())
+                .context("Failed to get run")?
+            {
+                let run: Run = codec::decode(value.value())
+                    .context("Failed to deserialize run")?;
+                runs.push(run);
+            }
+        }
+
+        runs.sort_by_key(|r| std::cmp::Reverse(r.started_at));
+
+        Ok(runs)
+    }
+
     /// Update run status
     pub fn update_run_status(&self, run_id: &RunId, status: RunStatus) -> Result<()> {
         let mut run = self
             .get_run(run_id)?
             .context("Run not found")?;
 
-        run.status = status;
-        if matches!(
-            status,
-            RunStatus::Completed | RunStatus::Failed | RunStatus::Cancelled
-        ) {
-            run.completed_at = Some(chrono::Utc::now());
-        }
-
+        run.transition_to(status)?;
         self.index_run(&run)
     }
 
     /// Store a role
     pub fn store_role(&self, role: &RoleSpec) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(ROLES_TABLE)
-                .context("Failed to open table")?;
-
-            let key = &role.id.0;
-            let value = serde_json::to_vec(role).context("Failed to serialize role")?;
-
-            table
-                .insert(key.as_str(), value.as_slice())
-                .context("Failed to insert role")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.store_role(role))
     }
 
     /// Get a role by ID
@@ -184,7 +442,7 @@ impl RedbIndexStore {
         match value {
             Some(guard) => {
                 let bytes = guard.value();
-                let role: RoleSpec = serde_json::from_slice(bytes).context("Failed to deserialize role")?;
+                let role: RoleSpec = codec::decode(bytes).context("Failed to deserialize role")?;
                 Ok(Some(role))
             }
             None => Ok(None),
@@ -199,7 +457,7 @@ impl RedbIndexStore {
         let mut roles = Vec::new();
         for item in table.iter().context("Failed to iterate roles")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let role: RoleSpec = serde_json::from_slice(value.value())
+            let role: RoleSpec = codec::decode(value.value())
                 .context("Failed to deserialize role")?;
             roles.push(role);
         }
@@ -209,37 +467,12 @@ impl RedbIndexStore {
 
     /// Delete a role
     pub fn delete_role(&self, role_id: &RoleId) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(ROLES_TABLE)
-                .context("Failed to open table")?;
-
-            table
-                .remove(role_id.0.as_str())
-                .context("Failed to delete role")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.delete_role(role_id))
     }
 
     /// Store a policy
     pub fn store_policy(&self, policy: &PolicySpec) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(POLICIES_TABLE)
-                .context("Failed to open table")?;
-
-            let key = &policy.id.0;
-            let value = serde_json::to_vec(policy).context("Failed to serialize policy")?;
-
-            table
-                .insert(key.as_str(), value.as_slice())
-                .context("Failed to insert policy")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.store_policy(policy))
     }
 
     /// Get a policy by ID
@@ -252,7 +485,7 @@ impl RedbIndexStore {
         match value {
             Some(guard) => {
                 let bytes = guard.value();
-                let policy: PolicySpec = serde_json::from_slice(bytes).context("Failed to deserialize policy")?;
+                let policy: PolicySpec = codec::decode(bytes).context("Failed to deserialize policy")?;
                 Ok(Some(policy))
             }
             None => Ok(None),
@@ -267,7 +500,7 @@ impl RedbIndexStore {
         let mut policies = Vec::new();
         for item in table.iter().context("Failed to iterate policies")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let policy: PolicySpec = serde_json::from_slice(value.value())
+            let policy: PolicySpec = codec::decode(value.value())
                 .context("Failed to deserialize policy")?;
             policies.push(policy);
         }
@@ -277,37 +510,12 @@ impl RedbIndexStore {
 
     /// Delete a policy
     pub fn delete_policy(&self, policy_id: &PolicyId) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(POLICIES_TABLE)
-                .context("Failed to open table")?;
-
-            table
-                .remove(policy_id.0.as_str())
-                .context("Failed to delete policy")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.delete_policy(policy_id))
     }
 
     /// Store an organization
     pub fn store_organization(&self, org: &Organization) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(ORGS_TABLE)
-                .context("Failed to open table")?;
-
-            let key = &org.id.0;
-            let value = serde_json::to_vec(org).context("Failed to serialize organization")?;
-
-            table
-                .insert(key.as_str(), value.as_slice())
-                .context("Failed to insert organization")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.store_organization(org))
     }
 
     /// Get an organization by ID
@@ -320,7 +528,7 @@ impl RedbIndexStore {
         match value {
             Some(guard) => {
                 let bytes = guard.value();
-                let org: Organization = serde_json::from_slice(bytes).context("Failed to deserialize organization")?;
+                let org: Organization = codec::decode(bytes).context("Failed to deserialize organization")?;
                 Ok(Some(org))
             }
             None => Ok(None),
@@ -335,7 +543,7 @@ impl RedbIndexStore {
         let mut orgs = Vec::new();
         for item in table.iter().context("Failed to iterate organizations")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let org: Organization = serde_json::from_slice(value.value())
+            let org: Organization = codec::decode(value.value())
                 .context("Failed to deserialize organization")?;
             orgs.push(org);
         }
@@ -345,37 +553,12 @@ impl RedbIndexStore {
 
     /// Delete an organization
     pub fn delete_organization(&self, org_id: &OrgId) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(ORGS_TABLE)
-                .context("Failed to open table")?;
-
-            table
-                .remove(org_id.0.as_str())
-                .context("Failed to delete organization")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.delete_organization(org_id))
     }
 
     /// Store a process template
     pub fn store_template(&self, template: &ProcessTemplate) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(TEMPLATES_TABLE)
-                .context("Failed to open table")?;
-
-            let key = &template.id.0;
-            let value = serde_json::to_vec(template).context("Failed to serialize template")?;
-
-            table
-                .insert(key.as_str(), value.as_slice())
-                .context("Failed to insert template")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.store_template(template))
     }
 
     /// Get a template by ID
@@ -388,7 +571,7 @@ impl RedbIndexStore {
         match value {
             Some(guard) => {
                 let bytes = guard.value();
-                let template: ProcessTemplate = serde_json::from_slice(bytes).context("Failed to deserialize template")?;
+                let template: ProcessTemplate = codec::decode(bytes).context("Failed to deserialize template")?;
                 Ok(Some(template))
             }
             None => Ok(None),
@@ -403,7 +586,7 @@ impl RedbIndexStore {
         let mut templates = Vec::new();
         for item in table.iter().context("Failed to iterate templates")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let template: ProcessTemplate = serde_json::from_slice(value.value())
+            let template: ProcessTemplate = codec::decode(value.value())
                 .context("Failed to deserialize template")?;
             templates.push(template);
         }
@@ -413,37 +596,12 @@ impl RedbIndexStore {
 
     /// Delete a template
     pub fn delete_template(&self, template_id: &TemplateId) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(TEMPLATES_TABLE)
-                .context("Failed to open table")?;
-
-            table
-                .remove(template_id.0.as_str())
-                .context("Failed to delete template")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.delete_template(template_id))
     }
 
     /// Store a capacity source
     pub fn store_capacity_source(&self, source: &CapacitySource) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(CAPACITY_SOURCES_TABLE)
-                .context("Failed to open table")?;
-
-            let key = &source.id.0;
-            let value = serde_json::to_vec(source).context("Failed to serialize capacity source")?;
-
-            table
-                .insert(key.as_str(), value.as_slice())
-                .context("Failed to insert capacity source")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.store_capacity_source(source))
     }
 
     /// Get a capacity source by ID
@@ -456,7 +614,7 @@ impl RedbIndexStore {
         match value {
             Some(guard) => {
                 let bytes = guard.value();
-                let source: CapacitySource = serde_json::from_slice(bytes)
+                let source: CapacitySource = codec::decode(bytes)
                     .context("Failed to deserialize capacity source")?;
                 Ok(Some(source))
             }
@@ -472,7 +630,7 @@ impl RedbIndexStore {
         let mut sources = Vec::new();
         for item in table.iter().context("Failed to iterate capacity sources")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let source: CapacitySource = serde_json::from_slice(value.value())
+            let source: CapacitySource = codec::decode(value.value())
                 .context("Failed to deserialize capacity source")?;
             sources.push(source);
         }
@@ -496,8 +654,9 @@ impl RedbIndexStore {
         Ok(())
     }
 
-    /// Store capacity usage record
-    pub fn store_capacity_usage(&self, usage: &CapacityUsage) -> Result<()> {
+    /// Store capacity usage record. Returns false, leaving the stored record as is, if
+    /// one with the same id was already stored.
+    pub fn store_capacity_usage(&self, usage: &CapacityUsage) -> Result<bool> {
         let write_txn = self.db.begin_write().context("Failed to begin write")?;
         {
             let mut table = write_txn
@@ -505,14 +664,17 @@ impl RedbIndexStore {
                 .context("Failed to open table")?;
 
             let key = &usage.id;
-            let value = serde_json::to_vec(usage).context("Failed to serialize capacity usage")?;
+            if table.get(key.as_str()).context("Failed to read capacity usage")?.is_some() {
+                return Ok(false);
+            }
+            let value = self.codec.encode(usage).context("Failed to serialize capacity usage")?;
 
             table
                 .insert(key.as_str(), value.as_slice())
                 .context("Failed to insert capacity usage")?;
         }
         write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        Ok(true)
     }
 
     /// List all capacity usage records
@@ -523,7 +685,7 @@ impl RedbIndexStore {
         let mut usage_records = Vec::new();
         for item in table.iter().context("Failed to iterate capacity usage")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let usage: CapacityUsage = serde_json::from_slice(value.value())
+            let usage: CapacityUsage = codec::decode(value.value())
                 .context("Failed to deserialize capacity usage")?;
             usage_records.push(usage);
         }
@@ -536,21 +698,7 @@ impl RedbIndexStore {
 
     /// Store a routine
     pub fn store_routine(&self, routine: &Routine) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(ROUTINES_TABLE)
-                .context("Failed to open table")?;
-
-            let key = &routine.id.0;
-            let value = serde_json::to_vec(routine).context("Failed to serialize routine")?;
-
-            table
-                .insert(key.as_str(), value.as_slice())
-                .context("Failed to insert routine")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.store_routine(routine))
     }
 
     /// Get a routine by ID
@@ -563,7 +711,7 @@ impl RedbIndexStore {
         match value {
             Some(guard) => {
                 let bytes = guard.value();
-                let routine: Routine = serde_json::from_slice(bytes).context("Failed to deserialize routine")?;
+                let routine: Routine = codec::decode(bytes).context("Failed to deserialize routine")?;
                 Ok(Some(routine))
             }
             None => Ok(None),
@@ -578,7 +726,7 @@ impl RedbIndexStore {
         let mut routines = Vec::new();
         for item in table.iter().context("Failed to iterate routines")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let routine: Routine = serde_json::from_slice(value.value())
+            let routine: Routine = codec::decode(value.value())
                 .context("Failed to deserialize routine")?;
             routines.push(routine);
         }
@@ -611,7 +759,7 @@ impl RedbIndexStore {
                 .context("Failed to open table")?;
 
             let key = &execution.id;
-            let value = serde_json::to_vec(execution).context("Failed to serialize execution")?;
+            let value = self.codec.encode(execution).context("Failed to serialize execution")?;
 
             table
                 .insert(key.as_str(), value.as_slice())
@@ -629,7 +777,7 @@ impl RedbIndexStore {
         let mut executions = Vec::new();
         for item in table.iter().context("Failed to iterate executions")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let execution: RoutineExecution = serde_json::from_slice(value.value())
+            let execution: RoutineExecution = codec::decode(value.value())
                 .context("Failed to deserialize execution")?;
             executions.push(execution);
         }
@@ -640,21 +788,7 @@ impl RedbIndexStore {
 
     /// Store approval board
     pub fn store_approval_board(&self, board: &ApprovalBoard) -> Result<()> {
-        let write_txn = self.db.begin_write().context("Failed to begin write")?;
-        {
-            let mut table = write_txn
-                .open_table(APPROVAL_BOARDS_TABLE)
-                .context("Failed to open table")?;
-
-            let key = &board.id.0;
-            let value = serde_json::to_vec(board).context("Failed to serialize board")?;
-
-            table
-                .insert(key.as_str(), value.as_slice())
-                .context("Failed to insert board")?;
-        }
-        write_txn.commit().context("Failed to commit")?;
-        Ok(())
+        self.transaction(|txn| txn.store_approval_board(board))
     }
 
     /// Get approval board by ID
@@ -667,7 +801,7 @@ impl RedbIndexStore {
         match value {
             Some(guard) => {
                 let bytes = guard.value();
-                let board: ApprovalBoard = serde_json::from_slice(bytes).context("Failed to deserialize board")?;
+                let board: ApprovalBoard = codec::decode(bytes).context("Failed to deserialize board")?;
                 Ok(Some(board))
             }
             None => Ok(None),
@@ -682,7 +816,7 @@ impl RedbIndexStore {
         let mut boards = Vec::new();
         for item in table.iter().context("Failed to iterate boards")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let board: ApprovalBoard = serde_json::from_slice(value.value())
+            let board: ApprovalBoard = codec::decode(value.value())
                 .context("Failed to deserialize board")?;
             boards.push(board);
         }
@@ -715,7 +849,7 @@ impl RedbIndexStore {
                 .context("Failed to open table")?;
 
             let key = &approval.id.0;
-            let value = serde_json::to_vec(approval).context("Failed to serialize approval")?;
+            let value = self.codec.encode(approval).context("Failed to serialize approval")?;
 
             table
                 .insert(key.as_str(), value.as_slice())
@@ -735,7 +869,7 @@ impl RedbIndexStore {
         match value {
             Some(guard) => {
                 let bytes = guard.value();
-                let approval: Approval = serde_json::from_slice(bytes).context("Failed to deserialize approval")?;
+                let approval: Approval = codec::decode(bytes).context("Failed to deserialize approval")?;
                 Ok(Some(approval))
             }
             None => Ok(None),
@@ -750,7 +884,7 @@ impl RedbIndexStore {
         let mut approvals = Vec::new();
         for item in table.iter().context("Failed to iterate approvals")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let approval: Approval = serde_json::from_slice(value.value())
+            let approval: Approval = codec::decode(value.value())
                 .context("Failed to deserialize approval")?;
             approvals.push(approval);
         }
@@ -768,7 +902,7 @@ impl RedbIndexStore {
                 .context("Failed to open table")?;
 
             let key = &change.id.0;
-            let value = serde_json::to_vec(change).context("Failed to serialize change")?;
+            let value = self.codec.encode(change).context("Failed to serialize change")?;
 
             table
                 .insert(key.as_str(), value.as_slice())
@@ -788,7 +922,7 @@ impl RedbIndexStore {
         match value {
             Some(guard) => {
                 let bytes = guard.value();
-                let change: ConfigChange = serde_json::from_slice(bytes).context("Failed to deserialize change")?;
+                let change: ConfigChange = codec::decode(bytes).context("Failed to deserialize change")?;
                 Ok(Some(change))
             }
             None => Ok(None),
@@ -803,7 +937,7 @@ impl RedbIndexStore {
         let mut changes = Vec::new();
         for item in table.iter().context("Failed to iterate changes")? {
             let (_key, value) = item.context("Failed to read item")?;
-            let change: ConfigChange = serde_json::from_slice(value.value())
+            let change: ConfigChange = codec::decode(value.value())
                 .context("Failed to deserialize change")?;
             changes.push(change);
         }
@@ -813,6 +947,137 @@ impl RedbIndexStore {
     }
 }
 
+type ValueTable = TableDefinition<'static, &'static str, &'static [u8]>;
+
+/// Writes made inside `RedbIndexStore::transaction`, visible to others only once it commits
+pub struct IndexTransaction {
+    txn: WriteTransaction,
+    codec: Codec,
+    /// Whether a role, policy or organization was written
+    config_changed: Cell<bool>,
+}
+
+impl IndexTransaction {
+    fn insert<T: Serialize>(&self, table: ValueTable, key: &str, value: &T, entity: &str) -> Result<()> {
+        let value = self
+            .codec
+            .encode(value)
+            .with_context(|| format!("Failed to serialize {}", entity))?;
+        let mut table = self.txn.open_table(table).context("Failed to open table")?;
+        table
+            .insert(key, value.as_slice())
+            .with_context(|| format!("Failed to insert {}", entity))?;
+        Ok(())
+    }
+
+    fn remove(&self, table: ValueTable, key: &str, entity: &str) -> Result<()> {
+        let mut table = self.txn.open_table(table).context("Failed to open table")?;
+        table
+            .remove(key)
+            .with_context(|| format!("Failed to delete {}", entity))?;
+        Ok(())
+    }
+}
+
+impl IndexWrites for IndexTransaction {
+    /// Store a role
+    fn store_role(&self, role: &RoleSpec) -> Result<()> {
+        self.config_changed.set(true);
+        self.insert(ROLES_TABLE, &role.id.0, role, "role")
+    }
+
+    /// Delete a role
+    fn delete_role(&self, role_id: &RoleId) -> Result<()> {
+        self.config_changed.set(true);
+        self.remove(ROLES_TABLE, &role_id.0, "role")
+    }
+
+    /// Store a policy
+    fn store_policy(&self, policy: &PolicySpec) -> Result<()> {
+        self.config_changed.set(true);
+        self.insert(POLICIES_TABLE, &policy.id.0, policy, "policy")
+    }
+
+    /// Delete a policy
+    fn delete_policy(&self, policy_id: &PolicyId) -> Result<()> {
+        self.config_changed.set(true);
+        self.remove(POLICIES_TABLE, &policy_id.0, "policy")
+    }
+
+    /// Store an organization
+    fn store_organization(&self, org: &Organization) -> Result<()> {
+        self.config_changed.set(true);
+        self.insert(ORGS_TABLE, &org.id.0, org, "organization")
+    }
+
+    /// Delete an organization
+    fn delete_organization(&self, org_id: &OrgId) -> Result<()> {
+        self.config_changed.set(true);
+        self.remove(ORGS_TABLE, &org_id.0, "organization")
+    }
+
+    /// Store a process template
+    fn store_template(&self, template: &ProcessTemplate) -> Result<()> {
+        self.insert(TEMPLATES_TABLE, &template.id.0, template, "template")
+    }
+
+    /// Delete a template
+    fn delete_template(&self, template_id: &TemplateId) -> Result<()> {
+        self.remove(TEMPLATES_TABLE, &template_id.0, "template")
+    }
+
+    /// Store a capacity source
+    fn store_capacity_source(&self, source: &CapacitySource) -> Result<()> {
+        self.insert(CAPACITY_SOURCES_TABLE, &source.id.0, source, "capacity source")
+    }
+
+    /// Store a routine
+    fn store_routine(&self, routine: &Routine) -> Result<()> {
+        self.insert(ROUTINES_TABLE, &routine.id.0, routine, "routine")
+    }
+
+    /// Store approval board
+    fn store_approval_board(&self, board: &ApprovalBoard) -> Result<()> {
+        self.insert(APPROVAL_BOARDS_TABLE, &board.id.0, board, "board")
+    }
+}
+
+/// Configuration writes that can be grouped into one all-or-nothing transaction
+pub trait IndexWrites {
+    /// Store a role
+    fn store_role(&self, role: &RoleSpec) -> Result<()>;
+
+    /// Delete a role
+    fn delete_role(&self, role_id: &RoleId) -> Result<()>;
+
+    /// Store a policy
+    fn store_policy(&self, policy: &PolicySpec) -> Result<()>;
+
+    /// Delete a policy
+    fn delete_policy(&self, policy_id: &PolicyId) -> Result<()>;
+
+    /// Store an organization
+    fn store_organization(&self, org: &Organization) -> Result<()>;
+
+    /// Delete an organization
+    fn delete_organization(&self, org_id: &OrgId) -> Result<()>;
+
+    /// Store a process template
+    fn store_template(&self, template: &ProcessTemplate) -> Result<()>;
+
+    /// Delete a template
+    fn delete_template(&self, template_id: &TemplateId) -> Result<()>;
+
+    /// Store a capacity source
+    fn store_capacity_source(&self, source: &CapacitySource) -> Result<()>;
+
+    /// Store a routine
+    fn store_routine(&self, routine: &Routine) -> Result<()>;
+
+    /// Store an approval board
+    fn store_approval_board(&self, board: &ApprovalBoard) -> Result<()>;
+}
+
 /// Trait for index storage
 pub trait IndexStore: Send + Sync {
     /// Index a run
@@ -821,11 +1086,17 @@ pub trait IndexStore: Send + Sync {
     /// Get a run by ID
     fn get_run(&self, run_id: &RunId) -> Result<Option<Run>>;
 
+    /// Get a run by its caller-supplied external ID
+    fn get_run_by_external_id(&self, external_id: &str) -> Result<Option<Run>>;
+
     /// List all runs
     fn list_runs(&self) -> Result<Vec<Run>>;
 
     /// Update run status
     fn update_run_status(&self, run_id: &RunId, status: RunStatus) -> Result<()>;
+
+    /// Get a role by ID
+    fn get_role(&self, role_id: &RoleId) -> Result<Option<RoleSpec>>;
 }
 
 impl IndexStore for RedbIndexStore {
@@ -837,6 +1108,10 @@ impl IndexStore for RedbIndexStore {
         RedbIndexStore::get_run(self, run_id)
     }
 
+    fn get_run_by_external_id(&self, external_id: &str) -> Result<Option<Run>> {
+        RedbIndexStore::get_run_by_external_id(self, external_id)
+    }
+
     fn list_runs(&self) -> Result<Vec<Run>> {
         RedbIndexStore::list_runs(self)
     }
@@ -844,11 +1119,337 @@ impl IndexStore for RedbIndexStore {
     fn update_run_status(&self, run_id: &RunId, status: RunStatus) -> Result<()> {
         RedbIndexStore::update_run_status(self, run_id, status)
     }
-}
+
+    fn get_role(&self, role_id: &RoleId) -> Result<Option<RoleSpec>> {
+        RedbIndexStore::get_role(self, role_id)
+    }
+}
+
+/// Full index surface, so the server can run against any storage backend.
+///
+/// `RedbIndexStore` is the embedded single-node backend and `InMemoryIndexStore` holds
+/// everything in memory for tests. Shared backends such as Postgres implement this
+/// trait behind their own feature flag.
+pub trait IndexBackend: IndexStore {
+    /// List all runs for a work item
+    fn list_runs_by_work_item(&self, work_item_id: &str) -> Result<Vec<Run>>;
+
+    /// List all runs with the given status
+    fn list_runs_by_status(&self, status: RunStatus) -> Result<Vec<Run>>;
+
+    /// List all runs carrying a `key:value` tag
+    fn list_runs_by_tag(&self, tag: &str) -> Result<Vec<Run>>;
+
+    /// Store a role
+    fn store_role(&self, role: &RoleSpec) -> Result<()>;
+
+    /// List all roles
+    fn list_roles(&self) -> Result<Vec<RoleSpec>>;
+
+    /// Delete a role
+    fn delete_role(&self, role_id: &RoleId) -> Result<()>;
+
+    /// Store a policy
+    fn store_policy(&self, policy: &PolicySpec) -> Result<()>;
+
+    /// Get a policy by ID
+    fn get_policy(&self, policy_id: &PolicyId) -> Result<Option<PolicySpec>>;
+
+    /// List all policies
+    fn list_policies(&self) -> Result<Vec<PolicySpec>>;
+
+    /// Delete a policy
+    fn delete_policy(&self, policy_id: &PolicyId) -> Result<()>;
+
+    /// Store an organization
+    fn store_organization(&self, org: &Organization) -> Result<()>;
+
+    /// Get an organization by ID
+    fn get_organization(&self, org_id: &OrgId) -> Result<Option<Organization>>;
+
+    /// List all organizations
+    fn list_organizations(&self) -> Result<Vec<Organization>>;
+
+    /// Delete an organization
+    fn delete_organization(&self, org_id: &OrgId) -> Result<()>;
+
+    /// Store a process template
+    fn store_template(&self, template: &ProcessTemplate) -> Result<()>;
+
+    /// Get a process template by ID
+    fn get_template(&self, template_id: &TemplateId) -> Result<Option<ProcessTemplate>>;
+
+    /// List all process templates
+    fn list_templates(&self) -> Result<Vec<ProcessTemplate>>;
+
+    /// Delete a process template
+    fn delete_template(&self, template_id: &TemplateId) -> Result<()>;
+
+    /// Store a capacity source
+    fn store_capacity_source(&self, source: &CapacitySource) -> Result<()>;
+
+    /// Get a capacity source by ID
+    fn get_capacity_source(&self, source_id: &CapacitySourceId) -> Result<Option<CapacitySource>>;
+
+    /// List all capacity sources
+    fn list_capacity_sources(&self) -> Result<Vec<CapacitySource>>;
+
+    /// Delete a capacity source
+    fn delete_capacity_source(&self, source_id: &CapacitySourceId) -> Result<()>;
+
+    /// Record capacity usage, unless a record with its id exists. Returns whether it
+    /// was stored.
+    fn store_capacity_usage(&self, usage: &CapacityUsage) -> Result<bool>;
+
+    /// List capacity usage, most recent first
+    fn list_capacity_usage(&self) -> Result<Vec<CapacityUsage>>;
+
+    /// Store a routine
+    fn store_routine(&self, routine: &Routine) -> Result<()>;
+
+    /// Get a routine by ID
+    fn get_routine(&self, routine_id: &RoutineId) -> Result<Option<Routine>>;
+
+    /// List all routines
+    fn list_routines(&self) -> Result<Vec<Routine>>;
+
+    /// Delete a routine
+    fn delete_routine(&self, routine_id: &RoutineId) -> Result<()>;
+
+    /// Record a routine execution
+    fn store_routine_execution(&self, execution: &RoutineExecution) -> Result<()>;
+
+    /// List routine executions, most recent first
+    fn list_routine_executions(&self) -> Result<Vec<RoutineExecution>>;
+
+    /// Store an approval board
+    fn store_approval_board(&self, board: &ApprovalBoard) -> Result<()>;
+
+    /// Get an approval board by ID
+    fn get_approval_board(&self, board_id: &ApprovalBoardId) -> Result<Option<ApprovalBoard>>;
+
+    /// List all approval boards
+    fn list_approval_boards(&self) -> Result<Vec<ApprovalBoard>>;
+
+    /// Delete an approval board
+    fn delete_approval_board(&self, board_id: &ApprovalBoardId) -> Result<()>;
+
+    /// Store an approval
+    fn store_approval(&self, approval: &Approval) -> Result<()>;
+
+    /// Get an approval by ID
+    fn get_approval(&self, approval_id: &ApprovalId) -> Result<Option<Approval>>;
+
+    /// List approvals, most recent first
+    fn list_approvals(&self) -> Result<Vec<Approval>>;
+
+    /// Store a config change
+    fn store_config_change(&self, change: &ConfigChange) -> Result<()>;
+
+    /// Get a config change by ID
+    fn get_config_change(&self, change_id: &ConfigChangeId) -> Result<Option<ConfigChange>>;
+
+    /// List config changes, most recent first
+    fn list_config_changes(&self) -> Result<Vec<ConfigChange>>;
+
+    /// Counter bumped whenever a role, policy or organization is written, so caches
+    /// derived from them can tell when they're stale
+    fn config_generation(&self) -> u64;
+
+    /// Run `f` in a single write transaction: everything it writes is committed
+    /// together if it returns `Ok`, and nothing is if it returns an error.
+    /// Callers holding a `dyn IndexBackend` usually want `transaction` instead.
+    fn write_transaction(&self, f: &mut dyn FnMut(&dyn IndexWrites) -> Result<()>) -> Result<()>;
+}
+
+impl dyn IndexBackend + '_ {
+    /// Run `f` in a single write transaction, returning its result
+    pub fn transaction<T>(&self, f: impl FnOnce(&dyn IndexWrites) -> Result<T>) -> Result<T> {
+        let mut f = Some(f);
+        let mut value = None;
+        self.write_transaction(&mut |txn| {
+            let f = f.take().context("Index transaction ran twice")?;
+            value = Some(f(txn)?);
+            Ok(())
+        })?;
+        value.context("Index transaction did not run")
+    }
+}
+
+impl IndexBackend for RedbIndexStore {
+    fn list_runs_by_work_item(&self, work_item_id: &str) -> Result<Vec<Run>> {
+        RedbIndexStore::list_runs_by_work_item(self, work_item_id)
+    }
+
+    fn list_runs_by_status(&self, status: RunStatus) -> Result<Vec<Run>> {
+        RedbIndexStore::list_runs_by_status(self, status)
+    }
+
+    fn list_runs_by_tag(&self, tag: &str) -> Result<Vec<Run>> {
+        RedbIndexStore::list_runs_by_tag(self, tag)
+    }
+
+    fn store_role(&self, role: &RoleSpec) -> Result<()> {
+        RedbIndexStore::store_role(self, role)
+    }
+
+    fn list_roles(&self) -> Result<Vec<RoleSpec>> {
+        RedbIndexStore::list_roles(self)
+    }
+
+    fn delete_role(&self, role_id: &RoleId) -> Result<()> {
+        RedbIndexStore::delete_role(self, role_id)
+    }
+
+    fn store_policy(&self, policy: &PolicySpec) -> Result<()> {
+        RedbIndexStore::store_policy(self, policy)
+    }
+
+    fn get_policy(&self, policy_id: &PolicyId) -> Result<Option<PolicySpec>> {
+        RedbIndexStore::get_policy(self, policy_id)
+    }
+
+    fn list_policies(&self) -> Result<Vec<PolicySpec>> {
+        RedbIndexStore::list_policies(self)
+    }
+
+    fn delete_policy(&self, policy_id: &PolicyId) -> Result<()> {
+        RedbIndexStore::delete_policy(self, policy_id)
+    }
+
+    fn store_organization(&self, org: &Organization) -> Result<()> {
+        RedbIndexStore::store_organization(self, org)
+    }
+
+    fn get_organization(&self, org_id: &OrgId) -> Result<Option<Organization>> {
+        RedbIndexStore::get_organization(self, org_id)
+    }
+
+    fn list_organizations(&self) -> Result<Vec<Organization>> {
+        RedbIndexStore::list_organizations(self)
+    }
+
+    fn delete_organization(&self, org_id: &OrgId) -> Result<()> {
+        RedbIndexStore::delete_organization(self, org_id)
+    }
+
+    fn store_template(&self, template: &ProcessTemplate) -> Result<()> {
+        RedbIndexStore::store_template(self, template)
+    }
+
+    fn get_template(&self, template_id: &TemplateId) -> Result<Option<ProcessTemplate>> {
+        RedbIndexStore::get_template(self, template_id)
+    }
+
+    fn list_templates(&self) -> Result<Vec<ProcessTemplate>> {
+        RedbIndexStore::list_templates(self)
+    }
+
+    fn delete_template(&self, template_id: &TemplateId) -> Result<()> {
+        RedbIndexStore::delete_template(self, template_id)
+    }
+
+    fn store_capacity_source(&self, source: &CapacitySource) -> Result<()> {
+        RedbIndexStore::store_capacity_source(self, source)
+    }
+
+    fn get_capacity_source(&self, source_id: &CapacitySourceId) -> Result<Option<CapacitySource>> {
+        RedbIndexStore::get_capacity_source(self, source_id)
+    }
+
+    fn list_capacity_sources(&self) -> Result<Vec<CapacitySource>> {
+        RedbIndexStore::list_capacity_sources(self)
+    }
+
+    fn delete_capacity_source(&self, source_id: &CapacitySourceId) -> Result<()> {
+        RedbIndexStore::delete_capacity_source(self, source_id)
+    }
+
+    fn store_capacity_usage(&self, usage: &CapacityUsage) -> Result<bool> {
+        RedbIndexStore::store_capacity_usage(self, usage)
+    }
+
+    fn list_capacity_usage(&self) -> Result<Vec<CapacityUsage>> {
+        RedbIndexStore::list_capacity_usage(self)
+    }
+
+    fn store_routine(&self, routine: &Routine) -> Result<()> {
+        RedbIndexStore::store_routine(self, routine)
+    }
+
+    fn get_routine(&self, routine_id: &RoutineId) -> Result<Option<Routine>> {
+        RedbIndexStore::get_routine(self, routine_id)
+    }
+
+    fn list_routines(&self) -> Result<Vec<Routine>> {
+        RedbIndexStore::list_routines(self)
+    }
+
+    fn delete_routine(&self, routine_id: &RoutineId) -> Result<()> {
+        RedbIndexStore::delete_routine(self, routine_id)
+    }
+
+    fn store_routine_execution(&self, execution: &RoutineExecution) -> Result<()> {
+        RedbIndexStore::store_routine_execution(self, execution)
+    }
+
+    fn list_routine_executions(&self) -> Result<Vec<RoutineExecution>> {
+        RedbIndexStore::list_routine_executions(self)
+    }
+
+    fn store_approval_board(&self, board: &ApprovalBoard) -> Result<()> {
+        RedbIndexStore::store_approval_board(self, board)
+    }
+
+    fn get_approval_board(&self, board_id: &ApprovalBoardId) -> Result<Option<ApprovalBoard>> {
+        RedbIndexStore::get_approval_board(self, board_id)
+    }
+
+    fn list_approval_boards(&self) -> Result<Vec<ApprovalBoard>> {
+        RedbIndexStore::list_approval_boards(self)
+    }
+
+    fn delete_approval_board(&self, board_id: &ApprovalBoardId) -> Result<()> {
+        RedbIndexStore::delete_approval_board(self, board_id)
+    }
+
+    fn store_approval(&self, approval: &Approval) -> Result<()> {
+        RedbIndexStore::store_approval(self, approval)
+    }
+
+    fn get_approval(&self, approval_id: &ApprovalId) -> Result<Option<Approval>> {
+        RedbIndexStore::get_approval(self, approval_id)
+    }
+
+    fn list_approvals(&self) -> Result<Vec<Approval>> {
+        RedbIndexStore::list_approvals(self)
+    }
+
+    fn store_config_change(&self, change: &ConfigChange) -> Result<()> {
+        RedbIndexStore::store_config_change(self, change)
+    }
+
+    fn get_config_change(&self, change_id: &ConfigChangeId) -> Result<Option<ConfigChange>> {
+        RedbIndexStore::get_config_change(self, change_id)
+    }
+
+    fn list_config_changes(&self) -> Result<Vec<ConfigChange>> {
+        RedbIndexStore::list_config_changes(self)
+    }
+
+    fn config_generation(&self) -> u64 {
+        self.config_generation.load(Ordering::SeqCst)
+    }
+
+    fn write_transaction(&self, f: &mut dyn FnMut(&dyn IndexWrites) -> Result<()>) -> Result<()> {
+        self.transaction(|txn| f(txn))
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
     use tempfile::NamedTempFile;
 
     #[test]
@@ -863,6 +1464,14 @@ mod tests {
             started_at: chrono::Utc::now(),
             completed_at: None,
             steps: vec![],
+            parent_run_id: None,
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
         };
 
         store.index_run(&run).unwrap();
@@ -880,4 +1489,310 @@ mod tests {
         assert_eq!(updated.status, RunStatus::Completed);
         assert!(updated.completed_at.is_some());
     }
+
+    fn role(id: &str) -> RoleSpec {
+        RoleSpec {
+            id: RoleId::new(id),
+            name: id.to_string(),
+            description: String::new(),
+            prompt_template: String::new(),
+            allowed_tools: vec![],
+            budgets: crate::types::RoleBudgets {
+                daily_tokens: None,
+                daily_cost_cents: None,
+                ..Default::default()
+            },
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        }
+    }
+
+    fn policy(id: &str) -> PolicySpec {
+        PolicySpec {
+            id: PolicyId(id.to_string()),
+            name: id.to_string(),
+            description: String::new(),
+            rules: vec![],
+        }
+    }
+
+    #[test]
+    fn test_failed_transaction_persists_nothing() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let store = RedbIndexStore::new(temp_file.path().to_path_buf()).unwrap();
+
+        let result = store.transaction(|txn| {
+            txn.store_role(&role("engineer"))?;
+            txn.store_policy(&policy("default"))?;
+            Err::<(), _>(anyhow::anyhow!("Organization is invalid"))
+        });
+        assert_eq!(result.unwrap_err().to_string(), "Organization is invalid");
+
+        assert!(store.get_role(&RoleId::new("engineer")).unwrap().is_none());
+        assert!(store.list_roles().unwrap().is_empty());
+        assert!(store.list_policies().unwrap().is_empty());
+
+        // The same writes land together once the closure succeeds
+        let stored = store
+            .transaction(|txn| {
+                txn.store_role(&role("engineer"))?;
+                txn.store_policy(&policy("default"))?;
+                Ok(2)
+            })
+            .unwrap();
+        assert_eq!(stored, 2);
+        assert!(store.get_role(&RoleId::new("engineer")).unwrap().is_some());
+        assert!(store.get_policy(&PolicyId("default".to_string())).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_run_secondary_indexes() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let store = RedbIndexStore::new(temp_file.path().to_path_buf()).unwrap();
+
+        let make_run = |work_item_id: &str| Run {
+            id: RunId::new(),
+            work_item_id: work_item_id.to_string(),
+            status: RunStatus::Running,
+            started_at: chrono::Utc::now(),
+            completed_at: None,
+            steps: vec![],
+            parent_run_id: None,
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        };
+
+        let run1 = make_run("job-a");
+        let run2 = make_run("job-a");
+        let run3 = make_run("job-b");
+        for run in [&run1, &run2, &run3] {
+            store.index_run(run).unwrap();
+        }
+
+        assert_eq!(store.list_runs_by_work_item("job-a").unwrap().len(), 2);
+        assert_eq!(store.list_runs_by_work_item("job-b").unwrap().len(), 1);
+        assert!(store.list_runs_by_work_item("job-c").unwrap().is_empty());
+        assert_eq!(store.list_runs_by_status(RunStatus::Running).unwrap().len(), 3);
+
+        // Status transitions move runs between status buckets
+        store.update_run_status(&run1.id, RunStatus::Failed).unwrap();
+        store.update_run_status(&run3.id, RunStatus::Completed).unwrap();
+
+        let running = store.list_runs_by_status(RunStatus::Running).unwrap();
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].id, run2.id);
+
+        let failed = store.list_runs_by_status(RunStatus::Failed).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].id, run1.id);
+        assert_eq!(failed[0].status, RunStatus::Failed);
+
+        assert_eq!(store.list_runs_by_status(RunStatus::Completed).unwrap().len(), 1);
+
+        // Work item index is unaffected by status changes
+        assert_eq!(store.list_runs_by_work_item("job-a").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_run_external_id_index() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let store = RedbIndexStore::new(temp_file.path().to_path_buf()).unwrap();
+
+        let make_run = |external_id: &str| Run {
+            id: RunId::new(),
+            work_item_id: "job-a".to_string(),
+            status: RunStatus::Running,
+            started_at: chrono::Utc::now(),
+            completed_at: None,
+            steps: vec![],
+            parent_run_id: None,
+            external_id: Some(external_id.to_string()),
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        };
+
+        let run = make_run("TICKET-1");
+        store.index_run(&run).unwrap();
+
+        // Re-indexing the same run keeps its external ID
+        store.update_run_status(&run.id, RunStatus::Completed).unwrap();
+        let found = store.get_run_by_external_id("TICKET-1").unwrap().unwrap();
+        assert_eq!(found.id, run.id);
+        assert_eq!(found.status, RunStatus::Completed);
+
+        // A different run cannot claim the same external ID
+        let duplicate = make_run("TICKET-1");
+        assert!(store.index_run(&duplicate).is_err());
+        assert!(store.get_run(&duplicate.id).unwrap().is_none());
+
+        assert!(store.get_run_by_external_id("TICKET-2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_run_secondary_indexes_backfilled_on_open() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_path_buf();
+
+        let run = Run {
+            id: RunId::new(),
+            work_item_id: "legacy-job".to_string(),
+            status: RunStatus::Completed,
+            started_at: chrono::Utc::now(),
+            completed_at: Some(chrono::Utc::now()),
+            steps: vec![],
+            parent_run_id: None,
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        };
+
+        // Simulate a database written before the secondary indexes existed
+        {
+            let db = Database::create(&path).unwrap();
+            let write_txn = db.begin_write().unwrap();
+            {
+                let mut table = write_txn.open_table(RUNS_TABLE).unwrap();
+                let value = serde_json::to_vec(&run).unwrap();
+                table.insert(run.id.to_string().as_str(), value.as_slice()).unwrap();
+            }
+            write_txn.commit().unwrap();
+        }
+
+        let store = RedbIndexStore::new(path).unwrap();
+        assert_eq!(store.list_runs_by_work_item("legacy-job").unwrap().len(), 1);
+        assert_eq!(store.list_runs_by_status(RunStatus::Completed).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_run_status_lifecycle() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let store = RedbIndexStore::new(temp_file.path().to_path_buf()).unwrap();
+
+        let run = Run {
+            id: RunId::new(),
+            work_item_id: "lifecycle".to_string(),
+            status: RunStatus::Pending,
+            started_at: chrono::Utc::now(),
+            completed_at: None,
+            steps: vec![],
+            parent_run_id: None,
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        };
+        store.index_run(&run).unwrap();
+
+        for status in [RunStatus::Running, RunStatus::Paused, RunStatus::Running] {
+            store.update_run_status(&run.id, status).unwrap();
+            assert!(store.get_run(&run.id).unwrap().unwrap().completed_at.is_none());
+        }
+
+        store.update_run_status(&run.id, RunStatus::Completed).unwrap();
+        let completed = store.get_run(&run.id).unwrap().unwrap();
+        assert_eq!(completed.status, RunStatus::Completed);
+        assert!(completed.completed_at.is_some());
+        assert_eq!(store.list_runs_by_status(RunStatus::Running).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_illegal_run_status_transition_rejected() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let store = RedbIndexStore::new(temp_file.path().to_path_buf()).unwrap();
+
+        let run = Run {
+            id: RunId::new(),
+            work_item_id: "done".to_string(),
+            status: RunStatus::Running,
+            started_at: chrono::Utc::now(),
+            completed_at: None,
+            steps: vec![],
+            parent_run_id: None,
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        };
+        store.index_run(&run).unwrap();
+        store.update_run_status(&run.id, RunStatus::Completed).unwrap();
+        let completed_at = store.get_run(&run.id).unwrap().unwrap().completed_at;
+
+        let err = store
+            .update_run_status(&run.id, RunStatus::Running)
+            .unwrap_err();
+        assert!(err.to_string().contains("Completed -> Running"));
+
+        // The stored run is untouched
+        let stored = store.get_run(&run.id).unwrap().unwrap();
+        assert_eq!(stored.status, RunStatus::Completed);
+        assert_eq!(stored.completed_at, completed_at);
+
+        // Pending runs can't skip straight to a result
+        assert!(!RunStatus::Pending.can_transition_to(RunStatus::Completed));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_mixed_codec_list_read() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_path_buf();
+        let new_run = |work_item_id: &str| Run {
+            id: RunId::new(),
+            work_item_id: work_item_id.to_string(),
+            status: RunStatus::Running,
+            started_at: chrono::Utc::now(),
+            completed_at: None,
+            steps: vec![],
+            parent_run_id: None,
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        };
+
+        // Legacy value written as JSON
+        let legacy = new_run("legacy");
+        RedbIndexStore::new(path.clone())
+            .unwrap()
+            .index_run(&legacy)
+            .unwrap();
+
+        // Reopen mid-migration, writing MessagePack
+        let store = RedbIndexStore::new(path).unwrap().with_codec(Codec::MessagePack);
+        let migrated = new_run("migrated");
+        store.index_run(&migrated).unwrap();
+
+        let mut runs = store.list_runs().unwrap();
+        runs.sort_by(|a, b| a.work_item_id.cmp(&b.work_item_id));
+        assert_eq!(runs, vec![legacy.clone(), migrated]);
+
+        // Rewriting the legacy run converts it
+        store.update_run_status(&legacy.id, RunStatus::Failed).unwrap();
+        assert_eq!(store.get_run(&legacy.id).unwrap().unwrap().status, RunStatus::Failed);
+        assert_eq!(store.list_runs_by_work_item("legacy").unwrap().len(), 1);
+    }
 }
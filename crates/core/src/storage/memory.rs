@@ -0,0 +1,646 @@
+use super::blob::BlobStore;
+use super::index::{run_tag_key, IndexBackend, IndexStore, IndexWrites};
+use crate::types::{
+    Approval, ApprovalBoard, ApprovalBoardId, ApprovalId, BlobHash, CapacitySource,
+    CapacitySourceId, CapacityUsage, ConfigChange, ConfigChangeId, OrgId, Organization, PolicyId,
+    PolicySpec, ProcessTemplate, RoleId, RoleSpec, Routine, RoutineExecution, RoutineId, Run,
+    RunId, RunStatus, TemplateId,
+};
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use sha2::{Digest, Sha256};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Index backend that keeps everything in memory (for tests and ephemeral deployments).
+///
+/// Tables are ordered by key like redb's, so listings come back in the same order as
+/// from `RedbIndexStore`. Nothing survives a restart.
+#[derive(Default)]
+pub struct InMemoryIndexStore {
+    tables: Mutex<Tables>,
+    config_generation: AtomicU64,
+}
+
+#[derive(Default)]
+struct Tables {
+    runs: BTreeMap<String, Run>,
+    runs_by_external_id: BTreeMap<String, String>,
+    roles: BTreeMap<String, RoleSpec>,
+    policies: BTreeMap<String, PolicySpec>,
+    organizations: BTreeMap<String, Organization>,
+    templates: BTreeMap<String, ProcessTemplate>,
+    capacity_sources: BTreeMap<String, CapacitySource>,
+    capacity_usage: BTreeMap<String, CapacityUsage>,
+    routines: BTreeMap<String, Routine>,
+    routine_executions: BTreeMap<String, RoutineExecution>,
+    approval_boards: BTreeMap<String, ApprovalBoard>,
+    approvals: BTreeMap<String, Approval>,
+    config_changes: BTreeMap<String, ConfigChange>,
+}
+
+impl InMemoryIndexStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap()
+    }
+
+    /// Called after a role, policy or organization write
+    fn config_changed(&self) {
+        self.config_generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn list_runs_where(&self, predicate: impl Fn(&Run) -> bool) -> Vec<Run> {
+        let mut runs: Vec<Run> = self
+            .tables()
+            .runs
+            .values()
+            .filter(|run| predicate(run))
+            .cloned()
+            .collect();
+        runs.sort_by_key(|r| std::cmp::Reverse(r.started_at));
+        runs
+    }
+}
+
+type TableWrite = Box<dyn FnOnce(&mut Tables)>;
+
+/// Writes staged inside `InMemoryIndexStore::write_transaction`, applied only once it succeeds
+#[derive(Default)]
+struct MemoryTransaction {
+    writes: RefCell<Vec<TableWrite>>,
+    /// Whether a role, policy or organization was written
+    config_changed: Cell<bool>,
+}
+
+impl MemoryTransaction {
+    fn stage(&self, write: impl FnOnce(&mut Tables) + 'static) -> Result<()> {
+        self.writes.borrow_mut().push(Box::new(write));
+        Ok(())
+    }
+}
+
+impl IndexWrites for MemoryTransaction {
+    fn store_role(&self, role: &RoleSpec) -> Result<()> {
+        self.config_changed.set(true);
+        let role = role.clone();
+        self.stage(move |tables| {
+            tables.roles.insert(role.id.0.clone(), role);
+        })
+    }
+
+    fn delete_role(&self, role_id: &RoleId) -> Result<()> {
+        self.config_changed.set(true);
+        let key = role_id.0.clone();
+        self.stage(move |tables| {
+            tables.roles.remove(&key);
+        })
+    }
+
+    fn store_policy(&self, policy: &PolicySpec) -> Result<()> {
+        self.config_changed.set(true);
+        let policy = policy.clone();
+        self.stage(move |tables| {
+            tables.policies.insert(policy.id.0.clone(), policy);
+        })
+    }
+
+    fn delete_policy(&self, policy_id: &PolicyId) -> Result<()> {
+        self.config_changed.set(true);
+        let key = policy_id.0.clone();
+        self.stage(move |tables| {
+            tables.policies.remove(&key);
+        })
+    }
+
+    fn store_organization(&self, org: &Organization) -> Result<()> {
+        self.config_changed.set(true);
+        let org = org.clone();
+        self.stage(move |tables| {
+            tables.organizations.insert(org.id.0.clone(), org);
+        })
+    }
+
+    fn delete_organization(&self, org_id: &OrgId) -> Result<()> {
+        self.config_changed.set(true);
+        let key = org_id.0.clone();
+        self.stage(move |tables| {
+            tables.organizations.remove(&key);
+        })
+    }
+
+    fn store_template(&self, template: &ProcessTemplate) -> Result<()> {
+        let template = template.clone();
+        self.stage(move |tables| {
+            tables.templates.insert(template.id.0.clone(), template);
+        })
+    }
+
+    fn delete_template(&self, template_id: &TemplateId) -> Result<()> {
+        let key = template_id.0.clone();
+        self.stage(move |tables| {
+            tables.templates.remove(&key);
+        })
+    }
+
+    fn store_capacity_source(&self, source: &CapacitySource) -> Result<()> {
+        let source = source.clone();
+        self.stage(move |tables| {
+            tables.capacity_sources.insert(source.id.0.clone(), source);
+        })
+    }
+
+    fn store_routine(&self, routine: &Routine) -> Result<()> {
+        let routine = routine.clone();
+        self.stage(move |tables| {
+            tables.routines.insert(routine.id.0.clone(), routine);
+        })
+    }
+
+    fn store_approval_board(&self, board: &ApprovalBoard) -> Result<()> {
+        let board = board.clone();
+        self.stage(move |tables| {
+            tables.approval_boards.insert(board.id.0.clone(), board);
+        })
+    }
+}
+
+impl IndexStore for InMemoryIndexStore {
+    fn index_run(&self, run: &Run) -> Result<()> {
+        let mut tables = self.tables();
+        let key = run.id.to_string();
+
+        // External IDs are unique; reject one already claimed by another run
+        if let Some(external_id) = &run.external_id {
+            if let Some(owner) = tables
+                .runs_by_external_id
+                .get(external_id)
+                .filter(|owner| **owner != key)
+            {
+                return Err(anyhow::anyhow!(
+                    "External ID {} is already used by run {}",
+                    external_id,
+                    owner
+                ));
+            }
+        }
+
+        if let Some(previous) = tables.runs.insert(key.clone(), run.clone()) {
+            if let Some(external_id) = &previous.external_id {
+                tables.runs_by_external_id.remove(external_id);
+            }
+        }
+        if let Some(external_id) = &run.external_id {
+            tables.runs_by_external_id.insert(external_id.clone(), key);
+        }
+        Ok(())
+    }
+
+    fn get_run(&self, run_id: &RunId) -> Result<Option<Run>> {
+        Ok(self.tables().runs.get(&run_id.to_string()).cloned())
+    }
+
+    fn get_run_by_external_id(&self, external_id: &str) -> Result<Option<Run>> {
+        let tables = self.tables();
+        Ok(tables
+            .runs_by_external_id
+            .get(external_id)
+            .and_then(|key| tables.runs.get(key))
+            .cloned())
+    }
+
+    fn list_runs(&self) -> Result<Vec<Run>> {
+        Ok(self.list_runs_where(|_| true))
+    }
+
+    fn update_run_status(&self, run_id: &RunId, status: RunStatus) -> Result<()> {
+        let mut run = self.get_run(run_id)?.context("Run not found")?;
+
+        run.transition_to(status)?;
+        self.index_run(&run)
+    }
+
+    fn get_role(&self, role_id: &RoleId) -> Result<Option<RoleSpec>> {
+        Ok(self.tables().roles.get(&role_id.0).cloned())
+    }
+}
+
+impl IndexBackend for InMemoryIndexStore {
+    fn list_runs_by_work_item(&self, work_item_id: &str) -> Result<Vec<Run>> {
+        Ok(self.list_runs_where(|run| run.work_item_id == work_item_id))
+    }
+
+    fn list_runs_by_status(&self, status: RunStatus) -> Result<Vec<Run>> {
+        Ok(self.list_runs_where(|run| run.status == status))
+    }
+
+    fn list_runs_by_tag(&self, tag: &str) -> Result<Vec<Run>> {
+        Ok(self.list_runs_where(|run| run.tags.iter().any(|(k, v)| run_tag_key(k, v) == tag)))
+    }
+
+    fn store_role(&self, role: &RoleSpec) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.store_role(role))
+    }
+
+    fn list_roles(&self) -> Result<Vec<RoleSpec>> {
+        Ok(self.tables().roles.values().cloned().collect())
+    }
+
+    fn delete_role(&self, role_id: &RoleId) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.delete_role(role_id))
+    }
+
+    fn store_policy(&self, policy: &PolicySpec) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.store_policy(policy))
+    }
+
+    fn get_policy(&self, policy_id: &PolicyId) -> Result<Option<PolicySpec>> {
+        Ok(self.tables().policies.get(&policy_id.0).cloned())
+    }
+
+    fn list_policies(&self) -> Result<Vec<PolicySpec>> {
+        Ok(self.tables().policies.values().cloned().collect())
+    }
+
+    fn delete_policy(&self, policy_id: &PolicyId) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.delete_policy(policy_id))
+    }
+
+    fn store_organization(&self, org: &Organization) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.store_organization(org))
+    }
+
+    fn get_organization(&self, org_id: &OrgId) -> Result<Option<Organization>> {
+        Ok(self.tables().organizations.get(&org_id.0).cloned())
+    }
+
+    fn list_organizations(&self) -> Result<Vec<Organization>> {
+        Ok(self.tables().organizations.values().cloned().collect())
+    }
+
+    fn delete_organization(&self, org_id: &OrgId) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.delete_organization(org_id))
+    }
+
+    fn store_template(&self, template: &ProcessTemplate) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.store_template(template))
+    }
+
+    fn get_template(&self, template_id: &TemplateId) -> Result<Option<ProcessTemplate>> {
+        Ok(self.tables().templates.get(&template_id.0).cloned())
+    }
+
+    fn list_templates(&self) -> Result<Vec<ProcessTemplate>> {
+        Ok(self.tables().templates.values().cloned().collect())
+    }
+
+    fn delete_template(&self, template_id: &TemplateId) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.delete_template(template_id))
+    }
+
+    fn store_capacity_source(&self, source: &CapacitySource) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.store_capacity_source(source))
+    }
+
+    fn get_capacity_source(
+        &self,
+        source_id: &CapacitySourceId,
+    ) -> Result<Option<CapacitySource>> {
+        Ok(self.tables().capacity_sources.get(&source_id.0).cloned())
+    }
+
+    fn list_capacity_sources(&self) -> Result<Vec<CapacitySource>> {
+        Ok(self.tables().capacity_sources.values().cloned().collect())
+    }
+
+    fn delete_capacity_source(&self, source_id: &CapacitySourceId) -> Result<()> {
+        self.tables().capacity_sources.remove(&source_id.0);
+        Ok(())
+    }
+
+    fn store_capacity_usage(&self, usage: &CapacityUsage) -> Result<bool> {
+        let mut tables = self.tables();
+        if tables.capacity_usage.contains_key(&usage.id) {
+            return Ok(false);
+        }
+        tables.capacity_usage.insert(usage.id.clone(), usage.clone());
+        Ok(true)
+    }
+
+    fn list_capacity_usage(&self) -> Result<Vec<CapacityUsage>> {
+        let mut usage_records: Vec<_> = self.tables().capacity_usage.values().cloned().collect();
+        usage_records.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+        Ok(usage_records)
+    }
+
+    fn store_routine(&self, routine: &Routine) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.store_routine(routine))
+    }
+
+    fn get_routine(&self, routine_id: &RoutineId) -> Result<Option<Routine>> {
+        Ok(self.tables().routines.get(&routine_id.0).cloned())
+    }
+
+    fn list_routines(&self) -> Result<Vec<Routine>> {
+        Ok(self.tables().routines.values().cloned().collect())
+    }
+
+    fn delete_routine(&self, routine_id: &RoutineId) -> Result<()> {
+        self.tables().routines.remove(&routine_id.0);
+        Ok(())
+    }
+
+    fn store_routine_execution(&self, execution: &RoutineExecution) -> Result<()> {
+        self.tables()
+            .routine_executions
+            .insert(execution.id.clone(), execution.clone());
+        Ok(())
+    }
+
+    fn list_routine_executions(&self) -> Result<Vec<RoutineExecution>> {
+        let mut executions: Vec<_> = self.tables().routine_executions.values().cloned().collect();
+        executions.sort_by_key(|r| std::cmp::Reverse(r.executed_at));
+        Ok(executions)
+    }
+
+    fn store_approval_board(&self, board: &ApprovalBoard) -> Result<()> {
+        self.write_transaction(&mut |txn| txn.store_approval_board(board))
+    }
+
+    fn get_approval_board(&self, board_id: &ApprovalBoardId) -> Result<Option<ApprovalBoard>> {
+        Ok(self.tables().approval_boards.get(&board_id.0).cloned())
+    }
+
+    fn list_approval_boards(&self) -> Result<Vec<ApprovalBoard>> {
+        Ok(self.tables().approval_boards.values().cloned().collect())
+    }
+
+    fn delete_approval_board(&self, board_id: &ApprovalBoardId) -> Result<()> {
+        self.tables().approval_boards.remove(&board_id.0);
+        Ok(())
+    }
+
+    fn store_approval(&self, approval: &Approval) -> Result<()> {
+        self.tables().approvals.insert(approval.id.0.clone(), approval.clone());
+        Ok(())
+    }
+
+    fn get_approval(&self, approval_id: &ApprovalId) -> Result<Option<Approval>> {
+        Ok(self.tables().approvals.get(&approval_id.0).cloned())
+    }
+
+    fn list_approvals(&self) -> Result<Vec<Approval>> {
+        let mut approvals: Vec<_> = self.tables().approvals.values().cloned().collect();
+        approvals.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+        Ok(approvals)
+    }
+
+    fn store_config_change(&self, change: &ConfigChange) -> Result<()> {
+        self.tables().config_changes.insert(change.id.0.clone(), change.clone());
+        Ok(())
+    }
+
+    fn get_config_change(&self, change_id: &ConfigChangeId) -> Result<Option<ConfigChange>> {
+        Ok(self.tables().config_changes.get(&change_id.0).cloned())
+    }
+
+    fn list_config_changes(&self) -> Result<Vec<ConfigChange>> {
+        let mut changes: Vec<_> = self.tables().config_changes.values().cloned().collect();
+        changes.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+        Ok(changes)
+    }
+
+    fn config_generation(&self) -> u64 {
+        self.config_generation.load(Ordering::SeqCst)
+    }
+
+    fn write_transaction(&self, f: &mut dyn FnMut(&dyn IndexWrites) -> Result<()>) -> Result<()> {
+        let txn = MemoryTransaction::default();
+        f(&txn)?;
+
+        // Apply under one lock so readers see all of the writes or none of them
+        let mut tables = self.tables();
+        for write in txn.writes.into_inner() {
+            write(&mut tables);
+        }
+        drop(tables);
+        if txn.config_changed.get() {
+            self.config_changed();
+        }
+        Ok(())
+    }
+}
+
+/// Content-addressed blob store that keeps blobs in memory (for tests)
+#[derive(Default)]
+pub struct InMemoryBlobStore {
+    blobs: Mutex<HashMap<String, Bytes>>,
+}
+
+impl InMemoryBlobStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl BlobStore for InMemoryBlobStore {
+    async fn put(&self, data: Bytes) -> Result<BlobHash> {
+        let hash = hex::encode(Sha256::digest(&data));
+        self.blobs.lock().unwrap().entry(hash.clone()).or_insert(data);
+        Ok(BlobHash(hash))
+    }
+
+    async fn get(&self, hash: &BlobHash) -> Result<Option<Bytes>> {
+        Ok(self.blobs.lock().unwrap().get(&hash.0).cloned())
+    }
+
+    async fn exists(&self, hash: &BlobHash) -> Result<bool> {
+        Ok(self.blobs.lock().unwrap().contains_key(&hash.0))
+    }
+
+    async fn delete(&self, hash: &BlobHash) -> Result<()> {
+        self.blobs.lock().unwrap().remove(&hash.0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{FilesystemBlobStore, RedbIndexStore};
+    use crate::types::{RoleBudgets, RunId};
+    use chrono::{Duration, Utc};
+    use tempfile::{NamedTempFile, TempDir};
+
+    fn run(work_item_id: &str, status: RunStatus, started_secs_ago: i64) -> Run {
+        Run {
+            id: RunId::new(),
+            work_item_id: work_item_id.to_string(),
+            status,
+            started_at: Utc::now() - Duration::seconds(started_secs_ago),
+            completed_at: None,
+            steps: vec![],
+            parent_run_id: None,
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        }
+    }
+
+    fn role(id: &str) -> RoleSpec {
+        RoleSpec {
+            id: RoleId::new(id),
+            name: id.to_string(),
+            description: String::new(),
+            prompt_template: String::new(),
+            allowed_tools: vec![],
+            budgets: RoleBudgets {
+                daily_tokens: None,
+                daily_cost_cents: None,
+                ..Default::default()
+            },
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        }
+    }
+
+    /// Behaviour every index backend must share
+    fn index_backend_suite(store: &dyn IndexBackend) {
+        let mut older = run("job-a", RunStatus::Running, 20);
+        older.tags.insert("team".to_string(), "payments".to_string());
+        let mut newer = run("job-a", RunStatus::Pending, 10);
+        newer.external_id = Some("ext-1".to_string());
+        newer.tags.insert("team".to_string(), "search".to_string());
+        older.tags.insert("region:eu".to_string(), "west".to_string());
+        let mut other = run("job-b", RunStatus::Running, 5);
+        other.tags.insert("region".to_string(), "eu:west".to_string());
+        for r in [&older, &newer, &other] {
+            store.index_run(r).unwrap();
+        }
+
+        let ids = |runs: Vec<Run>| runs.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids(store.list_runs().unwrap()), vec![other.id, newer.id, older.id]);
+        assert_eq!(ids(store.list_runs_by_work_item("job-a").unwrap()), vec![newer.id, older.id]);
+        assert_eq!(
+            ids(store.list_runs_by_status(RunStatus::Running).unwrap()),
+            vec![other.id, older.id]
+        );
+        assert_eq!(store.get_run_by_external_id("ext-1").unwrap().unwrap().id, newer.id);
+        assert_eq!(ids(store.list_runs_by_tag("team:payments").unwrap()), vec![older.id]);
+        // A colon in the tag's key doesn't collide with one in another tag's value
+        let tagged =
+            |key: &str, value: &str| ids(store.list_runs_by_tag(&run_tag_key(key, value)).unwrap());
+        assert_eq!(tagged("region:eu", "west"), vec![older.id]);
+        assert_eq!(tagged("region", "eu:west"), vec![other.id]);
+
+        // External IDs stay unique across runs
+        let mut clash = run("job-c", RunStatus::Pending, 0);
+        clash.external_id = Some("ext-1".to_string());
+        let err = store.index_run(&clash).unwrap_err();
+        assert!(err.to_string().contains("already used by run"));
+        assert!(store.get_run(&clash.id).unwrap().is_none());
+
+        // Status updates move the run between secondary indexes
+        store.update_run_status(&newer.id, RunStatus::Running).unwrap();
+        assert_eq!(
+            ids(store.list_runs_by_status(RunStatus::Running).unwrap()),
+            vec![other.id, newer.id, older.id]
+        );
+        assert!(store.list_runs_by_status(RunStatus::Pending).unwrap().is_empty());
+        assert!(store.update_run_status(&RunId::new(), RunStatus::Completed).is_err());
+
+        // Re-indexing without the external ID or tag releases them
+        let mut released = store.get_run(&newer.id).unwrap().unwrap();
+        released.external_id = None;
+        released.tags.clear();
+        store.index_run(&released).unwrap();
+        assert!(store.get_run_by_external_id("ext-1").unwrap().is_none());
+        assert!(store.list_runs_by_tag("team:search").unwrap().is_empty());
+        store.index_run(&clash).unwrap();
+
+        // Keyed tables list in key order and ignore deletes of missing entries
+        store.store_role(&role("reviewer")).unwrap();
+        store.store_role(&role("engineer")).unwrap();
+        let role_ids: Vec<_> = store.list_roles().unwrap().into_iter().map(|r| r.id.0).collect();
+        assert_eq!(role_ids, vec!["engineer", "reviewer"]);
+        assert!(store.get_role(&RoleId::new("engineer")).unwrap().is_some());
+
+        store.delete_role(&RoleId::new("engineer")).unwrap();
+        store.delete_role(&RoleId::new("missing")).unwrap();
+        assert!(store.get_role(&RoleId::new("engineer")).unwrap().is_none());
+        assert_eq!(store.list_roles().unwrap().len(), 1);
+
+        // A failed transaction leaves nothing behind, a successful one lands in full
+        let generation = store.config_generation();
+        let failed = store.transaction(|txn| {
+            txn.store_role(&role("designer"))?;
+            txn.delete_role(&RoleId::new("reviewer"))?;
+            Err::<(), _>(anyhow::anyhow!("Template is invalid"))
+        });
+        assert_eq!(failed.unwrap_err().to_string(), "Template is invalid");
+        assert!(store.get_role(&RoleId::new("designer")).unwrap().is_none());
+        assert!(store.get_role(&RoleId::new("reviewer")).unwrap().is_some());
+        assert_eq!(store.config_generation(), generation);
+
+        let written = store
+            .transaction(|txn| {
+                txn.store_role(&role("designer"))?;
+                txn.delete_role(&RoleId::new("reviewer"))?;
+                Ok(2)
+            })
+            .unwrap();
+        assert_eq!(written, 2);
+        let role_ids: Vec<_> = store.list_roles().unwrap().into_iter().map(|r| r.id.0).collect();
+        assert_eq!(role_ids, vec!["designer"]);
+        assert!(store.config_generation() > generation);
+    }
+
+    async fn blob_store_suite(store: &dyn BlobStore) {
+        let hash = store.put(Bytes::from_static(b"hello")).await.unwrap();
+        assert_eq!(store.put(Bytes::from_static(b"hello")).await.unwrap(), hash);
+        assert!(store.exists(&hash).await.unwrap());
+        assert_eq!(store.get(&hash).await.unwrap().unwrap(), Bytes::from_static(b"hello"));
+
+        let mut reader = store.get_stream(&hash).await.unwrap().unwrap();
+        let mut streamed = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut streamed)
+            .await
+            .unwrap();
+        assert_eq!(streamed, b"hello");
+
+        store.delete(&hash).await.unwrap();
+        assert!(!store.exists(&hash).await.unwrap());
+        assert!(store.get(&hash).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_redb_index_backend() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let store = RedbIndexStore::new(temp_file.path().to_path_buf()).unwrap();
+        index_backend_suite(&store);
+    }
+
+    #[test]
+    fn test_in_memory_index_backend() {
+        index_backend_suite(&InMemoryIndexStore::new());
+    }
+
+    #[tokio::test]
+    async fn test_blob_backends() {
+        let temp_dir = TempDir::new().unwrap();
+        blob_store_suite(&FilesystemBlobStore::new(temp_dir.path().to_path_buf()).unwrap()).await;
+        blob_store_suite(&InMemoryBlobStore::new()).await;
+    }
+}
@@ -1,9 +1,11 @@
 pub mod blob;
 pub mod event_log;
 pub mod index;
+pub mod memory;
 pub mod tenant_storage;
 
-pub use blob::{BlobStore, FilesystemBlobStore};
-pub use event_log::{EventLogStore, JsonlEventLog};
-pub use index::{IndexStore, RedbIndexStore};
-pub use tenant_storage::{TenantStorage, TenantStorageStats};
+pub use blob::{BlobReader, BlobStore, FilesystemBlobStore};
+pub use event_log::{CompactionReport, EventLogStore, JsonlEventLog, RetentionPolicy};
+pub use index::{IndexBackend, IndexStore, IndexTransaction, IndexWrites, RedbIndexStore};
+pub use memory::{InMemoryBlobStore, InMemoryIndexStore};
+pub use tenant_storage::{TenantConfigCloneSummary, TenantStorage, TenantStorageStats};
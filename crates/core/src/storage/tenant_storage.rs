@@ -1,9 +1,11 @@
 use anyhow::Result;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::tenant::TenantId;
-use super::{FilesystemBlobStore, JsonlEventLog, RedbIndexStore};
+use crate::types::{ApprovalBoardId, OrgId, PolicyId, RoleId, StepAction, TemplateId, WorkflowSpec};
+use super::{FilesystemBlobStore, IndexWrites, JsonlEventLog, RedbIndexStore};
 
 /// Tenant-scoped blob storage
 pub struct TenantBlobStore {
@@ -140,6 +142,75 @@ impl TenantStorage {
         Ok(())
     }
 
+    /// Copy configuration (roles, policies, templates, organizations and approval boards)
+    /// from one tenant to another, giving every copied entity a fresh ID.
+    ///
+    /// Runtime data such as runs, events and approvals is not copied. Template steps,
+    /// including those of nested sub-workflows, are rewritten to reference the
+    /// regenerated role IDs.
+    pub fn clone_tenant_config(
+        &self,
+        source: &TenantId,
+        target: &TenantId,
+    ) -> Result<TenantConfigCloneSummary> {
+        let source_index = self.index_store(source)?;
+        let target_index = self.index_store(target)?;
+        let mut summary = TenantConfigCloneSummary::default();
+
+        // All or nothing, so a failed clone can simply be retried
+        target_index.transaction(|txn| {
+            let mut role_ids = HashMap::new();
+            for mut role in source_index.list_roles()? {
+                let new_id = RoleId::new(uuid::Uuid::new_v4().to_string());
+                role_ids.insert(role.id.clone(), new_id.clone());
+                role.id = new_id;
+                txn.store_role(&role)?;
+                summary.roles += 1;
+            }
+
+            for mut policy in source_index.list_policies()? {
+                policy.id = PolicyId(uuid::Uuid::new_v4().to_string());
+                txn.store_policy(&policy)?;
+                summary.policies += 1;
+            }
+
+            for mut template in source_index.list_templates()? {
+                template.id = TemplateId::new(uuid::Uuid::new_v4().to_string());
+                remap_step_roles(&mut template.workflow_template, &role_ids);
+                txn.store_template(&template)?;
+                summary.templates += 1;
+            }
+
+            for mut org in source_index.list_organizations()? {
+                org.id = OrgId::new(uuid::Uuid::new_v4().to_string());
+                for person in &mut org.people {
+                    if let Some(new_id) = role_ids.get(&person.role) {
+                        person.role = new_id.clone();
+                    }
+                }
+                txn.store_organization(&org)?;
+                summary.organizations += 1;
+            }
+
+            for mut board in source_index.list_approval_boards()? {
+                board.id = ApprovalBoardId::new(uuid::Uuid::new_v4().to_string());
+                txn.store_approval_board(&board)?;
+                summary.approval_boards += 1;
+            }
+
+            Ok(())
+        })?;
+
+        tracing::info!(
+            "Cloned configuration from tenant {} to {}: {:?}",
+            source.0,
+            target.0,
+            summary
+        );
+
+        Ok(summary)
+    }
+
     /// Get storage statistics for a tenant
     pub fn tenant_stats(&self, tenant_id: &TenantId) -> Result<TenantStorageStats> {
         let blob_path = self.blob_store.tenant_path(tenant_id);
@@ -186,6 +257,18 @@ impl TenantStorage {
     }
 }
 
+/// Point every step of `workflow`, and of its sub-workflows, at its role's new ID
+fn remap_step_roles(workflow: &mut WorkflowSpec, role_ids: &HashMap<RoleId, RoleId>) {
+    for step in &mut workflow.steps {
+        if let Some(new_id) = role_ids.get(&step.role) {
+            step.role = new_id.clone();
+        }
+        if let StepAction::SubWorkflow { workflow } = &mut step.action {
+            remap_step_roles(workflow, role_ids);
+        }
+    }
+}
+
 /// Storage statistics for a tenant
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TenantStorageStats {
@@ -193,6 +276,16 @@ pub struct TenantStorageStats {
     pub file_count: usize,
 }
 
+/// Counts of configuration entities copied by `TenantStorage::clone_tenant_config`
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct TenantConfigCloneSummary {
+    pub roles: usize,
+    pub policies: usize,
+    pub templates: usize,
+    pub organizations: usize,
+    pub approval_boards: usize,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,4 +355,165 @@ mod tests {
         assert!(stats.total_bytes > 0); // Index file should exist
         assert!(stats.file_count > 0);
     }
+
+    #[test]
+    fn test_clone_tenant_config() {
+        use crate::tenant::{Tenant, TenantManager, TenantQuota, TenantSettings, TenantStatus};
+        use crate::types::{
+            OrgChart, Organization, Person, PersonId, PolicySpec, ProcessTemplate, RoleBudgets,
+            RoleSpec, Run, RunId, RunStatus, StepId, StepSpec, TeamId,
+        };
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = TenantStorage::new(temp_dir.path().to_path_buf()).unwrap();
+        let manager = TenantManager::new();
+
+        let source = Tenant {
+            id: TenantId::new("acme"),
+            name: "Acme".to_string(),
+            description: "Template tenant".to_string(),
+            status: TenantStatus::Active,
+            quota: TenantQuota::default(),
+            settings: TenantSettings::default(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        manager.register_tenant(source.clone()).unwrap();
+        storage.initialize_tenant(&source.id).unwrap();
+
+        let source_index = storage.index_store(&source.id).unwrap();
+        source_index
+            .store_role(&RoleSpec {
+                id: RoleId::new("engineer"),
+                name: "Engineer".to_string(),
+                description: "Writes code".to_string(),
+                prompt_template: "You are an engineer".to_string(),
+                allowed_tools: vec!["read_file".to_string()],
+                budgets: RoleBudgets {
+                    daily_tokens: Some(1000),
+                    daily_cost_cents: None,
+                    ..Default::default()
+                },
+                requires_approval_for: Vec::new(),
+                model_fallback: vec![],
+                max_concurrent: None,
+                claude_settings: None,
+            })
+            .unwrap();
+        source_index
+            .store_policy(&PolicySpec {
+                id: PolicyId("no-secrets".to_string()),
+                name: "No secrets".to_string(),
+                description: "Deny secret paths".to_string(),
+                rules: Vec::new(),
+            })
+            .unwrap();
+        source_index
+            .store_organization(&Organization {
+                id: OrgId::new("acme-org"),
+                name: "Acme".to_string(),
+                description: String::new(),
+                teams: Vec::new(),
+                people: vec![Person {
+                    id: PersonId::new("alice"),
+                    name: "Alice".to_string(),
+                    email: "alice@acme.test".to_string(),
+                    role: RoleId::new("engineer"),
+                    team: TeamId::new("platform"),
+                    reports_to: None,
+                    can_approve: Vec::new(),
+                }],
+                org_chart: OrgChart {
+                    root_team: TeamId::new("platform"),
+                    reporting_structure: HashMap::new(),
+                },
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        let step = |id: &str, action| StepSpec {
+            id: StepId::new(id),
+            name: id.to_string(),
+            description: None,
+            role: RoleId::new("engineer"),
+            action,
+            timeout_secs: None,
+            retry_policy: None,
+            requires_approval: false,
+        };
+        let nested = WorkflowSpec {
+            steps: vec![step("build", StepAction::AgentTask { prompt: "Build".to_string() })],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+        source_index
+            .store_template(&ProcessTemplate {
+                id: TemplateId::new("release"),
+                name: "Release".to_string(),
+                description: String::new(),
+                category: "deployment".to_string(),
+                parameters: Vec::new(),
+                workflow_template: WorkflowSpec {
+                    steps: vec![step("release", StepAction::SubWorkflow { workflow: nested })],
+                    dependencies: HashMap::new(),
+                    inputs: Vec::new(),
+                },
+                created_at: chrono::Utc::now(),
+                created_by: "admin".to_string(),
+            })
+            .unwrap();
+        source_index
+            .index_run(&Run {
+                id: RunId::new(),
+                work_item_id: "job1".to_string(),
+                status: RunStatus::Completed,
+                started_at: chrono::Utc::now(),
+                completed_at: None,
+                steps: Vec::new(),
+                parent_run_id: None,
+                external_id: None,
+                workflow_hash: None,
+                retry_of: None,
+                tags: HashMap::new(),
+                inputs: HashMap::new(),
+                priority: 0,
+                tenant_id: None,
+            })
+            .unwrap();
+        drop(source_index);
+
+        let clone = manager.clone_tenant(&source.id, "Acme Two".to_string()).unwrap();
+        assert_ne!(clone.id, source.id);
+        assert_eq!(clone.name, "Acme Two");
+
+        storage.initialize_tenant(&clone.id).unwrap();
+        let summary = storage.clone_tenant_config(&source.id, &clone.id).unwrap();
+        assert_eq!(summary.roles, 1);
+        assert_eq!(summary.policies, 1);
+
+        let clone_index = storage.index_store(&clone.id).unwrap();
+        let roles = clone_index.list_roles().unwrap();
+        let policies = clone_index.list_policies().unwrap();
+
+        assert_eq!(roles.len(), 1);
+        assert_eq!(roles[0].name, "Engineer");
+        assert_ne!(roles[0].id, RoleId::new("engineer"));
+        assert_eq!(policies.len(), 1);
+        assert_eq!(policies[0].name, "No secrets");
+        assert_ne!(policies[0].id, PolicyId("no-secrets".to_string()));
+        assert!(clone_index.list_runs().unwrap().is_empty());
+
+        // People keep their role under its new ID
+        let orgs = clone_index.list_organizations().unwrap();
+        assert_eq!(orgs[0].people[0].role, roles[0].id);
+
+        // So do template steps, down into sub-workflows
+        let templates = clone_index.list_templates().unwrap();
+        let release = &templates[0].workflow_template.steps[0];
+        assert_eq!(release.role, roles[0].id);
+        let StepAction::SubWorkflow { workflow } = &release.action else {
+            panic!("release step lost its sub-workflow");
+        };
+        assert_eq!(workflow.steps[0].role, roles[0].id);
+    }
 }
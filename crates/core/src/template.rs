@@ -1,11 +1,65 @@
 // Process template system for reusable workflows
 
 use crate::types::{
-    ProcessTemplate, StepAction, TemplateId, TemplateInstance, TemplateParameter,
-    TemplateParameterType, WorkflowSpec,
+    Organization, PersonId, ProcessTemplate, RoleId, RoleSpec, StepAction, StepId, TeamId,
+    TemplateId, TemplateInstance, TemplateParameter, TemplateParameterType, WorkflowSpec,
 };
+use crate::validation::ValidationErrors;
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// A template category and how many templates belong to it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateCategory {
+    pub category: String,
+    pub count: usize,
+}
+
+/// A role referenced by a workflow that doesn't exist
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissingRole {
+    pub role_id: RoleId,
+    /// Steps assigned to the role, including those in sub-workflows
+    pub step_ids: Vec<StepId>,
+}
+
+/// Dependencies an instantiated workflow references that the environment lacks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissingDependencies {
+    pub roles: Vec<MissingRole>,
+}
+
+impl MissingDependencies {
+    pub fn is_empty(&self) -> bool {
+        self.roles.is_empty()
+    }
+}
+
+/// Roles, teams and people that ID-typed template parameters may reference
+#[derive(Debug, Clone, Default)]
+pub struct KnownEntities {
+    pub roles: HashSet<RoleId>,
+    pub teams: HashSet<TeamId>,
+    pub people: HashSet<PersonId>,
+}
+
+impl KnownEntities {
+    /// Collect every role, and the teams and people of every organization
+    pub fn new(roles: &[RoleSpec], organizations: &[Organization]) -> Self {
+        Self {
+            roles: roles.iter().map(|r| r.id.clone()).collect(),
+            teams: organizations
+                .iter()
+                .flat_map(|o| o.teams.iter().map(|t| t.id.clone()))
+                .collect(),
+            people: organizations
+                .iter()
+                .flat_map(|o| o.people.iter().map(|p| p.id.clone()))
+                .collect(),
+        }
+    }
+}
 
 /// Template processor for instantiating workflow templates
 pub struct TemplateProcessor;
@@ -16,90 +70,192 @@ impl TemplateProcessor {
         template: &ProcessTemplate,
         instance: &TemplateInstance,
     ) -> Result<WorkflowSpec> {
-        // Validate that all required parameters are provided
-        for param in &template.parameters {
-            if param.required && !instance.parameters.contains_key(&param.name) {
-                if param.default_value.is_none() {
-                    anyhow::bail!("Required parameter '{}' not provided", param.name);
-                }
-            }
-        }
+        Self::instantiate_inner(template, instance, None)
+    }
 
-        // Build parameter map with defaults
+    /// Instantiate a template, additionally rejecting role, team and person parameters
+    /// that don't name an entity in `entities`
+    pub fn instantiate_checked(
+        template: &ProcessTemplate,
+        instance: &TemplateInstance,
+        entities: &KnownEntities,
+    ) -> Result<WorkflowSpec> {
+        Self::instantiate_inner(template, instance, Some(entities))
+    }
+
+    fn instantiate_inner(
+        template: &ProcessTemplate,
+        instance: &TemplateInstance,
+        entities: Option<&KnownEntities>,
+    ) -> Result<WorkflowSpec> {
+        // Build parameter map with defaults, collecting every missing or invalid value
+        let mut errors = ValidationErrors::new();
         let mut param_values: HashMap<String, String> = HashMap::new();
         for param in &template.parameters {
-            let value = instance
-                .parameters
-                .get(&param.name)
-                .or(param.default_value.as_ref())
-                .context(format!("Parameter '{}' not provided and has no default", param.name))?;
+            let field = format!("parameters.{}", param.name);
+            let Some(value) = instance.parameters.get(&param.name).or(param.default_value.as_ref())
+            else {
+                let message = if param.required {
+                    format!("Required parameter '{}' not provided", param.name)
+                } else {
+                    format!("Parameter '{}' not provided and has no default", param.name)
+                };
+                errors.push(field, message);
+                continue;
+            };
 
-            // Validate parameter type
-            Self::validate_parameter(param, value)?;
+            // Validate parameter type, substituting the value in canonical form
+            let value = match Self::validate_parameter(param, value) {
+                Ok(value) => value,
+                Err(e) => {
+                    errors.push(field, e.to_string());
+                    continue;
+                }
+            };
 
-            param_values.insert(param.name.clone(), value.clone());
+            if let Some(entities) = entities {
+                if let Err(e) = Self::check_reference(param, &value, entities) {
+                    errors.push(field, e.to_string());
+                    continue;
+                }
+            }
+
+            param_values.insert(param.name.clone(), value);
         }
+        errors.into_result()?;
 
         // Instantiate the workflow by replacing parameters
         let mut workflow = template.workflow_template.clone();
 
-        // Replace parameters in step prompts and other fields
+        Self::replace_workflow_parameters(&mut workflow, &param_values);
+
+        Ok(workflow)
+    }
+
+    /// Find roles the workflow assigns steps to that aren't in `roles`, in the order
+    /// they are first referenced
+    pub fn missing_dependencies(workflow: &WorkflowSpec, roles: &[RoleSpec]) -> MissingDependencies {
+        fn collect(workflow: &WorkflowSpec, roles: &[RoleSpec], missing: &mut Vec<MissingRole>) {
+            for step in &workflow.steps {
+                if !roles.iter().any(|r| r.id == step.role) {
+                    match missing.iter_mut().find(|m| m.role_id == step.role) {
+                        Some(entry) => entry.step_ids.push(step.id.clone()),
+                        None => missing.push(MissingRole {
+                            role_id: step.role.clone(),
+                            step_ids: vec![step.id.clone()],
+                        }),
+                    }
+                }
+
+                if let StepAction::SubWorkflow { workflow } = &step.action {
+                    collect(workflow, roles, missing);
+                }
+            }
+        }
+
+        let mut missing = Vec::new();
+        collect(workflow, roles, &mut missing);
+        MissingDependencies { roles: missing }
+    }
+
+    /// Replace parameters in step names and actions, including nested sub-workflows
+    fn replace_workflow_parameters(
+        workflow: &mut WorkflowSpec,
+        param_values: &HashMap<String, String>,
+    ) {
         for step in &mut workflow.steps {
             // Replace in step name
-            step.name = Self::replace_parameters(&step.name, &param_values);
+            step.name = Self::replace_parameters(&step.name, param_values);
 
             // Replace in action prompts
             match &mut step.action {
                 StepAction::AgentTask { prompt } => {
-                    *prompt = Self::replace_parameters(prompt, &param_values);
+                    *prompt = Self::replace_parameters(prompt, param_values);
                 }
                 StepAction::ManualApproval { approvers } => {
                     // Replace approver placeholders
                     for approver in approvers {
-                        *approver = Self::replace_parameters(approver, &param_values);
+                        *approver = Self::replace_parameters(approver, param_values);
                     }
                 }
                 StepAction::Script { command, args } => {
-                    *command = Self::replace_parameters(command, &param_values);
+                    *command = Self::replace_parameters(command, param_values);
                     for arg in args {
-                        *arg = Self::replace_parameters(arg, &param_values);
+                        *arg = Self::replace_parameters(arg, param_values);
                     }
                 }
                 StepAction::ToolSequence { .. } => {
                     // Could replace tool parameters if needed
                 }
+                StepAction::SubWorkflow { workflow } => {
+                    Self::replace_workflow_parameters(workflow, param_values);
+                }
+                StepAction::WaitForEvent { event_key, .. } => {
+                    *event_key = Self::replace_parameters(event_key, param_values);
+                }
             }
         }
-
-        Ok(workflow)
     }
 
-    /// Validate a parameter value against its type
-    fn validate_parameter(param: &TemplateParameter, value: &str) -> Result<()> {
+    /// Validate a parameter value against its type, returning it in canonical form:
+    /// numbers, booleans and IDs are trimmed and booleans lowercased
+    fn validate_parameter(param: &TemplateParameter, value: &str) -> Result<String> {
         match param.param_type {
-            TemplateParameterType::String => Ok(()),
+            TemplateParameterType::String => Ok(value.to_string()),
             TemplateParameterType::Number => {
+                let value = value.trim();
                 value
                     .parse::<f64>()
                     .context(format!("Parameter '{}' must be a number", param.name))?;
-                Ok(())
+                Ok(value.to_string())
             }
             TemplateParameterType::Boolean => {
-                value
+                let value = value
+                    .trim()
+                    .to_ascii_lowercase()
                     .parse::<bool>()
                     .context(format!("Parameter '{}' must be true or false", param.name))?;
-                Ok(())
+                Ok(value.to_string())
             }
             TemplateParameterType::RoleId
             | TemplateParameterType::TeamId
             | TemplateParameterType::PersonId => {
-                // Just check it's not empty
-                if value.trim().is_empty() {
+                let value = value.trim();
+                if value.is_empty() {
                     anyhow::bail!("Parameter '{}' cannot be empty", param.name);
                 }
-                Ok(())
+                Ok(value.to_string())
+            }
+        }
+    }
+
+    /// Check that an ID-typed parameter names an entity that exists
+    fn check_reference(
+        param: &TemplateParameter,
+        value: &str,
+        entities: &KnownEntities,
+    ) -> Result<()> {
+        let (kind, exists) = match param.param_type {
+            TemplateParameterType::RoleId => {
+                ("Role", entities.roles.contains(&RoleId::new(value)))
             }
+            TemplateParameterType::TeamId => {
+                ("Team", entities.teams.contains(&TeamId::new(value)))
+            }
+            TemplateParameterType::PersonId => {
+                ("Person", entities.people.contains(&PersonId::new(value)))
+            }
+            _ => return Ok(()),
+        };
+        if !exists {
+            anyhow::bail!(
+                "{} '{}' given for parameter '{}' does not exist",
+                kind,
+                value,
+                param.name
+            );
         }
+        Ok(())
     }
 
     /// Replace parameter placeholders in a string
@@ -143,12 +299,59 @@ impl TemplateProcessor {
         params.dedup();
         params
     }
+
+    /// Filter templates by exact category and a fuzzy search over name and description
+    pub fn filter(
+        templates: Vec<ProcessTemplate>,
+        category: Option<&str>,
+        search: Option<&str>,
+    ) -> Vec<ProcessTemplate> {
+        templates
+            .into_iter()
+            .filter(|t| category.is_none_or(|c| t.category == c))
+            .filter(|t| search.is_none_or(|q| Self::matches_search(t, q)))
+            .collect()
+    }
+
+    /// Case-insensitive match of every search term against a template's name and
+    /// description. A term matches if it is a substring of either, or if its letters
+    /// appear in order in the name (so `cdrev` finds "Code Review").
+    pub fn matches_search(template: &ProcessTemplate, query: &str) -> bool {
+        let name = template.name.to_lowercase();
+        let description = template.description.to_lowercase();
+
+        query.split_whitespace().all(|term| {
+            let term = term.to_lowercase();
+            name.contains(&term) || description.contains(&term) || is_subsequence(&term, &name)
+        })
+    }
+
+    /// Distinct template categories with counts, sorted by category name
+    pub fn categories(templates: &[ProcessTemplate]) -> Vec<TemplateCategory> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for template in templates {
+            *counts.entry(template.category.as_str()).or_default() += 1;
+        }
+
+        counts
+            .into_iter()
+            .map(|(category, count)| TemplateCategory {
+                category: category.to_string(),
+                count,
+            })
+            .collect()
+    }
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{RoleId, StepId, StepSpec};
+    use crate::types::{RoleBudgets, StepSpec};
     use chrono::Utc;
 
     #[test]
@@ -207,6 +410,7 @@ mod tests {
                     requires_approval: false,
                 }],
                 dependencies: HashMap::new(),
+                inputs: Vec::new(),
             },
             created_at: Utc::now(),
             created_by: "admin".to_string(),
@@ -232,6 +436,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_instantiate_reports_missing_role() {
+        let step = |id: &str, role: &str| StepSpec {
+            id: StepId::new(id),
+            name: id.to_string(),
+            description: None,
+            role: RoleId::new(role),
+            action: StepAction::AgentTask {
+                prompt: "Do {{task}}".to_string(),
+            },
+            timeout_secs: None,
+            retry_policy: None,
+            requires_approval: false,
+        };
+
+        let mut publish = step("publish", "release_manager");
+        publish.action = StepAction::SubWorkflow {
+            workflow: WorkflowSpec {
+                steps: vec![step("announce", "release_manager")],
+                dependencies: HashMap::new(),
+                inputs: Vec::new(),
+            },
+        };
+
+        let template = ProcessTemplate {
+            id: TemplateId::new("release"),
+            name: "Release".to_string(),
+            description: "Release process".to_string(),
+            category: "release".to_string(),
+            parameters: vec![TemplateParameter {
+                name: "task".to_string(),
+                description: "What to release".to_string(),
+                param_type: TemplateParameterType::String,
+                default_value: None,
+                required: true,
+            }],
+            workflow_template: WorkflowSpec {
+                steps: vec![step("build", "engineer"), publish],
+                dependencies: HashMap::new(),
+                inputs: Vec::new(),
+            },
+            created_at: Utc::now(),
+            created_by: "admin".to_string(),
+        };
+
+        let instance = TemplateInstance {
+            template_id: template.id.clone(),
+            parameters: HashMap::from([("task".to_string(), "v1.2".to_string())]),
+            created_at: Utc::now(),
+            created_by: "user".to_string(),
+        };
+
+        let mut workflow = TemplateProcessor::instantiate(&template, &instance).unwrap();
+
+        let roles = vec![RoleSpec {
+            id: RoleId::new("engineer"),
+            name: "Engineer".to_string(),
+            description: String::new(),
+            prompt_template: String::new(),
+            allowed_tools: vec![],
+            budgets: RoleBudgets {
+                daily_tokens: None,
+                daily_cost_cents: None,
+                ..Default::default()
+            },
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        }];
+
+        let missing = TemplateProcessor::missing_dependencies(&workflow, &roles);
+        assert_eq!(
+            missing,
+            MissingDependencies {
+                roles: vec![MissingRole {
+                    role_id: RoleId::new("release_manager"),
+                    step_ids: vec![StepId::new("publish"), StepId::new("announce")],
+                }],
+            }
+        );
+
+        workflow.steps.pop();
+        assert!(TemplateProcessor::missing_dependencies(&workflow, &roles).is_empty());
+    }
+
     #[test]
     fn test_missing_required_parameter() {
         let template = ProcessTemplate {
@@ -249,6 +539,7 @@ mod tests {
             workflow_template: WorkflowSpec {
                 steps: vec![],
                 dependencies: HashMap::new(),
+                inputs: Vec::new(),
             },
             created_at: Utc::now(),
             created_by: "admin".to_string(),
@@ -279,4 +570,152 @@ mod tests {
         assert!(TemplateProcessor::validate_parameter(&param, "3.14").is_ok());
         assert!(TemplateProcessor::validate_parameter(&param, "not a number").is_err());
     }
+
+    #[test]
+    fn test_role_parameter_must_exist() {
+        let template = ProcessTemplate {
+            id: TemplateId::new("handoff"),
+            name: "Handoff".to_string(),
+            description: "Hand work to a role".to_string(),
+            category: "ops".to_string(),
+            parameters: vec![TemplateParameter {
+                name: "owner".to_string(),
+                description: "Role that takes over".to_string(),
+                param_type: TemplateParameterType::RoleId,
+                default_value: None,
+                required: true,
+            }],
+            workflow_template: WorkflowSpec {
+                steps: vec![StepSpec {
+                    id: StepId::new("handoff"),
+                    name: "Hand off to {{owner}}".to_string(),
+                    description: None,
+                    role: RoleId::new("coordinator"),
+                    action: StepAction::AgentTask {
+                        prompt: "Brief {{owner}}".to_string(),
+                    },
+                    timeout_secs: None,
+                    retry_policy: None,
+                    requires_approval: false,
+                }],
+                dependencies: HashMap::new(),
+                inputs: Vec::new(),
+            },
+            created_at: Utc::now(),
+            created_by: "admin".to_string(),
+        };
+        let instance = |owner: &str| TemplateInstance {
+            template_id: TemplateId::new("handoff"),
+            parameters: HashMap::from([("owner".to_string(), owner.to_string())]),
+            created_at: Utc::now(),
+            created_by: "user".to_string(),
+        };
+        let entities = KnownEntities {
+            roles: HashSet::from([RoleId::new("reviewer")]),
+            ..Default::default()
+        };
+
+        // Values are trimmed before lookup and substitution
+        let workflow =
+            TemplateProcessor::instantiate_checked(&template, &instance(" reviewer "), &entities)
+                .unwrap();
+        assert_eq!(workflow.steps[0].name, "Hand off to reviewer");
+
+        let err = TemplateProcessor::instantiate_checked(&template, &instance("ghost"), &entities)
+            .unwrap_err();
+        let errors = err.downcast_ref::<ValidationErrors>().unwrap();
+        assert_eq!(errors.errors()[0].field, "parameters.owner");
+        assert!(errors.errors()[0].message.contains("Role 'ghost'"));
+
+        // Unchecked instantiation only validates the value's shape
+        assert!(TemplateProcessor::instantiate(&template, &instance("ghost")).is_ok());
+    }
+
+    fn catalog_template(
+        id: &str,
+        name: &str,
+        description: &str,
+        category: &str,
+    ) -> ProcessTemplate {
+        ProcessTemplate {
+            id: TemplateId::new(id),
+            name: name.to_string(),
+            description: description.to_string(),
+            category: category.to_string(),
+            parameters: vec![],
+            workflow_template: WorkflowSpec {
+                steps: vec![],
+                dependencies: HashMap::new(),
+                inputs: Vec::new(),
+            },
+            created_at: Utc::now(),
+            created_by: "admin".to_string(),
+        }
+    }
+
+    fn catalog() -> Vec<ProcessTemplate> {
+        vec![
+            catalog_template(
+                "code_review",
+                "Code Review",
+                "Standard code review process",
+                "engineering",
+            ),
+            catalog_template(
+                "deploy",
+                "Deploy Service",
+                "Roll out a release to production",
+                "engineering",
+            ),
+            catalog_template(
+                "invoice",
+                "Monthly Invoicing",
+                "Send invoices to customers",
+                "finance",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_filter_by_category() {
+        let filtered = TemplateProcessor::filter(catalog(), Some("engineering"), None);
+        let ids: Vec<_> = filtered.iter().map(|t| t.id.0.as_str()).collect();
+        assert_eq!(ids, vec!["code_review", "deploy"]);
+
+        assert!(TemplateProcessor::filter(catalog(), Some("legal"), None).is_empty());
+        assert_eq!(TemplateProcessor::filter(catalog(), None, None).len(), 3);
+    }
+
+    #[test]
+    fn test_search_matches_description() {
+        let filtered = TemplateProcessor::filter(catalog(), None, Some("PRODUCTION release"));
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id.0, "deploy");
+
+        // Fuzzy match on the name
+        let filtered = TemplateProcessor::filter(catalog(), None, Some("cdrev"));
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id.0, "code_review");
+
+        // Category and search combine
+        assert!(TemplateProcessor::filter(catalog(), Some("finance"), Some("release")).is_empty());
+    }
+
+    #[test]
+    fn test_categories_with_counts() {
+        let categories = TemplateProcessor::categories(&catalog());
+        assert_eq!(
+            categories,
+            vec![
+                TemplateCategory {
+                    category: "engineering".to_string(),
+                    count: 2,
+                },
+                TemplateCategory {
+                    category: "finance".to_string(),
+                    count: 1,
+                },
+            ]
+        );
+    }
 }
@@ -184,6 +184,35 @@ impl TenantManager {
         Ok(())
     }
 
+    /// Create a new tenant with the same quota and settings as `source`.
+    ///
+    /// Only the tenant record is copied; use `TenantStorage::clone_tenant_config` to copy
+    /// the source tenant's roles, policies, templates and boards.
+    pub fn clone_tenant(&self, source: &TenantId, new_name: String) -> anyhow::Result<Tenant> {
+        let mut tenants = self.tenants.lock().unwrap();
+
+        let source_tenant = tenants
+            .get(source)
+            .ok_or_else(|| anyhow::anyhow!("Tenant not found: {}", source.0))?;
+
+        let now = Utc::now();
+        let tenant = Tenant {
+            id: TenantId::generate(),
+            name: new_name,
+            description: source_tenant.description.clone(),
+            status: TenantStatus::Active,
+            quota: source_tenant.quota.clone(),
+            settings: source_tenant.settings.clone(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        tenants.insert(tenant.id.clone(), tenant.clone());
+        tracing::info!("Cloned tenant {} into {}", source.0, tenant.id.0);
+
+        Ok(tenant)
+    }
+
     /// Check if a tenant is active
     pub fn is_active(&self, tenant_id: &TenantId) -> bool {
         self.tenants
@@ -1,3 +1,4 @@
+use crate::tenant::TenantId;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -73,11 +74,34 @@ impl BlobHash {
 pub enum RunStatus {
     Pending,
     Running,
+    /// Suspended mid-run; resumes by returning to `Running`
+    Paused,
     Completed,
     Failed,
     Cancelled,
 }
 
+impl RunStatus {
+    /// Every status that isn't terminal, i.e. that of a run still in flight
+    pub const ACTIVE: [RunStatus; 3] = [RunStatus::Pending, RunStatus::Running, RunStatus::Paused];
+
+    /// Whether the run has finished and its status can no longer change
+    pub fn is_terminal(self) -> bool {
+        matches!(self, RunStatus::Completed | RunStatus::Failed | RunStatus::Cancelled)
+    }
+
+    /// Whether a run may move from this status to `next`
+    pub fn can_transition_to(self, next: RunStatus) -> bool {
+        use RunStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Running | Cancelled)
+                | (Running, Paused | Completed | Failed | Cancelled)
+                | (Paused, Running | Failed | Cancelled)
+        )
+    }
+}
+
 /// Status of a workflow step
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -115,6 +139,19 @@ pub struct Job {
     pub workflow: WorkflowSpec,
     pub created_at: DateTime<Utc>,
     pub created_by: String,
+    /// Caller-supplied ID, e.g. a ticket in an external system
+    #[serde(default)]
+    pub external_id: Option<String>,
+    /// Priority of the job's runs; see `Run::priority`
+    #[serde(default = "default_run_priority")]
+    pub priority: u8,
+}
+
+/// Priority of runs that don't ask for one
+pub const DEFAULT_RUN_PRIORITY: u8 = 50;
+
+fn default_run_priority() -> u8 {
+    DEFAULT_RUN_PRIORITY
 }
 
 /// Unique identifier for a routine
@@ -141,6 +178,9 @@ pub struct Routine {
     pub created_at: DateTime<Utc>,
     pub created_by: String,
     pub updated_at: DateTime<Utc>,
+    /// Labels such as `team: payments`, copied onto every run the routine starts
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
 }
 
 /// Cron schedule configuration
@@ -155,6 +195,113 @@ pub struct RoutineSchedule {
 pub struct WorkflowSpec {
     pub steps: Vec<StepSpec>,
     pub dependencies: HashMap<StepId, Vec<StepId>>,
+    /// Values a run is started with, referenced from step actions as `{{inputs.<name>}}`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<WorkflowInput>,
+}
+
+/// An input a workflow declares for its runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowInput {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Runs must supply a value unless the input has a default
+    #[serde(default)]
+    pub required: bool,
+    /// Value used when a run doesn't supply one
+    #[serde(default)]
+    pub default: Option<serde_json::Value>,
+}
+
+impl WorkflowSpec {
+    /// Fill in defaults for inputs the run didn't supply. Fails with `ValidationErrors`
+    /// naming each required input that is still missing; undeclared inputs pass through.
+    pub fn resolve_inputs(
+        &self,
+        mut inputs: HashMap<String, serde_json::Value>,
+    ) -> anyhow::Result<HashMap<String, serde_json::Value>> {
+        let mut errors = crate::validation::ValidationErrors::new();
+        for input in &self.inputs {
+            if inputs.contains_key(&input.name) {
+                continue;
+            }
+            match &input.default {
+                Some(default) => {
+                    inputs.insert(input.name.clone(), default.clone());
+                }
+                None if input.required => errors.push(
+                    format!("inputs.{}", input.name),
+                    format!("Input '{}' is required", input.name),
+                ),
+                None => {}
+            }
+        }
+        errors.into_result()?;
+        Ok(inputs)
+    }
+
+    /// Hash of the spec's canonical serialization: object keys are sorted and each
+    /// step's dependency list is treated as a set, so structurally identical specs hash
+    /// identically regardless of map iteration order.
+    pub fn content_hash(&self) -> BlobHash {
+        let mut value = serde_json::to_value(self).expect("workflow specs serialize to JSON");
+        canonicalize_dependencies(&mut value);
+
+        let mut canonical = String::new();
+        write_canonical_json(&value, &mut canonical);
+        BlobHash::from_bytes(canonical.as_bytes())
+    }
+}
+
+/// Sort the dependency lists of a serialized spec and of any nested sub-workflows
+fn canonicalize_dependencies(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if let Some(serde_json::Value::Object(deps)) = map.get_mut("dependencies") {
+                for list in deps.values_mut() {
+                    if let serde_json::Value::Array(ids) = list {
+                        ids.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+                    }
+                }
+            }
+            map.values_mut().for_each(canonicalize_dependencies);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(canonicalize_dependencies),
+        _ => {}
+    }
+}
+
+/// Write JSON with object keys in sorted order, independent of how `Map` is backed
+fn write_canonical_json(value: &serde_json::Value, out: &mut String) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical_json(&map[key], out);
+            }
+            out.push('}');
+        }
+        serde_json::Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical_json(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
 }
 
 /// Specification for a single workflow step
@@ -176,12 +323,25 @@ pub struct StepSpec {
 pub enum StepAction {
     /// Execute an agent with a prompt
     AgentTask { prompt: String },
-    /// Execute a sequence of tool calls
-    ToolSequence { tools: Vec<ToolCallSpec> },
+    /// Execute a sequence of tool calls in order. The sequence stops at the first
+    /// failing tool unless `continue_on_error` is set.
+    ToolSequence {
+        tools: Vec<ToolCallSpec>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        continue_on_error: bool,
+    },
     /// Wait for manual approval
     ManualApproval { approvers: Vec<String> },
     /// Run a subprocess/script
     Script { command: String, args: Vec<String> },
+    /// Execute a nested workflow as a child run
+    SubWorkflow { workflow: WorkflowSpec },
+    /// Park until a signal with `event_key` is delivered to the run, failing after
+    /// `timeout_secs` if one is set
+    WaitForEvent {
+        event_key: String,
+        timeout_secs: Option<u64>,
+    },
 }
 
 /// Specification for a tool call
@@ -199,7 +359,7 @@ pub struct RetryPolicy {
 }
 
 /// A specific execution of a workflow
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Run {
     pub id: RunId,
     pub work_item_id: String,
@@ -207,10 +367,73 @@ pub struct Run {
     pub started_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
     pub steps: Vec<StepExecution>,
+    /// Run that spawned this one through a `SubWorkflow` step
+    #[serde(default)]
+    pub parent_run_id: Option<RunId>,
+    /// Caller-supplied ID for correlating the run with an external system; unique across runs
+    #[serde(default)]
+    pub external_id: Option<String>,
+    /// `WorkflowSpec::content_hash` of the spec the run executes, shared by runs of
+    /// structurally identical workflows
+    #[serde(default)]
+    pub workflow_hash: Option<BlobHash>,
+    /// Failed or cancelled run this one was started to retry
+    #[serde(default)]
+    pub retry_of: Option<RunId>,
+    /// Labels such as `env: staging`, for organizing and filtering runs
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+    /// Values the run was started with, after defaults were applied
+    #[serde(default)]
+    pub inputs: HashMap<String, serde_json::Value>,
+    /// Higher-priority runs get concurrency-limited role slots and queued LLM capacity
+    /// first. Sub-workflow runs and retries inherit it.
+    #[serde(default = "default_run_priority")]
+    pub priority: u8,
+    /// Tenant the run was started for; `None` for platform runs. Tenant-scoped API keys
+    /// only see their own tenant's runs.
+    #[serde(default)]
+    pub tenant_id: Option<TenantId>,
+}
+
+/// Where a retried run starts executing from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryFrom {
+    /// Reuse the results of steps that completed and execute the rest
+    #[default]
+    Failed,
+    /// Execute every step again
+    Start,
+}
+
+impl Run {
+    /// Move the run to `status`, stamping `completed_at` when it reaches a terminal
+    /// status. Re-applying the current status is a no-op; illegal transitions fail.
+    pub fn transition_to(&mut self, status: RunStatus) -> anyhow::Result<()> {
+        if self.status == status {
+            return Ok(());
+        }
+
+        if !self.status.can_transition_to(status) {
+            return Err(anyhow::anyhow!(
+                "Illegal run status transition: {:?} -> {:?}",
+                self.status,
+                status
+            ));
+        }
+
+        self.status = status;
+        if status.is_terminal() {
+            self.completed_at = Some(Utc::now());
+        }
+
+        Ok(())
+    }
 }
 
 /// Execution state of a workflow step
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StepExecution {
     pub id: StepId,
     pub status: StepStatus,
@@ -218,6 +441,12 @@ pub struct StepExecution {
     pub completed_at: Option<DateTime<Utc>>,
     pub attempt: u32,
     pub error: Option<String>,
+    /// Tokens used by the step's LLM call, for steps that made one
+    #[serde(default)]
+    pub tokens: Option<u64>,
+    /// Cost of the step's LLM call as billed by the capacity source
+    #[serde(default)]
+    pub cost: Option<f64>,
 }
 
 /// Role specification
@@ -230,13 +459,97 @@ pub struct RoleSpec {
     pub allowed_tools: Vec<String>,
     pub budgets: RoleBudgets,
     pub requires_approval_for: Vec<String>, // Tool IDs or tiers
+    /// Models to fall back to, in order, when no source for the requested model has
+    /// capacity, rather than queueing the request
+    #[serde(default)]
+    pub model_fallback: Vec<String>,
+    /// Most of this role's steps that may run at once across all runs; excess steps
+    /// queue until a slot frees up
+    #[serde(default)]
+    pub max_concurrent: Option<u32>,
+    /// Limits on this role's LLM requests, also used for its compiled Claude config
+    #[serde(default)]
+    pub claude_settings: Option<ClaudeSettings>,
+}
+
+impl RoleSpec {
+    /// Check the spec for problems, reporting all of them at once
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut errors = crate::validation::ValidationErrors::new();
+        if self.id.0.trim().is_empty() {
+            errors.push("id", "Role ID cannot be empty");
+        }
+        if self.name.trim().is_empty() {
+            errors.push("name", format!("Role {} must have a name", self.id.0));
+        }
+
+        let lists = [
+            ("allowed_tools", &self.allowed_tools),
+            ("requires_approval_for", &self.requires_approval_for),
+            ("model_fallback", &self.model_fallback),
+        ];
+        for (field, values) in lists {
+            for (i, value) in values.iter().enumerate() {
+                if value.trim().is_empty() {
+                    let message = format!("{} entries cannot be empty", field);
+                    errors.push(format!("{}[{}]", field, i), message);
+                }
+            }
+        }
+
+        if self.max_concurrent == Some(0) {
+            errors.push("max_concurrent", "max_concurrent must be at least 1");
+        }
+
+        // Falling back to a model the role may not use would only fail later
+        if let Some(settings) = &self.claude_settings {
+            if !settings.allowed_models.is_empty() {
+                for (i, model) in self.model_fallback.iter().enumerate() {
+                    if !settings.allowed_models.contains(model) {
+                        errors.push(
+                            format!("model_fallback[{}]", i),
+                            format!("Fallback model {} is not in allowed_models", model),
+                        );
+                    }
+                }
+            }
+        }
+
+        errors.into_result()
+    }
 }
 
-/// Budget limits for a role
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Budget limits for a role. Crossing a soft limit requires approval; crossing a hard
+/// limit denies. `daily_tokens`/`daily_cost_cents` are hard limits, kept for specs
+/// written before soft limits existed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RoleBudgets {
     pub daily_tokens: Option<u64>,
     pub daily_cost_cents: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_daily_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hard_daily_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_daily_cost_cents: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hard_daily_cost_cents: Option<u64>,
+    /// Who approves calls past a soft limit. If empty, the policy engine's default
+    /// config change approvers do.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub soft_limit_approvers: Vec<String>,
+}
+
+impl RoleBudgets {
+    /// Daily token limit past which calls are denied
+    pub fn hard_token_limit(&self) -> Option<u64> {
+        self.hard_daily_tokens.or(self.daily_tokens)
+    }
+
+    /// Daily cost limit, in cents, past which calls are denied
+    pub fn hard_cost_limit(&self) -> Option<u64> {
+        self.hard_daily_cost_cents.or(self.daily_cost_cents)
+    }
 }
 
 /// Policy specification
@@ -249,11 +562,16 @@ pub struct PolicySpec {
 }
 
 /// Individual policy rule
+///
+/// When a URL matches both an `AllowDomain` and a `DenyDomain` rule, the deny wins:
+/// every rule has to allow a call for it to go through.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum PolicyRule {
     DenyPath { patterns: Vec<String> },
     AllowDomain { domains: Vec<String> },
+    /// Deny tool calls whose `url` host is one of `domains` or a subdomain of one
+    DenyDomain { domains: Vec<String> },
     RequireApproval { tool_ids: Vec<String> },
     EnforceEnvironment { environment: String },
 }
@@ -271,7 +589,7 @@ pub struct ConfigProposal {
 }
 
 /// Diff of configuration changes
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ConfigDiff {
     pub roles_added: Vec<RoleSpec>,
     pub roles_modified: Vec<RoleSpec>,
@@ -279,6 +597,45 @@ pub struct ConfigDiff {
     pub policies_added: Vec<PolicySpec>,
     pub policies_modified: Vec<PolicySpec>,
     pub policies_removed: Vec<PolicyId>,
+    #[serde(default)]
+    pub templates_added: Vec<ProcessTemplate>,
+    #[serde(default)]
+    pub templates_modified: Vec<ProcessTemplate>,
+    #[serde(default)]
+    pub templates_removed: Vec<TemplateId>,
+    #[serde(default)]
+    pub capacity_sources_added: Vec<CapacitySource>,
+    #[serde(default)]
+    pub capacity_sources_modified: Vec<CapacitySource>,
+    #[serde(default)]
+    pub capacity_sources_removed: Vec<CapacitySourceId>,
+}
+
+impl ConfigDiff {
+    /// Number of entities added, modified or removed
+    pub fn change_count(&self) -> usize {
+        self.roles_added.len()
+            + self.roles_modified.len()
+            + self.roles_removed.len()
+            + self.policies_added.len()
+            + self.policies_modified.len()
+            + self.policies_removed.len()
+            + self.templates_added.len()
+            + self.templates_modified.len()
+            + self.templates_removed.len()
+            + self.capacity_sources_added.len()
+            + self.capacity_sources_modified.len()
+            + self.capacity_sources_removed.len()
+    }
+
+    /// IDs of every capacity source the diff touches
+    pub fn capacity_source_ids(&self) -> impl Iterator<Item = &CapacitySourceId> {
+        self.capacity_sources_added
+            .iter()
+            .chain(&self.capacity_sources_modified)
+            .map(|source| &source.id)
+            .chain(&self.capacity_sources_removed)
+    }
 }
 
 // === Phase 3: Organization & Templates ===
@@ -416,6 +773,9 @@ pub struct ClaudeConfig {
     pub mcp_servers: HashMap<String, McpServerConfig>,
     pub tools: Vec<ToolConfig>,
     pub settings: ClaudeSettings,
+    /// The role's prompt template with its variables filled in
+    #[serde(default)]
+    pub system_prompt: Option<String>,
 }
 
 /// MCP server configuration
@@ -436,11 +796,66 @@ pub struct ToolConfig {
 }
 
 /// Claude settings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClaudeSettings {
     pub max_tokens: Option<u64>,
     pub temperature: Option<f64>,
     pub model: Option<String>,
+    /// Models requests may ask for; empty allows any
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    /// What happens to a request asking for more than `max_tokens`
+    #[serde(default)]
+    pub max_tokens_enforcement: LimitEnforcement,
+}
+
+/// How a request exceeding a configured limit is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitEnforcement {
+    /// Lower the request to the limit
+    #[default]
+    Clamp,
+    /// Refuse the request
+    Reject,
+}
+
+impl ClaudeSettings {
+    /// Bound `request` by these settings: `max_tokens` is clamped or rejected according
+    /// to `max_tokens_enforcement`, and a model outside `allowed_models` is rejected. A
+    /// request without a model gets `model`, or else the first allowed model, so it can't
+    /// be served by whichever source happens to be free.
+    pub fn bound_request(&self, mut request: LlmRequest) -> anyhow::Result<LlmRequest> {
+        if request.model.is_none() {
+            request.model = self.model.clone().or_else(|| self.allowed_models.first().cloned());
+        }
+        if let Some(model) = &request.model {
+            if !self.allowed_models.is_empty() && !self.allowed_models.contains(model) {
+                return Err(anyhow::anyhow!(
+                    "Model {} is not allowed; allowed models: {}",
+                    model,
+                    self.allowed_models.join(", ")
+                ));
+            }
+        }
+
+        if let Some(limit) = self.max_tokens {
+            if u64::from(request.max_tokens) > limit {
+                match self.max_tokens_enforcement {
+                    LimitEnforcement::Clamp => request.max_tokens = limit as u32,
+                    LimitEnforcement::Reject => {
+                        return Err(anyhow::anyhow!(
+                            "Requested max_tokens {} exceeds the limit of {}",
+                            request.max_tokens,
+                            limit
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(request)
+    }
 }
 
 // === Phase 4: Capacity Broker ===
@@ -509,6 +924,9 @@ pub struct CapacityUsage {
     pub request_count: u32,
     pub run_id: Option<RunId>,
     pub step_id: Option<StepId>,
+    /// Model the request asked for, when a fallback model served it instead
+    #[serde(default)]
+    pub fallback_from: Option<String>,
 }
 
 /// Rate limit state for a capacity source
@@ -534,10 +952,87 @@ pub struct PriorityRequest {
     pub role: RoleId,
     pub prompt: String,
     pub max_tokens: u32,
+    /// Model the request asked for; retries only use sources serving it
+    #[serde(default)]
+    pub model: Option<String>,
     pub created_at: DateTime<Utc>,
     pub attempts: u32,
 }
 
+/// Queued request that failed on every attempt, kept for inspection and requeueing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub request: PriorityRequest,
+    pub last_error: String,
+    pub dead_lettered_at: DateTime<Utc>,
+}
+
+/// Why a capacity alert was raised
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapacityAlertKind {
+    /// A source was rate limited and is backing off
+    RateLimited,
+    /// A queued request exhausted its attempts
+    DeadLettered,
+    /// A source kept failing authentication and was disabled
+    SourceDisabled,
+}
+
+/// Notification that capacity is running short
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapacityAlert {
+    pub kind: CapacityAlertKind,
+    /// Source the alert concerns, if any
+    pub source_id: Option<CapacitySourceId>,
+    pub message: String,
+    pub raised_at: DateTime<Utc>,
+}
+
+/// Why source selection passed over a source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SourceSkipReason {
+    Disabled,
+    /// Backing off after the provider rate limited it
+    Backoff { until: DateTime<Utc> },
+    /// Requests per minute exhausted
+    RequestRateLimit { requests_in_window: u32, limit: u32 },
+    /// Tokens per minute would be exceeded
+    TokenRateLimit {
+        tokens_in_window: u32,
+        required_tokens: u32,
+        limit: u32,
+    },
+    /// Tokens per day would be exceeded
+    DailyCap {
+        daily_tokens: u32,
+        required_tokens: u32,
+        limit: u32,
+    },
+}
+
+/// One source as seen by source selection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceConsideration {
+    pub source_id: CapacitySourceId,
+    pub priority: u8,
+    /// Why the source was passed over; `None` for the chosen source
+    pub skipped: Option<SourceSkipReason>,
+}
+
+/// Record of a single source selection, in the order sources were considered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectionTrace {
+    pub required_tokens: u32,
+    /// Model the sources were restricted to, if any
+    #[serde(default)]
+    pub model: Option<String>,
+    pub considered: Vec<SourceConsideration>,
+    pub selected: Option<CapacitySourceId>,
+    pub selected_at: DateTime<Utc>,
+}
+
 /// LLM request sent to a capacity source
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmRequest {
@@ -556,6 +1051,21 @@ pub struct LlmResponse {
     pub cost: f64,
     pub model: String,
     pub source_id: CapacitySourceId,
+    /// Model the request asked for, when a fallback model served it instead
+    #[serde(default)]
+    pub fallback_from: Option<String>,
+    /// Served from the broker's response cache without calling the provider
+    #[serde(default)]
+    pub cache_hit: bool,
+}
+
+/// Incremental piece of a streamed LLM response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmChunk {
+    /// Text generated since the previous chunk
+    pub delta: String,
+    /// Complete response, set only on the final chunk once usage has been recorded
+    pub response: Option<LlmResponse>,
 }
 
 /// Error from LLM API
@@ -581,6 +1091,9 @@ pub struct RoutineExecution {
     pub executed_at: DateTime<Utc>,
     pub status: RunStatus,
     pub error: Option<String>,
+    /// Triggered by an operator rather than the schedule
+    #[serde(default)]
+    pub manual: bool,
 }
 
 /// Unique identifier for an approval board
@@ -613,6 +1126,13 @@ pub enum QuorumRule {
     Majority,  // More than 50% must approve
     MinCount { min: u32 }, // At least N approvers
     Percentage { percent: u8 }, // At least X% of approvers (0-100)
+    All(Vec<QuorumRule>), // Every sub-rule must pass
+    Any(Vec<QuorumRule>), // At least one sub-rule must pass
+    /// Evaluate `rule` against only the given approvers and their votes
+    Scoped {
+        approvers: Vec<PersonId>,
+        rule: Box<QuorumRule>,
+    },
 }
 
 /// Unique identifier for an approval
@@ -632,10 +1152,37 @@ pub struct Approval {
     pub board_id: ApprovalBoardId,
     pub subject: ApprovalSubject,
     pub status: ApprovalStatus,
+    /// Board membership when the approval was created. Votes and quorum use this
+    /// roster, so later board edits don't alter open approvals. Empty for approvals
+    /// recorded before rosters were snapshotted; see `Approval::roster`.
+    #[serde(default)]
+    pub approvers: Vec<PersonId>,
     pub votes: Vec<ApprovalVote>,
     pub created_at: DateTime<Utc>,
     pub created_by: String,
     pub resolved_at: Option<DateTime<Utc>>,
+    /// Set when an admin decided the approval instead of the board's quorum
+    #[serde(default)]
+    pub forced_resolution: Option<ForcedResolution>,
+}
+
+impl Approval {
+    /// The people who may vote on this approval: its snapshotted roster, or `board`'s
+    /// current one if the approval predates snapshots
+    pub fn roster<'a>(&'a self, board: &'a ApprovalBoard) -> &'a [PersonId] {
+        if self.approvers.is_empty() {
+            &board.approvers
+        } else {
+            &self.approvers
+        }
+    }
+}
+
+/// An admin override of an approval's outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForcedResolution {
+    pub resolved_by: String,
+    pub justification: String,
 }
 
 /// What is being approved
@@ -690,10 +1237,13 @@ pub struct ConfigChange {
     pub after: String, // JSON of proposed change
     pub applied_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// Other open changes targeting the same entity
+    #[serde(default)]
+    pub conflicts_with: Vec<ConfigChangeId>,
 }
 
 /// Type of configuration change
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ConfigChangeType {
     Role,
@@ -716,3 +1266,57 @@ pub enum ConfigChangeStatus {
     Applied,
     Failed,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(id: &str) -> StepSpec {
+        StepSpec {
+            id: StepId::new(id),
+            name: id.to_string(),
+            description: None,
+            role: RoleId::new("engineer"),
+            action: StepAction::ToolSequence {
+                tools: vec![ToolCallSpec {
+                    tool_id: "search".to_string(),
+                    parameters: serde_json::json!({"query": id, "limit": 5}),
+                }],
+                continue_on_error: false,
+            },
+            timeout_secs: None,
+            retry_policy: None,
+            requires_approval: false,
+        }
+    }
+
+    #[test]
+    fn test_workflow_content_hash_ignores_map_order() {
+        let ids = ["a", "b", "c", "d", "e"];
+
+        let mut forward = HashMap::new();
+        for id in &ids[1..] {
+            forward.insert(StepId::new(*id), vec![StepId::new("a")]);
+        }
+        forward.insert(StepId::new("e"), vec![StepId::new("b"), StepId::new("c")]);
+
+        let mut backward = HashMap::new();
+        backward.insert(StepId::new("e"), vec![StepId::new("c"), StepId::new("b")]);
+        for id in ids[1..4].iter().rev() {
+            backward.insert(StepId::new(*id), vec![StepId::new("a")]);
+        }
+
+        let spec = |dependencies| WorkflowSpec {
+            steps: ids.iter().map(|id| step(id)).collect(),
+            dependencies,
+            inputs: Vec::new(),
+        };
+        let first = spec(forward);
+        let second = spec(backward);
+        assert_eq!(first.content_hash(), second.content_hash());
+
+        let mut changed = second.clone();
+        changed.steps[0].name = "renamed".to_string();
+        assert_ne!(first.content_hash(), changed.content_hash());
+    }
+}
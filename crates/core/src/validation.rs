@@ -0,0 +1,60 @@
+// Validation errors collected across a whole submission
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// One problem with submitted input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationError {
+    /// Path to the offending field, e.g. `teams[0].members[1]`
+    pub field: String,
+    pub message: String,
+}
+
+/// Every validation failure found in a submission. Converts into `anyhow::Error` so it
+/// can travel through `Result`, and the API layer downcasts it to report all of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationErrors(pub Vec<ValidationError>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(ValidationError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    pub fn extend(&mut self, other: ValidationErrors) {
+        self.0.extend(other.0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.0
+    }
+
+    /// `Ok` when nothing was collected
+    pub fn into_result(self) -> anyhow::Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self.into())
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<_> = self.0.iter().map(|e| e.message.as_str()).collect();
+        write!(f, "Validation failed: {}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
@@ -391,11 +391,13 @@ mod tests {
         let spec1 = WorkflowSpec {
             steps: vec![],
             dependencies: HashMap::new(),
+            inputs: Vec::new(),
         };
 
         let spec2 = WorkflowSpec {
             steps: vec![],
             dependencies: HashMap::new(),
+            inputs: Vec::new(),
         };
 
         // Register first version
@@ -434,6 +436,7 @@ mod tests {
         let spec = WorkflowSpec {
             steps: vec![],
             dependencies: HashMap::new(),
+            inputs: Vec::new(),
         };
 
         manager.register_version(
@@ -1,8 +1,154 @@
-use crate::types::{StepId, StepSpec, WorkflowSpec};
+use crate::types::{ApprovalBoard, Organization, StepAction, StepId, StepSpec, WorkflowSpec};
+use crate::validation::ValidationErrors;
 use anyhow::{anyhow, Result};
 use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::visit::Topo;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Category of a workflow lint warning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintWarningKind {
+    /// Step has no role assigned
+    MissingRole,
+    /// Step neither depends on nor is depended on by any other step
+    OrphanStep,
+    /// Manual approval step with nobody to approve it
+    EmptyApprovers,
+    /// Retry policy that never runs the step
+    ZeroRetryAttempts,
+    /// Manual approval step naming an approver that is neither a person nor a board
+    UnknownApprover,
+}
+
+/// A non-fatal issue found in a workflow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintWarning {
+    pub kind: LintWarningKind,
+    pub step_id: StepId,
+    pub message: String,
+}
+
+/// People and approval boards that manual approval steps may name as approvers
+#[derive(Debug, Clone, Default)]
+pub struct ApproverDirectory {
+    people: HashSet<String>,
+    boards: HashSet<String>,
+}
+
+impl ApproverDirectory {
+    pub fn new(organizations: &[Organization], boards: &[ApprovalBoard]) -> Self {
+        Self {
+            people: organizations
+                .iter()
+                .flat_map(|org| &org.people)
+                .map(|person| person.id.0.clone())
+                .collect(),
+            boards: boards.iter().map(|board| board.id.0.clone()).collect(),
+        }
+    }
+
+    /// Whether `approver` is a person in one of the organizations or an approval board
+    pub fn contains(&self, approver: &str) -> bool {
+        self.people.contains(approver) || self.boards.contains(approver)
+    }
+}
+
+/// Upper bounds on a workflow's size, checked before its DAG is built so an oversized
+/// spec is rejected without being processed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowLimits {
+    /// Most steps in one workflow, counting each nested sub-workflow separately
+    #[serde(default = "default_max_steps")]
+    pub max_steps: usize,
+
+    /// Most dependencies a single step may list
+    #[serde(default = "default_max_dependencies_per_step")]
+    pub max_dependencies_per_step: usize,
+
+    /// Most dependency edges in one workflow
+    #[serde(default = "default_max_edges")]
+    pub max_edges: usize,
+}
+
+fn default_max_steps() -> usize {
+    1_000
+}
+
+fn default_max_dependencies_per_step() -> usize {
+    100
+}
+
+fn default_max_edges() -> usize {
+    10_000
+}
+
+impl Default for WorkflowLimits {
+    fn default() -> Self {
+        Self {
+            max_steps: default_max_steps(),
+            max_dependencies_per_step: default_max_dependencies_per_step(),
+            max_edges: default_max_edges(),
+        }
+    }
+}
+
+impl WorkflowLimits {
+    /// Every limit the workflow or one of its nested sub-workflows exceeds
+    pub fn check(&self, workflow: &WorkflowSpec) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        self.check_nested(workflow, "", &mut errors);
+        errors
+    }
+
+    fn check_nested(&self, workflow: &WorkflowSpec, prefix: &str, errors: &mut ValidationErrors) {
+        if workflow.steps.len() > self.max_steps {
+            errors.push(
+                format!("{}steps", prefix),
+                format!(
+                    "Workflow has {} steps, more than the limit of {}",
+                    workflow.steps.len(),
+                    self.max_steps
+                ),
+            );
+        }
+
+        let mut dependencies: Vec<_> = workflow.dependencies.iter().collect();
+        dependencies.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+        let mut edges = 0;
+        for (step_id, deps) in dependencies {
+            edges += deps.len();
+            if deps.len() > self.max_dependencies_per_step {
+                errors.push(
+                    format!("{}dependencies.{}", prefix, step_id),
+                    format!(
+                        "Step {} has {} dependencies, more than the limit of {}",
+                        step_id,
+                        deps.len(),
+                        self.max_dependencies_per_step
+                    ),
+                );
+            }
+        }
+        if edges > self.max_edges {
+            errors.push(
+                format!("{}dependencies", prefix),
+                format!(
+                    "Workflow has {} dependency edges, more than the limit of {}",
+                    edges, self.max_edges
+                ),
+            );
+        }
+
+        for (i, step) in workflow.steps.iter().enumerate() {
+            if let StepAction::SubWorkflow { workflow } = &step.action {
+                let prefix = format!("{}steps[{}].action.workflow.", prefix, i);
+                self.check_nested(workflow, &prefix, errors);
+            }
+        }
+    }
+}
 
 /// DAG representation of a workflow
 #[derive(Debug)]
@@ -12,37 +158,63 @@ pub struct WorkflowDag {
 }
 
 impl WorkflowDag {
-    /// Build a DAG from a workflow specification
+    /// Build a DAG from a workflow specification within the default [`WorkflowLimits`].
+    /// Structural problems are collected into a single [`ValidationErrors`].
     pub fn from_workflow(workflow: &WorkflowSpec) -> Result<Self> {
+        Self::from_workflow_with_limits(workflow, &WorkflowLimits::default())
+    }
+
+    /// Like `from_workflow`, rejecting a workflow that exceeds `limits` before building
+    /// anything
+    pub fn from_workflow_with_limits(
+        workflow: &WorkflowSpec,
+        limits: &WorkflowLimits,
+    ) -> Result<Self> {
+        limits.check(workflow).into_result()?;
+
         let mut graph = DiGraph::new();
         let mut step_indices = HashMap::new();
+        let mut errors = ValidationErrors::new();
 
         // Add all steps as nodes
-        for step in &workflow.steps {
+        for (i, step) in workflow.steps.iter().enumerate() {
             let node = graph.add_node(step.clone());
-            step_indices.insert(step.id.clone(), node);
+            if step_indices.insert(step.id.clone(), node).is_some() {
+                errors.push(format!("steps[{}].id", i), format!("Duplicate step ID {}", step.id));
+            }
         }
 
-        // Add dependency edges
-        for (step_id, dependencies) in &workflow.dependencies {
-            let step_idx = step_indices
-                .get(step_id)
-                .ok_or_else(|| anyhow!("Step {} referenced in dependencies but not defined", step_id))?;
-
-            for dep_id in dependencies {
-                let dep_idx = step_indices
-                    .get(dep_id)
-                    .ok_or_else(|| anyhow!("Dependency {} not found for step {}", dep_id, step_id))?;
+        // Add dependency edges, in step order so errors are reported deterministically
+        let mut dependencies: Vec<_> = workflow.dependencies.iter().collect();
+        dependencies.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+        for (step_id, dependencies) in dependencies {
+            let Some(step_idx) = step_indices.get(step_id) else {
+                errors.push(
+                    format!("dependencies.{}", step_id),
+                    format!("Step {} referenced in dependencies but not defined", step_id),
+                );
+                continue;
+            };
 
-                // Edge from dependency to dependent (dep -> step)
-                graph.add_edge(*dep_idx, *step_idx, ());
+            for (j, dep_id) in dependencies.iter().enumerate() {
+                match step_indices.get(dep_id) {
+                    // Edge from dependency to dependent (dep -> step)
+                    Some(dep_idx) => {
+                        graph.add_edge(*dep_idx, *step_idx, ());
+                    }
+                    None => errors.push(
+                        format!("dependencies.{}[{}]", step_id, j),
+                        format!("Dependency {} not found for step {}", dep_id, step_id),
+                    ),
+                }
             }
         }
 
         // Verify the graph is acyclic
         if petgraph::algo::is_cyclic_directed(&graph) {
-            return Err(anyhow!("Workflow contains circular dependencies"));
+            errors.push("dependencies", "Workflow contains circular dependencies");
         }
+        errors.into_result()?;
 
         Ok(Self {
             graph,
@@ -104,6 +276,138 @@ impl WorkflowDag {
         Ok(deps.iter().all(|dep| completed_steps.contains(dep)))
     }
 
+    /// Check the workflow for likely mistakes that don't prevent it from running
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        let multi_step = self.graph.node_count() > 1;
+
+        for node in self.graph.node_indices() {
+            let step = &self.graph[node];
+            let mut warn = |kind, message: String| {
+                warnings.push(LintWarning {
+                    kind,
+                    step_id: step.id.clone(),
+                    message,
+                })
+            };
+
+            if step.role.0.trim().is_empty() {
+                warn(
+                    LintWarningKind::MissingRole,
+                    format!("Step {} has no role", step.id),
+                );
+            }
+
+            if multi_step && self.graph.neighbors_undirected(node).next().is_none() {
+                warn(
+                    LintWarningKind::OrphanStep,
+                    format!("Step {} is not connected to any other step", step.id),
+                );
+            }
+
+            if let StepAction::ManualApproval { approvers } = &step.action {
+                if approvers.is_empty() {
+                    warn(
+                        LintWarningKind::EmptyApprovers,
+                        format!("Manual approval step {} has no approvers", step.id),
+                    );
+                }
+            }
+
+            if step.retry_policy.as_ref().is_some_and(|p| p.max_attempts == 0) {
+                warn(
+                    LintWarningKind::ZeroRetryAttempts,
+                    format!("Step {} has a retry policy with max_attempts of 0", step.id),
+                );
+            }
+        }
+
+        warnings
+    }
+
+    /// Find manual approval approvers missing from `directory`, including in nested
+    /// sub-workflows, which would otherwise only fail once the step runs
+    pub fn unknown_approvers(&self, directory: &ApproverDirectory) -> Vec<LintWarning> {
+        fn check(step: &StepSpec, directory: &ApproverDirectory, warnings: &mut Vec<LintWarning>) {
+            match &step.action {
+                StepAction::ManualApproval { approvers } => {
+                    for approver in approvers.iter().filter(|a| !directory.contains(a)) {
+                        warnings.push(LintWarning {
+                            kind: LintWarningKind::UnknownApprover,
+                            step_id: step.id.clone(),
+                            message: format!("Step {} names unknown approver {}", step.id, approver),
+                        });
+                    }
+                }
+                StepAction::SubWorkflow { workflow } => {
+                    for child in &workflow.steps {
+                        check(child, directory, warnings);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut warnings = Vec::new();
+        for node in self.graph.node_indices() {
+            check(&self.graph[node], directory, &mut warnings);
+        }
+        warnings
+    }
+
+    /// The chain of dependent steps with the greatest total `weight`, from an entry step
+    /// to the step it ends at. Ties go to the chain with more steps, so weightless steps
+    /// still extend it, then to the step with the smaller ID.
+    pub fn longest_path(&self, weight: impl Fn(&StepId) -> f64) -> Vec<StepId> {
+        // Heaviest chain ending at each node: its weight, its length and the node before
+        let mut best: HashMap<NodeIndex, Chain> = HashMap::new();
+        let mut topo = Topo::new(&self.graph);
+        while let Some(node) = topo.next(&self.graph) {
+            let previous = self
+                .graph
+                .neighbors_directed(node, petgraph::Direction::Incoming)
+                .max_by(|a, b| self.compare_chains(&best, *a, *b));
+            let (before, steps) = previous
+                .map(|p| (best[&p].weight, best[&p].steps))
+                .unwrap_or((0.0, 0));
+            best.insert(
+                node,
+                Chain {
+                    weight: before + weight(&self.graph[node].id),
+                    steps: steps + 1,
+                    previous,
+                },
+            );
+        }
+
+        let mut path = Vec::new();
+        let mut next = best
+            .keys()
+            .copied()
+            .max_by(|a, b| self.compare_chains(&best, *a, *b));
+        while let Some(node) = next {
+            path.push(self.graph[node].id.clone());
+            next = best[&node].previous;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Order the chains ending at `a` and `b` by weight, then length, preferring the
+    /// smaller step ID on ties
+    fn compare_chains(
+        &self,
+        best: &HashMap<NodeIndex, Chain>,
+        a: NodeIndex,
+        b: NodeIndex,
+    ) -> std::cmp::Ordering {
+        best[&a]
+            .weight
+            .total_cmp(&best[&b].weight)
+            .then_with(|| best[&a].steps.cmp(&best[&b].steps))
+            .then_with(|| self.graph[b].id.0.cmp(&self.graph[a].id.0))
+    }
+
     /// Get all steps with no dependencies (can start immediately)
     pub fn entry_steps(&self) -> Vec<StepSpec> {
         self.graph
@@ -119,10 +423,17 @@ impl WorkflowDag {
     }
 }
 
+/// The heaviest chain of steps ending at a node, found by `WorkflowDag::longest_path`
+struct Chain {
+    weight: f64,
+    steps: usize,
+    previous: Option<NodeIndex>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{RoleId, StepAction};
+    use crate::types::{RetryPolicy, RoleId};
 
     fn create_test_step(id: &str, name: &str) -> StepSpec {
         StepSpec {
@@ -154,6 +465,7 @@ mod tests {
             .iter()
             .cloned()
             .collect(),
+            inputs: Vec::new(),
         };
 
         let dag = WorkflowDag::from_workflow(&workflow).unwrap();
@@ -181,6 +493,7 @@ mod tests {
             .iter()
             .cloned()
             .collect(),
+            inputs: Vec::new(),
         };
 
         let dag = WorkflowDag::from_workflow(&workflow).unwrap();
@@ -193,6 +506,149 @@ mod tests {
         assert_eq!(deps.len(), 2);
     }
 
+    #[test]
+    fn test_lint_clean_workflow() {
+        let workflow = WorkflowSpec {
+            steps: vec![
+                create_test_step("step1", "Step 1"),
+                create_test_step("step2", "Step 2"),
+            ],
+            dependencies: HashMap::from([(StepId::new("step2"), vec![StepId::new("step1")])]),
+            inputs: Vec::new(),
+        };
+
+        let dag = WorkflowDag::from_workflow(&workflow).unwrap();
+        assert!(dag.lint().is_empty());
+
+        // A lone step is not an orphan
+        let single = WorkflowSpec {
+            steps: vec![create_test_step("step1", "Step 1")],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+        assert!(WorkflowDag::from_workflow(&single).unwrap().lint().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_approvers() {
+        let board = ApprovalBoard {
+            id: crate::types::ApprovalBoardId::new("release-board"),
+            name: "Release board".to_string(),
+            description: String::new(),
+            approvers: vec![],
+            quorum_rule: crate::types::QuorumRule::Unanimous,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let directory = ApproverDirectory::new(&[], &[board]);
+
+        let mut approve = create_test_step("approve", "Approve");
+        approve.action = StepAction::ManualApproval {
+            approvers: vec!["release-board".to_string(), "nobody".to_string()],
+        };
+        let mut nested = create_test_step("nested", "Nested");
+        let mut nested_approve = create_test_step("nested_approve", "Nested approve");
+        nested_approve.action = StepAction::ManualApproval {
+            approvers: vec!["ghost".to_string()],
+        };
+        nested.action = StepAction::SubWorkflow {
+            workflow: WorkflowSpec {
+                steps: vec![nested_approve],
+                dependencies: HashMap::new(),
+                inputs: Vec::new(),
+            },
+        };
+
+        let workflow = WorkflowSpec {
+            steps: vec![approve, nested],
+            dependencies: HashMap::from([(StepId::new("nested"), vec![StepId::new("approve")])]),
+            inputs: Vec::new(),
+        };
+        let mut unknown = WorkflowDag::from_workflow(&workflow)
+            .unwrap()
+            .unknown_approvers(&directory);
+        unknown.sort_by(|a, b| a.step_id.0.cmp(&b.step_id.0));
+
+        assert_eq!(unknown.len(), 2);
+        assert!(unknown.iter().all(|w| w.kind == LintWarningKind::UnknownApprover));
+        assert_eq!(unknown[0].step_id.0, "approve");
+        assert!(unknown[0].message.contains("nobody"));
+        assert_eq!(unknown[1].step_id.0, "nested_approve");
+    }
+
+    #[test]
+    fn test_lint_warnings() {
+        let mut no_role = create_test_step("no_role", "No role");
+        no_role.role = RoleId::new("");
+
+        let mut no_approvers = create_test_step("no_approvers", "No approvers");
+        no_approvers.action = StepAction::ManualApproval { approvers: vec![] };
+
+        let mut no_retries = create_test_step("no_retries", "No retries");
+        no_retries.retry_policy = Some(RetryPolicy {
+            max_attempts: 0,
+            backoff_secs: 1,
+        });
+
+        let workflow = WorkflowSpec {
+            steps: vec![
+                no_role,
+                no_approvers,
+                no_retries,
+                create_test_step("orphan", "Orphan"),
+            ],
+            dependencies: HashMap::from([
+                (StepId::new("no_approvers"), vec![StepId::new("no_role")]),
+                (StepId::new("no_retries"), vec![StepId::new("no_approvers")]),
+            ]),
+            inputs: Vec::new(),
+        };
+
+        let dag = WorkflowDag::from_workflow(&workflow).unwrap();
+        let warnings: Vec<(LintWarningKind, String)> = dag
+            .lint()
+            .into_iter()
+            .map(|w| (w.kind, w.step_id.0))
+            .collect();
+
+        assert_eq!(
+            warnings,
+            vec![
+                (LintWarningKind::MissingRole, "no_role".to_string()),
+                (LintWarningKind::EmptyApprovers, "no_approvers".to_string()),
+                (LintWarningKind::ZeroRetryAttempts, "no_retries".to_string()),
+                (LintWarningKind::OrphanStep, "orphan".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_longest_path_keeps_weightless_steps() {
+        // step1 and step2 both feed step3, which feeds step4
+        let workflow = WorkflowSpec {
+            steps: vec![
+                create_test_step("step1", "Step 1"),
+                create_test_step("step2", "Step 2"),
+                create_test_step("step3", "Step 3"),
+                create_test_step("step4", "Step 4"),
+            ],
+            dependencies: HashMap::from([
+                (StepId::new("step3"), vec![StepId::new("step1"), StepId::new("step2")]),
+                (StepId::new("step4"), vec![StepId::new("step3")]),
+            ]),
+            inputs: Vec::new(),
+        };
+        let dag = WorkflowDag::from_workflow(&workflow).unwrap();
+        let ids = |path: Vec<StepId>| -> Vec<String> { path.into_iter().map(|s| s.0).collect() };
+
+        // Without weights every chain ties, and the longest one wins
+        assert_eq!(ids(dag.longest_path(|_| 0.0)), vec!["step1", "step3", "step4"]);
+
+        // Steps after the heaviest one still extend the path
+        let weight = |id: &StepId| if id.0 == "step2" { 10.0 } else { 0.0 };
+        assert_eq!(ids(dag.longest_path(weight)), vec!["step2", "step3", "step4"]);
+    }
+
     #[test]
     fn test_cyclic_dag_rejected() {
         let workflow = WorkflowSpec {
@@ -207,6 +663,7 @@ mod tests {
             .iter()
             .cloned()
             .collect(),
+            inputs: Vec::new(),
         };
 
         let result = WorkflowDag::from_workflow(&workflow);
@@ -1,12 +1,190 @@
-use super::dag::WorkflowDag;
-use super::step_executor::StepExecutor;
+use super::dag::{WorkflowDag, WorkflowLimits};
+use super::replay::{step_path, ReplayReport, RunCapture};
+use super::step_executor::{
+    Artifact, DryAgentResponses, ScriptConfig, StepExecutor, StepResult, ToolInvoker,
+};
+use crate::capacity::CapacityBroker;
 use crate::events::{Event, EventLog, EventType};
+use crate::policy::PolicyEngine;
+use crate::redaction::Redactor;
 use crate::storage::{BlobStore, IndexStore};
-use crate::types::{Run, RunId, RunStatus, StepExecution, StepId, StepStatus, WorkflowSpec};
+use crate::tenant::TenantId;
+use crate::types::{
+    RetryFrom, RoleId, Run, RunId, RunStatus, StepAction, StepExecution, StepId, StepSpec,
+    StepStatus, WorkflowSpec, DEFAULT_RUN_PRIORITY,
+};
 use anyhow::{Context, Result};
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{oneshot, watch, RwLock};
+
+/// Maximum nesting depth of sub-workflows, guarding against unbounded recursion
+pub const MAX_SUBWORKFLOW_DEPTH: usize = 8;
+
+/// Cancellation handle for an in-flight run
+struct ActiveRun {
+    cancel_tx: watch::Sender<bool>,
+    parent_run_id: Option<RunId>,
+}
+
+/// Step slots per concurrency-limited role
+type RoleSlots = HashMap<RoleId, Arc<PrioritySlots>>;
+
+/// A limited number of slots, handed to the highest-priority waiter first and to the
+/// longest-waiting among equals
+struct PrioritySlots {
+    state: Mutex<SlotState>,
+}
+
+struct SlotState {
+    limit: u32,
+    /// Negative after the limit drops below the slots held, until enough are released
+    free: i64,
+    waiters: BinaryHeap<SlotWaiter>,
+    next_seq: u64,
+}
+
+/// A step waiting for a slot
+struct SlotWaiter {
+    priority: u8,
+    seq: u64,
+    tx: oneshot::Sender<SlotPermit>,
+}
+
+impl PartialEq for SlotWaiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.seq == other.seq
+    }
+}
+
+impl Eq for SlotWaiter {}
+
+impl PartialOrd for SlotWaiter {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SlotWaiter {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Higher priority first, then earlier arrivals first
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// A held slot, passed on to the next waiter when dropped
+struct SlotPermit {
+    slots: Option<Arc<PrioritySlots>>,
+}
+
+impl PrioritySlots {
+    fn new(limit: u32) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(SlotState {
+                limit,
+                free: limit.into(),
+                waiters: BinaryHeap::new(),
+                next_seq: 0,
+            }),
+        })
+    }
+
+    fn is_full(&self) -> bool {
+        self.state.lock().unwrap().free <= 0
+    }
+
+    /// Change the limit, keeping held slots and queued waiters. Waiters get any slots a
+    /// raised limit frees; a lowered one takes effect as held slots are released.
+    fn resize(self: &Arc<Self>, limit: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.free += i64::from(limit) - i64::from(state.limit);
+        state.limit = limit;
+        while state.free > 0 {
+            let Some(waiter) = state.waiters.pop() else {
+                break;
+            };
+            state.free -= 1;
+            let permit = SlotPermit {
+                slots: Some(self.clone()),
+            };
+            // The waiting step was cancelled, so its slot stays free
+            if let Err(mut permit) = waiter.tx.send(permit) {
+                permit.slots = None;
+                state.free += 1;
+            }
+        }
+    }
+
+    async fn acquire(self: &Arc<Self>, priority: u8) -> Result<SlotPermit> {
+        let rx = {
+            let mut state = self.state.lock().unwrap();
+            if state.free > 0 {
+                state.free -= 1;
+                return Ok(SlotPermit {
+                    slots: Some(self.clone()),
+                });
+            }
+            let (tx, rx) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiters.push(SlotWaiter { priority, seq, tx });
+            rx
+        };
+
+        rx.await
+            .map_err(|_| anyhow::anyhow!("Role slot was dropped while waiting for it"))
+    }
+
+    fn release(self: &Arc<Self>) {
+        let mut state = self.state.lock().unwrap();
+        // Retire the slot if the limit was lowered while it was held
+        if state.free < 0 {
+            state.free += 1;
+            return;
+        }
+        while let Some(waiter) = state.waiters.pop() {
+            let permit = SlotPermit {
+                slots: Some(self.clone()),
+            };
+            match waiter.tx.send(permit) {
+                Ok(()) => return,
+                // The waiting step was cancelled; make sure the permit doesn't release again
+                Err(mut permit) => permit.slots = None,
+            }
+        }
+        state.free += 1;
+    }
+}
+
+impl Drop for SlotPermit {
+    fn drop(&mut self) {
+        if let Some(slots) = self.slots.take() {
+            slots.release();
+        }
+    }
+}
+
+/// The run a new run retries, and the completed steps it carries over from it
+struct RetrySource {
+    run_id: RunId,
+    reused: HashMap<StepId, ReusedStep>,
+}
+
+/// A step that completed in the retried run, with the artifacts it produced
+struct ReusedStep {
+    execution: StepExecution,
+    artifacts: Vec<Artifact>,
+}
+
+/// How a DAG execution ended
+enum DagOutcome {
+    Completed(Vec<StepExecution>),
+    Cancelled(Vec<StepExecution>),
+    /// A step failed; the executions record how far the run got
+    Failed(Vec<StepExecution>, anyhow::Error),
+}
 
 /// Workflow executor that coordinates DAG execution
 pub struct WorkflowExecutor {
@@ -15,7 +193,10 @@ pub struct WorkflowExecutor {
     index_store: Arc<dyn IndexStore>,
     step_executor: Arc<StepExecutor>,
     // Track active runs for cancellation
-    active_runs: Arc<RwLock<HashMap<RunId, tokio::sync::watch::Sender<bool>>>>,
+    active_runs: Arc<RwLock<HashMap<RunId, ActiveRun>>>,
+    // Step slots for roles with `max_concurrent`, shared by every run
+    role_slots: Arc<Mutex<RoleSlots>>,
+    limits: WorkflowLimits,
 }
 
 impl WorkflowExecutor {
@@ -35,25 +216,307 @@ impl WorkflowExecutor {
             index_store,
             step_executor,
             active_runs: Arc::new(RwLock::new(HashMap::new())),
+            role_slots: Arc::new(Mutex::new(HashMap::new())),
+            limits: WorkflowLimits::default(),
         }
     }
 
+    /// Refuse to run workflows, including sub-workflows, that exceed `limits`
+    pub fn with_workflow_limits(mut self, limits: WorkflowLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Run the tools of `ToolSequence` steps through `tools`
+    pub fn with_tools(mut self, tools: Arc<dyn ToolInvoker>) -> Self {
+        let step_executor = (*self.step_executor).clone();
+        self.step_executor = Arc::new(step_executor.with_tools(tools));
+        self
+    }
+
+    /// Send agent task prompts to an LLM through `broker`
+    pub fn with_capacity_broker(mut self, broker: Arc<CapacityBroker>) -> Self {
+        let step_executor = (*self.step_executor).clone();
+        self.step_executor = Arc::new(step_executor.with_capacity_broker(broker));
+        self
+    }
+
+    /// Answer agent tasks with canned `responses`, bypassing the capacity broker
+    pub fn with_dry_agent(mut self, responses: DryAgentResponses) -> Self {
+        let step_executor = (*self.step_executor).clone();
+        self.step_executor = Arc::new(step_executor.with_dry_agent(responses));
+        self
+    }
+
+    /// Redact secrets from script output before it is stored
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        let step_executor = (*self.step_executor).clone();
+        self.step_executor = Arc::new(step_executor.with_redactor(redactor));
+        self
+    }
+
+    /// Check tool calls against the step role's policy before running them
+    pub fn with_policy_engine(mut self, policy_engine: Arc<dyn PolicyEngine>) -> Self {
+        let step_executor = (*self.step_executor).clone();
+        self.step_executor = Arc::new(step_executor.with_policy_engine(policy_engine));
+        self
+    }
+
+    /// Allow script steps as `config` permits; they are refused otherwise
+    pub fn with_scripts(mut self, config: ScriptConfig) -> Self {
+        let step_executor = (*self.step_executor).clone();
+        self.step_executor = Arc::new(step_executor.with_scripts(config));
+        self
+    }
+
     /// Execute a workflow and return the run
     pub async fn execute(&self, work_item_id: String, workflow: WorkflowSpec) -> Result<Run> {
+        self.execute_run(
+            work_item_id,
+            workflow,
+            None,
+            HashMap::new(),
+            HashMap::new(),
+            DEFAULT_RUN_PRIORITY,
+            None,
+            None,
+            0,
+            None,
+        )
+        .await
+    }
+
+    /// Start a new run of a finished run's workflow.
+    ///
+    /// With `RetryFrom::Failed`, steps that completed in the original run are not
+    /// executed again: the new run records their results and artifacts as-is and
+    /// executes from the first step that didn't complete. `RetryFrom::Start` executes
+    /// every step.
+    pub async fn retry(&self, run_id: RunId, from: RetryFrom) -> Result<Run> {
+        let original = self
+            .index_store
+            .get_run(&run_id)?
+            .ok_or_else(|| anyhow::anyhow!("Run {} not found", run_id))?;
+        if !original.status.is_terminal() {
+            return Err(anyhow::anyhow!("Run {} is still in progress", run_id));
+        }
+
+        let events = self.event_log.get_run_events(run_id).await?;
+        let workflow = recorded_workflow(run_id, &events)?;
+
+        let reused = match from {
+            RetryFrom::Start => HashMap::new(),
+            RetryFrom::Failed if original.status == RunStatus::Completed => {
+                return Err(anyhow::anyhow!("Run {} has no failed steps to retry", run_id));
+            }
+            RetryFrom::Failed => completed_steps(&original, &events),
+        };
+
+        tracing::info!(
+            "Retrying run {} from {:?}, reusing {} completed steps",
+            run_id,
+            from,
+            reused.len()
+        );
+
+        self.execute_run(
+            original.work_item_id,
+            workflow,
+            None,
+            original.tags,
+            original.inputs,
+            original.priority,
+            original.tenant_id,
+            None,
+            0,
+            Some(RetrySource { run_id, reused }),
+        )
+        .await
+    }
+
+    /// Re-execute a finished run's workflow as a new run, feeding each step the agent
+    /// responses, tool results and failures its original run captured instead of making
+    /// live calls, and report whether the same path through the DAG was taken.
+    ///
+    /// The replay run is tagged `replay_of` with the original run's ID. Runs with
+    /// sub-workflow steps can't be replayed.
+    pub async fn replay(&self, run_id: RunId) -> Result<ReplayReport> {
+        let original = self
+            .index_store
+            .get_run(&run_id)?
+            .ok_or_else(|| anyhow::anyhow!("Run {} not found", run_id))?;
+        if !original.status.is_terminal() {
+            return Err(anyhow::anyhow!("Run {} is still in progress", run_id));
+        }
+
+        let events = self.event_log.get_run_events(run_id).await?;
+        let workflow = recorded_workflow(run_id, &events)?;
+        if workflow
+            .steps
+            .iter()
+            .any(|s| matches!(s.action, StepAction::SubWorkflow { .. }))
+        {
+            return Err(anyhow::anyhow!(
+                "Run {} has sub-workflow steps, which can't be replayed",
+                run_id
+            ));
+        }
+
+        let capture = RunCapture::from_events(run_id, &events, self.blob_store.as_ref()).await?;
+        let replayer = WorkflowExecutor {
+            event_log: self.event_log.clone(),
+            blob_store: self.blob_store.clone(),
+            index_store: self.index_store.clone(),
+            step_executor: Arc::new(
+                (*self.step_executor)
+                    .clone()
+                    .with_replay(Arc::new(capture)),
+            ),
+            active_runs: self.active_runs.clone(),
+            role_slots: self.role_slots.clone(),
+            limits: self.limits.clone(),
+        };
+
+        let mut tags = original.tags;
+        tags.insert("replay_of".to_string(), run_id.to_string());
+        let replay = replayer
+            .execute_run(
+                original.work_item_id,
+                workflow,
+                None,
+                tags,
+                original.inputs,
+                original.priority,
+                original.tenant_id,
+                None,
+                0,
+                None,
+            )
+            .await?;
+
+        let report = ReplayReport::new(
+            run_id,
+            replay.id,
+            step_path(&events),
+            step_path(&self.event_log.get_run_events(replay.id).await?),
+        );
+        if let Some(index) = report.diverged_at {
+            tracing::warn!(
+                "Replay {} of run {} diverged at step {}",
+                replay.id,
+                run_id,
+                index
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Execute a workflow whose run can later be looked up by `external_id`.
+    /// Fails without starting the run if the external ID is already taken.
+    pub async fn execute_with_external_id(
+        &self,
+        work_item_id: String,
+        workflow: WorkflowSpec,
+        external_id: Option<String>,
+    ) -> Result<Run> {
+        self.execute_tagged(
+            work_item_id,
+            workflow,
+            external_id,
+            HashMap::new(),
+            HashMap::new(),
+            DEFAULT_RUN_PRIORITY,
+            None,
+        )
+        .await
+    }
+
+    /// Like `execute_with_external_id`, labelling the run with `tags` and starting it
+    /// with `inputs` at `priority` for `tenant_id`. Sub-workflow runs and retries carry the
+    /// same tags, inputs, priority and tenant. Fails without starting the run if a required
+    /// input is missing.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn execute_tagged(
+        &self,
+        work_item_id: String,
+        workflow: WorkflowSpec,
+        external_id: Option<String>,
+        tags: HashMap<String, String>,
+        inputs: HashMap<String, serde_json::Value>,
+        priority: u8,
+        tenant_id: Option<TenantId>,
+    ) -> Result<Run> {
+        if let Some(external_id) = &external_id {
+            if let Some(existing) = self.index_store.get_run_by_external_id(external_id)? {
+                return Err(anyhow::anyhow!(
+                    "External ID {} is already used by run {}",
+                    external_id,
+                    existing.id
+                ));
+            }
+        }
+
+        self.execute_run(
+            work_item_id,
+            workflow,
+            external_id,
+            tags,
+            inputs,
+            priority,
+            tenant_id,
+            None,
+            0,
+            None,
+        )
+        .await
+    }
+
+    /// Execute a workflow as a run, optionally as the child of another run or as the
+    /// retry of a finished one
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_run(
+        &self,
+        work_item_id: String,
+        workflow: WorkflowSpec,
+        external_id: Option<String>,
+        tags: HashMap<String, String>,
+        inputs: HashMap<String, serde_json::Value>,
+        priority: u8,
+        tenant_id: Option<TenantId>,
+        parent_run_id: Option<RunId>,
+        depth: usize,
+        retry: Option<RetrySource>,
+    ) -> Result<Run> {
+        let inputs = workflow.resolve_inputs(inputs)?;
+
+        // Build the DAG before registering the run, so a rejected spec leaves nothing behind.
+        // The recorded spec keeps its placeholders, so retries render it afresh.
+        let rendered = render_inputs(&workflow, &inputs);
+        let dag = WorkflowDag::from_workflow_with_limits(&rendered, &self.limits)
+            .context("Failed to build DAG")?;
+
         let run_id = RunId::new();
         let started_at = chrono::Utc::now();
 
-        tracing::info!("Starting workflow execution: run_id={}", run_id);
+        tracing::info!(
+            "Starting workflow execution: run_id={}, parent_run_id={:?}",
+            run_id,
+            parent_run_id
+        );
 
         // Create cancellation channel
-        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
-        self.active_runs.write().await.insert(run_id, cancel_tx);
-
-        // Build DAG
-        let dag = WorkflowDag::from_workflow(&workflow).context("Failed to build DAG")?;
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        self.active_runs.write().await.insert(
+            run_id,
+            ActiveRun {
+                cancel_tx,
+                parent_run_id,
+            },
+        );
 
         // Initialize run state
-        let mut run = Run {
+        let run = Run {
             id: run_id,
             work_item_id: work_item_id.clone(),
             status: RunStatus::Running,
@@ -69,8 +532,18 @@ impl WorkflowExecutor {
                     completed_at: None,
                     attempt: 0,
                     error: None,
+                    tokens: None,
+                    cost: None,
                 })
                 .collect(),
+            parent_run_id,
+            external_id,
+            workflow_hash: Some(workflow.content_hash()),
+            retry_of: retry.as_ref().map(|retry| retry.run_id),
+            tags,
+            inputs,
+            priority,
+            tenant_id,
         };
 
         // Emit RunStarted event
@@ -78,7 +551,7 @@ impl WorkflowExecutor {
             .append(Event::new(
                 run_id,
                 EventType::RunStarted {
-                    work_item_id,
+                    work_item_id: work_item_id.clone(),
                     workflow_spec: workflow.clone(),
                 },
             ))
@@ -87,19 +560,97 @@ impl WorkflowExecutor {
         // Index the run
         self.index_store.index_run(&run)?;
 
+        self.drive_run(run, &dag, &rendered, depth, retry.as_ref(), cancel_rx).await
+    }
+
+    /// Pick up a run whose execution was interrupted, e.g. by a restart while one of its
+    /// steps was parked waiting for an event. Steps that completed are kept and the rest
+    /// are executed under the same run ID; a parked step goes back to waiting, seeing
+    /// signals delivered in the meantime.
+    pub async fn resume(&self, run_id: RunId) -> Result<Run> {
+        let run = self
+            .index_store
+            .get_run(&run_id)?
+            .ok_or_else(|| anyhow::anyhow!("Run {} not found", run_id))?;
+        if run.status.is_terminal() {
+            return Err(anyhow::anyhow!("Run {} has already finished", run_id));
+        }
+
+        let events = self.event_log.get_run_events(run_id).await?;
+        let workflow = recorded_workflow(run_id, &events)?;
+        let rendered = render_inputs(&workflow, &run.inputs);
+        let dag = WorkflowDag::from_workflow_with_limits(&rendered, &self.limits)
+            .context("Failed to build DAG")?;
+
+        let mut depth = 0;
+        let mut parent_run_id = run.parent_run_id;
+        while let Some(parent) = parent_run_id {
+            depth += 1;
+            parent_run_id = self.index_store.get_run(&parent)?.and_then(|p| p.parent_run_id);
+        }
+
+        let cancel_rx = {
+            let mut active_runs = self.active_runs.write().await;
+            if active_runs.contains_key(&run_id) {
+                return Err(anyhow::anyhow!("Run {} is already executing", run_id));
+            }
+            let (cancel_tx, cancel_rx) = watch::channel(false);
+            active_runs.insert(
+                run_id,
+                ActiveRun {
+                    cancel_tx,
+                    parent_run_id: run.parent_run_id,
+                },
+            );
+            cancel_rx
+        };
+
+        let retry = RetrySource {
+            run_id,
+            reused: finished_steps(&events),
+        };
+        tracing::info!(
+            "Resuming run {}, keeping {} completed steps",
+            run_id,
+            retry.reused.len()
+        );
+
+        self.drive_run(run, &dag, &rendered, depth, Some(&retry), cancel_rx).await
+    }
+
+    /// Execute a registered run's DAG and record how it ended
+    async fn drive_run(
+        &self,
+        mut run: Run,
+        dag: &WorkflowDag,
+        rendered: &WorkflowSpec,
+        depth: usize,
+        retry: Option<&RetrySource>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<Run> {
+        let run_id = run.id;
+
         // Execute the workflow
         let result = self
-            .execute_dag(run_id, &dag, &workflow, cancel_rx)
-            .await;
+            .execute_dag(
+                run_id,
+                &run.work_item_id,
+                dag,
+                rendered,
+                run.priority,
+                depth,
+                retry,
+                cancel_rx,
+            )
+            .await
+            .unwrap_or_else(|e| DagOutcome::Failed(run.steps.clone(), e));
 
         // Update run status
-        let duration = started_at.elapsed_seconds_from(chrono::Utc::now());
-        let completed_at = chrono::Utc::now();
-        run.completed_at = Some(completed_at);
+        let duration = run.started_at.elapsed_seconds_from(chrono::Utc::now());
 
         match result {
-            Ok(steps) => {
-                run.status = RunStatus::Completed;
+            DagOutcome::Completed(steps) => {
+                run.transition_to(RunStatus::Completed)?;
                 run.steps = steps;
 
                 self.event_log
@@ -113,8 +664,17 @@ impl WorkflowExecutor {
 
                 tracing::info!("Workflow execution completed: run_id={}", run_id);
             }
-            Err(e) => {
-                run.status = RunStatus::Failed;
+            DagOutcome::Cancelled(steps) => {
+                // `cancel` has already recorded the RunCancelled event
+                run.transition_to(RunStatus::Cancelled)?;
+                run.steps = steps;
+
+                tracing::info!("Workflow execution cancelled: run_id={}", run_id);
+            }
+            DagOutcome::Failed(steps, e) => {
+                // Keep how far the run got, so it can be retried from the failure
+                run.transition_to(RunStatus::Failed)?;
+                run.steps = steps;
 
                 self.event_log
                     .append(Event::new(
@@ -140,13 +700,18 @@ impl WorkflowExecutor {
     }
 
     /// Execute the DAG
+    #[allow(clippy::too_many_arguments)]
     async fn execute_dag(
         &self,
         run_id: RunId,
+        work_item_id: &str,
         dag: &WorkflowDag,
         workflow: &WorkflowSpec,
-        cancel_rx: tokio::sync::watch::Receiver<bool>,
-    ) -> Result<Vec<StepExecution>> {
+        priority: u8,
+        depth: usize,
+        retry: Option<&RetrySource>,
+        mut cancel_rx: watch::Receiver<bool>,
+    ) -> Result<DagOutcome> {
         let mut completed_steps: HashSet<StepId> = HashSet::new();
         let mut failed_steps: HashSet<StepId> = HashSet::new();
         let mut step_executions: HashMap<StepId, StepExecution> = HashMap::new();
@@ -172,6 +737,8 @@ impl WorkflowExecutor {
                     completed_at: None,
                     attempt: 0,
                     error: None,
+                    tokens: None,
+                    cost: None,
                 },
             );
         }
@@ -184,7 +751,15 @@ impl WorkflowExecutor {
             // Check for cancellation
             if *cancel_rx.borrow() {
                 tracing::warn!("Workflow execution cancelled: run_id={}", run_id);
-                return Err(anyhow::anyhow!("Workflow cancelled"));
+
+                for exec in step_executions.values_mut() {
+                    if exec.status == StepStatus::Pending {
+                        self.skip_step(run_id, &exec.id, "Run cancelled").await?;
+                        exec.status = StepStatus::Skipped;
+                    }
+                }
+
+                return Ok(DagOutcome::Cancelled(sorted_executions(step_executions)));
             }
 
             // Skip if dependencies failed
@@ -212,11 +787,50 @@ impl WorkflowExecutor {
                 continue;
             }
 
+            // Carry over the step's result from the run being retried, or from before
+            // this run was resumed, in which case its events are already logged
+            if let Some((source, reused)) = retry
+                .and_then(|retry| retry.reused.get(&step.id).map(|reused| (retry.run_id, reused)))
+            {
+                if source != run_id {
+                    self.reuse_step(run_id, source, reused).await?;
+                }
+                step_executions.insert(step.id.clone(), reused.execution.clone());
+                completed_steps.insert(step.id.clone());
+                continue;
+            }
+
             // Execute the step
             tracing::info!("Executing step: {}", step.id);
 
             let started_at = chrono::Utc::now();
-            let result = self.step_executor.execute(run_id, &step, 1).await?;
+            let result = match &step.action {
+                StepAction::SubWorkflow { workflow: child } => {
+                    // Not raced against cancellation: `cancel` reaches the child run,
+                    // which winds itself down and returns
+                    self.execute_sub_workflow(
+                        run_id,
+                        work_item_id,
+                        &step,
+                        child,
+                        priority,
+                        depth,
+                        &cancel_rx,
+                    )
+                    .await?
+                }
+                _ => {
+                    let result = tokio::select! {
+                        result = self.execute_step(run_id, &step, priority) => Some(result?),
+                        _ = cancellation(&mut cancel_rx) => None,
+                    };
+
+                    match result {
+                        Some(result) => result,
+                        None => self.skip_step(run_id, &step.id, "Run cancelled").await?,
+                    }
+                }
+            };
             let completed_at = chrono::Utc::now();
 
             // Update execution state
@@ -226,6 +840,8 @@ impl WorkflowExecutor {
                 exec.completed_at = Some(completed_at);
                 exec.attempt = 1;
                 exec.error = result.error.clone();
+                exec.tokens = result.tokens;
+                exec.cost = result.cost;
             }
 
             match result.status {
@@ -236,60 +852,1590 @@ impl WorkflowExecutor {
                     failed_steps.insert(step.id.clone());
                     // For now, fail the entire workflow on any step failure
                     // In the future, we could make this configurable
-                    return Err(anyhow::anyhow!(
+                    let error = anyhow::anyhow!(
                         "Step {} failed: {}",
                         step.id,
                         result.error.unwrap_or_else(|| "Unknown error".to_string())
-                    ));
+                    );
+                    return Ok(DagOutcome::Failed(sorted_executions(step_executions), error));
                 }
                 _ => {}
             }
         }
 
-        // Convert executions to Vec
-        let mut executions: Vec<StepExecution> = step_executions.into_values().collect();
-        executions.sort_by(|a, b| a.id.0.cmp(&b.id.0));
+        Ok(DagOutcome::Completed(sorted_executions(step_executions)))
+    }
 
-        Ok(executions)
+    /// Run a step once a slot is free for its role, if the role limits concurrency
+    async fn execute_step(
+        &self,
+        run_id: RunId,
+        step: &StepSpec,
+        priority: u8,
+    ) -> Result<StepResult> {
+        let _slot = self.acquire_role_slot(run_id, step, priority).await?;
+        self.step_executor.execute(run_id, step, 1, priority).await
     }
 
-    /// Get the status of a running workflow
-    pub async fn get_run(&self, run_id: RunId) -> Result<Option<Run>> {
-        self.index_store.get_run(&run_id)
+    /// Wait for one of the step's role's `max_concurrent` slots. Steps of higher-priority
+    /// runs are given a freed slot first. Returns `None` for roles without a limit.
+    async fn acquire_role_slot(
+        &self,
+        run_id: RunId,
+        step: &StepSpec,
+        priority: u8,
+    ) -> Result<Option<SlotPermit>> {
+        let Some(limit) = self
+            .index_store
+            .get_role(&step.role)?
+            .and_then(|role| role.max_concurrent)
+        else {
+            return Ok(None);
+        };
+        let limit = limit.max(1);
+
+        let slots = {
+            let mut role_slots = self.role_slots.lock().unwrap();
+            let slots = role_slots
+                .entry(step.role.clone())
+                .or_insert_with(|| PrioritySlots::new(limit));
+            // Steps already running or queued count against a changed limit
+            slots.resize(limit);
+            slots.clone()
+        };
+
+        if slots.is_full() {
+            tracing::info!(
+                "Step {} of run {} queued: role {} is at its limit of {} concurrent steps",
+                step.id,
+                run_id,
+                step.role.0,
+                limit
+            );
+        }
+
+        Ok(Some(slots.acquire(priority).await?))
     }
 
-    /// Cancel a running workflow
-    pub async fn cancel(&self, run_id: RunId) -> Result<()> {
-        let active_runs = self.active_runs.read().await;
+    /// Record a step as completed with the artifacts it produced in `source_run_id`,
+    /// without running it again
+    async fn reuse_step(
+        &self,
+        run_id: RunId,
+        source_run_id: RunId,
+        reused: &ReusedStep,
+    ) -> Result<()> {
+        let step_id = &reused.execution.id;
+        tracing::info!("Reusing result of step {} from run {}", step_id, source_run_id);
+
+        let duration_secs = match (reused.execution.started_at, reused.execution.completed_at) {
+            (Some(started_at), Some(completed_at)) => {
+                started_at.elapsed_seconds_from(completed_at).max(0) as u64
+            }
+            _ => 0,
+        };
 
-        if let Some(cancel_tx) = active_runs.get(&run_id) {
-            cancel_tx.send(true).ok();
-            tracing::info!("Cancellation signal sent for run {}", run_id);
+        self.event_log
+            .append(Event::new(
+                run_id,
+                EventType::StepCompleted {
+                    step_id: step_id.clone(),
+                    duration_secs,
+                },
+            ))
+            .await?;
 
-            // Emit cancellation event
+        for artifact in &reused.artifacts {
             self.event_log
                 .append(Event::new(
                     run_id,
-                    EventType::RunCancelled {
-                        reason: "User requested cancellation".to_string(),
+                    EventType::ArtifactProduced {
+                        step_id: step_id.clone(),
+                        artifact_type: artifact.artifact_type.clone(),
+                        content_hash: artifact.content_hash.clone(),
+                        metadata: artifact.metadata.clone(),
                     },
                 ))
                 .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Record a step as skipped without running it
+    async fn skip_step(&self, run_id: RunId, step_id: &StepId, reason: &str) -> Result<StepResult> {
+        self.event_log
+            .append(Event::new(
+                run_id,
+                EventType::StepSkipped {
+                    step_id: step_id.clone(),
+                    reason: reason.to_string(),
+                },
+            ))
+            .await?;
+
+        Ok(StepResult {
+            status: StepStatus::Skipped,
+            error: None,
+            artifacts: vec![],
+            tokens: None,
+            cost: None,
+        })
+    }
+
+    /// Run a `SubWorkflow` step as a child run and roll its outcome up to the step
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_sub_workflow(
+        &self,
+        run_id: RunId,
+        work_item_id: &str,
+        step: &StepSpec,
+        child: &WorkflowSpec,
+        priority: u8,
+        depth: usize,
+        cancel_rx: &watch::Receiver<bool>,
+    ) -> Result<StepResult> {
+        self.event_log
+            .append(Event::new(
+                run_id,
+                EventType::StepStarted {
+                    step_id: step.id.clone(),
+                    attempt: 1,
+                },
+            ))
+            .await?;
+
+        let start = std::time::Instant::now();
 
-            Ok(())
+        let error = if depth + 1 > MAX_SUBWORKFLOW_DEPTH {
+            Some(format!(
+                "Sub-workflow nesting exceeds maximum depth of {}",
+                MAX_SUBWORKFLOW_DEPTH
+            ))
         } else {
-            Err(anyhow::anyhow!("Run {} is not active", run_id))
+            let (tags, inputs, tenant_id) = self
+                .index_store
+                .get_run(&run_id)?
+                .map(|parent| (parent.tags, parent.inputs, parent.tenant_id))
+                .unwrap_or_default();
+
+            // Boxed because sub-workflows recurse back into run execution
+            let child_run = Box::pin(self.execute_run(
+                work_item_id.to_string(),
+                child.clone(),
+                None,
+                tags,
+                inputs,
+                priority,
+                tenant_id,
+                Some(run_id),
+                depth + 1,
+                None,
+            ))
+            .await?;
+
+            match child_run.status {
+                RunStatus::Completed => None,
+                RunStatus::Cancelled if *cancel_rx.borrow() => {
+                    return self.skip_step(run_id, &step.id, "Run cancelled").await;
+                }
+                status => Some(format!(
+                    "Sub-workflow run {} finished with status {:?}",
+                    child_run.id, status
+                )),
+            }
+        };
+
+        match error {
+            None => {
+                self.event_log
+                    .append(Event::new(
+                        run_id,
+                        EventType::StepCompleted {
+                            step_id: step.id.clone(),
+                            duration_secs: start.elapsed().as_secs(),
+                        },
+                    ))
+                    .await?;
+
+                Ok(StepResult {
+                    status: StepStatus::Completed,
+                    error: None,
+                    artifacts: vec![],
+                    tokens: None,
+                    cost: None,
+                })
+            }
+            Some(error) => {
+                self.event_log
+                    .append(Event::new(
+                        run_id,
+                        EventType::StepFailed {
+                            step_id: step.id.clone(),
+                            error: error.clone(),
+                            attempt: 1,
+                            will_retry: false,
+                        },
+                    ))
+                    .await?;
+
+                Ok(StepResult {
+                    status: StepStatus::Failed,
+                    error: Some(error),
+                    artifacts: vec![],
+                    tokens: None,
+                    cost: None,
+                })
+            }
         }
     }
-}
 
-// Helper trait for duration calculation
-trait ElapsedSeconds {
-    fn elapsed_seconds_from(&self, other: chrono::DateTime<chrono::Utc>) -> i64;
-}
+    /// Whether this executor is executing the run
+    pub async fn is_active(&self, run_id: RunId) -> bool {
+        self.active_runs.read().await.contains_key(&run_id)
+    }
 
-impl ElapsedSeconds for chrono::DateTime<chrono::Utc> {
-    fn elapsed_seconds_from(&self, other: chrono::DateTime<chrono::Utc>) -> i64 {
-        (other - *self).num_seconds()
+    /// Get the status of a running workflow
+    pub async fn get_run(&self, run_id: RunId) -> Result<Option<Run>> {
+        self.index_store.get_run(&run_id)
+    }
+
+    /// Cancel a running workflow along with every sub-workflow run below it
+    pub async fn cancel(&self, run_id: RunId) -> Result<()> {
+        let cancelled = {
+            let active_runs = self.active_runs.read().await;
+
+            if !active_runs.contains_key(&run_id) {
+                return Err(anyhow::anyhow!("Run {} is not active", run_id));
+            }
+
+            let mut cancelled = vec![run_id];
+            let mut next = 0;
+            while let Some(&parent) = cancelled.get(next) {
+                cancelled.extend(
+                    active_runs
+                        .iter()
+                        .filter(|(_, run)| run.parent_run_id == Some(parent))
+                        .map(|(id, _)| *id),
+                );
+                next += 1;
+            }
+
+            for id in &cancelled {
+                active_runs[id].cancel_tx.send(true).ok();
+                tracing::info!("Cancellation signal sent for run {}", id);
+            }
+
+            cancelled
+        };
+
+        // Emit cancellation events
+        for id in cancelled {
+            let reason = if id == run_id {
+                "User requested cancellation".to_string()
+            } else {
+                format!("Parent run {} was cancelled", run_id)
+            };
+
+            self.event_log
+                .append(Event::new(id, EventType::RunCancelled { reason }))
+                .await?;
+        }
+
+        self.reap_orphans().await?;
+
+        Ok(())
+    }
+
+    /// Cancel active child runs whose parent run is no longer active, e.g. because the
+    /// parent's task was aborted. Their registry entries are dropped and any run still
+    /// marked as in flight is indexed as cancelled. Returns the reaped run IDs.
+    pub async fn reap_orphans(&self) -> Result<Vec<RunId>> {
+        let mut orphans = Vec::new();
+        {
+            let mut active_runs = self.active_runs.write().await;
+            loop {
+                let ids: Vec<RunId> = active_runs
+                    .iter()
+                    .filter(|(_, run)| {
+                        run.parent_run_id
+                            .is_some_and(|parent| !active_runs.contains_key(&parent))
+                    })
+                    .map(|(id, _)| *id)
+                    .collect();
+                if ids.is_empty() {
+                    break;
+                }
+
+                for id in ids {
+                    if let Some(orphan) = active_runs.remove(&id) {
+                        orphan.cancel_tx.send(true).ok();
+                        orphans.push((id, orphan.parent_run_id));
+                    }
+                }
+            }
+        }
+
+        for (id, parent_run_id) in &orphans {
+            tracing::warn!("Reaping orphaned run {} (parent {:?} is gone)", id, parent_run_id);
+
+            if let Some(mut run) = self.index_store.get_run(id)? {
+                if !run.status.is_terminal() {
+                    for step in &mut run.steps {
+                        if matches!(step.status, StepStatus::Pending | StepStatus::Running) {
+                            step.status = StepStatus::Skipped;
+                        }
+                    }
+                    run.transition_to(RunStatus::Cancelled)?;
+                    self.index_store.index_run(&run)?;
+                }
+            }
+
+            self.event_log
+                .append(Event::new(
+                    *id,
+                    EventType::RunCancelled {
+                        reason: "Parent run is no longer active".to_string(),
+                    },
+                ))
+                .await?;
+        }
+
+        Ok(orphans.into_iter().map(|(id, _)| id).collect())
+    }
+}
+
+/// Steps that completed in `run`, with the artifacts they produced
+fn completed_steps(run: &Run, events: &[Event]) -> HashMap<StepId, ReusedStep> {
+    let mut reused: HashMap<StepId, ReusedStep> = run
+        .steps
+        .iter()
+        .filter(|step| step.status == StepStatus::Completed)
+        .map(|step| {
+            let reused = ReusedStep {
+                execution: step.clone(),
+                artifacts: vec![],
+            };
+            (step.id.clone(), reused)
+        })
+        .collect();
+
+    for event in events {
+        if let EventType::ArtifactProduced {
+            step_id,
+            artifact_type,
+            content_hash,
+            metadata,
+        } = &event.event_type
+        {
+            if let Some(step) = reused.get_mut(step_id) {
+                step.artifacts.push(Artifact {
+                    artifact_type: artifact_type.clone(),
+                    content_hash: content_hash.clone(),
+                    metadata: metadata.clone(),
+                });
+            }
+        }
+    }
+
+    reused
+}
+
+/// Steps whose completion is recorded in a run's events
+fn finished_steps(events: &[Event]) -> HashMap<StepId, ReusedStep> {
+    let mut started = HashMap::new();
+    let mut finished = HashMap::new();
+    for event in events {
+        match &event.event_type {
+            EventType::StepStarted { step_id, attempt } => {
+                started.insert(step_id.clone(), (event.timestamp, *attempt));
+            }
+            EventType::StepCompleted { step_id, .. } => {
+                let (started_at, attempt) = started.get(step_id).copied().unzip();
+                let execution = StepExecution {
+                    id: step_id.clone(),
+                    status: StepStatus::Completed,
+                    started_at,
+                    completed_at: Some(event.timestamp),
+                    attempt: attempt.unwrap_or(1),
+                    error: None,
+                    tokens: None,
+                    cost: None,
+                };
+                finished.insert(
+                    step_id.clone(),
+                    ReusedStep {
+                        execution,
+                        artifacts: vec![],
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+    finished
+}
+
+/// Substitute `{{inputs.<name>}}` placeholders in agent prompts and tool parameters.
+/// String values are inserted as-is, others as JSON. Script commands, approvers and
+/// awaited event keys are never rendered, so a caller can't choose what runs, who
+/// approves or what unblocks a step; nested sub-workflows are left for their own run.
+fn render_inputs(
+    workflow: &WorkflowSpec,
+    inputs: &HashMap<String, serde_json::Value>,
+) -> WorkflowSpec {
+    let render = |text: &mut String| {
+        for (name, value) in inputs {
+            let placeholder = format!("{{{{inputs.{}}}}}", name);
+            if text.contains(&placeholder) {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                *text = text.replace(&placeholder, &value);
+            }
+        }
+    };
+
+    let mut workflow = workflow.clone();
+    for step in &mut workflow.steps {
+        match &mut step.action {
+            StepAction::AgentTask { prompt } => render(prompt),
+            StepAction::ToolSequence { tools, .. } => {
+                for tool in tools {
+                    render_json_strings(&mut tool.parameters, &render);
+                }
+            }
+            StepAction::ManualApproval { .. }
+            | StepAction::Script { .. }
+            | StepAction::SubWorkflow { .. }
+            | StepAction::WaitForEvent { .. } => {}
+        }
+    }
+    workflow
+}
+
+fn render_json_strings(value: &mut serde_json::Value, render: &impl Fn(&mut String)) {
+    match value {
+        serde_json::Value::String(s) => render(s),
+        serde_json::Value::Array(items) => {
+            items.iter_mut().for_each(|item| render_json_strings(item, render))
+        }
+        serde_json::Value::Object(map) => {
+            map.values_mut().for_each(|item| render_json_strings(item, render))
+        }
+        _ => {}
+    }
+}
+
+/// The workflow a run was started with, from its `RunStarted` event
+pub fn recorded_workflow(run_id: RunId, events: &[Event]) -> Result<WorkflowSpec> {
+    events
+        .iter()
+        .find_map(|e| match &e.event_type {
+            EventType::RunStarted { workflow_spec, .. } => Some(workflow_spec.clone()),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("Run {} has no recorded workflow", run_id))
+}
+
+/// Step executions ordered by step ID
+fn sorted_executions(step_executions: HashMap<StepId, StepExecution>) -> Vec<StepExecution> {
+    let mut executions: Vec<StepExecution> = step_executions.into_values().collect();
+    executions.sort_by(|a, b| a.id.0.cmp(&b.id.0));
+    executions
+}
+
+/// Resolves once the run is flagged for cancellation
+async fn cancellation(cancel_rx: &mut watch::Receiver<bool>) {
+    if cancel_rx.wait_for(|cancelled| *cancelled).await.is_err() {
+        // The registry entry is gone, so no cancellation can arrive
+        std::future::pending::<()>().await;
+    }
+}
+
+// Helper trait for duration calculation
+trait ElapsedSeconds {
+    fn elapsed_seconds_from(&self, other: chrono::DateTime<chrono::Utc>) -> i64;
+}
+
+impl ElapsedSeconds for chrono::DateTime<chrono::Utc> {
+    fn elapsed_seconds_from(&self, other: chrono::DateTime<chrono::Utc>) -> i64 {
+        (other - *self).num_seconds()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::MessageDirection;
+    use crate::storage::{FilesystemBlobStore, JsonlEventLog, RedbIndexStore};
+    use crate::types::{RoleId, ToolCallSpec};
+    use tempfile::TempDir;
+
+    fn create_executor(
+        temp_dir: &TempDir,
+    ) -> (WorkflowExecutor, Arc<RedbIndexStore>, Arc<JsonlEventLog>) {
+        let event_dir = temp_dir.path().join("events");
+        let blob_dir = temp_dir.path().join("blobs");
+        std::fs::create_dir_all(&event_dir).unwrap();
+        std::fs::create_dir_all(&blob_dir).unwrap();
+
+        let index_store = Arc::new(RedbIndexStore::new(temp_dir.path().join("index.redb")).unwrap());
+        let event_log = Arc::new(JsonlEventLog::new(event_dir).unwrap());
+        let blob_store = Arc::new(FilesystemBlobStore::new(blob_dir).unwrap());
+
+        (
+            WorkflowExecutor::new(event_log.clone(), blob_store, index_store.clone()),
+            index_store,
+            event_log,
+        )
+    }
+
+    fn step(id: &str, action: StepAction) -> StepSpec {
+        StepSpec {
+            id: StepId::new(id),
+            name: id.to_string(),
+            description: None,
+            role: RoleId::new("engineer"),
+            action,
+            timeout_secs: None,
+            retry_policy: None,
+            requires_approval: false,
+        }
+    }
+
+    fn agent_step(id: &str) -> StepSpec {
+        step(
+            id,
+            StepAction::AgentTask {
+                prompt: format!("Run {}", id),
+            },
+        )
+    }
+
+    #[test]
+    fn test_render_inputs_leaves_approvers_event_keys_and_scripts_alone() {
+        let workflow = WorkflowSpec {
+            steps: vec![
+                step(
+                    "triage",
+                    StepAction::AgentTask {
+                        prompt: "Triage {{inputs.ticket}}".to_string(),
+                    },
+                ),
+                step(
+                    "fetch",
+                    StepAction::ToolSequence {
+                        tools: vec![ToolCallSpec {
+                            tool_id: "web_fetch".to_string(),
+                            parameters: serde_json::json!({ "url": ["{{inputs.ticket}}"] }),
+                        }],
+                        continue_on_error: false,
+                    },
+                ),
+                step(
+                    "approve",
+                    StepAction::ManualApproval {
+                        approvers: vec!["{{inputs.approver}}".to_string()],
+                    },
+                ),
+                step(
+                    "wait",
+                    StepAction::WaitForEvent {
+                        event_key: "{{inputs.event}}".to_string(),
+                        timeout_secs: None,
+                    },
+                ),
+                step(
+                    "script",
+                    StepAction::Script {
+                        command: "echo".to_string(),
+                        args: vec!["{{inputs.ticket}}".to_string()],
+                    },
+                ),
+            ],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+        let inputs = HashMap::from([
+            ("ticket".to_string(), serde_json::json!("T-1")),
+            ("approver".to_string(), serde_json::json!("mallory")),
+            ("event".to_string(), serde_json::json!("deploy-approved")),
+        ]);
+
+        let rendered = render_inputs(&workflow, &inputs);
+        let actions: Vec<_> = rendered.steps.iter().map(|s| &s.action).collect();
+        assert!(matches!(actions[0], StepAction::AgentTask { prompt } if prompt == "Triage T-1"));
+        assert!(matches!(
+            actions[1],
+            StepAction::ToolSequence { tools, .. } if tools[0].parameters["url"][0] == "T-1"
+        ));
+        assert!(matches!(
+            actions[2],
+            StepAction::ManualApproval { approvers } if approvers == &["{{inputs.approver}}"]
+        ));
+        assert!(matches!(
+            actions[3],
+            StepAction::WaitForEvent { event_key, .. } if event_key == "{{inputs.event}}"
+        ));
+        assert!(matches!(
+            actions[4],
+            StepAction::Script { args, .. } if args == &["{{inputs.ticket}}"]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dry_agent_answers_agent_tasks_with_canned_responses() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, _, event_log) = create_executor(&temp_dir);
+        // Canned responses take precedence over the broker's simulated fallback
+        let executor = executor
+            .with_capacity_broker(Arc::new(CapacityBroker::new()))
+            .with_dry_agent(
+                DryAgentResponses {
+                    default_response: "Canned: {{prompt}}".to_string(),
+                    ..Default::default()
+                }
+                .with_step("review", "LGTM from {{role}}"),
+            );
+        let blob_store = FilesystemBlobStore::new(temp_dir.path().join("blobs")).unwrap();
+
+        let workflow = WorkflowSpec {
+            steps: vec![agent_step("plan"), agent_step("review")],
+            dependencies: HashMap::from([(StepId::new("review"), vec![StepId::new("plan")])]),
+            inputs: Vec::new(),
+        };
+
+        let mut outputs = Vec::new();
+        for _ in 0..2 {
+            let run = executor.execute("job".to_string(), workflow.clone()).await.unwrap();
+            assert_eq!(run.status, RunStatus::Completed);
+            assert!(run.steps.iter().all(|s| s.tokens == Some(0) && s.cost.is_none()));
+
+            let mut responses = Vec::new();
+            for event in event_log.get_run_events(run.id).await.unwrap() {
+                if let EventType::AgentMessage {
+                    step_id,
+                    direction: MessageDirection::FromAgent,
+                    content_hash,
+                    ..
+                } = event.event_type
+                {
+                    let content = blob_store.get(&content_hash).await.unwrap().unwrap();
+                    responses.push((step_id.0, String::from_utf8(content.to_vec()).unwrap()));
+                }
+            }
+            outputs.push(responses);
+        }
+
+        assert_eq!(
+            outputs[0],
+            [
+                ("plan".to_string(), "Canned: Run plan".to_string()),
+                ("review".to_string(), "LGTM from engineer".to_string()),
+            ]
+        );
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    #[tokio::test]
+    async fn test_sub_workflow_runs_as_child() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, _) = create_executor(&temp_dir);
+
+        let child = WorkflowSpec {
+            steps: vec![agent_step("child_a"), agent_step("child_b")],
+            dependencies: HashMap::from([(
+                StepId::new("child_b"),
+                vec![StepId::new("child_a")],
+            )]),
+            inputs: Vec::new(),
+        };
+        let parent = WorkflowSpec {
+            steps: vec![
+                step("nested", StepAction::SubWorkflow { workflow: child }),
+                agent_step("after"),
+            ],
+            dependencies: HashMap::from([(StepId::new("after"), vec![StepId::new("nested")])]),
+            inputs: Vec::new(),
+        };
+
+        let run = executor.execute("job1".to_string(), parent).await.unwrap();
+        assert_eq!(run.status, RunStatus::Completed);
+        assert!(run.parent_run_id.is_none());
+
+        let child_run = index_store
+            .list_runs()
+            .unwrap()
+            .into_iter()
+            .find(|r| r.parent_run_id == Some(run.id))
+            .expect("child run should be indexed");
+        assert_eq!(child_run.status, RunStatus::Completed);
+        assert_eq!(child_run.steps.len(), 2);
+        assert!(child_run
+            .steps
+            .iter()
+            .all(|s| s.status == StepStatus::Completed));
+
+        // The parent step only completes once the child run has finished
+        let nested = run.steps.iter().find(|s| s.id.0 == "nested").unwrap();
+        assert_eq!(nested.status, StepStatus::Completed);
+        assert!(nested.completed_at.unwrap() >= child_run.completed_at.unwrap());
+        assert!(run.completed_at.unwrap() >= child_run.completed_at.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_sub_workflow_depth_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, _) = create_executor(&temp_dir);
+
+        let mut workflow = WorkflowSpec {
+            steps: vec![agent_step("leaf")],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+        for _ in 0..=MAX_SUBWORKFLOW_DEPTH {
+            workflow = WorkflowSpec {
+                steps: vec![step("nested", StepAction::SubWorkflow { workflow })],
+                dependencies: HashMap::new(),
+                inputs: Vec::new(),
+            };
+        }
+
+        let run = executor.execute("job1".to_string(), workflow).await.unwrap();
+        assert_eq!(run.status, RunStatus::Failed);
+
+        let runs = index_store.list_runs().unwrap();
+        assert_eq!(runs.len(), MAX_SUBWORKFLOW_DEPTH + 1);
+        assert!(runs.iter().all(|r| r.status == RunStatus::Failed));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_workflow_rejected_before_execution() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, _) = create_executor(&temp_dir);
+        let executor = executor.with_workflow_limits(WorkflowLimits {
+            max_steps: 2,
+            ..WorkflowLimits::default()
+        });
+
+        let workflow = WorkflowSpec {
+            steps: vec![agent_step("a"), agent_step("b"), agent_step("c")],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+        let err = executor
+            .execute("job1".to_string(), workflow)
+            .await
+            .unwrap_err();
+        let errors = err.downcast_ref::<crate::validation::ValidationErrors>().unwrap();
+        assert_eq!(errors.errors()[0].field, "steps");
+        assert!(errors.errors()[0].message.contains("limit of 2"));
+
+        assert!(index_store.list_runs().unwrap().is_empty());
+        assert!(executor.active_runs.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_parent_cancels_active_child() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, event_log) = create_executor(&temp_dir);
+        let executor = Arc::new(executor);
+
+        let child = WorkflowSpec {
+            steps: vec![wait_step(Some(30)), agent_step("review")],
+            dependencies: HashMap::from([(StepId::new("review"), vec![StepId::new("upload")])]),
+            inputs: Vec::new(),
+        };
+        let parent = WorkflowSpec {
+            steps: vec![
+                step("nested", StepAction::SubWorkflow { workflow: child }),
+                agent_step("after"),
+            ],
+            dependencies: HashMap::from([(StepId::new("after"), vec![StepId::new("nested")])]),
+            inputs: Vec::new(),
+        };
+
+        let handle = tokio::spawn({
+            let executor = executor.clone();
+            async move { executor.execute("job1".to_string(), parent).await }
+        });
+
+        // Wait for the child's step to park
+        let child_id = loop {
+            let child = index_store
+                .list_runs()
+                .unwrap()
+                .into_iter()
+                .find(|r| r.parent_run_id.is_some());
+            if let Some(child) = child {
+                let events = event_log.get_run_events(child.id).await.unwrap();
+                if !crate::events::waiting_steps(&events).is_empty() {
+                    break child.id;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        let parent_id = index_store.get_run(&child_id).unwrap().unwrap().parent_run_id.unwrap();
+
+        executor.cancel(parent_id).await.unwrap();
+
+        let run = tokio::time::timeout(std::time::Duration::from_secs(5), handle)
+            .await
+            .expect("cancelled run should stop promptly")
+            .unwrap()
+            .unwrap();
+        assert_eq!(run.status, RunStatus::Cancelled);
+        assert!(run.steps.iter().all(|s| s.status == StepStatus::Skipped));
+
+        let child_run = index_store.get_run(&child_id).unwrap().unwrap();
+        assert_eq!(child_run.status, RunStatus::Cancelled);
+        assert!(child_run.steps.iter().all(|s| s.status == StepStatus::Skipped));
+
+        for (id, step_id) in [(parent_id, "after"), (child_id, "review")] {
+            let events = event_log.get_run_events(id).await.unwrap();
+            assert!(events
+                .iter()
+                .any(|e| matches!(e.event_type, EventType::RunCancelled { .. })));
+            assert!(!events.iter().any(|e| matches!(
+                &e.event_type,
+                EventType::StepStarted { step_id: started, .. } if started.0 == step_id
+            )));
+        }
+
+        assert!(executor.active_runs.read().await.is_empty());
+        assert!(executor.cancel(parent_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reap_orphaned_child_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, _) = create_executor(&temp_dir);
+
+        // A child left behind by a parent whose task went away
+        let child = Run {
+            id: RunId::new(),
+            work_item_id: "job1".to_string(),
+            status: RunStatus::Running,
+            started_at: chrono::Utc::now(),
+            completed_at: None,
+            steps: vec![],
+            parent_run_id: Some(RunId::new()),
+            external_id: None,
+            workflow_hash: None,
+            retry_of: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: 0,
+            tenant_id: None,
+        };
+        index_store.index_run(&child).unwrap();
+        executor.active_runs.write().await.insert(
+            child.id,
+            ActiveRun {
+                cancel_tx: watch::channel(false).0,
+                parent_run_id: child.parent_run_id,
+            },
+        );
+
+        assert_eq!(executor.reap_orphans().await.unwrap(), vec![child.id]);
+        assert!(executor.active_runs.read().await.is_empty());
+
+        let reaped = index_store.get_run(&child.id).unwrap().unwrap();
+        assert_eq!(reaped.status, RunStatus::Cancelled);
+        assert!(reaped.completed_at.is_some());
+    }
+
+    fn wait_step(timeout_secs: Option<u64>) -> StepSpec {
+        step(
+            "upload",
+            StepAction::WaitForEvent {
+                event_key: "file_uploaded".to_string(),
+                timeout_secs,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_event_resumes_on_signal() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, event_log) = create_executor(&temp_dir);
+
+        let workflow = WorkflowSpec {
+            steps: vec![wait_step(Some(30)), agent_step("review")],
+            dependencies: HashMap::from([(StepId::new("review"), vec![StepId::new("upload")])]),
+            inputs: Vec::new(),
+        };
+        let handle =
+            tokio::spawn(async move { executor.execute("job1".to_string(), workflow).await });
+
+        // Wait for the step to park
+        let run_id = loop {
+            if let Some(run) = index_store.list_runs().unwrap().pop() {
+                let events = event_log.get_run_events(run.id).await.unwrap();
+                if !crate::events::waiting_steps(&events).is_empty() {
+                    break run.id;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        assert!(!handle.is_finished());
+
+        event_log
+            .append(Event::new(
+                run_id,
+                EventType::SignalReceived {
+                    event_key: "file_uploaded".to_string(),
+                    payload: Some(serde_json::json!({"path": "report.pdf"})),
+                },
+            ))
+            .await
+            .unwrap();
+
+        let run = handle.await.unwrap().unwrap();
+        assert_eq!(run.status, RunStatus::Completed);
+        assert!(run.steps.iter().all(|s| s.status == StepStatus::Completed));
+
+        let events = event_log.get_run_events(run_id).await.unwrap();
+        assert!(crate::events::waiting_steps(&events).is_empty());
+        assert!(events.iter().any(|e| matches!(
+            &e.event_type,
+            EventType::ArtifactProduced { artifact_type, .. } if artifact_type == "signal_payload"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_resumed_run_completes_after_signal() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, event_log) = create_executor(&temp_dir);
+
+        let workflow = WorkflowSpec {
+            steps: vec![agent_step("prepare"), wait_step(None), agent_step("review")],
+            dependencies: HashMap::from([
+                (StepId::new("upload"), vec![StepId::new("prepare")]),
+                (StepId::new("review"), vec![StepId::new("upload")]),
+            ]),
+            inputs: Vec::new(),
+        };
+        let handle =
+            tokio::spawn(async move { executor.execute("job1".to_string(), workflow).await });
+
+        let run_id = loop {
+            if let Some(run) = index_store.list_runs().unwrap().pop() {
+                let events = event_log.get_run_events(run.id).await.unwrap();
+                if !crate::events::waiting_steps(&events).is_empty() {
+                    break run.id;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+
+        // The server goes down with the step parked
+        handle.abort();
+        let _ = handle.await;
+        drop((index_store, event_log));
+
+        let (executor, index_store, event_log) = create_executor(&temp_dir);
+        event_log
+            .append(Event::new(
+                run_id,
+                EventType::SignalReceived {
+                    event_key: "file_uploaded".to_string(),
+                    payload: None,
+                },
+            ))
+            .await
+            .unwrap();
+
+        let run = executor.resume(run_id).await.unwrap();
+        assert_eq!(run.id, run_id);
+        assert_eq!(run.status, RunStatus::Completed);
+        assert!(run.steps.iter().all(|s| s.status == StepStatus::Completed));
+        assert_eq!(index_store.get_run(&run_id).unwrap().unwrap().status, RunStatus::Completed);
+
+        // The step that finished before the restart ran once
+        let events = event_log.get_run_events(run_id).await.unwrap();
+        let prepared = events.iter().filter(|e| {
+            matches!(
+                &e.event_type,
+                EventType::AgentMessage { step_id, .. } if step_id.0 == "prepare"
+            )
+        });
+        assert_eq!(prepared.count(), 2);
+        assert!(executor.resume(run_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_role_max_concurrent_defers_excess_steps() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, event_log) = create_executor(&temp_dir);
+        let executor = Arc::new(executor);
+
+        index_store
+            .store_role(&crate::types::RoleSpec {
+                id: RoleId::new("engineer"),
+                name: "Engineer".to_string(),
+                description: String::new(),
+                prompt_template: String::new(),
+                allowed_tools: vec![],
+                budgets: crate::types::RoleBudgets {
+                    daily_tokens: None,
+                    daily_cost_cents: None,
+                    ..Default::default()
+                },
+                requires_approval_for: vec![],
+                model_fallback: vec![],
+                max_concurrent: Some(2),
+                claude_settings: None,
+            })
+            .unwrap();
+
+        let handles: Vec<_> = (0..3)
+            .map(|i| {
+                let executor = executor.clone();
+                let workflow = WorkflowSpec {
+                    steps: vec![wait_step(Some(30))],
+                    dependencies: HashMap::new(),
+                    inputs: Vec::new(),
+                };
+                tokio::spawn(async move { executor.execute(format!("job{}", i), workflow).await })
+            })
+            .collect();
+
+        // Runs whose upload step has started, i.e. holds one of the role's slots
+        let started_runs = || async {
+            let mut started = Vec::new();
+            for run in index_store.list_runs().unwrap() {
+                let events = event_log.get_run_events(run.id).await.unwrap();
+                if events
+                    .iter()
+                    .any(|e| matches!(e.event_type, EventType::StepStarted { .. }))
+                {
+                    started.push(run.id);
+                }
+            }
+            started
+        };
+
+        let started = loop {
+            let started = started_runs().await;
+            if started.len() >= 2 && index_store.list_runs().unwrap().len() == 3 {
+                break started;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+
+        // The third step stays queued while both slots are held
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert_eq!(started_runs().await.len(), 2);
+
+        event_log
+            .append(Event::new(
+                started[0],
+                EventType::SignalReceived {
+                    event_key: "file_uploaded".to_string(),
+                    payload: None,
+                },
+            ))
+            .await
+            .unwrap();
+
+        loop {
+            if started_runs().await.len() == 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        for run in index_store.list_runs().unwrap() {
+            if run.status == RunStatus::Running && run.id != started[0] {
+                event_log
+                    .append(Event::new(
+                        run.id,
+                        EventType::SignalReceived {
+                            event_key: "file_uploaded".to_string(),
+                            payload: None,
+                        },
+                    ))
+                    .await
+                    .unwrap();
+            }
+        }
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap().status, RunStatus::Completed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resized_role_slots_keep_held_and_queued_steps() {
+        let slots = PrioritySlots::new(1);
+        let first = slots.acquire(0).await.unwrap();
+        let waiting = tokio::spawn({
+            let slots = slots.clone();
+            async move { slots.acquire(0).await }
+        });
+        while slots.state.lock().unwrap().waiters.is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        // Raising the limit hands the new slot to the queued step
+        slots.resize(2);
+        let second = waiting.await.unwrap().unwrap();
+        assert!(slots.is_full());
+
+        // Lowering it below the slots held retires released slots until under the limit
+        slots.resize(1);
+        drop(first);
+        assert!(slots.is_full());
+        drop(second);
+        assert!(!slots.is_full());
+        let _third = slots.acquire(0).await.unwrap();
+        assert!(slots.is_full());
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_run_gets_role_slot_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, event_log) = create_executor(&temp_dir);
+        let executor = Arc::new(executor);
+
+        index_store
+            .store_role(&crate::types::RoleSpec {
+                id: RoleId::new("engineer"),
+                name: "Engineer".to_string(),
+                description: String::new(),
+                prompt_template: String::new(),
+                allowed_tools: vec![],
+                budgets: crate::types::RoleBudgets::default(),
+                requires_approval_for: vec![],
+                model_fallback: vec![],
+                max_concurrent: Some(1),
+                claude_settings: None,
+            })
+            .unwrap();
+
+        let submit = |work_item_id: &str, priority: u8| {
+            let executor = executor.clone();
+            let work_item_id = work_item_id.to_string();
+            let workflow = WorkflowSpec {
+                steps: vec![wait_step(Some(30))],
+                dependencies: HashMap::new(),
+                inputs: Vec::new(),
+            };
+            tokio::spawn(async move {
+                executor
+                    .execute_tagged(
+                        work_item_id,
+                        workflow,
+                        None,
+                        HashMap::new(),
+                        HashMap::new(),
+                        priority,
+                        None,
+                    )
+                    .await
+            })
+        };
+        // Priorities of the steps waiting for the role's slot
+        let queued = || {
+            let role_slots = executor.role_slots.lock().unwrap();
+            role_slots
+                .get(&RoleId::new("engineer"))
+                .map(|slots| {
+                    let state = slots.state.lock().unwrap();
+                    state.waiters.iter().map(|w| w.priority).collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+        let run_of = |work_item_id: &str| {
+            index_store
+                .list_runs()
+                .unwrap()
+                .into_iter()
+                .find(|run| run.work_item_id == work_item_id)
+        };
+        let has_started = |run_id: RunId| {
+            let event_log = event_log.clone();
+            async move {
+                event_log
+                    .get_run_events(run_id)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .any(|e| matches!(e.event_type, EventType::StepStarted { .. }))
+            }
+        };
+        let signal = |run_id: RunId| {
+            let event_log = event_log.clone();
+            async move {
+                event_log
+                    .append(Event::new(
+                        run_id,
+                        EventType::SignalReceived {
+                            event_key: "file_uploaded".to_string(),
+                            payload: None,
+                        },
+                    ))
+                    .await
+                    .unwrap();
+            }
+        };
+
+        // A run holding the only slot, then a low-priority run queued ahead of a
+        // high-priority one
+        let mut handles = vec![submit("blocker", DEFAULT_RUN_PRIORITY)];
+        let blocker = loop {
+            if let Some(run) = run_of("blocker") {
+                if has_started(run.id).await {
+                    break run.id;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        handles.push(submit("low", 10));
+        while queued().is_empty() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        handles.push(submit("high", 200));
+        while queued().len() < 2 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let low = run_of("low").unwrap();
+        let high = run_of("high").unwrap();
+        assert_eq!((low.priority, high.priority), (10, 200));
+
+        // The freed slot goes to the high-priority run, though it queued last
+        signal(blocker).await;
+        while queued().len() > 1 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(queued(), [10]);
+        while !has_started(high.id).await {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(!has_started(low.id).await);
+
+        signal(high.id).await;
+        while !has_started(low.id).await {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        signal(low.id).await;
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap().status, RunStatus::Completed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_from_failed_step_reuses_completed_steps() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, event_log) = create_executor(&temp_dir);
+        let executor = Arc::new(executor);
+
+        // a -> b -> upload -> d -> e, where upload times out the first time round
+        let workflow = WorkflowSpec {
+            steps: vec![
+                agent_step("a"),
+                agent_step("b"),
+                wait_step(Some(2)),
+                agent_step("d"),
+                agent_step("e"),
+            ],
+            dependencies: HashMap::from([
+                (StepId::new("b"), vec![StepId::new("a")]),
+                (StepId::new("upload"), vec![StepId::new("b")]),
+                (StepId::new("d"), vec![StepId::new("upload")]),
+                (StepId::new("e"), vec![StepId::new("d")]),
+            ]),
+            inputs: Vec::new(),
+        };
+        let failed = executor.execute("job1".to_string(), workflow).await.unwrap();
+        assert_eq!(failed.status, RunStatus::Failed);
+
+        let retry = {
+            let executor = executor.clone();
+            tokio::spawn(async move { executor.retry(failed.id, RetryFrom::Failed).await })
+        };
+
+        // Let the re-executed upload step succeed this time
+        let retry_id = loop {
+            let retried = index_store.list_runs().unwrap().into_iter().find(|r| r.id != failed.id);
+            if let Some(run) = retried {
+                let events = event_log.get_run_events(run.id).await.unwrap();
+                if !crate::events::waiting_steps(&events).is_empty() {
+                    break run.id;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        event_log
+            .append(Event::new(
+                retry_id,
+                EventType::SignalReceived {
+                    event_key: "file_uploaded".to_string(),
+                    payload: None,
+                },
+            ))
+            .await
+            .unwrap();
+
+        let run = retry.await.unwrap().unwrap();
+        assert_eq!(run.id, retry_id);
+        assert_eq!(run.status, RunStatus::Completed);
+        assert_eq!(run.retry_of, Some(failed.id));
+        assert!(run.steps.iter().all(|s| s.status == StepStatus::Completed));
+
+        let started = |events: &[Event]| -> Vec<String> {
+            events
+                .iter()
+                .filter_map(|e| match &e.event_type {
+                    EventType::StepStarted { step_id, .. } => Some(step_id.0.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        let artifacts = |events: &[Event], step: &str| -> Vec<crate::types::BlobHash> {
+            events
+                .iter()
+                .filter_map(|e| match &e.event_type {
+                    EventType::ArtifactProduced { step_id, content_hash, .. }
+                        if step_id.0 == step =>
+                    {
+                        Some(content_hash.clone())
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let original_events = event_log.get_run_events(failed.id).await.unwrap();
+        let retry_events = event_log.get_run_events(retry_id).await.unwrap();
+        assert_eq!(started(&retry_events), vec!["upload", "d", "e"]);
+        for step in ["a", "b"] {
+            assert!(!artifacts(&original_events, step).is_empty());
+            assert_eq!(artifacts(&retry_events, step), artifacts(&original_events, step));
+        }
+
+        // A completed run has nothing to resume, but can be re-run from the start
+        assert!(executor.retry(run.id, RetryFrom::Failed).await.is_err());
+        let rerun = executor.retry(failed.id, RetryFrom::Start).await.unwrap();
+        let rerun_events = event_log.get_run_events(rerun.id).await.unwrap();
+        assert_eq!(started(&rerun_events), vec!["a", "b", "upload"]);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_event_times_out() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, _, event_log) = create_executor(&temp_dir);
+
+        let workflow = WorkflowSpec {
+            steps: vec![wait_step(Some(1))],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+
+        let run = executor.execute("job1".to_string(), workflow).await.unwrap();
+        assert_eq!(run.status, RunStatus::Failed);
+
+        let events = event_log.get_run_events(run.id).await.unwrap();
+        assert!(events.iter().any(|e| matches!(
+            &e.event_type,
+            EventType::StepFailed { error, .. } if error.contains("Timed out")
+        )));
+        assert!(crate::events::waiting_steps(&events).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_completed_steps_record_tokens_and_cost() {
+        use crate::types::{
+            CapacitySource, CapacitySourceId, CostPerToken, LlmProvider, RateLimits,
+        };
+
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, _) = create_executor(&temp_dir);
+        let broker = Arc::new(CapacityBroker::new());
+        broker
+            .register_source(CapacitySource {
+                id: CapacitySourceId::new("src1"),
+                name: "Source".to_string(),
+                provider: LlmProvider::Anthropic,
+                api_key_hash: "hash".to_string(),
+                model: "claude-opus-4".to_string(),
+                rate_limits: RateLimits {
+                    requests_per_minute: 60,
+                    tokens_per_minute: 100_000,
+                    tokens_per_day: None,
+                },
+                cost_per_token: CostPerToken {
+                    input_cost: 15.0,
+                    output_cost: 75.0,
+                },
+                priority: 100,
+                enabled: true,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        let executor = executor.with_capacity_broker(broker.clone());
+
+        let workflow = WorkflowSpec {
+            steps: vec![agent_step("a"), agent_step("b")],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+        let run = executor.execute("job1".to_string(), workflow).await.unwrap();
+        assert_eq!(run.status, RunStatus::Completed);
+
+        // The persisted run matches what the broker recorded, without joining usage
+        let stored = index_store.get_run(&run.id).unwrap().unwrap();
+        let usage = broker.get_all_usage(chrono::Utc::now() - chrono::Duration::hours(1));
+        assert_eq!(usage.len(), 2);
+        for step in &stored.steps {
+            let recorded = usage
+                .iter()
+                .find(|u| u.step_id.as_ref() == Some(&step.id))
+                .unwrap();
+            assert_eq!(step.tokens, Some(u64::from(recorded.total_tokens)));
+            assert_eq!(step.cost, Some(recorded.cost));
+        }
+
+        // Simulated responses, with no broker, don't make up a token count
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, _, _) = create_executor(&temp_dir);
+        let workflow = WorkflowSpec {
+            steps: vec![agent_step("a")],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+        let run = executor.execute("job2".to_string(), workflow).await.unwrap();
+        assert_eq!(run.status, RunStatus::Completed);
+        assert_eq!(run.steps[0].tokens, None);
+    }
+
+    /// Counts its calls; `lookup` succeeds and anything else fails
+    #[derive(Default)]
+    struct CountingTools {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl ToolInvoker for CountingTools {
+        fn tool_tier(&self, _tool_id: &str) -> Option<u8> {
+            Some(0)
+        }
+
+        async fn invoke(
+            &self,
+            tool_id: &str,
+            parameters: serde_json::Value,
+        ) -> Result<serde_json::Value> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            match tool_id {
+                "lookup" => Ok(serde_json::json!({ "found": parameters })),
+                _ => Err(anyhow::anyhow!("{} is unavailable", tool_id)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_reproduces_run_without_live_calls() {
+        use std::sync::atomic::Ordering;
+
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, index_store, event_log) = create_executor(&temp_dir);
+        let live_tools = Arc::new(CountingTools::default());
+        let executor = executor
+            .with_dry_agent(DryAgentResponses::default())
+            .with_tools(live_tools.clone());
+
+        let tool_step = |id: &str, tool_ids: &[&str], continue_on_error: bool| {
+            step(
+                id,
+                StepAction::ToolSequence {
+                    tools: tool_ids
+                        .iter()
+                        .map(|tool_id| ToolCallSpec {
+                            tool_id: tool_id.to_string(),
+                            parameters: serde_json::json!({ "step": id }),
+                        })
+                        .collect(),
+                    continue_on_error,
+                },
+            )
+        };
+        // plan -> gather -> summarise -> publish, where publish's tool is down
+        let workflow = WorkflowSpec {
+            steps: vec![
+                agent_step("plan"),
+                tool_step("gather", &["lookup", "archive"], true),
+                agent_step("summarise"),
+                tool_step("publish", &["lookup", "upload"], false),
+            ],
+            dependencies: HashMap::from([
+                (StepId::new("gather"), vec![StepId::new("plan")]),
+                (StepId::new("summarise"), vec![StepId::new("gather")]),
+                (StepId::new("publish"), vec![StepId::new("summarise")]),
+            ]),
+            inputs: Vec::new(),
+        };
+        let original = executor.execute("job".to_string(), workflow).await.unwrap();
+        assert_eq!(original.status, RunStatus::Failed);
+        assert_eq!(live_tools.calls.load(Ordering::SeqCst), 4);
+
+        // Replaying needs neither the agent nor the tools: the broker has no sources
+        let blob_store = Arc::new(FilesystemBlobStore::new(temp_dir.path().join("blobs")).unwrap());
+        let replay_tools = Arc::new(CountingTools::default());
+        let replayer = WorkflowExecutor::new(event_log.clone(), blob_store, index_store.clone())
+            .with_capacity_broker(Arc::new(CapacityBroker::new()))
+            .with_tools(replay_tools.clone());
+
+        let report = replayer.replay(original.id).await.unwrap();
+        assert!(report.matches());
+        assert_eq!(report.actual, report.expected);
+        let path: Vec<_> = report
+            .actual
+            .iter()
+            .map(|o| (o.step_id.0.as_str(), o.status))
+            .collect();
+        assert_eq!(
+            path,
+            vec![
+                ("plan", StepStatus::Completed),
+                ("gather", StepStatus::Completed),
+                ("summarise", StepStatus::Completed),
+                ("publish", StepStatus::Failed),
+            ]
+        );
+        assert_eq!(replay_tools.calls.load(Ordering::SeqCst), 0);
+
+        let replay = index_store.get_run(&report.replay_run_id).unwrap().unwrap();
+        assert_eq!(replay.status, RunStatus::Failed);
+        assert_eq!(replay.tags["replay_of"], original.id.to_string());
+        let statuses = |run: &Run| -> Vec<_> {
+            run.steps.iter().map(|s| (s.id.clone(), s.status, s.error.clone())).collect()
+        };
+        assert_eq!(statuses(&replay), statuses(&original));
+
+        // Agent responses and tool outputs are stored again, byte for byte
+        let artifacts = |events: &[Event]| -> Vec<(String, crate::types::BlobHash)> {
+            events
+                .iter()
+                .filter_map(|e| match &e.event_type {
+                    EventType::ArtifactProduced {
+                        step_id,
+                        content_hash,
+                        ..
+                    } => Some((step_id.0.clone(), content_hash.clone())),
+                    _ => None,
+                })
+                .collect()
+        };
+        let original_events = event_log.get_run_events(original.id).await.unwrap();
+        let replay_events = event_log.get_run_events(replay.id).await.unwrap();
+        assert_eq!(artifacts(&original_events).len(), 4);
+        assert_eq!(artifacts(&replay_events), artifacts(&original_events));
     }
 }
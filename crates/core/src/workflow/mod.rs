@@ -2,10 +2,12 @@ pub mod dag;
 pub mod executor;
 pub mod step_executor;
 pub mod advanced;
+pub mod replay;
 
-pub use dag::WorkflowDag;
+pub use dag::{ApproverDirectory, LintWarning, LintWarningKind, WorkflowDag, WorkflowLimits};
 pub use executor::WorkflowExecutor;
-pub use step_executor::StepExecutor;
+pub use step_executor::{DryAgentResponses, ScriptConfig, StepExecutor, ToolInvoker};
+pub use replay::{ReplayReport, RunCapture, StepOutcome};
 pub use advanced::{
     AdvancedPattern, ParallelForEachBuilder, WorkflowVersion, WorkflowVersionManager,
     evaluate_condition, expand_parallel_foreach,
@@ -0,0 +1,336 @@
+// Deterministic replay of finished runs from what their event logs captured
+
+use super::step_executor::ToolInvoker;
+use crate::events::{Event, EventType, MessageDirection};
+use crate::storage::BlobStore;
+use crate::types::{BlobHash, RunId, StepId, StepStatus};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// What one attempt of a step received from the outside world
+#[derive(Debug, Clone, Default)]
+pub struct CapturedAttempt {
+    /// The agent's response, for agent tasks
+    pub agent_response: Option<String>,
+    pub agent_tokens: Option<u64>,
+    /// Tool calls in the order they were made, for tool sequences
+    pub tool_calls: Vec<CapturedToolCall>,
+    /// Why the attempt failed, if it did
+    pub error: Option<String>,
+}
+
+/// One tool call and what it returned
+#[derive(Debug, Clone)]
+pub struct CapturedToolCall {
+    pub tool_id: String,
+    pub tool_tier: u8,
+    /// The tool's output or error. `None` if the log doesn't say.
+    pub result: Option<std::result::Result<serde_json::Value, String>>,
+    /// Refused by policy before reaching the tool
+    pub denied: bool,
+    // Whether a `tool_error` artifact has been matched to the call
+    error_recorded: bool,
+}
+
+/// Every step attempt captured in a run's event log, handed out in order as a replay
+/// executes the same steps again
+pub struct RunCapture {
+    pub run_id: RunId,
+    attempts: Mutex<HashMap<StepId, VecDeque<CapturedAttempt>>>,
+}
+
+impl RunCapture {
+    /// Read the agent responses, tool results and failures of each step attempt from
+    /// `events`, loading response and output bodies from `blob_store`
+    pub async fn from_events(
+        run_id: RunId,
+        events: &[Event],
+        blob_store: &dyn BlobStore,
+    ) -> Result<Self> {
+        let mut attempts: HashMap<StepId, Vec<CapturedAttempt>> = HashMap::new();
+
+        for event in events {
+            match &event.event_type {
+                EventType::StepStarted { step_id, .. } => {
+                    attempts.entry(step_id.clone()).or_default().push(Default::default());
+                }
+                // Steps carried over from a retried run complete without starting
+                EventType::StepCompleted { step_id, .. } => {
+                    let step_attempts = attempts.entry(step_id.clone()).or_default();
+                    if step_attempts.is_empty() {
+                        step_attempts.push(Default::default());
+                    }
+                }
+                EventType::AgentMessage {
+                    step_id,
+                    direction: MessageDirection::FromAgent,
+                    content_hash,
+                    tokens,
+                } => {
+                    if let Some(attempt) = last_attempt(&mut attempts, step_id) {
+                        attempt.agent_response = Some(read_text(blob_store, content_hash).await?);
+                        attempt.agent_tokens = *tokens;
+                    }
+                }
+                EventType::ToolCallProposed {
+                    step_id,
+                    tool_id,
+                    tool_tier,
+                    ..
+                } => {
+                    if let Some(attempt) = last_attempt(&mut attempts, step_id) {
+                        attempt.tool_calls.push(CapturedToolCall {
+                            tool_id: tool_id.clone(),
+                            tool_tier: *tool_tier,
+                            result: None,
+                            denied: false,
+                            error_recorded: false,
+                        });
+                    }
+                }
+                EventType::ToolCallDenied { step_id, .. } => {
+                    if let Some(call) = last_call(&mut attempts, step_id) {
+                        call.denied = true;
+                    }
+                }
+                EventType::ToolCallExecuted {
+                    step_id,
+                    result_hash,
+                    ..
+                } => {
+                    if let Some(call) = last_call(&mut attempts, step_id) {
+                        let output = read_text(blob_store, result_hash).await?;
+                        call.result = Some(Ok(serde_json::from_str(&output)?));
+                    }
+                }
+                EventType::StepFailed { step_id, error, .. } => {
+                    if let Some(attempt) = last_attempt(&mut attempts, step_id) {
+                        // A tool that failed the step did so without an output
+                        if let Some(call) = attempt
+                            .tool_calls
+                            .iter_mut()
+                            .rev()
+                            .find(|c| c.result.is_none() && !c.denied)
+                        {
+                            let prefix = format!("Tool {} failed: ", call.tool_id);
+                            let message = error.strip_prefix(&prefix).unwrap_or(error);
+                            call.result = Some(Err(message.to_string()));
+                        }
+                        attempt.error = Some(error.clone());
+                    }
+                }
+                EventType::ArtifactProduced {
+                    step_id,
+                    artifact_type,
+                    content_hash,
+                    metadata,
+                } => {
+                    let Some(attempt) = last_attempt(&mut attempts, step_id) else {
+                        continue;
+                    };
+                    match artifact_type.as_str() {
+                        "agent_response" if attempt.agent_response.is_none() => {
+                            attempt.agent_response =
+                                Some(read_text(blob_store, content_hash).await?);
+                            attempt.agent_tokens =
+                                metadata.get("tokens").and_then(|t| t.as_u64());
+                        }
+                        // Errors of tools a `continue_on_error` sequence carried on past,
+                        // in call order alongside those of denied calls
+                        "tool_error" => {
+                            let tool_id = metadata.get("tool_id").and_then(|t| t.as_str());
+                            let call = attempt.tool_calls.iter_mut().find(|c| {
+                                Some(c.tool_id.as_str()) == tool_id
+                                    && !c.error_recorded
+                                    && (c.denied || c.result.is_none())
+                            });
+                            if let Some(call) = call {
+                                call.error_recorded = true;
+                                if !call.denied {
+                                    call.result =
+                                        Some(Err(read_text(blob_store, content_hash).await?));
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            run_id,
+            attempts: Mutex::new(
+                attempts
+                    .into_iter()
+                    .map(|(step_id, attempts)| (step_id, attempts.into()))
+                    .collect(),
+            ),
+        })
+    }
+
+    /// The next captured attempt of a step, if any are left
+    pub fn next_attempt(&self, step_id: &StepId) -> Option<CapturedAttempt> {
+        self.attempts
+            .lock()
+            .unwrap()
+            .get_mut(step_id)
+            .and_then(|attempts| attempts.pop_front())
+    }
+}
+
+fn last_attempt<'a>(
+    attempts: &'a mut HashMap<StepId, Vec<CapturedAttempt>>,
+    step_id: &StepId,
+) -> Option<&'a mut CapturedAttempt> {
+    attempts.get_mut(step_id).and_then(|a| a.last_mut())
+}
+
+fn last_call<'a>(
+    attempts: &'a mut HashMap<StepId, Vec<CapturedAttempt>>,
+    step_id: &StepId,
+) -> Option<&'a mut CapturedToolCall> {
+    last_attempt(attempts, step_id).and_then(|a| a.tool_calls.last_mut())
+}
+
+async fn read_text(blob_store: &dyn BlobStore, hash: &BlobHash) -> Result<String> {
+    let bytes = blob_store
+        .get(hash)
+        .await?
+        .ok_or_else(|| anyhow!("Captured blob {} is missing", hash.0))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Answers a replayed tool sequence's calls with those of its captured attempt, in order
+pub struct ReplayTools {
+    calls: Mutex<VecDeque<CapturedToolCall>>,
+    tiers: HashMap<String, u8>,
+    error: Option<String>,
+}
+
+impl ReplayTools {
+    pub fn new(attempt: &CapturedAttempt) -> Self {
+        Self {
+            calls: Mutex::new(attempt.tool_calls.iter().filter(|c| !c.denied).cloned().collect()),
+            tiers: attempt
+                .tool_calls
+                .iter()
+                .map(|c| (c.tool_id.clone(), c.tool_tier))
+                .collect(),
+            error: attempt.error.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolInvoker for ReplayTools {
+    /// Tools the attempt never proposed weren't registered when it ran
+    fn tool_tier(&self, tool_id: &str) -> Option<u8> {
+        self.tiers.get(tool_id).copied()
+    }
+
+    async fn invoke(
+        &self,
+        tool_id: &str,
+        _parameters: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let call = self
+            .calls
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| anyhow!("No captured call of tool {} left to replay", tool_id))?;
+        if call.tool_id != tool_id {
+            return Err(anyhow!(
+                "Replay diverged: expected a call to tool {}, not {}",
+                call.tool_id,
+                tool_id
+            ));
+        }
+
+        match call.result {
+            Some(Ok(output)) => Ok(output),
+            Some(Err(error)) => Err(anyhow!(error)),
+            None => Err(anyhow!(self
+                .error
+                .clone()
+                .unwrap_or_else(|| format!("No output was captured for tool {}", tool_id)))),
+        }
+    }
+}
+
+/// A step's final status, in the order steps finished
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepOutcome {
+    pub step_id: StepId,
+    pub status: StepStatus,
+}
+
+/// The path a run took through its DAG: each step's final status, in the order the
+/// steps finished
+pub fn step_path(events: &[Event]) -> Vec<StepOutcome> {
+    events
+        .iter()
+        .filter_map(|e| {
+            let (step_id, status) = match &e.event_type {
+                EventType::StepCompleted { step_id, .. } => (step_id, StepStatus::Completed),
+                EventType::StepFailed {
+                    step_id,
+                    will_retry: false,
+                    ..
+                } => (step_id, StepStatus::Failed),
+                EventType::StepSkipped { step_id, .. } => (step_id, StepStatus::Skipped),
+                _ => return None,
+            };
+            Some(StepOutcome {
+                step_id: step_id.clone(),
+                status,
+            })
+        })
+        .collect()
+}
+
+/// Outcome of replaying a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub original_run_id: RunId,
+    /// The run the replay executed as
+    pub replay_run_id: RunId,
+    /// Path the original run took
+    pub expected: Vec<StepOutcome>,
+    /// Path the replay took
+    pub actual: Vec<StepOutcome>,
+    /// Index of the first step where the paths differ, if they do
+    pub diverged_at: Option<usize>,
+}
+
+impl ReplayReport {
+    pub fn new(
+        original_run_id: RunId,
+        replay_run_id: RunId,
+        expected: Vec<StepOutcome>,
+        actual: Vec<StepOutcome>,
+    ) -> Self {
+        let shorter = expected.len().min(actual.len());
+        let diverged_at = expected
+            .iter()
+            .zip(&actual)
+            .position(|(e, a)| e != a)
+            .or_else(|| (expected.len() != actual.len()).then_some(shorter));
+        Self {
+            original_run_id,
+            replay_run_id,
+            expected,
+            actual,
+            diverged_at,
+        }
+    }
+
+    /// Whether the replay took the same path as the original run
+    pub fn matches(&self) -> bool {
+        self.diverged_at.is_none()
+    }
+}
@@ -1,18 +1,153 @@
-use crate::events::{Event, EventLog, EventType, MessageDirection};
+use super::replay::{ReplayTools, RunCapture};
+use crate::capacity::CapacityBroker;
+use crate::events::{Event, EventLog, EventType, MessageDirection, OutputStream};
+use crate::policy::{PolicyContext, PolicyDecision, PolicyEngine};
+use crate::redaction::Redactor;
 use crate::storage::BlobStore;
-use crate::types::{BlobHash, RunId, StepAction, StepId, StepSpec, StepStatus};
+use crate::types::{
+    BlobHash, LlmRequest, RoleId, RunId, StepAction, StepId, StepSpec, StepStatus,
+    ToolCallSpec,
+};
 use anyhow::{anyhow, Result};
 use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio::time::timeout;
 
+/// Longest a waiting step goes without re-reading the event log, so signals appended
+/// through another event log instance are still seen
+const SIGNAL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Largest response an agent task asks the capacity broker for
+const AGENT_TASK_MAX_TOKENS: u32 = 4096;
+
+/// Bytes of each script output stream kept in the event log; longer output is stored
+/// whole as a blob
+pub const DEFAULT_LOG_LIMIT_BYTES: usize = 64 * 1024;
+
+/// Whether and how `Script` steps may run. Scripts run with the server's privileges,
+/// so they are refused unless enabled, and then only for allowlisted commands.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptConfig {
+    /// Run script steps at all
+    #[serde(default)]
+    pub enabled: bool,
+    /// Command lines script steps may run, e.g. `cargo test --workspace`. A step's command
+    /// and args must match an entry word for word, except that an entry ending in `*`
+    /// allows any further args. Interpreters such as `sh`, `make`, `python` or `git` run
+    /// code named by their args, so their entries can't end in `*`.
+    #[serde(default)]
+    pub allowed_commands: Vec<String>,
+    /// Directory scripts run in. Required once scripts are enabled.
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    /// The whole environment scripts see; the server's own is not inherited
+    #[serde(default = "default_script_env")]
+    pub env: HashMap<String, String>,
+    /// Bytes of each output stream read into memory; the rest is counted and discarded
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: usize,
+}
+
+fn default_script_env() -> HashMap<String, String> {
+    HashMap::from([("PATH".to_string(), "/usr/local/bin:/usr/bin:/bin".to_string())])
+}
+
+fn default_max_output_bytes() -> usize {
+    4 * 1024 * 1024
+}
+
+impl Default for ScriptConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_commands: Vec::new(),
+            working_dir: None,
+            env: default_script_env(),
+            max_output_bytes: default_max_output_bytes(),
+        }
+    }
+}
+
+/// Commands that run code named by their args, which an allowlist entry may not leave open
+const INTERPRETERS: &[&str] = &[
+    "sh", "bash", "zsh", "dash", "ksh", "fish", "env", "xargs", "make", "python", "perl", "ruby",
+    "node", "deno", "php", "git",
+];
+
+fn is_interpreter(command: &str) -> bool {
+    let name = std::path::Path::new(command)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(command);
+    // Versioned binaries such as `python3.12`
+    let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS.contains(&name)
+}
+
+impl ScriptConfig {
+    /// Enable scripts running the `commands` command lines in `working_dir`
+    pub fn allowing(commands: &[&str], working_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            enabled: true,
+            allowed_commands: commands.iter().map(|c| c.to_string()).collect(),
+            working_dir: Some(working_dir.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Reject allowlist entries that would let an interpreter run arbitrary code
+    pub fn validate(&self) -> Result<()> {
+        for entry in &self.allowed_commands {
+            let mut words = entry.split_whitespace();
+            let command = words
+                .next()
+                .ok_or_else(|| anyhow!("Empty entry in allowed script commands"))?;
+            if words.last() == Some("*") && is_interpreter(command) {
+                return Err(anyhow!(
+                    "Allowed script command '{}' lets the interpreter '{}' take any args",
+                    entry,
+                    command
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether an allowlist entry permits running `command` with `args`
+    pub fn allows(&self, command: &str, args: &[String]) -> bool {
+        self.allowed_commands.iter().any(|entry| {
+            let mut words = entry.split_whitespace();
+            if words.next() != Some(command) {
+                return false;
+            }
+            let pattern: Vec<&str> = words.collect();
+            match pattern.split_last() {
+                Some((&"*", _)) if is_interpreter(command) => false,
+                Some((&"*", fixed)) => {
+                    args.len() >= fixed.len() && fixed.iter().zip(args).all(|(p, a)| p == a)
+                }
+                _ => pattern.len() == args.len() && pattern.iter().zip(args).all(|(p, a)| p == a),
+            }
+        })
+    }
+}
+
 /// Result of executing a step
 #[derive(Debug, Clone)]
 pub struct StepResult {
     pub status: StepStatus,
     pub error: Option<String>,
     pub artifacts: Vec<Artifact>,
+    /// Tokens used by the step's LLM call, if it made one
+    pub tokens: Option<u64>,
+    /// Cost of the step's LLM call
+    pub cost: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -22,10 +157,74 @@ pub struct Artifact {
     pub metadata: serde_json::Value,
 }
 
+/// Runs the tools named in `ToolSequence` steps (implemented by the MCP tool registry)
+#[async_trait::async_trait]
+pub trait ToolInvoker: Send + Sync {
+    /// Security tier of a registered tool (0 = read-only, 1 = write, 2 = dangerous), or
+    /// `None` if no such tool is registered
+    fn tool_tier(&self, tool_id: &str) -> Option<u8>;
+
+    /// Call a tool and return its output
+    async fn invoke(&self, tool_id: &str, parameters: serde_json::Value)
+        -> Result<serde_json::Value>;
+}
+
+/// Canned agent responses for "dry agent" mode, where agent tasks never reach an LLM.
+/// Responses may use `{{step_id}}`, `{{role}}` and `{{prompt}}` placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryAgentResponses {
+    /// Response for agent tasks without one of their own
+    #[serde(default = "default_dry_agent_response")]
+    pub default_response: String,
+    /// Responses by step ID
+    #[serde(default)]
+    pub steps: HashMap<String, String>,
+}
+
+fn default_dry_agent_response() -> String {
+    "Dry run response to: {{prompt}}".to_string()
+}
+
+impl Default for DryAgentResponses {
+    fn default() -> Self {
+        Self {
+            default_response: default_dry_agent_response(),
+            steps: HashMap::new(),
+        }
+    }
+}
+
+impl DryAgentResponses {
+    /// Give the agent task `step_id` its own response
+    pub fn with_step(mut self, step_id: impl Into<String>, response: impl Into<String>) -> Self {
+        self.steps.insert(step_id.into(), response.into());
+        self
+    }
+
+    /// The response to `step`, with its placeholders filled in
+    pub fn response(&self, step: &StepSpec, prompt: &str) -> String {
+        self.steps
+            .get(&step.id.0)
+            .unwrap_or(&self.default_response)
+            .replace("{{step_id}}", &step.id.0)
+            .replace("{{role}}", &step.role.0)
+            .replace("{{prompt}}", prompt)
+    }
+}
+
 /// Step executor with retry and timeout logic
+#[derive(Clone)]
 pub struct StepExecutor {
     event_log: Arc<dyn EventLog>,
     blob_store: Arc<dyn BlobStore>,
+    tools: Option<Arc<dyn ToolInvoker>>,
+    policy_engine: Option<Arc<dyn PolicyEngine>>,
+    capacity_broker: Option<Arc<CapacityBroker>>,
+    dry_agent: Option<DryAgentResponses>,
+    replay: Option<Arc<RunCapture>>,
+    redactor: Option<Redactor>,
+    log_limit_bytes: usize,
+    scripts: ScriptConfig,
 }
 
 impl StepExecutor {
@@ -33,15 +232,76 @@ impl StepExecutor {
         Self {
             event_log,
             blob_store,
+            tools: None,
+            policy_engine: None,
+            capacity_broker: None,
+            dry_agent: None,
+            replay: None,
+            redactor: None,
+            log_limit_bytes: DEFAULT_LOG_LIMIT_BYTES,
+            scripts: ScriptConfig::default(),
         }
     }
 
-    /// Execute a step with retry and timeout logic
+    /// Dispatch `ToolSequence` steps through `tools`
+    pub fn with_tools(mut self, tools: Arc<dyn ToolInvoker>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Send agent task prompts to an LLM through `broker`. Without one, agent tasks
+    /// return a simulated response.
+    pub fn with_capacity_broker(mut self, broker: Arc<CapacityBroker>) -> Self {
+        self.capacity_broker = Some(broker);
+        self
+    }
+
+    /// Answer agent tasks from `responses` instead of calling an LLM, even if a broker
+    /// is configured. Dry responses use no tokens.
+    pub fn with_dry_agent(mut self, responses: DryAgentResponses) -> Self {
+        self.dry_agent = Some(responses);
+        self
+    }
+
+    /// Feed steps what `capture` recorded of an earlier run instead of calling agents,
+    /// tools or scripts. Approvals run as usual.
+    pub fn with_replay(mut self, capture: Arc<RunCapture>) -> Self {
+        self.replay = Some(capture);
+        self
+    }
+
+    /// Redact secrets from script output before it is stored
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Keep at most `bytes` of each script output stream in the event log
+    pub fn with_log_limit(mut self, bytes: usize) -> Self {
+        self.log_limit_bytes = bytes;
+        self
+    }
+
+    /// Check each tool call against the step role's policy before running it
+    pub fn with_policy_engine(mut self, policy_engine: Arc<dyn PolicyEngine>) -> Self {
+        self.policy_engine = Some(policy_engine);
+        self
+    }
+
+    /// Allow script steps as `config` permits. Without it, script steps fail.
+    pub fn with_scripts(mut self, config: ScriptConfig) -> Self {
+        self.scripts = config;
+        self
+    }
+
+    /// Execute a step with retry and timeout logic. `priority` is the run's, and orders
+    /// its agent tasks in the capacity broker's queue when no source is free.
     pub async fn execute(
         &self,
         run_id: RunId,
         step: &StepSpec,
         attempt: u32,
+        priority: u8,
     ) -> Result<StepResult> {
         tracing::info!(
             "Executing step {} (attempt {}) for run {}",
@@ -67,7 +327,7 @@ impl StepExecutor {
         let result = if let Some(timeout_secs) = step.timeout_secs {
             match timeout(
                 Duration::from_secs(timeout_secs),
-                self.execute_action(run_id, step, attempt),
+                self.execute_action(run_id, step, attempt, priority),
             )
             .await
             {
@@ -79,7 +339,7 @@ impl StepExecutor {
                 }
             }
         } else {
-            self.execute_action(run_id, step, attempt).await
+            self.execute_action(run_id, step, attempt, priority).await
         };
 
         let duration = start.elapsed();
@@ -149,13 +409,15 @@ impl StepExecutor {
                     tokio::time::sleep(backoff_duration).await;
 
                     // Retry (boxed to avoid infinite recursion size)
-                    return Box::pin(self.execute(run_id, step, attempt + 1)).await;
+                    return Box::pin(self.execute(run_id, step, attempt + 1, priority)).await;
                 }
 
                 Ok(StepResult {
                     status: StepStatus::Failed,
                     error: Some(error_msg),
                     artifacts: vec![],
+                    tokens: None,
+                    cost: None,
                 })
             }
         }
@@ -166,31 +428,225 @@ impl StepExecutor {
         &self,
         run_id: RunId,
         step: &StepSpec,
-        _attempt: u32,
+        attempt: u32,
+        priority: u8,
     ) -> Result<StepResult> {
+        if let Some(capture) = &self.replay {
+            return self.replay_action(run_id, step, attempt, capture).await;
+        }
+
         match &step.action {
             StepAction::AgentTask { prompt } => {
-                self.execute_agent_task(run_id, &step.id, prompt).await
+                self.execute_agent_task(run_id, step, prompt, priority, None).await
             }
-            StepAction::ToolSequence { tools } => {
-                self.execute_tool_sequence(run_id, &step.id, tools).await
+            StepAction::ToolSequence {
+                tools,
+                continue_on_error,
+            } => {
+                let invoker = self.tools.as_ref().ok_or_else(|| {
+                    anyhow!("No tool registry is configured for tool sequence steps")
+                })?;
+                self.execute_tool_sequence(
+                    run_id,
+                    step,
+                    invoker.as_ref(),
+                    tools,
+                    *continue_on_error,
+                )
+                .await
             }
             StepAction::ManualApproval { approvers } => {
                 self.execute_manual_approval(run_id, &step.id, approvers).await
             }
             StepAction::Script { command, args } => {
-                self.execute_script(run_id, &step.id, command, args).await
+                self.execute_script(run_id, &step.id, attempt, command, args).await
+            }
+            StepAction::SubWorkflow { .. } => Err(anyhow!(
+                "Sub-workflow steps must be run by the workflow executor"
+            )),
+            StepAction::WaitForEvent {
+                event_key,
+                timeout_secs,
+            } => {
+                self.execute_wait_for_event(run_id, &step.id, attempt, event_key, *timeout_secs)
+                    .await
             }
         }
     }
 
-    /// Execute an agent task (stub for now - will integrate with MCP in Phase 2)
-    async fn execute_agent_task(
+    /// Execute a step's action with the responses its original run received for the
+    /// same attempt. Scripts and waits aren't run; they end as they did originally.
+    async fn replay_action(
+        &self,
+        run_id: RunId,
+        step: &StepSpec,
+        attempt: u32,
+        capture: &RunCapture,
+    ) -> Result<StepResult> {
+        let captured = capture.next_attempt(&step.id).ok_or_else(|| {
+            anyhow!(
+                "Run {} has no captured attempt {} of step {}",
+                capture.run_id,
+                attempt,
+                step.id
+            )
+        })?;
+
+        match &step.action {
+            StepAction::AgentTask { prompt } => {
+                let response = match captured.agent_response {
+                    Some(response) => (response, captured.agent_tokens),
+                    None => {
+                        return Err(anyhow!(captured.error.unwrap_or_else(|| {
+                            format!("No agent response was captured for step {}", step.id)
+                        })))
+                    }
+                };
+                self.execute_agent_task(run_id, step, prompt, 0, Some(response)).await
+            }
+            StepAction::ToolSequence {
+                tools,
+                continue_on_error,
+            } => {
+                let invoker = ReplayTools::new(&captured);
+                self.execute_tool_sequence(run_id, step, &invoker, tools, *continue_on_error)
+                    .await
+            }
+            StepAction::ManualApproval { approvers } => {
+                self.execute_manual_approval(run_id, &step.id, approvers).await
+            }
+            StepAction::Script { .. } | StepAction::WaitForEvent { .. } => match captured.error {
+                Some(error) => Err(anyhow!(error)),
+                None => Ok(StepResult {
+                    status: StepStatus::Completed,
+                    error: None,
+                    artifacts: vec![],
+                    tokens: None,
+                    cost: None,
+                }),
+            },
+            StepAction::SubWorkflow { .. } => Err(anyhow!(
+                "Sub-workflow steps must be run by the workflow executor"
+            )),
+        }
+    }
+
+    /// Park until a matching `SignalReceived` event is appended to the run.
+    ///
+    /// The parked state lives in the event log: if this attempt already parked (e.g.
+    /// before a restart), it picks up where it left off, keeping the original deadline
+    /// and seeing signals delivered in between.
+    async fn execute_wait_for_event(
         &self,
         run_id: RunId,
         step_id: &StepId,
+        attempt: u32,
+        event_key: &str,
+        timeout_secs: Option<u64>,
+    ) -> Result<StepResult> {
+        let events = self.event_log.get_run_events(run_id).await?;
+        let parked = events.iter().position(|e| {
+            matches!(
+                &e.event_type,
+                EventType::StepWaiting { step_id: s, event_key: k, attempt: a, .. }
+                    if s == step_id && k == event_key && *a == attempt
+            )
+        });
+
+        let (mut after, parked_at) = match parked {
+            Some(index) => (index + 1, events[index].timestamp),
+            None => {
+                let event = Event::new(
+                    run_id,
+                    EventType::StepWaiting {
+                        step_id: step_id.clone(),
+                        event_key: event_key.to_string(),
+                        attempt,
+                        timeout_secs,
+                    },
+                );
+                let (event_id, parked_at) = (event.id.clone(), event.timestamp);
+                self.event_log.append(event).await?;
+
+                // Other events may have landed since the log was read
+                let events = self.event_log.get_run_events(run_id).await?;
+                let index = events
+                    .iter()
+                    .position(|e| e.id == event_id)
+                    .ok_or_else(|| anyhow!("Step {} did not park", step_id))?;
+                (index + 1, parked_at)
+            }
+        };
+
+        tracing::info!("Step {} waiting for event '{}'", step_id, event_key);
+
+        let deadline = timeout_secs.map(|secs| parked_at + chrono::Duration::seconds(secs as i64));
+
+        loop {
+            let wait = match deadline {
+                Some(deadline) => match (deadline - chrono::Utc::now()).to_std() {
+                    Ok(remaining) if !remaining.is_zero() => remaining.min(SIGNAL_POLL_INTERVAL),
+                    _ => {
+                        return Err(anyhow!(
+                            "Timed out after {} seconds waiting for event '{}'",
+                            timeout_secs.unwrap_or_default(),
+                            event_key
+                        ))
+                    }
+                },
+                None => SIGNAL_POLL_INTERVAL,
+            };
+
+            let appended = self.event_log.tail_run_events(run_id, after, wait).await?;
+            after += appended.len();
+
+            let signal = appended.into_iter().find_map(|e| match e.event_type {
+                EventType::SignalReceived {
+                    event_key: key,
+                    payload,
+                } if key == event_key => Some(payload),
+                _ => None,
+            });
+
+            if let Some(payload) = signal {
+                tracing::info!("Step {} received event '{}'", step_id, event_key);
+
+                let mut artifacts = vec![];
+                if let Some(payload) = payload {
+                    let content_hash = self
+                        .blob_store
+                        .put(Bytes::from(serde_json::to_vec(&payload)?))
+                        .await?;
+                    artifacts.push(Artifact {
+                        artifact_type: "signal_payload".to_string(),
+                        content_hash,
+                        metadata: serde_json::json!({ "event_key": event_key }),
+                    });
+                }
+
+                return Ok(StepResult {
+                    status: StepStatus::Completed,
+                    error: None,
+                    artifacts,
+                    tokens: None,
+                    cost: None,
+                });
+            }
+        }
+    }
+
+    /// Execute an agent task, through the capacity broker if one is configured. A
+    /// `replayed` response and its token count are used as-is. Without a broker the
+    /// response is simulated and, since no model ran, no tokens are recorded.
+    async fn execute_agent_task(
+        &self,
+        run_id: RunId,
+        step: &StepSpec,
         prompt: &str,
+        priority: u8,
+        replayed: Option<(String, Option<u64>)>,
     ) -> Result<StepResult> {
+        let step_id = &step.id;
         // Store prompt as blob
         let prompt_bytes = Bytes::from(prompt.to_string());
         let prompt_hash = self.blob_store.put(prompt_bytes).await?;
@@ -208,8 +664,62 @@ impl StepExecutor {
             ))
             .await?;
 
-        // For MVP: simulate agent response
-        let response = format!("Agent response to: {}", prompt);
+        let (response, tokens, cost) = match (replayed, &self.dry_agent, &self.capacity_broker) {
+            (Some((response, tokens)), _, _) => (response, tokens, None),
+            (None, Some(dry_agent), _) => (dry_agent.response(step, prompt), Some(0), None),
+            // Without any source the broker could only queue the request, so the
+            // simulated response stands in as it does when no broker is installed
+            (None, None, Some(broker)) if broker.has_sources() => {
+                let request = LlmRequest {
+                    prompt: prompt.to_string(),
+                    max_tokens: AGENT_TASK_MAX_TOKENS,
+                    temperature: None,
+                    model: None,
+                };
+                // The role's Claude settings and model fallbacks apply when its spec is
+                // known, i.e. when roles are loaded into a policy engine
+                let response = match &self.policy_engine {
+                    Some(policy_engine) => {
+                        let role = policy_engine
+                            .get_role(&step.role)
+                            .await?
+                            .ok_or_else(|| anyhow!("Role not found: {}", step.role.0))?;
+                        broker
+                            .execute_request_for_role(
+                                request,
+                                &role,
+                                run_id,
+                                step_id.clone(),
+                                priority,
+                            )
+                            .await
+                    }
+                    None => {
+                        broker
+                            .execute_request(
+                                request,
+                                run_id,
+                                step_id.clone(),
+                                step.role.clone(),
+                                priority,
+                            )
+                            .await
+                    }
+                };
+                // The step fails now, so nothing would collect a queued retry's response
+                let response = response.inspect_err(|_| {
+                    broker.remove_queued(run_id, step_id);
+                })?;
+                let tokens = u64::from(response.input_tokens + response.output_tokens);
+                // Counted against the role's budget, unless the broker's cache answered
+                if let (Some(policy_engine), false) = (&self.policy_engine, response.cache_hit) {
+                    let cost_cents = (response.cost * 100.0).round() as u64;
+                    policy_engine.record_usage(&step.role, tokens, cost_cents).await?;
+                }
+                (response.text, Some(tokens), Some(response.cost))
+            }
+            (None, None, _) => (format!("Agent response to: {}", prompt), None, None),
+        };
         let response_bytes = Bytes::from(response);
         let response_hash = self.blob_store.put(response_bytes).await?;
 
@@ -221,7 +731,7 @@ impl StepExecutor {
                     step_id: step_id.clone(),
                     direction: MessageDirection::FromAgent,
                     content_hash: response_hash.clone(),
-                    tokens: Some(100), // Simulated
+                    tokens,
                 },
             ))
             .await?;
@@ -233,26 +743,158 @@ impl StepExecutor {
                 artifact_type: "agent_response".to_string(),
                 content_hash: response_hash,
                 metadata: serde_json::json!({
-                    "tokens": 100,
+                    "tokens": tokens,
                 }),
             }],
+            tokens,
+            cost,
         })
     }
 
-    /// Execute a sequence of tool calls (stub for Phase 2)
+    /// Execute a sequence of tool calls in order, storing each output as an artifact.
+    ///
+    /// A failing or denied tool fails the step, unless `continue_on_error` is set, in
+    /// which case its error is stored as an artifact and the sequence carries on.
     async fn execute_tool_sequence(
         &self,
-        _run_id: RunId,
-        _step_id: &StepId,
-        tools: &[crate::types::ToolCallSpec],
+        run_id: RunId,
+        step: &StepSpec,
+        invoker: &dyn ToolInvoker,
+        tools: &[ToolCallSpec],
+        continue_on_error: bool,
     ) -> Result<StepResult> {
-        // For MVP: just log that we would execute tools
-        tracing::info!("Would execute {} tool calls", tools.len());
+        let mut artifacts = Vec::with_capacity(tools.len());
+        for (index, call) in tools.iter().enumerate() {
+            let metadata = serde_json::json!({ "tool_id": call.tool_id, "index": index });
+            match self.execute_tool_call(run_id, step, invoker, call).await {
+                Ok(output) => {
+                    let content_hash =
+                        self.blob_store.put(Bytes::from(serde_json::to_vec(&output)?)).await?;
+                    artifacts.push(Artifact {
+                        artifact_type: "tool_output".to_string(),
+                        content_hash,
+                        metadata,
+                    });
+                }
+                Err(e) if continue_on_error => {
+                    tracing::warn!("Tool {} failed in step {}: {}", call.tool_id, step.id, e);
+                    let content_hash = self.blob_store.put(Bytes::from(e.to_string())).await?;
+                    artifacts.push(Artifact {
+                        artifact_type: "tool_error".to_string(),
+                        content_hash,
+                        metadata,
+                    });
+                }
+                Err(e) => {
+                    return Err(anyhow!("Tool {} failed: {}", call.tool_id, e));
+                }
+            }
+        }
 
         Ok(StepResult {
             status: StepStatus::Completed,
             error: None,
-            artifacts: vec![],
+            artifacts,
+            tokens: None,
+            cost: None,
+        })
+    }
+
+    /// Check a tool call against policy and run it, recording it in the event log
+    async fn execute_tool_call(
+        &self,
+        run_id: RunId,
+        step: &StepSpec,
+        invoker: &dyn ToolInvoker,
+        call: &ToolCallSpec,
+    ) -> Result<serde_json::Value> {
+        let tool_tier = invoker
+            .tool_tier(&call.tool_id)
+            .ok_or_else(|| anyhow!("Tool {} is not registered", call.tool_id))?;
+
+        let parameters_hash = self
+            .blob_store
+            .put(Bytes::from(serde_json::to_vec(&call.parameters)?))
+            .await?;
+        self.event_log
+            .append(Event::new(
+                run_id,
+                EventType::ToolCallProposed {
+                    step_id: step.id.clone(),
+                    tool_id: call.tool_id.clone(),
+                    parameters_hash,
+                    role_id: Some(step.role.clone()),
+                    tool_tier,
+                },
+            ))
+            .await?;
+
+        if let Some(reason) = self.check_tool_policy(&step.role, call, tool_tier).await? {
+            self.event_log
+                .append(Event::new(
+                    run_id,
+                    EventType::ToolCallDenied {
+                        step_id: step.id.clone(),
+                        tool_id: call.tool_id.clone(),
+                        denied_by: "policy".to_string(),
+                        reason: reason.clone(),
+                    },
+                ))
+                .await?;
+            return Err(anyhow!(reason));
+        }
+
+        let start = std::time::Instant::now();
+        let output = invoker.invoke(&call.tool_id, call.parameters.clone()).await?;
+
+        let result_hash = self
+            .blob_store
+            .put(Bytes::from(serde_json::to_vec(&output)?))
+            .await?;
+        self.event_log
+            .append(Event::new(
+                run_id,
+                EventType::ToolCallExecuted {
+                    step_id: step.id.clone(),
+                    tool_id: call.tool_id.clone(),
+                    result_hash,
+                    duration_ms: start.elapsed().as_millis() as u64,
+                },
+            ))
+            .await?;
+
+        Ok(output)
+    }
+
+    /// Why the policy engine won't let `role_id` make a tool call, if it won't.
+    ///
+    /// Tool sequences have no one to ask for approval mid-run, so calls that need
+    /// approval are refused as well.
+    async fn check_tool_policy(
+        &self,
+        role_id: &RoleId,
+        call: &ToolCallSpec,
+        tool_tier: u8,
+    ) -> Result<Option<String>> {
+        let Some(policy_engine) = &self.policy_engine else {
+            return Ok(None);
+        };
+
+        let context = PolicyContext {
+            role_id: role_id.clone(),
+            tool_id: call.tool_id.clone(),
+            tool_tier,
+            parameters: call.parameters.clone(),
+            timestamp: chrono::Utc::now(),
+        };
+        Ok(match policy_engine.check_tool_call(&context).await? {
+            PolicyDecision::Allow => None,
+            PolicyDecision::Deny { reason } => Some(reason),
+            PolicyDecision::RequiresApproval { approvers } => Some(format!(
+                "Tool {} requires approval from {}",
+                call.tool_id,
+                approvers.join(", ")
+            )),
         })
     }
 
@@ -293,27 +935,124 @@ impl StepExecutor {
             status: StepStatus::Completed,
             error: None,
             artifacts: vec![],
+            tokens: None,
+            cost: None,
         })
     }
 
-    /// Execute a script (stub for Phase 2)
+    /// Run a script, recording its stdout and stderr. A non-zero exit fails the step.
     async fn execute_script(
         &self,
-        _run_id: RunId,
-        _step_id: &StepId,
+        run_id: RunId,
+        step_id: &StepId,
+        attempt: u32,
         command: &str,
         args: &[String],
     ) -> Result<StepResult> {
-        // For MVP: just log
-        tracing::info!("Would execute script: {} {:?}", command, args);
+        if !self.scripts.enabled {
+            return Err(anyhow!("Script steps are disabled"));
+        }
+        if !self.scripts.allows(command, args) {
+            return Err(anyhow!("Script command '{} {}' is not allowed", command, args.join(" ")));
+        }
+        let working_dir = self
+            .scripts
+            .working_dir
+            .as_ref()
+            .ok_or_else(|| anyhow!("Script steps have no working directory configured"))?;
+
+        tracing::info!("Executing script: {} {:?}", command, args);
+
+        let mut child = tokio::process::Command::new(command)
+            .args(args)
+            .env_clear()
+            .envs(&self.scripts.env)
+            .current_dir(working_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // A step timeout drops the future, which must not leave the process running
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("Failed to run script '{}': {}", command, e))?;
+
+        let limit = self.scripts.max_output_bytes;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let (status, stdout, stderr) = tokio::try_join!(
+            child.wait(),
+            read_capped(stdout, limit),
+            read_capped(stderr, limit),
+        )
+        .map_err(|e| anyhow!("Failed to run script '{}': {}", command, e))?;
+
+        for (stream, captured) in [
+            (OutputStream::Stdout, stdout),
+            (OutputStream::Stderr, stderr),
+        ] {
+            self.record_output(run_id, step_id, attempt, stream, captured).await?;
+        }
+
+        if !status.success() {
+            return Err(anyhow!("Script '{}' exited with {}", command, status));
+        }
 
         Ok(StepResult {
             status: StepStatus::Completed,
             error: None,
             artifacts: vec![],
+            tokens: None,
+            cost: None,
         })
     }
 
+    /// Record one redacted output stream, storing it whole as a blob when it is over the
+    /// log limit
+    async fn record_output(
+        &self,
+        run_id: RunId,
+        step_id: &StepId,
+        attempt: u32,
+        stream: OutputStream,
+        captured: CapturedOutput,
+    ) -> Result<()> {
+        if captured.bytes.is_empty() {
+            return Ok(());
+        }
+
+        let mut content = String::from_utf8_lossy(&captured.bytes).into_owned();
+        if let Some(redactor) = &self.redactor {
+            content = redactor.redact(&content);
+        }
+
+        let total_bytes = content.len() as u64 + captured.discarded;
+        let log_hash = if content.len() > self.log_limit_bytes {
+            let hash = self.blob_store.put(Bytes::from(content.clone())).await?;
+            let mut end = self.log_limit_bytes;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content.truncate(end);
+            Some(hash)
+        } else {
+            None
+        };
+
+        self.event_log
+            .append(Event::new(
+                run_id,
+                EventType::StepOutput {
+                    step_id: step_id.clone(),
+                    attempt,
+                    stream,
+                    content,
+                    total_bytes,
+                    log_hash,
+                },
+            ))
+            .await
+    }
+
     /// Check if we should retry a failed step
     fn should_retry(&self, step: &StepSpec, attempt: u32) -> bool {
         if let Some(retry_policy) = &step.retry_policy {
@@ -323,3 +1062,413 @@ impl StepExecutor {
         }
     }
 }
+
+/// The first bytes of a script output stream, and how many more were discarded
+struct CapturedOutput {
+    bytes: Vec<u8>,
+    discarded: u64,
+}
+
+/// Read `reader` to the end, keeping at most `limit` bytes in memory
+async fn read_capped(
+    mut reader: impl AsyncRead + Unpin,
+    limit: usize,
+) -> std::io::Result<CapturedOutput> {
+    let mut captured = CapturedOutput {
+        bytes: Vec::new(),
+        discarded: 0,
+    };
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(captured);
+        }
+        let keep = n.min(limit - captured.bytes.len());
+        captured.bytes.extend_from_slice(&buf[..keep]);
+        captured.discarded += (n - keep) as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{InMemoryBlobStore, JsonlEventLog};
+    use crate::types::DEFAULT_RUN_PRIORITY;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    /// Echoes its parameters back, except for the `fail` tool
+    #[derive(Default)]
+    struct RecordingTools {
+        calls: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ToolInvoker for RecordingTools {
+        fn tool_tier(&self, _tool_id: &str) -> Option<u8> {
+            Some(0)
+        }
+
+        async fn invoke(
+            &self,
+            tool_id: &str,
+            parameters: serde_json::Value,
+        ) -> Result<serde_json::Value> {
+            self.calls.lock().unwrap().push(tool_id.to_string());
+            match tool_id {
+                "fail" => Err(anyhow!("Repository not found")),
+                _ => Ok(serde_json::json!({ "tool": tool_id, "echo": parameters })),
+            }
+        }
+    }
+
+    fn tool_step(tool_ids: &[&str], continue_on_error: bool) -> StepSpec {
+        StepSpec {
+            id: StepId::new("tools"),
+            name: "tools".to_string(),
+            description: None,
+            role: RoleId::new("engineer"),
+            action: StepAction::ToolSequence {
+                tools: tool_ids
+                    .iter()
+                    .map(|id| ToolCallSpec {
+                        tool_id: id.to_string(),
+                        parameters: serde_json::json!({ "path": id }),
+                    })
+                    .collect(),
+                continue_on_error,
+            },
+            timeout_secs: None,
+            retry_policy: None,
+            requires_approval: false,
+        }
+    }
+
+    fn create_executor(temp_dir: &TempDir) -> (StepExecutor, Arc<RecordingTools>) {
+        let event_log = Arc::new(JsonlEventLog::new(temp_dir.path().to_path_buf()).unwrap());
+        let blob_store = Arc::new(InMemoryBlobStore::new());
+        let tools = Arc::new(RecordingTools::default());
+        let executor = StepExecutor::new(event_log, blob_store.clone()).with_tools(tools.clone());
+        (executor, tools)
+    }
+
+    #[tokio::test]
+    async fn test_tool_sequence_runs_tools_in_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, tools) = create_executor(&temp_dir);
+        let run_id = RunId::new();
+
+        let result = executor
+            .execute(
+                run_id,
+                &tool_step(&["repo_read", "context_get"], false),
+                1,
+                DEFAULT_RUN_PRIORITY,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.status, StepStatus::Completed);
+        assert_eq!(*tools.calls.lock().unwrap(), vec!["repo_read", "context_get"]);
+
+        assert_eq!(result.artifacts.len(), 2);
+        for (index, (artifact, tool_id)) in
+            result.artifacts.iter().zip(["repo_read", "context_get"]).enumerate()
+        {
+            assert_eq!(artifact.artifact_type, "tool_output");
+            assert_eq!(artifact.metadata["index"], index);
+            let output = executor.blob_store.get(&artifact.content_hash).await.unwrap().unwrap();
+            let output: serde_json::Value = serde_json::from_slice(&output).unwrap();
+            assert_eq!(output["tool"], tool_id);
+            assert_eq!(output["echo"]["path"], tool_id);
+        }
+
+        let events = executor.event_log.get_run_events(run_id).await.unwrap();
+        let executed = events
+            .iter()
+            .filter(|e| matches!(e.event_type, EventType::ToolCallExecuted { .. }))
+            .count();
+        assert_eq!(executed, 2);
+    }
+
+    #[tokio::test]
+    async fn test_tool_failure_halts_sequence() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, tools) = create_executor(&temp_dir);
+
+        let result = executor
+            .execute(
+                RunId::new(),
+                &tool_step(&["fail", "context_get"], false),
+                1,
+                DEFAULT_RUN_PRIORITY,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.status, StepStatus::Failed);
+        assert_eq!(result.error.as_deref(), Some("Tool fail failed: Repository not found"));
+        assert_eq!(*tools.calls.lock().unwrap(), vec!["fail"]);
+
+        // Unless the sequence is configured to carry on
+        tools.calls.lock().unwrap().clear();
+        let result = executor
+            .execute(
+                RunId::new(),
+                &tool_step(&["fail", "context_get"], true),
+                1,
+                DEFAULT_RUN_PRIORITY,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.status, StepStatus::Completed);
+        assert_eq!(*tools.calls.lock().unwrap(), vec!["fail", "context_get"]);
+        let types: Vec<_> = result.artifacts.iter().map(|a| a.artifact_type.as_str()).collect();
+        assert_eq!(types, vec!["tool_error", "tool_output"]);
+    }
+
+    #[tokio::test]
+    async fn test_script_output_captured_and_truncated() {
+        use crate::events::{step_logs, OutputStream};
+        use crate::redaction::{RedactionConfig, REDACTED};
+
+        use std::os::unix::fs::PermissionsExt;
+
+        // Scripts checked into the working directory, run by path
+        let temp_dir = TempDir::new().unwrap();
+        let write_script = |name: &str, body: &str| {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path.to_str().unwrap().to_string()
+        };
+        let emit = write_script("emit", "echo 'token sk-abcdefghijkl'; printf 'x%.0s' $(seq 100) >&2");
+        let broken = write_script("broken", "echo broken >&2; exit 3");
+
+        let (executor, _) = create_executor(&temp_dir);
+        let executor = executor
+            .with_redactor(Redactor::new(RedactionConfig::default()))
+            .with_log_limit(32)
+            .with_scripts(ScriptConfig::allowing(&[&emit, &broken], temp_dir.path()));
+        let run_id = RunId::new();
+
+        let mut step = tool_step(&[], false);
+        step.action = StepAction::Script {
+            command: emit,
+            args: vec![],
+        };
+        let result = executor.execute(run_id, &step, 1, DEFAULT_RUN_PRIORITY).await.unwrap();
+        assert_eq!(result.status, StepStatus::Completed);
+
+        let events = executor.event_log.get_run_events(run_id).await.unwrap();
+        let logs = step_logs(&events);
+        assert_eq!(logs.len(), 2);
+
+        let stdout = &logs[0];
+        assert_eq!(stdout.stream, OutputStream::Stdout);
+        assert_eq!(stdout.content, format!("token {}\n", REDACTED));
+        assert!(!stdout.truncated);
+
+        let stderr = &logs[1];
+        assert_eq!(stderr.stream, OutputStream::Stderr);
+        assert_eq!(stderr.content, "x".repeat(32));
+        assert_eq!(stderr.total_bytes, 100);
+        assert!(stderr.truncated);
+        let full = executor
+            .blob_store
+            .get(stderr.log_hash.as_ref().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(full, "x".repeat(100).as_bytes());
+
+        // A failing script fails the step, with its output still recorded
+        step.action = StepAction::Script {
+            command: broken,
+            args: vec![],
+        };
+        let run_id = RunId::new();
+        let result = executor.execute(run_id, &step, 1, DEFAULT_RUN_PRIORITY).await.unwrap();
+        assert_eq!(result.status, StepStatus::Failed);
+        let events = executor.event_log.get_run_events(run_id).await.unwrap();
+        assert_eq!(step_logs(&events)[0].content, "broken\n");
+    }
+
+    #[tokio::test]
+    async fn test_scripts_gated_by_config() {
+        use crate::events::step_logs;
+
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, _) = create_executor(&temp_dir);
+        let mut step = tool_step(&[], false);
+        let script = |command: &str, args: &[&str]| StepAction::Script {
+            command: command.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        };
+        step.action = script("pwd", &[]);
+
+        // Disabled by default
+        let result = executor.execute(RunId::new(), &step, 1, DEFAULT_RUN_PRIORITY).await.unwrap();
+        assert_eq!(result.status, StepStatus::Failed);
+        assert!(result.error.unwrap().contains("disabled"));
+
+        // Only allowlisted command lines run
+        let executor = executor.with_scripts(ScriptConfig::allowing(
+            &["true", "pwd", "printenv", "seq 100"],
+            temp_dir.path(),
+        ));
+        step.action = script("seq", &["1000000000"]);
+        let result = executor.execute(RunId::new(), &step, 1, DEFAULT_RUN_PRIORITY).await.unwrap();
+        assert_eq!(result.status, StepStatus::Failed);
+        assert!(result.error.unwrap().contains("not allowed"));
+
+        // Allowed scripts run in the working directory with only the configured environment
+        std::env::set_var("SHIIOO_SCRIPT_TEST_SECRET", "leaked");
+        let output = |action: StepAction| {
+            let mut step = step.clone();
+            step.action = action;
+            let executor = &executor;
+            async move {
+                let run_id = RunId::new();
+                let result = executor.execute(run_id, &step, 1, DEFAULT_RUN_PRIORITY).await.unwrap();
+                assert_eq!(result.status, StepStatus::Completed);
+                let events = executor.event_log.get_run_events(run_id).await.unwrap();
+                step_logs(&events).remove(0)
+            }
+        };
+        let dir = temp_dir.path().canonicalize().unwrap();
+        assert_eq!(output(script("pwd", &[])).await.content, format!("{}\n", dir.display()));
+        let env = output(script("printenv", &[])).await.content;
+        assert!(env.contains("PATH="));
+        assert!(!env.contains("leaked"));
+
+        // Output past the capture limit is counted but not kept
+        let config = ScriptConfig {
+            max_output_bytes: 24,
+            ..ScriptConfig::allowing(&["seq *"], temp_dir.path())
+        };
+        let executor = executor.with_scripts(config);
+        step.action = script("seq", &["100"]);
+        let run_id = RunId::new();
+        executor.execute(run_id, &step, 1, DEFAULT_RUN_PRIORITY).await.unwrap();
+        let events = executor.event_log.get_run_events(run_id).await.unwrap();
+        let stdout = &step_logs(&events)[0];
+        let full: String = (1..=100).map(|i| format!("{}\n", i)).collect();
+        assert_eq!(stdout.content, full[..24]);
+        assert_eq!(stdout.total_bytes, full.len() as u64);
+    }
+
+    #[test]
+    fn test_script_allowlist_matches_whole_command_lines() {
+        let config = ScriptConfig::allowing(&["cargo test *", "make build"], "/tmp");
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        assert!(config.allows("cargo", &args(&["test"])));
+        assert!(config.allows("cargo", &args(&["test", "--workspace"])));
+        assert!(!config.allows("cargo", &args(&["run"])));
+        assert!(config.allows("make", &args(&["build"])));
+        assert!(!config.allows("make", &args(&["-f", "/tmp/evil.mk"])));
+        assert!(config.validate().is_ok());
+
+        // Interpreters can't be left open to any args
+        for entry in ["sh *", "bash -c *", "/usr/bin/python3.12 *", "git *"] {
+            let config = ScriptConfig::allowing(&[entry], "/tmp");
+            assert!(config.validate().is_err(), "{}", entry);
+            let command = entry.split_whitespace().next().unwrap();
+            assert!(!config.allows(command, &args(&["-c", "true"])), "{}", entry);
+        }
+    }
+
+    fn capacity_source(enabled: bool) -> crate::types::CapacitySource {
+        use crate::types::{CapacitySource, CapacitySourceId, CostPerToken, LlmProvider, RateLimits};
+
+        CapacitySource {
+            id: CapacitySourceId::new("src1"),
+            name: "Source".to_string(),
+            provider: LlmProvider::Anthropic,
+            api_key_hash: "hash".to_string(),
+            model: "claude-opus-4".to_string(),
+            rate_limits: RateLimits {
+                requests_per_minute: 60,
+                tokens_per_minute: 100_000,
+                tokens_per_day: None,
+            },
+            cost_per_token: CostPerToken {
+                input_cost: 15.0,
+                output_cost: 75.0,
+            },
+            priority: 100,
+            enabled,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_task_without_capacity_leaves_nothing_queued() {
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, _) = create_executor(&temp_dir);
+        let broker = Arc::new(CapacityBroker::new());
+        let executor = executor.with_capacity_broker(broker.clone());
+        let mut step = tool_step(&[], false);
+        step.action = StepAction::AgentTask {
+            prompt: "Summarize the diff".to_string(),
+        };
+
+        // A broker with no sources falls back to the simulated response
+        let result = executor.execute(RunId::new(), &step, 1, DEFAULT_RUN_PRIORITY).await.unwrap();
+        assert_eq!(result.status, StepStatus::Completed);
+        assert_eq!(broker.queue_length(), 0);
+
+        // With only a disabled source the step fails, and its request isn't left queued
+        broker.register_source(capacity_source(false)).unwrap();
+        let result = executor.execute(RunId::new(), &step, 1, DEFAULT_RUN_PRIORITY).await.unwrap();
+        assert_eq!(result.status, StepStatus::Failed);
+        assert!(result.error.unwrap().contains("No capacity available"));
+        assert_eq!(broker.queue_length(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_agent_task_bound_by_role_settings() {
+        use crate::policy::InMemoryPolicyEngine;
+        use crate::types::{ClaudeSettings, LimitEnforcement, RoleSpec};
+
+        let temp_dir = TempDir::new().unwrap();
+        let (executor, _) = create_executor(&temp_dir);
+        let broker = Arc::new(CapacityBroker::new());
+        broker.register_source(capacity_source(true)).unwrap();
+        let role = RoleSpec {
+            id: RoleId::new("engineer"),
+            name: "Engineer".to_string(),
+            description: String::new(),
+            prompt_template: String::new(),
+            allowed_tools: vec![],
+            budgets: Default::default(),
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: Some(ClaudeSettings {
+                max_tokens: Some(1000),
+                max_tokens_enforcement: LimitEnforcement::Reject,
+                ..Default::default()
+            }),
+        };
+        let executor = executor.with_capacity_broker(broker).with_policy_engine(Arc::new(
+            InMemoryPolicyEngine::new().with_specs(vec![role], vec![]),
+        ));
+        let mut step = tool_step(&[], false);
+        step.action = StepAction::AgentTask {
+            prompt: "Summarize the diff".to_string(),
+        };
+
+        // The task's max_tokens is over the role's limit, which rejects rather than clamps
+        let result = executor.execute(RunId::new(), &step, 1, DEFAULT_RUN_PRIORITY).await.unwrap();
+        assert_eq!(result.status, StepStatus::Failed);
+        assert!(result.error.unwrap().contains("exceeds the limit"));
+
+        // A role the policy engine doesn't know can't run agent tasks
+        step.role = RoleId::new("ghost");
+        let result = executor.execute(RunId::new(), &step, 1, DEFAULT_RUN_PRIORITY).await.unwrap();
+        assert_eq!(result.status, StepStatus::Failed);
+        assert!(result.error.unwrap().contains("Role not found"));
+    }
+}
@@ -1,6 +1,8 @@
 // Standalone MCP server binary
 
 use anyhow::Result;
+use shiioo_core::audit::AuditLog;
+use shiioo_core::redaction::{RedactionConfig, Redactor};
 use shiioo_core::storage::{FilesystemBlobStore, JsonlEventLog, RedbIndexStore};
 use shiioo_mcp::server::McpServer;
 use shiioo_mcp::tools::*;
@@ -50,8 +52,12 @@ async fn main() -> Result<()> {
 
     tracing::info!("Registered {} tools", registry.list_schemas().len());
 
+    // Audit each tool call, tagged with the caller's trace context and run
+    let redactor = Redactor::new(RedactionConfig::default());
+    let audit_log = Arc::new(AuditLog::new().with_redactor(redactor));
+
     // Start MCP server
-    let server = McpServer::new(registry);
+    let server = McpServer::new(registry).with_audit_log(audit_log);
     server.start().await?;
 
     Ok(())
@@ -145,6 +145,18 @@ pub struct ListToolsResult {
 pub struct CallToolParams {
     pub name: String,
     pub arguments: serde_json::Value,
+    /// Caller's trace, so the tool call can be correlated with what it affects
+    #[serde(rename = "traceContext", default, skip_serializing_if = "Option::is_none")]
+    pub trace_context: Option<TraceContext>,
+}
+
+/// Trace identifiers propagated from the calling agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceContext {
+    pub trace_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_span_id: Option<String>,
 }
 
 /// Call tool response
@@ -3,13 +3,17 @@
 use crate::protocol::*;
 use crate::tools::ToolRegistry;
 use anyhow::Result;
+use shiioo_core::audit::{AuditAction, AuditCategory, AuditLog, AuditSeverity};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::sync::RwLock;
+use tracing::Instrument;
 
 pub struct McpServer {
     registry: Arc<RwLock<ToolRegistry>>,
     initialized: Arc<RwLock<bool>>,
+    audit_log: Option<Arc<AuditLog>>,
 }
 
 impl McpServer {
@@ -17,9 +21,16 @@ impl McpServer {
         Self {
             registry: Arc::new(RwLock::new(registry)),
             initialized: Arc::new(RwLock::new(false)),
+            audit_log: None,
         }
     }
 
+    /// Record an audit entry for each tool call, tagged with the caller's trace context
+    pub fn with_audit_log(mut self, audit_log: Arc<AuditLog>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
     /// Start the MCP server (JSON-RPC over stdio)
     pub async fn start(&self) -> Result<()> {
         tracing::info!("Starting MCP server");
@@ -176,8 +187,20 @@ impl McpServer {
             }
         };
 
-        // Execute the tool
-        match tool.execute(params.arguments).await {
+        // Execute the tool inside a span carrying the caller's trace, so everything the
+        // tool logs can be joined with the agent's side of the call
+        let trace = params.trace_context.as_ref();
+        let span = tracing::info_span!(
+            "tool_call",
+            tool = %params.name,
+            trace_id = trace.map(|t| t.trace_id.as_str()),
+            parent_span_id = trace.and_then(|t| t.parent_span_id.as_deref()),
+        );
+        let result = tool.execute(params.arguments.clone()).instrument(span).await;
+        let succeeded = matches!(&result, Ok(r) if r.is_error != Some(true));
+        self.audit_tool_call(&params, succeeded);
+
+        match result {
             Ok(result) => JsonRpcResponse::success(id, result),
             Err(e) => {
                 let error_result = CallToolResult {
@@ -188,6 +211,41 @@ impl McpServer {
             }
         }
     }
+
+    fn audit_tool_call(&self, params: &CallToolParams, succeeded: bool) {
+        let Some(audit_log) = &self.audit_log else {
+            return;
+        };
+
+        let mut metadata = HashMap::new();
+        if let Some(trace) = &params.trace_context {
+            metadata.insert("trace_id".to_string(), trace.trace_id.clone());
+            if let Some(parent_span_id) = &trace.parent_span_id {
+                metadata.insert("parent_span_id".to_string(), parent_span_id.clone());
+            }
+        }
+        // Link the call to the run it targets, if any
+        if let Some(run_id) = params.arguments.get("run_id").and_then(|v| v.as_str()) {
+            metadata.insert("run_id".to_string(), run_id.to_string());
+        }
+
+        audit_log.record(
+            AuditCategory::WorkflowExecution,
+            if succeeded {
+                AuditSeverity::Info
+            } else {
+                AuditSeverity::Warning
+            },
+            AuditAction::ToolCalled {
+                tool_name: params.name.clone(),
+                succeeded,
+            },
+            None,
+            None,
+            None,
+            metadata,
+        );
+    }
 }
 
 impl Default for McpServer {
@@ -195,3 +253,73 @@ impl Default for McpServer {
         Self::new(ToolRegistry::new())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::Tool;
+
+    struct EchoTool;
+
+    #[async_trait::async_trait]
+    impl Tool for EchoTool {
+        fn schema(&self) -> ToolSchema {
+            ToolSchema {
+                name: "echo".to_string(),
+                description: "Echo the arguments".to_string(),
+                input_schema: serde_json::json!({"type": "object"}),
+            }
+        }
+
+        async fn execute(&self, arguments: serde_json::Value) -> Result<CallToolResult> {
+            Ok(CallToolResult {
+                content: vec![ToolContent::text(arguments.to_string())],
+                is_error: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_audit_carries_trace_id() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool));
+        let audit_log = Arc::new(AuditLog::new());
+        let server = McpServer::new(registry).with_audit_log(audit_log.clone());
+
+        let initialize = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "0"}
+            }
+        });
+        let response = server.handle_request(&initialize.to_string()).await;
+        assert!(response.error.is_none(), "{:?}", response.error);
+
+        let call = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {
+                "name": "echo",
+                "arguments": {"run_id": "run-42"},
+                "traceContext": {"traceId": "trace-abc", "parentSpanId": "span-1"}
+            }
+        });
+        let response = server.handle_request(&call.to_string()).await;
+        assert!(response.error.is_none(), "{:?}", response.error);
+
+        let entries = audit_log.list_entries();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            &entries[0].action,
+            AuditAction::ToolCalled { tool_name, succeeded: true } if tool_name == "echo"
+        ));
+        assert_eq!(entries[0].metadata["trace_id"], "trace-abc");
+        assert_eq!(entries[0].metadata["parent_span_id"], "span-1");
+        assert_eq!(entries[0].metadata["run_id"], "run-42");
+    }
+}
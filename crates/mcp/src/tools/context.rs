@@ -91,8 +91,8 @@ impl Tool for ContextSearchTool {
                 serde_json::json!({
                     "status": {
                         "type": "string",
-                        "description": "Filter by run status (pending, running, completed, failed, cancelled)",
-                        "enum": ["pending", "running", "completed", "failed", "cancelled"]
+                        "description": "Filter by run status (pending, running, paused, completed, failed, cancelled)",
+                        "enum": ["pending", "running", "paused", "completed", "failed", "cancelled"]
                     },
                     "limit": {
                         "type": "number",
@@ -115,6 +115,7 @@ impl Tool for ContextSearchTool {
             let status = match status_str.as_str() {
                 "pending" => shiioo_core::types::RunStatus::Pending,
                 "running" => shiioo_core::types::RunStatus::Running,
+                "paused" => shiioo_core::types::RunStatus::Paused,
                 "completed" => shiioo_core::types::RunStatus::Completed,
                 "failed" => shiioo_core::types::RunStatus::Failed,
                 "cancelled" => shiioo_core::types::RunStatus::Cancelled,
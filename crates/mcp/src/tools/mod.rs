@@ -8,5 +8,5 @@ pub use repo::RepoReadTool;
 pub use web::WebFetchTool;
 pub use registry::{
     json_schema_array, json_schema_boolean, json_schema_number, json_schema_object,
-    json_schema_string, Tool, ToolRegistry, ToolTier,
+    json_schema_string, Tool, ToolInfo, ToolRegistry, ToolTier,
 };
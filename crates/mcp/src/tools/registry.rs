@@ -1,7 +1,9 @@
 // MCP tool definitions and implementations
 
 use crate::protocol::{CallToolResult, ToolContent, ToolSchema};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use shiioo_core::workflow::ToolInvoker;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -21,7 +23,7 @@ pub trait Tool: Send + Sync {
 }
 
 /// Tool security tier
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum ToolTier {
     /// Read-only operations
     Tier0,
@@ -31,6 +33,16 @@ pub enum ToolTier {
     Tier2,
 }
 
+/// A registered tool's schema and tier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInfo {
+    pub name: String,
+    pub description: String,
+    pub tier: ToolTier,
+    /// JSON schema of the tool's arguments
+    pub input_schema: serde_json::Value,
+}
+
 /// Tool registry for managing available tools
 pub struct ToolRegistry {
     tools: HashMap<String, Arc<dyn Tool>>,
@@ -59,6 +71,25 @@ impl ToolRegistry {
         self.tools.values().map(|t| t.schema()).collect()
     }
 
+    /// Describe every registered tool, by name
+    pub fn list_tools(&self) -> Vec<ToolInfo> {
+        let mut tools: Vec<_> = self
+            .tools
+            .values()
+            .map(|tool| {
+                let schema = tool.schema();
+                ToolInfo {
+                    name: schema.name,
+                    description: schema.description,
+                    tier: tool.tier(),
+                    input_schema: schema.input_schema,
+                }
+            })
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+
     /// Check if a tool exists
     pub fn contains(&self, name: &str) -> bool {
         self.tools.contains_key(name)
@@ -71,6 +102,39 @@ impl Default for ToolRegistry {
     }
 }
 
+/// Lets workflow `ToolSequence` steps call registered tools
+#[async_trait::async_trait]
+impl ToolInvoker for ToolRegistry {
+    fn tool_tier(&self, tool_id: &str) -> Option<u8> {
+        self.get(tool_id).map(|tool| tool.tier() as u8)
+    }
+
+    async fn invoke(
+        &self,
+        tool_id: &str,
+        parameters: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let tool = self
+            .get(tool_id)
+            .ok_or_else(|| anyhow!("Tool {} is not registered", tool_id))?;
+        let result = tool.execute(parameters).await?;
+
+        if result.is_error == Some(true) {
+            let message: Vec<_> = result
+                .content
+                .iter()
+                .filter_map(|content| match content {
+                    ToolContent::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            return Err(anyhow!("{}", message.join("\n")));
+        }
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
+
 // Helper functions for creating tool schemas
 
 pub fn json_schema_object(properties: serde_json::Value, required: Vec<&str>) -> serde_json::Value {
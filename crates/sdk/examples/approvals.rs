@@ -86,7 +86,7 @@ async fn main() -> ShiiooResult<()> {
     if let Some(approval) = pending.first() {
         println!("\nCasting vote on approval {}...", approval.id.0);
 
-        let vote_response = client
+        let updated = client
             .approvals()
             .vote(
                 &approval.id,
@@ -98,10 +98,7 @@ async fn main() -> ShiiooResult<()> {
             )
             .await?;
 
-        println!("Vote cast: {}", vote_response.message);
-
-        // Check updated status
-        let updated = client.approvals().get(&approval.id).await?;
+        println!("Vote cast.");
         println!("Updated status: {:?}", updated.status);
         println!("Votes: {}", updated.votes.len());
     }
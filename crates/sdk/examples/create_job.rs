@@ -54,6 +54,7 @@ async fn main() -> ShiiooResult<()> {
             deps.insert(StepId::new("report"), vec![StepId::new("analyze")]);
             deps
         },
+        inputs: Vec::new(),
     };
 
     // Create the job
@@ -66,6 +67,10 @@ async fn main() -> ShiiooResult<()> {
             workflow,
             created_by: Some("sdk-example".to_string()),
             execute: Some(true), // Execute immediately
+            external_id: None,
+            tags: HashMap::from([("team".to_string(), "platform".to_string())]),
+            inputs: HashMap::new(),
+            priority: None,
         })
         .await?;
 
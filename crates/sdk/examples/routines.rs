@@ -35,6 +35,7 @@ async fn main() -> ShiiooResult<()> {
             requires_approval: false,
         }],
         dependencies: HashMap::new(),
+        inputs: Vec::new(),
     };
 
     // Create a routine that runs daily at 9 AM
@@ -51,6 +52,7 @@ async fn main() -> ShiiooResult<()> {
             workflow,
             enabled: Some(true),
             created_by: Some("sdk-example".to_string()),
+            tags: HashMap::new(),
         })
         .await?;
 
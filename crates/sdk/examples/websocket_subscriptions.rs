@@ -28,6 +28,12 @@ async fn main() -> ShiiooResult<()> {
     println!("Subscribing to metrics updates...");
     subscription.subscribe_metrics().await?;
 
+    println!("Subscribing to approval updates...");
+    subscription.subscribe_approvals().await?;
+
+    println!("Subscribing to capacity alerts...");
+    subscription.subscribe_capacity().await?;
+
     println!("\nListening for events (Ctrl+C to stop)...\n");
 
     // Process incoming events
@@ -87,6 +93,41 @@ async fn main() -> ShiiooResult<()> {
                     );
                 }
 
+                SubscriptionEvent::ApprovalUpdate {
+                    approval_id,
+                    status,
+                } => {
+                    println!("[APPROVAL] {}: {:?}", approval_id, status);
+                }
+
+                SubscriptionEvent::ApprovalReminder {
+                    approval_id,
+                    approvers,
+                    reminder,
+                } => {
+                    println!("[REMINDER #{}] {} awaiting {:?}", reminder, approval_id, approvers);
+                }
+
+                SubscriptionEvent::CapacityAlert {
+                    kind,
+                    source_id,
+                    message,
+                } => {
+                    println!("[CAPACITY] {:?} {:?}: {}", kind, source_id, message);
+                }
+
+                SubscriptionEvent::AuditEvent { entry } => {
+                    println!("[AUDIT] {:?} {:?}", entry.category, entry.action);
+                }
+
+                SubscriptionEvent::Gap {
+                    topic,
+                    from_seq,
+                    to_seq,
+                } => {
+                    println!("[GAP] Missed {:?} updates {}..={}", topic, from_seq, to_seq);
+                }
+
                 SubscriptionEvent::Subscribed { subscription_id } => {
                     println!("[SUBSCRIBED] ID: {}", subscription_id);
                 }
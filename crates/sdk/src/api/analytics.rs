@@ -4,7 +4,7 @@ use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use serde::{Deserialize, Serialize};
 use shiioo_core::analytics::{BottleneckReport, ExecutionTrace, StepStats, WorkflowStats};
-use shiioo_core::types::RunId;
+use shiioo_core::types::{RunId, StepId};
 
 /// Analytics API for workflow analytics and traces.
 pub struct AnalyticsApi<'a> {
@@ -60,6 +60,23 @@ impl<'a> AnalyticsApi<'a> {
             .get(&format!("/api/analytics/bottlenecks/{}", workflow_id))
             .await
     }
+
+    /// Get the chain of steps that determines a workflow's wall-clock time.
+    pub async fn critical_path(&self, workflow_id: &str) -> ShiiooResult<CriticalPath> {
+        self.client
+            .http
+            .get(&format!("/api/analytics/critical-path/{}", workflow_id))
+            .await
+    }
+}
+
+/// Critical path through a workflow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CriticalPath {
+    pub workflow_id: String,
+    pub steps: Vec<StepId>,
+    /// Sum of the path's average step durations.
+    pub estimated_duration_secs: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,9 +17,7 @@ impl<'a> ApprovalBoardsApi<'a> {
 
     /// List all approval boards.
     pub async fn list(&self) -> ShiiooResult<Vec<ApprovalBoard>> {
-        let response: ListApprovalBoardsResponse =
-            self.client.http.get("/api/approval-boards").await?;
-        Ok(response.boards)
+        self.client.http.get_all_pages("/api/approval-boards", &()).await
     }
 
     /// Get a specific approval board.
@@ -47,11 +45,6 @@ impl<'a> ApprovalBoardsApi<'a> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ListApprovalBoardsResponse {
-    boards: Vec<ApprovalBoard>,
-}
-
 /// Response from creating an approval board.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateApprovalBoardResponse {
@@ -3,7 +3,8 @@
 use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use serde::{Deserialize, Serialize};
-use shiioo_core::types::{Approval, ApprovalId, PersonId, VoteDecision};
+use shiioo_core::approval::{BulkVote, BulkVoteResult};
+use shiioo_core::types::{Approval, ApprovalId, ApprovalStatus, PersonId, VoteDecision};
 
 /// Approvals API for managing approvals.
 pub struct ApprovalsApi<'a> {
@@ -17,8 +18,7 @@ impl<'a> ApprovalsApi<'a> {
 
     /// List all approvals.
     pub async fn list(&self) -> ShiiooResult<Vec<Approval>> {
-        let response: ListApprovalsResponse = self.client.http.get("/api/approvals").await?;
-        Ok(response.approvals)
+        self.client.http.get_all_pages("/api/approvals", &()).await
     }
 
     /// Get a specific approval.
@@ -29,22 +29,77 @@ impl<'a> ApprovalsApi<'a> {
             .await
     }
 
-    /// Cast a vote on an approval.
+    /// Cast a vote on an approval, returning the approval with the vote and any
+    /// resulting status change. With an API key, the voter must be the key's user.
     pub async fn vote(
         &self,
         approval_id: &ApprovalId,
         request: CastVoteRequest,
-    ) -> ShiiooResult<CastVoteResponse> {
+    ) -> ShiiooResult<Approval> {
         self.client
             .http
             .post(&format!("/api/approvals/{}/vote", approval_id.0), &request)
             .await
     }
+
+    /// Cast votes on several approvals at once.
+    ///
+    /// Each vote is applied independently; failures are reported per item
+    /// rather than failing the whole request. With an API key, `voter_id` must be
+    /// the key's user, or the whole request is refused.
+    pub async fn bulk_vote(
+        &self,
+        voter_id: PersonId,
+        votes: Vec<BulkVote>,
+    ) -> ShiiooResult<Vec<BulkVoteResult>> {
+        let request = BulkVoteRequest { voter_id, votes };
+        let response: BulkVoteResponse = self
+            .client
+            .http
+            .post("/api/approvals/bulk-vote", &request)
+            .await?;
+        Ok(response.results)
+    }
+
+    /// Approve or deny an approval without waiting for quorum.
+    ///
+    /// Requires the `Override` permission on approvals. The justification is
+    /// recorded in the audit log alongside the caller's identity.
+    pub async fn force_resolve(
+        &self,
+        approval_id: &ApprovalId,
+        decision: ApprovalStatus,
+        justification: impl Into<String>,
+    ) -> ShiiooResult<Approval> {
+        let request = ForceResolveRequest {
+            decision,
+            justification: justification.into(),
+        };
+        self.client
+            .http
+            .post(
+                &format!("/api/approvals/{}/force-resolve", approval_id.0),
+                &request,
+            )
+            .await
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ListApprovalsResponse {
-    approvals: Vec<Approval>,
+struct BulkVoteRequest {
+    voter_id: PersonId,
+    votes: Vec<BulkVote>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ForceResolveRequest {
+    decision: ApprovalStatus,
+    justification: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BulkVoteResponse {
+    results: Vec<BulkVoteResult>,
 }
 
 /// Request to cast a vote.
@@ -56,8 +111,3 @@ pub struct CastVoteRequest {
     pub comment: Option<String>,
 }
 
-/// Response from casting a vote.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CastVoteResponse {
-    pub message: String,
-}
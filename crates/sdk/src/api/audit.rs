@@ -4,7 +4,9 @@ use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use shiioo_core::audit::{AuditCategory, AuditEntry, AuditStatistics};
+use shiioo_core::audit::{
+    AuditCategory, AuditCheckpoint, AuditEntry, AuditStatistics, AuditSubmission,
+};
 
 /// Audit API for audit log access.
 pub struct AuditApi<'a> {
@@ -29,6 +31,17 @@ impl<'a> AuditApi<'a> {
             .await
     }
 
+    /// Append events from this component to the audit chain.
+    ///
+    /// Requires `Create` on the audit log. The server assigns IDs, timestamps and
+    /// chain hashes; the recorded entries are returned in order.
+    pub async fn ingest(&self, entries: Vec<AuditSubmission>) -> ShiiooResult<Vec<AuditEntry>> {
+        let request = IngestAuditRequest { entries };
+        let response: IngestAuditResponse =
+            self.client.http.post("/api/audit/ingest", &request).await?;
+        Ok(response.entries)
+    }
+
     /// Get audit statistics.
     pub async fn statistics(&self) -> ShiiooResult<AuditStatistics> {
         self.client.http.get("/api/audit/statistics").await
@@ -38,6 +51,25 @@ impl<'a> AuditApi<'a> {
     pub async fn verify_chain(&self) -> ShiiooResult<AuditChainVerification> {
         self.client.http.get("/api/audit/verify-chain").await
     }
+
+    /// Verify the audit chain from the checkpoint at entry `checkpoint_index` onwards.
+    pub async fn verify_chain_from(
+        &self,
+        checkpoint_index: usize,
+    ) -> ShiiooResult<AuditChainVerification> {
+        self.client
+            .http
+            .get_with_query(
+                "/api/audit/verify-chain",
+                &[("from_checkpoint", checkpoint_index)],
+            )
+            .await
+    }
+
+    /// List signed audit checkpoints, oldest first.
+    pub async fn checkpoints(&self) -> ShiiooResult<Vec<AuditCheckpoint>> {
+        self.client.http.get("/api/audit/checkpoints").await
+    }
 }
 
 /// Filter for audit entries.
@@ -53,9 +85,21 @@ pub struct AuditFilter {
     pub end_time: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct IngestAuditRequest {
+    entries: Vec<AuditSubmission>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IngestAuditResponse {
+    entries: Vec<AuditEntry>,
+}
+
 /// Result of audit chain verification.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditChainVerification {
     pub is_valid: bool,
     pub message: String,
+    #[serde(default)]
+    pub errors: Vec<String>,
 }
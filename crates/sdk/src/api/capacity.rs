@@ -2,8 +2,12 @@
 
 use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use shiioo_core::types::{CapacitySource, CapacitySourceId, CapacityUsage};
+use shiioo_core::analytics::{CostBucket, CostGrouping, MonthlyCostProjection};
+use shiioo_core::types::{
+    CapacitySource, CapacitySourceId, CapacityUsage, DeadLetter, PriorityRequest, SelectionTrace,
+};
 
 /// Capacity API for managing LLM capacity sources.
 pub struct CapacityApi<'a> {
@@ -17,9 +21,7 @@ impl<'a> CapacityApi<'a> {
 
     /// List all capacity sources.
     pub async fn sources(&self) -> ShiiooResult<Vec<CapacitySource>> {
-        let response: ListCapacitySourcesResponse =
-            self.client.http.get("/api/capacity/sources").await?;
-        Ok(response.sources)
+        self.client.http.get_all_pages("/api/capacity/sources", &()).await
     }
 
     /// Get a specific capacity source.
@@ -56,15 +58,69 @@ impl<'a> CapacityApi<'a> {
         Ok(response.usage)
     }
 
+    /// Report usage for requests made directly against providers.
+    /// Records for unknown sources are rejected individually.
+    pub async fn report_usage(
+        &self,
+        usage: &[CapacityUsage],
+    ) -> ShiiooResult<IngestCapacityUsageResponse> {
+        self.client.http.post("/api/capacity/usage/batch", &usage).await
+    }
+
     /// Get capacity cost summary.
     pub async fn cost(&self) -> ShiiooResult<CapacityCostResponse> {
         self.client.http.get("/api/capacity/cost").await
     }
+
+    /// Get the cost summary of usage since `since` (all usage if `None`), broken down
+    /// into hourly or daily buckets.
+    pub async fn cost_by(
+        &self,
+        group_by: CostGrouping,
+        since: Option<DateTime<Utc>>,
+    ) -> ShiiooResult<CapacityCostResponse> {
+        let query = CostQuery { group_by, since };
+        self.client
+            .http
+            .get_with_query("/api/capacity/cost", &query)
+            .await
+    }
+
+    /// Explain the most recent source selection: which sources were considered, why
+    /// each was skipped and which was chosen. `None` if no selection has happened yet.
+    pub async fn last_selection_trace(&self) -> ShiiooResult<Option<SelectionTrace>> {
+        let response: SelectionTraceResponse =
+            self.client.http.get("/api/capacity/selection-trace").await?;
+        Ok(response.trace)
+    }
+
+    /// List queued requests that exhausted their attempts.
+    pub async fn dead_letters(&self) -> ShiiooResult<Vec<DeadLetter>> {
+        self.client.http.get_all_pages("/api/capacity/dead-letter", &()).await
+    }
+
+    /// Move a dead-lettered request back onto the queue.
+    pub async fn requeue_dead_letter(
+        &self,
+        request_id: &str,
+    ) -> ShiiooResult<RequeueDeadLetterResponse> {
+        self.client
+            .http
+            .post(&format!("/api/capacity/dead-letter/{}/requeue", request_id), &())
+            .await
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CostQuery {
+    group_by: CostGrouping,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ListCapacitySourcesResponse {
-    sources: Vec<CapacitySource>,
+struct SelectionTraceResponse {
+    trace: Option<SelectionTrace>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -72,6 +128,13 @@ struct ListCapacityUsageResponse {
     usage: Vec<CapacityUsage>,
 }
 
+/// Response from requeueing a dead-lettered request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequeueDeadLetterResponse {
+    pub request: PriorityRequest,
+    pub message: String,
+}
+
 /// Response from creating a capacity source.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateCapacitySourceResponse {
@@ -89,7 +152,30 @@ pub struct DeleteCapacitySourceResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapacityCostResponse {
     pub total_cost: f64,
-    pub total_tokens: u32,
-    pub total_requests: u32,
+    pub total_tokens: u64,
+    pub total_requests: u64,
     pub record_count: usize,
+    /// Per-bucket totals, oldest first; empty unless requested with `cost_by`
+    #[serde(default)]
+    pub buckets: Vec<CostBucket>,
+    /// This month's spend so far and its linear projection to the month's end
+    #[serde(default)]
+    pub projection: Option<MonthlyCostProjection>,
+}
+
+/// Response from reporting a batch of usage records.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestCapacityUsageResponse {
+    pub stored: usize,
+    pub results: Vec<CapacityUsageResult>,
+}
+
+/// Outcome of one record in a usage batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapacityUsageResult {
+    pub usage_id: String,
+    /// Already ingested by an earlier request, so not counted again.
+    #[serde(default)]
+    pub duplicate: bool,
+    pub error: Option<String>,
 }
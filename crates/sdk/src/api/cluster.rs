@@ -3,7 +3,7 @@
 use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use serde::{Deserialize, Serialize};
-use shiioo_core::cluster::{ClusterNode, NodeId};
+use shiioo_core::cluster::{ClusterNode, NodeId, RegionHealth};
 use std::collections::HashMap;
 
 /// Cluster API for managing cluster nodes.
@@ -51,6 +51,48 @@ impl<'a> ClusterApi<'a> {
             .await
     }
 
+    /// Send a heartbeat carrying the latest leader term the node knows of, which the
+    /// server adopts if it is newer than its own.
+    pub async fn heartbeat_with_term(
+        &self,
+        node_id: &NodeId,
+        term: u64,
+    ) -> ShiiooResult<HeartbeatResponse> {
+        self.client
+            .http
+            .post(&format!("/api/cluster/nodes/{}/heartbeat?term={}", node_id.0, term), &())
+            .await
+    }
+
+    /// Stop assigning new work to a node while it finishes its in-flight work.
+    pub async fn drain_node(&self, node_id: &NodeId) -> ShiiooResult<ClusterNode> {
+        self.client
+            .http
+            .post(&format!("/api/cluster/nodes/{}/drain", node_id.0), &())
+            .await
+    }
+
+    /// Return a draining node to service.
+    pub async fn undrain_node(&self, node_id: &NodeId) -> ShiiooResult<ClusterNode> {
+        self.client
+            .http
+            .post(&format!("/api/cluster/nodes/{}/undrain", node_id.0), &())
+            .await
+    }
+
+    /// Pick a healthy node whose labels satisfy `requirements`, e.g. `region=eu-west`.
+    pub async fn select_node(
+        &self,
+        requirements: &HashMap<String, String>,
+    ) -> ShiiooResult<Option<ClusterNode>> {
+        let response: SelectNodeResponse = self
+            .client
+            .http
+            .get_with_query("/api/cluster/select", requirements)
+            .await?;
+        Ok(response.node)
+    }
+
     /// Get the current leader node.
     pub async fn leader(&self) -> ShiiooResult<Option<ClusterNode>> {
         let response: LeaderResponse = self.client.http.get("/api/cluster/leader").await?;
@@ -63,6 +105,11 @@ impl<'a> ClusterApi<'a> {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct SelectNodeResponse {
+    node: Option<ClusterNode>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ListNodesResponse {
     nodes: Vec<ClusterNode>,
@@ -93,6 +140,9 @@ pub struct RemoveNodeResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HeartbeatResponse {
     pub message: String,
+    /// Current leader term; writes stamped with an older term are rejected
+    #[serde(default)]
+    pub term: u64,
 }
 
 /// Cluster health status.
@@ -103,4 +153,7 @@ pub struct ClusterHealthResponse {
     pub has_leader: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub leader_id: Option<String>,
+    /// Node counts and quorum per region
+    #[serde(default)]
+    pub by_region: HashMap<String, RegionHealth>,
 }
@@ -17,9 +17,7 @@ impl<'a> ConfigChangesApi<'a> {
 
     /// List all config changes.
     pub async fn list(&self) -> ShiiooResult<Vec<ConfigChange>> {
-        let response: ListConfigChangesResponse =
-            self.client.http.get("/api/config-changes").await?;
-        Ok(response.changes)
+        self.client.http.get_all_pages("/api/config-changes", &()).await
     }
 
     /// Get a specific config change.
@@ -46,12 +44,23 @@ impl<'a> ConfigChangesApi<'a> {
             .await
     }
 
-    /// Reject a config change.
+    /// Check whether a config change would apply cleanly, without applying it.
+    pub async fn dry_run_apply(
+        &self,
+        change_id: &ConfigChangeId,
+    ) -> ShiiooResult<ApplyConfigChangeResponse> {
+        self.client
+            .http
+            .post(&format!("/api/config-changes/{}/apply?dry_run=true", change_id.0), &())
+            .await
+    }
+
+    /// Reject a config change, returning it as rejected.
     pub async fn reject(
         &self,
         change_id: &ConfigChangeId,
         request: RejectConfigChangeRequest,
-    ) -> ShiiooResult<RejectConfigChangeResponse> {
+    ) -> ShiiooResult<ConfigChange> {
         self.client
             .http
             .post(&format!("/api/config-changes/{}/reject", change_id.0), &request)
@@ -59,16 +68,12 @@ impl<'a> ConfigChangesApi<'a> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ListConfigChangesResponse {
-    changes: Vec<ConfigChange>,
-}
-
 /// Request to propose a config change.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProposeConfigChangeRequest {
     pub change_type: ConfigChangeType,
     pub description: String,
+    /// Ignored by the server, which snapshots the target's current config itself.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub before: Option<String>,
     pub after: String,
@@ -90,6 +95,17 @@ pub struct ProposeConfigChangeResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApplyConfigChangeResponse {
     pub message: String,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Why the change would not apply (dry runs only).
+    #[serde(default)]
+    pub errors: Vec<String>,
+    #[serde(default)]
+    pub current: Option<String>,
+    #[serde(default)]
+    pub after: String,
+    /// The change as it now stands after applying (or unchanged, for a dry run).
+    pub change: ConfigChange,
 }
 
 /// Request to reject a config change.
@@ -98,8 +114,3 @@ pub struct RejectConfigChangeRequest {
     pub reason: String,
 }
 
-/// Response from rejecting a config change.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RejectConfigChangeResponse {
-    pub message: String,
-}
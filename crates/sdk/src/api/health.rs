@@ -3,6 +3,7 @@
 use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use serde::{Deserialize, Serialize};
+use shiioo_core::compliance::ComplianceFramework;
 
 /// Health API for checking server status.
 pub struct HealthApi<'a> {
@@ -44,3 +45,30 @@ pub struct HealthStatusResponse {
     pub failed_executions: u64,
     pub success_rate: f64,
 }
+
+/// Server version and capabilities, as returned by `ShiiooClient::describe`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerDescription {
+    pub service: String,
+    pub version: String,
+    pub api_version: String,
+    pub features: ServerFeatures,
+    pub compliance_frameworks: Vec<ComplianceFramework>,
+}
+
+impl ServerDescription {
+    /// Check whether the server supports a compliance framework.
+    pub fn supports_framework(&self, framework: ComplianceFramework) -> bool {
+        self.compliance_frameworks.contains(&framework)
+    }
+}
+
+/// Optional server features.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerFeatures {
+    pub graphql: bool,
+    pub mcp: bool,
+    pub webhooks: bool,
+    #[serde(default)]
+    pub api_key_auth: bool,
+}
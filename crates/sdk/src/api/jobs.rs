@@ -4,6 +4,8 @@ use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use serde::{Deserialize, Serialize};
 use shiioo_core::types::{RunId, WorkflowSpec};
+use shiioo_core::workflow::LintWarning;
+use std::collections::HashMap;
 
 /// Jobs API for creating and managing jobs.
 pub struct JobsApi<'a> {
@@ -19,6 +21,24 @@ impl<'a> JobsApi<'a> {
     pub async fn create(&self, request: CreateJobRequest) -> ShiiooResult<CreateJobResponse> {
         self.client.http.post("/api/jobs", &request).await
     }
+
+    /// Check a workflow for likely mistakes without running it.
+    ///
+    /// Circular or dangling dependencies are reported as errors; everything
+    /// else comes back as warnings.
+    pub async fn lint(&self, workflow: &WorkflowSpec) -> ShiiooResult<Vec<LintWarning>> {
+        let response: LintWorkflowResponse = self
+            .client
+            .http
+            .post("/api/jobs/lint", &serde_json::json!({ "workflow": workflow }))
+            .await?;
+        Ok(response.warnings)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LintWorkflowResponse {
+    warnings: Vec<LintWarning>,
 }
 
 /// Request to create a new job.
@@ -32,6 +52,18 @@ pub struct CreateJobRequest {
     pub created_by: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub execute: Option<bool>,
+    /// Unique caller-supplied ID for fetching the run later
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    /// Labels for the run, e.g. `team: payments`, that runs can be listed by
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tags: HashMap<String, String>,
+    /// Values for the workflow's declared inputs, referenced in steps as `{{inputs.<name>}}`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub inputs: HashMap<String, serde_json::Value>,
+    /// 0-255; higher runs first when role slots or capacity are contended (server default 50)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
 }
 
 /// Response from creating a job.
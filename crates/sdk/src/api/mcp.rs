@@ -0,0 +1,50 @@
+//! MCP tool registry endpoints.
+
+use crate::client::ShiiooClient;
+use crate::error::ShiiooResult;
+use serde::{Deserialize, Serialize};
+
+/// MCP API for inspecting the server's tool registry.
+pub struct McpApi<'a> {
+    client: &'a ShiiooClient,
+}
+
+impl<'a> McpApi<'a> {
+    pub(crate) fn new(client: &'a ShiiooClient) -> Self {
+        Self { client }
+    }
+
+    /// List the registered tools, by name.
+    ///
+    /// These are the names role `allowed_tools` and `ToolSequence` steps refer to.
+    pub async fn list_tools(&self) -> ShiiooResult<Vec<McpTool>> {
+        let response: ListToolsResponse = self.client.http.get("/api/mcp/tools").await?;
+        Ok(response.tools)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ListToolsResponse {
+    tools: Vec<McpTool>,
+}
+
+/// A registered MCP tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpTool {
+    pub name: String,
+    pub description: String,
+    pub tier: ToolTier,
+    /// JSON schema of the tool's arguments
+    pub input_schema: serde_json::Value,
+}
+
+/// Security tier of an MCP tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ToolTier {
+    /// Read-only operations
+    Tier0,
+    /// Controlled writes (PR-only, reversible)
+    Tier1,
+    /// Dangerous operations (always require approval)
+    Tier2,
+}
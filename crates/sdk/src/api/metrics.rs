@@ -2,8 +2,12 @@
 
 use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
-use shiioo_core::metrics::{Counter, Gauge, Histogram};
+use chrono::{DateTime, Utc};
+use shiioo_core::metrics::{Counter, Gauge, Histogram, MetricSeries};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
 /// Metrics API for retrieving system metrics.
 pub struct MetricsApi<'a> {
@@ -19,11 +23,175 @@ impl<'a> MetricsApi<'a> {
     pub async fn get(&self) -> ShiiooResult<MetricsResponse> {
         self.client.http.get("/api/metrics").await
     }
+
+    /// Get the recent history of a counter or gauge, one series per label set.
+    ///
+    /// `resolution_secs` must be a multiple of the server's configured resolution and
+    /// defaults to it.
+    pub async fn history(
+        &self,
+        metric: &str,
+        since: Option<DateTime<Utc>>,
+        resolution_secs: Option<i64>,
+    ) -> ShiiooResult<MetricHistoryResponse> {
+        self.client
+            .http
+            .get_with_query(
+                "/api/metrics/history",
+                &MetricHistoryQuery {
+                    metric,
+                    since,
+                    resolution: resolution_secs,
+                },
+            )
+            .await
+    }
+
+    /// Poll metrics every `interval` and yield how they changed since the previous poll.
+    ///
+    /// The first delta arrives after the second poll. Metrics that first appear in a
+    /// poll are reported from the one after it. The stream ends after the first error.
+    pub fn stream(&self, interval: Duration) -> impl Stream<Item = ShiiooResult<MetricsDelta>> + 'a {
+        let client = self.client;
+
+        futures_util::stream::unfold(Some(None), move |state| async move {
+            let mut previous: Option<MetricsResponse> = state?;
+
+            loop {
+                if previous.is_some() {
+                    tokio::time::sleep(interval).await;
+                }
+
+                let current = match client.http.get::<MetricsResponse>("/api/metrics").await {
+                    Ok(current) => current,
+                    Err(e) => return Some((Err(e), None)),
+                };
+
+                match previous {
+                    Some(previous) => {
+                        let delta = MetricsDelta::between(&previous, &current);
+                        return Some((Ok(delta), Some(Some(current))));
+                    }
+                    None => previous = Some(current),
+                }
+            }
+        })
+    }
+}
+
+/// How metrics changed between two polls.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsDelta {
+    pub counters: Vec<CounterRate>,
+    pub gauges: Vec<GaugeChange>,
+}
+
+impl MetricsDelta {
+    /// Compute the change from `previous` to `current`, matching metrics by name and labels.
+    pub fn between(previous: &MetricsResponse, current: &MetricsResponse) -> Self {
+        let previous_counters: HashMap<_, _> = previous
+            .counters
+            .iter()
+            .map(|c| (series_key(&c.name, &c.labels), c))
+            .collect();
+        let previous_gauges: HashMap<_, _> = previous
+            .gauges
+            .iter()
+            .map(|g| (series_key(&g.name, &g.labels), g))
+            .collect();
+
+        let counters = current
+            .counters
+            .iter()
+            .filter_map(|counter| {
+                let before = previous_counters.get(&series_key(&counter.name, &counter.labels))?;
+                // A smaller value means the server restarted and the counter began again at zero
+                let increase = if counter.value >= before.value {
+                    counter.value - before.value
+                } else {
+                    counter.value
+                };
+                let elapsed = (current.captured_at - previous.captured_at).num_milliseconds();
+                let per_second = if elapsed > 0 {
+                    increase as f64 * 1000.0 / elapsed as f64
+                } else {
+                    0.0
+                };
+
+                Some(CounterRate {
+                    name: counter.name.clone(),
+                    labels: counter.labels.clone(),
+                    value: counter.value,
+                    increase,
+                    per_second,
+                })
+            })
+            .collect();
+
+        let gauges = current
+            .gauges
+            .iter()
+            .filter_map(|gauge| {
+                let before = previous_gauges.get(&series_key(&gauge.name, &gauge.labels))?;
+                Some(GaugeChange {
+                    name: gauge.name.clone(),
+                    labels: gauge.labels.clone(),
+                    value: gauge.value,
+                    change: gauge.value - before.value,
+                })
+            })
+            .collect();
+
+        Self { counters, gauges }
+    }
+}
+
+/// Rate at which a counter increased between two polls.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CounterRate {
+    pub name: String,
+    pub labels: HashMap<String, String>,
+    pub value: u64,
+    pub increase: u64,
+    /// Increase per second between the two snapshots.
+    pub per_second: f64,
+}
+
+/// Change in a gauge between two polls.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GaugeChange {
+    pub name: String,
+    pub labels: HashMap<String, String>,
+    pub value: f64,
+    pub change: f64,
+}
+
+fn series_key(name: &str, labels: &HashMap<String, String>) -> (String, BTreeMap<String, String>) {
+    (name.to_string(), labels.clone().into_iter().collect())
+}
+
+#[derive(Serialize)]
+struct MetricHistoryQuery<'a> {
+    metric: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolution: Option<i64>,
+}
+
+/// History of one metric.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricHistoryResponse {
+    pub metric: String,
+    pub series: Vec<MetricSeries>,
 }
 
 /// Response containing all metrics.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricsResponse {
+    /// When the server took the snapshot. Servers that don't report it are timed on receipt.
+    #[serde(default = "Utc::now")]
+    pub captured_at: DateTime<Utc>,
     pub counters: Vec<Counter>,
     pub gauges: Vec<Gauge>,
     pub histograms: Vec<Histogram>,
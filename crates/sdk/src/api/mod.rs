@@ -10,6 +10,7 @@ pub mod compliance;
 pub mod config_changes;
 pub mod health;
 pub mod jobs;
+pub mod mcp;
 pub mod metrics;
 pub mod organizations;
 pub mod policies;
@@ -32,6 +33,7 @@ pub use compliance::ComplianceApi;
 pub use config_changes::ConfigChangesApi;
 pub use health::HealthApi;
 pub use jobs::JobsApi;
+pub use mcp::McpApi;
 pub use metrics::MetricsApi;
 pub use organizations::OrganizationsApi;
 pub use policies::PoliciesApi;
@@ -17,9 +17,7 @@ impl<'a> OrganizationsApi<'a> {
 
     /// List all organizations.
     pub async fn list(&self) -> ShiiooResult<Vec<Organization>> {
-        let response: ListOrganizationsResponse =
-            self.client.http.get("/api/organizations").await?;
-        Ok(response.organizations)
+        self.client.http.get_all_pages("/api/organizations", &()).await
     }
 
     /// Get a specific organization by ID.
@@ -44,11 +42,6 @@ impl<'a> OrganizationsApi<'a> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ListOrganizationsResponse {
-    organizations: Vec<Organization>,
-}
-
 /// Response from creating an organization.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateOrganizationResponse {
@@ -3,6 +3,7 @@
 use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use serde::{Deserialize, Serialize};
+use shiioo_core::policy::{EvaluationStep, PolicyDecision};
 use shiioo_core::types::{PolicyId, PolicySpec};
 
 /// Policies API for managing policies.
@@ -17,8 +18,7 @@ impl<'a> PoliciesApi<'a> {
 
     /// List all policies.
     pub async fn list(&self) -> ShiiooResult<Vec<PolicySpec>> {
-        let response: ListPoliciesResponse = self.client.http.get("/api/policies").await?;
-        Ok(response.policies)
+        self.client.http.get_all_pages("/api/policies", &()).await
     }
 
     /// Get a specific policy by ID.
@@ -34,6 +34,23 @@ impl<'a> PoliciesApi<'a> {
         self.client.http.post("/api/policies", policy).await
     }
 
+    /// Explain how a tool call by `role_id` would be decided, check by check.
+    pub async fn explain(
+        &self,
+        role_id: &str,
+        tool_id: &str,
+        tool_tier: u8,
+        parameters: serde_json::Value,
+    ) -> ShiiooResult<ToolCallExplanation> {
+        let request = ExplainToolCallRequest {
+            role_id: role_id.to_string(),
+            tool_id: tool_id.to_string(),
+            tool_tier,
+            parameters,
+        };
+        self.client.http.post("/api/policies/explain", &request).await
+    }
+
     /// Delete a policy.
     pub async fn delete(&self, policy_id: &PolicyId) -> ShiiooResult<DeletePolicyResponse> {
         self.client
@@ -43,11 +60,6 @@ impl<'a> PoliciesApi<'a> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ListPoliciesResponse {
-    policies: Vec<PolicySpec>,
-}
-
 /// Response from creating a policy.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreatePolicyResponse {
@@ -55,6 +67,21 @@ pub struct CreatePolicyResponse {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct ExplainToolCallRequest {
+    role_id: String,
+    tool_id: String,
+    tool_tier: u8,
+    parameters: serde_json::Value,
+}
+
+/// How a tool call was decided, with the checks made in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallExplanation {
+    pub decision: PolicyDecision,
+    pub steps: Vec<EvaluationStep>,
+}
+
 /// Response from deleting a policy.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeletePolicyResponse {
@@ -3,7 +3,7 @@
 use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use serde::{Deserialize, Serialize};
-use shiioo_core::rbac::{Action, RbacRole, Resource};
+use shiioo_core::rbac::{Action, RbacRole, Resource, ResourceTarget};
 
 /// RBAC API for role-based access control.
 pub struct RbacApi<'a> {
@@ -71,6 +71,9 @@ pub struct CheckPermissionRequest {
     pub user_id: String,
     pub resource: Resource,
     pub action: Action,
+    /// Check against one concrete resource instead of the resource type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<ResourceTarget>,
 }
 
 /// Generic success response.
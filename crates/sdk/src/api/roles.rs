@@ -3,7 +3,9 @@
 use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use serde::{Deserialize, Serialize};
-use shiioo_core::types::{RoleId, RoleSpec};
+use chrono::{DateTime, Utc};
+use shiioo_core::policy::BudgetUsage;
+use shiioo_core::types::{RoleBudgets, RoleId, RoleSpec};
 
 /// Roles API for managing roles.
 pub struct RolesApi<'a> {
@@ -17,8 +19,7 @@ impl<'a> RolesApi<'a> {
 
     /// List all roles.
     pub async fn list(&self) -> ShiiooResult<Vec<RoleSpec>> {
-        let response: ListRolesResponse = self.client.http.get("/api/roles").await?;
-        Ok(response.roles)
+        self.client.http.get_all_pages("/api/roles", &()).await
     }
 
     /// Get a specific role by ID.
@@ -35,11 +36,14 @@ impl<'a> RolesApi<'a> {
     pub async fn delete(&self, role_id: &RoleId) -> ShiiooResult<DeleteRoleResponse> {
         self.client.http.delete(&format!("/api/roles/{}", role_id.0)).await
     }
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ListRolesResponse {
-    roles: Vec<RoleSpec>,
+    /// Get a role's budget usage for the current day and what remains of its limits.
+    pub async fn budget(&self, role_id: &RoleId) -> ShiiooResult<RoleBudget> {
+        self.client
+            .http
+            .get(&format!("/api/roles/{}/budget", role_id.0))
+            .await
+    }
 }
 
 /// Response from creating a role.
@@ -49,6 +53,19 @@ pub struct CreateRoleResponse {
     pub message: String,
 }
 
+/// A role's daily budget usage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleBudget {
+    pub role_id: RoleId,
+    pub usage: BudgetUsage,
+    pub limits: RoleBudgets,
+    /// `None` when the role has no token limit.
+    pub remaining_tokens: Option<u64>,
+    /// `None` when the role has no cost limit.
+    pub remaining_cost_cents: Option<u64>,
+    pub resets_at: DateTime<Utc>,
+}
+
 /// Response from deleting a role.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeleteRoleResponse {
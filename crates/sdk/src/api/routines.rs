@@ -4,6 +4,7 @@ use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use serde::{Deserialize, Serialize};
 use shiioo_core::types::{Routine, RoutineExecution, RoutineId, RoutineSchedule, WorkflowSpec};
+use std::collections::HashMap;
 
 /// Routines API for managing scheduled workflows.
 pub struct RoutinesApi<'a> {
@@ -17,8 +18,7 @@ impl<'a> RoutinesApi<'a> {
 
     /// List all routines.
     pub async fn list(&self) -> ShiiooResult<Vec<Routine>> {
-        let response: ListRoutinesResponse = self.client.http.get("/api/routines").await?;
-        Ok(response.routines)
+        self.client.http.get_all_pages("/api/routines", &()).await
     }
 
     /// Get a specific routine.
@@ -42,43 +42,42 @@ impl<'a> RoutinesApi<'a> {
             .await
     }
 
-    /// Enable a routine.
-    pub async fn enable(&self, routine_id: &RoutineId) -> ShiiooResult<EnableRoutineResponse> {
+    /// Enable a routine, returning it as updated.
+    pub async fn enable(&self, routine_id: &RoutineId) -> ShiiooResult<Routine> {
         self.client
             .http
             .post(&format!("/api/routines/{}/enable", routine_id.0), &())
             .await
     }
 
-    /// Disable a routine.
-    pub async fn disable(&self, routine_id: &RoutineId) -> ShiiooResult<DisableRoutineResponse> {
+    /// Disable a routine, returning it as updated.
+    pub async fn disable(&self, routine_id: &RoutineId) -> ShiiooResult<Routine> {
         self.client
             .http
             .post(&format!("/api/routines/{}/disable", routine_id.0), &())
             .await
     }
 
+    /// Run a routine once now without changing its schedule.
+    /// Disabled routines are only triggered when `force` is set.
+    pub async fn trigger(&self, routine_id: &RoutineId, force: bool) -> ShiiooResult<TriggerRoutineResponse> {
+        self.client
+            .http
+            .post(&format!("/api/routines/{}/trigger?force={}", routine_id.0, force), &())
+            .await
+    }
+
     /// Get execution history for a routine.
+    ///
+    /// Each execution's status is its run's current status.
     pub async fn executions(&self, routine_id: &RoutineId) -> ShiiooResult<Vec<RoutineExecution>> {
-        let response: GetRoutineExecutionsResponse = self
-            .client
+        self.client
             .http
-            .get(&format!("/api/routines/{}/executions", routine_id.0))
-            .await?;
-        Ok(response.executions)
+            .get_all_pages(&format!("/api/routines/{}/executions", routine_id.0), &())
+            .await
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ListRoutinesResponse {
-    routines: Vec<Routine>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct GetRoutineExecutionsResponse {
-    executions: Vec<RoutineExecution>,
-}
-
 /// Request to create a routine.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateRoutineRequest {
@@ -90,6 +89,9 @@ pub struct CreateRoutineRequest {
     pub enabled: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_by: Option<String>,
+    /// Labels copied onto every run the routine starts
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tags: HashMap<String, String>,
 }
 
 /// Response from creating a routine.
@@ -105,14 +107,9 @@ pub struct DeleteRoutineResponse {
     pub message: String,
 }
 
-/// Response from enabling a routine.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EnableRoutineResponse {
-    pub message: String,
-}
-
-/// Response from disabling a routine.
+/// Response from manually triggering a routine.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DisableRoutineResponse {
+pub struct TriggerRoutineResponse {
+    pub execution: RoutineExecution,
     pub message: String,
 }
@@ -2,9 +2,15 @@
 
 use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
-use shiioo_core::events::Event;
-use shiioo_core::types::{Run, RunId};
+use shiioo_core::analytics::RunComparison;
+use shiioo_core::events::{Event, StepLog};
+use shiioo_core::types::{RetryFrom, Run, RunId, StepId};
+use shiioo_core::workflow::ReplayReport;
+use std::collections::VecDeque;
+use std::time::Duration;
 
 /// Runs API for managing workflow runs.
 pub struct RunsApi<'a> {
@@ -18,8 +24,13 @@ impl<'a> RunsApi<'a> {
 
     /// List all runs.
     pub async fn list(&self) -> ShiiooResult<Vec<Run>> {
-        let response: ListRunsResponse = self.client.http.get("/api/runs").await?;
-        Ok(response.runs)
+        self.client.http.get_all_pages("/api/runs", &()).await
+    }
+
+    /// List runs carrying a tag, given as `key:value` (e.g. `team:payments`). Escape any
+    /// colon or backslash in the key with a backslash (`region\:eu:west`).
+    pub async fn list_by_tag(&self, tag: &str) -> ShiiooResult<Vec<Run>> {
+        self.client.http.get_all_pages("/api/runs", &[("tag", tag)]).await
     }
 
     /// Get a specific run by ID.
@@ -27,6 +38,33 @@ impl<'a> RunsApi<'a> {
         self.client.http.get(&format!("/api/runs/{}", run_id.0)).await
     }
 
+    /// Get a run by the external ID supplied when its job was created.
+    pub async fn get_by_external_id(&self, external_id: &str) -> ShiiooResult<Run> {
+        self.client
+            .http
+            .get(&format!("/api/runs/by-external/{}", external_id))
+            .await
+    }
+
+    /// Estimate when a run will finish from historical step durations.
+    /// `None` when the server lacks the history to project from.
+    pub async fn eta(&self, run_id: &RunId) -> ShiiooResult<Option<DateTime<Utc>>> {
+        let response: RunEtaResponse = self
+            .client
+            .http
+            .get(&format!("/api/runs/{}/eta", run_id.0))
+            .await?;
+        Ok(response.estimated_completion)
+    }
+
+    /// Compare two runs step by step, e.g. a failing run against a prior success.
+    pub async fn compare(&self, a: &RunId, b: &RunId) -> ShiiooResult<RunComparison> {
+        self.client
+            .http
+            .get(&format!("/api/runs/compare?a={}&b={}", a.0, b.0))
+            .await
+    }
+
     /// Get events for a run.
     pub async fn events(&self, run_id: &RunId) -> ShiiooResult<Vec<Event>> {
         let response: GetRunEventsResponse = self
@@ -36,14 +74,125 @@ impl<'a> RunsApi<'a> {
             .await?;
         Ok(response.events)
     }
+
+    /// Get the captured script output of a run's steps.
+    pub async fn logs(&self, run_id: &RunId) -> ShiiooResult<Vec<StepLog>> {
+        let response: GetRunLogsResponse = self
+            .client
+            .http
+            .get(&format!("/api/runs/{}/logs", run_id.0))
+            .await?;
+        Ok(response.logs)
+    }
+
+    /// Deliver a signal to a run, resuming steps waiting for `event_key`.
+    pub async fn signal(
+        &self,
+        run_id: &RunId,
+        event_key: &str,
+        payload: Option<serde_json::Value>,
+    ) -> ShiiooResult<SignalRunResponse> {
+        self.client
+            .http
+            .post(
+                &format!("/api/runs/{}/signal", run_id.0),
+                &serde_json::json!({ "event_key": event_key, "payload": payload }),
+            )
+            .await
+    }
+
+    /// Retry a finished run as a new run. `RetryFrom::Failed` reuses the results of
+    /// steps that completed and executes the rest; `RetryFrom::Start` executes
+    /// everything again.
+    pub async fn retry(&self, run_id: &RunId, from: RetryFrom) -> ShiiooResult<Run> {
+        let from = match from {
+            RetryFrom::Failed => "failed",
+            RetryFrom::Start => "start",
+        };
+        self.client
+            .http
+            .post(&format!("/api/runs/{}/retry?from={}", run_id.0, from), &())
+            .await
+    }
+
+    /// Replay a finished run as a new run, feeding its steps the agent responses and
+    /// tool results the original run captured instead of calling them live. The report
+    /// says whether the replay took the same path through the DAG.
+    pub async fn replay(&self, run_id: &RunId) -> ShiiooResult<ReplayReport> {
+        self.client
+            .http
+            .post(&format!("/api/runs/{}/replay", run_id.0), &())
+            .await
+    }
+
+    /// Stream a run's events as they are appended, without WebSockets.
+    ///
+    /// Built on the long-polling tail endpoint: each request holds for up to
+    /// `wait` for new events, so `wait` should stay below the client's request
+    /// timeout. The stream ends after the first error.
+    pub fn tail(
+        &self,
+        run_id: RunId,
+        wait: Duration,
+    ) -> impl Stream<Item = ShiiooResult<Event>> + 'a {
+        let client = self.client;
+
+        futures_util::stream::unfold(
+            Some((0usize, VecDeque::new())),
+            move |state| async move {
+                let (mut after, mut pending): (usize, VecDeque<Event>) = state?;
+
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        return Some((Ok(event), Some((after, pending))));
+                    }
+
+                    let path = format!(
+                        "/api/runs/{}/events/tail?after={}&wait={}",
+                        run_id.0,
+                        after,
+                        wait.as_secs()
+                    );
+
+                    match client.http.get::<TailRunEventsResponse>(&path).await {
+                        Ok(response) => {
+                            after = response.next_after;
+                            pending.extend(response.events);
+                        }
+                        Err(e) => return Some((Err(e), None)),
+                    }
+                }
+            },
+        )
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ListRunsResponse {
-    runs: Vec<Run>,
+struct RunEtaResponse {
+    estimated_completion: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct GetRunEventsResponse {
     events: Vec<Event>,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GetRunLogsResponse {
+    logs: Vec<StepLog>,
+}
+
+/// Response from signalling a run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalRunResponse {
+    pub resumed_steps: Vec<StepId>,
+    pub message: String,
+    /// The signalled run
+    pub run: Run,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TailRunEventsResponse {
+    events: Vec<Event>,
+    next_after: usize,
+}
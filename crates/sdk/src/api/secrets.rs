@@ -18,8 +18,7 @@ impl<'a> SecretsApi<'a> {
 
     /// List all secrets (without values).
     pub async fn list(&self) -> ShiiooResult<Vec<Secret>> {
-        let response: ListSecretsResponse = self.client.http.get("/api/secrets").await?;
-        Ok(response.secrets)
+        self.client.http.get_all_pages("/api/secrets", &()).await
     }
 
     /// Get a specific secret (metadata only).
@@ -92,17 +91,10 @@ impl<'a> SecretsApi<'a> {
 
     /// Get secrets needing rotation.
     pub async fn needing_rotation(&self) -> ShiiooResult<Vec<Secret>> {
-        let response: ListSecretsResponse =
-            self.client.http.get("/api/secrets/rotation/needed").await?;
-        Ok(response.secrets)
+        self.client.http.get_all_pages("/api/secrets/rotation/needed", &()).await
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ListSecretsResponse {
-    secrets: Vec<Secret>,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 struct SecretValueResponse {
     value: String,
@@ -124,6 +116,9 @@ pub struct CreateSecretRequest {
     pub rotation_policy: Option<RotationPolicy>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<HashMap<String, String>>,
+    /// Store the value even if it doesn't look like its `secret_type`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force: Option<bool>,
 }
 
 /// Request to update secret metadata.
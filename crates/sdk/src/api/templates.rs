@@ -3,6 +3,7 @@
 use crate::client::ShiiooClient;
 use crate::error::ShiiooResult;
 use serde::{Deserialize, Serialize};
+use shiioo_core::template::{MissingDependencies, TemplateCategory};
 use shiioo_core::types::{ProcessTemplate, TemplateId, TemplateInstance, WorkflowSpec};
 
 /// Templates API for managing process templates.
@@ -17,8 +18,22 @@ impl<'a> TemplatesApi<'a> {
 
     /// List all templates.
     pub async fn list(&self) -> ShiiooResult<Vec<ProcessTemplate>> {
-        let response: ListTemplatesResponse = self.client.http.get("/api/templates").await?;
-        Ok(response.templates)
+        self.client.http.get_all_pages("/api/templates", &()).await
+    }
+
+    /// List templates matching a category and/or search filter.
+    pub async fn list_with_filter(
+        &self,
+        filter: TemplateFilter,
+    ) -> ShiiooResult<Vec<ProcessTemplate>> {
+        self.client.http.get_all_pages("/api/templates", &filter).await
+    }
+
+    /// List distinct template categories with counts.
+    pub async fn categories(&self) -> ShiiooResult<Vec<TemplateCategory>> {
+        let response: ListTemplateCategoriesResponse =
+            self.client.http.get("/api/templates/categories").await?;
+        Ok(response.categories)
     }
 
     /// Get a specific template by ID.
@@ -56,8 +71,18 @@ impl<'a> TemplatesApi<'a> {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ListTemplatesResponse {
-    templates: Vec<ProcessTemplate>,
+struct ListTemplateCategoriesResponse {
+    categories: Vec<TemplateCategory>,
+}
+
+/// Filter for listing templates.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TemplateFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Matched against template name and description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
 }
 
 /// Response from creating a template.
@@ -77,5 +102,8 @@ pub struct DeleteTemplateResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstantiateTemplateResponse {
     pub workflow: WorkflowSpec,
+    /// Roles the workflow needs that don't exist yet
+    #[serde(default)]
+    pub missing_dependencies: MissingDependencies,
     pub message: String,
 }
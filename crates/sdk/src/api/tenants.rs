@@ -18,8 +18,7 @@ impl<'a> TenantsApi<'a> {
 
     /// List all tenants.
     pub async fn list(&self) -> ShiiooResult<Vec<Tenant>> {
-        let response: ListTenantsResponse = self.client.http.get("/api/tenants").await?;
-        Ok(response.tenants)
+        self.client.http.get_all_pages("/api/tenants", &()).await
     }
 
     /// Get a specific tenant.
@@ -80,11 +79,6 @@ impl<'a> TenantsApi<'a> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ListTenantsResponse {
-    tenants: Vec<Tenant>,
-}
-
 /// Request to register a new tenant.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegisterTenantRequest {
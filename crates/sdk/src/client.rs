@@ -1,9 +1,12 @@
 //! Main client for the Shiioo SDK.
 
+use crate::api::health::ServerDescription;
 use crate::api::*;
-use crate::config::{ClientConfig, RetryConfig};
+use crate::config::{CircuitBreakerConfig, ClientConfig, PemSource, RetryConfig, TlsConfig};
 use crate::error::{ShiiooError, ShiiooResult};
+use shiioo_core::codec::Codec;
 use crate::transport::{HttpTransport, WebSocketClient};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
@@ -21,6 +24,12 @@ impl ShiiooClient {
         ShiiooClientBuilder::new()
     }
 
+    /// Create a client configured from `SHIIOO_*` environment variables.
+    /// See [`ShiiooClientBuilder::from_env`].
+    pub fn from_env() -> ShiiooResult<Self> {
+        ShiiooClientBuilder::from_env()?.build()
+    }
+
     /// Create a client from configuration.
     fn from_config(config: ClientConfig) -> ShiiooResult<Self> {
         let config = Arc::new(config);
@@ -44,6 +53,11 @@ impl ShiiooClient {
         JobsApi::new(self)
     }
 
+    /// Get the MCP tool registry API.
+    pub fn mcp(&self) -> McpApi<'_> {
+        McpApi::new(self)
+    }
+
     /// Get the roles API.
     pub fn roles(&self) -> RolesApi<'_> {
         RolesApi::new(self)
@@ -134,8 +148,19 @@ impl ShiiooClient {
         SecurityApi::new(self)
     }
 
+    /// Describe the server's version and capabilities.
+    ///
+    /// Use this to gate behavior on features that older servers lack.
+    pub async fn describe(&self) -> ShiiooResult<ServerDescription> {
+        self.http.get("/api/describe").await
+    }
+
     /// Create a WebSocket subscription client.
     pub async fn subscribe(&self) -> ShiiooResult<WebSocketClient> {
+        #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+        let mut ws =
+            WebSocketClient::with_tls(self.config.clone(), self.http.tls_connector().clone());
+        #[cfg(not(any(feature = "rustls-tls", feature = "native-tls")))]
         let mut ws = WebSocketClient::new(self.config.clone());
         ws.connect().await?;
         Ok(ws)
@@ -149,6 +174,10 @@ pub struct ShiiooClientBuilder {
     timeout: Duration,
     retry_config: RetryConfig,
     tenant_id: Option<String>,
+    circuit_breaker: CircuitBreakerConfig,
+    codec: Codec,
+    sign_requests: bool,
+    tls: TlsConfig,
 }
 
 impl ShiiooClientBuilder {
@@ -160,9 +189,87 @@ impl ShiiooClientBuilder {
             timeout: Duration::from_secs(30),
             retry_config: RetryConfig::default(),
             tenant_id: None,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            codec: Codec::default(),
+            sign_requests: false,
+            tls: TlsConfig::default(),
         }
     }
 
+    /// Create a builder configured from environment variables, for further tweaking
+    /// before `build()`:
+    ///
+    /// | Variable | Default |
+    /// |----------|---------|
+    /// | `SHIIOO_BASE_URL` | required |
+    /// | `SHIIOO_API_KEY` | none |
+    /// | `SHIIOO_TENANT_ID` | none |
+    /// | `SHIIOO_TIMEOUT` | `30` (seconds) |
+    /// | `SHIIOO_MAX_RETRIES` | `3` |
+    /// | `SHIIOO_RETRY_INITIAL_BACKOFF_MS` | `100` |
+    /// | `SHIIOO_RETRY_MAX_BACKOFF_MS` | `10000` |
+    /// | `SHIIOO_SIGN_REQUESTS` | `false` |
+    pub fn from_env() -> ShiiooResult<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Configure a builder from variables looked up by name
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> ShiiooResult<Self> {
+        let var = |name: &str| var(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let parse = |name: &str, expected: &str| -> ShiiooResult<Option<u64>> {
+            var(name)
+                .map(|value| {
+                    value.parse().map_err(|_| {
+                        ShiiooError::Config(format!(
+                            "Invalid {} '{}': expected {}",
+                            name, value, expected
+                        ))
+                    })
+                })
+                .transpose()
+        };
+
+        let base_url = var("SHIIOO_BASE_URL").ok_or_else(|| {
+            ShiiooError::Config("SHIIOO_BASE_URL environment variable is required".to_string())
+        })?;
+        let mut builder = Self::new().base_url(base_url);
+
+        if let Some(api_key) = var("SHIIOO_API_KEY") {
+            builder = builder.api_key(api_key);
+        }
+        if let Some(tenant_id) = var("SHIIOO_TENANT_ID") {
+            builder = builder.tenant_id(tenant_id);
+        }
+        if let Some(secs) = parse("SHIIOO_TIMEOUT", "a number of seconds")? {
+            builder.timeout = Duration::from_secs(secs);
+        }
+        if let Some(max_retries) = parse("SHIIOO_MAX_RETRIES", "a number of retries")? {
+            builder.retry_config.max_retries = u32::try_from(max_retries).map_err(|_| {
+                ShiiooError::Config(format!("SHIIOO_MAX_RETRIES {} is too large", max_retries))
+            })?;
+        }
+        if let Some(ms) = parse("SHIIOO_RETRY_INITIAL_BACKOFF_MS", "milliseconds")? {
+            builder.retry_config.initial_backoff = Duration::from_millis(ms);
+        }
+        if let Some(ms) = parse("SHIIOO_RETRY_MAX_BACKOFF_MS", "milliseconds")? {
+            builder.retry_config.max_backoff = Duration::from_millis(ms);
+        }
+        if let Some(value) = var("SHIIOO_SIGN_REQUESTS") {
+            builder.sign_requests = match value.to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    return Err(ShiiooError::Config(format!(
+                        "Invalid SHIIOO_SIGN_REQUESTS '{}': expected true or false",
+                        value
+                    )))
+                }
+            };
+        }
+
+        Ok(builder)
+    }
+
     /// Set the base URL of the Shiioo server.
     pub fn base_url(mut self, url: impl Into<String>) -> Self {
         self.base_url = Some(url.into());
@@ -187,6 +294,57 @@ impl ShiiooClientBuilder {
         self
     }
 
+    /// Set the circuit breaker configuration.
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = config;
+        self
+    }
+
+    /// Set the encoding for request and response bodies. MessagePack requires the
+    /// `msgpack` feature on both the SDK and the server.
+    pub fn codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Sign mutating requests with a timestamp and single-use nonce. Required by
+    /// servers configured with `require_signed_requests`; needs an API key.
+    pub fn sign_requests(mut self, enabled: bool) -> Self {
+        self.sign_requests = enabled;
+        self
+    }
+
+    /// Trust an additional root certificate (PEM), e.g. an internal CA.
+    pub fn add_root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls.root_certificates.push(PemSource::Bytes(pem.into()));
+        self
+    }
+
+    /// Trust the root certificates in a PEM file, read when the client is built.
+    pub fn add_root_certificate_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.tls.root_certificates.push(PemSource::File(path.into()));
+        self
+    }
+
+    /// Present a client certificate for mutual TLS.
+    pub fn client_identity(mut self, certificate: PemSource, key: PemSource) -> Self {
+        self.tls.client_identity = Some((certificate, key));
+        self
+    }
+
+    /// Trust only the root certificates added to this builder, not the built-in ones.
+    pub fn custom_roots_only(mut self, enabled: bool) -> Self {
+        self.tls.custom_roots_only = enabled;
+        self
+    }
+
+    /// Accept only a server certificate with this SHA-256 fingerprint (hex), or one of
+    /// the others pinned. Needs the `rustls-tls` feature.
+    pub fn pin_certificate(mut self, sha256_fingerprint: impl Into<String>) -> Self {
+        self.tls.pinned_certificates.push(sha256_fingerprint.into());
+        self
+    }
+
     /// Set the tenant ID for multi-tenant operations.
     pub fn tenant_id(mut self, id: impl Into<String>) -> Self {
         self.tenant_id = Some(id.into());
@@ -207,6 +365,10 @@ impl ShiiooClientBuilder {
             timeout: self.timeout,
             retry_config: self.retry_config,
             tenant_id: self.tenant_id,
+            circuit_breaker: self.circuit_breaker,
+            codec: self.codec,
+            sign_requests: self.sign_requests,
+            tls: self.tls,
         };
 
         ShiiooClient::from_config(config)
@@ -248,6 +410,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_from_env() {
+        // The only test touching these variables, so it can't race with another
+        std::env::set_var("SHIIOO_BASE_URL", "https://shiioo.example.com");
+        std::env::set_var("SHIIOO_API_KEY", "sk-env-key");
+        std::env::set_var("SHIIOO_TIMEOUT", "5");
+        std::env::set_var("SHIIOO_MAX_RETRIES", "7");
+        std::env::set_var("SHIIOO_SIGN_REQUESTS", "true");
+
+        let client = ShiiooClient::from_env().unwrap();
+        assert_eq!(client.config.base_url.as_str(), "https://shiioo.example.com/");
+        assert_eq!(client.config.api_key.as_deref(), Some("sk-env-key"));
+        assert_eq!(client.config.timeout, Duration::from_secs(5));
+        assert_eq!(client.config.retry_config.max_retries, 7);
+        assert!(client.config.sign_requests);
+        // Unset variables keep their defaults
+        assert!(client.config.tenant_id.is_none());
+        assert_eq!(client.config.retry_config.initial_backoff, Duration::from_millis(100));
+
+        std::env::set_var("SHIIOO_TIMEOUT", "soon");
+        match ShiiooClient::from_env() {
+            Err(ShiiooError::Config(msg)) => assert!(msg.contains("SHIIOO_TIMEOUT")),
+            _ => panic!("Expected Config error"),
+        }
+
+        for name in [
+            "SHIIOO_BASE_URL",
+            "SHIIOO_API_KEY",
+            "SHIIOO_TIMEOUT",
+            "SHIIOO_MAX_RETRIES",
+            "SHIIOO_SIGN_REQUESTS",
+        ] {
+            std::env::remove_var(name);
+        }
+        match ShiiooClient::from_env() {
+            Err(ShiiooError::Config(msg)) => assert!(msg.contains("SHIIOO_BASE_URL")),
+            _ => panic!("Expected Config error"),
+        }
+    }
+
     #[test]
     fn test_builder_invalid_url() {
         let result = ShiiooClient::builder()
@@ -314,4 +516,179 @@ mod tests {
         let _ = client.roles();
         let _ = client.policies();
     }
+
+    #[tokio::test]
+    async fn test_describe() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/describe"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "service": "shiioo",
+                "version": "0.1.0",
+                "api_version": "v1",
+                "features": { "graphql": true, "mcp": true, "webhooks": false },
+                "compliance_frameworks": ["SOC2", "GDPR"],
+            })))
+            .mount(&server)
+            .await;
+
+        let client = ShiiooClient::builder()
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+
+        let description = client.describe().await.unwrap();
+        assert_eq!(description.api_version, "v1");
+        assert!(description.features.graphql);
+        assert!(!description.features.api_key_auth);
+        assert!(description.supports_framework(shiioo_core::compliance::ComplianceFramework::GDPR));
+        assert!(!description.supports_framework(shiioo_core::compliance::ComplianceFramework::HIPAA));
+    }
+
+    #[tokio::test]
+    async fn test_tail_run_events() {
+        use futures_util::StreamExt;
+        use shiioo_core::events::{Event, EventType};
+        use shiioo_core::types::RunId;
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let run_id = RunId::new();
+        let tail_path = format!("/api/runs/{}/events/tail", run_id.0);
+
+        let first = Event::new(run_id, EventType::RunCancelled { reason: "a".to_string() });
+        let second = Event::new(run_id, EventType::RunCancelled { reason: "b".to_string() });
+
+        Mock::given(method("GET"))
+            .and(path(tail_path.clone()))
+            .and(query_param("after", "0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "events": [first],
+                "next_after": 1,
+            })))
+            .mount(&server)
+            .await;
+
+        // An empty poll (timeout) is followed by the next event
+        Mock::given(method("GET"))
+            .and(path(tail_path.clone()))
+            .and(query_param("after", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "events": [],
+                "next_after": 1,
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(tail_path))
+            .and(query_param("after", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "events": [second],
+                "next_after": 2,
+            })))
+            .mount(&server)
+            .await;
+
+        let client = ShiiooClient::builder()
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+
+        let runs = client.runs();
+        let events: Vec<Event> = runs
+            .tail(run_id, Duration::from_secs(1))
+            .take(2)
+            .map(|e| e.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(events[0].id, first.id);
+        assert_eq!(events[1].id, second.id);
+    }
+
+    #[tokio::test]
+    async fn test_stream_metrics_rates() {
+        use futures_util::StreamExt;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let metrics = |captured: &str, jobs: u64, updated: &str, queue_depth: f64| {
+            serde_json::json!({
+                "captured_at": captured,
+                "counters": [{
+                    "name": "jobs_total",
+                    "value": jobs,
+                    "labels": { "status": "completed" },
+                    "last_updated": updated,
+                }],
+                "gauges": [{
+                    "name": "queue_depth",
+                    "value": queue_depth,
+                    "labels": {},
+                    "last_updated": updated,
+                }],
+                "histograms": [],
+            })
+        };
+
+        Mock::given(method("GET"))
+            .and(path("/api/metrics"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(metrics(
+                    "2026-01-01T00:00:00Z",
+                    100,
+                    "2025-12-31T23:59:57Z",
+                    4.0,
+                )),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+
+        // Ten seconds later, 50 more jobs have completed. The rate spans the snapshots,
+        // not the counter's last updates, which are only five seconds apart.
+        Mock::given(method("GET"))
+            .and(path("/api/metrics"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(metrics(
+                    "2026-01-01T00:00:10Z",
+                    150,
+                    "2026-01-01T00:00:02Z",
+                    1.0,
+                )),
+            )
+            .mount(&server)
+            .await;
+
+        let client = ShiiooClient::builder()
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+
+        let metrics_api = client.metrics();
+        let deltas: Vec<_> = metrics_api
+            .stream(Duration::from_millis(10))
+            .take(2)
+            .map(|d| d.unwrap())
+            .collect()
+            .await;
+
+        let jobs = &deltas[0].counters[0];
+        assert_eq!(jobs.name, "jobs_total");
+        assert_eq!(jobs.increase, 50);
+        assert_eq!(jobs.per_second, 5.0);
+        assert_eq!(deltas[0].gauges[0].change, -3.0);
+
+        // Nothing changed between the last two polls
+        assert_eq!(deltas[1].counters[0].increase, 0);
+        assert_eq!(deltas[1].counters[0].per_second, 0.0);
+    }
 }
@@ -1,5 +1,7 @@
 //! Configuration types for the Shiioo SDK.
 
+use shiioo_core::codec::Codec;
+use std::path::PathBuf;
 use std::time::Duration;
 use url::Url;
 
@@ -16,6 +18,14 @@ pub struct ClientConfig {
     pub retry_config: RetryConfig,
     /// Tenant ID for multi-tenant operations.
     pub tenant_id: Option<String>,
+    /// Circuit breaker configuration.
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Encoding for request and response bodies.
+    pub codec: Codec,
+    /// Sign mutating requests with the API key so the server can reject replays.
+    pub sign_requests: bool,
+    /// TLS trust roots and client certificate.
+    pub tls: TlsConfig,
 }
 
 impl ClientConfig {
@@ -27,10 +37,60 @@ impl ClientConfig {
             timeout: Duration::from_secs(30),
             retry_config: RetryConfig::default(),
             tenant_id: None,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            codec: Codec::default(),
+            sign_requests: false,
+            tls: TlsConfig::default(),
         }
     }
 }
 
+/// PEM data, given inline or read from a file when the client is built.
+#[derive(Debug, Clone)]
+pub enum PemSource {
+    Bytes(Vec<u8>),
+    File(PathBuf),
+}
+
+impl PemSource {
+    /// Read the PEM data; `what` names it in the error.
+    #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+    pub(crate) fn load(&self, what: &str) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes.clone()),
+            Self::File(path) => std::fs::read(path)
+                .map_err(|e| format!("Failed to read {} {}: {}", what, path.display(), e)),
+        }
+    }
+}
+
+/// TLS configuration for servers behind an internal CA or requiring mutual TLS.
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    /// Extra root certificates to trust, each a PEM bundle of one or more certificates.
+    pub root_certificates: Vec<PemSource>,
+    /// Client certificate chain and private key (PKCS#8, RSA or SEC1 PEM) for mutual TLS.
+    pub client_identity: Option<(PemSource, PemSource)>,
+    /// Trust only `root_certificates`, ignoring the built-in roots, so the client only
+    /// connects to servers whose chain ends in one of them.
+    pub custom_roots_only: bool,
+    /// SHA-256 fingerprints (hex, colons optional) of the server certificates to accept.
+    /// When set, the server's own certificate must match one of them as well as chain to
+    /// a trusted root. Needs the `rustls-tls` feature.
+    pub pinned_certificates: Vec<String>,
+}
+
+impl TlsConfig {
+    /// Whether any option differs from the platform defaults
+    #[cfg(not(any(feature = "rustls-tls", feature = "native-tls")))]
+    pub(crate) fn is_customized(&self) -> bool {
+        !self.root_certificates.is_empty()
+            || self.client_identity.is_some()
+            || self.custom_roots_only
+            || !self.pinned_certificates.is_empty()
+    }
+}
+
 /// Configuration for retry behavior.
 #[derive(Debug, Clone)]
 pub struct RetryConfig {
@@ -81,6 +141,35 @@ impl RetryConfig {
     }
 }
 
+/// Configuration for the client-side circuit breaker.
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed calls (after retries) before the breaker opens.
+    /// Zero disables the breaker.
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before allowing a trial call.
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Create a configuration with the circuit breaker disabled.
+    pub fn disabled() -> Self {
+        Self {
+            failure_threshold: 0,
+            ..Default::default()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
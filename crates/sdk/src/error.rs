@@ -28,6 +28,10 @@ pub enum ShiiooError {
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// MessagePack encoding or decoding error.
+    #[error("MessagePack error: {0}")]
+    MessagePack(String),
+
     /// WebSocket error.
     #[error("WebSocket error: {0}")]
     WebSocket(String),
@@ -55,6 +59,10 @@ pub enum ShiiooError {
     /// URL parsing error.
     #[error("Invalid URL: {0}")]
     InvalidUrl(#[from] url::ParseError),
+
+    /// The circuit breaker is open after repeated failures; the call was not attempted.
+    #[error("Circuit breaker open, retry after {retry_after:?}")]
+    CircuitOpen { retry_after: std::time::Duration },
 }
 
 impl ShiiooError {
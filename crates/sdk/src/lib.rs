@@ -61,7 +61,7 @@ pub mod transport;
 
 // Re-export main client
 pub use client::{ShiiooClient, ShiiooClientBuilder};
-pub use config::{ClientConfig, RetryConfig};
+pub use config::{CircuitBreakerConfig, ClientConfig, PemSource, RetryConfig, TlsConfig};
 pub use error::{ShiiooError, ShiiooResult};
 
 // Re-export core types for convenience
@@ -76,7 +76,7 @@ pub use shiioo_core::{
     // Workflow types
     types::{
         Job, RetryPolicy, Routine, RoutineExecution, RoutineSchedule, Run, StepAction,
-        StepExecution, StepSpec, WorkflowSpec,
+        StepExecution, StepSpec, WorkflowInput, WorkflowSpec,
     },
     // Role & Policy
     types::{PolicyRule, PolicySpec, RoleBudgets, RoleSpec},
@@ -92,14 +92,20 @@ pub use shiioo_core::{
     types::{ProcessTemplate, TemplateInstance, TemplateParameter, TemplateParameterType},
 };
 
+// Re-export bulk approval types
+pub use shiioo_core::approval::{BulkVote, BulkVoteResult};
+
 // Re-export events
 pub use shiioo_core::events::{Event, EventType};
 
 // Re-export analytics types
-pub use shiioo_core::analytics::{BottleneckReport, ExecutionTrace, StepStats, WorkflowStats};
+pub use shiioo_core::analytics::{
+    BottleneckReport, CostBucket, CostGrouping, ExecutionTrace, MonthlyCostProjection, StepStats,
+    WorkflowStats,
+};
 
 // Re-export metrics types
-pub use shiioo_core::metrics::{Counter, Gauge, Histogram};
+pub use shiioo_core::metrics::{Counter, Gauge, Histogram, MetricKind, MetricPoint, MetricSeries};
 
 // Re-export tenant types
 pub use shiioo_core::tenant::{Tenant, TenantId, TenantQuota, TenantSettings, TenantStatus};
@@ -116,7 +122,9 @@ pub use shiioo_core::audit::{
 };
 
 // Re-export RBAC types
-pub use shiioo_core::rbac::{Action, Permission, RbacRole, RbacUser, Resource};
+pub use shiioo_core::rbac::{
+    Action, Permission, RbacRole, RbacUser, Resource, ResourceSelector, ResourceTarget,
+};
 
 // Re-export compliance types
 pub use shiioo_core::compliance::{
@@ -1,3 +1,5 @@
 //! Real-time subscription streams.
 
-pub use crate::transport::websocket::{SubscriptionEvent, WebSocketClient, WsRequest};
+pub use crate::transport::websocket::{
+    SequencedEvent, SubscriptionEvent, SubscriptionTopic, WebSocketClient, WsRequest,
+};
@@ -0,0 +1,233 @@
+//! Client-side circuit breaker for the HTTP transport.
+
+use crate::config::CircuitBreakerConfig;
+use crate::error::{ShiiooError, ShiiooResult};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Current state of the circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls pass through; consecutive failures are counted.
+    Closed,
+    /// Calls fail fast until the cooldown elapses.
+    Open,
+    /// A single trial call is in flight to test recovery.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+/// Circuit breaker shared by all clones of an `HttpTransport`.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed circuit breaker.
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Arc::new(Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+            })),
+        }
+    }
+
+    /// Get the current state.
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Check whether a call may proceed, moving an open breaker to half-open once the
+    /// cooldown has elapsed. The returned permit is settled with the call's outcome.
+    pub fn acquire(&self) -> ShiiooResult<CallPermit> {
+        let permit = |trial| CallPermit {
+            breaker: self.clone(),
+            trial,
+        };
+        if self.config.failure_threshold == 0 {
+            return Ok(permit(false));
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            CircuitState::Closed => Ok(permit(false)),
+            CircuitState::HalfOpen => Err(ShiiooError::CircuitOpen {
+                retry_after: Duration::ZERO,
+            }),
+            CircuitState::Open => {
+                let elapsed = inner.opened_at.map(|t| t.elapsed()).unwrap_or_default();
+                if elapsed >= self.config.cooldown {
+                    info!("Circuit breaker half-open, sending trial request");
+                    inner.state = CircuitState::HalfOpen;
+                    Ok(permit(true))
+                } else {
+                    Err(ShiiooError::CircuitOpen {
+                        retry_after: self.config.cooldown - elapsed,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Record a successful call, closing the breaker.
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != CircuitState::Closed {
+            info!("Circuit breaker closed");
+        }
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+    }
+
+    /// Record a failed call, opening the breaker once the threshold is reached or
+    /// re-opening it if the half-open trial failed.
+    pub fn record_failure(&self) {
+        if self.config.failure_threshold == 0 {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures += 1;
+
+        if inner.state == CircuitState::HalfOpen
+            || inner.consecutive_failures >= self.config.failure_threshold
+        {
+            if inner.state != CircuitState::Open {
+                warn!(
+                    failures = inner.consecutive_failures,
+                    cooldown_ms = self.config.cooldown.as_millis(),
+                    "Circuit breaker opened"
+                );
+            }
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Whether an error indicates the server is unhealthy, as opposed to a bad request.
+    pub fn is_failure(error: &ShiiooError) -> bool {
+        match error {
+            ShiiooError::Http(_) | ShiiooError::Timeout => true,
+            ShiiooError::Api { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+/// Permission for one call through a `CircuitBreaker`, to be settled with the call's
+/// outcome. A half-open trial permit dropped unsettled, e.g. because the call was
+/// cancelled, returns the breaker to open so a later call can make the trial instead.
+#[derive(Debug)]
+#[must_use = "settle the permit with the call's outcome"]
+pub struct CallPermit {
+    breaker: CircuitBreaker,
+    trial: bool,
+}
+
+impl CallPermit {
+    /// Record that the call succeeded.
+    pub fn record_success(mut self) {
+        self.trial = false;
+        self.breaker.record_success();
+    }
+
+    /// Record that the call failed.
+    pub fn record_failure(mut self) {
+        self.trial = false;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for CallPermit {
+    fn drop(&mut self) {
+        if !self.trial {
+            return;
+        }
+        if let Ok(mut inner) = self.breaker.inner.lock() {
+            if inner.state == CircuitState::HalfOpen {
+                inner.state = CircuitState::Open;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(threshold: u32, cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: threshold,
+            cooldown,
+        })
+    }
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = breaker(2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.acquire().is_ok());
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(matches!(
+            breaker.acquire(),
+            Err(ShiiooError::CircuitOpen { .. })
+        ));
+    }
+
+    #[test]
+    fn test_failed_trial_reopens() {
+        let breaker = breaker(1, Duration::ZERO);
+
+        breaker.record_failure();
+        let trial = breaker.acquire().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        // Only one trial call at a time
+        assert!(breaker.acquire().is_err());
+
+        trial.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn test_abandoned_trial_reopens() {
+        let breaker = breaker(1, Duration::ZERO);
+
+        breaker.record_failure();
+        let trial = breaker.acquire().unwrap();
+        drop(trial);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // Not wedged half-open: the next call gets to make the trial
+        breaker.acquire().unwrap().record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_disabled_breaker_never_opens() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig::disabled());
+
+        for _ in 0..10 {
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.acquire().is_ok());
+    }
+}
@@ -1,9 +1,15 @@
 //! HTTP transport layer for the Shiioo SDK.
 
+use super::circuit_breaker::{CircuitBreaker, CircuitState};
+#[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+use super::tls::TlsConnector;
 use crate::config::ClientConfig;
 use crate::error::{ShiiooError, ShiiooResult};
-use reqwest::{header, Client, RequestBuilder, Response};
+use reqwest::{header, Client, Method, Request, RequestBuilder, Response};
 use serde::{de::DeserializeOwned, Serialize};
+use shiioo_core::api_key::{sign_request, NONCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use shiioo_core::codec::Codec;
+use shiioo_core::query::{Paginated, MAX_PAGE_LIMIT};
 use std::sync::Arc;
 use tracing::{debug, warn};
 
@@ -12,6 +18,9 @@ use tracing::{debug, warn};
 pub struct HttpTransport {
     client: Client,
     config: Arc<ClientConfig>,
+    circuit_breaker: CircuitBreaker,
+    #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+    tls: TlsConnector,
 }
 
 impl HttpTransport {
@@ -37,12 +46,49 @@ impl HttpTransport {
             );
         }
 
-        let client = Client::builder()
+        // Ask for responses in the configured codec
+        headers.insert(
+            header::ACCEPT,
+            header::HeaderValue::from_static(config.codec.content_type()),
+        );
+
+        let builder = Client::builder()
             .timeout(config.timeout)
-            .default_headers(headers)
-            .build()?;
+            .default_headers(headers);
+        #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+        let tls = TlsConnector::new(&config.tls)?;
+        #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+        let builder = tls.configure(builder);
+        #[cfg(not(any(feature = "rustls-tls", feature = "native-tls")))]
+        if config.tls.is_customized() {
+            return Err(ShiiooError::Config(
+                "TLS options need the rustls-tls or native-tls feature".to_string(),
+            ));
+        }
+        let client = builder
+            .build()
+            .map_err(|e| ShiiooError::Config(format!("Failed to build HTTP client: {}", e)))?;
+
+        let circuit_breaker = CircuitBreaker::new(config.circuit_breaker.clone());
+
+        Ok(Self {
+            client,
+            config,
+            circuit_breaker,
+            #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+            tls,
+        })
+    }
 
-        Ok(Self { client, config })
+    /// TLS configuration the client connects with, for WebSocket connections to share.
+    #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+    pub(crate) fn tls_connector(&self) -> &TlsConnector {
+        &self.tls
+    }
+
+    /// Get the current circuit breaker state.
+    pub fn circuit_state(&self) -> CircuitState {
+        self.circuit_breaker.state()
     }
 
     /// Build a URL for the given path.
@@ -53,17 +99,108 @@ impl HttpTransport {
             .map_err(|e| ShiiooError::InvalidUrl(e))
     }
 
-    /// Execute a request with retries.
+    /// Attach a request body encoded with the configured codec.
+    fn with_body<B: Serialize>(
+        &self,
+        request_builder: RequestBuilder,
+        body: &B,
+    ) -> ShiiooResult<RequestBuilder> {
+        match self.config.codec {
+            Codec::Json => Ok(request_builder.json(body)),
+            #[cfg(feature = "msgpack")]
+            codec @ Codec::MessagePack => {
+                let bytes = codec
+                    .encode(body)
+                    .map_err(|e| ShiiooError::MessagePack(e.to_string()))?;
+                Ok(request_builder
+                    .header(header::CONTENT_TYPE, codec.content_type())
+                    .body(bytes))
+            }
+        }
+    }
+
+    /// Decode a response body according to its `Content-Type`.
+    async fn read_body<T: DeserializeOwned>(response: Response) -> ShiiooResult<T> {
+        #[cfg(feature = "msgpack")]
+        {
+            let codec = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(Codec::from_content_type);
+            if codec == Some(Codec::MessagePack) {
+                let bytes = response.bytes().await?;
+                return shiioo_core::codec::msgpack::from_slice(&bytes)
+                    .map_err(|e| ShiiooError::MessagePack(e.to_string()));
+            }
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Add signature headers to a mutating request when signing is enabled. Each
+    /// attempt gets a fresh nonce, so retries aren't mistaken for replays.
+    fn sign(&self, mut request: Request) -> ShiiooResult<Request> {
+        let api_key = match &self.config.api_key {
+            Some(api_key) if self.config.sign_requests => api_key,
+            _ => return Ok(request),
+        };
+        if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+            return Ok(request);
+        }
+
+        let path = match request.url().query() {
+            Some(query) => format!("{}?{}", request.url().path(), query),
+            None => request.url().path().to_string(),
+        };
+        let body = request.body().and_then(|b| b.as_bytes()).unwrap_or_default();
+        let timestamp = chrono::Utc::now().timestamp();
+        let nonce = uuid::Uuid::new_v4().to_string();
+        let signature =
+            sign_request(api_key, request.method().as_str(), &path, body, timestamp, &nonce);
+
+        let headers = request.headers_mut();
+        for (name, value) in [
+            (TIMESTAMP_HEADER, timestamp.to_string()),
+            (NONCE_HEADER, nonce),
+            (SIGNATURE_HEADER, signature),
+        ] {
+            let invalid = || ShiiooError::Config(format!("Invalid {} header", name));
+            headers.insert(
+                header::HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?,
+                header::HeaderValue::from_str(&value).map_err(|_| invalid())?,
+            );
+        }
+
+        Ok(request)
+    }
+
+    /// Execute a request with retries, guarded by the circuit breaker.
     async fn execute_with_retry(&self, request_builder: RequestBuilder) -> ShiiooResult<Response> {
+        let permit = self.circuit_breaker.acquire()?;
+
+        let result = self.send_with_retry(request_builder).await;
+
+        match &result {
+            Err(e) if CircuitBreaker::is_failure(e) => permit.record_failure(),
+            _ => permit.record_success(),
+        }
+
+        result
+    }
+
+    /// Send a request, retrying on retryable statuses and timeouts.
+    async fn send_with_retry(&self, request_builder: RequestBuilder) -> ShiiooResult<Response> {
         let retry_config = &self.config.retry_config;
         let mut attempts = 0;
 
         loop {
             let request = request_builder
                 .try_clone()
-                .ok_or_else(|| ShiiooError::Config("Request cannot be cloned".to_string()))?;
+                .ok_or_else(|| ShiiooError::Config("Request cannot be cloned".to_string()))?
+                .build()?;
 
-            match request.send().await {
+            match self.client.execute(self.sign(request)?).await {
                 Ok(response) => {
                     let status = response.status().as_u16();
 
@@ -115,8 +252,7 @@ impl HttpTransport {
         debug!(url = %url, "GET request");
 
         let response = self.execute_with_retry(self.client.get(url)).await?;
-        let body = response.json().await?;
-        Ok(body)
+        Self::read_body(response).await
     }
 
     /// Execute a GET request with query parameters.
@@ -131,8 +267,48 @@ impl HttpTransport {
         let response = self
             .execute_with_retry(self.client.get(url).query(query))
             .await?;
-        let body = response.json().await?;
-        Ok(body)
+        Self::read_body(response).await
+    }
+
+    /// Fetch every page of a paginated list, following `next_cursor`, or the offset
+    /// when the server offers no cursor.
+    pub async fn get_all_pages<T: DeserializeOwned, Q: Serialize>(
+        &self,
+        path: &str,
+        query: &Q,
+    ) -> ShiiooResult<Vec<T>> {
+        #[derive(Serialize)]
+        struct PageParams<'a> {
+            limit: usize,
+            offset: usize,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            cursor: Option<&'a str>,
+        }
+
+        let mut items = Vec::new();
+        let mut offset = 0;
+        let mut cursor = None;
+        loop {
+            let url = self.build_url(path)?;
+            debug!(url = %url, offset, "GET page");
+
+            let params = PageParams {
+                limit: MAX_PAGE_LIMIT,
+                offset,
+                cursor: cursor.as_deref(),
+            };
+            let request = self.client.get(url).query(query).query(&params);
+            let page: Paginated<T> =
+                Self::read_body(self.execute_with_retry(request).await?).await?;
+
+            let fetched = page.items.len();
+            items.extend(page.items);
+            if !page.has_more || fetched == 0 {
+                return Ok(items);
+            }
+            offset = page.offset + fetched;
+            cursor = page.next_cursor;
+        }
     }
 
     /// Execute a POST request.
@@ -141,10 +317,9 @@ impl HttpTransport {
         debug!(url = %url, "POST request");
 
         let response = self
-            .execute_with_retry(self.client.post(url).json(body))
+            .execute_with_retry(self.with_body(self.client.post(url), body)?)
             .await?;
-        let body = response.json().await?;
-        Ok(body)
+        Self::read_body(response).await
     }
 
     /// Execute a POST request without a response body.
@@ -152,7 +327,7 @@ impl HttpTransport {
         let url = self.build_url(path)?;
         debug!(url = %url, "POST request (no response)");
 
-        self.execute_with_retry(self.client.post(url).json(body))
+        self.execute_with_retry(self.with_body(self.client.post(url), body)?)
             .await?;
         Ok(())
     }
@@ -163,10 +338,9 @@ impl HttpTransport {
         debug!(url = %url, "PUT request");
 
         let response = self
-            .execute_with_retry(self.client.put(url).json(body))
+            .execute_with_retry(self.with_body(self.client.put(url), body)?)
             .await?;
-        let body = response.json().await?;
-        Ok(body)
+        Self::read_body(response).await
     }
 
     /// Execute a DELETE request.
@@ -175,8 +349,7 @@ impl HttpTransport {
         debug!(url = %url, "DELETE request");
 
         let response = self.execute_with_retry(self.client.delete(url)).await?;
-        let body = response.json().await?;
-        Ok(body)
+        Self::read_body(response).await
     }
 
     /// Execute a DELETE request without a response body.
@@ -192,7 +365,7 @@ impl HttpTransport {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::RetryConfig;
+    use crate::config::{CircuitBreakerConfig, RetryConfig};
     use serde::{Deserialize, Serialize};
     use std::time::Duration;
     use wiremock::matchers::{method, path, header};
@@ -216,6 +389,10 @@ mod tests {
             timeout: Duration::from_secs(30),
             retry_config: RetryConfig::no_retry(),
             tenant_id: None,
+            circuit_breaker: CircuitBreakerConfig::disabled(),
+            codec: Codec::default(),
+            sign_requests: false,
+            tls: Default::default(),
         })
     }
 
@@ -226,6 +403,10 @@ mod tests {
             timeout: Duration::from_secs(30),
             retry_config: RetryConfig::no_retry(),
             tenant_id: None,
+            circuit_breaker: CircuitBreakerConfig::disabled(),
+            codec: Codec::default(),
+            sign_requests: false,
+            tls: Default::default(),
         })
     }
 
@@ -236,6 +417,10 @@ mod tests {
             timeout: Duration::from_secs(30),
             retry_config: RetryConfig::no_retry(),
             tenant_id: Some(tenant_id.to_string()),
+            circuit_breaker: CircuitBreakerConfig::disabled(),
+            codec: Codec::default(),
+            sign_requests: false,
+            tls: Default::default(),
         })
     }
 
@@ -426,4 +611,343 @@ mod tests {
         let url = transport.build_url("api/test").unwrap();
         assert_eq!(url.as_str(), "http://localhost:8080/api/test");
     }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_trips_and_recovers() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/flaky"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/flaky"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(TestResponse {
+                message: "recovered".to_string(),
+                value: 1,
+            }))
+            .mount(&server)
+            .await;
+
+        let mut config = (*create_config(&server.uri())).clone();
+        config.circuit_breaker = CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown: Duration::from_millis(100),
+        };
+        let transport = HttpTransport::new(Arc::new(config)).unwrap();
+
+        for _ in 0..2 {
+            let result: ShiiooResult<TestResponse> = transport.get("/api/flaky").await;
+            assert!(matches!(result, Err(ShiiooError::Api { status: 503, .. })));
+        }
+        assert_eq!(transport.circuit_state(), CircuitState::Open);
+
+        // Fails fast without reaching the server
+        let result: ShiiooResult<TestResponse> = transport.get("/api/flaky").await;
+        assert!(matches!(result, Err(ShiiooError::CircuitOpen { .. })));
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        let result: TestResponse = transport.get("/api/flaky").await.unwrap();
+        assert_eq!(result.message, "recovered");
+        assert_eq!(transport.circuit_state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_signed_post_verifies() {
+        use shiioo_core::api_key::RequestVerifier;
+
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/create"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(TestResponse {
+                message: "created".to_string(),
+                value: 1,
+            }))
+            .mount(&server)
+            .await;
+
+        let mut config = (*create_config_with_auth(&server.uri(), "sk-test-key")).clone();
+        config.sign_requests = true;
+        let transport = HttpTransport::new(Arc::new(config)).unwrap();
+
+        let request = TestRequest {
+            name: "test".to_string(),
+        };
+        let _: TestResponse = transport.post("/api/create", &request).await.unwrap();
+        let _: TestResponse = transport.post("/api/create", &request).await.unwrap();
+
+        let verifier = RequestVerifier::new(60);
+        for received in server.received_requests().await.unwrap() {
+            let header = |name: &str| received.headers.get(name).unwrap().to_str().unwrap();
+            verifier
+                .verify(
+                    "sk-test-key",
+                    "POST",
+                    "/api/create",
+                    &received.body,
+                    header(TIMESTAMP_HEADER).parse().unwrap(),
+                    header(NONCE_HEADER),
+                    header(SIGNATURE_HEADER),
+                )
+                .unwrap();
+        }
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn test_msgpack_codec() {
+        use shiioo_core::codec::msgpack;
+
+        let server = MockServer::start().await;
+        let response = TestResponse {
+            message: "packed".to_string(),
+            value: 7,
+        };
+
+        Mock::given(method("POST"))
+            .and(path("/api/packed"))
+            .and(header("content-type", "application/msgpack"))
+            .and(header("accept", "application/msgpack"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(msgpack::to_vec(&response).unwrap(), "application/msgpack"),
+            )
+            .mount(&server)
+            .await;
+
+        let mut config = (*create_config(&server.uri())).clone();
+        config.codec = Codec::MessagePack;
+        let transport = HttpTransport::new(Arc::new(config)).unwrap();
+
+        let request = TestRequest {
+            name: "test".to_string(),
+        };
+        let result: TestResponse = transport.post("/api/packed", &request).await.unwrap();
+        assert_eq!(result, response);
+    }
+
+    #[tokio::test]
+    async fn test_get_all_pages_follows_cursor() {
+        use wiremock::matchers::query_param;
+
+        let mock_server = MockServer::start().await;
+        let page = |items: &[i32], offset: usize, cursor: Option<&str>| {
+            serde_json::json!({
+                "items": items
+                    .iter()
+                    .map(|v| serde_json::json!({ "message": "item", "value": v }))
+                    .collect::<Vec<_>>(),
+                "total": 3,
+                "offset": offset,
+                "limit": 2,
+                "has_more": cursor.is_some(),
+                "next_cursor": cursor,
+            })
+        };
+
+        Mock::given(method("GET"))
+            .and(path("/api/things"))
+            .and(query_param("cursor", "b"))
+            .and(query_param("tag", "x"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page(&[3], 2, None)))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/things"))
+            .and(query_param("offset", "0"))
+            .and(query_param("tag", "x"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page(&[1, 2], 0, Some("b"))))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let transport = HttpTransport::new(create_config(&mock_server.uri())).unwrap();
+        let items: Vec<TestResponse> = transport
+            .get_all_pages("/api/things", &[("tag", "x")])
+            .await
+            .unwrap();
+        let values: Vec<_> = items.iter().map(|i| i.value).collect();
+        assert_eq!(values, vec![1, 2, 3]);
+    }
+
+    #[cfg(feature = "rustls-tls")]
+    mod tls {
+        use super::*;
+        use crate::config::{PemSource, TlsConfig};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio_rustls::rustls::pki_types::pem::PemObject;
+        use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+        use tokio_rustls::rustls::server::WebPkiClientVerifier;
+        use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+
+        const CA: &[u8] = include_bytes!("../../testdata/tls/ca.pem");
+        const SERVER_CERT: &[u8] = include_bytes!("../../testdata/tls/server.pem");
+        const SERVER_KEY: &[u8] = include_bytes!("../../testdata/tls/server.key");
+        const CLIENT_CERT: &[u8] = include_bytes!("../../testdata/tls/client.pem");
+        const CLIENT_KEY: &[u8] = include_bytes!("../../testdata/tls/client.key");
+
+        /// Serve a fixed JSON body over TLS with the test server certificate, optionally
+        /// requiring a client certificate issued by the test CA. Returns the base URL.
+        async fn start_tls_server(require_client_cert: bool) -> String {
+            let certs = vec![CertificateDer::from_pem_slice(SERVER_CERT).unwrap()];
+            let key = PrivateKeyDer::from_pem_slice(SERVER_KEY).unwrap();
+            let builder = ServerConfig::builder();
+            let builder = if require_client_cert {
+                let mut roots = RootCertStore::empty();
+                roots.add(CertificateDer::from_pem_slice(CA).unwrap()).unwrap();
+                let verifier = WebPkiClientVerifier::builder(Arc::new(roots)).build().unwrap();
+                builder.with_client_cert_verifier(verifier)
+            } else {
+                builder.with_no_client_auth()
+            };
+            let config = builder.with_single_cert(certs, key).unwrap();
+            let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let acceptor = acceptor.clone();
+                    tokio::spawn(async move {
+                        // Failed handshakes are what the rejection tests expect
+                        let Ok(mut stream) = acceptor.accept(stream).await else {
+                            return;
+                        };
+                        let mut request = Vec::new();
+                        let mut buf = [0u8; 1024];
+                        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                            match stream.read(&mut buf).await {
+                                Ok(0) | Err(_) => return,
+                                Ok(n) => request.extend_from_slice(&buf[..n]),
+                            }
+                        }
+                        let body = r#"{"message":"secure","value":1}"#;
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+                             content-length: {}\r\nconnection: close\r\n\r\n{}",
+                            body.len(),
+                            body
+                        );
+                        let _ = stream.write_all(response.as_bytes()).await;
+                        let _ = stream.shutdown().await;
+                    });
+                }
+            });
+
+            format!("https://localhost:{}", port)
+        }
+
+        fn tls_config(base_url: &str, tls: TlsConfig) -> Arc<ClientConfig> {
+            let mut config = (*create_config(base_url)).clone();
+            config.tls = tls;
+            Arc::new(config)
+        }
+
+        fn trusting_ca() -> TlsConfig {
+            TlsConfig {
+                root_certificates: vec![PemSource::Bytes(CA.to_vec())],
+                custom_roots_only: true,
+                ..Default::default()
+            }
+        }
+
+        #[tokio::test]
+        async fn test_custom_ca_connects_while_default_rejects() {
+            let base_url = start_tls_server(false).await;
+
+            let transport = HttpTransport::new(tls_config(&base_url, trusting_ca())).unwrap();
+            let result: TestResponse = transport.get("/api/health").await.unwrap();
+            assert_eq!(result.message, "secure");
+
+            let transport = HttpTransport::new(create_config(&base_url)).unwrap();
+            let result: ShiiooResult<TestResponse> = transport.get("/api/health").await;
+            assert!(matches!(result, Err(ShiiooError::Http(_))), "{:?}", result);
+        }
+
+        #[tokio::test]
+        async fn test_mutual_tls_presents_client_certificate() {
+            let base_url = start_tls_server(true).await;
+
+            let transport = HttpTransport::new(tls_config(&base_url, trusting_ca())).unwrap();
+            let result: ShiiooResult<TestResponse> = transport.get("/api/health").await;
+            assert!(result.is_err());
+
+            let tls = TlsConfig {
+                client_identity: Some((
+                    PemSource::Bytes(CLIENT_CERT.to_vec()),
+                    PemSource::Bytes(CLIENT_KEY.to_vec()),
+                )),
+                ..trusting_ca()
+            };
+            let transport = HttpTransport::new(tls_config(&base_url, tls)).unwrap();
+            let result: TestResponse = transport.get("/api/health").await.unwrap();
+            assert_eq!(result.value, 1);
+        }
+
+        #[tokio::test]
+        async fn test_pinned_certificate_required() {
+            use sha2::{Digest, Sha256};
+
+            let base_url = start_tls_server(false).await;
+            let server_cert = CertificateDer::from_pem_slice(SERVER_CERT).unwrap();
+            let fingerprint = hex::encode(Sha256::digest(server_cert.as_ref()));
+
+            let tls = TlsConfig {
+                pinned_certificates: vec![fingerprint.to_uppercase()],
+                ..trusting_ca()
+            };
+            let transport = HttpTransport::new(tls_config(&base_url, tls)).unwrap();
+            let result: TestResponse = transport.get("/api/health").await.unwrap();
+            assert_eq!(result.message, "secure");
+
+            // A chain the roots trust is still refused when its certificate isn't pinned
+            let tls = TlsConfig {
+                pinned_certificates: vec!["ab".repeat(32)],
+                ..trusting_ca()
+            };
+            let transport = HttpTransport::new(tls_config(&base_url, tls)).unwrap();
+            let result: ShiiooResult<TestResponse> = transport.get("/api/health").await;
+            assert!(matches!(result, Err(ShiiooError::Http(_))), "{:?}", result);
+
+            let tls = TlsConfig {
+                pinned_certificates: vec!["not-a-fingerprint".to_string()],
+                ..Default::default()
+            };
+            let err = HttpTransport::new(tls_config(&base_url, tls)).unwrap_err();
+            assert!(err.to_string().contains("Invalid pinned certificate"), "{}", err);
+        }
+
+        #[test]
+        fn test_malformed_certificates_rejected() {
+            let tls = TlsConfig {
+                root_certificates: vec![PemSource::Bytes(b"not a certificate".to_vec())],
+                ..Default::default()
+            };
+            let err = HttpTransport::new(tls_config("https://localhost", tls)).unwrap_err();
+            assert!(err.to_string().contains("Invalid root certificate"), "{}", err);
+
+            let tls = TlsConfig {
+                client_identity: Some((
+                    PemSource::Bytes(CLIENT_CERT.to_vec()),
+                    PemSource::Bytes(b"garbage".to_vec()),
+                )),
+                ..Default::default()
+            };
+            let err = HttpTransport::new(tls_config("https://localhost", tls)).unwrap_err();
+            assert!(err.to_string().contains("Invalid client certificate"), "{}", err);
+
+            let tls = TlsConfig {
+                root_certificates: vec![PemSource::File("/nonexistent/ca.pem".into())],
+                ..Default::default()
+            };
+            let err = HttpTransport::new(tls_config("https://localhost", tls)).unwrap_err();
+            assert!(err.to_string().contains("Failed to read root certificate"), "{}", err);
+        }
+    }
 }
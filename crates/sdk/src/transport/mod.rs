@@ -1,7 +1,11 @@
 //! Transport layer for the Shiioo SDK.
 
+pub mod circuit_breaker;
 pub mod http;
+#[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+mod tls;
 pub mod websocket;
 
+pub use circuit_breaker::{CallPermit, CircuitBreaker, CircuitState};
 pub use http::HttpTransport;
 pub use websocket::WebSocketClient;
@@ -0,0 +1,260 @@
+//! TLS connector shared by the HTTP and WebSocket transports.
+
+use crate::config::TlsConfig;
+use crate::error::{ShiiooError, ShiiooResult};
+use reqwest::ClientBuilder;
+use tokio_tungstenite::Connector;
+
+#[cfg(feature = "rustls-tls")]
+use rustls::pki_types::pem::PemObject;
+#[cfg(feature = "rustls-tls")]
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+#[cfg(feature = "rustls-tls")]
+use rustls::client::WebPkiServerVerifier;
+#[cfg(feature = "rustls-tls")]
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+#[cfg(feature = "rustls-tls")]
+use rustls::{DigitallySignedStruct, SignatureScheme};
+#[cfg(feature = "rustls-tls")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "rustls-tls")]
+use std::sync::Arc;
+
+/// One TLS client configuration, built from the [`TlsConfig`], that HTTP requests and
+/// WebSocket subscriptions both connect with, so they trust the same roots and present
+/// the same client certificate.
+#[derive(Clone)]
+pub(crate) struct TlsConnector {
+    #[cfg(feature = "rustls-tls")]
+    config: Arc<rustls::ClientConfig>,
+    #[cfg(all(feature = "native-tls", not(feature = "rustls-tls")))]
+    connector: native_tls::TlsConnector,
+}
+
+impl std::fmt::Debug for TlsConnector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsConnector").finish_non_exhaustive()
+    }
+}
+
+impl TlsConnector {
+    /// Load the custom roots and client identity, failing on certificates or keys that
+    /// don't parse.
+    #[cfg(feature = "rustls-tls")]
+    pub(crate) fn new(tls: &TlsConfig) -> ShiiooResult<Self> {
+        let mut roots = rustls::RootCertStore::empty();
+        if !tls.custom_roots_only {
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+        for pem in load_roots(tls)? {
+            let certificates = CertificateDer::pem_slice_iter(&pem)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| ShiiooError::Config(format!("Invalid root certificate: {}", e)))?;
+            if certificates.is_empty() {
+                return Err(no_certificates());
+            }
+            for certificate in certificates {
+                roots.add(certificate).map_err(|e| {
+                    ShiiooError::Config(format!("Invalid root certificate: {}", e))
+                })?;
+            }
+        }
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let invalid_config =
+            |e: String| ShiiooError::Config(format!("Invalid TLS configuration: {}", e));
+        let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| invalid_config(e.to_string()))?;
+        let builder = if tls.pinned_certificates.is_empty() {
+            builder.with_root_certificates(roots)
+        } else {
+            let chain = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|e| invalid_config(e.to_string()))?;
+            let verifier = PinnedCertificateVerifier {
+                chain,
+                pins: parse_pins(&tls.pinned_certificates)?,
+            };
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(verifier))
+        };
+
+        let config = match load_identity(tls)? {
+            Some((certificate, key)) => {
+                let invalid = |e: String| {
+                    ShiiooError::Config(format!("Invalid client certificate or key: {}", e))
+                };
+                let chain = CertificateDer::pem_slice_iter(&certificate)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| invalid(e.to_string()))?;
+                let key = PrivateKeyDer::from_pem_slice(&key).map_err(|e| invalid(e.to_string()))?;
+                builder
+                    .with_client_auth_cert(chain, key)
+                    .map_err(|e| invalid(e.to_string()))?
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        Ok(Self {
+            config: Arc::new(config),
+        })
+    }
+
+    /// Load the custom roots and client identity (PKCS#8 only, with native TLS), failing
+    /// on certificates or keys that don't parse.
+    #[cfg(all(feature = "native-tls", not(feature = "rustls-tls")))]
+    pub(crate) fn new(tls: &TlsConfig) -> ShiiooResult<Self> {
+        if !tls.pinned_certificates.is_empty() {
+            return Err(ShiiooError::Config(
+                "Certificate pinning needs the rustls-tls feature".to_string(),
+            ));
+        }
+        let mut builder = native_tls::TlsConnector::builder();
+        builder.disable_built_in_roots(tls.custom_roots_only);
+        for pem in load_roots(tls)? {
+            let text = String::from_utf8_lossy(&pem);
+            let blocks: Vec<_> = text
+                .split_inclusive("-----END CERTIFICATE-----")
+                .filter(|block| block.contains("-----BEGIN CERTIFICATE-----"))
+                .collect();
+            if blocks.is_empty() {
+                return Err(no_certificates());
+            }
+            for block in blocks {
+                let certificate = native_tls::Certificate::from_pem(block.as_bytes())
+                    .map_err(|e| ShiiooError::Config(format!("Invalid root certificate: {}", e)))?;
+                builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some((certificate, key)) = load_identity(tls)? {
+            let identity = native_tls::Identity::from_pkcs8(&certificate, &key).map_err(|e| {
+                ShiiooError::Config(format!("Invalid client certificate or key: {}", e))
+            })?;
+            builder.identity(identity);
+        }
+
+        let connector = builder
+            .build()
+            .map_err(|e| ShiiooError::Config(format!("Invalid TLS configuration: {}", e)))?;
+        Ok(Self { connector })
+    }
+
+    /// Have an HTTP client connect with this configuration.
+    pub(crate) fn configure(&self, builder: ClientBuilder) -> ClientBuilder {
+        #[cfg(feature = "rustls-tls")]
+        let tls = (*self.config).clone();
+        #[cfg(all(feature = "native-tls", not(feature = "rustls-tls")))]
+        let tls = self.connector.clone();
+        builder.use_preconfigured_tls(tls)
+    }
+
+    /// Connector for WebSocket connections made with this configuration.
+    pub(crate) fn websocket(&self) -> Connector {
+        #[cfg(feature = "rustls-tls")]
+        return Connector::Rustls(self.config.clone());
+        #[cfg(all(feature = "native-tls", not(feature = "rustls-tls")))]
+        return Connector::NativeTls(self.connector.clone());
+    }
+}
+
+/// Verifies the server's chain as usual, then requires its certificate to be pinned
+#[cfg(feature = "rustls-tls")]
+#[derive(Debug)]
+struct PinnedCertificateVerifier {
+    chain: Arc<WebPkiServerVerifier>,
+    pins: Vec<[u8; 32]>,
+}
+
+#[cfg(feature = "rustls-tls")]
+impl ServerCertVerifier for PinnedCertificateVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.chain
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+        let fingerprint: [u8; 32] = Sha256::digest(end_entity.as_ref()).into();
+        if !self.pins.contains(&fingerprint) {
+            return Err(rustls::Error::General(format!(
+                "Server certificate {} is not pinned",
+                hex::encode(fingerprint)
+            )));
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.chain.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.chain.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.chain.supported_verify_schemes()
+    }
+}
+
+/// Decode hex SHA-256 fingerprints, which may separate bytes with colons
+#[cfg(feature = "rustls-tls")]
+fn parse_pins(pins: &[String]) -> ShiiooResult<Vec<[u8; 32]>> {
+    pins.iter()
+        .map(|pin| {
+            let digits: String = pin.chars().filter(|c| *c != ':').collect();
+            hex::decode(&digits)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .ok_or_else(|| {
+                    ShiiooError::Config(format!(
+                        "Invalid pinned certificate '{}': expected a hex SHA-256 fingerprint",
+                        pin
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Each configured root certificate bundle, checking that trusting only custom roots
+/// leaves at least one.
+fn load_roots(tls: &TlsConfig) -> ShiiooResult<Vec<Vec<u8>>> {
+    if tls.custom_roots_only && tls.root_certificates.is_empty() {
+        return Err(ShiiooError::Config(
+            "Trusting only custom roots requires at least one root certificate".to_string(),
+        ));
+    }
+    tls.root_certificates
+        .iter()
+        .map(|source| source.load("root certificate").map_err(ShiiooError::Config))
+        .collect()
+}
+
+fn load_identity(tls: &TlsConfig) -> ShiiooResult<Option<(Vec<u8>, Vec<u8>)>> {
+    let Some((certificate, key)) = &tls.client_identity else {
+        return Ok(None);
+    };
+    let certificate = certificate.load("client certificate").map_err(ShiiooError::Config)?;
+    let key = key.load("client key").map_err(ShiiooError::Config)?;
+    Ok(Some((certificate, key)))
+}
+
+fn no_certificates() -> ShiiooError {
+    ShiiooError::Config("Invalid root certificate: no PEM certificates found".to_string())
+}
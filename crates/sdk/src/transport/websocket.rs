@@ -1,20 +1,26 @@
 //! WebSocket transport for real-time subscriptions.
 
+#[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+use super::tls::TlsConnector;
 use crate::config::ClientConfig;
 use crate::error::{ShiiooError, ShiiooResult};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use shiioo_core::audit::AuditEntry;
+use shiioo_core::types::{ApprovalStatus, CapacityAlertKind};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::mpsc;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::tungstenite::Message;
 use tracing::{debug, error, info};
 
 /// WebSocket client for real-time subscriptions.
 pub struct WebSocketClient {
     config: Arc<ClientConfig>,
+    #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+    tls: Option<TlsConnector>,
     sender: Option<mpsc::Sender<WsRequest>>,
-    receiver: Option<mpsc::Receiver<ShiiooResult<SubscriptionEvent>>>,
+    receiver: Option<mpsc::Receiver<ShiiooResult<SequencedEvent>>>,
 }
 
 impl WebSocketClient {
@@ -22,19 +28,39 @@ impl WebSocketClient {
     pub fn new(config: Arc<ClientConfig>) -> Self {
         Self {
             config,
+            #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+            tls: None,
             sender: None,
             receiver: None,
         }
     }
 
+    /// Create a WebSocket client that connects with an HTTP transport's TLS configuration.
+    #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+    pub(crate) fn with_tls(config: Arc<ClientConfig>, tls: TlsConnector) -> Self {
+        Self {
+            tls: Some(tls),
+            ..Self::new(config)
+        }
+    }
+
     /// Connect to the WebSocket endpoint.
     pub async fn connect(&mut self) -> ShiiooResult<()> {
         let ws_url = self.build_ws_url()?;
         debug!(url = %ws_url, "Connecting to WebSocket");
 
-        let (ws_stream, _) = connect_async(&ws_url)
-            .await
-            .map_err(|e| ShiiooError::WebSocket(e.to_string()))?;
+        #[cfg(any(feature = "rustls-tls", feature = "native-tls"))]
+        let connected = {
+            let connector = match &self.tls {
+                Some(tls) => tls.websocket(),
+                None => TlsConnector::new(&self.config.tls)?.websocket(),
+            };
+            tokio_tungstenite::connect_async_tls_with_config(&ws_url, None, false, Some(connector))
+                .await
+        };
+        #[cfg(not(any(feature = "rustls-tls", feature = "native-tls")))]
+        let connected = tokio_tungstenite::connect_async(&ws_url).await;
+        let (ws_stream, _) = connected.map_err(|e| ShiiooError::WebSocket(e.to_string()))?;
 
         let (mut write, mut read) = ws_stream.split();
 
@@ -42,7 +68,7 @@ impl WebSocketClient {
         let (request_tx, mut request_rx) = mpsc::channel::<WsRequest>(32);
 
         // Channel for receiving events from the WebSocket
-        let (event_tx, event_rx) = mpsc::channel::<ShiiooResult<SubscriptionEvent>>(128);
+        let (event_tx, event_rx) = mpsc::channel::<ShiiooResult<SequencedEvent>>(128);
 
         // Spawn task to handle outgoing messages
         tokio::spawn(async move {
@@ -67,7 +93,7 @@ impl WebSocketClient {
             while let Some(msg) = read.next().await {
                 match msg {
                     Ok(Message::Text(text)) => {
-                        let event = serde_json::from_str::<SubscriptionEvent>(&text)
+                        let event = serde_json::from_str::<SequencedEvent>(&text)
                             .map_err(|e| ShiiooError::Json(e));
                         if event_tx_clone.send(event).await.is_err() {
                             break;
@@ -159,6 +185,28 @@ impl WebSocketClient {
         self.send_request(WsRequest::SubscribeHealth).await
     }
 
+    /// Subscribe to approvals as they are resolved.
+    pub async fn subscribe_approvals(&self) -> ShiiooResult<()> {
+        self.send_request(WsRequest::SubscribeApprovals).await
+    }
+
+    /// Subscribe to capacity alerts.
+    pub async fn subscribe_capacity(&self) -> ShiiooResult<()> {
+        self.send_request(WsRequest::SubscribeCapacity).await
+    }
+
+    /// Subscribe to audit log entries.
+    pub async fn subscribe_audit(&self) -> ShiiooResult<()> {
+        self.send_request(WsRequest::SubscribeAudit).await
+    }
+
+    /// Replay the updates on a subscribed topic after `after_seq` that the server still
+    /// buffers, e.g. the last `seq` seen before reconnecting. Older ones arrive as a
+    /// `Gap`.
+    pub async fn resume(&self, topic: SubscriptionTopic, after_seq: u64) -> ShiiooResult<()> {
+        self.send_request(WsRequest::Resume { topic, after_seq }).await
+    }
+
     /// Unsubscribe from all subscriptions.
     pub async fn unsubscribe(&self) -> ShiiooResult<()> {
         self.send_request(WsRequest::Unsubscribe).await
@@ -166,6 +214,12 @@ impl WebSocketClient {
 
     /// Get the next event from the subscription.
     pub async fn next_event(&mut self) -> Option<ShiiooResult<SubscriptionEvent>> {
+        let event = self.next_sequenced_event().await?;
+        Some(event.map(|sequenced| sequenced.event))
+    }
+
+    /// Get the next event from the subscription with its sequence number.
+    pub async fn next_sequenced_event(&mut self) -> Option<ShiiooResult<SequencedEvent>> {
         self.receiver.as_mut()?.recv().await
     }
 }
@@ -178,9 +232,39 @@ pub enum WsRequest {
     SubscribeWorkflow { run_id: String },
     SubscribeMetrics,
     SubscribeHealth,
+    SubscribeApprovals,
+    SubscribeCapacity,
+    SubscribeAudit,
+    Resume {
+        topic: SubscriptionTopic,
+        after_seq: u64,
+    },
     Unsubscribe,
 }
 
+/// Stream of updates a subscription can lose messages on, each numbered on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionTopic {
+    Approvals,
+    ApprovalReminders,
+    Capacity,
+    Audit,
+}
+
+/// An event together with its position on its topic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequencedEvent {
+    /// Sequence number of the update on its topic, shared by every subscriber and
+    /// counting from 1 since the server started. Pass the last one seen to
+    /// [`WebSocketClient::resume`] after reconnecting. `None` for events outside a
+    /// topic and for `Gap`, which accounts for the numbers it covers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    #[serde(flatten)]
+    pub event: SubscriptionEvent,
+}
+
 /// Events received from WebSocket subscriptions.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -213,6 +297,33 @@ pub enum SubscriptionEvent {
         active_routines: usize,
         pending_approvals: usize,
     },
+    /// Approval resolved by a decisive vote.
+    ApprovalUpdate {
+        approval_id: String,
+        status: ApprovalStatus,
+    },
+    /// Approval still pending past its reminder time, for the approvers yet to vote.
+    ApprovalReminder {
+        approval_id: String,
+        approvers: Vec<String>,
+        reminder: u32,
+    },
+    /// Capacity source backed off or a queued request was dead-lettered.
+    CapacityAlert {
+        kind: CapacityAlertKind,
+        source_id: Option<String>,
+        message: String,
+    },
+    /// Audit entry recorded.
+    AuditEvent { entry: AuditEntry },
+    /// Updates `from_seq..=to_seq` on `topic` were dropped because the client fell
+    /// behind further than the server buffers. Backfill them from the REST API, e.g.
+    /// `runs().events()`.
+    Gap {
+        topic: SubscriptionTopic,
+        from_seq: u64,
+        to_seq: u64,
+    },
     /// Subscription confirmed.
     Subscribed { subscription_id: String },
     /// Error from server.
@@ -226,7 +337,8 @@ pub enum SubscriptionEvent {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{ClientConfig, RetryConfig};
+    use crate::config::{CircuitBreakerConfig, ClientConfig, RetryConfig};
+    use shiioo_core::codec::Codec;
     use std::time::Duration;
     use url::Url;
 
@@ -237,6 +349,10 @@ mod tests {
             timeout: Duration::from_secs(30),
             retry_config: RetryConfig::default(),
             tenant_id: None,
+            circuit_breaker: CircuitBreakerConfig::disabled(),
+            codec: Codec::default(),
+            sign_requests: false,
+            tls: Default::default(),
         })
     }
 
@@ -247,6 +363,10 @@ mod tests {
             timeout: Duration::from_secs(30),
             retry_config: RetryConfig::default(),
             tenant_id: None,
+            circuit_breaker: CircuitBreakerConfig::disabled(),
+            codec: Codec::default(),
+            sign_requests: false,
+            tls: Default::default(),
         })
     }
 
@@ -313,6 +433,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sequenced_event_deserialization() {
+        let json = r#"{"seq":7,"type":"approval_update","approval_id":"a-1","status":"approved"}"#;
+        let parsed: SequencedEvent = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.seq, Some(7));
+        assert!(matches!(
+            parsed.event,
+            SubscriptionEvent::ApprovalUpdate { ref approval_id, .. } if approval_id == "a-1"
+        ));
+
+        let parsed: SequencedEvent = serde_json::from_str(r#"{"type":"pong"}"#).unwrap();
+        assert_eq!(parsed.seq, None);
+    }
+
     #[test]
     fn test_ws_request_serialization() {
         let request = WsRequest::SubscribeWorkflow {
@@ -323,4 +457,57 @@ mod tests {
         assert!(json.contains("subscribe_workflow"));
         assert!(json.contains("run-456"));
     }
+
+    #[cfg(feature = "rustls-tls")]
+    #[tokio::test]
+    async fn test_wss_connects_with_configured_roots() {
+        use crate::config::{PemSource, TlsConfig};
+        use tokio_rustls::rustls::pki_types::pem::PemObject;
+        use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+        use tokio_rustls::rustls::ServerConfig;
+
+        let certs = vec![CertificateDer::from_pem_slice(include_bytes!(
+            "../../testdata/tls/server.pem"
+        ))
+        .unwrap()];
+        let key = PrivateKeyDer::from_pem_slice(include_bytes!("../../testdata/tls/server.key"))
+            .unwrap();
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let Ok(stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    if let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await {
+                        while let Some(Ok(_)) = ws.next().await {}
+                    }
+                });
+            }
+        });
+        let base_url = format!("https://localhost:{}", port);
+
+        // The built-in roots don't include the test CA
+        let mut client = WebSocketClient::new(create_config(&base_url));
+        let result = client.connect().await;
+        assert!(matches!(result, Err(ShiiooError::WebSocket(_))), "{:?}", result.err());
+
+        let mut config = (*create_config(&base_url)).clone();
+        config.tls = TlsConfig {
+            root_certificates: vec![PemSource::Bytes(
+                include_bytes!("../../testdata/tls/ca.pem").to_vec(),
+            )],
+            custom_roots_only: true,
+            ..Default::default()
+        };
+        let mut client = WebSocketClient::new(Arc::new(config));
+        client.connect().await.unwrap();
+    }
 }
@@ -1,40 +1,187 @@
-use super::{ApiResult, ErrorResponse};
+use super::{ApiResult, Conflict, ErrorResponse, Forbidden, NotFound, NotLeader};
 use crate::config::AppState;
+use crate::middleware::auth::RequestTenant;
 use axum::{
     extract::{Path, State},
+    http::header,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use shiioo_core::{
+    analytics::RunComparison,
+    approval::{BulkVote, BulkVoteResult},
     claude_compiler::ClaudeCompiler,
-    events::EventLog,
+    config_change::{changed_entity_id, config_entity_id},
+    events::{step_logs, EventLog, StepLog},
     organization::OrganizationManager,
-    template::TemplateProcessor,
+    policy::{
+        BudgetUsage, EvaluationStep, PolicyContext, PolicyDecision, PolicyEngine,
+        PolicySimulation, RecordedToolCall,
+    },
+    query::{PageRequest, Paginated, QuerySpec},
+    rbac::{Action, Permission, Resource, ResourceTarget},
+    storage::index::run_tag_key,
+    template::{KnownEntities, MissingDependencies, TemplateCategory, TemplateProcessor},
+    validation::ValidationErrors,
+    workflow::{ApproverDirectory, LintWarning, WorkflowDag},
     types::{
-        ApprovalBoard, ApprovalBoardId, ApprovalId, CapacitySource, CapacitySourceId,
-        ConfigChange, ConfigChangeId, ConfigChangeType, Job, OrgId, Organization, PersonId,
-        PolicyId, PolicySpec, ProcessTemplate, Routine, RoutineId, RoutineSchedule, RoleId,
-        RoleSpec, Run, RunId, TemplateId, TemplateInstance, VoteDecision, WorkflowSpec,
+        ApprovalBoard, ApprovalBoardId, ApprovalId, ApprovalSubject, CapacitySource,
+        CapacitySourceId,
+        ConfigChange, ConfigChangeId, ConfigChangeType, DeadLetter, Job, OrgId, Organization, PersonId,
+        PolicyId, PolicySpec, PriorityRequest, ProcessTemplate, Routine, RoutineExecution, RoutineId, RoutineSchedule, RoleId,
+        RetryFrom, RoleBudgets, RoleSpec, Run, RunId, RunStatus, StepId, TemplateId, TemplateInstance, VoteDecision, WorkflowSpec,
     },
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 
-/// List all runs
+/// Sorting, filtering and pagination shared by list endpoints, e.g.
+/// `?sort=created_at&order=desc&filter=status:failed&limit=50&cursor=...`
+#[derive(Debug, Default, Deserialize)]
+pub struct ListQueryParams {
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+impl ListQueryParams {
+    fn apply<T: Serialize>(&self, items: Vec<T>) -> anyhow::Result<Paginated<T>> {
+        self.apply_by(items, "id")
+    }
+
+    /// As `apply`, for items identified by another field
+    fn apply_by<T: Serialize>(&self, items: Vec<T>, id_field: &str) -> anyhow::Result<Paginated<T>> {
+        let page = PageRequest {
+            offset: self.offset,
+            limit: self.limit,
+            cursor: self.cursor.clone(),
+        };
+        QuerySpec::parse(self.sort.as_deref(), self.order.as_deref(), self.filter.as_deref())?
+            .paginate_by(items, &page, id_field)
+    }
+}
+
+/// Load a run the request may take `action` on. Another tenant's run, or one the key's
+/// grants don't select, is reported as not found rather than forbidden, so scoped keys
+/// can't probe for run IDs.
+fn visible_run(
+    state: &AppState,
+    tenant: &Option<axum::Extension<RequestTenant>>,
+    api_key: &Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    action: Action,
+    run_id: &RunId,
+) -> anyhow::Result<Run> {
+    state
+        .index_store
+        .get_run(run_id)?
+        .filter(|run| run_visible(state, tenant, api_key, action, run))
+        .ok_or_else(|| NotFound("Run not found".to_string()).into())
+}
+
+/// Fail as `visible_run` does for a tenant-scoped request, or one whose key's user may
+/// take `action` on some runs only. Other requests may read the events of runs that
+/// aren't indexed.
+fn ensure_run_visible(
+    state: &AppState,
+    tenant: &Option<axum::Extension<RequestTenant>>,
+    api_key: &Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    action: Action,
+    run_id: &RunId,
+) -> anyhow::Result<()> {
+    let limited_grant = api_key.as_ref().is_some_and(|key| {
+        let permission = Permission::new(Resource::Workflow, action.clone());
+        !state.rbac_manager.check_permission(&key.user_id, &permission)
+    });
+    if limited_grant || tenant.as_ref().is_some_and(|t| t.0 .0.is_some()) {
+        visible_run(state, tenant, api_key, action, run_id)?;
+    }
+    Ok(())
+}
+
+/// Whether the request's tenant, if any, owns the run, and the key's user, if any, holds
+/// `action` on it
+fn run_visible(
+    state: &AppState,
+    tenant: &Option<axum::Extension<RequestTenant>>,
+    api_key: &Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    action: Action,
+    run: &Run,
+) -> bool {
+    tenant
+        .as_ref()
+        .is_none_or(|t| t.sees(run.tenant_id.as_ref()))
+        && api_key.as_ref().is_none_or(|key| {
+            state.rbac_manager.check_permission_on(
+                &key.user_id,
+                &Permission::new(Resource::Workflow, action),
+                &run_target(run),
+            )
+        })
+}
+
+/// The resource RBAC checks a run against: its ID, tagged with its tags keyed as in the
+/// run tag index (see `run_tag_key`)
+fn run_target(run: &Run) -> ResourceTarget {
+    let tags = run.tags.iter().map(|(key, value)| run_tag_key(key, value)).collect();
+    ResourceTarget::new(run.id.to_string()).with_tags(tags)
+}
+
+/// List runs, optionally filtered by work item, status and/or tag
 pub async fn list_runs(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListRunsResponse>> {
-    let runs = state.index_store.list_runs()?;
-    Ok(Json(ListRunsResponse { runs }))
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    axum::extract::Query(params): axum::extract::Query<ListRunsQueryParams>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<Run>>> {
+    let runs = if let Some(tag) = &params.tag {
+        state
+            .index_store
+            .list_runs_by_tag(tag)?
+            .into_iter()
+            .filter(|r| params.work_item_id.as_ref().is_none_or(|w| r.work_item_id == *w))
+            .filter(|r| params.status.is_none_or(|s| r.status == s))
+            .collect()
+    } else {
+        match (params.work_item_id, params.status) {
+            (Some(work_item_id), Some(status)) => state
+                .index_store
+                .list_runs_by_work_item(&work_item_id)?
+                .into_iter()
+                .filter(|r| r.status == status)
+                .collect(),
+            (Some(work_item_id), None) => {
+                state.index_store.list_runs_by_work_item(&work_item_id)?
+            }
+            (None, Some(status)) => state.index_store.list_runs_by_status(status)?,
+            (None, None) => state.index_store.list_runs()?,
+        }
+    };
+    let runs = runs
+        .into_iter()
+        .filter(|r| run_visible(&state, &tenant, &api_key, Action::Read, r))
+        .collect();
+    Ok(Json(query.apply(runs)?))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListRunsResponse {
-    pub runs: Vec<Run>,
+#[derive(Debug, Deserialize)]
+pub struct ListRunsQueryParams {
+    pub work_item_id: Option<String>,
+    pub status: Option<RunStatus>,
+    /// Only runs carrying this tag, as `key:value`, with any colon or backslash in the
+    /// key escaped by a backslash
+    pub tag: Option<String>,
 }
 
 /// Get a specific run
 pub async fn get_run(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
     Path(run_id): Path<String>,
 ) -> ApiResult<Json<Run>> {
     let run_id = RunId(
@@ -43,17 +190,99 @@ pub async fn get_run(
             .map_err(|_| anyhow::anyhow!("Invalid run ID"))?,
     );
 
+    let run = visible_run(&state, &tenant, &api_key, Action::Read, &run_id)?;
+
+    Ok(Json(run))
+}
+
+/// Project when a run will finish from historical step durations
+pub async fn get_run_eta(
+    State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Path(run_id): Path<String>,
+) -> ApiResult<Json<RunEtaResponse>> {
+    let run_id = RunId(
+        run_id
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid run ID"))?,
+    );
+
+    let run = visible_run(&state, &tenant, &api_key, Action::Read, &run_id)?;
+
+    Ok(Json(RunEtaResponse {
+        run_id,
+        estimated_completion: state.analytics.estimate_completion(&run),
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunEtaResponse {
+    pub run_id: RunId,
+    /// `None` when there isn't enough step history to project from
+    pub estimated_completion: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Get a run by its caller-supplied external ID
+pub async fn get_run_by_external_id(
+    State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Path(external_id): Path<String>,
+) -> ApiResult<Json<Run>> {
     let run = state
         .index_store
-        .get_run(&run_id)?
-        .ok_or_else(|| anyhow::anyhow!("Run not found"))?;
+        .get_run_by_external_id(&external_id)?
+        .filter(|run| run_visible(&state, &tenant, &api_key, Action::Read, run))
+        .ok_or_else(|| NotFound("Run not found".to_string()))?;
 
     Ok(Json(run))
 }
 
+/// Compare two runs step by step, e.g. a failing run against a prior success
+pub async fn compare_runs(
+    State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    axum::extract::Query(params): axum::extract::Query<CompareRunsQueryParams>,
+) -> ApiResult<Json<RunComparison>> {
+    let load = |run_id: &str| -> anyhow::Result<Run> {
+        let run_id = RunId(
+            run_id
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid run ID: {}", run_id))?,
+        );
+        state
+            .index_store
+            .get_run(&run_id)?
+            .filter(|run| run_visible(&state, &tenant, &api_key, Action::Read, run))
+            .ok_or_else(|| NotFound(format!("Run not found: {}", run_id.0)).into())
+    };
+
+    let run_a = load(&params.a)?;
+    let run_b = load(&params.b)?;
+    let trace_a = state.analytics.get_trace(&run_a.id);
+    let trace_b = state.analytics.get_trace(&run_b.id);
+
+    Ok(Json(RunComparison::compare(
+        &run_a,
+        trace_a.as_ref(),
+        &run_b,
+        trace_b.as_ref(),
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareRunsQueryParams {
+    pub a: String,
+    pub b: String,
+}
+
 /// Get events for a run
 pub async fn get_run_events(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
     Path(run_id): Path<String>,
 ) -> ApiResult<Json<GetRunEventsResponse>> {
     let run_id = RunId(
@@ -61,6 +290,7 @@ pub async fn get_run_events(
             .parse()
             .map_err(|_| anyhow::anyhow!("Invalid run ID"))?,
     );
+    ensure_run_visible(&state, &tenant, &api_key, Action::Read, &run_id)?;
 
     let events = state.event_log.get_run_events(run_id).await?;
 
@@ -72,11 +302,237 @@ pub struct GetRunEventsResponse {
     pub events: Vec<shiioo_core::events::Event>,
 }
 
+/// Get the captured script output of a run's steps. Output over the executor's log limit
+/// is truncated, with the full log referenced by blob hash.
+pub async fn get_run_logs(
+    State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Path(run_id): Path<String>,
+) -> ApiResult<Json<GetRunLogsResponse>> {
+    let run_id = RunId(
+        run_id
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid run ID"))?,
+    );
+    ensure_run_visible(&state, &tenant, &api_key, Action::Read, &run_id)?;
+
+    let events = state.event_log.get_run_events(run_id).await?;
+
+    Ok(Json(GetRunLogsResponse {
+        logs: step_logs(&events),
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetRunLogsResponse {
+    pub logs: Vec<StepLog>,
+}
+
+/// Longest a tail request may hold the connection open
+const MAX_TAIL_WAIT_SECS: u64 = 60;
+
+/// Long-poll for run events past `after`, holding up to `wait` seconds for a new one
+pub async fn tail_run_events(
+    State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Path(run_id): Path<String>,
+    axum::extract::Query(params): axum::extract::Query<TailRunEventsQueryParams>,
+) -> ApiResult<Json<TailRunEventsResponse>> {
+    let run_id = RunId(
+        run_id
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid run ID"))?,
+    );
+    ensure_run_visible(&state, &tenant, &api_key, Action::Read, &run_id)?;
+
+    let after = params.after.unwrap_or(0);
+    let wait = std::time::Duration::from_secs(params.wait.unwrap_or(30).min(MAX_TAIL_WAIT_SECS));
+
+    let events = state.event_log.tail_run_events(run_id, after, wait).await?;
+
+    Ok(Json(TailRunEventsResponse {
+        next_after: after + events.len(),
+        events,
+    }))
+}
+
+/// Retry a finished run as a new run, by default reusing its completed steps
+/// (`?from=failed`) or executing everything again with `?from=start`
+pub async fn retry_run(
+    State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Path(run_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<RetryRunQuery>,
+) -> ApiResult<Json<Run>> {
+    let run_id = RunId(
+        run_id
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid run ID"))?,
+    );
+    ensure_run_visible(&state, &tenant, &api_key, Action::Execute, &run_id)?;
+
+    let run = state.workflow_executor.retry(run_id, query.from).await?;
+    tracing::info!("Retried run {} as run {}", run_id, run.id);
+
+    Ok(Json(run))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RetryRunQuery {
+    #[serde(default)]
+    pub from: RetryFrom,
+}
+
+/// Replay a finished run from its captured agent responses and tool results, without
+/// live calls, reporting whether it took the same path through the DAG
+pub async fn replay_run(
+    State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Path(run_id): Path<String>,
+) -> ApiResult<Json<shiioo_core::workflow::ReplayReport>> {
+    let run_id = RunId(
+        run_id
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid run ID"))?,
+    );
+    ensure_run_visible(&state, &tenant, &api_key, Action::Execute, &run_id)?;
+
+    let report = state.workflow_executor.replay(run_id).await?;
+    tracing::info!(
+        "Replayed run {} as run {} (matched: {})",
+        run_id,
+        report.replay_run_id,
+        report.matches()
+    );
+
+    Ok(Json(report))
+}
+
+/// Deliver a signal to a run, resuming any step waiting for its event key, and return
+/// the run as it stands
+pub async fn signal_run(
+    State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Path(run_id): Path<String>,
+    Json(req): Json<SignalRunRequest>,
+) -> ApiResult<Json<SignalRunResponse>> {
+    let run_id = RunId(
+        run_id
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid run ID"))?,
+    );
+    ensure_run_visible(&state, &tenant, &api_key, Action::Execute, &run_id)?;
+
+    // Read from the event log rather than the executor, so this finds steps parked
+    // before a restart too
+    let events = state.event_log.get_run_events(run_id).await?;
+    let resumed: Vec<StepId> = shiioo_core::events::waiting_steps(&events)
+        .into_iter()
+        .filter(|(_, key)| *key == req.event_key)
+        .map(|(step_id, _)| step_id)
+        .collect();
+
+    if resumed.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Run {} has no step waiting for event '{}'",
+            run_id,
+            req.event_key
+        )
+        .into());
+    }
+
+    state
+        .event_log
+        .append(shiioo_core::events::Event::new(
+            run_id,
+            shiioo_core::events::EventType::SignalReceived {
+                event_key: req.event_key.clone(),
+                payload: req.payload,
+            },
+        ))
+        .await?;
+
+    tracing::info!("Signalled event '{}' to run {}", req.event_key, run_id);
+
+    // A run parked before a restart has nothing waiting on the signal until it is resumed
+    if !state.workflow_executor.is_active(run_id).await {
+        let executor = state.workflow_executor.clone();
+        tokio::spawn(async move {
+            if let Err(e) = executor.resume(run_id).await {
+                tracing::error!("Failed to resume run {}: {}", run_id, e);
+            }
+        });
+    }
+
+    let run = visible_run(&state, &tenant, &api_key, Action::Execute, &run_id)?;
+
+    Ok(Json(SignalRunResponse {
+        resumed_steps: resumed,
+        message: format!("Signal '{}' delivered", req.event_key),
+        run,
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignalRunRequest {
+    pub event_key: String,
+    pub payload: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignalRunResponse {
+    pub resumed_steps: Vec<StepId>,
+    pub message: String,
+    /// The signalled run
+    pub run: Run,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TailRunEventsQueryParams {
+    /// Number of events the caller has already seen
+    pub after: Option<usize>,
+    /// Seconds to wait for a new event (default 30, capped at 60)
+    pub wait: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TailRunEventsResponse {
+    pub events: Vec<shiioo_core::events::Event>,
+    /// Value of `after` for the next tail request
+    pub next_after: usize,
+}
+
 /// Create a new job
 pub async fn create_job(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
     Json(req): Json<CreateJobRequest>,
 ) -> ApiResult<Json<CreateJobResponse>> {
+    let mut errors = ValidationErrors::new();
+    match WorkflowDag::from_workflow_with_limits(&req.workflow, &state.workflow_limits) {
+        Ok(dag) => {
+            for warning in dag.unknown_approvers(&approver_directory(&state)?) {
+                errors.push(format!("steps.{}.approvers", warning.step_id), warning.message);
+            }
+        }
+        Err(e) => match e.downcast::<ValidationErrors>() {
+            Ok(dag_errors) => errors.extend(dag_errors),
+            Err(e) => return Err(e.into()),
+        },
+    }
+    if let Err(e) = req.workflow.resolve_inputs(req.inputs.clone()) {
+        match e.downcast::<ValidationErrors>() {
+            Ok(input_errors) => errors.extend(input_errors),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    errors.into_result()?;
+
     let job = Job {
         id: uuid::Uuid::new_v4().to_string(),
         name: req.name.clone(),
@@ -84,6 +540,10 @@ pub async fn create_job(
         workflow: req.workflow.clone(),
         created_at: chrono::Utc::now(),
         created_by: req.created_by.clone().unwrap_or_else(|| "system".to_string()),
+        external_id: req.external_id.clone(),
+        priority: req
+            .priority
+            .unwrap_or(shiioo_core::types::DEFAULT_RUN_PRIORITY),
     };
 
     tracing::info!("Created job: {} ({})", job.name, job.id);
@@ -95,7 +555,17 @@ pub async fn create_job(
         let workflow = req.workflow;
 
         // Spawn execution in background
-        let run = executor.execute(work_item_id, workflow).await?;
+        let run = executor
+            .execute_tagged(
+                work_item_id,
+                workflow,
+                job.external_id.clone(),
+                req.tags,
+                req.inputs,
+                job.priority,
+                tenant.and_then(|t| t.0 .0.clone()),
+            )
+            .await?;
 
         tracing::info!("Started workflow execution: run_id={}", run.id);
         Some(run.id)
@@ -122,6 +592,18 @@ pub struct CreateJobRequest {
     pub created_by: Option<String>,
     /// Whether to execute the job immediately (default: true)
     pub execute: Option<bool>,
+    /// Caller-supplied ID the run can later be fetched by; must be unique
+    #[serde(default)]
+    pub external_id: Option<String>,
+    /// Labels for the run, e.g. `team: payments`
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+    /// Values for the workflow's declared inputs, e.g. `pr_number: 42`
+    #[serde(default)]
+    pub inputs: HashMap<String, serde_json::Value>,
+    /// 0-255, higher runs first when role slots or capacity are contended (default: 50)
+    #[serde(default)]
+    pub priority: Option<u8>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -132,19 +614,44 @@ pub struct CreateJobResponse {
     pub message: String,
 }
 
+/// Check a workflow for likely mistakes without running it
+pub async fn lint_workflow(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<LintWorkflowRequest>,
+) -> ApiResult<Json<LintWorkflowResponse>> {
+    let dag = WorkflowDag::from_workflow_with_limits(&req.workflow, &state.workflow_limits)?;
+    let mut warnings = dag.lint();
+    warnings.extend(dag.unknown_approvers(&approver_directory(&state)?));
+    Ok(Json(LintWorkflowResponse { warnings }))
+}
+
+/// Everyone a manual approval step may name: people in stored organizations and
+/// approval boards
+fn approver_directory(state: &AppState) -> anyhow::Result<ApproverDirectory> {
+    Ok(ApproverDirectory::new(
+        &state.index_store.list_organizations()?,
+        &state.approval_manager.list_boards(),
+    ))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LintWorkflowRequest {
+    pub workflow: WorkflowSpec,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LintWorkflowResponse {
+    pub warnings: Vec<LintWarning>,
+}
+
 // === Role Management Endpoints ===
 
 /// List all roles
 pub async fn list_roles(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListRolesResponse>> {
-    let roles = state.index_store.list_roles()?;
-    Ok(Json(ListRolesResponse { roles }))
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListRolesResponse {
-    pub roles: Vec<RoleSpec>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<RoleSpec>>> {
+    Ok(Json(query.apply(state.index_store.list_roles()?)?))
 }
 
 /// Get a specific role
@@ -162,12 +669,57 @@ pub async fn get_role(
     Ok(Json(role))
 }
 
+/// Get a role's budget usage for the current day against its configured limits
+pub async fn get_role_budget(
+    State(state): State<Arc<AppState>>,
+    Path(role_id): Path<String>,
+) -> ApiResult<Json<RoleBudgetResponse>> {
+    let role_id = RoleId::new(role_id);
+
+    let role = state
+        .index_store
+        .get_role(&role_id)?
+        .ok_or_else(|| anyhow::anyhow!("Role not found"))?;
+
+    let now = chrono::Utc::now();
+    let usage = state.policy_engine.get_budget_usage(&role_id).await?.as_of(now);
+
+    Ok(Json(RoleBudgetResponse {
+        remaining_tokens: role
+            .budgets
+            .hard_token_limit()
+            .map(|limit| limit.saturating_sub(usage.tokens_used)),
+        remaining_cost_cents: role
+            .budgets
+            .hard_cost_limit()
+            .map(|limit| limit.saturating_sub(usage.cost_cents)),
+        resets_at: BudgetUsage::next_reset(now),
+        role_id,
+        usage,
+        limits: role.budgets,
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleBudgetResponse {
+    pub role_id: RoleId,
+    pub usage: BudgetUsage,
+    pub limits: RoleBudgets,
+    /// `None` when the role has no token limit
+    pub remaining_tokens: Option<u64>,
+    /// `None` when the role has no cost limit
+    pub remaining_cost_cents: Option<u64>,
+    pub resets_at: chrono::DateTime<chrono::Utc>,
+}
+
 /// Create or update a role
 pub async fn create_role(
     State(state): State<Arc<AppState>>,
     Json(role): Json<RoleSpec>,
 ) -> ApiResult<Json<CreateRoleResponse>> {
+    role.validate()?;
     state.index_store.store_role(&role)?;
+    reload_policy_engine(&state).await?;
 
     tracing::info!("Created/updated role: {} ({})", role.name, role.id.0);
 
@@ -191,6 +743,7 @@ pub async fn delete_role(
     let role_id = RoleId::new(role_id);
 
     state.index_store.delete_role(&role_id)?;
+    reload_policy_engine(&state).await?;
 
     tracing::info!("Deleted role: {}", role_id.0);
 
@@ -209,14 +762,9 @@ pub struct DeleteRoleResponse {
 /// List all policies
 pub async fn list_policies(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListPoliciesResponse>> {
-    let policies = state.index_store.list_policies()?;
-    Ok(Json(ListPoliciesResponse { policies }))
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListPoliciesResponse {
-    pub policies: Vec<PolicySpec>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<PolicySpec>>> {
+    Ok(Json(query.apply(state.index_store.list_policies()?)?))
 }
 
 /// Get a specific policy
@@ -240,6 +788,7 @@ pub async fn create_policy(
     Json(policy): Json<PolicySpec>,
 ) -> ApiResult<Json<CreatePolicyResponse>> {
     state.index_store.store_policy(&policy)?;
+    reload_policy_engine(&state).await?;
 
     tracing::info!("Created/updated policy: {} ({})", policy.name, policy.id.0);
 
@@ -263,6 +812,7 @@ pub async fn delete_policy(
     let policy_id = PolicyId(policy_id);
 
     state.index_store.delete_policy(&policy_id)?;
+    reload_policy_engine(&state).await?;
 
     tracing::info!("Deleted policy: {}", policy_id.0);
 
@@ -276,19 +826,119 @@ pub struct DeletePolicyResponse {
     pub message: String,
 }
 
+/// Explain how the live policy engine would decide a tool call for a role, step by
+/// step, with budgets checked against the usage recorded so far. Nothing is recorded.
+pub async fn explain_tool_call(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ExplainToolCallRequest>,
+) -> ApiResult<Json<ExplainToolCallResponse>> {
+    let context = PolicyContext {
+        role_id: RoleId::new(req.role_id),
+        tool_id: req.tool_id,
+        tool_tier: req.tool_tier,
+        parameters: req.parameters,
+        timestamp: chrono::Utc::now(),
+    };
+    let (decision, steps) = state.policy_engine.check_tool_call_explained(&context).await?;
+
+    Ok(Json(ExplainToolCallResponse { decision, steps }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExplainToolCallRequest {
+    pub role_id: String,
+    pub tool_id: String,
+    #[serde(default)]
+    pub tool_tier: u8,
+    #[serde(default)]
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExplainToolCallResponse {
+    pub decision: PolicyDecision,
+    pub steps: Vec<EvaluationStep>,
+}
+
+/// Replay tool calls recorded in a time window against the stored policies plus a
+/// candidate policy, reporting calls whose decision would change. Nothing is stored,
+/// and the live policy engine is unaffected.
+pub async fn simulate_policy(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SimulatePolicyRequest>,
+) -> ApiResult<Json<PolicySimulation>> {
+    let until = req.until.unwrap_or_else(chrono::Utc::now);
+    let since = req.since.unwrap_or(until - chrono::Duration::hours(24));
+
+    let mut calls = Vec::new();
+    for run in state.index_store.list_runs()? {
+        if run.started_at > until || run.completed_at.is_some_and(|c| c < since) {
+            continue;
+        }
+
+        for event in state.event_log.get_run_events(run.id).await? {
+            if event.timestamp < since || event.timestamp > until {
+                continue;
+            }
+            let shiioo_core::events::EventType::ToolCallProposed {
+                step_id,
+                tool_id,
+                parameters_hash,
+                role_id: Some(role_id),
+                tool_tier,
+            } = event.event_type
+            else {
+                continue;
+            };
+
+            let parameters = match state.blob_store.get(&parameters_hash).await? {
+                Some(bytes) => serde_json::from_slice(&bytes)?,
+                None => serde_json::Value::Null,
+            };
+            calls.push(RecordedToolCall {
+                run_id: run.id,
+                step_id,
+                context: PolicyContext {
+                    role_id,
+                    tool_id,
+                    tool_tier,
+                    parameters,
+                    timestamp: event.timestamp,
+                },
+            });
+        }
+    }
+
+    let simulation = shiioo_core::policy::simulate_policy(
+        state.index_store.list_roles()?,
+        state.index_store.list_policies()?,
+        req.policy,
+        &calls,
+    )
+    .await?;
+
+    Ok(Json(simulation))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimulatePolicyRequest {
+    pub policy: PolicySpec,
+    /// Start of the replay window; defaults to 24 hours before `until`
+    #[serde(default)]
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    /// End of the replay window; defaults to now
+    #[serde(default)]
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 // === Organization Management Endpoints ===
 
 /// List all organizations
 pub async fn list_organizations(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListOrganizationsResponse>> {
-    let orgs = state.index_store.list_organizations()?;
-    Ok(Json(ListOrganizationsResponse { organizations: orgs }))
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListOrganizationsResponse {
-    pub organizations: Vec<Organization>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<Organization>>> {
+    Ok(Json(query.apply(state.index_store.list_organizations()?)?))
 }
 
 /// Get a specific organization
@@ -306,6 +956,20 @@ pub async fn get_organization(
     Ok(Json(org))
 }
 
+/// Render an organization's chart as Graphviz DOT
+pub async fn get_organization_chart_dot(
+    State(state): State<Arc<AppState>>,
+    Path(org_id): Path<String>,
+) -> ApiResult<([(header::HeaderName, &'static str); 1], String)> {
+    let org = state
+        .index_store
+        .get_organization(&OrgId::new(org_id))?
+        .ok_or_else(|| anyhow::anyhow!("Organization not found"))?;
+
+    let dot = OrganizationManager::new(org)?.to_dot();
+    Ok(([(header::CONTENT_TYPE, "text/vnd.graphviz")], dot))
+}
+
 /// Create or update an organization
 pub async fn create_organization(
     State(state): State<Arc<AppState>>,
@@ -353,17 +1017,39 @@ pub struct DeleteOrganizationResponse {
 
 // === Template Management Endpoints ===
 
-/// List all templates
+/// List templates, optionally filtered by category and/or a search over name and description
 pub async fn list_templates(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListTemplatesResponse>> {
+    axum::extract::Query(params): axum::extract::Query<ListTemplatesQueryParams>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<ProcessTemplate>>> {
+    let templates = TemplateProcessor::filter(
+        state.index_store.list_templates()?,
+        params.category.as_deref(),
+        params.search.as_deref(),
+    );
+    Ok(Json(query.apply(templates)?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListTemplatesQueryParams {
+    pub category: Option<String>,
+    pub search: Option<String>,
+}
+
+/// List distinct template categories with the number of templates in each
+pub async fn list_template_categories(
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<ListTemplateCategoriesResponse>> {
     let templates = state.index_store.list_templates()?;
-    Ok(Json(ListTemplatesResponse { templates }))
+    Ok(Json(ListTemplateCategoriesResponse {
+        categories: TemplateProcessor::categories(&templates),
+    }))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ListTemplatesResponse {
-    pub templates: Vec<ProcessTemplate>,
+pub struct ListTemplateCategoriesResponse {
+    pub categories: Vec<TemplateCategory>,
 }
 
 /// Get a specific template
@@ -440,19 +1126,43 @@ pub async fn instantiate_template(
         .get_template(&template_id)?
         .ok_or_else(|| anyhow::anyhow!("Template not found"))?;
 
-    let workflow = TemplateProcessor::instantiate(&template, &instance)?;
+    let roles = state.index_store.list_roles()?;
+    let entities = KnownEntities::new(&roles, &state.index_store.list_organizations()?);
+    let workflow = TemplateProcessor::instantiate_checked(&template, &instance, &entities)?;
+    let missing_dependencies = TemplateProcessor::missing_dependencies(&workflow, &roles);
 
-    tracing::info!("Instantiated template: {}", template.name);
+    let message = if missing_dependencies.is_empty() {
+        tracing::info!("Instantiated template: {}", template.name);
+        "Template instantiated successfully".to_string()
+    } else {
+        let roles: Vec<&str> = missing_dependencies
+            .roles
+            .iter()
+            .map(|r| r.role_id.0.as_str())
+            .collect();
+        tracing::warn!(
+            "Instantiated template {} references missing roles: {}",
+            template.name,
+            roles.join(", ")
+        );
+        format!(
+            "Template instantiated, but the workflow references missing roles: {}",
+            roles.join(", ")
+        )
+    };
 
     Ok(Json(InstantiateTemplateResponse {
         workflow,
-        message: "Template instantiated successfully".to_string(),
+        missing_dependencies,
+        message,
     }))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InstantiateTemplateResponse {
     pub workflow: WorkflowSpec,
+    /// Roles the workflow needs that don't exist yet; running it as-is would fail
+    pub missing_dependencies: MissingDependencies,
     pub message: String,
 }
 
@@ -465,24 +1175,20 @@ pub async fn compile_claude_config(
 ) -> ApiResult<Json<CompileClaudeConfigResponse>> {
     let role_id = RoleId::new(role_id);
 
-    // Get organization (assume first one for MVP)
-    let orgs = state.index_store.list_organizations()?;
-    let org = orgs
-        .first()
-        .ok_or_else(|| anyhow::anyhow!("No organization configured"))?;
-
-    // Get all roles and policies
-    let roles = state.index_store.list_roles()?;
-    let policies = state.index_store.list_policies()?;
-
-    // Compile configuration
-    let compiler = ClaudeCompiler::new(org.clone(), roles, policies);
-    let config = compiler.compile_for_role(&role_id)?;
-    let readme = compiler.generate_readme(&role_id)?;
+    // Read the generation before the store so a concurrent edit can't be cached as current.
+    // Uses the first organization for MVP.
+    let generation = state.index_store.config_generation();
+    let compiled = state
+        .claude_config_cache
+        .get_or_compile(None, role_id.clone(), generation, || {
+            ClaudeCompiler::from_store(state.index_store.as_ref(), None)?.compile(&role_id)
+        })
+        .await?;
 
     Ok(Json(CompileClaudeConfigResponse {
-        config,
-        readme,
+        config: compiled.config.clone(),
+        readme: compiled.readme.clone(),
+        warnings: compiled.warnings.clone(),
         message: "Claude configuration compiled successfully".to_string(),
     }))
 }
@@ -491,22 +1197,91 @@ pub async fn compile_claude_config(
 pub struct CompileClaudeConfigResponse {
     pub config: shiioo_core::types::ClaudeConfig,
     pub readme: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
     pub message: String,
 }
 
+/// Re-compile every role's Claude configuration in one pass, e.g. after an org-wide
+/// policy change, refreshing the compile cache with the results
+pub async fn compile_all_claude_configs(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<CompileAllQueryParams>,
+) -> ApiResult<Json<CompileAllClaudeConfigsResponse>> {
+    let org_id = params.org_id.map(OrgId::new);
+
+    // Read the generation before the store, as for single-role compiles
+    let generation = state.index_store.config_generation();
+    let compiler = ClaudeCompiler::from_store(state.index_store.as_ref(), org_id.as_ref())?;
+
+    let mut configs = HashMap::new();
+    let mut failed = HashMap::new();
+    for (role_id, result) in compiler.compile_all() {
+        match result {
+            Ok(compiled) => {
+                let compiled = state
+                    .claude_config_cache
+                    .get_or_compile(org_id.clone(), role_id.clone(), generation, || Ok(compiled))
+                    .await?;
+                configs.insert(
+                    role_id,
+                    CompiledRoleConfig {
+                        config: compiled.config.clone(),
+                        warnings: compiled.warnings.clone(),
+                    },
+                );
+            }
+            Err(e) => {
+                failed.insert(role_id, e.to_string());
+            }
+        }
+    }
+
+    Ok(Json(CompileAllClaudeConfigsResponse { configs, failed }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompileAllQueryParams {
+    /// Organization to compile for (default: the first one)
+    pub org_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompileAllClaudeConfigsResponse {
+    pub configs: HashMap<RoleId, CompiledRoleConfig>,
+    /// Roles that failed to compile, with the error
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub failed: HashMap<RoleId, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompiledRoleConfig {
+    pub config: shiioo_core::types::ClaudeConfig,
+    pub warnings: Vec<String>,
+}
+
+// === MCP Tool Registry Endpoint ===
+
+/// List the tools `ToolSequence` steps and role `allowed_tools` can refer to
+pub async fn list_mcp_tools(State(state): State<Arc<AppState>>) -> Json<ListMcpToolsResponse> {
+    Json(ListMcpToolsResponse {
+        tools: state.tool_registry.list_tools(),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListMcpToolsResponse {
+    pub tools: Vec<shiioo_mcp::tools::ToolInfo>,
+}
+
 // === Capacity Management Endpoints ===
 
 /// List all capacity sources
 pub async fn list_capacity_sources(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListCapacitySourcesResponse>> {
-    let sources = state.index_store.list_capacity_sources()?;
-    Ok(Json(ListCapacitySourcesResponse { sources }))
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListCapacitySourcesResponse {
-    pub sources: Vec<CapacitySource>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<CapacitySource>>> {
+    Ok(Json(query.apply(state.index_store.list_capacity_sources()?)?))
 }
 
 /// Get a specific capacity source
@@ -530,6 +1305,7 @@ pub async fn create_capacity_source(
     Json(source): Json<CapacitySource>,
 ) -> ApiResult<Json<CreateCapacitySourceResponse>> {
     state.index_store.store_capacity_source(&source)?;
+    state.capacity_broker.register_source(source.clone())?;
 
     tracing::info!(
         "Created/updated capacity source: {} ({})",
@@ -557,6 +1333,7 @@ pub async fn delete_capacity_source(
     let source_id = CapacitySourceId::new(source_id);
 
     state.index_store.delete_capacity_source(&source_id)?;
+    state.capacity_broker.remove_source(&source_id)?;
 
     tracing::info!("Deleted capacity source: {}", source_id.0);
 
@@ -583,46 +1360,169 @@ pub struct ListCapacityUsageResponse {
     pub usage: Vec<shiioo_core::types::CapacityUsage>,
 }
 
+/// Ingest usage reported by clients that call providers themselves.
+/// Records for unknown sources are rejected individually; the rest are stored. Clients
+/// retry failed batches, so records whose id was already ingested are skipped.
+pub async fn ingest_capacity_usage(
+    State(state): State<Arc<AppState>>,
+    Json(records): Json<Vec<shiioo_core::types::CapacityUsage>>,
+) -> ApiResult<Json<IngestCapacityUsageResponse>> {
+    let results: Vec<CapacityUsageResult> = records
+        .into_iter()
+        .map(|usage| {
+            let usage_id = usage.id.clone();
+            // Store before counting, so a record that fails to store, or was stored by an
+            // earlier attempt, isn't counted against its source's limits
+            let result = state
+                .capacity_broker
+                .get_source(&usage.source_id)
+                .ok_or_else(|| anyhow::anyhow!("Unknown capacity source: {}", usage.source_id.0))
+                .and_then(|_| state.index_store.store_capacity_usage(&usage))
+                .and_then(|stored| {
+                    if stored {
+                        state.capacity_broker.ingest_usage(usage)?;
+                    }
+                    Ok(!stored)
+                });
+            match result {
+                Ok(duplicate) => CapacityUsageResult { usage_id, duplicate, error: None },
+                Err(e) => CapacityUsageResult {
+                    usage_id,
+                    duplicate: false,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .collect();
+
+    let stored = results.iter().filter(|r| r.error.is_none() && !r.duplicate).count();
+    tracing::info!("Ingested {} of {} capacity usage records", stored, results.len());
+
+    Ok(Json(IngestCapacityUsageResponse { stored, results }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CapacityUsageResult {
+    pub usage_id: String,
+    /// Already ingested by an earlier request, so left uncounted
+    #[serde(default)]
+    pub duplicate: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IngestCapacityUsageResponse {
+    pub stored: usize,
+    pub results: Vec<CapacityUsageResult>,
+}
+
 /// Get capacity cost summary
 pub async fn get_capacity_cost(
     State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<CapacityCostQueryParams>,
 ) -> ApiResult<Json<CapacityCostResponse>> {
-    let usage = state.index_store.list_capacity_usage()?;
+    let all_usage = state.index_store.list_capacity_usage()?;
+    let now = chrono::Utc::now();
+    let projection = shiioo_core::analytics::project_month_cost(&all_usage, now);
+
+    let usage: Vec<_> = all_usage
+        .into_iter()
+        .filter(|u| params.since.is_none_or(|since| u.timestamp >= since))
+        .collect();
 
     // Calculate total cost and usage by source
     let total_cost: f64 = usage.iter().map(|u| u.cost).sum();
-    let total_tokens: u32 = usage.iter().map(|u| u.total_tokens).sum();
-    let total_requests: u32 = usage.iter().map(|u| u.request_count).sum();
+    let total_tokens =
+        usage.iter().fold(0u64, |sum, u| sum.saturating_add(u64::from(u.total_tokens)));
+    let total_requests =
+        usage.iter().fold(0u64, |sum, u| sum.saturating_add(u64::from(u.request_count)));
+    let buckets = match params.group_by {
+        Some(grouping) => {
+            shiioo_core::analytics::cost_buckets(&usage, grouping, params.since, now)?
+        }
+        None => Vec::new(),
+    };
 
     Ok(Json(CapacityCostResponse {
         total_cost,
         total_tokens,
         total_requests,
         record_count: usage.len(),
+        buckets,
+        projection,
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CapacityCostQueryParams {
+    /// Break the totals down by `hour` or `day`
+    pub group_by: Option<shiioo_core::analytics::CostGrouping>,
+    /// Only count usage recorded at or after this time
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CapacityCostResponse {
     pub total_cost: f64,
-    pub total_tokens: u32,
-    pub total_requests: u32,
+    pub total_tokens: u64,
+    pub total_requests: u64,
     pub record_count: usize,
+    /// Per-bucket totals when `group_by` is given, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buckets: Vec<shiioo_core::analytics::CostBucket>,
+    /// This month's spend so far and its linear projection, regardless of `since`
+    pub projection: shiioo_core::analytics::MonthlyCostProjection,
 }
 
-// === Routine Management Endpoints (Phase 5) ===
+/// List queued capacity requests that exhausted their attempts
+pub async fn list_dead_letters(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<DeadLetter>>> {
+    Ok(Json(query.apply_by(state.capacity_broker.list_dead_letters(), "request.id")?))
+}
 
-/// List all routines
-pub async fn list_routines(
+/// Explain the most recent capacity source selection
+pub async fn get_selection_trace(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListRoutinesResponse>> {
-    let routines = state.routine_scheduler.list_routines();
-    Ok(Json(ListRoutinesResponse { routines }))
+) -> ApiResult<Json<SelectionTraceResponse>> {
+    Ok(Json(SelectionTraceResponse {
+        trace: state.capacity_broker.last_selection_trace(),
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectionTraceResponse {
+    pub trace: Option<shiioo_core::types::SelectionTrace>,
+}
+
+/// Move a dead-lettered capacity request back onto the queue
+pub async fn requeue_dead_letter(
+    State(state): State<Arc<AppState>>,
+    Path(request_id): Path<String>,
+) -> ApiResult<Json<RequeueDeadLetterResponse>> {
+    let request = state.capacity_broker.requeue_dead_letter(&request_id)?;
+
+    Ok(Json(RequeueDeadLetterResponse {
+        request,
+        message: "Request requeued successfully".to_string(),
+    }))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ListRoutinesResponse {
-    pub routines: Vec<Routine>,
+pub struct RequeueDeadLetterResponse {
+    pub request: PriorityRequest,
+    pub message: String,
+}
+
+// === Routine Management Endpoints (Phase 5) ===
+
+/// List all routines
+pub async fn list_routines(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<Routine>>> {
+    Ok(Json(query.apply(state.routine_scheduler.list_routines())?))
 }
 
 /// Get a specific routine
@@ -657,6 +1557,7 @@ pub async fn create_routine(
         created_at: chrono::Utc::now(),
         created_by: req.created_by.unwrap_or_else(|| "system".to_string()),
         updated_at: chrono::Utc::now(),
+        tags: req.tags,
     };
 
     state.routine_scheduler.register_routine(routine.clone())?;
@@ -677,6 +1578,9 @@ pub struct CreateRoutineRequest {
     pub workflow: WorkflowSpec,
     pub enabled: Option<bool>,
     pub created_by: Option<String>,
+    /// Labels copied onto every run the routine starts
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -706,63 +1610,108 @@ pub struct DeleteRoutineResponse {
     pub message: String,
 }
 
-/// Enable a routine
+/// Enable a routine, returning it as updated
 pub async fn enable_routine(
     State(state): State<Arc<AppState>>,
     Path(routine_id): Path<String>,
-) -> ApiResult<Json<EnableRoutineResponse>> {
+) -> ApiResult<Json<Routine>> {
     let routine_id = RoutineId::new(routine_id);
 
     state.routine_scheduler.enable_routine(&routine_id)?;
 
     tracing::info!("Enabled routine: {}", routine_id.0);
 
-    Ok(Json(EnableRoutineResponse {
-        message: "Routine enabled successfully".to_string(),
-    }))
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct EnableRoutineResponse {
-    pub message: String,
+    Ok(Json(updated_routine(&state, &routine_id)?))
 }
 
-/// Disable a routine
+/// Disable a routine, returning it as updated
 pub async fn disable_routine(
     State(state): State<Arc<AppState>>,
     Path(routine_id): Path<String>,
-) -> ApiResult<Json<DisableRoutineResponse>> {
+) -> ApiResult<Json<Routine>> {
     let routine_id = RoutineId::new(routine_id);
 
     state.routine_scheduler.disable_routine(&routine_id)?;
 
     tracing::info!("Disabled routine: {}", routine_id.0);
 
-    Ok(Json(DisableRoutineResponse {
-        message: "Routine disabled successfully".to_string(),
+    Ok(Json(updated_routine(&state, &routine_id)?))
+}
+
+fn updated_routine(state: &AppState, routine_id: &RoutineId) -> anyhow::Result<Routine> {
+    state
+        .routine_scheduler
+        .get_routine(routine_id)
+        .ok_or_else(|| anyhow::anyhow!("Routine not found"))
+}
+
+/// Run a routine once now, without changing its schedule
+pub async fn trigger_routine(
+    State(state): State<Arc<AppState>>,
+    Path(routine_id): Path<String>,
+    axum::extract::Query(params): axum::extract::Query<TriggerRoutineQueryParams>,
+) -> ApiResult<Json<TriggerRoutineResponse>> {
+    let routine_id = RoutineId::new(routine_id);
+
+    let execution = state
+        .routine_scheduler
+        .trigger_now(&routine_id, params.force)
+        .await?;
+
+    tracing::info!("Manually triggered routine: {}", routine_id.0);
+
+    Ok(Json(TriggerRoutineResponse {
+        execution,
+        message: "Routine triggered successfully".to_string(),
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct TriggerRoutineQueryParams {
+    /// Trigger even if the routine is disabled
+    #[serde(default)]
+    pub force: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
-pub struct DisableRoutineResponse {
+pub struct TriggerRoutineResponse {
+    pub execution: RoutineExecution,
     pub message: String,
 }
 
-/// Get execution history for a routine
+/// Get execution history for a routine. Each execution's status is read from its run,
+/// since the status recorded at execution time goes stale once the run moves on.
 pub async fn get_routine_executions(
     State(state): State<Arc<AppState>>,
     Path(routine_id): Path<String>,
-) -> ApiResult<Json<GetRoutineExecutionsResponse>> {
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<RoutineExecutionView>>> {
     let routine_id = RoutineId::new(routine_id);
 
-    let executions = state.routine_scheduler.get_executions(&routine_id);
+    let executions = state
+        .routine_scheduler
+        .get_executions(&routine_id)
+        .into_iter()
+        .map(|mut execution| {
+            if let Some(run) = state.index_store.get_run(&execution.run_id)? {
+                execution.status = run.status;
+            }
+            Ok(RoutineExecutionView {
+                run_url: format!("/api/runs/{}", execution.run_id.0),
+                execution,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
-    Ok(Json(GetRoutineExecutionsResponse { executions }))
+    Ok(Json(query.apply(executions)?))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct GetRoutineExecutionsResponse {
-    pub executions: Vec<shiioo_core::types::RoutineExecution>,
+pub struct RoutineExecutionView {
+    #[serde(flatten)]
+    pub execution: RoutineExecution,
+    /// Where to fetch the execution's run
+    pub run_url: String,
 }
 
 // === Approval Board Management Endpoints (Phase 5) ===
@@ -770,21 +1719,16 @@ pub struct GetRoutineExecutionsResponse {
 /// List all approval boards
 pub async fn list_approval_boards(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListApprovalBoardsResponse>> {
-    let boards = state.approval_manager.list_boards();
-    Ok(Json(ListApprovalBoardsResponse { boards }))
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListApprovalBoardsResponse {
-    pub boards: Vec<ApprovalBoard>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<ApprovalBoard>>> {
+    Ok(Json(query.apply(state.approval_manager.list_boards())?))
 }
 
 /// Get a specific approval board
 pub async fn get_approval_board(
     State(state): State<Arc<AppState>>,
     Path(board_id): Path<String>,
-) -> ApiResult<Json<ApprovalBoard>> {
+) -> ApiResult<Json<ApprovalBoardResponse>> {
     let board_id = ApprovalBoardId::new(board_id);
 
     let board = state
@@ -792,7 +1736,20 @@ pub async fn get_approval_board(
         .get_board(&board_id)
         .ok_or_else(|| anyhow::anyhow!("Approval board not found"))?;
 
-    Ok(Json(board))
+    Ok(Json(ApprovalBoardResponse {
+        min_approvals_needed: board.min_approvals_needed(),
+        quorum_description: board.quorum_description(),
+        board,
+    }))
+}
+
+/// An approval board with its effective quorum requirement
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApprovalBoardResponse {
+    #[serde(flatten)]
+    pub board: ApprovalBoard,
+    pub min_approvals_needed: usize,
+    pub quorum_description: String,
 }
 
 /// Create an approval board
@@ -842,14 +1799,9 @@ pub struct DeleteApprovalBoardResponse {
 /// List all approvals
 pub async fn list_approvals(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListApprovalsResponse>> {
-    let approvals = state.approval_manager.list_approvals();
-    Ok(Json(ListApprovalsResponse { approvals }))
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListApprovalsResponse {
-    pub approvals: Vec<shiioo_core::types::Approval>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<shiioo_core::types::Approval>>> {
+    Ok(Json(query.apply(state.approval_manager.list_approvals())?))
 }
 
 /// Get a specific approval
@@ -867,41 +1819,231 @@ pub async fn get_approval(
     Ok(Json(approval))
 }
 
-/// Cast a vote on an approval
+/// Refuse with 403 an authenticated caller voting as anyone but their API key's user
+fn check_voter(key: &shiioo_core::api_key::ApiKey, voter_id: &PersonId) -> Result<(), Forbidden> {
+    if key.user_id == voter_id.0 {
+        return Ok(());
+    }
+    Err(Forbidden(format!("{} cannot vote as {}", key.user_id, voter_id.0)))
+}
+
+/// Cast a vote on an approval. An authenticated caller votes as their API key's user.
 pub async fn cast_vote(
     State(state): State<Arc<AppState>>,
     Path(approval_id): Path<String>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
     Json(req): Json<CastVoteRequest>,
-) -> ApiResult<Json<CastVoteResponse>> {
+) -> ApiResult<Json<shiioo_core::types::Approval>> {
     let approval_id = ApprovalId::new(approval_id);
     let voter_id = req.voter_id.clone();
 
+    // Authenticated callers need `Approval:Approve` on this approval's board
+    if let Some(axum::Extension(key)) = &api_key {
+        check_voter(key, &voter_id)?;
+        let approval = state
+            .approval_manager
+            .get_approval(&approval_id)
+            .ok_or_else(|| anyhow::anyhow!("Approval not found"))?;
+        let target = approval_target(&state, &approval)?;
+        authorize(&state, &key.user_id, Resource::Approval, Action::Approve, Some(&target))?;
+    }
+
     state
         .approval_manager
         .cast_vote(&approval_id, req.voter_id, req.decision, req.comment)?;
 
     tracing::info!(
-        "Vote cast on approval {}: {:?} by {}",
-        approval_id.0,
-        req.decision,
-        voter_id.0
+        "Vote cast on approval {}: {:?} by {}",
+        approval_id.0,
+        req.decision,
+        voter_id.0
+    );
+
+    // The approval with the new vote and the status it led to
+    let approval = state
+        .approval_manager
+        .get_approval(&approval_id)
+        .ok_or_else(|| anyhow::anyhow!("Approval not found"))?;
+
+    Ok(Json(approval))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CastVoteRequest {
+    pub voter_id: PersonId,
+    pub decision: VoteDecision,
+    pub comment: Option<String>,
+}
+
+/// The resource RBAC checks an approval against: its board, tagged with the tags of the
+/// run it gates, if any, keyed as in the run tag index (see `run_tag_key`)
+fn approval_target(
+    state: &AppState,
+    approval: &shiioo_core::types::Approval,
+) -> anyhow::Result<ResourceTarget> {
+    let mut tags = Vec::new();
+    if let ApprovalSubject::WorkflowRun { run_id } = &approval.subject {
+        if let Some(run) = state.index_store.get_run(run_id)? {
+            tags = run.tags.iter().map(|(key, value)| run_tag_key(key, value)).collect();
+        }
+    }
+    Ok(ResourceTarget::new(approval.board_id.0.clone()).with_tags(tags))
+}
+
+/// Refuse `user_id` with 403, auditing the denial, unless RBAC grants it `action` on
+/// `resource`, or on `target` alone when given
+fn authorize(
+    state: &AppState,
+    user_id: &str,
+    resource: Resource,
+    action: Action,
+    target: Option<&ResourceTarget>,
+) -> Result<(), Forbidden> {
+    let permission = Permission::new(resource, action);
+    let granted = match target {
+        Some(target) => state.rbac_manager.check_permission_on(user_id, &permission, target),
+        None => state.rbac_manager.check_permission(user_id, &permission),
+    };
+    if granted {
+        return Ok(());
+    }
+
+    let denied = format!("{:?}:{:?}", permission.resource, permission.action);
+    state.audit_log.log(
+        shiioo_core::audit::AuditCategory::Authorization,
+        shiioo_core::audit::AuditSeverity::Warning,
+        shiioo_core::audit::AuditAction::PermissionDenied {
+            user_id: user_id.to_string(),
+            permission: denied.clone(),
+            resource: format!("{:?}", permission.resource),
+        },
+        Some(user_id.to_string()),
+        None,
+        None,
+    );
+    Err(Forbidden(format!("{} lacks {}", user_id, denied)))
+}
+
+/// Approve or deny a pending approval regardless of its votes. The resolver is the
+/// caller's API key user, who must hold the RBAC `Approval:Override` permission on the
+/// approval's board, and the decision is written to the audit log.
+pub async fn force_resolve_approval(
+    State(state): State<Arc<AppState>>,
+    Path(approval_id): Path<String>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Json(req): Json<ForceResolveApprovalRequest>,
+) -> ApiResult<Json<shiioo_core::types::Approval>> {
+    let approval_id = ApprovalId::new(approval_id);
+
+    // An override must be attributable, so there is no anonymous path even with auth off
+    let axum::Extension(key) = api_key.ok_or_else(|| {
+        Forbidden("force-resolving an approval requires an authenticated caller".to_string())
+    })?;
+    let resolved_by = key.user_id.clone();
+    let tenant_id = key.tenant_id.as_ref().map(|t| t.0.clone());
+
+    let approval = state
+        .approval_manager
+        .get_approval(&approval_id)
+        .ok_or_else(|| anyhow::anyhow!("Approval not found"))?;
+    let target = approval_target(&state, &approval)?;
+    authorize(&state, &resolved_by, Resource::Approval, Action::Override, Some(&target))?;
+
+    let approval = state.approval_manager.force_resolve(
+        &approval_id,
+        req.decision,
+        resolved_by.clone(),
+        req.justification.clone(),
+    )?;
+
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("justification".to_string(), req.justification);
+    state.audit_log.record(
+        shiioo_core::audit::AuditCategory::Authorization,
+        shiioo_core::audit::AuditSeverity::Critical,
+        shiioo_core::audit::AuditAction::ApprovalForceResolved {
+            approval_id: approval_id.0.clone(),
+            status: format!("{:?}", approval.status),
+            resolved_by: resolved_by.clone(),
+        },
+        Some(resolved_by),
+        tenant_id,
+        None,
+        metadata,
+    );
+
+    Ok(Json(approval))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForceResolveApprovalRequest {
+    /// `approved` or `denied`
+    pub decision: shiioo_core::types::ApprovalStatus,
+    pub justification: String,
+}
+
+/// Cast one voter's decisions on several approvals, reporting each outcome separately.
+/// Authenticated callers vote as their API key's user and need `Approval:Approve` on
+/// each approval's board, as for single votes.
+pub async fn bulk_vote(
+    State(state): State<Arc<AppState>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Json(req): Json<BulkVoteRequest>,
+) -> ApiResult<Json<BulkVoteResponse>> {
+    if let Some(axum::Extension(key)) = &api_key {
+        check_voter(key, &req.voter_id)?;
+    }
+    let results = match &api_key {
+        None => state.approval_manager.cast_votes(&req.voter_id, req.votes),
+        Some(axum::Extension(key)) => req
+            .votes
+            .into_iter()
+            .map(|vote| {
+                let result = state
+                    .approval_manager
+                    .get_approval(&vote.approval_id)
+                    .ok_or_else(|| anyhow::anyhow!("Approval not found"))
+                    .and_then(|approval| approval_target(&state, &approval))
+                    .and_then(|target| {
+                        let (resource, action) = (Resource::Approval, Action::Approve);
+                        Ok(authorize(&state, &key.user_id, resource, action, Some(&target))?)
+                    })
+                    .and_then(|()| {
+                        state.approval_manager.cast_vote(
+                            &vote.approval_id,
+                            req.voter_id.clone(),
+                            vote.decision,
+                            vote.comment,
+                        )
+                    });
+                BulkVoteResult {
+                    approval_id: vote.approval_id,
+                    status: result.as_ref().ok().copied(),
+                    error: result.err().map(|e| e.to_string()),
+                }
+            })
+            .collect(),
+    };
+
+    tracing::info!(
+        "Bulk vote by {}: {} of {} votes accepted",
+        req.voter_id.0,
+        results.iter().filter(|r| r.error.is_none()).count(),
+        results.len()
     );
 
-    Ok(Json(CastVoteResponse {
-        message: "Vote cast successfully".to_string(),
-    }))
+    Ok(Json(BulkVoteResponse { results }))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct CastVoteRequest {
+pub struct BulkVoteRequest {
     pub voter_id: PersonId,
-    pub decision: VoteDecision,
-    pub comment: Option<String>,
+    pub votes: Vec<BulkVote>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct CastVoteResponse {
-    pub message: String,
+pub struct BulkVoteResponse {
+    pub results: Vec<BulkVoteResult>,
 }
 
 // === Config Change Management Endpoints (Phase 5) ===
@@ -909,14 +2051,9 @@ pub struct CastVoteResponse {
 /// List all config changes
 pub async fn list_config_changes(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListConfigChangesResponse>> {
-    let changes = state.config_change_manager.list_changes();
-    Ok(Json(ListConfigChangesResponse { changes }))
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListConfigChangesResponse {
-    pub changes: Vec<ConfigChange>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<ConfigChange>>> {
+    Ok(Json(query.apply(state.config_change_manager.list_changes())?))
 }
 
 /// Get a specific config change
@@ -939,10 +2076,16 @@ pub async fn propose_config_change(
     State(state): State<Arc<AppState>>,
     Json(req): Json<ProposeConfigChangeRequest>,
 ) -> ApiResult<Json<ProposeConfigChangeResponse>> {
+    // Snapshot the target ourselves so rollbacks restore what was really there
+    let before = match config_entity_id(&req.after) {
+        Some(id) => stored_config(&state, req.change_type, id)?,
+        None => None,
+    };
+
     let change = state.config_change_manager.propose_change(
         req.change_type,
         req.description,
-        req.before,
+        before,
         req.after,
         req.proposed_by,
         req.approval_board,
@@ -965,6 +2108,8 @@ pub async fn propose_config_change(
 pub struct ProposeConfigChangeRequest {
     pub change_type: ConfigChangeType,
     pub description: String,
+    /// Ignored; the server snapshots the target's current config itself
+    #[serde(default)]
     pub before: Option<String>,
     pub after: String,
     pub proposed_by: String,
@@ -978,25 +2123,212 @@ pub struct ProposeConfigChangeResponse {
     pub message: String,
 }
 
-/// Apply a config change
+/// Apply a config change, or with `?dry_run=true` report whether it would apply cleanly
 pub async fn apply_config_change(
     State(state): State<Arc<AppState>>,
     Path(change_id): Path<String>,
+    axum::extract::Query(params): axum::extract::Query<ApplyConfigChangeQueryParams>,
 ) -> ApiResult<Json<ApplyConfigChangeResponse>> {
     let change_id = ConfigChangeId::new(change_id);
 
-    state.config_change_manager.apply_change(&change_id)?;
+    let change = state
+        .config_change_manager
+        .get_change(&change_id)
+        .ok_or_else(|| anyhow::anyhow!("Config change not found"))?;
+
+    // Checked against `before`, so a stale change can't clobber newer state
+    let current = config_change_target(&state, &change)?;
+
+    if params.dry_run {
+        let mut errors = Vec::new();
+        if let Err(e) = state
+            .config_change_manager
+            .check_apply(&change_id, current.as_deref())
+        {
+            errors.push(e.to_string());
+        }
+        if let Err(e) = parse_config_change_target(&change) {
+            errors.push(e.to_string());
+        }
+
+        return Ok(Json(ApplyConfigChangeResponse {
+            message: if errors.is_empty() {
+                "Config change would apply cleanly".to_string()
+            } else {
+                "Config change would fail to apply".to_string()
+            },
+            dry_run: true,
+            errors,
+            current,
+            after: change.after.clone(),
+            change,
+        }));
+    }
+
+    let target = parse_config_change_target(&change)?;
+    // Applying is a leader-only write, stamped with the term this node was elected in.
+    // If a newer election has superseded that term it's rejected before changing anything.
+    let term = state.cluster_manager.leader_term().ok_or(NotLeader)?;
+    if let Some(expected) = params.term.filter(|expected| *expected != term) {
+        return Err(Conflict(format!(
+            "Change was meant for leader term {}, but this node leads term {}",
+            expected, term
+        ))
+        .into());
+    }
+    state.cluster_manager.fenced_write(term, || {
+        state
+            .config_change_manager
+            .apply_change(&change_id, current.as_deref())?;
+
+        if let Err(e) = write_config_change_target(&state, target) {
+            state.config_change_manager.mark_failed(&change_id, e.to_string())?;
+            return Err(e);
+        }
+        Ok(())
+    })?;
+    if matches!(change.change_type, ConfigChangeType::Role | ConfigChangeType::Policy) {
+        reload_policy_engine(&state).await?;
+    }
 
     tracing::info!("Applied config change: {}", change_id.0);
 
+    let applied = state
+        .config_change_manager
+        .get_change(&change_id)
+        .ok_or_else(|| anyhow::anyhow!("Config change not found"))?;
+
     Ok(Json(ApplyConfigChangeResponse {
         message: "Config change applied successfully".to_string(),
+        dry_run: false,
+        errors: vec![],
+        current,
+        after: change.after,
+        change: applied,
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ApplyConfigChangeQueryParams {
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Leader term the caller expects the change to be applied under; refused if this
+    /// node leads a different one
+    pub term: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApplyConfigChangeResponse {
     pub message: String,
+    /// Whether state was left untouched
+    pub dry_run: bool,
+    /// Why the change would not apply (dry runs only)
+    pub errors: Vec<String>,
+    /// JSON of the target before the change
+    pub current: Option<String>,
+    /// JSON of the target after the change
+    pub after: String,
+    /// The change as it now stands, e.g. `Applied` with its `applied_at`
+    pub change: ConfigChange,
+}
+
+/// JSON of a config change's target as currently stored, or `None` if it doesn't exist
+fn config_change_target(state: &AppState, change: &ConfigChange) -> anyhow::Result<Option<String>> {
+    match changed_entity_id(change) {
+        Some(id) => stored_config(state, change.change_type, id),
+        None => Ok(None),
+    }
+}
+
+/// JSON of the stored entity of the given type, or `None` if it doesn't exist
+fn stored_config(
+    state: &AppState,
+    change_type: ConfigChangeType,
+    id: String,
+) -> anyhow::Result<Option<String>> {
+    fn to_json<T: Serialize>(value: Option<T>) -> anyhow::Result<Option<String>> {
+        Ok(value.map(|v| serde_json::to_string(&v)).transpose()?)
+    }
+
+    match change_type {
+        ConfigChangeType::Role => to_json(state.index_store.get_role(&RoleId::new(id))?),
+        ConfigChangeType::Policy => to_json(state.index_store.get_policy(&PolicyId(id))?),
+        ConfigChangeType::Organization => {
+            to_json(state.index_store.get_organization(&OrgId::new(id))?)
+        }
+        ConfigChangeType::Template => to_json(state.index_store.get_template(&TemplateId::new(id))?),
+        ConfigChangeType::CapacitySource => {
+            to_json(state.index_store.get_capacity_source(&CapacitySourceId::new(id))?)
+        }
+        ConfigChangeType::Routine => to_json(state.routine_scheduler.get_routine(&RoutineId::new(id))),
+        ConfigChangeType::ApprovalBoard => {
+            to_json(state.approval_manager.get_board(&ApprovalBoardId::new(id)))
+        }
+    }
+}
+
+/// A config change's `after`, deserialized as its target type
+enum ConfigChangeTarget {
+    Role(RoleSpec),
+    Policy(PolicySpec),
+    Organization(Organization),
+    Template(ProcessTemplate),
+    CapacitySource(CapacitySource),
+    Routine(Routine),
+    ApprovalBoard(ApprovalBoard),
+}
+
+fn parse_config_change_target(change: &ConfigChange) -> anyhow::Result<ConfigChangeTarget> {
+    let after = &change.after;
+    let target = match change.change_type {
+        ConfigChangeType::Role => serde_json::from_str(after).map(ConfigChangeTarget::Role),
+        ConfigChangeType::Policy => serde_json::from_str(after).map(ConfigChangeTarget::Policy),
+        ConfigChangeType::Organization => {
+            serde_json::from_str(after).map(ConfigChangeTarget::Organization)
+        }
+        ConfigChangeType::Template => serde_json::from_str(after).map(ConfigChangeTarget::Template),
+        ConfigChangeType::CapacitySource => {
+            serde_json::from_str(after).map(ConfigChangeTarget::CapacitySource)
+        }
+        ConfigChangeType::Routine => serde_json::from_str(after).map(ConfigChangeTarget::Routine),
+        ConfigChangeType::ApprovalBoard => {
+            serde_json::from_str(after).map(ConfigChangeTarget::ApprovalBoard)
+        }
+    };
+
+    target.map_err(|e| {
+        anyhow::anyhow!("Invalid {:?} in config change {}: {}", change.change_type, change.id.0, e)
+    })
+}
+
+/// Load the stored roles and policies into the policy engine the executor checks tool
+/// calls against
+async fn reload_policy_engine(state: &AppState) -> anyhow::Result<()> {
+    state
+        .policy_engine
+        .reload(state.index_store.list_roles()?, state.index_store.list_policies()?)
+        .await;
+    Ok(())
+}
+
+/// Store a config change's `after` as the new state of its target
+fn write_config_change_target(state: &AppState, target: ConfigChangeTarget) -> anyhow::Result<()> {
+    match target {
+        ConfigChangeTarget::Role(role) => state.index_store.store_role(&role),
+        ConfigChangeTarget::Policy(policy) => state.index_store.store_policy(&policy),
+        ConfigChangeTarget::Organization(org) => state.index_store.store_organization(&org),
+        ConfigChangeTarget::Template(template) => state.index_store.store_template(&template),
+        ConfigChangeTarget::CapacitySource(source) => {
+            state.index_store.store_capacity_source(&source)?;
+            state.capacity_broker.register_source(source)
+        }
+        ConfigChangeTarget::Routine(routine) => {
+            // Stop the old schedule before replacing it
+            state.routine_scheduler.unregister_routine(&routine.id)?;
+            state.routine_scheduler.register_routine(routine)
+        }
+        ConfigChangeTarget::ApprovalBoard(board) => state.approval_manager.register_board(board),
+    }
 }
 
 /// Reject a config change
@@ -1004,7 +2336,7 @@ pub async fn reject_config_change(
     State(state): State<Arc<AppState>>,
     Path(change_id): Path<String>,
     Json(req): Json<RejectConfigChangeRequest>,
-) -> ApiResult<Json<RejectConfigChangeResponse>> {
+) -> ApiResult<Json<ConfigChange>> {
     let change_id = ConfigChangeId::new(change_id);
 
     state
@@ -1013,9 +2345,12 @@ pub async fn reject_config_change(
 
     tracing::info!("Rejected config change: {}", change_id.0);
 
-    Ok(Json(RejectConfigChangeResponse {
-        message: "Config change rejected successfully".to_string(),
-    }))
+    let change = state
+        .config_change_manager
+        .get_change(&change_id)
+        .ok_or_else(|| anyhow::anyhow!("Config change not found"))?;
+
+    Ok(Json(change))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1023,9 +2358,38 @@ pub struct RejectConfigChangeRequest {
     pub reason: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct RejectConfigChangeResponse {
-    pub message: String,
+/// Preview which active runs and routines a config change would affect
+pub async fn get_config_change_impact(
+    State(state): State<Arc<AppState>>,
+    Path(change_id): Path<String>,
+) -> ApiResult<Json<shiioo_core::config_change::ConfigChangeImpact>> {
+    let change_id = ConfigChangeId::new(change_id);
+
+    // Recover the workflow each in-flight run is executing from its RunStarted event.
+    // Paused runs, and runs parked waiting for a signal, resume under the new config too.
+    let mut active_runs = Vec::new();
+    for status in RunStatus::ACTIVE {
+        for run in state.index_store.list_runs_by_status(status)? {
+            let events = state.event_log.get_run_events(run.id).await?;
+            let workflow = events.into_iter().find_map(|e| match e.event_type {
+                shiioo_core::events::EventType::RunStarted { workflow_spec, .. } => {
+                    Some(workflow_spec)
+                }
+                _ => None,
+            });
+            if let Some(workflow) = workflow {
+                active_runs.push((run.id, workflow));
+            }
+        }
+    }
+
+    let routines = state.routine_scheduler.list_routines();
+
+    let impact = state
+        .config_change_manager
+        .preview_impact(&change_id, &active_runs, &routines)?;
+
+    Ok(Json(impact))
 }
 
 // === Observability Endpoints (Phase 6) ===
@@ -1034,11 +2398,13 @@ pub struct RejectConfigChangeResponse {
 pub async fn get_metrics(
     State(state): State<Arc<AppState>>,
 ) -> ApiResult<Json<MetricsResponse>> {
+    let captured_at = chrono::Utc::now();
     let counters = state.metrics.get_counters();
     let gauges = state.metrics.get_gauges();
     let histograms = state.metrics.get_histograms();
 
     Ok(Json(MetricsResponse {
+        captured_at,
         counters,
         gauges,
         histograms,
@@ -1047,11 +2413,52 @@ pub async fn get_metrics(
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MetricsResponse {
+    /// When the snapshot was taken, so clients can compute rates between snapshots
+    pub captured_at: chrono::DateTime<chrono::Utc>,
     pub counters: Vec<shiioo_core::metrics::Counter>,
     pub gauges: Vec<shiioo_core::metrics::Gauge>,
     pub histograms: Vec<shiioo_core::metrics::Histogram>,
 }
 
+/// Recent values of a counter or gauge, one series per label set
+pub async fn get_metric_history(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<MetricHistoryQueryParams>,
+) -> ApiResult<Json<MetricHistoryResponse>> {
+    let resolution = params
+        .resolution
+        .map(|secs| {
+            chrono::Duration::try_seconds(secs).ok_or_else(|| {
+                let mut errors = ValidationErrors::new();
+                errors.push("resolution", "Resolution is out of range");
+                anyhow::Error::from(errors)
+            })
+        })
+        .transpose()?;
+    let series = state
+        .metrics
+        .get_history(&params.metric, params.since, resolution)?;
+
+    Ok(Json(MetricHistoryResponse {
+        metric: params.metric,
+        series,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetricHistoryQueryParams {
+    pub metric: String,
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Bucket width in seconds; defaults to the configured history resolution
+    pub resolution: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetricHistoryResponse {
+    pub metric: String,
+    pub series: Vec<shiioo_core::metrics::MetricSeries>,
+}
+
 /// Get workflow analytics
 pub async fn get_workflow_analytics(
     State(state): State<Arc<AppState>>,
@@ -1095,7 +2502,11 @@ pub struct StepAnalyticsResponse {
 pub async fn get_execution_traces(
     State(state): State<Arc<AppState>>,
 ) -> ApiResult<Json<ExecutionTracesResponse>> {
-    let traces = state.analytics.get_recent_traces(50);
+    let usage = state.index_store.list_capacity_usage()?;
+    let mut traces = state.analytics.get_recent_traces(50);
+    for trace in &mut traces {
+        trace.attribute_usage(&usage);
+    }
     Ok(Json(ExecutionTracesResponse { traces }))
 }
 
@@ -1107,6 +2518,8 @@ pub struct ExecutionTracesResponse {
 /// Get specific execution trace
 pub async fn get_execution_trace(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
     Path(run_id): Path<String>,
 ) -> ApiResult<Json<shiioo_core::analytics::ExecutionTrace>> {
     let run_id = RunId(
@@ -1114,28 +2527,97 @@ pub async fn get_execution_trace(
             .parse()
             .map_err(|_| anyhow::anyhow!("Invalid run ID"))?,
     );
+    ensure_run_visible(&state, &tenant, &api_key, Action::Read, &run_id)?;
 
-    let trace = state
+    let mut trace = state
         .analytics
         .get_trace(&run_id)
         .ok_or_else(|| anyhow::anyhow!("Execution trace not found"))?;
 
+    trace.attribute_usage(&state.index_store.list_capacity_usage()?);
+
     Ok(Json(trace))
 }
 
-/// Get bottleneck analysis for a workflow
+/// Get bottleneck analysis for a workflow, ranked by duration (default) or cost
 pub async fn get_bottleneck_analysis(
     State(state): State<Arc<AppState>>,
     Path(workflow_id): Path<String>,
+    axum::extract::Query(params): axum::extract::Query<BottleneckQueryParams>,
 ) -> ApiResult<Json<shiioo_core::analytics::BottleneckReport>> {
+    let usage = state.index_store.list_capacity_usage()?;
     let report = state
         .analytics
-        .detect_bottlenecks(&workflow_id)
+        .detect_bottlenecks_ranked(&workflow_id, params.rank_by.unwrap_or_default(), &usage)
         .ok_or_else(|| anyhow::anyhow!("Bottleneck analysis not available for this workflow"))?;
 
     Ok(Json(report))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct BottleneckQueryParams {
+    pub rank_by: Option<shiioo_core::analytics::BottleneckRanking>,
+}
+
+/// The chain of steps that determines a workflow's wall-clock time, over the step
+/// dependencies recorded by its latest run
+pub async fn get_critical_path(
+    State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Path(workflow_id): Path<String>,
+) -> ApiResult<Json<CriticalPathResponse>> {
+    let latest = state
+        .index_store
+        .list_runs_by_work_item(&workflow_id)?
+        .into_iter()
+        .filter(|run| run_visible(&state, &tenant, &api_key, Action::Read, run))
+        .max_by_key(|run| run.started_at)
+        .ok_or_else(|| anyhow::anyhow!("Critical path not available for this workflow"))?;
+    let events = state.event_log.get_run_events(latest.id).await?;
+    let workflow = shiioo_core::workflow::executor::recorded_workflow(latest.id, &events)?;
+
+    let steps = state.analytics.critical_path(&workflow)?;
+    if steps.is_empty() {
+        return Err(anyhow::anyhow!("Critical path not available for this workflow").into());
+    }
+
+    let estimated_duration_secs = steps
+        .iter()
+        .filter_map(|step| state.analytics.get_step_stats(&step.0))
+        .map(|stats| stats.avg_duration_secs)
+        .sum();
+
+    Ok(Json(CriticalPathResponse {
+        workflow_id,
+        steps,
+        estimated_duration_secs,
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CriticalPathResponse {
+    pub workflow_id: String,
+    pub steps: Vec<StepId>,
+    /// Sum of the path's average step durations
+    pub estimated_duration_secs: f64,
+}
+
+/// Get approval turnaround analytics
+pub async fn get_approval_analytics(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<ApprovalAnalyticsQueryParams>,
+) -> ApiResult<Json<shiioo_core::approval::ApprovalStats>> {
+    let board_id = params.board_id.map(ApprovalBoardId::new);
+    let stats = state.approval_manager.get_approval_stats(board_id.as_ref());
+    Ok(Json(stats))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalAnalyticsQueryParams {
+    pub board_id: Option<String>,
+}
+
 /// Get system health status
 pub async fn get_health_status(
     State(state): State<Arc<AppState>>,
@@ -1192,12 +2674,39 @@ pub struct HealthStatusResponse {
 
 use shiioo_core::secrets::{Secret, SecretId, SecretType, RotationPolicy};
 
-/// Create a new secret
+/// Load a secret the request may see. Another tenant's secret is reported as not found.
+fn visible_secret(
+    state: &AppState,
+    tenant: &Option<axum::Extension<RequestTenant>>,
+    secret_id: &SecretId,
+) -> anyhow::Result<Secret> {
+    state
+        .secret_manager
+        .get_secret(secret_id)
+        .filter(|secret| secret_visible(tenant, secret))
+        .ok_or_else(|| anyhow::anyhow!("Secret not found"))
+}
+
+/// Whether the request's tenant, if any, owns the secret
+fn secret_visible(tenant: &Option<axum::Extension<RequestTenant>>, secret: &Secret) -> bool {
+    tenant
+        .as_ref()
+        .is_none_or(|t| t.sees(secret.tenant_id.as_ref()))
+}
+
+/// Create a new secret. It belongs to the tenant the caller's API key acts for, if any,
+/// and is encrypted under that tenant's key.
 pub async fn create_secret(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
     Json(req): Json<CreateSecretRequest>,
 ) -> ApiResult<Json<Secret>> {
-    let secret = state.secret_manager.create_secret(
+    if !req.force.unwrap_or(false) {
+        req.secret_type.validate(&req.value)?;
+    }
+    let tenant_id = tenant.and_then(|t| t.0 .0.clone());
+    let secret = state.secret_manager.store_secret(
+        tenant_id,
         req.name,
         req.description,
         req.secret_type,
@@ -1217,32 +2726,34 @@ pub struct CreateSecretRequest {
     pub value: String,
     pub rotation_policy: Option<RotationPolicy>,
     pub tags: Option<std::collections::HashMap<String, String>>,
+    /// Store the value even if it doesn't look like its `secret_type` (default: false)
+    pub force: Option<bool>,
 }
 
 /// List all secrets (without values)
 pub async fn list_secrets(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListSecretsResponse>> {
-    let secrets = state.secret_manager.list_secrets();
-    Ok(Json(ListSecretsResponse { secrets }))
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListSecretsResponse {
-    pub secrets: Vec<Secret>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<Secret>>> {
+    let secrets = state
+        .secret_manager
+        .list_secrets()
+        .into_iter()
+        .filter(|secret| secret_visible(&tenant, secret))
+        .collect();
+    Ok(Json(query.apply(secrets)?))
 }
 
 /// Get secret metadata (without value)
 pub async fn get_secret(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
     Path(secret_id): Path<String>,
 ) -> ApiResult<Json<Secret>> {
     let secret_id = SecretId::new(secret_id);
 
-    let secret = state
-        .secret_manager
-        .get_secret(&secret_id)
-        .ok_or_else(|| anyhow::anyhow!("Secret not found"))?;
+    let secret = visible_secret(&state, &tenant, &secret_id)?;
 
     Ok(Json(secret))
 }
@@ -1250,9 +2761,11 @@ pub async fn get_secret(
 /// Get decrypted secret value
 pub async fn get_secret_value(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
     Path(secret_id): Path<String>,
 ) -> ApiResult<Json<SecretValueResponse>> {
     let secret_id = SecretId::new(secret_id);
+    visible_secret(&state, &tenant, &secret_id)?;
 
     let value = state.secret_manager.get_secret_value(&secret_id)?;
 
@@ -1267,10 +2780,12 @@ pub struct SecretValueResponse {
 /// Rotate a secret (create new version)
 pub async fn rotate_secret(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
     Path(secret_id): Path<String>,
     Json(req): Json<RotateSecretRequest>,
 ) -> ApiResult<Json<Secret>> {
     let secret_id = SecretId::new(secret_id);
+    visible_secret(&state, &tenant, &secret_id)?;
 
     let secret = state.secret_manager.rotate_secret(&secret_id, req.new_value)?;
 
@@ -1285,9 +2800,11 @@ pub struct RotateSecretRequest {
 /// Delete a secret
 pub async fn delete_secret(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
     Path(secret_id): Path<String>,
 ) -> ApiResult<Json<DeleteSecretResponse>> {
     let secret_id = SecretId::new(secret_id);
+    visible_secret(&state, &tenant, &secret_id)?;
 
     state.secret_manager.delete_secret(&secret_id)?;
 
@@ -1304,10 +2821,12 @@ pub struct DeleteSecretResponse {
 /// Update secret metadata
 pub async fn update_secret_metadata(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
     Path(secret_id): Path<String>,
     Json(req): Json<UpdateSecretMetadataRequest>,
 ) -> ApiResult<Json<Secret>> {
     let secret_id = SecretId::new(secret_id);
+    visible_secret(&state, &tenant, &secret_id)?;
 
     let secret = state.secret_manager.update_secret_metadata(
         &secret_id,
@@ -1331,9 +2850,11 @@ pub struct UpdateSecretMetadataRequest {
 /// Get secret version history
 pub async fn get_secret_versions(
     State(state): State<Arc<AppState>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
     Path(secret_id): Path<String>,
 ) -> ApiResult<Json<SecretVersionsResponse>> {
     let secret_id = SecretId::new(secret_id);
+    visible_secret(&state, &tenant, &secret_id)?;
 
     let versions = state.secret_manager.get_secret_versions(&secret_id);
 
@@ -1348,9 +2869,76 @@ pub struct SecretVersionsResponse {
 /// Get secrets needing rotation
 pub async fn get_secrets_needing_rotation(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListSecretsResponse>> {
-    let secrets = state.secret_manager.get_secrets_needing_rotation();
-    Ok(Json(ListSecretsResponse { secrets }))
+    tenant: Option<axum::Extension<RequestTenant>>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<Secret>>> {
+    let secrets = state
+        .secret_manager
+        .get_secrets_needing_rotation()
+        .into_iter()
+        .filter(|secret| secret_visible(&tenant, secret))
+        .collect();
+    Ok(Json(query.apply(secrets)?))
+}
+
+/// Shortest master key accepted by `rotate_master_key`
+const MIN_MASTER_KEY_BYTES: usize = 32;
+
+/// Re-encrypt every secret under a new master key. Authenticated callers must be RBAC
+/// admins acting for no particular tenant.
+pub async fn rotate_master_key(
+    State(state): State<Arc<AppState>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    tenant: Option<axum::Extension<RequestTenant>>,
+    Json(req): Json<RotateMasterKeyRequest>,
+) -> ApiResult<Json<RotateMasterKeyResponse>> {
+    if let Some(axum::Extension(caller)) = &api_key {
+        if !is_rbac_admin(&state, &caller.user_id) || tenant.is_some_and(|t| t.0 .0.is_some()) {
+            return Err(Forbidden(format!(
+                "{} may not rotate the secrets master key",
+                caller.user_id
+            ))
+            .into());
+        }
+    }
+
+    let mut errors = ValidationErrors::new();
+    if req.new_master_key.len() < MIN_MASTER_KEY_BYTES {
+        errors.push(
+            "new_master_key",
+            format!("must be at least {} bytes", MIN_MASTER_KEY_BYTES),
+        );
+    }
+    errors.into_result()?;
+
+    let reencrypted = state
+        .secret_manager
+        .rotate_master_key(req.new_master_key.as_bytes())?;
+
+    state.audit_log.log(
+        shiioo_core::audit::AuditCategory::SecretAccess,
+        shiioo_core::audit::AuditSeverity::Warning,
+        shiioo_core::audit::AuditAction::SecretRotated {
+            secret_id: "master_key".to_string(),
+            version: 0,
+        },
+        api_key.map(|k| k.0.user_id),
+        None,
+        None,
+    );
+
+    Ok(Json(RotateMasterKeyResponse { reencrypted }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RotateMasterKeyRequest {
+    pub new_master_key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RotateMasterKeyResponse {
+    /// Secret values and versions re-encrypted under the new key
+    pub reencrypted: usize,
 }
 
 // ============================================================================
@@ -1399,14 +2987,9 @@ pub struct RegisterTenantRequest {
 /// List all tenants
 pub async fn list_tenants(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListTenantsResponse>> {
-    let tenants = state.tenant_manager.list_tenants();
-    Ok(Json(ListTenantsResponse { tenants }))
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListTenantsResponse {
-    pub tenants: Vec<Tenant>,
+    axum::extract::Query(query): axum::extract::Query<ListQueryParams>,
+) -> ApiResult<Json<Paginated<Tenant>>> {
+    Ok(Json(query.apply(state.tenant_manager.list_tenants())?))
 }
 
 /// Get a specific tenant
@@ -1530,6 +3113,48 @@ pub async fn activate_tenant(
     Ok(Json(tenant))
 }
 
+/// Clone a tenant's configuration into a new tenant. If its storage can't be set up or
+/// the configuration can't be copied, the new tenant is deleted again.
+pub async fn clone_tenant(
+    State(state): State<Arc<AppState>>,
+    Path(tenant_id): Path<String>,
+    Json(req): Json<CloneTenantRequest>,
+) -> ApiResult<Json<CloneTenantResponse>> {
+    let source_id = TenantId::new(tenant_id);
+
+    let tenant = state.tenant_manager.clone_tenant(&source_id, req.name)?;
+
+    let copied = match state
+        .tenant_storage
+        .initialize_tenant(&tenant.id)
+        .and_then(|()| state.tenant_storage.clone_tenant_config(&source_id, &tenant.id))
+    {
+        Ok(copied) => copied,
+        Err(e) => {
+            if let Err(cleanup) = state.tenant_storage.delete_tenant_data(&tenant.id) {
+                tracing::error!("Failed to delete storage of tenant {}: {}", tenant.id.0, cleanup);
+            }
+            state.tenant_manager.delete_tenant(&tenant.id)?;
+            return Err(e.context(format!("Failed to clone tenant {}", source_id.0)).into());
+        }
+    };
+
+    tracing::info!("Cloned tenant {} into {} ({})", source_id.0, tenant.name, tenant.id.0);
+
+    Ok(Json(CloneTenantResponse { tenant, copied }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloneTenantRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloneTenantResponse {
+    pub tenant: Tenant,
+    pub copied: shiioo_core::storage::TenantConfigCloneSummary,
+}
+
 /// Get tenant storage statistics
 pub async fn get_tenant_storage_stats(
     State(state): State<Arc<AppState>>,
@@ -1585,6 +3210,26 @@ pub struct ListNodesResponse {
     pub nodes: Vec<ClusterNode>,
 }
 
+/// Pick a healthy node matching the placement labels given as query parameters,
+/// e.g. `?region=eu-west&gpu=a100`
+pub async fn select_cluster_node(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(requirements): axum::extract::Query<
+        std::collections::HashMap<String, String>,
+    >,
+) -> ApiResult<Json<SelectNodeResponse>> {
+    let node = state
+        .cluster_manager
+        .select_node(&requirements)
+        .and_then(|node_id| state.cluster_manager.get_node(&node_id));
+    Ok(Json(SelectNodeResponse { node }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectNodeResponse {
+    pub node: Option<ClusterNode>,
+}
+
 /// Get cluster node
 pub async fn get_cluster_node(
     State(state): State<Arc<AppState>>,
@@ -1604,21 +3249,52 @@ pub async fn get_cluster_node(
 pub async fn node_heartbeat(
     State(state): State<Arc<AppState>>,
     Path(node_id): Path<String>,
+    axum::extract::Query(params): axum::extract::Query<HeartbeatQueryParams>,
 ) -> ApiResult<Json<HeartbeatResponse>> {
     let node_id = NodeId::new(node_id);
 
     state.cluster_manager.heartbeat(&node_id)?;
+    if let Some(term) = params.term {
+        state.cluster_manager.observe_term(term)?;
+    }
 
     tracing::debug!("Heartbeat received from node: {}", node_id.0);
 
     Ok(Json(HeartbeatResponse {
         message: "Heartbeat acknowledged".to_string(),
+        term: state.cluster_manager.current_term(),
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct HeartbeatQueryParams {
+    /// Latest leader term the node knows of, adopted here if it is newer
+    pub term: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HeartbeatResponse {
     pub message: String,
+    /// Current leader term, for the node to adopt with `ClusterManager::observe_term`
+    pub term: u64,
+}
+
+/// Stop assigning new work to a node while it finishes what it's running
+pub async fn drain_cluster_node(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<String>,
+) -> ApiResult<Json<ClusterNode>> {
+    let node = state.cluster_manager.drain_node(&NodeId::new(node_id))?;
+    Ok(Json(node))
+}
+
+/// Return a draining node to service
+pub async fn undrain_cluster_node(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<String>,
+) -> ApiResult<Json<ClusterNode>> {
+    let node = state.cluster_manager.undrain_node(&NodeId::new(node_id))?;
+    Ok(Json(node))
 }
 
 /// Remove cluster node
@@ -1669,6 +3345,7 @@ pub async fn get_cluster_health(
         healthy_nodes,
         has_leader: leader.is_some(),
         leader_id: leader.map(|l| l.id.0),
+        by_region: state.cluster_manager.region_health(),
     }))
 }
 
@@ -1678,6 +3355,7 @@ pub struct ClusterHealthResponse {
     pub healthy_nodes: usize,
     pub has_leader: bool,
     pub leader_id: Option<String>,
+    pub by_region: std::collections::HashMap<String, shiioo_core::cluster::RegionHealth>,
 }
 
 // ============================================================================
@@ -1710,6 +3388,42 @@ pub struct AuditQueryParams {
     pub end_time: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Append a batch of events from an external component to the audit chain. The
+/// submitter is the caller's API key, whose user must hold the RBAC `AuditLog:Create`
+/// permission (the `audit_writer` role).
+pub async fn ingest_audit_entries(
+    State(state): State<Arc<AppState>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Json(req): Json<IngestAuditRequest>,
+) -> ApiResult<Json<IngestAuditResponse>> {
+    // Entries in the chain must be attributable, so there is no anonymous path even
+    // with auth off
+    let axum::Extension(key) = api_key.ok_or_else(|| {
+        Forbidden("ingesting audit entries requires an authenticated caller".to_string())
+    })?;
+    let submitted_by = key.user_id.clone();
+    let tenant_id = key.tenant_id.as_ref().map(|t| t.0.clone());
+
+    authorize(&state, &submitted_by, Resource::AuditLog, Action::Create, None)?;
+
+    let entries = state.audit_log.ingest(req.entries, submitted_by, tenant_id)?;
+
+    tracing::info!("Ingested {} audit entries", entries.len());
+
+    Ok(Json(IngestAuditResponse { entries }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IngestAuditRequest {
+    pub entries: Vec<shiioo_core::audit::AuditSubmission>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IngestAuditResponse {
+    /// The recorded entries, with their assigned IDs and chain hashes
+    pub entries: Vec<shiioo_core::audit::AuditEntry>,
+}
+
 /// Get audit log statistics
 pub async fn get_audit_statistics(
     State(state): State<Arc<AppState>>,
@@ -1718,11 +3432,18 @@ pub async fn get_audit_statistics(
     Ok(Json(stats))
 }
 
-/// Verify audit log chain integrity
+/// Verify audit log chain integrity, from genesis or from a signed checkpoint
 pub async fn verify_audit_chain(
     State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<VerifyAuditChainQueryParams>,
 ) -> ApiResult<Json<AuditChainVerification>> {
-    let is_valid = state.audit_log.verify_chain();
+    let result = match params.from_checkpoint {
+        Some(index) => state.audit_log.verify_chain_from(index),
+        None => state.audit_log.verify_chain_detailed(),
+    };
+    let errors = result.err().unwrap_or_default();
+    let is_valid = errors.is_empty();
+
     Ok(Json(AuditChainVerification {
         is_valid,
         message: if is_valid {
@@ -1730,13 +3451,28 @@ pub async fn verify_audit_chain(
         } else {
             "Audit log chain integrity check failed".to_string()
         },
+        errors,
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct VerifyAuditChainQueryParams {
+    /// Entry index of the checkpoint to start verification from
+    pub from_checkpoint: Option<usize>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AuditChainVerification {
     pub is_valid: bool,
     pub message: String,
+    pub errors: Vec<String>,
+}
+
+/// List signed audit checkpoints
+pub async fn list_audit_checkpoints(
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<Vec<shiioo_core::audit::AuditCheckpoint>>> {
+    Ok(Json(state.audit_log.list_checkpoints()))
 }
 
 /// List RBAC roles
@@ -1846,9 +3582,14 @@ pub async fn check_user_permission(
         request.action.clone(),
     );
 
-    let has_permission = state
-        .rbac_manager
-        .check_permission(&request.user_id, &permission);
+    let has_permission = match &request.target {
+        Some(target) => state
+            .rbac_manager
+            .check_permission_on(&request.user_id, &permission, target),
+        None => state
+            .rbac_manager
+            .check_permission(&request.user_id, &permission),
+    };
 
     Ok(Json(PermissionCheckResponse {
         has_permission,
@@ -1863,6 +3604,9 @@ pub struct CheckPermissionRequest {
     pub user_id: String,
     pub resource: shiioo_core::rbac::Resource,
     pub action: shiioo_core::rbac::Action,
+    /// Check against one concrete resource instead of the resource type
+    #[serde(default)]
+    pub target: Option<shiioo_core::rbac::ResourceTarget>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1873,16 +3617,120 @@ pub struct PermissionCheckResponse {
     pub action: shiioo_core::rbac::Action,
 }
 
+/// Issue an API key. The raw key is only returned in this response. Authenticated
+/// callers may only issue keys for their own user unless they are RBAC admins.
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Json(request): Json<shiioo_core::api_key::NewApiKey>,
+) -> ApiResult<Json<CreateApiKeyResponse>> {
+    if let Some(axum::Extension(caller)) = &api_key {
+        if request.user_id != caller.user_id && !is_rbac_admin(&state, &caller.user_id) {
+            return Err(Forbidden(format!(
+                "{} may only issue keys for itself",
+                caller.user_id
+            ))
+            .into());
+        }
+    }
+
+    let (key, raw_key) = state.api_keys.create(request)?;
+
+    state.audit_log.log(
+        shiioo_core::audit::AuditCategory::SecretAccess,
+        shiioo_core::audit::AuditSeverity::Info,
+        shiioo_core::audit::AuditAction::SecretCreated {
+            secret_id: key.id.0.clone(),
+            secret_type: "api_key".to_string(),
+        },
+        Some(key.user_id.clone()),
+        key.tenant_id.as_ref().map(|t| t.0.clone()),
+        None,
+    );
+
+    Ok(Json(CreateApiKeyResponse { key, raw_key }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateApiKeyResponse {
+    pub key: shiioo_core::api_key::ApiKey,
+    pub raw_key: String,
+}
+
+/// Whether the user holds the RBAC admin role's full access
+fn is_rbac_admin(state: &AppState, user_id: &str) -> bool {
+    state
+        .rbac_manager
+        .get_user_permissions(user_id)
+        .contains(&Permission::new(Resource::All, Action::All))
+}
+
+/// List API keys (hashes are never returned). Authenticated callers only see their own
+/// user's keys unless they are RBAC admins.
+pub async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+) -> ApiResult<Json<Vec<shiioo_core::api_key::ApiKey>>> {
+    let keys = state
+        .api_keys
+        .list()
+        .into_iter()
+        .filter(|key| match &api_key {
+            Some(axum::Extension(caller)) => {
+                key.user_id == caller.user_id || is_rbac_admin(&state, &caller.user_id)
+            }
+            None => true,
+        })
+        .collect();
+
+    Ok(Json(keys))
+}
+
+/// Revoke an API key. Authenticated callers may only revoke their own user's keys unless
+/// they are RBAC admins.
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    api_key: Option<axum::Extension<shiioo_core::api_key::ApiKey>>,
+    Path(key_id): Path<String>,
+) -> ApiResult<Json<SuccessResponse>> {
+    let key_id = shiioo_core::api_key::ApiKeyId::new(key_id);
+
+    if let Some(axum::Extension(caller)) = &api_key {
+        let owner = state
+            .api_keys
+            .get(&key_id)
+            .map(|key| key.user_id)
+            .ok_or_else(|| anyhow::anyhow!("API key not found"))?;
+        if owner != caller.user_id && !is_rbac_admin(&state, &caller.user_id) {
+            return Err(Forbidden(format!(
+                "{} may only revoke its own keys",
+                caller.user_id
+            ))
+            .into());
+        }
+    }
+
+    state.api_keys.revoke(&key_id)?;
+    let key_id = key_id.0;
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        message: format!("API key {} revoked", key_id),
+    }))
+}
+
 /// Generate compliance report
 pub async fn generate_compliance_report(
     State(state): State<Arc<AppState>>,
     Json(request): Json<ComplianceReportRequest>,
 ) -> ApiResult<Json<shiioo_core::compliance::ComplianceReport>> {
-    let report = state.compliance_checker.generate_report(
-        request.framework,
-        request.period_start,
-        request.period_end,
-    );
+    // Evaluate on the blocking pool so large audit logs don't stall the runtime
+    let checker = state.compliance_checker.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        checker.generate_report(request.framework, request.period_start, request.period_end)
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Compliance report task failed: {}", e))?;
 
     // Log audit event
     state.audit_log.log(
@@ -1907,6 +3755,46 @@ pub struct ComplianceReportRequest {
     pub period_end: chrono::DateTime<chrono::Utc>,
 }
 
+/// Start generating a compliance report in the background
+pub async fn start_compliance_report(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ComplianceReportRequest>,
+) -> ApiResult<Json<shiioo_core::compliance::ComplianceReportJob>> {
+    let job = state.compliance_reports.start(
+        &state.compliance_checker,
+        request.framework,
+        request.period_start,
+        request.period_end,
+    );
+
+    state.audit_log.log(
+        shiioo_core::audit::AuditCategory::ComplianceEvent,
+        shiioo_core::audit::AuditSeverity::Info,
+        shiioo_core::audit::AuditAction::ComplianceCheckStarted {
+            check_id: job.id.clone(),
+            framework: format!("{:?}", request.framework),
+        },
+        Some("system".to_string()),
+        None,
+        None,
+    );
+
+    Ok(Json(job))
+}
+
+/// Poll a background compliance report
+pub async fn get_compliance_report_job(
+    State(state): State<Arc<AppState>>,
+    Path(report_id): Path<String>,
+) -> ApiResult<Json<shiioo_core::compliance::ComplianceReportJob>> {
+    let job = state
+        .compliance_reports
+        .get(&report_id)
+        .ok_or_else(|| anyhow::anyhow!("Compliance report not found"))?;
+
+    Ok(Json(job))
+}
+
 /// Run security scan
 pub async fn run_security_scan(
     State(state): State<Arc<AppState>>,
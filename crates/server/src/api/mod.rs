@@ -1,3 +1,4 @@
+use crate::background::BackgroundTasks;
 use crate::config::{AppState, ServerConfig};
 use crate::ui;
 use anyhow::Result;
@@ -9,6 +10,8 @@ use axum::{
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use shiioo_core::cluster::StaleTermError;
+use shiioo_core::validation::{ValidationError, ValidationErrors};
 use std::sync::Arc;
 use tower_http::{
     cors::CorsLayer,
@@ -20,41 +23,67 @@ mod handlers;
 /// Start the API server
 pub async fn serve(addr: &str, config: ServerConfig) -> Result<()> {
     let state = AppState::new(&config)?;
+    let background = BackgroundTasks::start(&state, &config.background);
 
     // Build GraphQL schema (Phase 10)
     let schema = crate::graphql::build_schema(Arc::new(state.clone()));
 
+    let event_log = state.event_log.clone();
     let app = create_router(state, schema);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
     tracing::info!("API server listening on {}", addr);
 
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+            tracing::info!("Shutting down API server");
+        })
+        .await?;
+
+    // Let in-progress passes finish, then persist events still queued for the writer
+    background.shutdown().await;
+    event_log.flush().await?;
 
     Ok(())
 }
 
 /// Create the API router
 fn create_router(state: AppState, schema: crate::graphql::ShiiooSchema) -> Router {
-    Router::new()
+    let state = Arc::new(state);
+
+    let router = Router::new()
         // GraphQL endpoints (Phase 10)
         .route("/api/graphql", post(crate::graphql::graphql_handler))
         .route("/api/graphql", get(crate::graphql::graphql_playground))
         .route("/api/graphql/ws", get(crate::graphql::graphql_subscription_handler))
         // API routes
         .route("/api/health", get(health_check))
+        .route("/api/describe", get(describe))
         .route("/api/runs", get(handlers::list_runs))
+        .route("/api/runs/compare", get(handlers::compare_runs))
         .route("/api/runs/{run_id}", get(handlers::get_run))
+        .route("/api/runs/by-external/{external_id}", get(handlers::get_run_by_external_id))
+        .route("/api/runs/{run_id}/eta", get(handlers::get_run_eta))
         .route("/api/runs/{run_id}/events", get(handlers::get_run_events))
+        .route("/api/runs/{run_id}/events/tail", get(handlers::tail_run_events))
+        .route("/api/runs/{run_id}/logs", get(handlers::get_run_logs))
+        .route("/api/runs/{run_id}/signal", post(handlers::signal_run))
+        .route("/api/runs/{run_id}/retry", post(handlers::retry_run))
+        .route("/api/runs/{run_id}/replay", post(handlers::replay_run))
         .route("/api/jobs", post(handlers::create_job))
+        .route("/api/jobs/lint", post(handlers::lint_workflow))
         // Role management
         .route("/api/roles", get(handlers::list_roles))
         .route("/api/roles", post(handlers::create_role))
         .route("/api/roles/{role_id}", get(handlers::get_role))
         .route("/api/roles/{role_id}", delete(handlers::delete_role))
+        .route("/api/roles/{role_id}/budget", get(handlers::get_role_budget))
         // Policy management
         .route("/api/policies", get(handlers::list_policies))
         .route("/api/policies", post(handlers::create_policy))
+        .route("/api/policies/explain", post(handlers::explain_tool_call))
+        .route("/api/policies/simulate", post(handlers::simulate_policy))
         .route("/api/policies/{policy_id}", get(handlers::get_policy))
         .route("/api/policies/{policy_id}", delete(handlers::delete_policy))
         // Organization management
@@ -62,21 +91,30 @@ fn create_router(state: AppState, schema: crate::graphql::ShiiooSchema) -> Route
         .route("/api/organizations", post(handlers::create_organization))
         .route("/api/organizations/{org_id}", get(handlers::get_organization))
         .route("/api/organizations/{org_id}", delete(handlers::delete_organization))
+        .route("/api/organizations/{org_id}/chart.dot", get(handlers::get_organization_chart_dot))
         // Template management
         .route("/api/templates", get(handlers::list_templates))
         .route("/api/templates", post(handlers::create_template))
+        .route("/api/templates/categories", get(handlers::list_template_categories))
         .route("/api/templates/{template_id}", get(handlers::get_template))
         .route("/api/templates/{template_id}", delete(handlers::delete_template))
         .route("/api/templates/{template_id}/instantiate", post(handlers::instantiate_template))
         // Claude config compiler
         .route("/api/claude/compile/{role_id}", get(handlers::compile_claude_config))
+        .route("/api/claude/compile-all", post(handlers::compile_all_claude_configs))
+        // MCP tool registry
+        .route("/api/mcp/tools", get(handlers::list_mcp_tools))
         // Capacity management
         .route("/api/capacity/sources", get(handlers::list_capacity_sources))
         .route("/api/capacity/sources", post(handlers::create_capacity_source))
         .route("/api/capacity/sources/{source_id}", get(handlers::get_capacity_source))
         .route("/api/capacity/sources/{source_id}", delete(handlers::delete_capacity_source))
         .route("/api/capacity/usage", get(handlers::list_capacity_usage))
+        .route("/api/capacity/usage/batch", post(handlers::ingest_capacity_usage))
         .route("/api/capacity/cost", get(handlers::get_capacity_cost))
+        .route("/api/capacity/selection-trace", get(handlers::get_selection_trace))
+        .route("/api/capacity/dead-letter", get(handlers::list_dead_letters))
+        .route("/api/capacity/dead-letter/{request_id}/requeue", post(handlers::requeue_dead_letter))
         // Routine management (Phase 5)
         .route("/api/routines", get(handlers::list_routines))
         .route("/api/routines", post(handlers::create_routine))
@@ -84,6 +122,7 @@ fn create_router(state: AppState, schema: crate::graphql::ShiiooSchema) -> Route
         .route("/api/routines/{routine_id}", delete(handlers::delete_routine))
         .route("/api/routines/{routine_id}/enable", post(handlers::enable_routine))
         .route("/api/routines/{routine_id}/disable", post(handlers::disable_routine))
+        .route("/api/routines/{routine_id}/trigger", post(handlers::trigger_routine))
         .route("/api/routines/{routine_id}/executions", get(handlers::get_routine_executions))
         // Approval board management (Phase 5)
         .route("/api/approval-boards", get(handlers::list_approval_boards))
@@ -92,22 +131,28 @@ fn create_router(state: AppState, schema: crate::graphql::ShiiooSchema) -> Route
         .route("/api/approval-boards/{board_id}", delete(handlers::delete_approval_board))
         // Approval management (Phase 5)
         .route("/api/approvals", get(handlers::list_approvals))
+        .route("/api/approvals/bulk-vote", post(handlers::bulk_vote))
         .route("/api/approvals/{approval_id}", get(handlers::get_approval))
         .route("/api/approvals/{approval_id}/vote", post(handlers::cast_vote))
+        .route("/api/approvals/{approval_id}/force-resolve", post(handlers::force_resolve_approval))
         // Config change management (Phase 5)
         .route("/api/config-changes", get(handlers::list_config_changes))
         .route("/api/config-changes", post(handlers::propose_config_change))
         .route("/api/config-changes/{change_id}", get(handlers::get_config_change))
         .route("/api/config-changes/{change_id}/apply", post(handlers::apply_config_change))
         .route("/api/config-changes/{change_id}/reject", post(handlers::reject_config_change))
+        .route("/api/config-changes/{change_id}/impact", get(handlers::get_config_change_impact))
         // Observability (Phase 6)
         .route("/api/metrics", get(handlers::get_metrics))
+        .route("/api/metrics/history", get(handlers::get_metric_history))
         .route("/api/analytics/workflows", get(handlers::get_workflow_analytics))
         .route("/api/analytics/workflows/{workflow_id}", get(handlers::get_workflow_analytics_by_id))
         .route("/api/analytics/steps", get(handlers::get_step_analytics))
         .route("/api/analytics/traces", get(handlers::get_execution_traces))
         .route("/api/analytics/traces/{run_id}", get(handlers::get_execution_trace))
         .route("/api/analytics/bottlenecks/{workflow_id}", get(handlers::get_bottleneck_analysis))
+        .route("/api/analytics/critical-path/{workflow_id}", get(handlers::get_critical_path))
+        .route("/api/analytics/approvals", get(handlers::get_approval_analytics))
         .route("/api/health/status", get(handlers::get_health_status))
         // WebSocket for real-time updates
         .route("/api/ws", get(crate::websocket::ws_handler))
@@ -121,6 +166,7 @@ fn create_router(state: AppState, schema: crate::graphql::ShiiooSchema) -> Route
         .route("/api/secrets/{secret_id}/rotate", post(handlers::rotate_secret))
         .route("/api/secrets/{secret_id}/versions", get(handlers::get_secret_versions))
         .route("/api/secrets/rotation/needed", get(handlers::get_secrets_needing_rotation))
+        .route("/api/secrets/master-key/rotate", post(handlers::rotate_master_key))
         // Multi-tenancy (Phase 7)
         .route("/api/tenants", get(handlers::list_tenants))
         .route("/api/tenants", post(handlers::register_tenant))
@@ -129,6 +175,7 @@ fn create_router(state: AppState, schema: crate::graphql::ShiiooSchema) -> Route
         .route("/api/tenants/{tenant_id}", delete(handlers::delete_tenant))
         .route("/api/tenants/{tenant_id}/suspend", post(handlers::suspend_tenant))
         .route("/api/tenants/{tenant_id}/activate", post(handlers::activate_tenant))
+        .route("/api/tenants/{tenant_id}/clone", post(handlers::clone_tenant))
         .route("/api/tenants/{tenant_id}/storage-stats", get(handlers::get_tenant_storage_stats))
         // Cluster management (Phase 7)
         .route("/api/cluster/nodes", get(handlers::list_cluster_nodes))
@@ -136,33 +183,59 @@ fn create_router(state: AppState, schema: crate::graphql::ShiiooSchema) -> Route
         .route("/api/cluster/nodes/{node_id}", get(handlers::get_cluster_node))
         .route("/api/cluster/nodes/{node_id}", delete(handlers::remove_cluster_node))
         .route("/api/cluster/nodes/{node_id}/heartbeat", post(handlers::node_heartbeat))
+        .route("/api/cluster/nodes/{node_id}/drain", post(handlers::drain_cluster_node))
+        .route("/api/cluster/nodes/{node_id}/undrain", post(handlers::undrain_cluster_node))
+        .route("/api/cluster/select", get(handlers::select_cluster_node))
         .route("/api/cluster/leader", get(handlers::get_cluster_leader))
         .route("/api/cluster/health", get(handlers::get_cluster_health))
         // Audit logging (Phase 9)
         .route("/api/audit/entries", get(handlers::list_audit_entries))
+        .route("/api/audit/ingest", post(handlers::ingest_audit_entries))
         .route("/api/audit/statistics", get(handlers::get_audit_statistics))
         .route("/api/audit/verify-chain", get(handlers::verify_audit_chain))
+        .route("/api/audit/checkpoints", get(handlers::list_audit_checkpoints))
         // RBAC (Phase 9)
         .route("/api/rbac/roles", get(handlers::list_rbac_roles))
         .route("/api/rbac/roles", post(handlers::create_rbac_role))
         .route("/api/rbac/roles/{role_id}", get(handlers::get_rbac_role))
         .route("/api/rbac/assign-role", post(handlers::assign_user_role))
         .route("/api/rbac/check-permission", post(handlers::check_user_permission))
+        // API keys
+        .route("/api/auth/keys", get(handlers::list_api_keys))
+        .route("/api/auth/keys", post(handlers::create_api_key))
+        .route("/api/auth/keys/{key_id}", delete(handlers::revoke_api_key))
         // Compliance & Security (Phase 9)
         .route("/api/compliance/report", post(handlers::generate_compliance_report))
+        .route("/api/compliance/reports", post(handlers::start_compliance_report))
+        .route("/api/compliance/reports/{report_id}", get(handlers::get_compliance_report_job))
         .route("/api/security/scan", post(handlers::run_security_scan))
         // UI routes (Phase 10)
         .route("/dashboard", get(ui::serve_dashboard))
-        .fallback(ui::serve_ui)
+        .fallback(fallback);
+
+    // Inside authentication, so request signatures cover the bytes the client sent
+    #[cfg(feature = "msgpack")]
+    let router = router.layer(axum::middleware::from_fn(crate::middleware::negotiate_codec));
+
+    let router = router
         // Middleware
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::require_api_key,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::new(crate::middleware::LoadShedder::new(state.load_shedding_config.clone())),
+            crate::middleware::shed_load,
+        ))
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(DefaultMakeSpan::new().include_headers(true))
                 .on_response(DefaultOnResponse::new().include_headers(true)),
         )
         .layer(CorsLayer::permissive())
-        .layer(axum::Extension(schema))
-        .with_state(Arc::new(state))
+        .layer(axum::Extension(schema));
+
+    router.with_state(state)
 }
 
 /// Health check endpoint
@@ -174,12 +247,73 @@ async fn health_check() -> impl IntoResponse {
     }))
 }
 
+/// Unmatched `/api` paths get a JSON 404 so API clients never have to parse the UI's HTML.
+/// Everything else is served by the UI.
+async fn fallback(uri: axum::http::Uri) -> Response {
+    let path = uri.path();
+    if path == "/api" || path.starts_with("/api/") {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(format!("No API route for {}", path))),
+        )
+            .into_response();
+    }
+
+    ui::serve_ui(uri).await
+}
+
+/// Version of the REST API served under `/api`. v2 wraps list responses in `Paginated`.
+pub const API_VERSION: &str = "v2";
+
+/// Server capabilities, for clients that need to support several server versions
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerDescription {
+    pub service: String,
+    pub version: String,
+    pub api_version: String,
+    pub features: ServerFeatures,
+    pub compliance_frameworks: Vec<shiioo_core::compliance::ComplianceFramework>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerFeatures {
+    pub graphql: bool,
+    pub mcp: bool,
+    pub webhooks: bool,
+    pub api_key_auth: bool,
+}
+
+impl ServerDescription {
+    fn current(state: &AppState) -> Self {
+        Self {
+            service: "shiioo".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            api_version: API_VERSION.to_string(),
+            features: ServerFeatures {
+                graphql: true,
+                mcp: true,
+                webhooks: false,
+                api_key_auth: state.auth_config.enabled,
+            },
+            compliance_frameworks: shiioo_core::compliance::ComplianceFramework::all().to_vec(),
+        }
+    }
+}
+
+/// Describe server version and capabilities
+async fn describe(State(state): State<Arc<AppState>>) -> Json<ServerDescription> {
+    Json(ServerDescription::current(&state))
+}
+
 /// API error response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    /// Every validation failure, when the request was rejected as invalid
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ValidationError>,
 }
 
 impl ErrorResponse {
@@ -187,6 +321,7 @@ impl ErrorResponse {
         Self {
             error: error.into(),
             details: None,
+            errors: Vec::new(),
         }
     }
 
@@ -194,15 +329,94 @@ impl ErrorResponse {
         Self {
             error: error.into(),
             details: Some(details.into()),
+            errors: Vec::new(),
         }
     }
 }
 
+/// A caller lacking the RBAC permission a handler requires, answered with 403
+#[derive(Debug)]
+pub struct Forbidden(pub String);
+
+impl std::fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Permission denied: {}", self.0)
+    }
+}
+
+impl std::error::Error for Forbidden {}
+
+/// A resource that doesn't exist or that the caller may not see, answered with 404
+#[derive(Debug)]
+pub struct NotFound(pub String);
+
+impl std::fmt::Display for NotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// A request that conflicts with the server's current state, answered with 409
+#[derive(Debug)]
+pub struct Conflict(pub String);
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// A leader-only write sent to a node that isn't the leader, answered with 503 so
+/// the caller retries against the current leader
+#[derive(Debug)]
+pub struct NotLeader;
+
+impl std::fmt::Display for NotLeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("This node is not the cluster leader")
+    }
+}
+
+impl std::error::Error for NotLeader {}
+
 /// Custom error type for API handlers
+#[derive(Debug)]
 pub struct ApiError(anyhow::Error);
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        if let Some(forbidden) = self.0.downcast_ref::<Forbidden>() {
+            let response = ErrorResponse::new(forbidden.to_string());
+            return (StatusCode::FORBIDDEN, Json(response)).into_response();
+        }
+
+        if self.0.is::<NotFound>() {
+            let response = ErrorResponse::new(self.0.to_string());
+            return (StatusCode::NOT_FOUND, Json(response)).into_response();
+        }
+
+        if self.0.is::<Conflict>() || self.0.is::<StaleTermError>() {
+            let response = ErrorResponse::new(self.0.to_string());
+            return (StatusCode::CONFLICT, Json(response)).into_response();
+        }
+
+        if self.0.is::<NotLeader>() {
+            let response = ErrorResponse::new(self.0.to_string());
+            return (StatusCode::SERVICE_UNAVAILABLE, Json(response)).into_response();
+        }
+
+        if let Some(errors) = self.0.downcast_ref::<ValidationErrors>() {
+            let response = ErrorResponse {
+                errors: errors.errors().to_vec(),
+                ..ErrorResponse::new(errors.to_string())
+            };
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(response)).into_response();
+        }
+
         let error_msg = self.0.to_string();
         let details = self.0.chain().skip(1).map(|e| e.to_string()).collect::<Vec<_>>().join(": ");
 
@@ -226,3 +440,2294 @@ where
 }
 
 pub type ApiResult<T> = Result<T, ApiError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use shiioo_core::audit::AuditAction;
+    use shiioo_core::cluster::NodeRole;
+    use shiioo_core::compliance::ComplianceFramework;
+    use shiioo_core::types::{
+        ApprovalBoard, ApprovalBoardId, ApprovalStatus, ApprovalSubject, CapacitySource,
+        CapacitySourceId, CapacityUsage, ConfigChange, ConfigChangeId, ConfigChangeStatus,
+        ConfigChangeType, CostPerToken, LlmProvider, PersonId, QuorumRule, RateLimits,
+        RoleBudgets, RoleId, RoleSpec, Routine, RoutineId, RoutineSchedule, Run, RunStatus,
+        StepAction, StepId, StepSpec, VoteDecision, WorkflowSpec,
+    };
+    use shiioo_core::query::Paginated;
+    use std::collections::HashMap;
+
+    fn create_test_state(name: &str) -> Arc<AppState> {
+        let config = test_config(name);
+        Arc::new(AppState::new(&config).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_describe_reports_version_and_frameworks() {
+        let state = create_test_state("describe");
+
+        let Json(description) = describe(State(state)).await;
+
+        assert_eq!(description.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(description.api_version, API_VERSION);
+        assert!(description.features.graphql);
+        assert!(!description.features.api_key_auth);
+        assert_eq!(description.compliance_frameworks, ComplianceFramework::all());
+    }
+
+    #[tokio::test]
+    async fn test_compliance_report_covers_every_requirement() {
+        let state = create_test_state("compliance-report");
+
+        let now = chrono::Utc::now();
+        let request = handlers::ComplianceReportRequest {
+            framework: ComplianceFramework::SOC2,
+            period_start: now - chrono::Duration::days(30),
+            period_end: now,
+        };
+        let report = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            handlers::generate_compliance_report(State(state), Json(request)),
+        )
+        .await
+        .expect("report generation hung")
+        .unwrap()
+        .0;
+
+        assert!(report.requirements.len() > 1);
+        assert_eq!(report.summary.total_requirements, report.requirements.len());
+    }
+
+    #[tokio::test]
+    async fn test_compliance_report_signed_with_audit_key() {
+        let mut config: ServerConfig =
+            toml::from_str("[audit]\nsigning_key = \"audit-test-key\"").unwrap();
+        config.data_dir =
+            std::env::temp_dir().join(format!("shiioo-audit-key-{}", uuid::Uuid::new_v4()));
+        let state = Arc::new(AppState::new(&config).unwrap());
+
+        let now = chrono::Utc::now();
+        let request = handlers::ComplianceReportRequest {
+            framework: ComplianceFramework::SOC2,
+            period_start: now - chrono::Duration::days(30),
+            period_end: now,
+        };
+        let Json(report) = handlers::generate_compliance_report(State(state), Json(request))
+            .await
+            .unwrap();
+
+        assert!(report.verify_signature(b"audit-test-key"));
+        assert!(!report.verify_signature(b"shiioo-default-secret-key-change-me-in-production!"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_api_path_returns_json_404() {
+        use tower::ServiceExt;
+
+        let state = create_test_state("api-404");
+        let schema = crate::graphql::build_schema(state.clone());
+        let router = create_router((*state).clone(), schema);
+
+        let request = axum::http::Request::builder()
+            .uri("/api/nonexistent")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[axum::http::header::CONTENT_TYPE], "application/json");
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: ErrorResponse = serde_json::from_slice(&bytes).unwrap();
+        assert!(body.error.contains("/api/nonexistent"));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn test_signed_msgpack_request() {
+        use shiioo_core::api_key::{sign_request, NONCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+        use shiioo_core::codec::msgpack;
+        use tower::ServiceExt;
+
+        let raw_key = "sk-bootstrap-0123456789abcdef";
+        let config = ServerConfig {
+            auth: crate::config::AuthConfig {
+                enabled: true,
+                require_signed_requests: true,
+                signature_max_age_secs: 60,
+                bootstrap_key: Some(raw_key.to_string()),
+            },
+            ..test_config("signed-msgpack")
+        };
+        let state = AppState::new(&config).unwrap();
+        let schema = crate::graphql::build_schema(Arc::new(state.clone()));
+        let router = create_router(state, schema);
+
+        let path = "/api/rbac/check-permission";
+        let body = msgpack::to_vec(&serde_json::json!({
+            "user_id": "admin",
+            "resource": "Workflow",
+            "action": "Read",
+        }))
+        .unwrap();
+        let timestamp = chrono::Utc::now().timestamp();
+        let request = |signed_body: &[u8], nonce: &str| {
+            axum::http::Request::builder()
+                .method("POST")
+                .uri(path)
+                .header("Authorization", format!("Bearer {}", raw_key))
+                .header(axum::http::header::CONTENT_TYPE, "application/msgpack")
+                .header(axum::http::header::ACCEPT, "application/msgpack")
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(NONCE_HEADER, nonce)
+                .header(
+                    SIGNATURE_HEADER,
+                    sign_request(raw_key, "POST", path, signed_body, timestamp, nonce),
+                )
+                .body(axum::body::Body::from(body.clone()))
+                .unwrap()
+        };
+
+        // Signed over the MessagePack bytes the client sent
+        let response = router.clone().oneshot(request(&body, "n1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let checked: handlers::PermissionCheckResponse = msgpack::from_slice(&bytes).unwrap();
+        assert!(checked.has_permission);
+
+        // A signature over the JSON the server transcodes to doesn't verify
+        let json: serde_json::Value = msgpack::from_slice(&body).unwrap();
+        let json = serde_json::to_vec(&json).unwrap();
+        let response = router.oneshot(request(&json, "n2")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_organization_reports_all_errors() {
+        use tower::ServiceExt;
+
+        let state = create_test_state("org-validation");
+        let schema = crate::graphql::build_schema(state.clone());
+        let router = create_router((*state).clone(), schema);
+
+        let person = |id: &str, team: &str, reports_to: &str| {
+            serde_json::json!({
+                "id": id, "name": id, "email": format!("{}@example.com", id),
+                "role": "engineer", "team": team, "reports_to": reports_to, "can_approve": [],
+            })
+        };
+        let org = serde_json::json!({
+            "id": "acme",
+            "name": "Acme",
+            "description": "",
+            "teams": [{
+                "id": "eng", "name": "Eng", "description": "", "lead": "alice",
+                "members": ["alice", "bob"], "parent_team": null,
+            }],
+            "people": [person("alice", "eng", "bob"), person("bob", "design", "alice")],
+            "org_chart": { "root_team": "eng", "reporting_structure": {} },
+            "created_at": chrono::Utc::now(),
+            "updated_at": chrono::Utc::now(),
+        });
+
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri("/api/organizations")
+            .header(axum::http::header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(org.to_string()))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: ErrorResponse = serde_json::from_slice(&bytes).unwrap();
+        let fields: Vec<_> = body.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["people[1].team", "people[0].reports_to"]);
+        assert!(body.errors[1].message.contains("Reporting cycle"));
+        assert!(state.index_store.list_organizations().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_job_rejects_unknown_approver() {
+        let state = create_test_state("unknown-approver");
+
+        let request = handlers::CreateJobRequest {
+            name: "Release".to_string(),
+            description: None,
+            workflow: WorkflowSpec {
+                steps: vec![StepSpec {
+                    id: StepId::new("sign_off"),
+                    name: "Sign off".to_string(),
+                    description: None,
+                    role: RoleId::new("release_manager"),
+                    action: StepAction::ManualApproval {
+                        approvers: vec!["bogus-person".to_string()],
+                    },
+                    timeout_secs: None,
+                    retry_policy: None,
+                    requires_approval: false,
+                }],
+                dependencies: Default::default(),
+                inputs: Vec::new(),
+            },
+            created_by: None,
+            execute: Some(true),
+            external_id: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: None,
+        };
+
+        let err = handlers::create_job(State(state.clone()), None, Json(request))
+            .await
+            .unwrap_err();
+        let message = err.0.to_string();
+        assert!(message.contains("unknown approver"), "{}", message);
+        assert!(message.contains("sign_off") && message.contains("bogus-person"), "{}", message);
+        assert!(state.index_store.list_runs().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_job_accepts_registered_board_as_approver() {
+        let state = create_test_state("board-approver");
+        state
+            .approval_manager
+            .register_board(ApprovalBoard {
+                id: ApprovalBoardId::new("release-board"),
+                name: "Release".to_string(),
+                description: "Release sign-off".to_string(),
+                approvers: vec![PersonId::new("alice")],
+                quorum_rule: QuorumRule::Unanimous,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+
+        let request = handlers::CreateJobRequest {
+            name: "Release".to_string(),
+            description: None,
+            workflow: WorkflowSpec {
+                steps: vec![StepSpec {
+                    id: StepId::new("sign_off"),
+                    name: "Sign off".to_string(),
+                    description: None,
+                    role: RoleId::new("release_manager"),
+                    action: StepAction::ManualApproval {
+                        approvers: vec!["release-board".to_string()],
+                    },
+                    timeout_secs: None,
+                    retry_policy: None,
+                    requires_approval: false,
+                }],
+                dependencies: Default::default(),
+                inputs: Vec::new(),
+            },
+            created_by: None,
+            execute: Some(false),
+            external_id: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: None,
+        };
+
+        let Json(created) = handlers::create_job(State(state), None, Json(request)).await.unwrap();
+        assert!(created.run_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_critical_path_from_recorded_workflow() {
+        use tower::ServiceExt;
+
+        let state = create_test_state("critical-path");
+        let step = |id: &str| StepSpec {
+            id: StepId::new(id),
+            name: id.to_string(),
+            description: None,
+            role: RoleId::new("engineer"),
+            action: StepAction::AgentTask {
+                prompt: format!("Run {}", id),
+            },
+            timeout_secs: None,
+            retry_policy: None,
+            requires_approval: false,
+        };
+        let request = handlers::CreateJobRequest {
+            name: "Build".to_string(),
+            description: None,
+            workflow: WorkflowSpec {
+                steps: vec![step("build"), step("test"), step("deploy")],
+                dependencies: HashMap::from([
+                    (StepId::new("test"), vec![StepId::new("build")]),
+                    (StepId::new("deploy"), vec![StepId::new("test")]),
+                ]),
+                inputs: Vec::new(),
+            },
+            created_by: None,
+            execute: Some(true),
+            external_id: None,
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: None,
+        };
+        let Json(created) = handlers::create_job(State(state.clone()), None, Json(request))
+            .await
+            .unwrap();
+
+        let schema = crate::graphql::build_schema(state.clone());
+        let router = create_router((*state).clone(), schema);
+        let request = axum::http::Request::builder()
+            .uri(format!("/api/analytics/critical-path/{}", created.job_id))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["workflow_id"], created.job_id);
+        // The path follows the dependencies the run was started with
+        assert_eq!(body["steps"], serde_json::json!(["build", "test", "deploy"]));
+    }
+
+    #[tokio::test]
+    async fn test_mcp_tools_list_built_in_tools() {
+        let state = create_test_state("mcp-tools");
+
+        let Json(response) = handlers::list_mcp_tools(State(state)).await;
+        let names: Vec<_> = response.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["context_events", "context_get", "context_search"]
+        );
+        for tool in &response.tools {
+            assert_eq!(tool.tier, shiioo_mcp::tools::ToolTier::Tier0, "{}", tool.name);
+            assert_eq!(tool.input_schema["type"], "object");
+        }
+
+        // The repository and web tools are offered once configured
+        let mut config = test_config("mcp-tools-configured");
+        config.tools.repo_root = Some(config.data_dir.clone());
+        config.tools.web_fetch = true;
+        let state = Arc::new(AppState::new(&config).unwrap());
+
+        let Json(response) = handlers::list_mcp_tools(State(state)).await;
+        let names: Vec<_> = response.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["context_events", "context_get", "context_search", "repo_read", "web_fetch"]
+        );
+        for tool in &response.tools {
+            assert_eq!(tool.tier, shiioo_mcp::tools::ToolTier::Tier0, "{}", tool.name);
+            assert_eq!(tool.input_schema["type"], "object");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_job_inputs_render_into_agent_prompt() {
+        use shiioo_core::events::{EventLog, EventType, MessageDirection};
+        use shiioo_core::types::WorkflowInput;
+
+        let state = create_test_state("job-inputs");
+
+        let request = |inputs: HashMap<String, serde_json::Value>| handlers::CreateJobRequest {
+            name: "Review".to_string(),
+            description: None,
+            workflow: WorkflowSpec {
+                steps: vec![StepSpec {
+                    id: StepId::new("review"),
+                    name: "Review".to_string(),
+                    description: None,
+                    role: RoleId::new("reviewer"),
+                    action: StepAction::AgentTask {
+                        prompt: "Review PR #{{inputs.pr_number}}".to_string(),
+                    },
+                    timeout_secs: None,
+                    retry_policy: None,
+                    requires_approval: false,
+                }],
+                dependencies: Default::default(),
+                inputs: vec![WorkflowInput {
+                    name: "pr_number".to_string(),
+                    description: None,
+                    required: true,
+                    default: None,
+                }],
+            },
+            created_by: None,
+            execute: Some(true),
+            external_id: None,
+            tags: HashMap::new(),
+            inputs,
+            priority: None,
+        };
+
+        let err = handlers::create_job(State(state.clone()), None, Json(request(HashMap::new())))
+            .await
+            .unwrap_err();
+        let errors = err.0.downcast_ref::<ValidationErrors>().unwrap();
+        assert_eq!(errors.errors()[0].field, "inputs.pr_number");
+
+        let inputs = HashMap::from([("pr_number".to_string(), serde_json::json!(42))]);
+        let Json(created) = handlers::create_job(State(state.clone()), None, Json(request(inputs)))
+            .await
+            .unwrap();
+        let run_id = created.run_id.unwrap();
+        let run = state.index_store.get_run(&run_id).unwrap().unwrap();
+        assert_eq!(run.inputs["pr_number"], serde_json::json!(42));
+
+        let events = state.event_log.get_run_events(run_id).await.unwrap();
+        let prompt_hash = events
+            .iter()
+            .find_map(|e| match &e.event_type {
+                EventType::AgentMessage {
+                    direction: MessageDirection::ToAgent,
+                    content_hash,
+                    ..
+                } => Some(content_hash.clone()),
+                _ => None,
+            })
+            .unwrap();
+        let prompt = state.blob_store.get(&prompt_hash).await.unwrap().unwrap();
+        assert_eq!(&prompt[..], b"Review PR #42");
+    }
+
+    #[tokio::test]
+    async fn test_force_resolve_deadlocked_approval() {
+        use shiioo_core::api_key::NewApiKey;
+
+        let state = create_test_state("force-resolve");
+        let mut keys = std::collections::HashMap::new();
+        for user in ["root", "carol"] {
+            state
+                .rbac_manager
+                .register_user(shiioo_core::rbac::RbacUser::new(
+                    user.to_string(),
+                    user.to_string(),
+                    format!("{}@example.com", user),
+                ))
+                .unwrap();
+            let (key, _) = state
+                .api_keys
+                .create(NewApiKey {
+                    name: user.to_string(),
+                    user_id: user.to_string(),
+                    tenant_id: None,
+                    allowed_tenants: Vec::new(),
+                    allowed_actions: Vec::new(),
+                    expires_at: None,
+                })
+                .unwrap();
+            keys.insert(user, key);
+        }
+        state.rbac_manager.assign_role("root", "admin").unwrap();
+        state.rbac_manager.assign_role("carol", "workflow_manager").unwrap();
+
+        let board = ApprovalBoard {
+            id: ApprovalBoardId::new("release"),
+            name: "Release".to_string(),
+            description: "Release sign-off".to_string(),
+            approvers: vec![PersonId::new("alice"), PersonId::new("bob")],
+            quorum_rule: QuorumRule::MinCount { min: 2 },
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        state.approval_manager.register_board(board.clone()).unwrap();
+        let approval = state
+            .approval_manager
+            .create_approval(
+                board.id.clone(),
+                ApprovalSubject::Custom {
+                    subject_type: "release".to_string(),
+                    subject_id: "v2".to_string(),
+                },
+                "ci".to_string(),
+            )
+            .unwrap();
+
+        // An abstention leaves quorum unreachable but never denies it
+        state
+            .approval_manager
+            .cast_vote(&approval.id, PersonId::new("alice"), VoteDecision::Abstain, None)
+            .unwrap();
+
+        let force = |state: Arc<AppState>, user: Option<&str>| {
+            handlers::force_resolve_approval(
+                State(state),
+                axum::extract::Path(approval.id.0.clone()),
+                user.map(|user| axum::Extension(keys[user].clone())),
+                Json(handlers::ForceResolveApprovalRequest {
+                    decision: ApprovalStatus::Approved,
+                    justification: "Alice is on leave; release is blocking a fix".to_string(),
+                }),
+            )
+        };
+
+        // Without a key there is no one to attribute the override to
+        let err = force(state.clone(), None).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+
+        // Only admins may override a board
+        let err = force(state.clone(), Some("carol")).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            state.approval_manager.get_approval(&approval.id).unwrap().status,
+            ApprovalStatus::Pending
+        );
+
+        let Json(resolved) = force(state.clone(), Some("root")).await.unwrap();
+        assert_eq!(resolved.status, ApprovalStatus::Approved);
+        assert_eq!(resolved.forced_resolution.unwrap().resolved_by, "root");
+
+        let entry = state.audit_log.list_by_user("root").pop().unwrap();
+        match &entry.action {
+            AuditAction::ApprovalForceResolved { approval_id, .. } => {
+                assert_eq!(*approval_id, approval.id.0)
+            }
+            other => panic!("unexpected audit action {:?}", other),
+        }
+        assert_eq!(
+            entry.metadata["justification"],
+            "Alice is on leave; release is blocking a fix"
+        );
+
+        let err = force(state, Some("root")).await.unwrap_err();
+        assert!(err.0.to_string().contains("already resolved"));
+    }
+
+    #[tokio::test]
+    async fn test_api_keys_issued_only_for_own_user_unless_admin() {
+        use shiioo_core::api_key::NewApiKey;
+
+        let state = create_test_state("api-key-issue");
+        for user in ["root", "dave"] {
+            state
+                .rbac_manager
+                .register_user(shiioo_core::rbac::RbacUser::new(
+                    user.to_string(),
+                    user.to_string(),
+                    format!("{}@example.com", user),
+                ))
+                .unwrap();
+        }
+        state.rbac_manager.assign_role("root", "admin").unwrap();
+        state.rbac_manager.assign_role("dave", "secret_manager").unwrap();
+
+        let new_key = |user_id: &str| NewApiKey {
+            name: "ci".to_string(),
+            user_id: user_id.to_string(),
+            tenant_id: None,
+            allowed_tenants: Vec::new(),
+            allowed_actions: Vec::new(),
+            expires_at: None,
+        };
+        let issue = |caller: &str, user_id: &str| {
+            let (caller, _) = state.api_keys.create(new_key(caller)).unwrap();
+            handlers::create_api_key(
+                State(state.clone()),
+                Some(axum::Extension(caller)),
+                Json(new_key(user_id)),
+            )
+        };
+
+        let Json(own) = issue("dave", "dave").await.unwrap();
+        assert_eq!(own.key.user_id, "dave");
+
+        let err = issue("dave", "root").await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+
+        let Json(issued) = issue("root", "dave").await.unwrap();
+        assert_eq!(issued.key.user_id, "dave");
+
+        // Listing and revoking follow the same rule
+        let (dave, _) = state.api_keys.create(new_key("dave")).unwrap();
+        let (root, _) = state.api_keys.create(new_key("root")).unwrap();
+        let list = |caller: &shiioo_core::api_key::ApiKey| {
+            handlers::list_api_keys(State(state.clone()), Some(axum::Extension(caller.clone())))
+        };
+        let Json(listed) = list(&dave).await.unwrap();
+        assert!(listed.iter().all(|k| k.user_id == "dave"));
+        let Json(listed) = list(&root).await.unwrap();
+        assert!(listed.iter().any(|k| k.user_id == "root"));
+        assert!(listed.iter().any(|k| k.user_id == "dave"));
+
+        let revoke = |caller: &shiioo_core::api_key::ApiKey, key_id: &str| {
+            handlers::revoke_api_key(
+                State(state.clone()),
+                Some(axum::Extension(caller.clone())),
+                axum::extract::Path(key_id.to_string()),
+            )
+        };
+        let err = revoke(&dave, &root.id.0).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+        assert!(!state.api_keys.get(&root.id).unwrap().revoked);
+
+        let _ = revoke(&root, &issued.key.id.0).await.unwrap();
+        let _ = revoke(&dave, &own.key.id.0).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ingested_audit_batch_keeps_chain_valid() {
+        use shiioo_core::audit::{AuditCategory, AuditSeverity, AuditSubmission};
+
+        let state = create_test_state("audit-ingest");
+        for user in ["sdk-worker", "intruder"] {
+            state
+                .rbac_manager
+                .register_user(shiioo_core::rbac::RbacUser::new(
+                    user.to_string(),
+                    user.to_string(),
+                    format!("{}@example.com", user),
+                ))
+                .unwrap();
+        }
+        state.rbac_manager.assign_role("sdk-worker", "audit_writer").unwrap();
+        state.audit_log.log(
+            AuditCategory::SystemEvent,
+            AuditSeverity::Info,
+            AuditAction::SystemStartup,
+            None,
+            None,
+            None,
+        );
+
+        let submission = |tool: &str| AuditSubmission {
+            category: AuditCategory::SecurityEvent,
+            severity: AuditSeverity::Warning,
+            action: AuditAction::ToolCalled {
+                tool_name: tool.to_string(),
+                succeeded: false,
+            },
+            metadata: HashMap::from([("agent".to_string(), "sdk-worker-1".to_string())]),
+        };
+        let key_for = |user: &str| {
+            let (key, _) = state
+                .api_keys
+                .create(shiioo_core::api_key::NewApiKey {
+                    name: "sdk".to_string(),
+                    user_id: user.to_string(),
+                    tenant_id: None,
+                    allowed_tenants: Vec::new(),
+                    allowed_actions: Vec::new(),
+                    expires_at: None,
+                })
+                .unwrap();
+            Some(axum::Extension(key))
+        };
+        let ingest_as = |state: Arc<AppState>, key, entries| {
+            handlers::ingest_audit_entries(
+                State(state),
+                key,
+                Json(handlers::IngestAuditRequest { entries }),
+            )
+        };
+        let worker = key_for("sdk-worker");
+        let ingest = |state, entries| ingest_as(state, worker.clone(), entries);
+
+        // Submitters need an API key and the audit_writer permission
+        let err = ingest_as(state.clone(), None, vec![submission("shell")])
+            .await
+            .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+        let err = ingest_as(state.clone(), key_for("intruder"), vec![submission("shell")])
+            .await
+            .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+
+        let head = state.audit_log.last_hash();
+        let Json(response) =
+            ingest(state.clone(), vec![submission("shell"), submission("web_fetch")])
+                .await
+                .unwrap();
+        assert_eq!(response.entries.len(), 2);
+        assert_eq!(response.entries[0].previous_hash, head);
+        assert_eq!(
+            response.entries[1].previous_hash.as_ref(),
+            Some(&response.entries[0].entry_hash)
+        );
+        assert_eq!(response.entries[0].user_id.as_deref(), Some("sdk-worker"));
+        assert_eq!(response.entries[0].metadata["source"], "ingest");
+        assert!(state.audit_log.verify_chain_detailed().is_ok());
+
+        // Events only the server records can't be forged
+        let forged = AuditSubmission {
+            category: AuditCategory::Authorization,
+            severity: AuditSeverity::Critical,
+            action: AuditAction::ApprovalForceResolved {
+                approval_id: "a1".to_string(),
+                status: "Approved".to_string(),
+                resolved_by: "admin".to_string(),
+            },
+            metadata: HashMap::new(),
+        };
+        let err = ingest(state.clone(), vec![forged]).await.unwrap_err();
+        assert!(err.0.to_string().contains("can be ingested"), "{}", err.0);
+
+        // One bad entry rejects the whole batch
+        let mut oversized = submission("shell");
+        oversized.metadata.insert("dump".to_string(), "x".repeat(10_000));
+        let err = ingest(state.clone(), vec![submission("shell"), oversized])
+            .await
+            .unwrap_err();
+        assert!(err.0.to_string().contains("limited to"), "{}", err.0);
+        // Startup, the intruder's denial and the first batch
+        assert_eq!(state.audit_log.list_all().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_board_scoped_permission_allows_votes_only_on_that_board() {
+        use shiioo_core::api_key::NewApiKey;
+        use shiioo_core::rbac::{Action, Permission, RbacRole, RbacUser, Resource};
+
+        let state = create_test_state("scoped-vote");
+        let mut release_approver = RbacRole::new(
+            "release_approver".to_string(),
+            "Release approver".to_string(),
+            "Votes on release sign-offs".to_string(),
+        );
+        release_approver.add_permission(Permission::with_resource_id(
+            Resource::Approval,
+            Action::Approve,
+            "release".to_string(),
+        ));
+        state.rbac_manager.register_role(release_approver).unwrap();
+        state
+            .rbac_manager
+            .register_user(RbacUser::new(
+                "alice".to_string(),
+                "alice".to_string(),
+                "alice@example.com".to_string(),
+            ))
+            .unwrap();
+        state.rbac_manager.assign_role("alice", "release_approver").unwrap();
+        let (key, _) = state
+            .api_keys
+            .create(NewApiKey {
+                name: "alice".to_string(),
+                user_id: "alice".to_string(),
+                tenant_id: None,
+                allowed_tenants: Vec::new(),
+                allowed_actions: Vec::new(),
+                expires_at: None,
+            })
+            .unwrap();
+
+        let mut approvals = Vec::new();
+        for board_id in ["release", "infra"] {
+            let board = ApprovalBoard {
+                id: ApprovalBoardId::new(board_id),
+                name: board_id.to_string(),
+                description: String::new(),
+                approvers: vec![PersonId::new("alice"), PersonId::new("bob")],
+                quorum_rule: QuorumRule::MinCount { min: 2 },
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            };
+            state.approval_manager.register_board(board.clone()).unwrap();
+            let approval = state
+                .approval_manager
+                .create_approval(
+                    board.id,
+                    ApprovalSubject::Custom {
+                        subject_type: "change".to_string(),
+                        subject_id: board_id.to_string(),
+                    },
+                    "ci".to_string(),
+                )
+                .unwrap();
+            approvals.push(approval);
+        }
+
+        let vote = |approval: &shiioo_core::types::Approval, voter: &str| {
+            handlers::cast_vote(
+                State(state.clone()),
+                axum::extract::Path(approval.id.0.clone()),
+                Some(axum::Extension(key.clone())),
+                Json(handlers::CastVoteRequest {
+                    voter_id: PersonId::new(voter),
+                    decision: VoteDecision::Approve,
+                    comment: None,
+                }),
+            )
+        };
+
+        let Json(voted) = vote(&approvals[0], "alice").await.unwrap();
+        assert_eq!(voted.votes.len(), 1);
+
+        let err = vote(&approvals[1], "alice").await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+        let untouched = state.approval_manager.get_approval(&approvals[1].id).unwrap();
+        assert!(untouched.votes.is_empty());
+
+        // Alice's key can't vote on bob's behalf, singly or in bulk
+        let err = vote(&approvals[0], "bob").await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+        let err = handlers::bulk_vote(
+            State(state.clone()),
+            Some(axum::Extension(key.clone())),
+            Json(handlers::BulkVoteRequest {
+                voter_id: PersonId::new("bob"),
+                votes: vec![shiioo_core::approval::BulkVote {
+                    approval_id: approvals[0].id.clone(),
+                    decision: VoteDecision::Approve,
+                    comment: None,
+                }],
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+        let approval = state.approval_manager.get_approval(&approvals[0].id).unwrap();
+        assert_eq!(approval.votes.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cast_vote_returns_updated_approval() {
+        let state = create_test_state("cast-vote");
+
+        let board = ApprovalBoard {
+            id: ApprovalBoardId::new("release"),
+            name: "Release".to_string(),
+            description: "Release sign-off".to_string(),
+            approvers: vec![PersonId::new("alice"), PersonId::new("bob")],
+            quorum_rule: QuorumRule::MinCount { min: 2 },
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        state.approval_manager.register_board(board.clone()).unwrap();
+        let approval = state
+            .approval_manager
+            .create_approval(
+                board.id.clone(),
+                ApprovalSubject::Custom {
+                    subject_type: "release".to_string(),
+                    subject_id: "v2".to_string(),
+                },
+                "ci".to_string(),
+            )
+            .unwrap();
+
+        let vote = |state: Arc<AppState>, voter: &str| {
+            handlers::cast_vote(
+                State(state),
+                axum::extract::Path(approval.id.0.clone()),
+                None,
+                Json(handlers::CastVoteRequest {
+                    voter_id: PersonId::new(voter),
+                    decision: VoteDecision::Approve,
+                    comment: Some("LGTM".to_string()),
+                }),
+            )
+        };
+
+        let Json(updated) = vote(state.clone(), "alice").await.unwrap();
+        assert_eq!(updated.id, approval.id);
+        assert_eq!(updated.votes.len(), 1);
+        assert_eq!(updated.votes[0].voter, PersonId::new("alice"));
+        assert_eq!(updated.status, ApprovalStatus::Pending);
+
+        // The vote that reaches quorum comes back with the resolved status
+        let Json(updated) = vote(state, "bob").await.unwrap();
+        assert_eq!(updated.votes.len(), 2);
+        assert_eq!(updated.status, ApprovalStatus::Approved);
+    }
+
+    #[tokio::test]
+    async fn test_routine_executions_report_current_run_status() {
+        let state = create_test_state("routine-executions");
+
+        let routine = Routine {
+            id: RoutineId::new("nightly"),
+            name: "Nightly".to_string(),
+            description: String::new(),
+            schedule: RoutineSchedule {
+                cron: "0 0 * * *".to_string(),
+                timezone: "UTC".to_string(),
+            },
+            workflow: WorkflowSpec {
+                steps: vec![],
+                dependencies: Default::default(),
+                inputs: Vec::new(),
+            },
+            enabled: false,
+            last_run: None,
+            next_run: chrono::Utc::now(),
+            created_at: chrono::Utc::now(),
+            created_by: "test".to_string(),
+            updated_at: chrono::Utc::now(),
+            tags: HashMap::new(),
+        };
+        state.routine_scheduler.register_routine(routine.clone()).unwrap();
+
+        // The execution is recorded as running, but the empty workflow completes at once
+        let execution = state.routine_scheduler.trigger_now(&routine.id, true).await.unwrap();
+        assert_eq!(execution.status, RunStatus::Running);
+        let run = state.index_store.get_run(&execution.run_id).unwrap().unwrap();
+        assert_eq!(run.status, RunStatus::Completed);
+
+        state.routine_scheduler.trigger_now(&routine.id, true).await.unwrap();
+
+        // Executions are paged like other lists
+        let page = |cursor: Option<String>| handlers::ListQueryParams {
+            limit: Some(1),
+            cursor,
+            ..Default::default()
+        };
+        let Json(first) = handlers::get_routine_executions(
+            State(state.clone()),
+            axum::extract::Path(routine.id.0.clone()),
+            axum::extract::Query(page(None)),
+        )
+        .await
+        .unwrap();
+        assert_eq!((first.items.len(), first.total, first.has_more), (1, 2, true));
+        let Json(second) = handlers::get_routine_executions(
+            State(state.clone()),
+            axum::extract::Path(routine.id.0.clone()),
+            axum::extract::Query(page(first.next_cursor.clone())),
+        )
+        .await
+        .unwrap();
+        assert!(!second.has_more);
+
+        let view = first
+            .items
+            .iter()
+            .chain(&second.items)
+            .find(|view| view.execution.id == execution.id)
+            .unwrap();
+        assert_eq!(view.execution.status, RunStatus::Completed);
+        assert_eq!(view.run_url, format!("/api/runs/{}", execution.run_id.0));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_run_by_external_id() {
+        let state = create_test_state("external-id");
+
+        let create_request = || handlers::CreateJobRequest {
+            name: "Ticket job".to_string(),
+            description: None,
+            workflow: WorkflowSpec {
+                steps: vec![StepSpec {
+                    id: StepId::new("step1"),
+                    name: "Step 1".to_string(),
+                    description: None,
+                    role: RoleId::new("engineer"),
+                    action: StepAction::AgentTask {
+                        prompt: "Triage the ticket".to_string(),
+                    },
+                    timeout_secs: None,
+                    retry_policy: None,
+                    requires_approval: false,
+                }],
+                dependencies: Default::default(),
+                inputs: Vec::new(),
+            },
+            created_by: None,
+            execute: Some(true),
+            external_id: Some("TICKET-42".to_string()),
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: None,
+        };
+
+        let Json(created) = handlers::create_job(State(state.clone()), None, Json(create_request()))
+            .await
+            .unwrap();
+        let run_id = created.run_id.unwrap();
+
+        let Json(run) = handlers::get_run_by_external_id(
+            State(state.clone()),
+            None,
+            None,
+            axum::extract::Path("TICKET-42".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(run.id, run_id);
+        assert_eq!(run.external_id.as_deref(), Some("TICKET-42"));
+
+        // External IDs are unique across runs
+        assert!(handlers::create_job(State(state.clone()), None, Json(create_request()))
+            .await
+            .is_err());
+
+        assert!(handlers::get_run_by_external_id(
+            State(state),
+            None,
+            None,
+            axum::extract::Path("TICKET-43".to_string()),
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_sees_only_its_runs() {
+        use crate::middleware::auth::RequestTenant;
+        use shiioo_core::tenant::TenantId;
+
+        let state = create_test_state("tenant-runs");
+        let tenant = |id: &str| Some(axum::Extension(RequestTenant(Some(TenantId::new(id)))));
+
+        let request = handlers::CreateJobRequest {
+            name: "Tenant job".to_string(),
+            description: None,
+            workflow: WorkflowSpec {
+                steps: vec![StepSpec {
+                    id: StepId::new("step1"),
+                    name: "Step 1".to_string(),
+                    description: None,
+                    role: RoleId::new("engineer"),
+                    action: StepAction::AgentTask {
+                        prompt: "Triage the ticket".to_string(),
+                    },
+                    timeout_secs: None,
+                    retry_policy: None,
+                    requires_approval: false,
+                }],
+                dependencies: Default::default(),
+                inputs: Vec::new(),
+            },
+            created_by: None,
+            execute: Some(true),
+            external_id: Some("TICKET-7".to_string()),
+            tags: HashMap::new(),
+            inputs: HashMap::new(),
+            priority: None,
+        };
+        let Json(created) = handlers::create_job(State(state.clone()), tenant("tenant1"), Json(request))
+            .await
+            .unwrap();
+        let run_id = created.run_id.unwrap().0.to_string();
+
+        let get = |tenant| {
+            let path = axum::extract::Path(run_id.clone());
+            handlers::get_run(State(state.clone()), tenant, None, path)
+        };
+        let Json(run) = get(tenant("tenant1")).await.unwrap();
+        assert_eq!(run.tenant_id, Some(TenantId::new("tenant1")));
+        assert!(get(None).await.is_ok());
+        assert!(get(tenant("tenant2")).await.is_err());
+        assert!(handlers::get_run_by_external_id(
+            State(state.clone()),
+            tenant("tenant2"),
+            None,
+            axum::extract::Path("TICKET-7".to_string()),
+        )
+        .await
+        .is_err());
+
+        let list = |tenant| {
+            handlers::list_runs(
+                State(state.clone()),
+                tenant,
+                None,
+                axum::extract::Query(handlers::ListRunsQueryParams {
+                    work_item_id: None,
+                    status: None,
+                    tag: None,
+                }),
+                axum::extract::Query(handlers::ListQueryParams::default()),
+            )
+        };
+        assert_eq!(list(tenant("tenant1")).await.unwrap().0.items.len(), 1);
+        assert!(list(tenant("tenant2")).await.unwrap().0.items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_clone_deletes_new_tenant() {
+        use shiioo_core::tenant::{Tenant, TenantId, TenantQuota, TenantSettings, TenantStatus};
+
+        let config = test_config("tenant-clone");
+        let state = Arc::new(AppState::new(&config).unwrap());
+        let source = Tenant {
+            id: TenantId::new("source"),
+            name: "Source".to_string(),
+            description: String::new(),
+            status: TenantStatus::Active,
+            quota: TenantQuota::default(),
+            settings: TenantSettings::default(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        state.tenant_manager.register_tenant(source).unwrap();
+        // A directory where the source's index belongs, so it can't be opened
+        let tenants_dir = config.data_dir.join("tenants");
+        std::fs::create_dir_all(tenants_dir.join("source").join("index.redb")).unwrap();
+
+        let result = handlers::clone_tenant(
+            State(state.clone()),
+            axum::extract::Path("source".to_string()),
+            Json(handlers::CloneTenantRequest {
+                name: "Copy".to_string(),
+            }),
+        )
+        .await;
+        assert!(result.is_err());
+
+        let tenants = state.tenant_manager.list_tenants();
+        assert_eq!(tenants.len(), 1);
+        assert_eq!(tenants[0].id, TenantId::new("source"));
+        let dirs: Vec<_> = std::fs::read_dir(&tenants_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(dirs, vec!["source"]);
+    }
+
+    #[tokio::test]
+    async fn test_secrets_scoped_to_key_tenant() {
+        use crate::middleware::auth::RequestTenant;
+        use shiioo_core::api_key::NewApiKey;
+        use shiioo_core::secrets::SecretType;
+        use shiioo_core::tenant::TenantId;
+
+        let state = create_test_state("tenant-secrets");
+        let tenant = |id: &str| Some(axum::Extension(RequestTenant(Some(TenantId::new(id)))));
+
+        let Json(secret) = handlers::create_secret(
+            State(state.clone()),
+            tenant("tenant1"),
+            Json(handlers::CreateSecretRequest {
+                name: "db".to_string(),
+                description: "Database password".to_string(),
+                secret_type: SecretType::Generic,
+                value: "tenant1-db-password".to_string(),
+                rotation_policy: None,
+                tags: None,
+                force: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(secret.tenant_id, Some(TenantId::new("tenant1")));
+
+        let list = |tenant| {
+            handlers::list_secrets(
+                State(state.clone()),
+                tenant,
+                axum::extract::Query(handlers::ListQueryParams::default()),
+            )
+        };
+        assert_eq!(list(tenant("tenant1")).await.unwrap().0.items.len(), 1);
+        assert!(list(tenant("tenant2")).await.unwrap().0.items.is_empty());
+
+        let value = |tenant| {
+            handlers::get_secret_value(
+                State(state.clone()),
+                tenant,
+                axum::extract::Path(secret.id.0.clone()),
+            )
+        };
+        assert!(value(tenant("tenant2")).await.is_err());
+        assert_eq!(value(tenant("tenant1")).await.unwrap().0.value, "tenant1-db-password");
+
+        // Only admins rotate the master key, and the value survives it
+        state
+            .rbac_manager
+            .register_user(shiioo_core::rbac::RbacUser::new(
+                "dave".to_string(),
+                "dave".to_string(),
+                "dave@example.com".to_string(),
+            ))
+            .unwrap();
+        state.rbac_manager.assign_role("dave", "secret_manager").unwrap();
+        let (dave, _) = state
+            .api_keys
+            .create(NewApiKey {
+                name: "ci".to_string(),
+                user_id: "dave".to_string(),
+                tenant_id: None,
+                allowed_tenants: Vec::new(),
+                allowed_actions: Vec::new(),
+                expires_at: None,
+            })
+            .unwrap();
+        let rotate = |caller, key: &str| {
+            handlers::rotate_master_key(
+                State(state.clone()),
+                caller,
+                None,
+                Json(handlers::RotateMasterKeyRequest {
+                    new_master_key: key.to_string(),
+                }),
+            )
+        };
+        let err = rotate(Some(axum::Extension(dave)), &"k".repeat(32)).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+        let err = rotate(None, "short").await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let Json(rotated) = rotate(None, &"k".repeat(32)).await.unwrap();
+        assert_eq!(rotated.reencrypted, 2);
+        assert_eq!(value(tenant("tenant1")).await.unwrap().0.value, "tenant1-db-password");
+    }
+
+    #[tokio::test]
+    async fn test_tag_scoped_reader_sees_only_selected_runs() {
+        use shiioo_core::api_key::NewApiKey;
+        use shiioo_core::rbac::{Action, Permission, RbacRole, RbacUser, Resource, ResourceSelector};
+        use shiioo_core::storage::index::run_tag_key;
+        use shiioo_core::types::RunId;
+
+        let state = create_test_state("tag-scoped-runs");
+        let mut payments_reader = RbacRole::new(
+            "payments_reader".to_string(),
+            "Payments reader".to_string(),
+            "Reads payments runs".to_string(),
+        );
+        payments_reader.add_permission(Permission::with_selector(
+            Resource::Workflow,
+            Action::Read,
+            ResourceSelector::Tag(run_tag_key("team", "payments")),
+        ));
+        state.rbac_manager.register_role(payments_reader).unwrap();
+        state
+            .rbac_manager
+            .register_user(RbacUser::new(
+                "carol".to_string(),
+                "carol".to_string(),
+                "carol@example.com".to_string(),
+            ))
+            .unwrap();
+        state.rbac_manager.assign_role("carol", "payments_reader").unwrap();
+        let (key, _) = state
+            .api_keys
+            .create(NewApiKey {
+                name: "carol".to_string(),
+                user_id: "carol".to_string(),
+                tenant_id: None,
+                allowed_tenants: Vec::new(),
+                allowed_actions: Vec::new(),
+                expires_at: None,
+            })
+            .unwrap();
+
+        let mut run_ids = Vec::new();
+        for team in ["payments", "search"] {
+            let run_id = RunId::new();
+            state
+                .index_store
+                .index_run(&Run {
+                    id: run_id,
+                    work_item_id: format!("{}-job", team),
+                    status: RunStatus::Completed,
+                    started_at: chrono::Utc::now(),
+                    completed_at: None,
+                    steps: vec![],
+                    parent_run_id: None,
+                    external_id: None,
+                    workflow_hash: None,
+                    retry_of: None,
+                    tags: HashMap::from([("team".to_string(), team.to_string())]),
+                    inputs: HashMap::new(),
+                    priority: 0,
+                    tenant_id: None,
+                })
+                .unwrap();
+            run_ids.push(run_id);
+        }
+
+        let key = || Some(axum::Extension(key.clone()));
+        let get = |run_id: RunId| {
+            let path = axum::extract::Path(run_id.0.to_string());
+            handlers::get_run(State(state.clone()), None, key(), path)
+        };
+        assert_eq!(get(run_ids[0]).await.unwrap().0.id, run_ids[0]);
+        let err = get(run_ids[1]).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+
+        let path = axum::extract::Path(run_ids[1].0.to_string());
+        let err = handlers::get_run_events(State(state.clone()), None, key(), path)
+            .await
+            .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+
+        let Json(listed) = handlers::list_runs(
+            State(state.clone()),
+            None,
+            key(),
+            axum::extract::Query(handlers::ListRunsQueryParams {
+                work_item_id: None,
+                status: None,
+                tag: None,
+            }),
+            axum::extract::Query(handlers::ListQueryParams::default()),
+        )
+        .await
+        .unwrap();
+        let listed: Vec<_> = listed.items.iter().map(|run| run.id).collect();
+        assert_eq!(listed, vec![run_ids[0]]);
+    }
+
+    #[tokio::test]
+    async fn test_list_runs_by_tag() {
+        use tower::ServiceExt;
+
+        let state = create_test_state("run-tags");
+
+        let mut run_ids = Vec::new();
+        for team in ["payments", "search"] {
+            let request = handlers::CreateJobRequest {
+                name: format!("{} job", team),
+                description: None,
+                workflow: WorkflowSpec {
+                    steps: vec![StepSpec {
+                        id: StepId::new("step1"),
+                        name: "Step 1".to_string(),
+                        description: None,
+                        role: RoleId::new("engineer"),
+                        action: StepAction::AgentTask {
+                            prompt: "Reconcile ledgers".to_string(),
+                        },
+                        timeout_secs: None,
+                        retry_policy: None,
+                        requires_approval: false,
+                    }],
+                    dependencies: Default::default(),
+                    inputs: Vec::new(),
+                },
+                created_by: None,
+                execute: Some(true),
+                external_id: None,
+                tags: HashMap::from([
+                    ("team".to_string(), team.to_string()),
+                    ("env".to_string(), "staging".to_string()),
+                ]),
+                inputs: HashMap::new(),
+                priority: None,
+            };
+            let Json(created) = handlers::create_job(State(state.clone()), None, Json(request))
+                .await
+                .unwrap();
+            run_ids.push(created.run_id.unwrap());
+        }
+
+        let schema = crate::graphql::build_schema(state.clone());
+        let router = create_router((*state).clone(), schema);
+        let list = |uri: String| {
+            let router = router.clone();
+            async move {
+                let request = axum::http::Request::builder()
+                    .uri(uri)
+                    .body(axum::body::Body::empty())
+                    .unwrap();
+                let response = router.oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<Paginated<Run>>(&bytes).unwrap()
+            }
+        };
+
+        let runs = list("/api/runs?tag=team:payments".to_string()).await.items;
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].id, run_ids[0]);
+        assert_eq!(runs[0].tags["team"], "payments");
+
+        assert_eq!(list("/api/runs?tag=env:staging".to_string()).await.items.len(), 2);
+        assert!(list("/api/runs?tag=team:billing".to_string()).await.items.is_empty());
+        assert_eq!(list("/api/runs".to_string()).await.items.len(), 2);
+
+        // Pages report the overall total and whether more remain
+        let first = list("/api/runs?tag=env:staging&limit=1".to_string()).await;
+        assert_eq!((first.items.len(), first.total, first.has_more), (1, 2, true));
+        let cursor = first.next_cursor.unwrap();
+        let uri = format!("/api/runs?tag=env:staging&limit=1&cursor={}", cursor);
+        let second = list(uri).await;
+        assert_eq!((second.items.len(), second.offset, second.has_more), (1, 1, false));
+        assert_ne!(second.items[0].id, first.items[0].id);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_capacity_usage_batch() {
+        let state = create_test_state("usage-batch");
+
+        state
+            .capacity_broker
+            .register_source(CapacitySource {
+                id: CapacitySourceId::new("anthropic"),
+                name: "Anthropic".to_string(),
+                provider: LlmProvider::Anthropic,
+                api_key_hash: "hash".to_string(),
+                model: "claude-opus-4".to_string(),
+                rate_limits: RateLimits {
+                    requests_per_minute: 60,
+                    tokens_per_minute: 100_000,
+                    tokens_per_day: None,
+                },
+                cost_per_token: CostPerToken {
+                    input_cost: 15.0,
+                    output_cost: 75.0,
+                },
+                priority: 1,
+                enabled: true,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+
+        let usage = |id: &str, source: &str| CapacityUsage {
+            id: id.to_string(),
+            source_id: CapacitySourceId::new(source),
+            timestamp: chrono::Utc::now(),
+            input_tokens: 100,
+            output_tokens: 50,
+            total_tokens: 150,
+            cost: 0.01,
+            request_count: 1,
+            run_id: None,
+            step_id: None,
+            fallback_from: None,
+        };
+
+        let ingest = || {
+            handlers::ingest_capacity_usage(
+                State(state.clone()),
+                Json(vec![
+                    usage("u1", "anthropic"),
+                    usage("u2", "unknown"),
+                    usage("u3", "anthropic"),
+                ]),
+            )
+        };
+        let Json(response) = ingest().await.unwrap();
+
+        assert_eq!(response.stored, 2);
+        assert!(response.results[0].error.is_none());
+        assert!(response.results[1].error.as_ref().unwrap().contains("unknown"));
+        assert!(response.results[2].error.is_none());
+
+        let mut stored: Vec<_> = state
+            .index_store
+            .list_capacity_usage()
+            .unwrap()
+            .into_iter()
+            .map(|u| u.id)
+            .collect();
+        stored.sort();
+        assert_eq!(stored, vec!["u1", "u3"]);
+
+        let limits = state
+            .capacity_broker
+            .get_rate_limit_state(&CapacitySourceId::new("anthropic"))
+            .unwrap();
+        assert_eq!(limits.requests_in_window, 2);
+        assert_eq!(limits.tokens_in_window, 300);
+
+        // A client retrying the batch after a lost response doesn't count it twice
+        let Json(retry) = ingest().await.unwrap();
+        assert_eq!(retry.stored, 0);
+        assert!(retry.results[0].duplicate && retry.results[2].duplicate);
+        assert_eq!(state.index_store.list_capacity_usage().unwrap().len(), 2);
+        let limits = state
+            .capacity_broker
+            .get_rate_limit_state(&CapacitySourceId::new("anthropic"))
+            .unwrap();
+        assert_eq!(limits.requests_in_window, 2);
+        assert_eq!(limits.tokens_in_window, 300);
+    }
+
+    #[tokio::test]
+    async fn test_role_budget_reports_usage_and_remaining() {
+        use shiioo_core::policy::PolicyEngine;
+
+        let state = create_test_state("role-budget");
+
+        let role = RoleSpec {
+            id: RoleId::new("analyst"),
+            name: "Analyst".to_string(),
+            description: "Reads reports".to_string(),
+            prompt_template: String::new(),
+            allowed_tools: vec![],
+            budgets: RoleBudgets {
+                daily_tokens: Some(10_000),
+                daily_cost_cents: None,
+                ..Default::default()
+            },
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        };
+        state.index_store.store_role(&role).unwrap();
+
+        state.policy_engine.record_usage(&role.id, 2_500, 40).await.unwrap();
+        state.policy_engine.record_usage(&role.id, 500, 10).await.unwrap();
+
+        let Json(budget) = handlers::get_role_budget(
+            State(state.clone()),
+            axum::extract::Path(role.id.0.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(budget.usage.tokens_used, 3_000);
+        assert_eq!(budget.usage.cost_cents, 50);
+        assert_eq!(budget.remaining_tokens, Some(7_000));
+        assert_eq!(budget.remaining_cost_cents, None);
+        assert!(budget.resets_at > chrono::Utc::now());
+    }
+
+    #[tokio::test]
+    async fn test_signal_run_returns_the_run() {
+        use shiioo_core::events::{Event, EventLog, EventType};
+        use shiioo_core::types::RunId;
+
+        let state = create_test_state("signal-run");
+        let run_id = RunId::new();
+        state
+            .index_store
+            .index_run(&Run {
+                id: run_id,
+                work_item_id: "deploy".to_string(),
+                status: RunStatus::Running,
+                started_at: chrono::Utc::now(),
+                completed_at: None,
+                steps: vec![],
+                parent_run_id: None,
+                external_id: None,
+                workflow_hash: None,
+                retry_of: None,
+                tags: HashMap::new(),
+                inputs: HashMap::new(),
+                priority: 0,
+                tenant_id: None,
+            })
+            .unwrap();
+        state
+            .event_log
+            .append(Event::new(
+                run_id,
+                EventType::StepWaiting {
+                    step_id: StepId::new("await_approval"),
+                    event_key: "approved".to_string(),
+                    attempt: 1,
+                    timeout_secs: None,
+                },
+            ))
+            .await
+            .unwrap();
+
+        let Json(signalled) = handlers::signal_run(
+            State(state),
+            None,
+            None,
+            axum::extract::Path(run_id.0.to_string()),
+            Json(handlers::SignalRunRequest {
+                event_key: "approved".to_string(),
+                payload: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(signalled.resumed_steps, vec![StepId::new("await_approval")]);
+        assert_eq!(signalled.run.id, run_id);
+        assert_eq!(signalled.run.status, RunStatus::Running);
+    }
+
+    #[tokio::test]
+    async fn test_explain_tool_call_checks_recorded_budget_usage() {
+        use shiioo_core::policy::{PolicyDecision, PolicyEngine};
+
+        let state = create_test_state("explain-budget");
+        let role = RoleSpec {
+            id: RoleId::new("analyst"),
+            name: "Analyst".to_string(),
+            description: "Reads reports".to_string(),
+            prompt_template: String::new(),
+            allowed_tools: vec![],
+            budgets: RoleBudgets {
+                daily_tokens: Some(1_000),
+                ..Default::default()
+            },
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        };
+        state.index_store.store_role(&role).unwrap();
+        state.policy_engine.reload(vec![role.clone()], vec![]).await;
+        state
+            .policy_engine
+            .record_usage(&role.id, 1_500, 0)
+            .await
+            .unwrap();
+
+        let Json(explained) = handlers::explain_tool_call(
+            State(state.clone()),
+            Json(handlers::ExplainToolCallRequest {
+                role_id: "analyst".to_string(),
+                tool_id: "context_get".to_string(),
+                tool_tier: 0,
+                parameters: serde_json::Value::Null,
+            }),
+        )
+        .await
+        .unwrap();
+        let PolicyDecision::Deny { reason } = &explained.decision else {
+            panic!("expected a deny, got {:?}", explained.decision);
+        };
+        assert!(reason.contains("budget exceeded"), "{}", reason);
+    }
+
+    #[tokio::test]
+    async fn test_agent_task_usage_counts_against_role_budget() {
+        let state = create_test_state("agent-budget");
+        state
+            .capacity_broker
+            .register_source(CapacitySource {
+                id: CapacitySourceId::new("anthropic"),
+                name: "Anthropic".to_string(),
+                provider: LlmProvider::Anthropic,
+                api_key_hash: "hash".to_string(),
+                model: "claude-opus-4".to_string(),
+                rate_limits: RateLimits {
+                    requests_per_minute: 60,
+                    tokens_per_minute: 100_000,
+                    tokens_per_day: None,
+                },
+                cost_per_token: CostPerToken {
+                    input_cost: 15.0,
+                    output_cost: 75.0,
+                },
+                priority: 1,
+                enabled: true,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        let role = RoleSpec {
+            id: RoleId::new("analyst"),
+            name: "Analyst".to_string(),
+            description: "Reads reports".to_string(),
+            prompt_template: String::new(),
+            allowed_tools: vec![],
+            budgets: RoleBudgets {
+                daily_tokens: Some(100_000),
+                ..Default::default()
+            },
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        };
+        state.index_store.store_role(&role).unwrap();
+        state.policy_engine.reload(vec![role.clone()], vec![]).await;
+
+        let workflow = WorkflowSpec {
+            steps: vec![StepSpec {
+                id: StepId::new("summarize"),
+                name: "Summarize".to_string(),
+                description: None,
+                role: role.id.clone(),
+                action: StepAction::AgentTask {
+                    prompt: "Summarize the quarter".to_string(),
+                },
+                timeout_secs: None,
+                retry_policy: None,
+                requires_approval: false,
+            }],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+        let run = state
+            .workflow_executor
+            .execute("report".to_string(), workflow)
+            .await
+            .unwrap();
+        assert_eq!(run.status, RunStatus::Completed);
+
+        // The budget endpoint reads the usage the executor recorded
+        let Json(budget) = handlers::get_role_budget(
+            State(state.clone()),
+            axum::extract::Path(role.id.0.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(Some(budget.usage.tokens_used), run.steps[0].tokens);
+        assert!(budget.usage.tokens_used > 0);
+    }
+
+    #[tokio::test]
+    async fn test_executor_enforces_stored_policies() {
+        use shiioo_core::events::{EventLog, EventType};
+        use shiioo_core::types::{PolicyId, PolicyRule, PolicySpec, ToolCallSpec};
+
+        let state = create_test_state("executor-policy");
+        let Json(_) = handlers::create_role(
+            State(state.clone()),
+            Json(RoleSpec {
+                id: RoleId::new("engineer"),
+                name: "Engineer".to_string(),
+                description: "Writes code".to_string(),
+                prompt_template: String::new(),
+                allowed_tools: vec!["context_get".to_string()],
+                budgets: RoleBudgets::default(),
+                requires_approval_for: vec![],
+                model_fallback: vec![],
+                max_concurrent: None,
+                claude_settings: None,
+            }),
+        )
+        .await
+        .unwrap();
+        let Json(_) = handlers::create_policy(
+            State(state.clone()),
+            Json(PolicySpec {
+                id: PolicyId("no-secrets".to_string()),
+                name: "No secrets".to_string(),
+                description: "Keep agents out of secrets".to_string(),
+                rules: vec![PolicyRule::DenyPath {
+                    patterns: vec!["secrets/".to_string()],
+                }],
+            }),
+        )
+        .await
+        .unwrap();
+
+        let workflow = WorkflowSpec {
+            steps: vec![StepSpec {
+                id: StepId::new("read"),
+                name: "Read".to_string(),
+                description: None,
+                role: RoleId::new("engineer"),
+                action: StepAction::ToolSequence {
+                    tools: vec![ToolCallSpec {
+                        tool_id: "context_get".to_string(),
+                        parameters: serde_json::json!({ "key": "secrets/prod.env" }),
+                    }],
+                    continue_on_error: false,
+                },
+                timeout_secs: None,
+                retry_policy: None,
+                requires_approval: false,
+            }],
+            dependencies: Default::default(),
+            inputs: Vec::new(),
+        };
+        let run = state
+            .workflow_executor
+            .execute("work-1".to_string(), workflow)
+            .await
+            .unwrap();
+        assert_eq!(run.status, RunStatus::Failed);
+
+        let events = state.event_log.get_run_events(run.id).await.unwrap();
+        assert!(events.iter().any(|e| matches!(
+            &e.event_type,
+            EventType::ToolCallDenied { reason, .. } if reason.contains("secrets/")
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_config_change_apply() {
+        let state = create_test_state("dry-run-apply");
+
+        let role = |prompt: &str| RoleSpec {
+            id: RoleId::new("engineer"),
+            name: "Engineer".to_string(),
+            description: "Writes code".to_string(),
+            prompt_template: prompt.to_string(),
+            allowed_tools: vec![],
+            budgets: RoleBudgets {
+                daily_tokens: None,
+                daily_cost_cents: None,
+                ..Default::default()
+            },
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        };
+        state.index_store.store_role(&role("v1")).unwrap();
+
+        let change = state
+            .config_change_manager
+            .propose_change(
+                ConfigChangeType::Role,
+                "Update engineer prompt".to_string(),
+                Some(serde_json::to_string(&role("v1")).unwrap()),
+                serde_json::to_string(&role("v2")).unwrap(),
+                "admin".to_string(),
+                None,
+            )
+            .unwrap();
+
+        let dry_run = |state: Arc<AppState>| {
+            handlers::apply_config_change(
+                State(state),
+                axum::extract::Path(change.id.0.clone()),
+                axum::extract::Query(handlers::ApplyConfigChangeQueryParams {
+                    dry_run: true,
+                    term: None,
+                }),
+            )
+        };
+
+        let Json(response) = dry_run(state.clone()).await.unwrap();
+        assert!(response.dry_run);
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        // Nothing was touched
+        let stored = state.index_store.get_role(&RoleId::new("engineer")).unwrap().unwrap();
+        assert_eq!(stored.prompt_template, "v1");
+        let change_status = state.config_change_manager.get_change(&change.id).unwrap().status;
+        assert_eq!(change_status, ConfigChangeStatus::Proposed);
+
+        // Someone else edits the role; the dry run now reports the conflict
+        state.index_store.store_role(&role("edited")).unwrap();
+        let Json(response) = dry_run(state.clone()).await.unwrap();
+        assert_eq!(response.errors.len(), 1);
+        assert!(response.errors[0].contains("changed underneath you"));
+    }
+
+    #[tokio::test]
+    async fn test_config_change_impact_counts_paused_runs() {
+        use shiioo_core::events::{Event, EventLog, EventType};
+        use shiioo_core::types::RunId;
+
+        let state = create_test_state("impact-paused");
+        let workflow = WorkflowSpec {
+            steps: vec![StepSpec {
+                id: StepId::new("build"),
+                name: "Build".to_string(),
+                description: None,
+                role: RoleId::new("engineer"),
+                action: StepAction::AgentTask {
+                    prompt: "Build it".to_string(),
+                },
+                timeout_secs: None,
+                retry_policy: None,
+                requires_approval: false,
+            }],
+            dependencies: HashMap::new(),
+            inputs: Vec::new(),
+        };
+
+        let mut run_ids = Vec::new();
+        for status in [RunStatus::Paused, RunStatus::Running, RunStatus::Completed] {
+            let run_id = RunId::new();
+            state
+                .index_store
+                .index_run(&Run {
+                    id: run_id,
+                    work_item_id: "build".to_string(),
+                    status,
+                    started_at: chrono::Utc::now(),
+                    completed_at: None,
+                    steps: vec![],
+                    parent_run_id: None,
+                    external_id: None,
+                    workflow_hash: None,
+                    retry_of: None,
+                    tags: HashMap::new(),
+                    inputs: HashMap::new(),
+                    priority: 0,
+                    tenant_id: None,
+                })
+                .unwrap();
+            state
+                .event_log
+                .append(Event::new(
+                    run_id,
+                    EventType::RunStarted {
+                        work_item_id: "build".to_string(),
+                        workflow_spec: workflow.clone(),
+                    },
+                ))
+                .await
+                .unwrap();
+            run_ids.push(run_id);
+        }
+
+        let role = RoleSpec {
+            id: RoleId::new("engineer"),
+            name: "Engineer".to_string(),
+            description: "Writes code".to_string(),
+            prompt_template: "v2".to_string(),
+            allowed_tools: vec![],
+            budgets: Default::default(),
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        };
+        let change = state
+            .config_change_manager
+            .propose_change(
+                ConfigChangeType::Role,
+                "Update engineer".to_string(),
+                None,
+                serde_json::to_string(&role).unwrap(),
+                "admin".to_string(),
+                None,
+            )
+            .unwrap();
+
+        let Json(impact) = handlers::get_config_change_impact(
+            State(state.clone()),
+            axum::extract::Path(change.id.0.clone()),
+        )
+        .await
+        .unwrap();
+
+        // The completed run is done; the paused one will pick up the change on resume
+        let mut affected = impact.affected_runs;
+        affected.sort_by_key(|id| id.0);
+        let mut expected = run_ids[..2].to_vec();
+        expected.sort_by_key(|id| id.0);
+        assert_eq!(affected, expected);
+    }
+
+    #[tokio::test]
+    async fn test_nodes_sharing_leader_lock_elect_one_leader() {
+        let lock = Arc::new(shiioo_core::cluster::DistributedLock::new(30));
+        let first = AppState::with_leader_lock(&test_config("shared-lock-1"), lock.clone()).unwrap();
+        let second = AppState::with_leader_lock(&test_config("shared-lock-2"), lock).unwrap();
+
+        assert!(first.cluster_manager.is_leader());
+        assert_eq!(first.cluster_manager.leader_term(), Some(1));
+        assert!(!second.cluster_manager.is_leader());
+        assert_eq!(second.cluster_manager.leader_term(), None);
+        assert_eq!(second.cluster_manager.current_term(), 1);
+
+        // The second node takes over in a new term once the first steps down
+        let first_id = first.cluster_manager.local_node_id().clone();
+        let second_id = second.cluster_manager.local_node_id().clone();
+        first.leader_election.step_down(&first_id).unwrap();
+        assert!(second.leader_election.try_become_leader(&second_id).unwrap());
+        assert_eq!(second.cluster_manager.leader_term(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_config_change_apply_fenced_by_leader_term() {
+        let state = create_test_state("fenced-apply");
+
+        let role = |id: &str| RoleSpec {
+            id: RoleId::new(id),
+            name: id.to_string(),
+            description: "Writes code".to_string(),
+            prompt_template: "v1".to_string(),
+            allowed_tools: vec![],
+            budgets: Default::default(),
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        };
+        let propose = |role: &RoleSpec| {
+            state
+                .config_change_manager
+                .propose_change(
+                    ConfigChangeType::Role,
+                    format!("Add {}", role.id.0),
+                    None,
+                    serde_json::to_string(role).unwrap(),
+                    "admin".to_string(),
+                    None,
+                )
+                .unwrap()
+        };
+        let apply = |change: &ConfigChange, term| {
+            handlers::apply_config_change(
+                State(state.clone()),
+                axum::extract::Path(change.id.0.clone()),
+                axum::extract::Query(handlers::ApplyConfigChangeQueryParams {
+                    dry_run: false,
+                    term,
+                }),
+            )
+        };
+
+        // A standalone server leads its own cluster from startup
+        let term = state.cluster_manager.leader_term().unwrap();
+        let engineer = propose(&role("engineer"));
+        let err = apply(&engineer, Some(term + 1)).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::CONFLICT);
+        let Json(applied) = apply(&engineer, Some(term)).await.unwrap();
+        assert_eq!(applied.change.status, ConfigChangeStatus::Applied);
+
+        // A node reports that a newer leader was elected in a later term
+        let Json(node) = handlers::register_cluster_node(
+            State(state.clone()),
+            Json(handlers::RegisterNodeRequest {
+                address: "http://node-2".to_string(),
+                region: None,
+                metadata: None,
+            }),
+        )
+        .await
+        .unwrap();
+        let Json(heartbeat) = handlers::node_heartbeat(
+            State(state.clone()),
+            axum::extract::Path(node.id.0.clone()),
+            axum::extract::Query(handlers::HeartbeatQueryParams { term: Some(term + 2) }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(heartbeat.term, term + 2);
+
+        // A term implausibly far ahead is refused rather than adopted
+        let err = handlers::node_heartbeat(
+            State(state.clone()),
+            axum::extract::Path(node.id.0.clone()),
+            axum::extract::Query(handlers::HeartbeatQueryParams { term: Some(u64::MAX) }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(state.cluster_manager.current_term(), term + 2);
+
+        // This node still thinks it leads, but its writes carry the superseded term
+        let reviewer = propose(&role("reviewer"));
+        for expected in [None, Some(term)] {
+            let err = apply(&reviewer, expected).await.unwrap_err();
+            assert!(err.0.to_string().contains("stale leader term"), "{}", err.0);
+            assert_eq!(err.into_response().status(), StatusCode::CONFLICT);
+        }
+        assert!(state.index_store.get_role(&RoleId::new("reviewer")).unwrap().is_none());
+        let status = state.config_change_manager.get_change(&reviewer.id).unwrap().status;
+        assert_eq!(status, ConfigChangeStatus::Proposed);
+
+        // Once demoted it refuses outright, so the caller retries against the leader
+        let local_id = state.cluster_manager.local_node_id().clone();
+        let mut local = state.cluster_manager.get_node(&local_id).unwrap();
+        local.role = NodeRole::Follower;
+        state.cluster_manager.register_node(local).unwrap();
+        let err = apply(&reviewer, None).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_unusable_data_dir_fails_startup() {
+        // A regular file where the data directory should be; unlike permissions this
+        // holds even when the tests run as root
+        let file = std::env::temp_dir().join(format!("shiioo-file-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&file, b"not a directory").unwrap();
+        let config = ServerConfig {
+            data_dir: file.join("data"),
+            ..Default::default()
+        };
+
+        let error = AppState::new(&config).err().expect("startup should fail");
+        let message = format!("{:#}", error);
+        assert!(message.contains("Data directory is not usable"), "{}", message);
+        assert!(message.contains(&file.display().to_string()), "{}", message);
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_out_of_range_metrics_config_rejected_at_load() {
+        let dir = std::env::temp_dir().join(format!("shiioo-config-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let toml = "[metrics]\nhistory_resolution_secs = 0\nhistory_buckets = 4294967296\n";
+        std::fs::write(&path, toml).unwrap();
+
+        let error = ServerConfig::load(&path, dir.join("data")).unwrap_err();
+        let errors = error.downcast_ref::<ValidationErrors>().unwrap();
+        let fields: Vec<_> = errors.errors().iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["metrics.history_resolution_secs", "metrics.history_buckets"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_metric_history_resolution_out_of_range() {
+        let state = create_test_state("metric-history-range");
+
+        for resolution in [i64::MAX, i64::MIN] {
+            let err = handlers::get_metric_history(
+                State(state.clone()),
+                axum::extract::Query(handlers::MetricHistoryQueryParams {
+                    metric: "requests".to_string(),
+                    since: None,
+                    resolution: Some(resolution),
+                }),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(err.into_response().status(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_logs_returns_captured_output() {
+        use shiioo_core::events::{Event, EventLog, EventType, OutputStream};
+        use shiioo_core::types::RunId;
+
+        let state = create_test_state("run-logs");
+        let run_id = RunId::new();
+        state
+            .event_log
+            .append(Event::new(
+                run_id,
+                EventType::StepOutput {
+                    step_id: StepId::new("build"),
+                    attempt: 1,
+                    stream: OutputStream::Stdout,
+                    content: "compiled".to_string(),
+                    total_bytes: 8,
+                    log_hash: None,
+                },
+            ))
+            .await
+            .unwrap();
+
+        let path = axum::extract::Path(run_id.0.to_string());
+        let Json(response) = handlers::get_run_logs(State(state), None, None, path).await.unwrap();
+        assert_eq!(response.logs.len(), 1);
+        assert_eq!(response.logs[0].step_id, StepId::new("build"));
+        assert_eq!(response.logs[0].content, "compiled");
+        assert!(!response.logs[0].truncated);
+    }
+
+    #[tokio::test]
+    async fn test_propose_config_change_snapshots_current_role() {
+        let state = create_test_state("propose-snapshot");
+
+        let role = |prompt: &str| RoleSpec {
+            id: RoleId::new("engineer"),
+            name: "Engineer".to_string(),
+            description: "Writes code".to_string(),
+            prompt_template: prompt.to_string(),
+            allowed_tools: vec![],
+            budgets: RoleBudgets::default(),
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        };
+        state.index_store.store_role(&role("v1")).unwrap();
+
+        let propose = |state: Arc<AppState>, role: RoleSpec| {
+            handlers::propose_config_change(
+                State(state),
+                Json(handlers::ProposeConfigChangeRequest {
+                    change_type: ConfigChangeType::Role,
+                    description: "Update engineer".to_string(),
+                    before: Some(r#"{"id":"engineer","stale":true}"#.to_string()),
+                    after: serde_json::to_string(&role).unwrap(),
+                    proposed_by: "admin".to_string(),
+                    approval_board: None,
+                }),
+            )
+        };
+
+        let Json(response) = propose(state.clone(), role("v2")).await.unwrap();
+        let change_id = ConfigChangeId::new(response.change_id);
+        let change = state.config_change_manager.get_change(&change_id).unwrap();
+        let before: serde_json::Value = serde_json::from_str(&change.before.unwrap()).unwrap();
+        assert_eq!(before, serde_json::to_value(role("v1")).unwrap());
+
+        // Creating a new role has nothing to snapshot
+        let mut new_role = role("v1");
+        new_role.id = RoleId::new("reviewer");
+        let Json(response) = propose(state.clone(), new_role).await.unwrap();
+        let change_id = ConfigChangeId::new(response.change_id);
+        let change = state.config_change_manager.get_change(&change_id).unwrap();
+        assert_eq!(change.before, None);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_policy_reports_newly_denied_call() {
+        use shiioo_core::events::{Event, EventLog, EventType};
+        use shiioo_core::policy::{PolicyContext, PolicyDecision, PolicyEngine};
+        use shiioo_core::types::{PolicyId, PolicyRule, PolicySpec, RunId};
+
+        let state = create_test_state("policy-simulate");
+        let role = RoleSpec {
+            id: RoleId::new("engineer"),
+            name: "Engineer".to_string(),
+            description: String::new(),
+            prompt_template: String::new(),
+            allowed_tools: vec![],
+            budgets: RoleBudgets::default(),
+            requires_approval_for: vec![],
+            model_fallback: vec![],
+            max_concurrent: None,
+            claude_settings: None,
+        };
+        state.index_store.store_role(&role).unwrap();
+
+        let run_id = RunId::new();
+        state
+            .index_store
+            .index_run(&Run {
+                id: run_id,
+                work_item_id: "deploy".to_string(),
+                status: RunStatus::Completed,
+                started_at: chrono::Utc::now() - chrono::Duration::minutes(5),
+                completed_at: Some(chrono::Utc::now()),
+                steps: vec![],
+                parent_run_id: None,
+                external_id: None,
+                workflow_hash: None,
+                retry_of: None,
+                tags: HashMap::new(),
+                inputs: HashMap::new(),
+                priority: 0,
+                tenant_id: None,
+            })
+            .unwrap();
+        for path in ["/etc/passwd", "/srv/app/config.toml"] {
+            let parameters = serde_json::json!({ "path": path });
+            let parameters_hash = state
+                .blob_store
+                .put(serde_json::to_vec(&parameters).unwrap().into())
+                .await
+                .unwrap();
+            state
+                .event_log
+                .append(Event::new(
+                    run_id,
+                    EventType::ToolCallProposed {
+                        step_id: StepId::new("read"),
+                        tool_id: "file_read".to_string(),
+                        parameters_hash,
+                        role_id: Some(role.id.clone()),
+                        tool_tier: 0,
+                    },
+                ))
+                .await
+                .unwrap();
+        }
+
+        let request = handlers::SimulatePolicyRequest {
+            policy: PolicySpec {
+                id: PolicyId("protect-etc".to_string()),
+                name: "Protect /etc".to_string(),
+                description: String::new(),
+                rules: vec![PolicyRule::DenyPath {
+                    patterns: vec!["/etc/".to_string()],
+                }],
+            },
+            since: None,
+            until: None,
+        };
+        let Json(simulation) = handlers::simulate_policy(State(state.clone()), Json(request))
+            .await
+            .unwrap();
+
+        assert_eq!(simulation.evaluated, 2);
+        assert_eq!(simulation.changes.len(), 1);
+        let change = &simulation.changes[0];
+        assert_eq!(change.run_id, run_id);
+        assert_eq!(change.current, PolicyDecision::Allow);
+        assert!(matches!(change.simulated, PolicyDecision::Deny { .. }));
+
+        // The candidate was neither stored nor loaded into the live engine
+        assert!(state.index_store.list_policies().unwrap().is_empty());
+        state.policy_engine.load_roles(vec![role]).await.unwrap();
+        let context = PolicyContext {
+            role_id: RoleId::new("engineer"),
+            tool_id: "file_read".to_string(),
+            tool_tier: 0,
+            parameters: serde_json::json!({ "path": "/etc/passwd" }),
+            timestamp: chrono::Utc::now(),
+        };
+        let decision = state.policy_engine.check_tool_call(&context).await.unwrap();
+        assert_eq!(decision, PolicyDecision::Allow);
+    }
+}
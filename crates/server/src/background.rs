@@ -0,0 +1,294 @@
+use crate::config::{AppState, BackgroundConfig};
+use shiioo_core::secrets::{RandomSecretRotator, SecretRotationExecutor, SecretType};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{watch, Semaphore};
+use tokio::task::{AbortHandle, JoinSet};
+use tokio::time::MissedTickBehavior;
+
+/// Periodic background tasks sharing a concurrency cap and a shutdown signal
+pub struct BackgroundTasks {
+    tasks: Arc<Mutex<JoinSet<()>>>,
+    /// One-off work such as routine runs. It can run for as long as a workflow does, so
+    /// it takes no permit and shutdown cancels rather than waits for it.
+    work: Arc<Mutex<JoinSet<()>>>,
+    permits: Arc<Semaphore>,
+    shutdown: watch::Sender<bool>,
+}
+
+/// Starts one-off work on a [`BackgroundTasks`] set, outside its concurrency cap and
+/// cancelled on shutdown
+#[derive(Clone)]
+pub struct TaskSpawner {
+    work: Arc<Mutex<JoinSet<()>>>,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl TaskSpawner {
+    /// Run `work` until it finishes or the task set shuts down
+    pub fn spawn(&self, work: impl Future<Output = ()> + Send + 'static) -> AbortHandle {
+        let mut shutdown = self.shutdown.clone();
+        self.work.lock().unwrap().spawn(async move {
+            tokio::select! {
+                biased;
+                _ = shutdown.wait_for(|stop| *stop) => {}
+                _ = work => {}
+            }
+        })
+    }
+}
+
+impl BackgroundTasks {
+    pub fn new(max_concurrent_tasks: usize) -> Self {
+        Self {
+            tasks: Arc::new(Mutex::new(JoinSet::new())),
+            work: Arc::new(Mutex::new(JoinSet::new())),
+            permits: Arc::new(Semaphore::new(max_concurrent_tasks.max(1))),
+            shutdown: watch::channel(false).0,
+        }
+    }
+
+    /// Spawn the server's periodic tasks with the configured intervals
+    pub fn start(state: &AppState, config: &BackgroundConfig) -> Self {
+        let mut tasks = Self::new(config.max_concurrent_tasks);
+
+        // Routine executions outlive the pass that starts them, so they're started on
+        // the task set's spawner, which cancels them on shutdown
+        let scheduler = state.routine_scheduler.clone();
+        let spawner = tasks.spawner();
+        tasks.spawn_periodic(
+            "routine scheduler",
+            Duration::from_secs(config.routine_interval_secs),
+            move || {
+                let scheduler = scheduler.clone();
+                let spawner = spawner.clone();
+                async move { scheduler.run_due_on(|run| spawner.spawn(run)) }
+            },
+        );
+
+        let broker = state.capacity_broker.clone();
+        tasks.spawn_periodic(
+            "capacity queue",
+            Duration::from_secs(config.capacity_queue_interval_secs),
+            move || {
+                let broker = broker.clone();
+                async move { broker.process_queue().await }
+            },
+        );
+
+        // Only generic secrets get a random value. Every other type is checked by
+        // something outside Shiioo (a database, a provider), which a new value here
+        // wouldn't reach, so they are left for manual rotation.
+        let rotation = Arc::new(
+            SecretRotationExecutor::new(state.secret_manager.clone())
+                .with_rotator(SecretType::Generic, Arc::new(RandomSecretRotator)),
+        );
+        tasks.spawn_periodic(
+            "secret rotation",
+            Duration::from_secs(config.secret_rotation_interval_secs),
+            move || {
+                let rotation = rotation.clone();
+                async move {
+                    rotation.rotate_due().await;
+                }
+            },
+        );
+
+        let executor = state.workflow_executor.clone();
+        tasks.spawn_periodic(
+            "orphan reaper",
+            Duration::from_secs(config.orphan_reaper_interval_secs),
+            move || {
+                let executor = executor.clone();
+                async move {
+                    if let Err(e) = executor.reap_orphans().await {
+                        tracing::error!("Failed to reap orphaned runs: {}", e);
+                    }
+                }
+            },
+        );
+
+        let cluster = state.cluster_manager.clone();
+        let election = state.leader_election.clone();
+        tasks.spawn_periodic(
+            "stale node sweep",
+            Duration::from_secs(config.stale_node_interval_secs),
+            move || {
+                let cluster = cluster.clone();
+                let election = election.clone();
+                async move {
+                    // This node is alive by definition
+                    let _ = cluster.heartbeat(cluster.local_node_id());
+                    // Renew the leader lease, or take over from a leader that let it lapse
+                    if let Err(e) = election.try_become_leader(cluster.local_node_id()) {
+                        tracing::error!("Failed to renew leader election: {}", e);
+                    }
+                    let stale = cluster.check_stale_nodes();
+                    if !stale.is_empty() {
+                        tracing::warn!("{} cluster nodes missed their heartbeat", stale.len());
+                    }
+                }
+            },
+        );
+
+        let event_log = state.event_log.clone();
+        tasks.spawn_periodic(
+            "event log compaction",
+            Duration::from_secs(config.event_compaction_interval_secs),
+            move || {
+                let event_log = event_log.clone();
+                async move {
+                    if let Err(e) = event_log.compact().await {
+                        tracing::error!("Failed to compact event log: {}", e);
+                    }
+                }
+            },
+        );
+
+        let approvals = state.approval_manager.clone();
+        tasks.spawn_periodic(
+            "approval reminders",
+            Duration::from_secs(config.approval_reminder_interval_secs),
+            move || {
+                let approvals = approvals.clone();
+                async move {
+                    approvals.send_due_reminders();
+                }
+            },
+        );
+
+        tasks
+    }
+
+    /// A handle for starting one-off work on this task set
+    pub fn spawner(&self) -> TaskSpawner {
+        TaskSpawner {
+            work: self.work.clone(),
+            shutdown: self.shutdown.subscribe(),
+        }
+    }
+
+    /// Run `pass` every `interval`, starting immediately, until shutdown. Each pass holds
+    /// one of the shared permits while it runs.
+    pub fn spawn_periodic<F, Fut>(&mut self, name: &'static str, interval: Duration, mut pass: F)
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let permits = self.permits.clone();
+        let mut shutdown = self.shutdown.subscribe();
+        self.tasks.lock().unwrap().spawn(async move {
+            let mut ticker = tokio::time::interval(interval.max(Duration::from_millis(1)));
+            // Passes delayed by the concurrency cap shouldn't then fire back to back
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = shutdown.changed() => break,
+                    _ = ticker.tick() => {}
+                }
+                let permit = tokio::select! {
+                    _ = shutdown.changed() => break,
+                    permit = permits.acquire() => permit,
+                };
+                let Ok(_permit) = permit else { break };
+                pass().await;
+            }
+            tracing::debug!("Background task '{}' stopped", name);
+        });
+    }
+
+    /// Signal every task to stop and wait for them. A pass already under way is run to
+    /// completion first; one-off work is cancelled, leaving an interrupted run
+    /// unfinished just as a restart would.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        while let Some(result) = tasks.join_next().await {
+            if let Err(e) = result {
+                tracing::error!("Background task failed: {}", e);
+            }
+        }
+        // Finished passes can't start any more work, as the spawner checks the signal
+        let mut work = std::mem::take(&mut *self.work.lock().unwrap());
+        while let Some(result) = work.join_next().await {
+            if let Err(e) = result {
+                tracing::error!("Background work failed: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn counting_pass(count: &Arc<AtomicUsize>) -> impl FnMut() -> std::future::Ready<()> {
+        let count = count.clone();
+        move || {
+            count.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_interval_respected_and_shutdown_joins_tasks() {
+        let fast = Arc::new(AtomicUsize::new(0));
+        let slow = Arc::new(AtomicUsize::new(0));
+
+        let mut tasks = BackgroundTasks::new(1);
+        tasks.spawn_periodic("fast", Duration::from_secs(10), counting_pass(&fast));
+        tasks.spawn_periodic("slow", Duration::from_secs(60), counting_pass(&slow));
+        assert_eq!(tasks.tasks.lock().unwrap().len(), 2);
+
+        // Ticks at 0s, 10s and 20s; the slow task only fires at 0s
+        tokio::time::sleep(Duration::from_secs(25)).await;
+        assert_eq!(fast.load(Ordering::SeqCst), 3);
+        assert_eq!(slow.load(Ordering::SeqCst), 1);
+
+        tasks.shutdown().await;
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(fast.load(Ordering::SeqCst), 3);
+        assert_eq!(slow.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_spawned_work_uncapped_and_cancelled_on_shutdown() {
+        let done = Arc::new(AtomicUsize::new(0));
+        let passes = Arc::new(AtomicUsize::new(0));
+        let mut tasks = BackgroundTasks::new(1);
+        let spawner = tasks.spawner();
+
+        for _ in 0..3 {
+            let done = done.clone();
+            spawner.spawn(async move {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                done.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        let long_run = done.clone();
+        spawner.spawn(async move {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            long_run.fetch_add(1, Ordering::SeqCst);
+        });
+
+        // Runs don't take the single permit, so they go at once and passes still fire
+        tasks.spawn_periodic("pass", Duration::from_secs(5), counting_pass(&passes));
+        tokio::time::sleep(Duration::from_secs(15)).await;
+        assert_eq!(done.load(Ordering::SeqCst), 3);
+        assert_eq!(passes.load(Ordering::SeqCst), 3);
+
+        // Shutdown doesn't wait out the long run
+        tokio::time::timeout(Duration::from_secs(1), tasks.shutdown()).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(3600)).await;
+        assert_eq!(done.load(Ordering::SeqCst), 3);
+
+        let late = done.clone();
+        spawner.spawn(async move {
+            late.fetch_add(1, Ordering::SeqCst);
+        });
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(done.load(Ordering::SeqCst), 3);
+    }
+}
@@ -1,29 +1,109 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use shiioo_core::analytics::PerformanceAnalytics;
-use shiioo_core::approval::ApprovalManager;
-use shiioo_core::audit::AuditLog;
-use shiioo_core::cluster::ClusterManager;
-use shiioo_core::compliance::{ComplianceChecker, SecurityScanner};
+use shiioo_core::analytics::{PerformanceAnalytics, TraceRetention};
+use shiioo_core::api_key::{ApiKeyStore, NewApiKey, RequestVerifier};
+use shiioo_core::approval::{ApprovalManager, ReminderPolicy};
+use shiioo_core::audit::{AuditLog, DEFAULT_CHECKPOINT_INTERVAL};
+use shiioo_core::capacity::CapacityBroker;
+use shiioo_core::claude_compiler::ClaudeConfigCache;
+use shiioo_core::cluster::{
+    ClusterManager, ClusterNode, DistributedLock, LeaderElection, NodeRole, NodeStatus,
+};
+use shiioo_core::codec::Codec;
+use shiioo_core::compliance::{ComplianceChecker, ComplianceReportJobs, SecurityScanner};
 use shiioo_core::config_change::ConfigChangeManager;
-use shiioo_core::metrics::MetricsCollector;
+use shiioo_core::metrics::{
+    MetricsCollector, DEFAULT_HISTORY_BUCKETS, DEFAULT_HISTORY_RESOLUTION_SECS,
+    MAX_HISTORY_BUCKETS, MAX_HISTORY_RESOLUTION_SECS,
+};
+use shiioo_core::policy::{ChangeRiskPolicy, InMemoryPolicyEngine};
+use shiioo_core::redaction::{RedactionConfig, Redactor};
 use shiioo_core::rbac::RbacManager;
 use shiioo_core::scheduler::RoutineScheduler;
-use shiioo_core::storage::{FilesystemBlobStore, JsonlEventLog, RedbIndexStore, TenantStorage};
+use shiioo_core::storage::{
+    BlobStore, FilesystemBlobStore, IndexBackend, JsonlEventLog, RedbIndexStore, RetentionPolicy,
+    TenantStorage,
+};
 use shiioo_core::cluster::NodeId;
 use shiioo_core::secrets::SecretManager;
 use shiioo_core::tenant::TenantManager;
-use shiioo_core::workflow::WorkflowExecutor;
-use std::path::PathBuf;
+use shiioo_core::validation::ValidationErrors;
+use shiioo_core::workflow::{DryAgentResponses, ScriptConfig, WorkflowExecutor, WorkflowLimits};
+use shiioo_mcp::tools::{
+    ContextEventsTool, ContextGetTool, ContextSearchTool, RepoReadTool, ToolRegistry, WebFetchTool,
+};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::websocket::UpdateHub;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ServerConfig {
     #[serde(skip)]
     pub data_dir: PathBuf,
 
     #[serde(default)]
     pub storage: StorageConfig,
+
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+
+    #[serde(default)]
+    pub auth: AuthConfig,
+
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+
+    #[serde(default)]
+    pub load_shedding: LoadSheddingConfig,
+
+    #[serde(default)]
+    pub background: BackgroundConfig,
+
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    #[serde(default)]
+    pub audit: AuditConfig,
+
+    #[serde(default)]
+    pub approval_reminders: ReminderPolicy,
+
+    #[serde(default)]
+    pub workflow_limits: WorkflowLimits,
+
+    /// How config changes are classified by risk, and who approves them
+    #[serde(default)]
+    pub change_risk: ChangeRiskPolicy,
+
+    /// Answer agent tasks with canned responses instead of calling an LLM, for demos and
+    /// integration tests
+    #[serde(default)]
+    pub dry_agent: Option<DryAgentResponses>,
+
+    /// Which commands `Script` steps may run; off by default. Scripts run in
+    /// `<data_dir>/scripts` unless a working directory is configured.
+    #[serde(default)]
+    pub scripts: ScriptConfig,
+
+    #[serde(default)]
+    pub tools: ToolsConfig,
+
+    /// How many finished execution traces analytics keeps in memory
+    #[serde(default)]
+    pub trace_retention: TraceRetention,
+}
+
+/// Opt-in tools for `ToolSequence` steps, beyond the read-only context tools
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolsConfig {
+    /// Directory `repo_read` serves files from; the tool isn't offered without one
+    #[serde(default)]
+    pub repo_root: Option<PathBuf>,
+
+    /// Offer `web_fetch`
+    #[serde(default)]
+    pub web_fetch: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +116,15 @@ pub struct StorageConfig {
 
     #[serde(default = "default_index_file")]
     pub index_file: String,
+
+    /// Encoding for newly written index values. Existing values stay readable in
+    /// either format, so switching is safe on a populated index.
+    #[serde(default)]
+    pub codec: Codec,
+
+    /// Segment rotation and expiry of the event log, compacted by a background task
+    #[serde(default)]
+    pub event_retention: RetentionPolicy,
 }
 
 fn default_blob_dir() -> String {
@@ -56,6 +145,274 @@ impl Default for StorageConfig {
             blob_dir: default_blob_dir(),
             event_log_dir: default_event_log_dir(),
             index_file: default_index_file(),
+            codec: Codec::default(),
+            event_retention: RetentionPolicy::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketConfig {
+    /// Close the socket if no client message or pong arrives within this window
+    #[serde(default = "default_ws_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+
+    /// Absolute maximum lifetime of a session, regardless of activity
+    #[serde(default = "default_ws_max_lifetime_secs")]
+    pub max_lifetime_secs: u64,
+
+    /// How often the server pings the client, so a client that only listens still
+    /// answers with pongs inside the idle window
+    #[serde(default = "default_ws_ping_interval_secs")]
+    pub ping_interval_secs: u64,
+}
+
+fn default_ws_idle_timeout_secs() -> u64 {
+    60
+}
+
+fn default_ws_ping_interval_secs() -> u64 {
+    20
+}
+
+fn default_ws_max_lifetime_secs() -> u64 {
+    3600
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout_secs: default_ws_idle_timeout_secs(),
+            max_lifetime_secs: default_ws_max_lifetime_secs(),
+            ping_interval_secs: default_ws_ping_interval_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Require a valid API key on all `/api/*` routes except `/api/health`
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Key registered at startup for the `admin` user, used to issue further keys
+    #[serde(default)]
+    pub bootstrap_key: Option<String>,
+
+    /// Require mutating requests to carry a timestamped, single-use HMAC signature
+    #[serde(default)]
+    pub require_signed_requests: bool,
+
+    /// How far a signed request's timestamp may drift from the server's clock
+    #[serde(default = "default_signature_max_age_secs")]
+    pub signature_max_age_secs: u64,
+}
+
+fn default_signature_max_age_secs() -> u64 {
+    300
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bootstrap_key: None,
+            require_signed_requests: false,
+            signature_max_age_secs: default_signature_max_age_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadSheddingConfig {
+    /// Reject low-priority requests with 503 once the server is busy
+    #[serde(default = "default_load_shedding_enabled")]
+    pub enabled: bool,
+
+    /// In-flight request count at which low-priority requests start being shed
+    #[serde(default = "default_low_priority_max_in_flight")]
+    pub low_priority_max_in_flight: usize,
+
+    /// Value of the `Retry-After` header on shed requests
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+}
+
+fn default_load_shedding_enabled() -> bool {
+    true
+}
+
+fn default_low_priority_max_in_flight() -> usize {
+    256
+}
+
+fn default_retry_after_secs() -> u64 {
+    5
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_load_shedding_enabled(),
+            low_priority_max_in_flight: default_low_priority_max_in_flight(),
+            retry_after_secs: default_retry_after_secs(),
+        }
+    }
+}
+
+/// Intervals of the periodic background tasks. Routines aren't listed: each runs on
+/// its own cron schedule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundConfig {
+    /// How often routines are checked for a due scheduled run
+    #[serde(default = "default_routine_interval_secs")]
+    pub routine_interval_secs: u64,
+
+    /// How often queued capacity requests are retried
+    #[serde(default = "default_capacity_queue_interval_secs")]
+    pub capacity_queue_interval_secs: u64,
+
+    /// How often secrets are checked for due rotations
+    #[serde(default = "default_secret_rotation_interval_secs")]
+    pub secret_rotation_interval_secs: u64,
+
+    /// How often child runs whose parent has stopped are cancelled
+    #[serde(default = "default_orphan_reaper_interval_secs")]
+    pub orphan_reaper_interval_secs: u64,
+
+    /// How often cluster nodes that missed their heartbeat are marked unhealthy
+    #[serde(default = "default_stale_node_interval_secs")]
+    pub stale_node_interval_secs: u64,
+
+    /// How often expired event log segments are deleted or archived
+    #[serde(default = "default_event_compaction_interval_secs")]
+    pub event_compaction_interval_secs: u64,
+
+    /// How often pending approvals are checked for due reminders
+    #[serde(default = "default_approval_reminder_interval_secs")]
+    pub approval_reminder_interval_secs: u64,
+
+    /// Most background passes allowed to run at the same time; the rest wait their turn
+    #[serde(default = "default_max_concurrent_tasks")]
+    pub max_concurrent_tasks: usize,
+}
+
+fn default_routine_interval_secs() -> u64 {
+    1
+}
+
+fn default_capacity_queue_interval_secs() -> u64 {
+    5
+}
+
+fn default_secret_rotation_interval_secs() -> u64 {
+    3600
+}
+
+fn default_orphan_reaper_interval_secs() -> u64 {
+    60
+}
+
+fn default_stale_node_interval_secs() -> u64 {
+    30
+}
+
+fn default_event_compaction_interval_secs() -> u64 {
+    3600
+}
+
+fn default_approval_reminder_interval_secs() -> u64 {
+    300
+}
+
+fn default_max_concurrent_tasks() -> usize {
+    2
+}
+
+impl Default for BackgroundConfig {
+    fn default() -> Self {
+        Self {
+            routine_interval_secs: default_routine_interval_secs(),
+            capacity_queue_interval_secs: default_capacity_queue_interval_secs(),
+            secret_rotation_interval_secs: default_secret_rotation_interval_secs(),
+            orphan_reaper_interval_secs: default_orphan_reaper_interval_secs(),
+            stale_node_interval_secs: default_stale_node_interval_secs(),
+            event_compaction_interval_secs: default_event_compaction_interval_secs(),
+            approval_reminder_interval_secs: default_approval_reminder_interval_secs(),
+            max_concurrent_tasks: default_max_concurrent_tasks(),
+        }
+    }
+}
+
+/// Signing of audit checkpoints and compliance reports
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// HMAC key for audit checkpoints and compliance report signatures. Without one, a
+    /// random key is generated at startup, so signatures can't be verified after a
+    /// restart.
+    #[serde(default)]
+    pub signing_key: Option<String>,
+}
+
+impl AuditConfig {
+    /// The configured signing key, or a fresh random one
+    fn signing_key(&self) -> Vec<u8> {
+        match &self.signing_key {
+            Some(key) => key.as_bytes().to_vec(),
+            None => {
+                tracing::warn!("No audit signing key configured; using a random key");
+                let (a, b) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+                [a.as_bytes().as_slice(), b.as_bytes().as_slice()].concat()
+            }
+        }
+    }
+}
+
+/// In-memory history kept for each counter and gauge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Width of each history bucket
+    #[serde(default = "default_history_resolution_secs")]
+    pub history_resolution_secs: u64,
+
+    /// Buckets kept per series; older ones are dropped. Zero disables history.
+    #[serde(default = "default_history_buckets")]
+    pub history_buckets: usize,
+}
+
+fn default_history_resolution_secs() -> u64 {
+    DEFAULT_HISTORY_RESOLUTION_SECS as u64
+}
+
+fn default_history_buckets() -> usize {
+    DEFAULT_HISTORY_BUCKETS
+}
+
+impl MetricsConfig {
+    /// Check the history fits in memory and its time span can be represented
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = ValidationErrors::new();
+        if !(1..=MAX_HISTORY_RESOLUTION_SECS as u64).contains(&self.history_resolution_secs) {
+            errors.push(
+                "metrics.history_resolution_secs",
+                format!("Must be between 1 and {}", MAX_HISTORY_RESOLUTION_SECS),
+            );
+        }
+        if self.history_buckets > MAX_HISTORY_BUCKETS {
+            errors.push(
+                "metrics.history_buckets",
+                format!("Must be at most {}", MAX_HISTORY_BUCKETS),
+            );
+        }
+        errors.into_result()
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            history_resolution_secs: default_history_resolution_secs(),
+            history_buckets: default_history_buckets(),
         }
     }
 }
@@ -72,13 +429,15 @@ impl ServerConfig {
             toml::from_str(&content).context("Failed to parse configuration file")?
         } else {
             tracing::info!("Configuration file not found, using defaults");
-            Self {
-                data_dir: data_dir.clone(),
-                storage: Default::default(),
-            }
+            Self::default()
         };
 
         config.data_dir = data_dir;
+        config.metrics.validate().context("Invalid metrics configuration")?;
+        config
+            .approval_reminders
+            .validate()
+            .context("Invalid approval reminder configuration")?;
 
         Ok(config)
     }
@@ -99,57 +458,184 @@ impl ServerConfig {
     }
 }
 
+/// Default config over a fresh temporary data directory, for tests to adjust
+#[cfg(test)]
+pub(crate) fn test_config(name: &str) -> ServerConfig {
+    ServerConfig {
+        data_dir: std::env::temp_dir().join(format!("shiioo-{}-{}", name, uuid::Uuid::new_v4())),
+        ..Default::default()
+    }
+}
+
 /// Application state shared across handlers
 #[derive(Clone)]
 pub struct AppState {
-    pub blob_store: Arc<FilesystemBlobStore>,
+    pub blob_store: Arc<dyn BlobStore>,
     pub event_log: Arc<JsonlEventLog>,
-    pub index_store: Arc<RedbIndexStore>,
+    pub index_store: Arc<dyn IndexBackend>,
+    pub capacity_broker: Arc<CapacityBroker>,
     pub workflow_executor: Arc<WorkflowExecutor>,
+    /// Tools available to `ToolSequence` steps
+    pub tool_registry: Arc<ToolRegistry>,
     pub routine_scheduler: Arc<RoutineScheduler>,
     pub approval_manager: Arc<ApprovalManager>,
     pub config_change_manager: Arc<ConfigChangeManager>,
+    pub claude_config_cache: Arc<ClaudeConfigCache>,
+    /// Tracks each role's daily budget usage
+    pub policy_engine: Arc<InMemoryPolicyEngine>,
     pub metrics: Arc<MetricsCollector>,
     pub analytics: Arc<PerformanceAnalytics>,
     pub tenant_manager: Arc<TenantManager>,
     pub tenant_storage: Arc<TenantStorage>,
     pub cluster_manager: Arc<ClusterManager>,
+    pub leader_election: Arc<LeaderElection>,
     pub secret_manager: Arc<SecretManager>,
     pub audit_log: Arc<AuditLog>,
     pub rbac_manager: Arc<RbacManager>,
     pub compliance_checker: Arc<ComplianceChecker>,
+    pub compliance_reports: Arc<ComplianceReportJobs>,
     pub security_scanner: Arc<SecurityScanner>,
+    pub websocket_config: WebSocketConfig,
+    /// Numbered subscription updates shared by every WebSocket session
+    pub ws_updates: Arc<UpdateHub>,
+    pub api_keys: Arc<ApiKeyStore>,
+    pub request_verifier: Arc<RequestVerifier>,
+    pub auth_config: AuthConfig,
+    pub load_shedding_config: LoadSheddingConfig,
+    pub workflow_limits: WorkflowLimits,
 }
 
 impl AppState {
     pub fn new(config: &ServerConfig) -> Result<Self> {
-        let blob_store = Arc::new(
+        Self::with_leader_lock(config, Arc::new(DistributedLock::new(30)))
+    }
+
+    /// Build the state for a node that elects its leader through `leader_lock`.
+    /// Nodes of one cluster must share the lock, which also issues leader terms.
+    pub fn with_leader_lock(
+        config: &ServerConfig,
+        leader_lock: Arc<DistributedLock>,
+    ) -> Result<Self> {
+        // Fail fast on an unusable data directory rather than on the first request
+        check_writable(&config.data_dir).context("Data directory is not usable")?;
+
+        let blob_store: Arc<dyn BlobStore> = Arc::new(
             FilesystemBlobStore::new(config.blob_path())
                 .context("Failed to create blob store")?,
         );
 
+        // Shared by the event log, audit log, and secret manager so stored secret
+        // values are scrubbed from everything persisted
+        let redactor = Redactor::new(config.redaction.clone());
+
         let event_log = Arc::new(
-            JsonlEventLog::new(config.event_log_path()).context("Failed to create event log")?,
+            JsonlEventLog::new(config.event_log_path())
+                .context("Failed to create event log")?
+                .with_redactor(redactor.clone())
+                .with_retention(config.storage.event_retention.clone())
+                .with_archive(blob_store.clone()),
         );
+        check_writable(&config.event_log_path()).context("Event log is not usable")?;
+
+        let index_store = RedbIndexStore::new(config.index_path())
+            .with_context(|| format!("Failed to open index {}", config.index_path().display()))?
+            .with_codec(config.storage.codec);
+        index_store.probe().context("Index store failed its read/write check")?;
+        let index_store: Arc<dyn IndexBackend> = Arc::new(index_store);
 
-        let index_store = Arc::new(
-            RedbIndexStore::new(config.index_path()).context("Failed to create index store")?,
+        // TODO: Load encryption key from environment or config file
+        let encryption_key = b"shiioo-default-secret-key-change-me-in-production!";
+
+        // Tamper-proof audit log; the capacity broker records disabled sources in it.
+        // Its key is kept apart from the encryption key so neither can stand in for the other.
+        let audit_key = config.audit.signing_key();
+        let audit_log = Arc::new(
+            AuditLog::new()
+                .with_redactor(redactor.clone())
+                .with_checkpoints(DEFAULT_CHECKPOINT_INTERVAL, &audit_key),
         );
 
-        let workflow_executor = Arc::new(WorkflowExecutor::new(
-            event_log.clone(),
-            blob_store.clone(),
-            index_store.clone(),
-        ));
+        let capacity_broker = Arc::new(
+            CapacityBroker::new()
+                .with_audit_log((*audit_log).clone())
+                .with_index_store(index_store.clone()),
+        );
+        for source in index_store
+            .list_capacity_sources()
+            .context("Failed to load capacity sources")?
+        {
+            capacity_broker
+                .register_source(source)
+                .context("Failed to register capacity source")?;
+        }
+
+        // Tool sequence steps can use the read-only context tools, and the repository and
+        // web tools only when configured
+        let mut tools = ToolRegistry::new();
+        tools.register(Arc::new(ContextGetTool::new(index_store.clone())));
+        tools.register(Arc::new(ContextSearchTool::new(index_store.clone())));
+        tools.register(Arc::new(ContextEventsTool::new(event_log.clone())));
+        if let Some(repo_root) = &config.tools.repo_root {
+            tools.register(Arc::new(RepoReadTool::new(repo_root.clone())));
+        }
+        if config.tools.web_fetch {
+            tools.register(Arc::new(WebFetchTool::new()));
+        }
+        let tool_registry = Arc::new(tools);
+
+        // Shared with the executor so tool calls are checked against the stored roles and
+        // policies; handlers reload it whenever those change
+        let policy_engine = Arc::new(
+            InMemoryPolicyEngine::new()
+                .with_change_risk_policy(config.change_risk.clone())
+                .with_specs(
+                    index_store.list_roles().context("Failed to load roles")?,
+                    index_store.list_policies().context("Failed to load policies")?,
+                ),
+        );
+
+        let mut workflow_executor =
+            WorkflowExecutor::new(event_log.clone(), blob_store.clone(), index_store.clone())
+                .with_tools(tool_registry.clone())
+                .with_policy_engine(policy_engine.clone())
+                .with_capacity_broker(capacity_broker.clone())
+                .with_redactor(redactor.clone())
+                .with_workflow_limits(config.workflow_limits.clone());
+        if let Some(responses) = &config.dry_agent {
+            tracing::warn!("Dry agent mode is on: agent tasks get canned responses");
+            workflow_executor = workflow_executor.with_dry_agent(responses.clone());
+        }
+        if config.scripts.enabled {
+            config.scripts.validate().context("Invalid script configuration")?;
+            let mut scripts = config.scripts.clone();
+            let working_dir =
+                scripts.working_dir.get_or_insert_with(|| config.data_dir.join("scripts"));
+            std::fs::create_dir_all(working_dir.as_path()).with_context(|| {
+                format!("Failed to create script directory {}", working_dir.display())
+            })?;
+            tracing::warn!("Script steps are enabled for {:?}", scripts.allowed_commands);
+            workflow_executor = workflow_executor.with_scripts(scripts);
+        }
+        let workflow_executor = Arc::new(workflow_executor);
 
         // Phase 5: Routine scheduler, approval boards, and config changes
-        let approval_manager = Arc::new(ApprovalManager::new());
-        let config_change_manager = Arc::new(ConfigChangeManager::new(approval_manager.clone()));
+        let approval_manager = Arc::new(
+            ApprovalManager::new().with_reminder_policy(config.approval_reminders.clone()),
+        );
+        approval_manager.add_reminder_handler(Arc::new((*audit_log).clone()));
+        let config_change_manager = Arc::new(
+            ConfigChangeManager::new(approval_manager.clone())
+                .with_change_risk_policy(config.change_risk.clone()),
+        );
         let routine_scheduler = Arc::new(RoutineScheduler::new(workflow_executor.clone()));
 
         // Phase 6: Observability - metrics and analytics
-        let metrics = Arc::new(MetricsCollector::new());
-        let analytics = Arc::new(PerformanceAnalytics::new());
+        let metrics = Arc::new(MetricsCollector::new().with_history(
+            chrono::Duration::seconds(config.metrics.history_resolution_secs as i64),
+            config.metrics.history_buckets,
+        ));
+        let analytics =
+            Arc::new(PerformanceAnalytics::new().with_retention(config.trace_retention));
 
         // Phase 7: Multi-tenancy and high availability
         let tenant_manager = Arc::new(TenantManager::new());
@@ -160,15 +646,36 @@ impl AppState {
 
         // Generate a unique node ID for this server instance
         let local_node_id = NodeId::generate();
-        let cluster_manager = Arc::new(ClusterManager::new(local_node_id, 30)); // 30 sec heartbeat timeout
+        let cluster_manager = Arc::new(ClusterManager::new(local_node_id.clone(), 30)); // 30 sec heartbeat timeout
+
+        // Until another node holds the leader lock, this node leads the cluster, so
+        // leader-only writes such as applying config changes work
+        cluster_manager.register_node(ClusterNode {
+            id: local_node_id.clone(),
+            address: "local".to_string(),
+            region: None,
+            status: NodeStatus::Healthy,
+            role: NodeRole::Follower,
+            last_heartbeat: chrono::Utc::now(),
+            started_at: chrono::Utc::now(),
+            metadata: Default::default(),
+        })?;
+        // The stale node sweep renews the lease, so it outlasts a couple of missed sweeps
+        let lease_secs = config.background.stale_node_interval_secs.saturating_mul(3);
+        let leader_election = Arc::new(LeaderElection::new(
+            cluster_manager.clone(),
+            leader_lock,
+            i64::try_from(lease_secs).unwrap_or(i64::MAX),
+        ));
+        if !leader_election.try_become_leader(&local_node_id)? {
+            leader_election.sync_term();
+        }
 
         // Phase 8: Secret management
-        // TODO: Load encryption key from environment or config file
-        let encryption_key = b"shiioo-default-secret-key-change-me-in-production!";
-        let secret_manager = Arc::new(SecretManager::new(encryption_key));
+        let secret_manager =
+            Arc::new(SecretManager::new(encryption_key).with_redactor(redactor));
 
         // Phase 9: Security and compliance
-        let audit_log = Arc::new(AuditLog::new());
         let rbac_manager = Arc::new(RbacManager::new());
 
         // Initialize system roles
@@ -177,30 +684,84 @@ impl AppState {
                 .context("Failed to register system role")?;
         }
 
-        let compliance_checker = Arc::new(ComplianceChecker::new(
-            (*audit_log).clone(),
-            (*rbac_manager).clone(),
-        ));
+        let compliance_checker = Arc::new(
+            ComplianceChecker::new((*audit_log).clone(), (*rbac_manager).clone())
+                .with_signing_key(&audit_key),
+        );
         let security_scanner = Arc::new(SecurityScanner::new((*audit_log).clone()));
 
+        let api_keys = Arc::new(ApiKeyStore::new());
+        if let Some(bootstrap_key) = &config.auth.bootstrap_key {
+            // The bootstrap key issues everyone else's, so its user is an admin
+            rbac_manager
+                .register_user(shiioo_core::rbac::RbacUser::new(
+                    "admin".to_string(),
+                    "admin".to_string(),
+                    "admin@localhost".to_string(),
+                ))
+                .context("Failed to register bootstrap admin")?;
+            rbac_manager
+                .assign_role("admin", "admin")
+                .context("Failed to register bootstrap admin")?;
+            api_keys
+                .register(
+                    bootstrap_key,
+                    NewApiKey {
+                        name: "bootstrap".to_string(),
+                        user_id: "admin".to_string(),
+                        tenant_id: None,
+                        allowed_tenants: Vec::new(),
+                        allowed_actions: Vec::new(),
+                        expires_at: None,
+                    },
+                )
+                .context("Failed to register bootstrap API key")?;
+        }
+
         Ok(Self {
             blob_store,
             event_log,
             index_store,
+            capacity_broker,
             workflow_executor,
+            tool_registry,
             routine_scheduler,
             approval_manager,
             config_change_manager,
+            claude_config_cache: Arc::new(ClaudeConfigCache::new()),
+            policy_engine,
             metrics,
             analytics,
             tenant_manager,
             tenant_storage,
             cluster_manager,
+            leader_election,
             secret_manager,
             audit_log,
             rbac_manager,
             compliance_checker,
+            compliance_reports: Arc::new(ComplianceReportJobs::new()),
             security_scanner,
+            websocket_config: config.websocket.clone(),
+            ws_updates: Arc::new(UpdateHub::default()),
+            api_keys,
+            request_verifier: Arc::new(RequestVerifier::new(config.auth.signature_max_age_secs)),
+            auth_config: config.auth.clone(),
+            load_shedding_config: config.load_shedding.clone(),
+            workflow_limits: config.workflow_limits.clone(),
         })
     }
 }
+
+/// Create `dir` if needed and confirm files can be written in it
+fn check_writable(dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Cannot create directory {}", dir.display()))?;
+
+    let probe = dir.join(".shiioo-probe");
+    std::fs::write(&probe, b"probe")
+        .with_context(|| format!("Cannot write to directory {}", dir.display()))?;
+    std::fs::remove_file(&probe)
+        .with_context(|| format!("Cannot remove files from directory {}", dir.display()))?;
+    Ok(())
+}
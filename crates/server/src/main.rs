@@ -3,6 +3,7 @@ use clap::Parser;
 use std::path::PathBuf;
 
 mod api;
+mod background;
 mod config;
 mod graphql;
 mod middleware;
@@ -1,13 +1,34 @@
+use crate::config::AppState;
 use axum::{
     extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    http::{HeaderMap, Method, StatusCode},
     middleware::Next,
     response::Response,
 };
 use serde::{Deserialize, Serialize};
-use shiioo_core::rbac::{Action, Permission, RbacManager, Resource};
+use shiioo_core::api_key::{NONCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use shiioo_core::audit::{AuditAction, AuditCategory, AuditSeverity};
+use shiioo_core::rbac::{Action, Permission, RbacManager, Resource, ResourceTarget};
+use shiioo_core::tenant::TenantId;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Header a platform admin sets to scope a request to another tenant
+pub const IMPERSONATE_TENANT_HEADER: &str = "X-Impersonate-Tenant";
+
+/// The tenant an authenticated request acts for, added to the request extensions by
+/// `require_api_key`. `None` for unscoped keys that name no tenant, which see every tenant's
+/// resources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestTenant(pub Option<TenantId>);
+
+impl RequestTenant {
+    /// Whether a resource owned by `owner` is visible to the request
+    pub fn sees(&self, owner: Option<&TenantId>) -> bool {
+        self.0.is_none() || self.0.as_ref() == owner
+    }
+}
+
 /// Authentication token claims
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthClaims {
@@ -67,6 +88,367 @@ pub async fn require_permission(
     }
 }
 
+/// Extract the raw API key from a `Bearer` authorization header
+pub fn extract_api_key(headers: &HeaderMap) -> Option<String> {
+    let auth_str = headers.get("Authorization")?.to_str().ok()?;
+    let key = auth_str.strip_prefix("Bearer ")?.trim();
+
+    if key.is_empty() {
+        None
+    } else {
+        Some(key.to_string())
+    }
+}
+
+/// Whether a request path must carry an API key
+fn requires_api_key(path: &str) -> bool {
+    path.starts_with("/api/") && path != "/api/health"
+}
+
+/// Largest body buffered to check a request signature
+const MAX_SIGNED_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+fn is_mutating(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Check the signature headers of a mutating request against its API key
+fn verify_signature(
+    state: &AppState,
+    raw_key: &str,
+    parts: &axum::http::request::Parts,
+    body: &[u8],
+) -> anyhow::Result<()> {
+    let header = |name: &str| {
+        parts
+            .headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| anyhow::anyhow!("Missing {} header", name))
+    };
+
+    let timestamp = header(TIMESTAMP_HEADER)?
+        .parse::<i64>()
+        .map_err(|_| anyhow::anyhow!("Invalid {} header", TIMESTAMP_HEADER))?;
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or_else(|| parts.uri.path());
+
+    state.request_verifier.verify(
+        raw_key,
+        parts.method.as_str(),
+        path,
+        body,
+        timestamp,
+        header(NONCE_HEADER)?,
+        header(SIGNATURE_HEADER)?,
+    )
+}
+
+/// Map an API route to the RBAC resource and action it operates on
+pub fn route_permission(method: &Method, path: &str) -> (Resource, Action) {
+    let mut segments = path.trim_start_matches("/api/").split('/');
+
+    let resource = match segments.next().unwrap_or_default() {
+        "runs" | "jobs" | "analytics" | "claude" => Resource::Workflow,
+        "roles" | "rbac" => Resource::Role,
+        "policies" => Resource::Policy,
+        "organizations" => Resource::Organization,
+        "templates" => Resource::Template,
+        "routines" => Resource::Routine,
+        "approvals" | "approval-boards" | "config-changes" => Resource::Approval,
+        "tenants" => Resource::Tenant,
+        "cluster" => Resource::Cluster,
+        "secrets" | "auth" => Resource::Secret,
+        "audit" | "compliance" | "security" => Resource::AuditLog,
+        "capacity" => Resource::Capacity,
+        "mcp" | "ws" => Resource::Workflow,
+        "metrics" | "health" | "describe" => Resource::Cluster,
+        // GraphQL reaches every resource, as does anything not mapped above, so both
+        // need a grant on all resources
+        _ => Resource::All,
+    };
+
+    let action = match *method {
+        Method::GET | Method::HEAD => Action::Read,
+        Method::PUT | Method::PATCH => Action::Update,
+        Method::DELETE => Action::Delete,
+        _ => match path.rsplit('/').next().unwrap_or_default() {
+            "lint" | "explain" | "simulate" => Action::Read,
+            "vote" | "bulk-vote" | "apply" | "reject" => Action::Approve,
+            "force-resolve" => Action::Override,
+            "jobs" | "instantiate" | "requeue" | "trigger" | "signal" | "retry" | "replay" => {
+                Action::Execute
+            }
+            "suspend" | "activate" | "enable" | "disable" | "rotate" | "heartbeat" | "drain"
+            | "undrain" => Action::Update,
+            _ => Action::Create,
+        },
+    };
+
+    (resource, action)
+}
+
+/// Whether the handler behind a route checks the instance it acts on against grants limited
+/// to some instances (see `ResourceSelector`). Every other route needs a grant on all
+/// instances of its resource.
+pub fn route_checks_instance(path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_start_matches("/api/").split('/').collect();
+    matches!(
+        segments.as_slice(),
+        ["runs", ..]
+            | ["analytics", "traces" | "critical-path", _]
+            | ["approvals", _, "vote" | "force-resolve"]
+            | ["approvals", "bulk-vote"]
+    )
+}
+
+/// The tenant a request targets, from the `/api/tenants/{id}` path or the `X-Tenant-ID` header
+pub fn target_tenant(path: &str, headers: &HeaderMap) -> Option<TenantId> {
+    if let Some(tenant_id) = path
+        .strip_prefix("/api/tenants/")
+        .and_then(|rest| rest.split('/').next())
+        .filter(|id| !id.is_empty())
+    {
+        return Some(TenantId::new(tenant_id));
+    }
+
+    headers
+        .get("X-Tenant-ID")
+        .and_then(|v| v.to_str().ok())
+        .map(TenantId::new)
+}
+
+/// The tenant a platform operator asks to act as, from the `X-Impersonate-Tenant` header
+pub fn impersonated_tenant(headers: &HeaderMap) -> Option<TenantId> {
+    headers
+        .get(IMPERSONATE_TENANT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(TenantId::new)
+}
+
+fn client_ip(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("X-Forwarded-For")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(|ip| ip.trim().to_string())
+}
+
+/// API key authentication middleware.
+///
+/// When authentication is enabled, requests to `/api/*` (other than `/api/health`) must carry
+/// a valid, unexpired key (401 otherwise) whose tenant and action scopes cover the request
+/// (403 otherwise). A request that names no tenant acts on the key's own, and a key scoped to
+/// several tenants must name one, and an `X-Tenant-ID` header that disagrees with the tenant
+/// in the path is rejected. Scoped keys can't issue new keys. The key's user must also hold
+/// the RBAC permission the route maps to (see `route_permission`): on at least some
+/// instances for routes whose handlers check the instance (see `route_checks_instance`),
+/// and on all of them otherwise. `/api/auth/*` routes are exempt since their handlers limit
+/// users to their own keys. The
+/// resolved `ApiKey` and the effective `RequestTenant` are added to the request extensions.
+///
+/// A key whose user holds the `Tenant:Impersonate` RBAC permission may send
+/// `X-Impersonate-Tenant` to scope the request to that tenant without being scoped for it
+/// itself. Each impersonated request is audited; sending the header without the
+/// permission is rejected with 403.
+///
+/// With `require_signed_requests`, mutating requests must also carry a signature made
+/// with the raw key over the method, path, body, timestamp and nonce. Stale timestamps,
+/// bad signatures and replayed nonces are rejected with 401.
+pub async fn require_api_key(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if !state.auth_config.enabled || !requires_api_key(req.uri().path()) {
+        return Ok(next.run(req).await);
+    }
+
+    let ip_address = client_ip(req.headers());
+
+    let raw_key = extract_api_key(req.headers());
+    let mut result = match &raw_key {
+        Some(raw_key) => state.api_keys.validate_session(raw_key),
+        None => Err(anyhow::anyhow!("Missing API key")),
+    };
+
+    if let (Ok(_), Some(raw_key)) = (&result, &raw_key) {
+        if state.auth_config.require_signed_requests && is_mutating(req.method()) {
+            let (parts, body) = req.into_parts();
+            let body = axum::body::to_bytes(body, MAX_SIGNED_BODY_BYTES)
+                .await
+                .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+
+            if let Err(e) = verify_signature(&state, raw_key, &parts, &body) {
+                result = Err(e);
+            }
+            req = Request::from_parts(parts, axum::body::Body::from(body));
+        }
+    }
+
+    match result {
+        Ok((key, new_session)) => {
+            // Audited once per session rather than on every request
+            if new_session {
+                state.audit_log.log(
+                    AuditCategory::Authentication,
+                    AuditSeverity::Info,
+                    AuditAction::UserLogin {
+                        user_id: key.user_id.clone(),
+                        ip_address: ip_address.clone().unwrap_or_else(|| "unknown".to_string()),
+                    },
+                    Some(key.user_id.clone()),
+                    key.tenant_id.as_ref().map(|t| t.0.clone()),
+                    ip_address.clone(),
+                );
+            }
+
+            // Impersonation may be granted for particular tenants only
+            let impersonated = impersonated_tenant(req.headers());
+            let may_impersonate = impersonated.as_ref().is_none_or(|tenant| {
+                state.rbac_manager.check_permission_on(
+                    &key.user_id,
+                    &Permission::new(Resource::Tenant, Action::Impersonate),
+                    &ResourceTarget::new(tenant.0.clone()),
+                )
+            });
+
+            if let Some(tenant) = impersonated.as_ref().filter(|_| may_impersonate) {
+                let header = axum::http::HeaderValue::from_str(&tenant.0)
+                    .map_err(|_| StatusCode::BAD_REQUEST)?;
+                req.headers_mut().insert("X-Tenant-ID", header);
+            }
+
+            let (resource, action) = route_permission(req.method(), req.uri().path());
+
+            // A request that names no tenant acts on the key's own
+            let mut tenant = target_tenant(req.uri().path(), req.headers());
+            if tenant.is_none() {
+                tenant = key.default_tenant();
+                if let Some(default) = &tenant {
+                    let header = axum::http::HeaderValue::from_str(&default.0)
+                        .map_err(|_| StatusCode::BAD_REQUEST)?;
+                    req.headers_mut().insert("X-Tenant-ID", header);
+                }
+            }
+
+            let header_tenant = req
+                .headers()
+                .get("X-Tenant-ID")
+                .and_then(|v| v.to_str().ok())
+                .map(TenantId::new);
+            let conflicting_tenant = header_tenant.filter(|h| tenant.as_ref() != Some(h));
+
+            let permission = Permission::new(resource.clone(), action.clone());
+            let granted = if route_checks_instance(req.uri().path()) {
+                state.rbac_manager.check_permission_on_some(&key.user_id, &permission)
+            } else {
+                state.rbac_manager.check_permission(&key.user_id, &permission)
+            };
+
+            let mints_key = req.method() == Method::POST && req.uri().path() == "/api/auth/keys";
+            let manages_own_keys = req.uri().path().starts_with("/api/auth/");
+
+            let denied = if !may_impersonate {
+                Some("Tenant:Impersonate".to_string())
+            } else if !key.permits_action(resource.clone(), action.clone()) {
+                Some(format!("{:?}:{:?}", resource, action))
+            } else if mints_key && key.is_scoped() {
+                // A scoped key could otherwise mint itself an unscoped one
+                Some("unscoped key".to_string())
+            } else if let Some(h) = conflicting_tenant {
+                Some(format!("tenant:{}", h.0))
+            } else if !manages_own_keys && !granted {
+                Some(format!("{:?}:{:?}", resource, action))
+            } else {
+                match &tenant {
+                    Some(t) if Some(t) != impersonated.as_ref() && !key.permits_tenant(t) => {
+                        Some(format!("tenant:{}", t.0))
+                    }
+                    None if key.is_tenant_scoped() => Some("tenant".to_string()),
+                    _ => None,
+                }
+            };
+
+            if let Some(permission) = denied {
+                tracing::warn!(
+                    "API key {} is not scoped for {} on {}",
+                    key.id.0,
+                    permission,
+                    req.uri().path()
+                );
+
+                state.audit_log.log(
+                    AuditCategory::Authorization,
+                    AuditSeverity::Warning,
+                    AuditAction::PermissionDenied {
+                        user_id: key.user_id.clone(),
+                        permission,
+                        resource: req.uri().path().to_string(),
+                    },
+                    Some(key.user_id.clone()),
+                    key.tenant_id.as_ref().map(|t| t.0.clone()),
+                    ip_address,
+                );
+
+                return Err(StatusCode::FORBIDDEN);
+            }
+
+            if let Some(tenant) = impersonated {
+                tracing::info!(
+                    "User {} impersonating tenant {} for {}",
+                    key.user_id,
+                    tenant.0,
+                    req.uri().path()
+                );
+
+                state.audit_log.record(
+                    AuditCategory::DataAccess,
+                    AuditSeverity::Warning,
+                    AuditAction::DataAccessed {
+                        resource_type: "tenant".to_string(),
+                        resource_id: tenant.0.clone(),
+                    },
+                    Some(key.user_id.clone()),
+                    Some(tenant.0),
+                    ip_address,
+                    HashMap::from([
+                        ("impersonated_by".to_string(), key.user_id.clone()),
+                        ("method".to_string(), req.method().to_string()),
+                        ("path".to_string(), req.uri().path().to_string()),
+                    ]),
+                );
+            }
+
+            req.extensions_mut().insert(RequestTenant(tenant));
+            req.extensions_mut().insert(key);
+            Ok(next.run(req).await)
+        }
+        Err(e) => {
+            tracing::warn!("Rejected request to {}: {}", req.uri().path(), e);
+
+            state.audit_log.log(
+                AuditCategory::Authentication,
+                AuditSeverity::Warning,
+                AuditAction::LoginFailed {
+                    user_id: "anonymous".to_string(),
+                    reason: e.to_string(),
+                },
+                None,
+                None,
+                ip_address,
+            );
+
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,4 +498,641 @@ mod tests {
             Action::Create
         ));
     }
+
+    fn create_auth_state() -> Arc<AppState> {
+        create_auth_state_with(crate::config::AuthConfig {
+            enabled: true,
+            ..Default::default()
+        })
+    }
+
+    fn create_auth_state_with(auth: crate::config::AuthConfig) -> Arc<AppState> {
+        let config = crate::config::ServerConfig {
+            auth,
+            ..crate::config::test_config("auth")
+        };
+        let state = AppState::new(&config).unwrap();
+
+        // The user behind `new_key` may run workflows and read tenants
+        let mut role = shiioo_core::rbac::RbacRole::new(
+            "operator".to_string(),
+            "Operator".to_string(),
+            "Runs workflows".to_string(),
+        );
+        role.add_permission(Permission::new(Resource::Workflow, Action::All));
+        role.add_permission(Permission::new(Resource::Tenant, Action::Read));
+        state.rbac_manager.register_role(role).unwrap();
+        state
+            .rbac_manager
+            .register_user(shiioo_core::rbac::RbacUser::new(
+                "user1".to_string(),
+                "user1".to_string(),
+                "user1@example.com".to_string(),
+            ))
+            .unwrap();
+        state.rbac_manager.assign_role("user1", "operator").unwrap();
+
+        Arc::new(state)
+    }
+
+    fn create_auth_router(state: Arc<AppState>) -> axum::Router {
+        axum::Router::new()
+            .route("/api/runs", axum::routing::get(|| async { "ok" }))
+            .route("/api/jobs", axum::routing::post(|| async { "ok" }))
+            .route("/api/auth/keys", axum::routing::post(|| async { "ok" }))
+            .route("/api/capacity/sources", axum::routing::post(|| async { "ok" }))
+            .route("/api/graphql", axum::routing::post(|| async { "ok" }))
+            .route("/api/secrets/{secret_id}", axum::routing::get(|| async { "ok" }))
+            .route("/api/analytics/workflows", axum::routing::get(|| async { "ok" }))
+            .route("/api/tenants/{tenant_id}", axum::routing::get(|| async { "ok" }))
+            .route("/api/health", axum::routing::get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(state, require_api_key))
+    }
+
+    fn new_key(expires_at: Option<chrono::DateTime<chrono::Utc>>) -> shiioo_core::api_key::NewApiKey {
+        shiioo_core::api_key::NewApiKey {
+            name: "test".to_string(),
+            user_id: "user1".to_string(),
+            tenant_id: None,
+            allowed_tenants: Vec::new(),
+            allowed_actions: Vec::new(),
+            expires_at,
+        }
+    }
+
+    async fn get_status(router: axum::Router, path: &str, api_key: Option<&str>) -> StatusCode {
+        send(router, Method::GET, path, api_key, None).await
+    }
+
+    async fn send(
+        router: axum::Router,
+        method: Method,
+        path: &str,
+        api_key: Option<&str>,
+        tenant_id: Option<&str>,
+    ) -> StatusCode {
+        use tower::ServiceExt;
+
+        let mut request = axum::http::Request::builder().method(method).uri(path);
+        if let Some(tenant_id) = tenant_id {
+            request = request.header("X-Tenant-ID", tenant_id);
+        }
+        if let Some(key) = api_key {
+            request = request.header("Authorization", format!("Bearer {}", key));
+        }
+
+        router
+            .oneshot(request.body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_valid_api_key_accepted() {
+        let state = create_auth_state();
+        let (_, raw_key) = state.api_keys.create(new_key(None)).unwrap();
+
+        for _ in 0..3 {
+            let status =
+                get_status(create_auth_router(state.clone()), "/api/runs", Some(&raw_key)).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        // One login per session, not per request
+        let logins = state
+            .audit_log
+            .list_by_category(AuditCategory::Authentication);
+        assert_eq!(
+            logins
+                .iter()
+                .filter(|e| matches!(e.action, AuditAction::UserLogin { .. }))
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_api_key_rejected() {
+        let state = create_auth_state();
+        state.api_keys.create(new_key(None)).unwrap();
+
+        let status = get_status(create_auth_router(state.clone()), "/api/runs", Some("sk-bogus")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let status = get_status(create_auth_router(state.clone()), "/api/runs", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // Health stays reachable without a key
+        let status = get_status(create_auth_router(state.clone()), "/api/health", None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let failures = state
+            .audit_log
+            .list_by_category(AuditCategory::Authentication);
+        assert_eq!(
+            failures
+                .iter()
+                .filter(|e| matches!(e.action, AuditAction::LoginFailed { .. }))
+                .count(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_api_key_rejected() {
+        let state = create_auth_state();
+        let (_, raw_key) = state
+            .api_keys
+            .create(new_key(Some(chrono::Utc::now() - chrono::Duration::minutes(5))))
+            .unwrap();
+
+        let status = get_status(create_auth_router(state), "/api/runs", Some(&raw_key)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_key_cannot_create_jobs() {
+        let state = create_auth_state();
+        let mut read_only = new_key(None);
+        read_only.allowed_actions = vec![Permission::new(Resource::All, Action::Read)];
+        let (_, raw_key) = state.api_keys.create(read_only).unwrap();
+
+        let status = get_status(create_auth_router(state.clone()), "/api/runs", Some(&raw_key)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let status = send(
+            create_auth_router(state.clone()),
+            Method::POST,
+            "/api/jobs",
+            Some(&raw_key),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let denials = state.audit_log.list_by_category(AuditCategory::Authorization);
+        assert!(denials
+            .iter()
+            .any(|e| matches!(e.action, AuditAction::PermissionDenied { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_tenant_scoped_key_cannot_access_other_tenant() {
+        let state = create_auth_state();
+        let mut scoped = new_key(None);
+        scoped.allowed_tenants = vec![TenantId::new("tenant1")];
+        let (_, raw_key) = state.api_keys.create(scoped).unwrap();
+
+        let status = get_status(create_auth_router(state.clone()), "/api/tenants/tenant1", Some(&raw_key)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let status = get_status(create_auth_router(state.clone()), "/api/tenants/tenant2", Some(&raw_key)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let status = send(
+            create_auth_router(state.clone()),
+            Method::GET,
+            "/api/runs",
+            Some(&raw_key),
+            Some("tenant2"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // A scoped key can't mint itself an unscoped one
+        let status = send(
+            create_auth_router(state),
+            Method::POST,
+            "/api/auth/keys",
+            Some(&raw_key),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_key_defaults_to_own_tenant() {
+        let state = create_auth_state();
+        let mut own = new_key(None);
+        own.tenant_id = Some(TenantId::new("tenant1"));
+        let (_, raw_key) = state.api_keys.create(own).unwrap();
+
+        // Requests that name no tenant act on the key's own
+        let router = axum::Router::new()
+            .route(
+                "/api/runs",
+                axum::routing::get(|headers: HeaderMap| async move {
+                    target_tenant("/api/runs", &headers).map(|t| t.0).unwrap_or_default()
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(state.clone(), require_api_key));
+        let response = tower::ServiceExt::oneshot(
+            router,
+            axum::http::Request::builder()
+                .uri("/api/runs")
+                .header("Authorization", format!("Bearer {}", raw_key))
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], b"tenant1");
+
+        // Naming another tenant is rejected
+        let status = send(
+            create_auth_router(state.clone()),
+            Method::GET,
+            "/api/runs",
+            Some(&raw_key),
+            Some("tenant2"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // The header can't contradict the tenant in the path
+        let status = send(
+            create_auth_router(state.clone()),
+            Method::GET,
+            "/api/tenants/tenant1",
+            Some(&raw_key),
+            Some("tenant2"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // A key scoped to several tenants must name one
+        let mut several = new_key(None);
+        several.allowed_tenants = vec![TenantId::new("tenant1"), TenantId::new("tenant2")];
+        let (_, raw_key) = state.api_keys.create(several).unwrap();
+        let status = get_status(create_auth_router(state), "/api/runs", Some(&raw_key)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_request_tenant_follows_key() {
+        let state = create_auth_state();
+        let mut own = new_key(None);
+        own.tenant_id = Some(TenantId::new("tenant1"));
+        let (_, scoped_key) = state.api_keys.create(own).unwrap();
+        let (_, unscoped_key) = state.api_keys.create(new_key(None)).unwrap();
+
+        let router = axum::Router::new()
+            .route(
+                "/api/runs",
+                axum::routing::get(|axum::Extension(tenant): axum::Extension<RequestTenant>| async move {
+                    tenant.0.map(|t| t.0).unwrap_or_else(|| "*".to_string())
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(state, require_api_key));
+        let tenant_of = |raw_key: String| {
+            let router = router.clone();
+            async move {
+                let response = tower::ServiceExt::oneshot(
+                    router,
+                    axum::http::Request::builder()
+                        .uri("/api/runs")
+                        .header("Authorization", format!("Bearer {}", raw_key))
+                        .body(axum::body::Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+                let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+
+        assert_eq!(tenant_of(scoped_key).await, "tenant1");
+        assert_eq!(tenant_of(unscoped_key).await, "*");
+
+        let request_tenant = RequestTenant(Some(TenantId::new("tenant1")));
+        assert!(request_tenant.sees(Some(&TenantId::new("tenant1"))));
+        assert!(!request_tenant.sees(Some(&TenantId::new("tenant2"))));
+        assert!(!request_tenant.sees(None));
+        assert!(RequestTenant(None).sees(Some(&TenantId::new("tenant2"))));
+    }
+
+    #[tokio::test]
+    async fn test_key_user_needs_route_permission() {
+        let state = create_auth_state();
+        state
+            .rbac_manager
+            .register_user(shiioo_core::rbac::RbacUser::new(
+                "reader".to_string(),
+                "reader".to_string(),
+                "reader@example.com".to_string(),
+            ))
+            .unwrap();
+        state.rbac_manager.assign_role("reader", "viewer").unwrap();
+
+        let mut reader = new_key(None);
+        reader.user_id = "reader".to_string();
+        let (_, raw_key) = state.api_keys.create(reader).unwrap();
+
+        let status = get_status(create_auth_router(state.clone()), "/api/runs", Some(&raw_key)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // The key itself is unscoped, but the viewer role can't start jobs
+        let status = send(
+            create_auth_router(state.clone()),
+            Method::POST,
+            "/api/jobs",
+            Some(&raw_key),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // Managing one's own keys needs no role
+        let status = send(
+            create_auth_router(state.clone()),
+            Method::POST,
+            "/api/auth/keys",
+            Some(&raw_key),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // A user with no roles at all is denied
+        let mut stranger = new_key(None);
+        stranger.user_id = "stranger".to_string();
+        let (_, raw_key) = state.api_keys.create(stranger).unwrap();
+        let status = get_status(create_auth_router(state), "/api/runs", Some(&raw_key)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_grant_on_one_resource_does_not_reach_others() {
+        let state = create_auth_state();
+        let post = |raw_key: String, path: &'static str| {
+            let router = create_auth_router(state.clone());
+            async move { send(router, Method::POST, path, Some(&raw_key), None).await }
+        };
+
+        // user1 may do anything with workflows, and a key scoped to that is no wider
+        let (_, workflow_key) = state.api_keys.create(new_key(None)).unwrap();
+        let mut scoped = new_key(None);
+        scoped.allowed_actions = vec![Permission::new(Resource::Workflow, Action::All)];
+        let (_, scoped_key) = state.api_keys.create(scoped).unwrap();
+        for key in [&workflow_key, &scoped_key] {
+            assert_eq!(post(key.clone(), "/api/jobs").await, StatusCode::OK);
+            assert_eq!(post(key.clone(), "/api/capacity/sources").await, StatusCode::FORBIDDEN);
+            assert_eq!(post(key.clone(), "/api/graphql").await, StatusCode::FORBIDDEN);
+        }
+
+        // An admin's key scoped to creating roles can't create capacity sources either
+        state
+            .rbac_manager
+            .register_user(shiioo_core::rbac::RbacUser::new(
+                "root".to_string(),
+                "root".to_string(),
+                "root@example.com".to_string(),
+            ))
+            .unwrap();
+        state.rbac_manager.assign_role("root", "admin").unwrap();
+        let mut role_creator = new_key(None);
+        role_creator.user_id = "root".to_string();
+        role_creator.allowed_actions = vec![Permission::new(Resource::Role, Action::Create)];
+        let (_, role_key) = state.api_keys.create(role_creator).unwrap();
+        assert_eq!(post(role_key, "/api/capacity/sources").await, StatusCode::FORBIDDEN);
+
+        let mut admin = new_key(None);
+        admin.user_id = "root".to_string();
+        let (_, admin_key) = state.api_keys.create(admin).unwrap();
+        assert_eq!(post(admin_key.clone(), "/api/capacity/sources").await, StatusCode::OK);
+        assert_eq!(post(admin_key, "/api/graphql").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_limited_grant_only_reaches_routes_that_check_the_instance() {
+        use shiioo_core::rbac::ResourceSelector;
+
+        let state = create_auth_state();
+        let mut limited = shiioo_core::rbac::RbacRole::new(
+            "limited".to_string(),
+            "Limited".to_string(),
+            "Reads one secret and payments runs".to_string(),
+        );
+        limited.add_permission(Permission::with_resource_id(
+            Resource::Secret,
+            Action::Read,
+            "s1".to_string(),
+        ));
+        limited.add_permission(Permission::with_selector(
+            Resource::Workflow,
+            Action::Read,
+            ResourceSelector::Tag("team:payments".to_string()),
+        ));
+        state.rbac_manager.register_role(limited).unwrap();
+        state
+            .rbac_manager
+            .register_user(shiioo_core::rbac::RbacUser::new(
+                "dave".to_string(),
+                "dave".to_string(),
+                "dave@example.com".to_string(),
+            ))
+            .unwrap();
+        state.rbac_manager.assign_role("dave", "limited").unwrap();
+        let mut key = new_key(None);
+        key.user_id = "dave".to_string();
+        let (_, raw_key) = state.api_keys.create(key).unwrap();
+        let get = |path: &'static str| {
+            get_status(create_auth_router(state.clone()), path, Some(&raw_key))
+        };
+
+        // Run handlers filter by the grant's selector; the other routes don't, so they
+        // need an unlimited grant
+        assert_eq!(get("/api/runs").await, StatusCode::OK);
+        assert_eq!(get("/api/analytics/workflows").await, StatusCode::FORBIDDEN);
+        assert_eq!(get("/api/secrets/s1").await, StatusCode::FORBIDDEN);
+        assert_eq!(get("/api/secrets/s2").await, StatusCode::FORBIDDEN);
+    }
+
+    async fn get_impersonating(router: axum::Router, api_key: &str, tenant_id: &str) -> StatusCode {
+        use tower::ServiceExt;
+
+        let request = axum::http::Request::builder()
+            .uri("/api/runs")
+            .header("Authorization", format!("Bearer {}", api_key))
+            .header(IMPERSONATE_TENANT_HEADER, tenant_id)
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        router.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_platform_admin_impersonates_tenant() {
+        let state = create_auth_state();
+        state
+            .rbac_manager
+            .register_user(shiioo_core::rbac::RbacUser::new(
+                "ops".to_string(),
+                "ops".to_string(),
+                "ops@example.com".to_string(),
+            ))
+            .unwrap();
+        state.rbac_manager.assign_role("ops", "platform_admin").unwrap();
+
+        // The operator's key is scoped to its own tenant only
+        let mut operator = new_key(None);
+        operator.user_id = "ops".to_string();
+        operator.allowed_tenants = vec![TenantId::new("platform")];
+        let (_, raw_key) = state.api_keys.create(operator).unwrap();
+
+        let status = send(
+            create_auth_router(state.clone()),
+            Method::GET,
+            "/api/runs",
+            Some(&raw_key),
+            Some("tenantA"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let status = get_impersonating(create_auth_router(state.clone()), &raw_key, "tenantA").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let accesses = state.audit_log.list_by_category(AuditCategory::DataAccess);
+        let entry = accesses
+            .iter()
+            .find(|e| matches!(&e.action, AuditAction::DataAccessed { resource_id, .. } if resource_id == "tenantA"))
+            .unwrap();
+        assert_eq!(entry.tenant_id.as_deref(), Some("tenantA"));
+        assert_eq!(entry.metadata["impersonated_by"], "ops");
+    }
+
+    #[tokio::test]
+    async fn test_impersonation_requires_permission() {
+        let state = create_auth_state();
+        let (_, raw_key) = state.api_keys.create(new_key(None)).unwrap();
+
+        let status = get_impersonating(create_auth_router(state.clone()), &raw_key, "tenantA").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        assert!(state
+            .audit_log
+            .list_by_category(AuditCategory::DataAccess)
+            .is_empty());
+        let denials = state.audit_log.list_by_category(AuditCategory::Authorization);
+        assert!(denials.iter().any(|e| matches!(
+            &e.action,
+            AuditAction::PermissionDenied { permission, .. } if permission == "Tenant:Impersonate"
+        )));
+    }
+
+    async fn post_signed(
+        router: axum::Router,
+        raw_key: &str,
+        timestamp: i64,
+        nonce: &str,
+    ) -> StatusCode {
+        use tower::ServiceExt;
+
+        let body = br#"{"name":"nightly"}"#;
+        let signature =
+            shiioo_core::api_key::sign_request(raw_key, "POST", "/api/jobs", body, timestamp, nonce);
+        let request = axum::http::Request::builder()
+            .method(Method::POST)
+            .uri("/api/jobs")
+            .header("Authorization", format!("Bearer {}", raw_key))
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(NONCE_HEADER, nonce)
+            .header(SIGNATURE_HEADER, signature)
+            .body(axum::body::Body::from(&body[..]))
+            .unwrap();
+
+        router.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_signed_requests() {
+        let state = create_auth_state_with(crate::config::AuthConfig {
+            enabled: true,
+            require_signed_requests: true,
+            signature_max_age_secs: 60,
+            ..Default::default()
+        });
+        let (_, raw_key) = state.api_keys.create(new_key(None)).unwrap();
+        let router = create_auth_router(state);
+        let now = chrono::Utc::now().timestamp();
+
+        // A fresh signature is accepted
+        assert_eq!(post_signed(router.clone(), &raw_key, now, "n1").await, StatusCode::OK);
+
+        // The same nonce can't be replayed
+        assert_eq!(
+            post_signed(router.clone(), &raw_key, now, "n1").await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        // Nor can an old request with a new nonce
+        assert_eq!(
+            post_signed(router.clone(), &raw_key, now - 120, "n2").await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        // Unsigned writes are rejected, reads still need only the key
+        assert_eq!(
+            send(router.clone(), Method::POST, "/api/jobs", Some(&raw_key), None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(get_status(router, "/api/runs", Some(&raw_key)).await, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_route_permission() {
+        assert_eq!(
+            route_permission(&Method::GET, "/api/runs/123"),
+            (Resource::Workflow, Action::Read)
+        );
+        assert_eq!(
+            route_permission(&Method::POST, "/api/jobs"),
+            (Resource::Workflow, Action::Execute)
+        );
+        assert_eq!(
+            route_permission(&Method::POST, "/api/runs/123/retry"),
+            (Resource::Workflow, Action::Execute)
+        );
+        assert_eq!(
+            route_permission(&Method::POST, "/api/approvals/a1/vote"),
+            (Resource::Approval, Action::Approve)
+        );
+        assert_eq!(
+            route_permission(&Method::POST, "/api/approvals/a1/force-resolve"),
+            (Resource::Approval, Action::Override)
+        );
+        assert_eq!(
+            route_permission(&Method::POST, "/api/audit/ingest"),
+            (Resource::AuditLog, Action::Create)
+        );
+        assert_eq!(
+            route_permission(&Method::DELETE, "/api/secrets/s1"),
+            (Resource::Secret, Action::Delete)
+        );
+        assert_eq!(
+            route_permission(&Method::POST, "/api/capacity/dead-letter/r1/requeue"),
+            (Resource::Capacity, Action::Execute)
+        );
+        assert_eq!(
+            route_permission(&Method::GET, "/api/metrics"),
+            (Resource::Cluster, Action::Read)
+        );
+        assert_eq!(
+            route_permission(&Method::POST, "/api/graphql"),
+            (Resource::All, Action::Create)
+        );
+
+        assert!(route_checks_instance("/api/runs/123/events"));
+        assert!(route_checks_instance("/api/approvals/a1/vote"));
+        assert!(route_checks_instance("/api/approvals/bulk-vote"));
+        assert!(!route_checks_instance("/api/approvals/a1"));
+        assert!(!route_checks_instance("/api/config-changes/c1/apply"));
+        assert!(!route_checks_instance("/api/secrets/s1"));
+    }
 }
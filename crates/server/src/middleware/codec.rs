@@ -0,0 +1,131 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use shiioo_core::codec::{msgpack, Codec};
+
+/// Largest body that will be transcoded between MessagePack and JSON
+const MAX_TRANSCODE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Content negotiation for MessagePack clients.
+///
+/// Handlers speak JSON only. A `Content-Type: application/msgpack` request body is
+/// transcoded to JSON before it reaches them, and successful JSON responses are
+/// transcoded to MessagePack when the client's `Accept` header asks for it. Error
+/// responses stay JSON so every client can read them.
+pub async fn negotiate_codec(req: Request, next: Next) -> Response {
+    let wants_msgpack = req
+        .headers()
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|mime| Codec::from_content_type(mime) == Some(Codec::MessagePack));
+
+    let req = if content_codec(req.headers()) == Some(Codec::MessagePack) {
+        let (mut parts, body) = req.into_parts();
+        let json = match axum::body::to_bytes(body, MAX_TRANSCODE_BYTES)
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| msgpack::from_slice::<serde_json::Value>(&bytes))
+            .and_then(|value| Ok(serde_json::to_vec(&value)?))
+        {
+            Ok(json) => json,
+            Err(e) => {
+                return (StatusCode::BAD_REQUEST, format!("Invalid MessagePack body: {}", e))
+                    .into_response()
+            }
+        };
+
+        set_content_type(&mut parts.headers, Codec::Json);
+        Request::from_parts(parts, Body::from(json))
+    } else {
+        req
+    };
+
+    let response = next.run(req).await;
+    if !wants_msgpack
+        || !response.status().is_success()
+        || content_codec(response.headers()) != Some(Codec::Json)
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let encoded = axum::body::to_bytes(body, MAX_TRANSCODE_BYTES)
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| Ok(serde_json::from_slice::<serde_json::Value>(&bytes)?))
+        .and_then(|value| msgpack::to_vec(&value));
+
+    match encoded {
+        Ok(encoded) => {
+            set_content_type(&mut parts.headers, Codec::MessagePack);
+            Response::from_parts(parts, Body::from(encoded))
+        }
+        Err(e) => {
+            tracing::error!("Failed to encode response as MessagePack: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn content_codec(headers: &HeaderMap) -> Option<Codec> {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Codec::from_content_type)
+}
+
+fn set_content_type(headers: &mut HeaderMap, codec: Codec) {
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(codec.content_type()));
+    headers.remove(header::CONTENT_LENGTH);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_msgpack_request_and_response() {
+        let router = Router::new()
+            .route(
+                "/api/echo",
+                post(|Json(body): Json<serde_json::Value>| async move { Json(body) }),
+            )
+            .layer(axum::middleware::from_fn(negotiate_codec));
+
+        let body = serde_json::json!({"name": "Code Review", "count": 3});
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/echo")
+            .header(header::CONTENT_TYPE, "application/msgpack")
+            .header(header::ACCEPT, "application/msgpack")
+            .body(Body::from(msgpack::to_vec(&body).unwrap()))
+            .unwrap();
+
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(content_codec(response.headers()), Some(Codec::MessagePack));
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let echoed: serde_json::Value = msgpack::from_slice(&bytes).unwrap();
+        assert_eq!(echoed, body);
+
+        // JSON clients are unaffected
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/echo")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&body).unwrap()))
+            .unwrap();
+
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(content_codec(response.headers()), Some(Codec::Json));
+    }
+}
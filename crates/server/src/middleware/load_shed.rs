@@ -0,0 +1,199 @@
+use crate::config::LoadSheddingConfig;
+use axum::{
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// How important a request is to keep serving under load
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestPriority {
+    /// Listings and reporting that clients can retry later
+    Low,
+    /// Everything else, e.g. run status, jobs and approvals
+    Critical,
+}
+
+/// Reporting endpoints whose reads can be deferred under load
+const LOW_PRIORITY_PREFIXES: &[&str] = &[
+    "/api/analytics",
+    "/api/metrics",
+    "/api/audit",
+    "/api/compliance",
+    "/api/security",
+    "/api/graphql",
+];
+
+/// Collections whose full listings can be deferred under load. Single-resource reads of
+/// them stay critical, as do approval listings that reviewers act on.
+const LOW_PRIORITY_LISTINGS: &[&str] = &[
+    "/api/runs",
+    "/api/config-changes",
+    "/api/organizations",
+    "/api/policies",
+    "/api/roles",
+    "/api/routines",
+    "/api/templates",
+    "/api/tenants",
+];
+
+/// Classify a request for load shedding.
+///
+/// Reads of analytics and reporting endpoints, and listings of the collections in
+/// `LOW_PRIORITY_LISTINGS`, are low priority. Everything else, including all writes,
+/// is critical.
+pub fn route_priority(method: &Method, path: &str) -> RequestPriority {
+    if method != Method::GET {
+        return RequestPriority::Critical;
+    }
+
+    let is_reporting = LOW_PRIORITY_PREFIXES.iter().any(|prefix| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    });
+
+    let is_listing = LOW_PRIORITY_LISTINGS.contains(&path);
+
+    if is_reporting || is_listing {
+        RequestPriority::Low
+    } else {
+        RequestPriority::Critical
+    }
+}
+
+/// Tracks in-flight requests and decides when to shed low-priority ones
+#[derive(Debug)]
+pub struct LoadShedder {
+    config: LoadSheddingConfig,
+    in_flight: AtomicUsize,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        Self {
+            config,
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of requests currently being handled
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Count a request as in flight until the guard is dropped
+    pub fn track(&self) -> InFlightGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard { shedder: self }
+    }
+
+    fn should_shed(&self, priority: RequestPriority) -> bool {
+        self.config.enabled
+            && priority == RequestPriority::Low
+            && self.in_flight() >= self.config.low_priority_max_in_flight
+    }
+}
+
+/// Marks a request as finished when dropped
+pub struct InFlightGuard<'a> {
+    shedder: &'a LoadShedder,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.shedder.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Load-shedding middleware.
+///
+/// Once the number of in-flight requests reaches `low_priority_max_in_flight`, low-priority
+/// requests are rejected with 503 and a `Retry-After` header, so capacity goes to critical
+/// endpoints.
+pub async fn shed_load(
+    State(shedder): State<Arc<LoadShedder>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let priority = route_priority(req.method(), req.uri().path());
+
+    if shedder.should_shed(priority) {
+        tracing::warn!(
+            "Shedding low-priority request to {} ({} in flight)",
+            req.uri().path(),
+            shedder.in_flight()
+        );
+
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, shedder.config.retry_after_secs.to_string())],
+            "Server is overloaded, retry later",
+        )
+            .into_response();
+    }
+
+    let _guard = shedder.track();
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_route_priority() {
+        assert_eq!(route_priority(&Method::GET, "/api/analytics/workflows"), RequestPriority::Low);
+        assert_eq!(route_priority(&Method::GET, "/api/metrics"), RequestPriority::Low);
+        assert_eq!(route_priority(&Method::GET, "/api/runs"), RequestPriority::Low);
+        assert_eq!(route_priority(&Method::GET, "/api/runs/123"), RequestPriority::Critical);
+        assert_eq!(route_priority(&Method::GET, "/api/health"), RequestPriority::Critical);
+        assert_eq!(route_priority(&Method::GET, "/api/approvals"), RequestPriority::Critical);
+        assert_eq!(route_priority(&Method::GET, "/api/ws"), RequestPriority::Critical);
+        assert_eq!(route_priority(&Method::POST, "/api/jobs"), RequestPriority::Critical);
+        assert_eq!(
+            route_priority(&Method::POST, "/api/approvals/a1/vote"),
+            RequestPriority::Critical
+        );
+        assert_eq!(route_priority(&Method::GET, "/api/auditors/a1"), RequestPriority::Critical);
+    }
+
+    #[tokio::test]
+    async fn test_sheds_analytics_but_serves_run_status_under_load() {
+        let shedder = Arc::new(LoadShedder::new(LoadSheddingConfig {
+            enabled: true,
+            low_priority_max_in_flight: 2,
+            retry_after_secs: 7,
+        }));
+
+        let router = Router::new()
+            .route("/api/analytics/workflows", get(|| async { "ok" }))
+            .route("/api/runs/{run_id}", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(shedder.clone(), shed_load));
+
+        let get_path = |path: &str| {
+            let router = router.clone();
+            let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+            async move { router.oneshot(request).await.unwrap() }
+        };
+
+        assert_eq!(get_path("/api/analytics/workflows").await.status(), StatusCode::OK);
+
+        // Simulate two long-running requests
+        let load = [shedder.track(), shedder.track()];
+
+        let response = get_path("/api/analytics/workflows").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "7");
+
+        assert_eq!(get_path("/api/runs/123").await.status(), StatusCode::OK);
+
+        drop(load);
+        assert_eq!(shedder.in_flight(), 0);
+        assert_eq!(get_path("/api/analytics/workflows").await.status(), StatusCode::OK);
+    }
+}
@@ -1,3 +1,9 @@
 pub mod auth;
+pub mod load_shed;
+#[cfg(feature = "msgpack")]
+pub mod codec;
 
 pub use auth::*;
+pub use load_shed::{shed_load, LoadShedder};
+#[cfg(feature = "msgpack")]
+pub use codec::negotiate_codec;
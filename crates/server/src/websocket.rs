@@ -1,15 +1,33 @@
 use axum::{
     extract::{
-        ws::{Message, WebSocket},
+        ws::{close_code, CloseFrame, Message, WebSocket},
         State, WebSocketUpgrade,
     },
     response::Response,
 };
 use axum::body::Bytes;
+use axum::Extension;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use shiioo_core::api_key::ApiKey;
+use shiioo_core::approval::ApprovalReminder;
+use shiioo_core::audit::AuditEntry;
+use shiioo_core::rbac::{Action, Permission, Resource};
+use shiioo_core::tenant::TenantId;
+use shiioo_core::types::{Approval, ApprovalStatus, CapacityAlert, CapacityAlertKind};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
 
-use crate::config::AppState;
+use crate::config::{AppState, WebSocketConfig};
+
+/// Updates on each topic kept for sessions that fell behind or resume after reconnecting
+pub const REPLAY_BUFFER_SIZE: usize = 1024;
+
+/// Numbered updates each session's receiver may queue before it lags and replays the
+/// rest from the buffer
+const TOPIC_CHANNEL_CAPACITY: usize = 256;
 
 /// WebSocket message types for real-time updates
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +61,33 @@ pub enum WsMessage {
         active_routines: usize,
         pending_approvals: usize,
     },
+    /// Approval resolved by a decisive vote
+    ApprovalUpdate {
+        approval_id: String,
+        status: ApprovalStatus,
+    },
+    /// Approval still pending past its reminder time, for the approvers yet to vote
+    ApprovalReminder {
+        approval_id: String,
+        approvers: Vec<String>,
+        reminder: u32,
+    },
+    /// Capacity source backed off or a queued request was dead-lettered
+    CapacityAlert {
+        kind: CapacityAlertKind,
+        source_id: Option<String>,
+        message: String,
+    },
+    /// Audit entry recorded
+    AuditEvent { entry: AuditEntry },
+    /// Updates on `topic` with sequence numbers `from_seq..=to_seq` were dropped because
+    /// the client fell behind further than the replay buffer reaches; it should backfill
+    /// them from the REST API
+    Gap {
+        topic: SubscriptionTopic,
+        from_seq: u64,
+        to_seq: u64,
+    },
     /// Client subscription confirmation
     Subscribed { subscription_id: String },
     /// Error message
@@ -52,6 +97,16 @@ pub enum WsMessage {
     Pong,
 }
 
+/// Stream of updates a client can subscribe to, each numbered on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionTopic {
+    Approvals,
+    ApprovalReminders,
+    Capacity,
+    Audit,
+}
+
 /// WebSocket subscription request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "action")]
@@ -64,20 +119,418 @@ pub enum WsRequest {
     SubscribeMetrics,
     /// Subscribe to system health
     SubscribeHealth,
+    /// Subscribe to approval resolutions and reminders
+    SubscribeApprovals,
+    /// Subscribe to capacity alerts
+    SubscribeCapacity,
+    /// Subscribe to audit entries
+    SubscribeAudit,
+    /// Replay the buffered updates on a subscribed topic after `after_seq`, e.g. the last
+    /// one seen before reconnecting
+    Resume {
+        topic: SubscriptionTopic,
+        after_seq: u64,
+    },
     /// Unsubscribe
     Unsubscribe,
 }
 
-/// WebSocket handler for real-time updates
+/// Server-side limits on how long a WebSocket session may stay open
+#[derive(Debug, Clone, Copy)]
+pub struct SessionLimits {
+    pub idle_timeout: Duration,
+    pub max_lifetime: Duration,
+    pub ping_interval: Duration,
+}
+
+impl SessionLimits {
+    pub fn from_config(config: &WebSocketConfig) -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(config.idle_timeout_secs),
+            max_lifetime: Duration::from_secs(config.max_lifetime_secs),
+            ping_interval: Duration::from_secs(config.ping_interval_secs.max(1)),
+        }
+    }
+
+    /// When the session must be closed, and the close reason to report
+    pub fn deadline(&self, started_at: Instant, last_activity: Instant) -> (Instant, &'static str) {
+        let idle_deadline = last_activity + self.idle_timeout;
+        let lifetime_deadline = started_at + self.max_lifetime;
+
+        if idle_deadline < lifetime_deadline {
+            (idle_deadline, "idle timeout")
+        } else {
+            (lifetime_deadline, "max session lifetime reached")
+        }
+    }
+}
+
+/// A subscription update together with its position on its topic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequencedMessage {
+    /// Sequence number of the update on its topic, counting from 1 since the server
+    /// started and shared by every session. Updates a session's key may not see leave
+    /// holes in its numbering. `Gap` messages have none of their own, they account for
+    /// the sequence numbers they cover.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    #[serde(flatten)]
+    pub message: WsMessage,
+}
+
+/// The numbered update streams shared by every session, one per topic, each started
+/// on first subscription
+#[derive(Default)]
+pub struct UpdateHub {
+    streams: Mutex<HashMap<SubscriptionTopic, Arc<TopicStream>>>,
+}
+
+impl UpdateHub {
+    /// The stream of `topic`, spawning the task that numbers its updates on first use
+    pub fn stream(&self, topic: SubscriptionTopic, state: &AppState) -> Arc<TopicStream> {
+        let mut streams = self.streams.lock().unwrap();
+        streams
+            .entry(topic)
+            .or_insert_with(|| {
+                let stream = Arc::new(TopicStream::new());
+                match topic {
+                    SubscriptionTopic::Approvals => forward(
+                        state.approval_manager.subscribe(),
+                        stream.clone(),
+                        |approval: Approval| WsMessage::ApprovalUpdate {
+                            approval_id: approval.id.0,
+                            status: approval.status,
+                        },
+                    ),
+                    SubscriptionTopic::ApprovalReminders => forward(
+                        state.approval_manager.subscribe_reminders(),
+                        stream.clone(),
+                        |reminder: ApprovalReminder| WsMessage::ApprovalReminder {
+                            approval_id: reminder.approval_id.0,
+                            approvers: reminder.approvers.into_iter().map(|p| p.0).collect(),
+                            reminder: reminder.reminder,
+                        },
+                    ),
+                    SubscriptionTopic::Capacity => forward(
+                        state.capacity_broker.subscribe_alerts(),
+                        stream.clone(),
+                        |alert: CapacityAlert| WsMessage::CapacityAlert {
+                            kind: alert.kind,
+                            source_id: alert.source_id.map(|id| id.0),
+                            message: alert.message,
+                        },
+                    ),
+                    SubscriptionTopic::Audit => forward(
+                        state.audit_log.subscribe(),
+                        stream.clone(),
+                        |entry: AuditEntry| WsMessage::AuditEvent { entry },
+                    ),
+                }
+                stream
+            })
+            .clone()
+    }
+}
+
+/// Number each update from `source` on `stream` until the source closes
+fn forward<T: Clone + Send + 'static>(
+    mut source: broadcast::Receiver<T>,
+    stream: Arc<TopicStream>,
+    message: fn(T) -> WsMessage,
+) {
+    tokio::spawn(async move {
+        loop {
+            match source.recv().await {
+                Ok(update) => stream.publish(message(update)),
+                // Lost for every session, which see them as a gap
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("WebSocket update stream lagged, skipped {} updates", skipped);
+                    stream.skip(skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// One topic's updates, numbered once for every session, with the latest kept for
+/// replay
+pub struct TopicStream {
+    sender: broadcast::Sender<(u64, WsMessage)>,
+    replay: Mutex<Replay>,
+}
+
+#[derive(Default)]
+struct Replay {
+    last_seq: u64,
+    // The last `REPLAY_BUFFER_SIZE` updates, oldest first
+    buffer: VecDeque<(u64, WsMessage)>,
+}
+
+impl TopicStream {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(TOPIC_CHANNEL_CAPACITY);
+        Self {
+            sender,
+            replay: Mutex::new(Replay::default()),
+        }
+    }
+
+    fn publish(&self, message: WsMessage) {
+        // Sent under the lock, so subscribers see updates in sequence order
+        let mut replay = self.replay.lock().unwrap();
+        replay.last_seq += 1;
+        let seq = replay.last_seq;
+        if replay.buffer.len() == REPLAY_BUFFER_SIZE {
+            replay.buffer.pop_front();
+        }
+        replay.buffer.push_back((seq, message.clone()));
+        let _ = self.sender.send((seq, message));
+    }
+
+    /// Account for updates lost before they could be numbered
+    fn skip(&self, count: u64) {
+        let mut replay = self.replay.lock().unwrap();
+        replay.last_seq = replay.last_seq.saturating_add(count);
+    }
+
+    /// Receive updates numbered after the returned sequence number
+    fn subscribe(&self) -> (u64, broadcast::Receiver<(u64, WsMessage)>) {
+        let replay = self.replay.lock().unwrap();
+        (replay.last_seq, self.sender.subscribe())
+    }
+
+    /// The latest sequence number and the buffered updates after `after_seq`
+    fn replay_after(&self, after_seq: u64) -> (u64, Vec<(u64, WsMessage)>) {
+        let replay = self.replay.lock().unwrap();
+        let buffered = replay
+            .buffer
+            .iter()
+            .filter(|(seq, _)| *seq > after_seq)
+            .cloned()
+            .collect();
+        (replay.last_seq, buffered)
+    }
+}
+
+/// A session's receiver on one topic's stream
+struct TopicReceiver {
+    stream: Arc<TopicStream>,
+    updates: broadcast::Receiver<(u64, WsMessage)>,
+}
+
+/// Updates pushed to a client from the topics it subscribed to, limited to what its
+/// API key may see
+#[derive(Default)]
+pub struct SubscriptionFeed {
+    // Key the client authenticated with; `None` when authentication is off
+    key: Option<ApiKey>,
+    approvals: Option<TopicReceiver>,
+    reminders: Option<TopicReceiver>,
+    capacity: Option<TopicReceiver>,
+    audit: Option<TopicReceiver>,
+    // Sequence number of the last update delivered, skipped or reported missing, per topic
+    last_seqs: HashMap<SubscriptionTopic, u64>,
+    // Replayed updates and gaps waiting to be delivered, in order
+    pending: VecDeque<SequencedMessage>,
+}
+
+impl SubscriptionFeed {
+    /// Feed that only delivers updates `key` is scoped for
+    pub fn for_key(key: Option<ApiKey>) -> Self {
+        Self {
+            key,
+            ..Default::default()
+        }
+    }
+
+    pub fn subscribe_approvals(&mut self, state: &AppState) {
+        self.subscribe(SubscriptionTopic::Approvals, state);
+        self.subscribe(SubscriptionTopic::ApprovalReminders, state);
+    }
+
+    pub fn subscribe_capacity(&mut self, state: &AppState) {
+        self.subscribe(SubscriptionTopic::Capacity, state);
+    }
+
+    /// Subscribe to audit entries, if the key and its user may read the audit log
+    pub fn subscribe_audit(&mut self, state: &AppState) -> bool {
+        if let Some(key) = &self.key {
+            let read = Permission::new(Resource::AuditLog, Action::Read);
+            if !key.permits_action(Resource::AuditLog, Action::Read)
+                || !state.rbac_manager.check_permission(&key.user_id, &read)
+            {
+                return false;
+            }
+        }
+        self.subscribe(SubscriptionTopic::Audit, state);
+        true
+    }
+
+    /// Start receiving a topic's updates from now on, unless already subscribed
+    fn subscribe(&mut self, topic: SubscriptionTopic, state: &AppState) {
+        if self.receiver(topic).is_some() {
+            return;
+        }
+        let stream = state.ws_updates.stream(topic, state);
+        let (last_seq, updates) = stream.subscribe();
+        self.last_seqs.insert(topic, last_seq);
+        *self.receiver(topic) = Some(TopicReceiver { stream, updates });
+    }
+
+    fn receiver(&mut self, topic: SubscriptionTopic) -> &mut Option<TopicReceiver> {
+        match topic {
+            SubscriptionTopic::Approvals => &mut self.approvals,
+            SubscriptionTopic::ApprovalReminders => &mut self.reminders,
+            SubscriptionTopic::Capacity => &mut self.capacity,
+            SubscriptionTopic::Audit => &mut self.audit,
+        }
+    }
+
+    /// Replay the buffered updates on a subscribed topic after `after_seq`, with a `Gap`
+    /// for those no longer buffered. False if the client hasn't subscribed to it.
+    pub fn resume(&mut self, topic: SubscriptionTopic, after_seq: u64) -> bool {
+        let Some(receiver) = self.receiver(topic) else {
+            return false;
+        };
+        let stream = receiver.stream.clone();
+        let (last_seq, buffered) = stream.replay_after(after_seq);
+        // Numbering restarts with the server, so a later cursor resumes from now
+        let after_seq = after_seq.min(last_seq);
+        self.queue_replay(topic, after_seq, last_seq + 1, buffered);
+        self.last_seqs.insert(topic, last_seq);
+        true
+    }
+
+    /// Queue the buffered updates numbered between `after_seq` and `before_seq`, with a
+    /// `Gap` wherever the buffer no longer holds them
+    fn queue_replay(
+        &mut self,
+        topic: SubscriptionTopic,
+        after_seq: u64,
+        before_seq: u64,
+        buffered: Vec<(u64, WsMessage)>,
+    ) {
+        let mut expected = after_seq + 1;
+        for (seq, message) in buffered {
+            if seq <= after_seq || seq >= before_seq {
+                continue;
+            }
+            if seq > expected {
+                self.queue_gap(topic, expected, seq - 1);
+            }
+            expected = seq + 1;
+            if self.permits(&message) {
+                self.pending.push_back(SequencedMessage {
+                    seq: Some(seq),
+                    message,
+                });
+            }
+        }
+        if expected < before_seq {
+            self.queue_gap(topic, expected, before_seq - 1);
+        }
+    }
+
+    fn queue_gap(&mut self, topic: SubscriptionTopic, from_seq: u64, to_seq: u64) {
+        self.pending.push_back(SequencedMessage {
+            seq: None,
+            message: WsMessage::Gap {
+                topic,
+                from_seq,
+                to_seq,
+            },
+        });
+    }
+
+    /// Whether the key may see an update: one of a tenant it is scoped for, or one
+    /// belonging to no tenant if the key isn't limited to some
+    fn permits(&self, message: &WsMessage) -> bool {
+        let Some(key) = &self.key else {
+            return true;
+        };
+        let tenant = match message {
+            WsMessage::AuditEvent { entry } => entry.tenant_id.as_deref(),
+            _ => None,
+        };
+        match tenant {
+            Some(tenant) => key.permits_tenant(&TenantId::new(tenant)),
+            None => !key.is_tenant_scoped(),
+        }
+    }
+
+    /// Wait for the next update on any subscribed topic. Updates missed because the
+    /// client fell behind are replayed from the buffer first, or reported as a `Gap`
+    /// once they're no longer buffered. Never resolves if the client has not subscribed
+    /// to any topic.
+    pub async fn next(&mut self) -> SequencedMessage {
+        loop {
+            if let Some(message) = self.pending.pop_front() {
+                return message;
+            }
+
+            let (topic, (seq, message)) = self.recv().await;
+            let last_seq = self.last_seqs.get(&topic).copied().unwrap_or_default();
+            // Already replayed
+            if seq <= last_seq {
+                continue;
+            }
+            if seq > last_seq + 1 {
+                let stream = self.receiver(topic).as_ref().map(|r| r.stream.clone());
+                let buffered = stream.map(|s| s.replay_after(last_seq).1).unwrap_or_default();
+                self.queue_replay(topic, last_seq, seq, buffered);
+            }
+            self.last_seqs.insert(topic, seq);
+            if self.permits(&message) {
+                self.pending.push_back(SequencedMessage {
+                    seq: Some(seq),
+                    message,
+                });
+            }
+        }
+    }
+
+    /// The next numbered update on any subscribed topic
+    async fn recv(&mut self) -> (SubscriptionTopic, (u64, WsMessage)) {
+        tokio::select! {
+            update = recv_topic(&mut self.approvals) => (SubscriptionTopic::Approvals, update),
+            update = recv_topic(&mut self.reminders) => {
+                (SubscriptionTopic::ApprovalReminders, update)
+            }
+            update = recv_topic(&mut self.capacity) => (SubscriptionTopic::Capacity, update),
+            update = recv_topic(&mut self.audit) => (SubscriptionTopic::Audit, update),
+        }
+    }
+}
+
+/// Receive the next numbered update on a topic. After lagging behind the channel the
+/// receiver picks up at the oldest update still queued, and `next` replays the rest.
+async fn recv_topic(receiver: &mut Option<TopicReceiver>) -> (u64, WsMessage) {
+    while let Some(topic) = receiver {
+        match topic.updates.recv().await {
+            Ok(update) => return update,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::debug!("WebSocket client lagged, replaying {} updates", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => *receiver = None,
+        }
+    }
+    std::future::pending().await
+}
+
+/// WebSocket handler for real-time updates. With authentication on, the connection only
+/// receives updates the request's API key is scoped for.
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
+    api_key: Option<Extension<ApiKey>>,
 ) -> Response {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+    let key = api_key.map(|Extension(key)| key);
+    ws.on_upgrade(move |socket| handle_socket(socket, state, key))
 }
 
 /// Handle individual WebSocket connection
-async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
+async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>, key: Option<ApiKey>) {
     // Send initial connection confirmation
     let confirm_msg = WsMessage::Subscribed {
         subscription_id: uuid::Uuid::new_v4().to_string(),
@@ -87,8 +540,45 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
         let _ = socket.send(Message::Text(msg_json.into())).await;
     }
 
-    // Handle incoming messages from client
-    while let Some(msg_result) = socket.recv().await {
+    let limits = SessionLimits::from_config(&state.websocket_config);
+    let started_at = Instant::now();
+    let mut last_activity = started_at;
+    let mut feed = SubscriptionFeed::for_key(key);
+    let mut pings =
+        tokio::time::interval_at(started_at + limits.ping_interval, limits.ping_interval);
+
+    // Handle incoming messages from client, pushing subscribed updates and pings in between
+    loop {
+        let (deadline, reason) = limits.deadline(started_at, last_activity);
+        let received = tokio::select! {
+            received = tokio::time::timeout_at(deadline, socket.recv()) => received,
+            update = feed.next() => {
+                if let Ok(msg_json) = serde_json::to_string(&update) {
+                    let _ = socket.send(Message::Text(msg_json.into())).await;
+                }
+                continue;
+            }
+            _ = pings.tick() => {
+                let _ = socket.send(Message::Ping(Bytes::new())).await;
+                continue;
+            }
+        };
+        let msg_result = match received {
+            Ok(Some(msg_result)) => msg_result,
+            Ok(None) => break,
+            Err(_) => {
+                tracing::info!("Closing WebSocket connection: {}", reason);
+                let _ = socket
+                    .send(Message::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: reason.into(),
+                    })))
+                    .await;
+                break;
+            }
+        };
+        last_activity = Instant::now();
+
         match msg_result {
             Ok(Message::Text(text)) => {
                 tracing::debug!("Received WS message: {}", text);
@@ -137,6 +627,36 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
                                 let _ = socket.send(Message::Text(msg_json.into())).await;
                             }
                         }
+                        WsRequest::SubscribeApprovals => {
+                            tracing::info!("Client subscribed to approval updates");
+                            feed.subscribe_approvals(&state);
+                        }
+                        WsRequest::SubscribeCapacity => {
+                            tracing::info!("Client subscribed to capacity alerts");
+                            feed.subscribe_capacity(&state);
+                        }
+                        WsRequest::SubscribeAudit => {
+                            if feed.subscribe_audit(&state) {
+                                tracing::info!("Client subscribed to audit events");
+                            } else {
+                                let response = WsMessage::Error {
+                                    message: "Not permitted to read audit entries".to_string(),
+                                };
+                                if let Ok(msg_json) = serde_json::to_string(&response) {
+                                    let _ = socket.send(Message::Text(msg_json.into())).await;
+                                }
+                            }
+                        }
+                        WsRequest::Resume { topic, after_seq } => {
+                            if !feed.resume(topic, after_seq) {
+                                let response = WsMessage::Error {
+                                    message: format!("Not subscribed to {:?}", topic),
+                                };
+                                if let Ok(msg_json) = serde_json::to_string(&response) {
+                                    let _ = socket.send(Message::Text(msg_json.into())).await;
+                                }
+                            }
+                        }
                         WsRequest::Unsubscribe => {
                             tracing::info!("Client unsubscribed");
                             break;
@@ -162,3 +682,367 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
 
     tracing::info!("WebSocket connection terminated");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_config, ServerConfig};
+    use shiioo_core::audit::{AuditAction, AuditCategory, AuditSeverity};
+    use shiioo_core::types::{
+        ApprovalBoard, ApprovalBoardId, ApprovalSubject, PersonId, QuorumRule, VoteDecision,
+    };
+
+    fn test_limits() -> SessionLimits {
+        SessionLimits::from_config(&WebSocketConfig {
+            idle_timeout_secs: 60,
+            max_lifetime_secs: 3600,
+            ping_interval_secs: 20,
+        })
+    }
+
+    #[test]
+    fn test_silent_session_closed_after_idle_window() {
+        let limits = test_limits();
+        let started_at = Instant::now();
+
+        let (deadline, reason) = limits.deadline(started_at, started_at);
+        assert_eq!(deadline, started_at + Duration::from_secs(60));
+        assert_eq!(reason, "idle timeout");
+    }
+
+    #[test]
+    fn test_active_session_stays_open() {
+        let limits = test_limits();
+        let started_at = Instant::now();
+
+        // Client keeps talking every 50s, so the deadline keeps moving forward
+        let mut last_activity = started_at;
+        for _ in 0..5 {
+            last_activity += Duration::from_secs(50);
+            let (deadline, _) = limits.deadline(started_at, last_activity);
+            assert!(deadline > last_activity + Duration::from_secs(50));
+        }
+
+        // ...until the absolute lifetime caps it
+        let (deadline, reason) =
+            limits.deadline(started_at, started_at + Duration::from_secs(3590));
+        assert_eq!(deadline, started_at + Duration::from_secs(3600));
+        assert_eq!(reason, "max session lifetime reached");
+    }
+
+    #[tokio::test]
+    async fn test_server_pings_keep_listening_client_connected() {
+        use futures::StreamExt;
+        use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+        let config = ServerConfig {
+            websocket: WebSocketConfig {
+                idle_timeout_secs: 2,
+                max_lifetime_secs: 60,
+                ping_interval_secs: 1,
+            },
+            ..test_config("ws")
+        };
+        let state = Arc::new(AppState::new(&config).unwrap());
+        let app = axum::Router::new()
+            .route("/ws", axum::routing::get(ws_handler))
+            .with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+            .await
+            .unwrap();
+
+        // The client never sends anything of its own; it only answers pings while reading
+        let mut pings = 0;
+        let listen = async {
+            while let Some(message) = client.next().await {
+                match message.unwrap() {
+                    ClientMessage::Ping(_) => pings += 1,
+                    ClientMessage::Close(frame) => panic!("Closed while listening: {:?}", frame),
+                    _ => {}
+                }
+            }
+        };
+        let outlived = tokio::time::timeout(Duration::from_secs(4), listen).await;
+        assert!(outlived.is_err(), "Connection ended before twice the idle timeout");
+        assert!(pings >= 3, "{} pings", pings);
+    }
+
+    #[tokio::test]
+    async fn test_decisive_vote_pushes_approval_update() {
+        let config = test_config("ws");
+        let state = AppState::new(&config).unwrap();
+
+        let approvers = vec![PersonId::new("alice"), PersonId::new("bob")];
+        state
+            .approval_manager
+            .register_board(ApprovalBoard {
+                id: ApprovalBoardId::new("finance"),
+                name: "Finance".to_string(),
+                description: String::new(),
+                approvers: approvers.clone(),
+                quorum_rule: QuorumRule::Unanimous,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        let approval = state
+            .approval_manager
+            .create_approval(
+                ApprovalBoardId::new("finance"),
+                ApprovalSubject::Custom {
+                    subject_type: "budget".to_string(),
+                    subject_id: "q3".to_string(),
+                },
+                "test".to_string(),
+            )
+            .unwrap();
+
+        let mut feed = SubscriptionFeed::default();
+        feed.subscribe_approvals(&state);
+
+        // Neither audit entries nor undecided votes reach an approvals-only subscriber
+        state.audit_log.log(
+            AuditCategory::Authentication,
+            AuditSeverity::Info,
+            AuditAction::UserLogin {
+                user_id: "alice".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+            },
+            None,
+            None,
+            None,
+        );
+        state
+            .approval_manager
+            .cast_vote(&approval.id, approvers[0].clone(), VoteDecision::Approve, None)
+            .unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(50), feed.next())
+            .await
+            .is_err());
+
+        state
+            .approval_manager
+            .cast_vote(&approval.id, approvers[1].clone(), VoteDecision::Approve, None)
+            .unwrap();
+        let update = feed.next().await;
+        assert_eq!(update.seq, Some(1));
+        match update.message {
+            WsMessage::ApprovalUpdate {
+                approval_id,
+                status,
+            } => {
+                assert_eq!(approval_id, approval.id.0);
+                assert_eq!(status, ApprovalStatus::Approved);
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lagged_subscriber_gets_gap() {
+        let config = test_config("ws");
+        let state = AppState::new(&config).unwrap();
+
+        let mut feed = SubscriptionFeed::default();
+        feed.subscribe_audit(&state);
+        feed.subscribe_approvals(&state);
+
+        let log_login = |user: &str| {
+            state.audit_log.log(
+                AuditCategory::Authentication,
+                AuditSeverity::Info,
+                AuditAction::UserLogin {
+                    user_id: user.to_string(),
+                    ip_address: "127.0.0.1".to_string(),
+                },
+                None,
+                None,
+                None,
+            );
+        };
+
+        log_login("first");
+        assert_eq!(feed.next().await.seq, Some(1));
+
+        // Overflow the 256-entry channel so the oldest 44 are dropped
+        for i in 0..300 {
+            log_login(&format!("user-{}", i));
+        }
+
+        let gap = feed.next().await;
+        assert_eq!(gap.seq, None);
+        match gap.message {
+            WsMessage::Gap {
+                topic,
+                from_seq,
+                to_seq,
+            } => {
+                assert_eq!(topic, SubscriptionTopic::Audit);
+                assert_eq!(from_seq, 2);
+                assert_eq!(to_seq, 45);
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+
+        // Delivery resumes right after the gap
+        let update = feed.next().await;
+        assert_eq!(update.seq, Some(46));
+        let json = serde_json::to_value(&update).unwrap();
+        assert_eq!(json["seq"], 46);
+        assert_eq!(json["type"], "AuditEvent");
+        match update.message {
+            WsMessage::AuditEvent { entry } => match entry.action {
+                AuditAction::UserLogin { user_id, .. } => assert_eq!(user_id, "user-44"),
+                other => panic!("Unexpected action: {:?}", other),
+            },
+            other => panic!("Unexpected message: {:?}", other),
+        }
+
+        // Other topics are numbered on their own, unaffected by the audit gap
+        for _ in 0..255 {
+            feed.next().await;
+        }
+        state
+            .approval_manager
+            .register_board(ApprovalBoard {
+                id: ApprovalBoardId::new("ops"),
+                name: "Ops".to_string(),
+                description: String::new(),
+                approvers: vec![PersonId::new("alice")],
+                quorum_rule: QuorumRule::Unanimous,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        let approval = state
+            .approval_manager
+            .create_approval(
+                ApprovalBoardId::new("ops"),
+                ApprovalSubject::Custom {
+                    subject_type: "deploy".to_string(),
+                    subject_id: "v2".to_string(),
+                },
+                "test".to_string(),
+            )
+            .unwrap();
+        state
+            .approval_manager
+            .cast_vote(&approval.id, PersonId::new("alice"), VoteDecision::Approve, None)
+            .unwrap();
+        let update = feed.next().await;
+        assert_eq!(update.seq, Some(1));
+        assert!(matches!(update.message, WsMessage::ApprovalUpdate { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_lagged_subscriber_replays_buffer_and_resumes() {
+        let config = test_config("ws");
+        let state = AppState::new(&config).unwrap();
+
+        let mut feed = SubscriptionFeed::default();
+        feed.subscribe_audit(&state);
+
+        // More than a session's channel holds, numbered as they come so none are lost
+        for i in 0..300 {
+            state.audit_log.log(
+                AuditCategory::Authentication,
+                AuditSeverity::Info,
+                AuditAction::UserLogin {
+                    user_id: format!("user-{}", i),
+                    ip_address: "127.0.0.1".to_string(),
+                },
+                None,
+                None,
+                None,
+            );
+            if i % 50 == 0 {
+                tokio::task::yield_now().await;
+            }
+        }
+        tokio::task::yield_now().await;
+
+        // The lagged session catches up from the replay buffer without a gap
+        for seq in 1..=300 {
+            assert_eq!(feed.next().await.seq, Some(seq));
+        }
+
+        // Another session picks up where a previous connection left off
+        let mut resumed = SubscriptionFeed::default();
+        assert!(!resumed.resume(SubscriptionTopic::Audit, 250));
+        resumed.subscribe_audit(&state);
+        assert!(resumed.resume(SubscriptionTopic::Audit, 250));
+        for seq in 251..=300 {
+            assert_eq!(resumed.next().await.seq, Some(seq));
+        }
+        assert!(tokio::time::timeout(Duration::from_millis(50), resumed.next())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_key_sees_only_its_tenants_audit_entries() {
+        use shiioo_core::api_key::NewApiKey;
+        use shiioo_core::rbac::RbacUser;
+
+        let config = test_config("ws");
+        let state = AppState::new(&config).unwrap();
+        for user in ["auditor-a", "viewer-a"] {
+            let email = format!("{}@example.com", user);
+            let user = RbacUser::new(user.to_string(), user.to_string(), email);
+            state.rbac_manager.register_user(user).unwrap();
+        }
+        state.rbac_manager.assign_role("auditor-a", "auditor").unwrap();
+        let tenant_key = |user_id: &str| {
+            let (key, _) = state
+                .api_keys
+                .create(NewApiKey {
+                    name: "dashboard".to_string(),
+                    user_id: user_id.to_string(),
+                    tenant_id: Some(TenantId::new("tenant-a")),
+                    allowed_tenants: Vec::new(),
+                    allowed_actions: Vec::new(),
+                    expires_at: None,
+                })
+                .unwrap();
+            key
+        };
+
+        // Only users who may read the audit log can subscribe to it
+        let mut feed = SubscriptionFeed::for_key(Some(tenant_key("viewer-a")));
+        assert!(!feed.subscribe_audit(&state));
+
+        let mut feed = SubscriptionFeed::for_key(Some(tenant_key("auditor-a")));
+        assert!(feed.subscribe_audit(&state));
+        feed.subscribe_approvals(&state);
+        for tenant in [Some("tenant-b"), None, Some("tenant-a")] {
+            state.audit_log.log(
+                AuditCategory::Authentication,
+                AuditSeverity::Info,
+                AuditAction::UserLogin {
+                    user_id: "someone".to_string(),
+                    ip_address: "127.0.0.1".to_string(),
+                },
+                None,
+                tenant.map(String::from),
+                None,
+            );
+        }
+
+        // Numbering is shared by every session, so the hidden entries leave a hole
+        let update = feed.next().await;
+        assert_eq!(update.seq, Some(3));
+        match update.message {
+            WsMessage::AuditEvent { entry } => {
+                assert_eq!(entry.tenant_id.as_deref(), Some("tenant-a"))
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+        assert!(tokio::time::timeout(Duration::from_millis(50), feed.next())
+            .await
+            .is_err());
+    }
+}